@@ -290,6 +290,10 @@ impl sc_transaction_pool_api::TransactionPool for Transactions {
 		Default::default()
 	}
 
+	fn cancel(&self, _hash: &TxHash<Self>) -> Vec<Arc<Self::InPoolTransaction>> {
+		unimplemented!()
+	}
+
 	fn status(&self) -> PoolStatus {
 		unimplemented!()
 	}
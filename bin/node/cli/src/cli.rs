@@ -101,4 +101,8 @@ pub enum Subcommand {
 
 	/// Db meta columns information.
 	ChainInfo(sc_cli::ChainInfoCmd),
+
+	/// Database maintenance utilities.
+	#[command(subcommand)]
+	Db(sc_cli::DbSubcommand),
 }
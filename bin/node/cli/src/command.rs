@@ -261,5 +261,22 @@ pub fn run() -> Result<()> {
 			let runner = cli.create_runner(cmd)?;
 			runner.sync_run(|config| cmd.run::<Block>(&config))
 		},
+		Some(Subcommand::Db(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			match cmd {
+				sc_cli::DbSubcommand::Migrate(cmd) =>
+					runner.sync_run(|config| cmd.run::<Block>(&config)),
+				sc_cli::DbSubcommand::Check(cmd) => runner.async_run(|config| {
+					let PartialComponents { client, task_manager, backend, .. } =
+						new_partial(&config)?;
+					let aux_revert = Box::new(|client: Arc<FullClient>, backend, blocks| {
+						sc_consensus_babe::revert(client.clone(), backend, blocks)?;
+						grandpa::revert(client, blocks)?;
+						Ok(())
+					});
+					Ok((cmd.run(client, backend, Some(aux_revert)), task_manager))
+				}),
+			}
+		},
 	}
 }
@@ -238,6 +238,32 @@ pub struct IoInfo {
 	pub state_writes_nodes: u64,
 }
 
+/// Hit statistics of the state cache, since the start of the client.
+#[derive(Default, Clone, Debug)]
+pub struct StateCacheHitInfo {
+	/// Lookups of trie nodes in the state cache.
+	pub node_fetch_attempts: u64,
+	/// Lookups of trie nodes found in the state cache.
+	pub node_hits: u64,
+	/// Lookups of storage values in the state cache.
+	pub value_fetch_attempts: u64,
+	/// Lookups of storage values found in the state cache.
+	pub value_hits: u64,
+}
+
+/// Progress of the state compaction that runs after the state pruning mode was changed.
+#[derive(Default, Clone, Debug)]
+pub struct StateCompactionInfo {
+	/// Trie nodes found to be retained so far.
+	pub marked_nodes: u64,
+	/// Completed passes removing the nodes that are not retained.
+	pub sweep_passes_done: u32,
+	/// Total number of sweep passes. `0` until the nodes to remove are counted.
+	pub sweep_passes: u32,
+	/// Trie nodes removed so far.
+	pub removed_nodes: u64,
+}
+
 /// Usage statistics for running client instance.
 ///
 /// Returning backend determines the scope of these stats,
@@ -249,6 +275,10 @@ pub struct UsageInfo {
 	pub memory: MemoryInfo,
 	/// I/O statistics.
 	pub io: IoInfo,
+	/// State cache hit statistics, if the state cache is enabled.
+	pub state_cache_hits: Option<StateCacheHitInfo>,
+	/// State compaction progress, if a compaction is running.
+	pub state_compaction: Option<StateCompactionInfo>,
 }
 
 impl fmt::Display for UsageInfo {
@@ -66,6 +66,21 @@ pub trait ProofProvider<Block: BlockT> {
 		size_limit: usize,
 	) -> sp_blockchain::Result<(CompactProof, u32)>;
 
+	/// Given a `Hash` iterate over the storage values of the top trie, or of the given child
+	/// trie, starting at `start_at` (inclusive) and restricted to `prefix`.
+	/// Proof is build until size limit is reached and always include at
+	/// least one key if any is left.
+	/// Returns the compact proof, the last key of the range and the key following it, at which
+	/// the next range starts.
+	fn read_range_proof(
+		&self,
+		hash: Block::Hash,
+		child_info: Option<&ChildInfo>,
+		prefix: Option<&[u8]>,
+		start_at: Option<&[u8]>,
+		size_limit: usize,
+	) -> sp_blockchain::Result<(CompactProof, Option<Vec<u8>>, Option<Vec<u8>>)>;
+
 	/// Given a `Hash` iterate over all storage values starting at `start_key`.
 	/// Returns collected keys and values.
 	/// Returns the collected keys values content of the top trie followed by the
@@ -264,6 +264,32 @@ impl Into<sc_network::config::SyncMode> for SyncMode {
 	}
 }
 
+/// Block history download mode, for the blocks skipped by warp sync.
+#[derive(Debug, Clone, Copy, ValueEnum, PartialEq)]
+#[value(rename_all = "kebab-case")]
+pub enum GapSyncMode {
+	/// Download the headers, bodies and justifications of the blocks.
+	Full,
+	/// Download the headers and justifications of the blocks.
+	HeadersAndJustifications,
+	/// Download the headers of the blocks.
+	Headers,
+	/// Don't download the block history.
+	Skip,
+}
+
+impl Into<sc_network::config::GapSyncMode> for GapSyncMode {
+	fn into(self) -> sc_network::config::GapSyncMode {
+		match self {
+			GapSyncMode::Full => sc_network::config::GapSyncMode::Full,
+			GapSyncMode::HeadersAndJustifications =>
+				sc_network::config::GapSyncMode::HeadersAndJustifications,
+			GapSyncMode::Headers => sc_network::config::GapSyncMode::Headers,
+			GapSyncMode::Skip => sc_network::config::GapSyncMode::Skip,
+		}
+	}
+}
+
 /// Default value for the `--execution-syncing` parameter.
 pub const DEFAULT_EXECUTION_SYNCING: ExecutionStrategy = ExecutionStrategy::Wasm;
 /// Default value for the `--execution-import-block` parameter.
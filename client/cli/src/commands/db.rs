@@ -0,0 +1,54 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Database related CLI utilities
+
+use super::{db_check_cmd::DbCheckCmd, db_migrate_cmd::DbMigrateCmd};
+use crate::{CliConfiguration, DatabaseParams, PruningParams, SharedParams};
+
+/// Database utilities for the cli.
+#[derive(Debug, Clone, clap::Subcommand)]
+pub enum DbSubcommand {
+	/// Copy the database to another database backend.
+	Migrate(DbMigrateCmd),
+
+	/// Check the consistency of the database and optionally repair it.
+	Check(DbCheckCmd),
+}
+
+impl CliConfiguration for DbSubcommand {
+	fn shared_params(&self) -> &SharedParams {
+		match self {
+			DbSubcommand::Migrate(cmd) => cmd.shared_params(),
+			DbSubcommand::Check(cmd) => cmd.shared_params(),
+		}
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		match self {
+			DbSubcommand::Migrate(cmd) => cmd.database_params(),
+			DbSubcommand::Check(cmd) => cmd.database_params(),
+		}
+	}
+
+	fn pruning_params(&self) -> Option<&PruningParams> {
+		match self {
+			DbSubcommand::Migrate(cmd) => cmd.pruning_params(),
+			DbSubcommand::Check(cmd) => cmd.pruning_params(),
+		}
+	}
+}
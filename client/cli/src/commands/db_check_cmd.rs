@@ -0,0 +1,115 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+	commands::revert_cmd::AuxRevertHandler, error, CliConfiguration, DatabaseParams, PruningParams,
+	SharedParams,
+};
+use sc_client_api::UsageProvider;
+use sc_service::chain_ops::revert_chain;
+use sp_runtime::traits::{Block as BlockT, One, Saturating};
+use std::sync::Arc;
+
+/// The `db check` command used to check the consistency of the database.
+///
+/// The metadata, the canonical chain and the retained state are checked. With `--repair`, the
+/// unfinalized blocks from the first inconsistent block on are reverted.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct DbCheckCmd {
+	/// Revert to the last consistent block if an inconsistency is found.
+	#[arg(long)]
+	pub repair: bool,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub database_params: DatabaseParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub pruning_params: PruningParams,
+}
+
+impl DbCheckCmd {
+	/// Run the `db check` command
+	pub async fn run<B, C>(
+		&self,
+		client: Arc<C>,
+		backend: Arc<sc_client_db::Backend<B>>,
+		aux_revert: Option<AuxRevertHandler<C, sc_client_db::Backend<B>, B>>,
+	) -> error::Result<()>
+	where
+		B: BlockT,
+		C: UsageProvider<B>,
+	{
+		let report = backend.check_consistency()?;
+		for inconsistency in &report.inconsistencies {
+			println!("{}", inconsistency);
+		}
+		println!(
+			"Checked {} blocks and {} states ({} trie nodes), {} inconsistencies found.",
+			report.checked_blocks,
+			report.checked_states,
+			report.checked_nodes,
+			report.inconsistencies.len(),
+		);
+
+		let first_inconsistent = match report.first_inconsistent_block() {
+			Some(number) => number,
+			None => return Ok(()),
+		};
+		if !self.repair {
+			return Err("The database is inconsistent. Run with `--repair` to revert to the last \
+				consistent block."
+				.into())
+		}
+
+		let info = client.usage_info().chain;
+		if first_inconsistent <= info.finalized_number {
+			return Err(format!(
+				"Block #{} is finalized and can't be reverted. The database must be restored or \
+				resynced.",
+				first_inconsistent,
+			)
+			.into())
+		}
+		let blocks = info.best_number.saturating_sub(first_inconsistent) + One::one();
+		if let Some(aux_revert) = aux_revert {
+			aux_revert(client.clone(), backend.clone(), blocks)?;
+		}
+		revert_chain(client, backend, blocks)?;
+
+		Ok(())
+	}
+}
+
+impl CliConfiguration for DbCheckCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+
+	fn pruning_params(&self) -> Option<&PruningParams> {
+		Some(&self.pruning_params)
+	}
+}
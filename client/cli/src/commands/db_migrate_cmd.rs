@@ -0,0 +1,87 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{arg_enums::Database, error, CliConfiguration, DatabaseParams, SharedParams};
+use sc_service::DatabaseSource;
+use sp_runtime::traits::Block as BlockT;
+use std::{fmt::Debug, path::PathBuf};
+
+/// The `db migrate` command used to copy the database to another database backend.
+///
+/// The source database is selected with `--database`. An interrupted migration is resumed when
+/// the command is run again with the same target. Migrating from ParityDb is refused if the
+/// database contains aux or offchain data, because ParityDb does not keep the keys of this data.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct DbMigrateCmd {
+	/// Database backend to migrate to.
+	#[arg(
+		long,
+		value_name = "DB",
+		ignore_case = true,
+		value_enum,
+		default_value_t = Database::ParityDb,
+	)]
+	pub target_database: Database,
+
+	/// Path of the target database.
+	///
+	/// Defaults to the path the node uses for the target database backend.
+	#[arg(long, value_name = "PATH")]
+	pub target_path: Option<PathBuf>,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+impl DbMigrateCmd {
+	/// Run the `db migrate` command
+	pub fn run<B: BlockT>(&self, config: &sc_service::Configuration) -> error::Result<()> {
+		let cache_size = self.database_cache_size()?.unwrap_or(1024);
+		let target = match (self.target_database, &self.target_path) {
+			(Database::Auto, _) =>
+				return Err(error::Error::Input(
+					"The target database backend must be given explicitly".into(),
+				)),
+			#[cfg(feature = "rocksdb")]
+			(Database::RocksDb, Some(path)) => DatabaseSource::RocksDb { path: path.clone(), cache_size },
+			(Database::ParityDb | Database::ParityDbDeprecated, Some(path)) =>
+				DatabaseSource::ParityDb { path: path.clone() },
+			(database, None) => self.database_config(&config.data_path, cache_size, database)?,
+		};
+
+		sc_client_db::migrate_database::<B>(&config.database, &target)?;
+		if let Some(path) = target.path() {
+			println!("Database migrated to {:?}.", path);
+		}
+		Ok(())
+	}
+}
+
+impl CliConfiguration for DbMigrateCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
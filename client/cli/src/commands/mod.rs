@@ -18,6 +18,9 @@
 mod build_spec_cmd;
 mod chain_info_cmd;
 mod check_block_cmd;
+mod db;
+mod db_check_cmd;
+mod db_migrate_cmd;
 mod export_blocks_cmd;
 mod export_state_cmd;
 mod generate;
@@ -29,6 +32,7 @@ mod inspect_node_key;
 mod key;
 mod purge_chain_cmd;
 mod revert_cmd;
+mod rotate_password;
 mod run_cmd;
 mod sign;
 mod test;
@@ -38,9 +42,11 @@ mod verify;
 
 pub use self::{
 	build_spec_cmd::BuildSpecCmd, chain_info_cmd::ChainInfoCmd, check_block_cmd::CheckBlockCmd,
+	db::DbSubcommand, db_check_cmd::DbCheckCmd, db_migrate_cmd::DbMigrateCmd,
 	export_blocks_cmd::ExportBlocksCmd, export_state_cmd::ExportStateCmd, generate::GenerateCmd,
 	generate_node_key::GenerateNodeKeyCmd, import_blocks_cmd::ImportBlocksCmd,
 	insert_key::InsertKeyCmd, inspect_key::InspectKeyCmd, inspect_node_key::InspectNodeKeyCmd,
-	key::KeySubcommand, purge_chain_cmd::PurgeChainCmd, revert_cmd::RevertCmd, run_cmd::RunCmd,
-	sign::SignCmd, vanity::VanityCmd, verify::VerifyCmd,
+	key::KeySubcommand, purge_chain_cmd::PurgeChainCmd, revert_cmd::RevertCmd,
+	rotate_password::RotatePasswordCmd, run_cmd::RunCmd, sign::SignCmd, vanity::VanityCmd,
+	verify::VerifyCmd,
 };
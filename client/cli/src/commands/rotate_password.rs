@@ -0,0 +1,205 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Implementation of the `rotate-password` subcommand
+
+use crate::{secret_string_from_str, Error, KeystoreParams, SharedParams, SubstrateCli};
+use clap::Parser;
+use sc_keystore::LocalKeystore;
+use sc_service::config::{BasePath, KeystoreConfig};
+use sp_core::crypto::{ExposeSecret, SecretString};
+use std::{fs, path::PathBuf};
+
+/// The `rotate-password` command
+#[derive(Debug, Clone, Parser)]
+#[command(
+	name = "rotate-password",
+	about = "Re-encrypt the keystore of a node with a new password."
+)]
+pub struct RotatePasswordCmd {
+	/// Use interactive shell for entering the new keystore password.
+	#[arg(long, conflicts_with_all = &["new_password", "new_password_filename"])]
+	pub new_password_interactive: bool,
+
+	/// New password for the keystore.
+	#[arg(
+		long,
+		value_parser = secret_string_from_str,
+		conflicts_with_all = &["new_password_interactive", "new_password_filename"]
+	)]
+	pub new_password: Option<SecretString>,
+
+	/// File that contains the new password for the keystore.
+	#[arg(
+		long,
+		value_name = "PATH",
+		conflicts_with_all = &["new_password_interactive", "new_password"]
+	)]
+	pub new_password_filename: Option<PathBuf>,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub keystore_params: KeystoreParams,
+}
+
+impl RotatePasswordCmd {
+	/// Run the command
+	pub fn run<C: SubstrateCli>(&self, cli: &C) -> Result<(), Error> {
+		let base_path = self
+			.shared_params
+			.base_path()?
+			.unwrap_or_else(|| BasePath::from_project("", "", &C::executable_name()));
+		let chain_id = self.shared_params.chain_id(self.shared_params.is_dev());
+		let chain_spec = cli.load_spec(&chain_id)?;
+		let config_dir = base_path.config_dir(chain_spec.id());
+
+		let (path, password) = match self.keystore_params.keystore_config(&config_dir)? {
+			KeystoreConfig::Path { path, password } => (path, password),
+			KeystoreConfig::Remote { .. } =>
+				return Err(Error::Input(
+					"The password of a remote keystore can not be rotated".into(),
+				)),
+			KeystoreConfig::InMemory =>
+				unreachable!("keystore_config never returns an in-memory keystore; qed"),
+		};
+
+		let new_password = self.new_password()?;
+		let keystore = LocalKeystore::open(path, password)?;
+		let rotated = keystore.change_password(new_password)?;
+		println!("Re-encrypted {} key files", rotated);
+
+		Ok(())
+	}
+
+	/// Get the new password from the command line arguments.
+	fn new_password(&self) -> Result<SecretString, Error> {
+		let password = if self.new_password_interactive {
+			let password = rpassword::prompt_password("New keystore password: ")?;
+			let repeated = rpassword::prompt_password("Repeat new keystore password: ")?;
+			if password != repeated {
+				return Err(Error::Input("The passwords do not match".into()))
+			}
+			SecretString::new(password)
+		} else if let Some(ref file) = self.new_password_filename {
+			SecretString::new(fs::read_to_string(file)?)
+		} else if let Some(ref password) = self.new_password {
+			password.clone()
+		} else {
+			return Err(Error::Input(
+				"One of --new-password, --new-password-interactive or --new-password-filename \
+				 is required"
+					.into(),
+			))
+		};
+
+		if password.expose_secret().is_empty() {
+			return Err(Error::Input("The new password must not be empty".into()))
+		}
+
+		Ok(password)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sc_service::{ChainSpec, ChainType, GenericChainSpec, NoExtension};
+	use sp_core::{crypto::KeyTypeId, ByteArray};
+	use sp_keystore::Keystore;
+	use std::str::FromStr;
+	use tempfile::TempDir;
+
+	struct Cli;
+
+	impl SubstrateCli for Cli {
+		fn impl_name() -> String {
+			"test".into()
+		}
+
+		fn impl_version() -> String {
+			"2.0".into()
+		}
+
+		fn description() -> String {
+			"test".into()
+		}
+
+		fn support_url() -> String {
+			"test.test".into()
+		}
+
+		fn copyright_start_year() -> i32 {
+			2021
+		}
+
+		fn author() -> String {
+			"test".into()
+		}
+
+		fn native_runtime_version(_: &Box<dyn ChainSpec>) -> &'static sp_version::RuntimeVersion {
+			unimplemented!("Not required in tests")
+		}
+
+		fn load_spec(&self, _: &str) -> std::result::Result<Box<dyn ChainSpec>, String> {
+			Ok(Box::new(GenericChainSpec::from_genesis(
+				"test",
+				"test_id",
+				ChainType::Development,
+				|| unimplemented!("Not required in tests"),
+				Vec::new(),
+				None,
+				None,
+				None,
+				None,
+				NoExtension::None,
+			)))
+		}
+	}
+
+	#[test]
+	fn rotate_password_with_custom_base_path() {
+		let path = TempDir::new().unwrap();
+		let path_str = format!("{}", path.path().display());
+		let keystore_path = path.path().join("chains").join("test_id").join("keystore");
+		let key_type = KeyTypeId(*b"test");
+
+		let keystore =
+			LocalKeystore::open(&keystore_path, Some(FromStr::from_str("old").unwrap())).unwrap();
+		let public = keystore.sr25519_generate_new(key_type, None).unwrap();
+		drop(keystore);
+
+		let rotate = RotatePasswordCmd::parse_from(&[
+			"rotate-password",
+			"-d",
+			&path_str,
+			"--password",
+			"old",
+			"--new-password",
+			"new",
+		]);
+		assert!(rotate.run(&Cli).is_ok());
+
+		let keystore =
+			LocalKeystore::open(&keystore_path, Some(FromStr::from_str("new").unwrap())).unwrap();
+		assert!(keystore.has_keys(&[(public.to_raw_vec(), key_type)]));
+		assert!(keystore.sr25519_sign(key_type, &public, b"message").unwrap().is_some());
+	}
+}
@@ -20,15 +20,15 @@ use crate::{
 	arg_enums::RpcMethods,
 	error::{Error, Result},
 	params::{
-		ImportParams, KeystoreParams, NetworkParams, OffchainWorkerParams, SharedParams,
-		TransactionPoolParams,
+		ChainHeadParams, ImportParams, KeystoreParams, NetworkParams, OffchainWorkerParams,
+		SharedParams, TransactionPoolParams,
 	},
 	CliConfiguration, PrometheusParams, RuntimeParams, TelemetryParams,
 };
 use clap::Parser;
 use regex::Regex;
 use sc_service::{
-	config::{BasePath, PrometheusConfig, TransactionPoolOptions},
+	config::{BasePath, ChainHeadConfig, PrometheusConfig, TransactionPoolOptions},
 	ChainSpec, Role,
 };
 use sc_telemetry::TelemetryEndpoints;
@@ -142,6 +142,10 @@ pub struct RunCmd {
 	#[clap(flatten)]
 	pub pool_config: TransactionPoolParams,
 
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub chain_head_params: ChainHeadParams,
+
 	#[allow(missing_docs)]
 	#[clap(flatten)]
 	pub keystore_params: KeystoreParams,
@@ -363,6 +367,10 @@ impl CliConfiguration for RunCmd {
 		Ok(self.rpc_max_subscriptions_per_connection)
 	}
 
+	fn rpc_chain_head(&self) -> Result<ChainHeadConfig> {
+		Ok(self.chain_head_params.chain_head())
+	}
+
 	fn transaction_pool(&self, is_dev: bool) -> Result<TransactionPoolOptions> {
 		Ok(self.pool_config.transaction_pool(is_dev))
 	}
@@ -27,9 +27,9 @@ use names::{Generator, Name};
 use sc_client_api::execution_extensions::ExecutionStrategies;
 use sc_service::{
 	config::{
-		BasePath, Configuration, DatabaseSource, KeystoreConfig, NetworkConfiguration,
-		NodeKeyConfig, OffchainWorkerConfig, PrometheusConfig, PruningMode, Role, RpcMethods,
-		TelemetryEndpoints, TransactionPoolOptions, WasmExecutionMethod,
+		BasePath, ChainHeadConfig, Configuration, DatabaseSource, KeystoreConfig,
+		NetworkConfiguration, NodeKeyConfig, OffchainWorkerConfig, PrometheusConfig, PruningMode,
+		Role, RpcMethods, TelemetryEndpoints, TransactionPoolOptions, WasmExecutionMethod,
 	},
 	BlocksPruning, ChainSpec, TracingReceiver,
 };
@@ -231,6 +231,14 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 		Ok(self.import_params().map(|x| x.trie_cache_maximum_size()).unwrap_or_default())
 	}
 
+	/// Returns `true` if the trie cache should be stored at shutdown and loaded at the next
+	/// start.
+	///
+	/// By default this is retrieved from `ImportParams` if it is available. Otherwise its `false`.
+	fn persist_trie_cache(&self) -> Result<bool> {
+		Ok(self.import_params().map(|x| x.persist_trie_cache).unwrap_or_default())
+	}
+
 	/// Get the state pruning mode.
 	///
 	/// By default this is retrieved from `PruningMode` if it is available. Otherwise its
@@ -251,6 +259,26 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 			.unwrap_or_else(|| Ok(BlocksPruning::KeepFinalized))
 	}
 
+	/// Get the justifications pruning mode.
+	///
+	/// By default this is retrieved from `justifications_pruning` if it is available. Otherwise
+	/// the justifications follow the block pruning mode.
+	fn justifications_pruning(&self) -> Result<Option<BlocksPruning>> {
+		self.pruning_params()
+			.map(|x| x.justifications_pruning())
+			.unwrap_or_else(|| Ok(None))
+	}
+
+	/// Get the indexed transactions pruning mode.
+	///
+	/// By default this is retrieved from `transactions_pruning` if it is available. Otherwise
+	/// the indexed transactions follow the block pruning mode.
+	fn transactions_pruning(&self) -> Result<Option<BlocksPruning>> {
+		self.pruning_params()
+			.map(|x| x.transactions_pruning())
+			.unwrap_or_else(|| Ok(None))
+	}
+
 	/// Get the chain ID (string).
 	///
 	/// By default this is retrieved from `SharedParams`.
@@ -335,6 +363,13 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 		Ok(Default::default())
 	}
 
+	/// Get the limits of the `chainHead` RPC methods.
+	///
+	/// By default this is `ChainHeadConfig::default()`.
+	fn rpc_chain_head(&self) -> Result<ChainHeadConfig> {
+		Ok(Default::default())
+	}
+
 	/// Get the prometheus configuration (`None` if disabled)
 	///
 	/// By default this is `None`.
@@ -471,6 +506,10 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 		let max_runtime_instances = self.max_runtime_instances()?.unwrap_or(8);
 		let is_validator = role.is_authority();
 		let keystore = self.keystore_config(&config_dir)?;
+		let mut transaction_pool = self.transaction_pool(is_dev)?;
+		if let Some(journal) = transaction_pool.journal.as_mut() {
+			journal.path = config_dir.join(&journal.path);
+		}
 		let telemetry_endpoints = self.telemetry_endpoints(&chain_spec)?;
 		let runtime_cache_size = self.runtime_cache_size()?;
 
@@ -478,7 +517,7 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 			impl_name: C::impl_name(),
 			impl_version: C::impl_version(),
 			tokio_handle,
-			transaction_pool: self.transaction_pool(is_dev)?,
+			transaction_pool,
 			network: self.network_config(
 				&chain_spec,
 				is_dev,
@@ -493,8 +532,11 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 			database: self.database_config(&config_dir, database_cache_size, database)?,
 			data_path: config_dir,
 			trie_cache_maximum_size: self.trie_cache_maximum_size()?,
+			persist_trie_cache: self.persist_trie_cache()?,
 			state_pruning: self.state_pruning()?,
 			blocks_pruning: self.blocks_pruning()?,
+			justifications_pruning: self.justifications_pruning()?,
+			transactions_pruning: self.transactions_pruning()?,
 			wasm_method: self.wasm_method()?,
 			wasm_runtime_overrides: self.wasm_runtime_overrides(),
 			execution_strategies: self.execution_strategies(is_dev, is_validator)?,
@@ -506,6 +548,7 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 			rpc_max_response_size: self.rpc_max_response_size()?,
 			rpc_id_provider: None,
 			rpc_max_subs_per_conn: self.rpc_max_subscriptions_per_connection()?,
+			rpc_chain_head: self.rpc_chain_head()?,
 			prometheus_config: self
 				.prometheus_config(DCV::prometheus_listen_port(), &chain_spec)?,
 			telemetry_endpoints,
@@ -0,0 +1,85 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use clap::Args;
+use sc_service::config::ChainHeadConfig;
+use std::time::Duration;
+
+/// Parameters used to limit the `chainHead` RPC methods.
+#[derive(Debug, Clone, Args)]
+pub struct ChainHeadParams {
+	/// Maximum number of blocks pinned by all the `chainHead` subscriptions together.
+	/// Defaults to 512.
+	///
+	/// Must not exceed the number of blocks the database keeps pinned in memory.
+	#[arg(long, value_name = "COUNT")]
+	pub rpc_chain_head_max_pinned_blocks: Option<usize>,
+
+	/// Maximum number of blocks pinned by a single `chainHead` subscription. Defaults to 256.
+	///
+	/// A subscription exceeding this limit is stopped.
+	#[arg(long, value_name = "COUNT")]
+	pub rpc_chain_head_max_pinned_blocks_per_subscription: Option<usize>,
+
+	/// Maximum number of seconds a block stays pinned by a `chainHead` subscription. Defaults
+	/// to 60s.
+	#[arg(long, value_name = "SECONDS")]
+	pub rpc_chain_head_max_pinned_duration: Option<u64>,
+
+	/// Maximum number of ongoing operations of a single `chainHead` subscription. Defaults to
+	/// 16.
+	#[arg(long, value_name = "COUNT")]
+	pub rpc_chain_head_max_operations: Option<usize>,
+
+	/// Maximum number of items reported by a `chainHead` storage operation before waiting for
+	/// the `continue` call. Defaults to 10.
+	#[arg(long, value_name = "COUNT")]
+	pub rpc_chain_head_max_storage_items: Option<usize>,
+
+	/// Maximum number of `chainHead` follow subscriptions per connection. Defaults to 4.
+	#[arg(long, value_name = "COUNT")]
+	pub rpc_chain_head_max_follow_subscriptions_per_connection: Option<usize>,
+}
+
+impl ChainHeadParams {
+	/// Fill the `ChainHeadConfig` by looking at the cli parameters.
+	pub fn chain_head(&self) -> ChainHeadConfig {
+		let mut config = ChainHeadConfig::default();
+
+		if let Some(count) = self.rpc_chain_head_max_pinned_blocks {
+			config.global_max_pinned_blocks = count;
+		}
+		if let Some(count) = self.rpc_chain_head_max_pinned_blocks_per_subscription {
+			config.subscription_max_pinned_blocks = count;
+		}
+		if let Some(seconds) = self.rpc_chain_head_max_pinned_duration {
+			config.subscription_max_pinned_duration = Duration::from_secs(seconds);
+		}
+		if let Some(count) = self.rpc_chain_head_max_operations {
+			config.subscription_max_ongoing_operations = count;
+		}
+		if let Some(count) = self.rpc_chain_head_max_storage_items {
+			config.operation_max_storage_items = count;
+		}
+		if let Some(count) = self.rpc_chain_head_max_follow_subscriptions_per_connection {
+			config.max_follow_subscriptions_per_connection = count;
+		}
+
+		config
+	}
+}
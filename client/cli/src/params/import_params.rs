@@ -82,6 +82,13 @@ pub struct ImportParams {
 	#[arg(long, value_name = "Bytes", default_value_t = 67108864)]
 	pub trie_cache_size: usize,
 
+	/// Store the trie cache at shutdown and load it at the next start.
+	///
+	/// The whole cache is loaded if the best block is still the same. Otherwise only the cached
+	/// nodes of the state of the best block are loaded.
+	#[arg(long)]
+	pub persist_trie_cache: bool,
+
 	/// DEPRECATED
 	/// Switch to `--trie-cache-size`.
 	#[arg(long)]
@@ -31,8 +31,15 @@ const DEFAULT_KEYSTORE_CONFIG_PATH: &str = "keystore";
 /// Parameters of the keystore
 #[derive(Debug, Clone, Args)]
 pub struct KeystoreParams {
-	/// Specify custom URIs to connect to for keystore-services
-	#[arg(long)]
+	/// Use a remote signer instead of the local keystore.
+	///
+	/// The signer is reached over a Unix socket (`unix:///path/to/signer.sock`) or over HTTP on a
+	/// loopback address (`http://127.0.0.1:<port>`).
+	#[arg(
+		long,
+		value_name = "URI",
+		conflicts_with_all = &["keystore_path", "password_interactive", "password", "password_filename"]
+	)]
 	pub keystore_uri: Option<String>,
 
 	/// Specify custom keystore path.
@@ -44,7 +51,9 @@ pub struct KeystoreParams {
 	pub password_interactive: bool,
 
 	/// Password used by the keystore. This allows appending an extra user-defined secret to the
-	/// seed.
+	/// seed of newly generated keys and encrypts the key files at rest.
+	///
+	/// Use `key rotate-password` to change the password of an existing keystore.
 	#[arg(
 		long,
 		value_parser = secret_string_from_str,
@@ -69,6 +78,10 @@ pub fn secret_string_from_str(s: &str) -> std::result::Result<SecretString, Stri
 impl KeystoreParams {
 	/// Get the keystore configuration for the parameters
 	pub fn keystore_config(&self, config_dir: &Path) -> Result<KeystoreConfig> {
+		if let Some(uri) = &self.keystore_uri {
+			return Ok(KeystoreConfig::Remote { uri: uri.clone() })
+		}
+
 		let password = if self.password_interactive {
 			Some(SecretString::new(input_keystore_password()?))
 		} else if let Some(ref file) = self.password_filename {
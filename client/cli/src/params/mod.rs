@@ -15,6 +15,7 @@
 
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
+mod chain_head_params;
 mod database_params;
 mod import_params;
 mod keystore_params;
@@ -39,9 +40,10 @@ use sp_runtime::{
 use std::{fmt::Debug, str::FromStr};
 
 pub use crate::params::{
-	database_params::*, import_params::*, keystore_params::*, message_params::*, network_params::*,
-	node_key_params::*, offchain_worker_params::*, prometheus_params::*, pruning_params::*,
-	runtime_params::*, shared_params::*, telemetry_params::*, transaction_pool_params::*,
+	chain_head_params::*, database_params::*, import_params::*, keystore_params::*,
+	message_params::*, network_params::*, node_key_params::*, offchain_worker_params::*,
+	prometheus_params::*, pruning_params::*, runtime_params::*, shared_params::*,
+	telemetry_params::*, transaction_pool_params::*,
 };
 
 /// Parse Ss58AddressFormat
@@ -16,11 +16,15 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::{arg_enums::SyncMode, params::node_key_params::NodeKeyParams};
+use crate::{
+	arg_enums::{GapSyncMode, SyncMode},
+	params::node_key_params::NodeKeyParams,
+};
 use clap::Args;
 use sc_network::{
 	config::{
-		NetworkConfiguration, NodeKeyConfig, NonReservedPeerMode, SetConfig, TransportConfig,
+		DefaultPeerScoring, NetworkConfiguration, NodeKeyConfig, NonReservedPeerMode, SetConfig,
+		TransportConfig,
 	},
 	multiaddr::Protocol,
 };
@@ -28,7 +32,7 @@ use sc_service::{
 	config::{Multiaddr, MultiaddrWithPeerId},
 	ChainSpec, ChainType,
 };
-use std::{borrow::Cow, path::PathBuf};
+use std::{borrow::Cow, path::PathBuf, sync::Arc};
 
 /// Parameters used to create the network configuration.
 #[derive(Debug, Clone, Args)]
@@ -136,6 +140,22 @@ pub struct NetworkParams {
 	)]
 	pub sync: SyncMode,
 
+	/// Block history download mode, for the blocks skipped by warp sync.
+	#[arg(
+		long,
+		value_enum,
+		value_name = "GAP_SYNC_MODE",
+		default_value_t = GapSyncMode::Full,
+		ignore_case = true
+	)]
+	pub gap_sync: GapSyncMode,
+
+	/// Maximum number of peers to download the block history from in parallel.
+	///
+	/// By default, the block history is downloaded from all the peers that are not busy otherwise.
+	#[arg(long, value_name = "COUNT")]
+	pub max_parallel_gap_downloads: Option<u32>,
+
 	/// Maximum number of blocks per request.
 	///
 	/// Try reducing this number from the default value if you have a slow network connection
@@ -238,6 +258,9 @@ impl NetworkParams {
 			yamux_window_size: None,
 			ipfs_server: self.ipfs_server,
 			sync_mode: self.sync.into(),
+			gap_sync_mode: self.gap_sync.into(),
+			max_parallel_gap_downloads: self.max_parallel_gap_downloads,
+			peer_scoring: Arc::new(DefaultPeerScoring),
 		}
 	}
 }
@@ -292,4 +315,14 @@ mod tests {
 
 		assert_eq!(SyncMode::Warp, params.network_params.sync);
 	}
+
+	#[test]
+	fn gap_sync_defaults_to_full() {
+		let params = Cli::try_parse_from([""]).expect("Parses network params");
+		assert_eq!(GapSyncMode::Full, params.network_params.gap_sync);
+
+		let params = Cli::try_parse_from(["", "--gap-sync", "headers-and-justifications"])
+			.expect("Parses network params");
+		assert_eq!(GapSyncMode::HeadersAndJustifications, params.network_params.gap_sync);
+	}
 }
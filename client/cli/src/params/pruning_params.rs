@@ -18,7 +18,8 @@
 
 use crate::error;
 use clap::Args;
-use sc_service::{BlocksPruning, PruningMode};
+use sc_service::{BlocksPruning, CheckpointPolicy, PruningMode};
+use std::time::Duration;
 
 /// Parameters to define the pruning mode
 #[derive(Debug, Clone, Args)]
@@ -26,9 +27,10 @@ pub struct PruningParams {
 	/// Specify the state pruning mode.
 	/// This mode specifies when the block's state (ie, storage)
 	/// should be pruned (ie, removed) from the database.
-	/// This setting can only be set on the first creation of the database. Every subsequent run
-	/// will load the pruning mode from the database and will error if the stored mode doesn't
-	/// match this CLI value. It is fine to drop this CLI flag for subsequent runs.
+	/// The mode is stored in the database, it is fine to drop this CLI flag for subsequent runs.
+	/// A database created with an archive mode may only be switched to a number with
+	/// `--state-pruning-allow-downgrade`. The number may be changed at any time. Switching to an
+	/// archive mode is not possible once the state has been pruned.
 	/// Possible values:
 	///  - archive: Keep the state of all blocks.
 	///  - 'archive-canonical' Keep only the state of finalized blocks.
@@ -37,8 +39,29 @@ pub struct PruningParams {
 	#[arg(alias = "pruning", long, value_name = "PRUNING_MODE")]
 	pub state_pruning: Option<DatabasePruningMode>,
 
+	/// Allow switching a database created with an archive `--state-pruning` mode to a number.
+	/// The state outside of the new window is then removed in the background, and can't be
+	/// recovered without syncing the chain again.
+	#[arg(long)]
+	pub state_pruning_allow_downgrade: bool,
+
+	/// Specify the state checkpoints to retain.
+	/// The state of a checkpoint block is kept after it leaves the `--state-pruning` window,
+	/// so that it can still be queried and exported. Requires a number `--state-pruning` and
+	/// the ParityDb database. The setting is stored in the database, it is fine to drop this CLI
+	/// flag for subsequent runs.
+	/// Comma separated list of:
+	///  - number: Keep the state of every block which number is a multiple of it.
+	///  - number followed by 'h' or 'd': Keep the state of one finalized block per given
+	///  number of hours or days.
+	///  - 'none': Stop creating checkpoints and forget the retained ones. Can't be combined with
+	///  other values. The state already retained is not removed from the database.
+	#[arg(long, value_name = "CHECKPOINTS")]
+	pub state_pruning_checkpoints: Option<StatePruningCheckpoints>,
+
 	/// Specify the blocks pruning mode.
-	/// This mode specifies when the block's body (including justifications)
+	/// This mode specifies when the block's body (including justifications and indexed
+	/// transactions, unless `--justifications-pruning` or `--transactions-pruning` is given)
 	/// should be pruned (ie, removed) from the database.
 	/// Possible values:
 	///  - 'archive' Keep all blocks.
@@ -52,18 +75,66 @@ pub struct PruningParams {
 		default_value = "archive-canonical"
 	)]
 	pub blocks_pruning: DatabasePruningMode,
+
+	/// Specify the justifications pruning mode.
+	/// This mode specifies when the block's justifications should be pruned (ie, removed) from
+	/// the database, independently of the block's body. Takes the same values as
+	/// `--blocks-pruning`.
+	/// [default: the `--blocks-pruning` mode]
+	#[arg(long, value_name = "PRUNING_MODE")]
+	pub justifications_pruning: Option<DatabasePruningMode>,
+
+	/// Specify the indexed transactions pruning mode.
+	/// This mode specifies when the transaction data indexed by the block's extrinsics should be
+	/// pruned (ie, removed) from the database. The indexed transactions can be kept longer than
+	/// the block's body, but not pruned before it. Takes the same values as `--blocks-pruning`.
+	/// [default: the `--blocks-pruning` mode]
+	#[arg(long, value_name = "PRUNING_MODE")]
+	pub transactions_pruning: Option<DatabasePruningMode>,
 }
 
 impl PruningParams {
 	/// Get the pruning value from the parameters
 	pub fn state_pruning(&self) -> error::Result<Option<PruningMode>> {
-		Ok(self.state_pruning.map(|v| v.into()))
+		let mode = self.state_pruning.map(|v| v.into());
+		if self.state_pruning_checkpoints.is_none() && !self.state_pruning_allow_downgrade {
+			return Ok(mode)
+		}
+		match mode.unwrap_or_default() {
+			PruningMode::Constrained(mut constraints) => {
+				if let Some(StatePruningCheckpoints(policy)) =
+					self.state_pruning_checkpoints.clone()
+				{
+					constraints.checkpoints = Some(policy);
+				}
+				constraints.allow_archive_downgrade = self.state_pruning_allow_downgrade;
+				Ok(Some(PruningMode::Constrained(constraints)))
+			},
+			PruningMode::ArchiveAll | PruningMode::ArchiveCanonical => {
+				let flag = if self.state_pruning_allow_downgrade {
+					"--state-pruning-allow-downgrade"
+				} else {
+					"--state-pruning-checkpoints"
+				};
+				Err(error::Error::Input(format!("`{}` requires a number `--state-pruning`", flag)))
+			},
+		}
 	}
 
 	/// Get the block pruning value from the parameters
 	pub fn blocks_pruning(&self) -> error::Result<BlocksPruning> {
 		Ok(self.blocks_pruning.into())
 	}
+
+	/// Get the justifications pruning value from the parameters
+	pub fn justifications_pruning(&self) -> error::Result<Option<BlocksPruning>> {
+		Ok(self.justifications_pruning.map(Into::into))
+	}
+
+	/// Get the indexed transactions pruning value from the parameters
+	pub fn transactions_pruning(&self) -> error::Result<Option<BlocksPruning>> {
+		Ok(self.transactions_pruning.map(Into::into))
+	}
 }
 
 /// Specifies the pruning mode of the database.
@@ -115,3 +186,39 @@ impl Into<BlocksPruning> for DatabasePruningMode {
 		}
 	}
 }
+
+/// Specifies the state checkpoints retained by a pruned database.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatePruningCheckpoints(pub CheckpointPolicy);
+
+impl std::str::FromStr for StatePruningCheckpoints {
+	type Err = String;
+
+	fn from_str(input: &str) -> Result<Self, Self::Err> {
+		let mut policy = CheckpointPolicy::default();
+		if input.trim() == "none" {
+			return Ok(Self(policy))
+		}
+		for item in input.split(',').map(str::trim) {
+			if item == "none" {
+				return Err("State checkpoints 'none' can't be combined with other values".into())
+			}
+			let invalid = || format!("Invalid state checkpoints specified: {}", item);
+			let (number, unit) = match item.find(|c: char| !c.is_ascii_digit()) {
+				Some(index) => item.split_at(index),
+				None => (item, ""),
+			};
+			let number: u32 = match number.parse() {
+				Ok(0) | Err(_) => return Err(invalid()),
+				Ok(number) => number,
+			};
+			match unit {
+				"" => policy.every_blocks = Some(number),
+				"h" => policy.period = Some(Duration::from_secs(number as u64 * 60 * 60)),
+				"d" => policy.period = Some(Duration::from_secs(number as u64 * 24 * 60 * 60)),
+				_ => return Err(invalid()),
+			}
+		}
+		Ok(Self(policy))
+	}
+}
@@ -17,7 +17,7 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 use clap::Args;
-use sc_service::config::TransactionPoolOptions;
+use sc_service::config::{TransactionPoolJournalOptions, TransactionPoolOptions};
 
 /// Parameters used to create the pool configuration.
 #[derive(Debug, Clone, Args)]
@@ -30,9 +30,62 @@ pub struct TransactionPoolParams {
 	#[arg(long, value_name = "COUNT", default_value_t = 20480)]
 	pub pool_kbytes: usize,
 
+	/// Maximum number of ready and of future transactions of a single sender in the pool.
+	///
+	/// Operational transactions are not subject to this limit.
+	#[arg(long, value_name = "COUNT")]
+	pub pool_sender_limit: Option<usize>,
+
+	/// Maximum number of kilobytes of the ready and of the future transactions of a single
+	/// sender in the pool.
+	///
+	/// Operational transactions are not subject to this limit.
+	#[arg(long, value_name = "COUNT")]
+	pub pool_sender_kbytes: Option<usize>,
+
+	/// Percentage of the ready queue capacity that is reserved for operational transactions.
+	///
+	/// Operational transactions are the ones submitted by the node itself and the ones with a
+	/// priority of at least `--pool-operational-priority`.
+	#[arg(
+		long,
+		value_name = "PERCENT",
+		default_value_t = 0,
+		value_parser = clap::value_parser!(u8).range(0..=100)
+	)]
+	pub pool_operational_reserve: u8,
+
+	/// Minimal priority of a transaction to be considered operational. Defaults to the maximal
+	/// priority.
+	#[arg(long, value_name = "PRIORITY")]
+	pub pool_operational_priority: Option<u64>,
+
+	/// Minimal priority increase, in percent, of a transaction replacing pending transactions
+	/// that provide the same tags, e.g. the same sender and nonce.
+	///
+	/// A replacement always needs a strictly higher priority than the replaced transactions.
+	#[arg(long, value_name = "PERCENT", default_value_t = 0)]
+	pub pool_replacement_bump: u32,
+
 	/// How long a transaction is banned for, if it is considered invalid. Defaults to 1800s.
 	#[arg(long, value_name = "SECONDS")]
 	pub tx_ban_seconds: Option<u64>,
+
+	/// Persist the submitted transactions in a journal, so that they are resubmitted to the
+	/// pool after a restart of the node.
+	#[arg(long)]
+	pub pool_journal: bool,
+
+	/// Maximum number of kilobytes of the transaction pool journal.
+	#[arg(long, value_name = "COUNT", default_value_t = 10240)]
+	pub pool_journal_kbytes: usize,
+
+	/// Keep a validated view of the transaction pool at the head of every fork.
+	///
+	/// Blocks can then be authored on top of any fork head, and the `Dropped`, `Usurped` and
+	/// `Invalid` transaction events are only emitted once settled by finality.
+	#[arg(long)]
+	pub pool_fork_aware: bool,
 }
 
 impl TransactionPoolParams {
@@ -49,6 +102,26 @@ impl TransactionPoolParams {
 		opts.future.count = self.pool_limit / factor;
 		opts.future.total_bytes = self.pool_kbytes * 1024 / factor;
 
+		// per-sender limits
+		if let Some(count) = self.pool_sender_limit {
+			opts.sender_limits.ready.count = count;
+			opts.sender_limits.future.count = count;
+		}
+		if let Some(kbytes) = self.pool_sender_kbytes {
+			opts.sender_limits.ready.total_bytes = kbytes * 1024;
+			opts.sender_limits.future.total_bytes = kbytes * 1024;
+		}
+
+		// capacity reserved for operational transactions
+		let reserve = self.pool_operational_reserve as usize;
+		opts.lanes.reserved.count = opts.ready.count * reserve / 100;
+		opts.lanes.reserved.total_bytes = opts.ready.total_bytes / 100 * reserve;
+		if let Some(priority) = self.pool_operational_priority {
+			opts.lanes.operational_priority = priority;
+		}
+
+		opts.replacement.min_bump_percent = self.pool_replacement_bump;
+
 		opts.ban_time = if let Some(ban_seconds) = self.tx_ban_seconds {
 			std::time::Duration::from_secs(ban_seconds)
 		} else if is_dev {
@@ -57,6 +130,16 @@ impl TransactionPoolParams {
 			std::time::Duration::from_secs(30 * 60)
 		};
 
+		opts.fork_aware = self.pool_fork_aware;
+
+		// the path is relative to the config directory of the chain
+		if self.pool_journal {
+			opts.journal = Some(TransactionPoolJournalOptions {
+				path: "txpool/journal".into(),
+				max_bytes: self.pool_journal_kbytes * 1024,
+			});
+		}
+
 		opts
 	}
 }
@@ -0,0 +1,534 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Consistency check of the database.
+//!
+//! The metadata and the canonical chain are checked for the presence of the headers, of the
+//! bodies retained by the blocks pruning mode and of decodable justifications. The state of the
+//! blocks retained by the state pruning mode is walked to check that all trie nodes are
+//! reachable. In archive modes, only the state of the finalized, best, checkpoint and
+//! non-canonical blocks is walked.
+//!
+//! Visited trie nodes are tracked by their key, so that a node shared by several states is only
+//! read once. A missing node is reported for the first walked block which state references it.
+
+use std::{collections::HashSet, fmt};
+
+use log::info;
+use sc_client_api::blockchain::Backend as _;
+use sp_blockchain::{HeaderBackend, HeaderMetadata, Result as ClientResult};
+use sp_runtime::traits::{
+	Block as BlockT, HashFor, Header as HeaderT, NumberFor, One, SaturatedConversion, Zero,
+};
+use sp_state_machine::DBValue;
+use sp_trie::{empty_trie_root, LayoutV1};
+
+use crate::{
+	trie_walk::{walk_trie, TrieVisitor},
+	Backend, BlocksPruning, StateMetaDb, StorageDb,
+};
+
+const LOG_TARGET: &str = "db::check";
+/// Interval between progress messages of the canonical chain check.
+const PROGRESS_INTERVAL: u64 = 100_000;
+
+/// An inconsistency found by [`Backend::check_consistency`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Inconsistency<Hash, Number> {
+	/// The genesis hash in the metadata is not the hash of the canonical block `0`.
+	GenesisMismatch {
+		/// Genesis hash in the metadata.
+		genesis: Hash,
+		/// Number of the genesis block.
+		number: Number,
+	},
+	/// A block referenced by the metadata is not in the canonical chain.
+	NotCanonical {
+		/// Block number.
+		number: Number,
+		/// Block hash.
+		hash: Hash,
+	},
+	/// No canonical block is recorded at a number.
+	MissingCanonicalBlock {
+		/// Block number.
+		number: Number,
+	},
+	/// The header of a block is missing.
+	MissingHeader {
+		/// Block number.
+		number: Number,
+		/// Block hash.
+		hash: Hash,
+	},
+	/// The number or the parent of a canonical header doesn't match the canonical chain.
+	InvalidHeader {
+		/// Block number.
+		number: Number,
+		/// Block hash.
+		hash: Hash,
+	},
+	/// The body of a block retained by the blocks pruning mode is missing.
+	MissingBody {
+		/// Block number.
+		number: Number,
+		/// Block hash.
+		hash: Hash,
+	},
+	/// The body of a block can't be decoded, or its indexed transactions are missing.
+	InvalidBody {
+		/// Block number.
+		number: Number,
+		/// Block hash.
+		hash: Hash,
+		/// Error reading the body.
+		error: String,
+	},
+	/// The justifications of a block can't be decoded.
+	InvalidJustifications {
+		/// Block number.
+		number: Number,
+		/// Block hash.
+		hash: Hash,
+		/// Error reading the justifications.
+		error: String,
+	},
+	/// A trie node of the state of a block is missing.
+	MissingStateNode {
+		/// Block number.
+		number: Number,
+		/// Block hash.
+		hash: Hash,
+		/// Key of the node in the state column.
+		key: Vec<u8>,
+	},
+	/// A trie node of the state of a block can't be decoded.
+	InvalidStateNode {
+		/// Block number.
+		number: Number,
+		/// Block hash.
+		hash: Hash,
+		/// Decoding error.
+		error: String,
+	},
+}
+
+impl<Hash, Number: Copy> Inconsistency<Hash, Number> {
+	/// Number of the inconsistent block.
+	pub fn number(&self) -> Number {
+		match self {
+			Self::GenesisMismatch { number, .. } |
+			Self::NotCanonical { number, .. } |
+			Self::MissingCanonicalBlock { number } |
+			Self::MissingHeader { number, .. } |
+			Self::InvalidHeader { number, .. } |
+			Self::MissingBody { number, .. } |
+			Self::InvalidBody { number, .. } |
+			Self::InvalidJustifications { number, .. } |
+			Self::MissingStateNode { number, .. } |
+			Self::InvalidStateNode { number, .. } => *number,
+		}
+	}
+}
+
+impl<Hash: fmt::Debug, Number: fmt::Display> fmt::Display for Inconsistency<Hash, Number> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Self::GenesisMismatch { genesis, number } =>
+				write!(f, "Genesis hash {:?} is not the hash of block #{}", genesis, number),
+			Self::NotCanonical { number, hash } =>
+				write!(f, "Block #{} ({:?}) is not canonical", number, hash),
+			Self::MissingCanonicalBlock { number } =>
+				write!(f, "No canonical block at #{}", number),
+			Self::MissingHeader { number, hash } =>
+				write!(f, "Missing header of block #{} ({:?})", number, hash),
+			Self::InvalidHeader { number, hash } => write!(
+				f,
+				"Header of block #{} ({:?}) doesn't match the canonical chain",
+				number, hash
+			),
+			Self::MissingBody { number, hash } =>
+				write!(f, "Missing body of block #{} ({:?})", number, hash),
+			Self::InvalidBody { number, hash, error } =>
+				write!(f, "Invalid body of block #{} ({:?}): {}", number, hash, error),
+			Self::InvalidJustifications { number, hash, error } =>
+				write!(f, "Invalid justifications of block #{} ({:?}): {}", number, hash, error),
+			Self::MissingStateNode { number, hash, key } => write!(
+				f,
+				"Missing trie node 0x{} of the state of block #{} ({:?})",
+				sp_core::hexdisplay::HexDisplay::from(key),
+				number,
+				hash
+			),
+			Self::InvalidStateNode { number, hash, error } => write!(
+				f,
+				"Invalid trie node in the state of block #{} ({:?}): {}",
+				number, hash, error
+			),
+		}
+	}
+}
+
+/// Result of [`Backend::check_consistency`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsistencyReport<Hash, Number> {
+	/// Number of checked canonical blocks.
+	pub checked_blocks: u64,
+	/// Number of blocks which state was walked.
+	pub checked_states: u64,
+	/// Number of distinct trie nodes read.
+	pub checked_nodes: u64,
+	/// Found inconsistencies.
+	pub inconsistencies: Vec<Inconsistency<Hash, Number>>,
+}
+
+impl<Hash, Number: Copy + Ord> ConsistencyReport<Hash, Number> {
+	/// Returns `true` if no inconsistency was found.
+	pub fn is_consistent(&self) -> bool {
+		self.inconsistencies.is_empty()
+	}
+
+	/// Lowest number of an inconsistent block.
+	pub fn first_inconsistent_block(&self) -> Option<Number> {
+		self.inconsistencies.iter().map(Inconsistency::number).min()
+	}
+}
+
+impl<Block: BlockT> Backend<Block> {
+	/// Check the consistency of the metadata, of the canonical chain and of the retained state.
+	///
+	/// This reads the whole canonical chain and should be run while the node is stopped.
+	pub fn check_consistency(
+		&self,
+	) -> ClientResult<ConsistencyReport<Block::Hash, NumberFor<Block>>> {
+		let mut report = ConsistencyReport {
+			checked_blocks: 0,
+			checked_states: 0,
+			checked_nodes: 0,
+			inconsistencies: Vec::new(),
+		};
+		self.check_meta(&mut report.inconsistencies)?;
+		self.check_canonical_chain(&mut report)?;
+		self.check_state(&mut report)?;
+		Ok(report)
+	}
+
+	fn check_meta(
+		&self,
+		inconsistencies: &mut Vec<Inconsistency<Block::Hash, NumberFor<Block>>>,
+	) -> ClientResult<()> {
+		let info = self.blockchain.info();
+		if info.genesis_hash != Default::default() &&
+			self.blockchain.hash(Zero::zero())? != Some(info.genesis_hash)
+		{
+			inconsistencies.push(Inconsistency::GenesisMismatch {
+				genesis: info.genesis_hash,
+				number: Zero::zero(),
+			});
+		}
+
+		let mut blocks =
+			vec![(info.best_number, info.best_hash), (info.finalized_number, info.finalized_hash)];
+		if let Some((hash, number)) = info.finalized_state {
+			blocks.push((number, hash));
+		}
+		for (number, hash) in blocks {
+			if hash == Default::default() {
+				continue
+			}
+			if self.blockchain.header(hash)?.is_none() {
+				push_unique(inconsistencies, Inconsistency::MissingHeader { number, hash });
+			}
+		}
+		if self.blockchain.hash(info.finalized_number)? != Some(info.finalized_hash) {
+			inconsistencies.push(Inconsistency::NotCanonical {
+				number: info.finalized_number,
+				hash: info.finalized_hash,
+			});
+		}
+		Ok(())
+	}
+
+	fn check_canonical_chain(
+		&self,
+		report: &mut ConsistencyReport<Block::Hash, NumberFor<Block>>,
+	) -> ClientResult<()> {
+		let info = self.blockchain.info();
+		// Bodies of the blocks older than the blocks pruning window are removed.
+		let first_body = match self.blocks_pruning {
+			BlocksPruning::KeepAll | BlocksPruning::KeepFinalized => Zero::zero(),
+			BlocksPruning::Some(keep) =>
+				(info.finalized_number + One::one()).saturating_sub(keep.max(1).into()),
+		};
+
+		let mut parent = None;
+		let mut number = Zero::zero();
+		while number <= info.best_number {
+			if let Some((start, end)) = info.block_gap {
+				if number == start {
+					// Blocks of the gap are not downloaded yet.
+					number = end + One::one();
+					parent = None;
+					continue
+				}
+			}
+			report.checked_blocks += 1;
+			if report.checked_blocks % PROGRESS_INTERVAL == 0 {
+				info!(target: LOG_TARGET, "Checked blocks up to #{}", number);
+			}
+
+			let hash = match self.blockchain.hash(number)? {
+				Some(hash) => hash,
+				None => {
+					report.inconsistencies.push(Inconsistency::MissingCanonicalBlock { number });
+					parent = None;
+					number += One::one();
+					continue
+				},
+			};
+			match self.blockchain.header(hash)? {
+				Some(header) =>
+					if *header.number() != number ||
+						parent.map_or(false, |parent| *header.parent_hash() != parent)
+					{
+						report.inconsistencies.push(Inconsistency::InvalidHeader { number, hash });
+					},
+				None => push_unique(
+					&mut report.inconsistencies,
+					Inconsistency::MissingHeader { number, hash },
+				),
+			}
+			match self.blockchain.body(hash) {
+				Ok(Some(_)) => {},
+				Ok(None) if number < first_body => {},
+				Ok(None) =>
+					report.inconsistencies.push(Inconsistency::MissingBody { number, hash }),
+				Err(e) => report.inconsistencies.push(Inconsistency::InvalidBody {
+					number,
+					hash,
+					error: e.to_string(),
+				}),
+			}
+			if let Err(e) = self.blockchain.justifications(hash) {
+				report.inconsistencies.push(Inconsistency::InvalidJustifications {
+					number,
+					hash,
+					error: e.to_string(),
+				});
+			}
+
+			parent = Some(hash);
+			number += One::one();
+		}
+		Ok(())
+	}
+
+	fn check_state(
+		&self,
+		report: &mut ConsistencyReport<Block::Hash, NumberFor<Block>>,
+	) -> ClientResult<()> {
+		let info = self.blockchain.info();
+		let mut blocks = sc_state_db::journaled_blocks::<Block::Hash, Vec<u8>, _>(&StateMetaDb(
+			self.storage.db.clone(),
+		))
+		.map_err(sp_blockchain::Error::from_state_db)?
+		.into_iter()
+		.map(|(hash, number)| (number.saturated_into(), hash))
+		.collect::<Vec<_>>();
+		blocks.extend(
+			self.storage
+				.state_db
+				.checkpoints()
+				.into_iter()
+				.map(|c| (c.number.saturated_into(), c.hash)),
+		);
+		blocks.extend(info.finalized_state.map(|(hash, number)| (number, hash)));
+		if info.best_hash != Default::default() {
+			blocks.push((info.best_number, info.best_hash));
+		}
+		blocks.sort();
+		blocks.dedup();
+
+		let mut checker = StateChecker {
+			storage: &self.storage,
+			visited: HashSet::new(),
+			block: Default::default(),
+			inconsistencies: &mut report.inconsistencies,
+		};
+		for (number, hash) in blocks {
+			let root = match self.blockchain.header_metadata(hash) {
+				Ok(header) => header.state_root,
+				Err(_) => {
+					push_unique(
+						checker.inconsistencies,
+						Inconsistency::MissingHeader { number, hash },
+					);
+					continue
+				},
+			};
+			// The empty trie root is not stored.
+			if root == empty_trie_root::<LayoutV1<HashFor<Block>>>() {
+				continue
+			}
+			checker.block = (number, hash);
+			let prefix_keys = self.storage.prefix_keys;
+			let mut child_roots = Vec::new();
+			walk_trie::<Block, _>(&mut checker, prefix_keys, root, &[], Some(&mut child_roots))?;
+			for (root, keyspace) in child_roots {
+				walk_trie::<Block, _>(&mut checker, prefix_keys, root, &keyspace, None)?;
+			}
+			report.checked_states += 1;
+		}
+		report.checked_nodes = checker.visited.len() as u64;
+		Ok(())
+	}
+}
+
+fn push_unique<T: PartialEq>(items: &mut Vec<T>, item: T) {
+	if !items.contains(&item) {
+		items.push(item);
+	}
+}
+
+/// Records the missing and invalid trie nodes of the state of a block.
+struct StateChecker<'a, Block: BlockT> {
+	storage: &'a StorageDb<Block>,
+	/// Keys of the visited nodes, shared by all checked states.
+	visited: HashSet<Vec<u8>>,
+	/// Number and hash of the block which state is walked.
+	block: (NumberFor<Block>, Block::Hash),
+	inconsistencies: &'a mut Vec<Inconsistency<Block::Hash, NumberFor<Block>>>,
+}
+
+impl<'a, Block: BlockT> StateChecker<'a, Block> {
+	fn read(&mut self, key: &[u8]) -> ClientResult<Option<DBValue>> {
+		if !self.visited.insert(key.to_vec()) {
+			return Ok(None)
+		}
+		let node = self
+			.storage
+			.state_db
+			.get(key, self.storage)
+			.map_err(sp_blockchain::Error::from_state_db)?;
+		if node.is_none() {
+			let (number, hash) = self.block;
+			self.inconsistencies.push(Inconsistency::MissingStateNode {
+				number,
+				hash,
+				key: key.to_vec(),
+			});
+		}
+		Ok(node)
+	}
+}
+
+impl<'a, Block: BlockT> TrieVisitor for StateChecker<'a, Block> {
+	type Error = sp_blockchain::Error;
+
+	fn node(&mut self, key: &[u8]) -> ClientResult<Option<DBValue>> {
+		self.read(key)
+	}
+
+	fn value_node(&mut self, key: &[u8]) -> ClientResult<()> {
+		self.read(key).map(|_| ())
+	}
+
+	fn invalid_node(&mut self, error: String) -> ClientResult<()> {
+		let (number, hash) = self.block;
+		self.inconsistencies
+			.push(Inconsistency::InvalidStateNode { number, hash, error });
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		columns,
+		tests::{insert_header, Block},
+		DatabaseSettings, DatabaseSource, PruningMode,
+	};
+	use sc_client_api::backend::Backend as _;
+	use sp_database::Transaction;
+
+	fn reopen(backend: Backend<Block>) -> Backend<Block> {
+		let db = backend.storage.db.clone();
+		drop(backend);
+		Backend::<Block>::new(
+			DatabaseSettings {
+				trie_cache_maximum_size: None,
+				trie_cache_snapshot: None,
+				state_pruning: Some(PruningMode::blocks_pruning(4)),
+				source: DatabaseSource::Custom { db, require_create_flag: false },
+				blocks_pruning: BlocksPruning::Some(4),
+				justifications_pruning: None,
+				transactions_pruning: None,
+			},
+			0,
+		)
+		.unwrap()
+	}
+
+	#[test]
+	fn detects_missing_header_and_state_node() {
+		let backend = Backend::<Block>::new_test(4, 0);
+		let mut hashes =
+			vec![insert_header(&backend, 0, Default::default(), None, Default::default())];
+		for number in 1..6 {
+			let hash = insert_header(
+				&backend,
+				number,
+				hashes[number as usize - 1],
+				None,
+				Default::default(),
+			);
+			hashes.push(hash);
+		}
+		for hash in &hashes[1..4] {
+			backend.finalize_block(*hash, None).unwrap();
+		}
+
+		let report = backend.check_consistency().unwrap();
+		assert!(report.is_consistent(), "{:?}", report.inconsistencies);
+		assert_eq!(report.checked_blocks, 6);
+
+		let backend = reopen(backend);
+		let root_3 = *backend.blockchain.header(hashes[3]).unwrap().unwrap().state_root();
+		let header_4 = crate::utils::number_and_hash_to_lookup_key(4, &hashes[4]).unwrap();
+		let mut transaction = Transaction::new();
+		transaction.remove(columns::STATE, root_3.as_ref());
+		transaction.remove(columns::HEADER, &header_4);
+		backend.storage.db.commit(transaction).unwrap();
+
+		let backend = reopen(backend);
+		let report = backend.check_consistency().unwrap();
+		assert_eq!(
+			report.inconsistencies,
+			vec![
+				Inconsistency::MissingHeader { number: 4, hash: hashes[4] },
+				Inconsistency::MissingStateNode {
+					number: 3,
+					hash: hashes[3],
+					key: root_3.as_ref().to_vec(),
+				},
+			],
+		);
+		assert_eq!(report.first_inconsistent_block(), Some(3));
+	}
+}
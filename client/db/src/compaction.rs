@@ -0,0 +1,495 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Background compaction of the state column after switching from an archive to a constrained
+//! state pruning mode.
+//!
+//! The state written before the switch is not tracked by the pruning window, so it is removed with
+//! a mark and sweep. Trie nodes reachable from the state that the new pruning mode retains are
+//! marked, along with any node that is written while the compaction runs. The state column is
+//! then swept in a number of passes, each removing the unmarked nodes of a part of the key space.
+//!
+//! The marks are the keys of the retained nodes. They are stored in a temporary parity-db
+//! database next to the node database, or in memory for a database that is itself in memory. The
+//! number of sweep passes is chosen after counting the unmarked nodes, so that a pass keeps at
+//! most [`SWEEP_PASS_NODES`] keys in memory, up to [`MAX_SWEEP_PASSES`] passes. The compaction
+//! restarts from the beginning if the node is restarted before it completes.
+
+use std::{
+	collections::{HashMap, HashSet},
+	io,
+	path::PathBuf,
+	sync::{
+		atomic::{AtomicU32, AtomicU64, Ordering},
+		Arc, Weak,
+	},
+};
+
+use log::{debug, info, warn};
+use parking_lot::Mutex;
+use sc_client_api::StateCompactionInfo;
+use sp_database::{error, Change, ColumnEntry, ColumnId, Database, Transaction};
+use sp_runtime::traits::{Block as BlockT, Hash, HashFor};
+use sp_state_machine::DBValue;
+
+use crate::{
+	apply_state_commit, columns,
+	trie_walk::{fingerprint, walk_trie, TrieVisitor},
+	DbHash, StorageDb,
+};
+
+const LOG_TARGET: &str = "db::compaction";
+/// Directory of the marks database, next to the node database.
+pub(crate) const MARKS_DIR: &str = "state-compaction";
+/// Maximum number of marks buffered in memory before being written to the marks database.
+const MARKS_BATCH: usize = 4096;
+/// Target number of unmarked nodes removed by a sweep pass. Their keys are kept in memory.
+const SWEEP_PASS_NODES: u64 = 1 << 20;
+/// Maximum number of passes over the state column. Each pass iterates over the whole column.
+const MAX_SWEEP_PASSES: u32 = 16;
+/// Maximum number of nodes removed in a single transaction.
+const SWEEP_BATCH: usize = 4096;
+
+fn db_error(e: impl std::error::Error + Send + Sync + 'static) -> error::DatabaseError {
+	error::DatabaseError(Box::new(e))
+}
+
+/// Keys of the retained nodes.
+enum Marks {
+	/// Marks of a database in memory.
+	Memory(HashSet<Vec<u8>>),
+	/// Marks stored in a temporary database. The last ones are buffered in `pending`.
+	Disk { db: parity_db::Db, path: PathBuf, pending: HashSet<Vec<u8>> },
+}
+
+impl Marks {
+	/// Open the marks, discarding the ones left by an interrupted compaction.
+	fn open(path: Option<PathBuf>) -> error::Result<Self> {
+		let path = match path {
+			Some(path) => path,
+			None => return Ok(Marks::Memory(Default::default())),
+		};
+		match std::fs::remove_dir_all(&path) {
+			Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(db_error(e)),
+			_ => {},
+		}
+		let mut options = parity_db::Options::with_columns(&path, 1);
+		// The marks are discarded if the node stops.
+		options.sync_wal = false;
+		options.sync_data = false;
+		options.stats = false;
+		let db = parity_db::Db::open_or_create(&options).map_err(db_error)?;
+		Ok(Marks::Disk { db, path, pending: Default::default() })
+	}
+
+	fn contains(&self, key: &[u8]) -> error::Result<bool> {
+		match self {
+			Marks::Memory(marks) => Ok(marks.contains(key)),
+			Marks::Disk { db, pending, .. } =>
+				Ok(pending.contains(key) || db.get(0, key).map_err(db_error)?.is_some()),
+		}
+	}
+
+	/// Mark a node. Returns `false` if it was already marked.
+	fn insert(&mut self, key: &[u8]) -> error::Result<bool> {
+		if self.contains(key)? {
+			return Ok(false)
+		}
+		match self {
+			Marks::Memory(marks) => {
+				marks.insert(key.to_vec());
+			},
+			Marks::Disk { db, pending, .. } => {
+				pending.insert(key.to_vec());
+				if pending.len() >= MARKS_BATCH {
+					db.commit(pending.drain().map(|key| (0, key, Some(Vec::new()))))
+						.map_err(db_error)?;
+				}
+			},
+		}
+		Ok(true)
+	}
+
+	/// Remove the marks database.
+	fn remove(self) {
+		if let Marks::Disk { db, path, .. } = self {
+			drop(db);
+			if let Err(e) = std::fs::remove_dir_all(&path) {
+				warn!(target: LOG_TARGET, "Failed to remove {}: {}", path.display(), e);
+			}
+		}
+	}
+}
+
+/// State of a running compaction, shared with the database wrapper.
+struct State {
+	marks: Marks,
+	/// The sweep pass that is running, with the number of passes.
+	pass: Option<(u32, u32)>,
+	/// References of the nodes of the running pass released by the commits since the pass
+	/// started.
+	released: HashMap<Vec<u8>, u32>,
+}
+
+impl State {
+	/// Record the changes of a commit to the state column.
+	fn record(&mut self, transaction: &Transaction<DbHash>) -> error::Result<u64> {
+		let mut marked = 0;
+		for change in &transaction.0 {
+			match change {
+				Change::Set(columns::STATE, key, _) => marked += self.marks.insert(key)? as u64,
+				Change::Store(columns::STATE, hash, _) |
+				Change::Reference(columns::STATE, hash) => marked += self.marks.insert(hash.as_ref())? as u64,
+				Change::Remove(columns::STATE, key) => self.release(key),
+				Change::Release(columns::STATE, hash) => self.release(hash.as_ref()),
+				_ => {},
+			}
+		}
+		Ok(marked)
+	}
+
+	fn release(&mut self, key: &[u8]) {
+		if let Some((pass, passes)) = self.pass {
+			if fingerprint(key) % passes as u64 == pass as u64 {
+				*self.released.entry(key.to_vec()).or_default() += 1;
+			}
+		}
+	}
+}
+
+/// Shared state of a running compaction.
+pub(crate) struct Compaction {
+	/// `None` once the compaction is completed.
+	state: Mutex<Option<State>>,
+	marked_nodes: AtomicU64,
+	sweep_passes_done: AtomicU32,
+	sweep_passes: AtomicU32,
+	removed_nodes: AtomicU64,
+}
+
+impl Compaction {
+	/// Create a compaction storing its marks in `marks_path`, or in memory if `None`.
+	pub fn new(marks_path: Option<PathBuf>) -> error::Result<Self> {
+		let state =
+			State { marks: Marks::open(marks_path)?, pass: None, released: Default::default() };
+		Ok(Compaction {
+			state: Mutex::new(Some(state)),
+			marked_nodes: AtomicU64::new(0),
+			sweep_passes_done: AtomicU32::new(0),
+			sweep_passes: AtomicU32::new(0),
+			removed_nodes: AtomicU64::new(0),
+		})
+	}
+
+	/// Returns the progress, or `None` if the compaction is completed.
+	pub fn info(&self) -> Option<StateCompactionInfo> {
+		self.state.lock().as_ref()?;
+		Some(StateCompactionInfo {
+			marked_nodes: self.marked_nodes.load(Ordering::Relaxed),
+			sweep_passes_done: self.sweep_passes_done.load(Ordering::Relaxed),
+			sweep_passes: self.sweep_passes.load(Ordering::Relaxed),
+			removed_nodes: self.removed_nodes.load(Ordering::Relaxed),
+		})
+	}
+
+	/// Mark a node. Returns `false` if it was already marked.
+	fn mark(&self, key: &[u8]) -> error::Result<bool> {
+		let marked = match self.state.lock().as_mut() {
+			Some(state) => state.marks.insert(key)?,
+			None => false,
+		};
+		if marked {
+			self.marked_nodes.fetch_add(1, Ordering::Relaxed);
+		}
+		Ok(marked)
+	}
+
+	fn is_marked(&self, key: &[u8]) -> error::Result<bool> {
+		self.state.lock().as_ref().map_or(Ok(true), |state| state.marks.contains(key))
+	}
+}
+
+/// A database wrapper marking the state nodes written while the compaction runs.
+pub(crate) struct CompactingDb {
+	inner: Arc<dyn Database<DbHash>>,
+	compaction: Arc<Compaction>,
+}
+
+impl CompactingDb {
+	pub fn new(inner: Arc<dyn Database<DbHash>>, compaction: Arc<Compaction>) -> Self {
+		CompactingDb { inner, compaction }
+	}
+}
+
+impl Database<DbHash> for CompactingDb {
+	fn commit(&self, transaction: Transaction<DbHash>) -> error::Result<()> {
+		// The sweep checks the marks and removes nodes under the same lock, so a node written
+		// here is either marked before the check or written again after the removal.
+		if let Some(state) = self.compaction.state.lock().as_mut() {
+			let marked = state.record(&transaction)?;
+			self.compaction.marked_nodes.fetch_add(marked, Ordering::Relaxed);
+		}
+		self.inner.commit(transaction)
+	}
+
+	fn get(&self, col: ColumnId, key: &[u8]) -> Option<Vec<u8>> {
+		self.inner.get(col, key)
+	}
+
+	fn contains(&self, col: ColumnId, key: &[u8]) -> bool {
+		self.inner.contains(col, key)
+	}
+
+	fn value_size(&self, col: ColumnId, key: &[u8]) -> Option<usize> {
+		self.inner.value_size(col, key)
+	}
+
+	fn with_get(&self, col: ColumnId, key: &[u8], f: &mut dyn FnMut(&[u8])) {
+		self.inner.with_get(col, key, f)
+	}
+
+	fn supports_ref_counting(&self) -> bool {
+		self.inner.supports_ref_counting()
+	}
+
+	fn iter_column(
+		&self,
+		col: ColumnId,
+		f: &mut dyn FnMut(ColumnEntry) -> bool,
+	) -> error::Result<()> {
+		self.inner.iter_column(col, f)
+	}
+
+	fn sanitize_key(&self, key: &mut Vec<u8>) {
+		self.inner.sanitize_key(key)
+	}
+}
+
+/// Reason for the compaction to stop before completing.
+enum Interrupted {
+	/// The backend was closed.
+	Closed,
+	/// A database operation failed.
+	Failed(error::DatabaseError),
+}
+
+/// Start the compaction in a background thread, retaining the state of the given roots.
+pub(crate) fn spawn<Block: BlockT>(
+	storage: Weak<StorageDb<Block>>,
+	db: Arc<dyn Database<DbHash>>,
+	compaction: Arc<Compaction>,
+	roots: Vec<Block::Hash>,
+) -> std::io::Result<()> {
+	std::thread::Builder::new().name("state-compaction".into()).spawn(move || {
+		info!(
+			target: LOG_TARGET,
+			"🧹 Compacting the state, retaining the state of {} blocks",
+			roots.len(),
+		);
+		match compact(&storage, &*db, &compaction, roots) {
+			Ok(()) => info!(
+				target: LOG_TARGET,
+				"🧹 State compaction completed, {} trie nodes removed",
+				compaction.removed_nodes.load(Ordering::Relaxed),
+			),
+			Err(Interrupted::Closed) => debug!(target: LOG_TARGET, "State compaction interrupted"),
+			Err(Interrupted::Failed(e)) => warn!(
+				target: LOG_TARGET,
+				"State compaction failed: {}. It is restarted with the node.",
+				e,
+			),
+		}
+	})?;
+	Ok(())
+}
+
+fn compact<Block: BlockT>(
+	storage: &Weak<StorageDb<Block>>,
+	db: &dyn Database<DbHash>,
+	compaction: &Compaction,
+	roots: Vec<Block::Hash>,
+) -> Result<(), Interrupted> {
+	let prefix_keys = storage.upgrade().ok_or(Interrupted::Closed)?.prefix_keys;
+	let mut marker = Marker { storage, compaction };
+	let mut child_roots = Vec::new();
+	for root in roots {
+		walk_trie::<Block, _>(&mut marker, prefix_keys, root, &[], Some(&mut child_roots))?;
+	}
+	for (root, keyspace) in child_roots {
+		walk_trie::<Block, _>(&mut marker, prefix_keys, root, &keyspace, None)?;
+	}
+
+	let unmarked = count_unmarked::<Block>(db, compaction).map_err(Interrupted::Failed)?;
+	let passes = ((unmarked + SWEEP_PASS_NODES - 1) / SWEEP_PASS_NODES)
+		.clamp(1, MAX_SWEEP_PASSES as u64) as u32;
+	compaction.sweep_passes.store(passes, Ordering::Relaxed);
+	debug!(
+		target: LOG_TARGET,
+		"Marked {} trie nodes, removing {} trie nodes in {} passes",
+		compaction.marked_nodes.load(Ordering::Relaxed),
+		unmarked,
+		passes,
+	);
+
+	for pass in 0..passes {
+		if storage.strong_count() == 0 {
+			return Err(Interrupted::Closed)
+		}
+		sweep::<Block>(db, compaction, pass, passes).map_err(Interrupted::Failed)?;
+		compaction.sweep_passes_done.store(pass + 1, Ordering::Relaxed);
+	}
+
+	let storage = storage.upgrade().ok_or(Interrupted::Closed)?;
+	let mut transaction = Transaction::new();
+	apply_state_commit(&mut transaction, storage.state_db.finish_compaction());
+	db.commit(transaction).map_err(Interrupted::Failed)?;
+	let state = compaction.state.lock().take();
+	if let Some(state) = state {
+		state.marks.remove();
+	}
+	Ok(())
+}
+
+/// Marks the visited trie nodes.
+struct Marker<'a, Block: BlockT> {
+	storage: &'a Weak<StorageDb<Block>>,
+	compaction: &'a Compaction,
+}
+
+impl<'a, Block: BlockT> TrieVisitor for Marker<'a, Block> {
+	type Error = Interrupted;
+
+	fn node(&mut self, key: &[u8]) -> Result<Option<DBValue>, Interrupted> {
+		let storage = self.storage.upgrade().ok_or(Interrupted::Closed)?;
+		if !self.compaction.mark(key).map_err(Interrupted::Failed)? {
+			return Ok(None)
+		}
+		// Nodes of the unfinalized blocks may still be in the state-db overlay. `None` if pruned
+		// since the compaction has started.
+		storage.state_db.get(key, &*storage).or_else(|e| {
+			debug!(target: LOG_TARGET, "Error reading trie node: {:?}", e);
+			Ok(None)
+		})
+	}
+
+	fn value_node(&mut self, key: &[u8]) -> Result<(), Interrupted> {
+		self.compaction.mark(key).map_err(Interrupted::Failed)?;
+		Ok(())
+	}
+
+	fn invalid_node(&mut self, error: String) -> Result<(), Interrupted> {
+		debug!(target: LOG_TARGET, "Error decoding trie node: {}", error);
+		Ok(())
+	}
+}
+
+/// Key of an entry of the state column.
+fn entry_key<Block: BlockT>(entry: &ColumnEntry) -> Vec<u8> {
+	// Keys of a reference counted column are the hashes of the values.
+	match entry.key {
+		Some(key) => key.to_vec(),
+		None => HashFor::<Block>::hash(entry.value).as_ref().to_vec(),
+	}
+}
+
+/// Call `f` with the key and the number of references of the unmarked nodes.
+fn for_each_unmarked<Block: BlockT>(
+	db: &dyn Database<DbHash>,
+	compaction: &Compaction,
+	mut f: impl FnMut(Vec<u8>, u32),
+) -> error::Result<()> {
+	let mut result = Ok(());
+	db.iter_column(columns::STATE, &mut |entry| {
+		let key = entry_key::<Block>(&entry);
+		match compaction.is_marked(&key) {
+			Ok(true) => {},
+			Ok(false) => f(key, entry.references),
+			Err(e) => {
+				result = Err(e);
+				return false
+			},
+		}
+		true
+	})?;
+	result
+}
+
+fn count_unmarked<Block: BlockT>(
+	db: &dyn Database<DbHash>,
+	compaction: &Compaction,
+) -> error::Result<u64> {
+	let mut unmarked = 0;
+	for_each_unmarked::<Block>(db, compaction, |_, _| unmarked += 1)?;
+	Ok(unmarked)
+}
+
+/// Remove the unmarked nodes which fingerprint falls into the given pass.
+fn sweep<Block: BlockT>(
+	db: &dyn Database<DbHash>,
+	compaction: &Compaction,
+	pass: u32,
+	passes: u32,
+) -> error::Result<()> {
+	match compaction.state.lock().as_mut() {
+		Some(state) => {
+			state.pass = Some((pass, passes));
+			state.released.clear();
+		},
+		None => return Ok(()),
+	}
+	let mut unmarked = Vec::new();
+	for_each_unmarked::<Block>(db, compaction, |key, references| {
+		if fingerprint(&key) % passes as u64 == pass as u64 {
+			unmarked.push((key, references));
+		}
+	})?;
+
+	for batch in unmarked.chunks(SWEEP_BATCH) {
+		let mut state = compaction.state.lock();
+		let state = match state.as_mut() {
+			Some(state) => state,
+			None => return Ok(()),
+		};
+		let mut transaction = Transaction::new();
+		let mut removed = 0;
+		for (key, references) in batch {
+			// Marked by a commit since the iteration.
+			if state.marks.contains(key)? {
+				continue
+			}
+			// The database only exposes the reference counts while iterating. They are updated
+			// here, under the lock that the commits take, with the references released since the
+			// pass started. A node released while it was iterated over may be left in place.
+			let references =
+				references.saturating_sub(state.released.get(key).copied().unwrap_or(0));
+			// Each reference is released separately from a reference counted column.
+			for _ in 0..references {
+				transaction.remove(columns::STATE, key);
+			}
+			removed += (references > 0) as u64;
+		}
+		db.commit(transaction)?;
+		compaction.removed_nodes.fetch_add(removed, Ordering::Relaxed);
+	}
+
+	if let Some(state) = compaction.state.lock().as_mut() {
+		state.pass = None;
+		state.released.clear();
+	}
+	Ok(())
+}
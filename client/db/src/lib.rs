@@ -32,11 +32,16 @@ pub mod offchain;
 
 pub mod bench;
 
+mod check;
 mod children;
+mod compaction;
+mod migration;
 mod parity_db;
 mod pinned_blocks_cache;
 mod record_stats_state;
 mod stats;
+mod trie_cache_snapshot;
+mod trie_walk;
 #[cfg(any(feature = "rocksdb", test))]
 mod upgrade;
 mod utils;
@@ -52,6 +57,7 @@ use std::{
 };
 
 use crate::{
+	compaction::{CompactingDb, Compaction},
 	pinned_blocks_cache::PinnedBlocksCache,
 	record_stats_state::RecordStatsState,
 	stats::StateUsageStats,
@@ -63,7 +69,7 @@ use sc_client_api::{
 	backend::NewBlockState,
 	leaves::{FinalizationOutcome, LeafSet},
 	utils::is_descendent_of,
-	IoInfo, MemoryInfo, MemorySize, UsageInfo,
+	IoInfo, MemoryInfo, MemorySize, StateCacheHitInfo, UsageInfo,
 };
 use sc_state_db::{IsPruned, LastCanonicalized, StateDb};
 use sp_arithmetic::traits::Saturating;
@@ -90,13 +96,15 @@ use sp_state_machine::{
 	StateMachineStats, StorageCollection, StorageIterator, StorageKey, StorageValue,
 	UsageInfo as StateUsageInfo,
 };
-use sp_trie::{cache::SharedTrieCache, prefixed_key, MemoryDB, PrefixedMemoryDB};
+use sp_trie::{cache::SharedTrieCache, prefixed_key, MemoryDB, MerkleValue, PrefixedMemoryDB};
 
 // Re-export the Database trait so that one can pass an implementation of it.
-pub use sc_state_db::PruningMode;
+pub use sc_state_db::{CheckpointPolicy, PruningMode};
 pub use sp_database::Database;
 
 pub use bench::BenchmarkingState;
+pub use check::{ConsistencyReport, Inconsistency};
+pub use migration::migrate_database;
 
 const CACHE_HEADERS: usize = 8;
 
@@ -215,6 +223,21 @@ impl<B: BlockT> StateBackend<HashFor<B>> for RefTrackingState<B> {
 		self.state.child_storage_hash(child_info, key)
 	}
 
+	fn closest_merkle_value(
+		&self,
+		key: &[u8],
+	) -> Result<Option<MerkleValue<B::Hash>>, Self::Error> {
+		self.state.closest_merkle_value(key)
+	}
+
+	fn child_closest_merkle_value(
+		&self,
+		child_info: &ChildInfo,
+		key: &[u8],
+	) -> Result<Option<MerkleValue<B::Hash>>, Self::Error> {
+		self.state.child_closest_merkle_value(child_info, key)
+	}
+
 	fn exists_storage(&self, key: &[u8]) -> Result<bool, Self::Error> {
 		self.state.exists_storage(key)
 	}
@@ -291,6 +314,10 @@ pub struct DatabaseSettings {
 	///
 	/// If `None` is given, the cache is disabled.
 	pub trie_cache_maximum_size: Option<usize>,
+	/// Where to store the trie cache at shutdown, to load it at the next start.
+	///
+	/// If `None` is given, the cache is not stored.
+	pub trie_cache_snapshot: Option<PathBuf>,
 	/// Requested state pruning mode.
 	pub state_pruning: Option<PruningMode>,
 	/// Where to find the database.
@@ -299,6 +326,13 @@ pub struct DatabaseSettings {
 	///
 	/// NOTE: only finalized blocks are subject for removal!
 	pub blocks_pruning: BlocksPruning,
+	/// Justifications pruning mode. Follows `blocks_pruning` if `None`.
+	pub justifications_pruning: Option<BlocksPruning>,
+	/// Pruning mode of the indexed transactions. Follows `blocks_pruning` if `None`.
+	///
+	/// The indexed transactions are part of the block bodies, so they can't be pruned before
+	/// them.
+	pub transactions_pruning: Option<BlocksPruning>,
 }
 
 /// Block pruning settings.
@@ -312,6 +346,37 @@ pub enum BlocksPruning {
 	Some(u32),
 }
 
+impl BlocksPruning {
+	/// True if this is an archive pruning mode (either KeepAll or KeepFinalized).
+	pub fn is_archive(&self) -> bool {
+		match *self {
+			BlocksPruning::KeepAll | BlocksPruning::KeepFinalized => true,
+			BlocksPruning::Some(_) => false,
+		}
+	}
+
+	/// True if the data of every block kept by `other` is also kept by this mode.
+	pub fn keeps_all_of(&self, other: &BlocksPruning) -> bool {
+		match (*self, *other) {
+			(BlocksPruning::KeepAll, _) => true,
+			(
+				BlocksPruning::KeepFinalized,
+				BlocksPruning::KeepFinalized | BlocksPruning::Some(_),
+			) => true,
+			(BlocksPruning::Some(n), BlocksPruning::Some(m)) => n.max(1) >= m.max(1),
+			_ => false,
+		}
+	}
+}
+
+/// Kinds of block data removed when a block is pruned.
+#[derive(Clone, Copy, Default)]
+struct PrunedData {
+	body: bool,
+	justifications: bool,
+	transactions: bool,
+}
+
 /// Where to find the database..
 #[derive(Debug, Clone)]
 pub enum DatabaseSource {
@@ -562,6 +627,33 @@ impl<Block: BlockT> BlockchainDb<Block> {
 		self.pinned_blocks_cache.write().unpin(hash);
 	}
 
+	/// Indexed transactions of a block which body was pruned before them.
+	fn pruned_body_transactions(&self, hash: Block::Hash) -> ClientResult<Option<Vec<Vec<u8>>>> {
+		let key = match utils::block_id_to_lookup_key(
+			&*self.db,
+			columns::KEY_LOOKUP,
+			BlockId::<Block>::Hash(hash),
+		)? {
+			Some(key) => utils::pruned_body_index_key(&key),
+			None => return Ok(None),
+		};
+		let hashes = match self.db.get(columns::BODY_INDEX, &key) {
+			Some(hashes) => Vec::<DbHash>::decode(&mut &hashes[..]).map_err(|err| {
+				sp_blockchain::Error::Backend(format!("Error decoding transaction list: {}", err))
+			})?,
+			None => return Ok(None),
+		};
+		hashes
+			.into_iter()
+			.map(|hash| {
+				self.db.get(columns::TRANSACTION, hash.as_ref()).ok_or_else(|| {
+					sp_blockchain::Error::Backend(format!("Missing indexed transaction {:?}", hash))
+				})
+			})
+			.collect::<ClientResult<_>>()
+			.map(Some)
+	}
+
 	fn justifications_uncached(&self, hash: Block::Hash) -> ClientResult<Option<Justifications>> {
 		match read_db(
 			&*self.db,
@@ -759,7 +851,7 @@ impl<Block: BlockT> sc_client_api::blockchain::Backend<Block> for BlockchainDb<B
 			BlockId::<Block>::Hash(hash),
 		)? {
 			Some(body) => body,
-			None => return Ok(None),
+			None => return self.pruned_body_transactions(hash),
 		};
 		match Vec::<DbExtrinsic<Block>>::decode(&mut &body[..]) {
 			Ok(index) => {
@@ -1101,10 +1193,14 @@ pub struct Backend<Block: BlockT> {
 	import_lock: Arc<RwLock<()>>,
 	is_archive: bool,
 	blocks_pruning: BlocksPruning,
+	justifications_pruning: BlocksPruning,
+	transactions_pruning: BlocksPruning,
 	io_stats: FrozenForDuration<(kvdb::IoStats, StateUsageInfo)>,
 	state_usage: Arc<StateUsageStats>,
 	genesis_state: RwLock<Option<Arc<DbGenesisStorage<Block>>>>,
 	shared_trie_cache: Option<sp_trie::cache::SharedTrieCache<HashFor<Block>>>,
+	trie_cache_snapshot: Option<PathBuf>,
+	state_compaction: Option<Arc<Compaction>>,
 }
 
 impl<Block: BlockT> Backend<Block> {
@@ -1151,9 +1247,12 @@ impl<Block: BlockT> Backend<Block> {
 		};
 		let db_setting = DatabaseSettings {
 			trie_cache_maximum_size: Some(16 * 1024 * 1024),
+			trie_cache_snapshot: None,
 			state_pruning: Some(state_pruning),
 			source: DatabaseSource::Custom { db, require_create_flag: true },
 			blocks_pruning,
+			justifications_pruning: None,
+			transactions_pruning: None,
 		};
 
 		Self::new(db_setting, canonicalization_delay).expect("failed to create test-db")
@@ -1182,6 +1281,20 @@ impl<Block: BlockT> Backend<Block> {
 		config: &DatabaseSettings,
 		should_init: bool,
 	) -> ClientResult<Self> {
+		if db.get(columns::META, meta_keys::MIGRATION).is_some() {
+			return Err(sp_blockchain::Error::Backend(
+				"The database is the target of an unfinished migration".into(),
+			))
+		}
+
+		let justifications_pruning = config.justifications_pruning.unwrap_or(config.blocks_pruning);
+		let transactions_pruning = config.transactions_pruning.unwrap_or(config.blocks_pruning);
+		if !transactions_pruning.keeps_all_of(&config.blocks_pruning) {
+			return Err(sp_blockchain::Error::Backend(
+				"Indexed transactions can't be pruned before the block bodies".into(),
+			))
+		}
+
 		let mut db_init_transaction = Transaction::new();
 
 		let requested_state_pruning = config.state_pruning.clone();
@@ -1198,6 +1311,21 @@ impl<Block: BlockT> Backend<Block> {
 
 		apply_state_commit(&mut db_init_transaction, state_db_init_commit_set);
 
+		// The state written before the pruning mode was changed is removed in the background.
+		// Commits go through a wrapper that keeps the compaction from removing new trie nodes.
+		let (db, state_compaction) = if state_db.compaction_pending() {
+			let marks_path =
+				config.source.path().map(|path| path.with_file_name(compaction::MARKS_DIR));
+			let compaction = Arc::new(Compaction::new(marks_path).map_err(|e| {
+				sp_blockchain::Error::Backend(format!("Failed to open the compaction marks: {}", e))
+			})?);
+			let compacting_db: Arc<dyn Database<DbHash>> =
+				Arc::new(CompactingDb::new(db.clone(), compaction.clone()));
+			(compacting_db, Some((db, compaction)))
+		} else {
+			(db, None)
+		};
+
 		let state_pruning_used = state_db.pruning_mode();
 		let is_archive_pruning = state_pruning_used.is_archive();
 		let blockchain = BlockchainDb::new(db.clone())?;
@@ -1217,10 +1345,14 @@ impl<Block: BlockT> Backend<Block> {
 			io_stats: FrozenForDuration::new(std::time::Duration::from_secs(1)),
 			state_usage: Arc::new(StateUsageStats::new()),
 			blocks_pruning: config.blocks_pruning,
+			justifications_pruning,
+			transactions_pruning,
 			genesis_state: RwLock::new(None),
 			shared_trie_cache: config.trie_cache_maximum_size.map(|maximum_size| {
 				SharedTrieCache::new(sp_trie::cache::CacheSize::new(maximum_size))
 			}),
+			trie_cache_snapshot: config.trie_cache_snapshot.clone(),
+			state_compaction: state_compaction.as_ref().map(|(_, compaction)| compaction.clone()),
 		};
 
 		// Older DB versions have no last state key. Check if the state is available and set it.
@@ -1243,9 +1375,65 @@ impl<Block: BlockT> Backend<Block> {
 
 		db.commit(db_init_transaction)?;
 
+		if let (Some(cache), Some(path)) =
+			(backend.shared_trie_cache.as_ref(), backend.trie_cache_snapshot.as_ref())
+		{
+			let info = backend.blockchain.info();
+			if let Ok(meta) = backend.blockchain.header_metadata(info.best_hash) {
+				trie_cache_snapshot::load::<Block>(path, cache, info.best_hash, meta.state_root);
+			}
+		}
+
+		if let Some((inner_db, compaction)) = state_compaction {
+			backend.start_state_compaction(inner_db, compaction)?;
+		}
+
 		Ok(backend)
 	}
 
+	/// Start removing the state written before the state pruning mode was changed, in a
+	/// background thread. The state of the last finalized block, of its unfinalized descendants
+	/// and of the checkpoints is retained.
+	///
+	/// `db` is the database without the compaction wrapper.
+	fn start_state_compaction(
+		&self,
+		db: Arc<dyn Database<DbHash>>,
+		compaction: Arc<Compaction>,
+	) -> ClientResult<()> {
+		let info = self.blockchain.info();
+		let mut blocks = vec![info.finalized_hash];
+		let mut visited = HashSet::new();
+		for leaf in self.blockchain.leaves()? {
+			let mut hash = leaf;
+			while visited.insert(hash) {
+				match self.blockchain.header_metadata(hash) {
+					Ok(meta) if meta.number > info.finalized_number => {
+						blocks.push(hash);
+						hash = meta.parent;
+					},
+					_ => break,
+				}
+			}
+		}
+		blocks.extend(self.storage.state_db.checkpoints().into_iter().map(|c| c.hash));
+
+		let roots: HashSet<_> = blocks
+			.into_iter()
+			.filter_map(|hash| self.blockchain.header_metadata(hash).ok())
+			.map(|meta| meta.state_root)
+			.collect();
+		compaction::spawn(
+			Arc::downgrade(&self.storage),
+			db,
+			compaction,
+			roots.into_iter().collect(),
+		)
+		.map_err(|e| {
+			sp_blockchain::Error::Backend(format!("Failed to start the state compaction: {}", e))
+		})
+	}
+
 	/// Handle setting head within a transaction. `route_to` should be the last
 	/// block that existed in the database. `best_to` should be the best block
 	/// to be set.
@@ -1801,36 +1989,62 @@ impl<Block: BlockT> Backend<Block> {
 		displaced: &FinalizationOutcome<Block::Hash, NumberFor<Block>>,
 		current_transaction_justifications: &mut HashMap<Block::Hash, Justification>,
 	) -> ClientResult<()> {
-		match self.blocks_pruning {
-			BlocksPruning::KeepAll => {},
-			BlocksPruning::Some(blocks_pruning) => {
+		// Bodies, justifications and indexed transactions leave their own windows.
+		let mut pruned: Vec<(NumberFor<Block>, PrunedData)> = Vec::new();
+		for (mode, data) in [
+			(self.blocks_pruning, PrunedData { body: true, ..Default::default() }),
+			(
+				self.justifications_pruning,
+				PrunedData { justifications: true, ..Default::default() },
+			),
+			(self.transactions_pruning, PrunedData { transactions: true, ..Default::default() }),
+		] {
+			if let BlocksPruning::Some(blocks_pruning) = mode {
 				// Always keep the last finalized block
 				let keep = std::cmp::max(blocks_pruning, 1);
 				if finalized_number >= keep.into() {
 					let number = finalized_number.saturating_sub(keep.into());
+					match pruned.iter_mut().find(|(n, _)| *n == number) {
+						Some((_, pruned_data)) => {
+							pruned_data.body |= data.body;
+							pruned_data.justifications |= data.justifications;
+							pruned_data.transactions |= data.transactions;
+						},
+						None => pruned.push((number, data)),
+					}
+				}
+			}
+		}
 
-					// Before we prune a block, check if it is pinned
-					if let Some(hash) = self.blockchain.hash(number)? {
-						self.blockchain.insert_persisted_body_if_pinned(hash)?;
-
-						// If the block was finalized in this transaction, it will not be in the db
-						// yet.
-						if let Some(justification) =
-							current_transaction_justifications.remove(&hash)
-						{
-							self.blockchain.insert_justifications_if_pinned(hash, justification);
-						} else {
-							self.blockchain.insert_persisted_justifications_if_pinned(hash)?;
-						}
-					};
+		for (number, data) in pruned {
+			// Before we prune a block, check if it is pinned
+			if let Some(hash) = self.blockchain.hash(number)? {
+				if data.body {
+					self.blockchain.insert_persisted_body_if_pinned(hash)?;
+				}
 
-					self.prune_block(transaction, BlockId::<Block>::number(number))?;
+				if data.justifications {
+					// If the block was finalized in this transaction, it will not be in the db
+					// yet.
+					if let Some(justification) = current_transaction_justifications.remove(&hash) {
+						self.blockchain.insert_justifications_if_pinned(hash, justification);
+					} else {
+						self.blockchain.insert_persisted_justifications_if_pinned(hash)?;
+					}
 				}
-				self.prune_displaced_branches(transaction, finalized_hash, displaced)?;
-			},
-			BlocksPruning::KeepFinalized => {
-				self.prune_displaced_branches(transaction, finalized_hash, displaced)?;
-			},
+			};
+
+			self.prune_block(transaction, BlockId::<Block>::number(number), data)?;
+		}
+
+		// Displaced branches are discarded unless all blocks are kept.
+		let displaced_data = PrunedData {
+			body: self.blocks_pruning != BlocksPruning::KeepAll,
+			justifications: self.justifications_pruning != BlocksPruning::KeepAll,
+			transactions: self.transactions_pruning != BlocksPruning::KeepAll,
+		};
+		if displaced_data.body || displaced_data.justifications || displaced_data.transactions {
+			self.prune_displaced_branches(transaction, finalized_hash, displaced, displaced_data)?;
 		}
 		Ok(())
 	}
@@ -1840,14 +2054,17 @@ impl<Block: BlockT> Backend<Block> {
 		transaction: &mut Transaction<DbHash>,
 		finalized: Block::Hash,
 		displaced: &FinalizationOutcome<Block::Hash, NumberFor<Block>>,
+		data: PrunedData,
 	) -> ClientResult<()> {
 		// Discard all blocks from displaced branches
 		for h in displaced.leaves() {
 			match sp_blockchain::tree_route(&self.blockchain, *h, finalized) {
 				Ok(tree_route) =>
 					for r in tree_route.retracted() {
-						self.blockchain.insert_persisted_body_if_pinned(r.hash)?;
-						self.prune_block(transaction, BlockId::<Block>::hash(r.hash))?;
+						if data.body {
+							self.blockchain.insert_persisted_body_if_pinned(r.hash)?;
+						}
+						self.prune_block(transaction, BlockId::<Block>::hash(r.hash), data)?;
 					},
 				Err(sp_blockchain::Error::UnknownBlock(_)) => {
 					// Sometimes routes can't be calculated. E.g. after warp sync.
@@ -1862,49 +2079,92 @@ impl<Block: BlockT> Backend<Block> {
 		&self,
 		transaction: &mut Transaction<DbHash>,
 		id: BlockId<Block>,
+		data: PrunedData,
 	) -> ClientResult<()> {
 		debug!(target: "db", "Removing block #{}", id);
-		utils::remove_from_db(
-			transaction,
-			&*self.storage.db,
-			columns::KEY_LOOKUP,
-			columns::BODY,
-			id,
-		)?;
-		utils::remove_from_db(
-			transaction,
-			&*self.storage.db,
-			columns::KEY_LOOKUP,
-			columns::JUSTIFICATIONS,
-			id,
-		)?;
-		if let Some(index) =
-			read_db(&*self.storage.db, columns::KEY_LOOKUP, columns::BODY_INDEX, id)?
-		{
+		if data.body {
 			utils::remove_from_db(
 				transaction,
 				&*self.storage.db,
 				columns::KEY_LOOKUP,
-				columns::BODY_INDEX,
+				columns::BODY,
 				id,
 			)?;
-			match Vec::<DbExtrinsic<Block>>::decode(&mut &index[..]) {
-				Ok(index) =>
-					for ex in index {
-						if let DbExtrinsic::Indexed { hash, .. } = ex {
-							transaction.release(columns::TRANSACTION, hash);
-						}
-					},
-				Err(err) =>
-					return Err(sp_blockchain::Error::Backend(format!(
-						"Error decoding body list: {}",
-						err
-					))),
+		}
+		if data.justifications {
+			utils::remove_from_db(
+				transaction,
+				&*self.storage.db,
+				columns::KEY_LOOKUP,
+				columns::JUSTIFICATIONS,
+				id,
+			)?;
+		}
+		if !data.body && !data.transactions {
+			return Ok(())
+		}
+
+		let lookup_key =
+			match utils::block_id_to_lookup_key(&*self.storage.db, columns::KEY_LOOKUP, id)? {
+				Some(key) => key,
+				None => return Ok(()),
+			};
+		let pruned_body_key = utils::pruned_body_index_key(&lookup_key);
+		let indexed = if data.body {
+			match self.storage.db.get(columns::BODY_INDEX, &lookup_key) {
+				Some(index) => {
+					transaction.remove(columns::BODY_INDEX, &lookup_key);
+					match Vec::<DbExtrinsic<Block>>::decode(&mut &index[..]) {
+						Ok(index) => index
+							.into_iter()
+							.filter_map(|ex| match ex {
+								DbExtrinsic::Indexed { hash, .. } => Some(hash),
+								DbExtrinsic::Full(_) => None,
+							})
+							.collect(),
+						Err(err) =>
+							return Err(sp_blockchain::Error::Backend(format!(
+								"Error decoding body list: {}",
+								err
+							))),
+					}
+				},
+				None => Vec::new(),
 			}
+		} else {
+			// The body was pruned before the indexed transactions.
+			match self.storage.db.get(columns::BODY_INDEX, &pruned_body_key) {
+				Some(hashes) => {
+					transaction.remove(columns::BODY_INDEX, &pruned_body_key);
+					Vec::<DbHash>::decode(&mut &hashes[..]).map_err(|err| {
+						sp_blockchain::Error::Backend(format!(
+							"Error decoding transaction list: {}",
+							err
+						))
+					})?
+				},
+				None => Vec::new(),
+			}
+		};
+
+		if data.transactions {
+			for hash in indexed {
+				transaction.release(columns::TRANSACTION, hash);
+			}
+		} else if !indexed.is_empty() {
+			// The references are released when the indexed transactions are pruned.
+			transaction.set_from_vec(columns::BODY_INDEX, &pruned_body_key, indexed.encode());
 		}
 		Ok(())
 	}
 
+	/// True if the body, justifications or indexed transactions of some blocks are removed.
+	fn prunes_block_data(&self) -> bool {
+		[self.blocks_pruning, self.justifications_pruning, self.transactions_pruning]
+			.iter()
+			.any(|mode| *mode != BlocksPruning::KeepAll)
+	}
+
 	fn empty_state(&self) -> RecordStatsState<RefTrackingState<Block>, Block> {
 		let root = EmptyStorage::<Block>::new().0; // Empty trie
 		let db_state = DbStateBuilder::<Block>::new(self.storage.clone(), root)
@@ -2030,6 +2290,19 @@ where
 	}
 }
 
+impl<Block: BlockT> Drop for Backend<Block> {
+	fn drop(&mut self) {
+		if let (Some(cache), Some(path)) =
+			(self.shared_trie_cache.as_ref(), self.trie_cache_snapshot.as_ref())
+		{
+			let best_hash = self.blockchain.info().best_hash;
+			if let Err(e) = trie_cache_snapshot::store::<Block>(path, cache, best_hash) {
+				warn!(target: "db", "Failed to store the trie cache: {}", e);
+			}
+		}
+	}
+}
+
 impl<Block: BlockT> sc_client_api::backend::Backend<Block> for Backend<Block> {
 	type BlockImportOperation = BlockImportOperation<Block>;
 	type Blockchain = BlockchainDb<Block>;
@@ -2182,6 +2455,16 @@ impl<Block: BlockT> sc_client_api::backend::Backend<Block> for Backend<Block> {
 				state_reads_cache: state_stats.cache_reads.ops,
 				state_writes_nodes: state_stats.nodes_writes.ops,
 			},
+			state_cache_hits: self.shared_trie_cache.as_ref().map(|cache| {
+				let stats = cache.stats();
+				StateCacheHitInfo {
+					node_fetch_attempts: stats.node_fetch_attempts,
+					node_hits: stats.node_hits,
+					value_fetch_attempts: stats.value_fetch_attempts,
+					value_hits: stats.value_hits,
+				}
+			}),
+			state_compaction: self.state_compaction.as_ref().and_then(|c| c.info()),
 		})
 	}
 
@@ -2507,7 +2790,7 @@ impl<Block: BlockT> sc_client_api::backend::Backend<Block> for Backend<Block> {
 			)))
 		}
 
-		if self.blocks_pruning != BlocksPruning::KeepAll {
+		if self.prunes_block_data() {
 			// Only increase reference count for this hash. Value is loaded once we prune.
 			self.blockchain.bump_ref(hash);
 		}
@@ -2517,7 +2800,7 @@ impl<Block: BlockT> sc_client_api::backend::Backend<Block> for Backend<Block> {
 	fn unpin_block(&self, hash: <Block as BlockT>::Hash) {
 		self.storage.state_db.unpin(&hash);
 
-		if self.blocks_pruning != BlocksPruning::KeepAll {
+		if self.prunes_block_data() {
 			self.blockchain.unpin(hash);
 		}
 	}
@@ -2669,9 +2952,12 @@ pub(crate) mod tests {
 		let backend = Backend::<Block>::new(
 			DatabaseSettings {
 				trie_cache_maximum_size: Some(16 * 1024 * 1024),
+				trie_cache_snapshot: None,
 				state_pruning: Some(PruningMode::blocks_pruning(1)),
 				source: DatabaseSource::Custom { db: backing, require_create_flag: false },
 				blocks_pruning: BlocksPruning::KeepFinalized,
+				justifications_pruning: None,
+				transactions_pruning: None,
 			},
 			0,
 		)
@@ -2682,6 +2968,101 @@ pub(crate) mod tests {
 		}
 	}
 
+	#[test]
+	fn compacts_state_after_switching_from_archive() {
+		let state_version = StateVersion::default();
+		let (backing, genesis_root, hash) = {
+			let db = Backend::<Block>::new_test_with_tx_storage(BlocksPruning::KeepFinalized, 0);
+			let mut op = db.begin_operation().unwrap();
+			let mut header = Header {
+				number: 0,
+				parent_hash: Default::default(),
+				state_root: Default::default(),
+				digest: Default::default(),
+				extrinsics_root: Default::default(),
+			};
+			let storage = vec![(vec![1, 3, 5], vec![2, 4, 6]), (vec![1, 2, 3], vec![9, 9, 9])];
+			header.state_root = op
+				.old_state
+				.storage_root(storage.iter().map(|(x, y)| (&x[..], Some(&y[..]))), state_version)
+				.0
+				.into();
+			let genesis_root = header.state_root;
+			let genesis_hash = header.hash();
+			op.reset_storage(
+				Storage {
+					top: storage.into_iter().collect(),
+					children_default: Default::default(),
+				},
+				state_version,
+			)
+			.unwrap();
+			op.set_block_data(header, Some(vec![]), None, None, NewBlockState::Final)
+				.unwrap();
+			db.commit_operation(op).unwrap();
+
+			let mut op = db.begin_operation().unwrap();
+			db.begin_state_operation(&mut op, genesis_hash).unwrap();
+			let mut header = Header {
+				number: 1,
+				parent_hash: genesis_hash,
+				state_root: Default::default(),
+				digest: Default::default(),
+				extrinsics_root: Default::default(),
+			};
+			let storage = vec![(vec![1, 3, 5], None), (vec![5, 5, 5], Some(vec![4, 5, 6]))];
+			let (root, overlay) = op.old_state.storage_root(
+				storage.iter().map(|(k, v)| (k.as_slice(), v.as_ref().map(|v| &v[..]))),
+				state_version,
+			);
+			op.update_db_storage(overlay).unwrap();
+			header.state_root = root.into();
+			let hash = header.hash();
+			op.update_storage(storage, Vec::new()).unwrap();
+			op.set_block_data(header, Some(vec![]), None, None, NewBlockState::Final)
+				.unwrap();
+			db.commit_operation(op).unwrap();
+
+			assert!(db.storage.db.get(columns::STATE, genesis_root.as_ref()).is_some());
+			(db.storage.db.clone(), genesis_root, hash)
+		};
+		let settings = |state_pruning| DatabaseSettings {
+			trie_cache_maximum_size: Some(16 * 1024 * 1024),
+			trie_cache_snapshot: None,
+			state_pruning: Some(state_pruning),
+			source: DatabaseSource::Custom { db: backing.clone(), require_create_flag: false },
+			blocks_pruning: BlocksPruning::KeepFinalized,
+			justifications_pruning: None,
+			transactions_pruning: None,
+		};
+
+		// the archive is only switched on request
+		assert!(Backend::<Block>::new(settings(PruningMode::blocks_pruning(1)), 0).is_err());
+		let backend = Backend::<Block>::new(
+			settings(PruningMode::Constrained(sc_state_db::Constraints {
+				max_blocks: Some(1),
+				checkpoints: None,
+				allow_archive_downgrade: true,
+			})),
+			0,
+		)
+		.unwrap();
+		for _ in 0..500 {
+			if backend.usage_info().unwrap().state_compaction.is_none() {
+				break
+			}
+			std::thread::sleep(std::time::Duration::from_millis(10));
+		}
+		assert!(backend.usage_info().unwrap().state_compaction.is_none());
+		assert!(!backend.storage.state_db.compaction_pending());
+
+		// the state of the finalized block is retained
+		assert!(backing.get(columns::STATE, genesis_root.as_ref()).is_none());
+		let state = backend.state_at(hash).unwrap();
+		assert_eq!(state.storage(&[1, 2, 3]).unwrap(), Some(vec![9, 9, 9]));
+		assert_eq!(state.storage(&[5, 5, 5]).unwrap(), Some(vec![4, 5, 6]));
+	}
+
 	#[test]
 	fn set_state_data() {
 		set_state_data_inner(StateVersion::V0);
@@ -3614,6 +3995,95 @@ pub(crate) mod tests {
 		assert_eq!(bc.indexed_transaction(x1_hash).unwrap(), None);
 	}
 
+	fn new_test_with_retention(
+		blocks_pruning: BlocksPruning,
+		justifications_pruning: Option<BlocksPruning>,
+		transactions_pruning: Option<BlocksPruning>,
+	) -> ClientResult<Backend<Block>> {
+		let db = sp_database::as_database(kvdb_memorydb::create(crate::utils::NUM_COLUMNS));
+		Backend::<Block>::new(
+			DatabaseSettings {
+				trie_cache_maximum_size: None,
+				trie_cache_snapshot: None,
+				state_pruning: Some(PruningMode::blocks_pruning(1)),
+				source: DatabaseSource::Custom { db, require_create_flag: true },
+				blocks_pruning,
+				justifications_pruning,
+				transactions_pruning,
+			},
+			0,
+		)
+	}
+
+	#[test]
+	fn independent_body_justifications_and_transactions_pruning() {
+		let backend = new_test_with_retention(
+			BlocksPruning::Some(1),
+			Some(BlocksPruning::KeepFinalized),
+			Some(BlocksPruning::Some(3)),
+		)
+		.unwrap();
+		let x0 = ExtrinsicWrapper::from(0u64).encode();
+		let x0_hash = <HashFor<Block> as sp_core::Hasher>::hash(&x0[1..]);
+		let index = vec![IndexOperation::Insert {
+			extrinsic: 0,
+			hash: x0_hash.as_ref().to_vec(),
+			size: (x0.len() - 1) as u32,
+		}];
+		let mut blocks = vec![insert_block(
+			&backend,
+			0,
+			Default::default(),
+			None,
+			Default::default(),
+			vec![0u64.into()],
+			Some(index),
+		)
+		.unwrap()];
+		for i in 1..5 {
+			let hash = insert_block(
+				&backend,
+				i,
+				blocks[i as usize - 1],
+				None,
+				Default::default(),
+				vec![i.into()],
+				None,
+			)
+			.unwrap();
+			blocks.push(hash);
+		}
+		let bc = backend.blockchain();
+
+		let justification = (CONS0_ENGINE_ID, vec![1]);
+		backend.finalize_block(blocks[1], Some(justification.clone())).unwrap();
+		// The body is pruned, the indexed transactions are kept.
+		assert_eq!(bc.body(blocks[0]).unwrap(), None);
+		assert_eq!(bc.block_indexed_body(blocks[0]).unwrap(), Some(vec![x0[1..].to_vec()]));
+		assert_eq!(bc.indexed_transaction(x0_hash).unwrap(), Some(x0[1..].to_vec()));
+
+		for i in 2..4 {
+			backend.finalize_block(blocks[i], None).unwrap();
+		}
+		// The justifications are kept.
+		assert_eq!(bc.body(blocks[1]).unwrap(), None);
+		assert_eq!(bc.justifications(blocks[1]).unwrap(), Some(justification.into()));
+		// The indexed transactions leave their window.
+		assert_eq!(bc.block_indexed_body(blocks[0]).unwrap(), None);
+		assert_eq!(bc.indexed_transaction(x0_hash).unwrap(), None);
+		assert_eq!(bc.body(blocks[3]).unwrap(), Some(vec![3.into()]));
+	}
+
+	#[test]
+	fn transactions_pruned_before_bodies_are_rejected() {
+		let some = BlocksPruning::Some;
+		assert!(new_test_with_retention(some(2), None, Some(some(2))).is_ok());
+		assert!(new_test_with_retention(some(2), None, Some(BlocksPruning::KeepFinalized)).is_ok());
+		assert!(new_test_with_retention(some(2), None, Some(some(1))).is_err());
+		assert!(new_test_with_retention(BlocksPruning::KeepFinalized, None, Some(some(9))).is_err());
+		assert!(new_test_with_retention(some(1), None, Some(some(0))).is_ok());
+	}
+
 	#[test]
 	fn renew_transaction_storage() {
 		let backend = Backend::<Block>::new_test_with_tx_storage(BlocksPruning::Some(2), 10);
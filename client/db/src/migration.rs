@@ -0,0 +1,1067 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Copying a database to another backend.
+//!
+//! Every column is copied as raw data, in batches. Each batch records the migration progress in
+//! the target database, so an interrupted migration resumes from the last committed batch. Once
+//! all columns are copied, the target is verified against the source and the progress record is
+//! removed. A database with a progress record is refused by the backend.
+//!
+//! When the target supports reference counting, trie nodes are stored under their sanitized key,
+//! with a reference for every prefixed key of the source, and the state-db journals and the indexed
+//! transaction counters are converted to match.
+//!
+//! ParityDb only keeps the hashes of the keys of its columns. When it is the source, the keys are
+//! rebuilt from the data instead: blocks are copied under the keys derived from their headers,
+//! indexed transactions under their hash and trie nodes under their prefixed keys, found by walking
+//! the state of every block from its root, most recent blocks first. The state-db journals are
+//! converted to the prefixed keys. The keys of the aux and offchain columns can't be rebuilt, so
+//! the migration is refused if these columns contain data. The state is verified for the blocks of
+//! the journals, the finalized state and the best block.
+
+use std::{
+	collections::{HashMap, HashSet},
+	sync::Arc,
+};
+
+use codec::{Decode, Encode};
+use log::info;
+use sc_state_db::StateDb;
+use sp_blockchain::{Error as ClientError, Result as ClientResult};
+use sp_core::{hexdisplay::HexDisplay, Hasher};
+use sp_database::{ColumnEntry, ColumnId, Database, Transaction};
+use sp_runtime::{
+	generic::BlockId,
+	traits::{Block as BlockT, HashFor, Header as HeaderT, UniqueSaturatedInto},
+};
+use sp_state_machine::DBValue;
+
+use crate::{
+	columns,
+	trie_walk::{walk_trie, TrieVisitor},
+	utils::{self, meta_keys, DatabaseType},
+	DatabaseSource, DbHash, PruningMode, StateMetaDb, StorageDb, DB_HASH_LEN,
+};
+
+/// Columns in the order they are copied. The state-db metadata goes last, so that its journals
+/// can be converted in a single step once the column is complete.
+const COLUMNS: [ColumnId; 11] = [
+	columns::META,
+	columns::KEY_LOOKUP,
+	columns::HEADER,
+	columns::BODY,
+	columns::BODY_INDEX,
+	columns::JUSTIFICATIONS,
+	columns::TRANSACTION,
+	columns::AUX,
+	columns::OFFCHAIN,
+	columns::STATE,
+	columns::STATE_META,
+];
+
+/// Metadata of the blocks copied from a source that doesn't keep its keys. The children of each
+/// block are copied with the block.
+const BLOCK_META_KEYS: [&[u8]; 7] = [
+	meta_keys::TYPE,
+	meta_keys::BEST_BLOCK,
+	meta_keys::FINALIZED_BLOCK,
+	meta_keys::FINALIZED_STATE,
+	meta_keys::BLOCK_GAP,
+	meta_keys::GENESIS_HASH,
+	meta_keys::LEAF_PREFIX,
+];
+
+/// Prefix of the target `META` entries that record the prefixed keys of the trie nodes referenced
+/// by the state-db journals of a source that doesn't keep its keys, until the journals are
+/// converted.
+const NODE_KEYS_PREFIX: &[u8] = b"migration_node_keys";
+
+/// Size of the data written to the target before the progress is committed.
+const MAX_BATCH_SIZE: usize = 64 * 1024 * 1024;
+
+/// Number of source entries checked between progress commits during verification.
+const VERIFY_BATCH: u64 = 64 * 1024;
+
+/// Migration progress, stored in the target `META` column.
+#[derive(Encode, Decode, Default, Clone, Copy, Debug)]
+struct Progress {
+	/// Index of the current step, see `Progress::step`.
+	step: u32,
+	/// Number of source entries of the current step that are done.
+	entries: u64,
+}
+
+#[derive(Clone, Copy)]
+enum Step {
+	Copy(ColumnId),
+	ConvertJournals,
+	Verify(ColumnId),
+	Rebuild(Rebuilt),
+	ConvertStateMeta,
+	VerifyRebuilt(Rebuilt),
+	Done,
+}
+
+/// Data rebuilt from a source that doesn't keep its keys.
+#[derive(Clone, Copy)]
+enum Rebuilt {
+	Blocks,
+	Transactions,
+	Meta,
+	State,
+}
+
+/// Steps of a migration from a source that doesn't keep its keys. The state-db metadata is
+/// converted once the prefixed keys of all trie nodes are known.
+const REBUILD_STEPS: [Step; 9] = [
+	Step::Rebuild(Rebuilt::Blocks),
+	Step::Rebuild(Rebuilt::Transactions),
+	Step::Rebuild(Rebuilt::Meta),
+	Step::Rebuild(Rebuilt::State),
+	Step::ConvertStateMeta,
+	Step::VerifyRebuilt(Rebuilt::Blocks),
+	Step::VerifyRebuilt(Rebuilt::Transactions),
+	Step::VerifyRebuilt(Rebuilt::Meta),
+	Step::VerifyRebuilt(Rebuilt::State),
+];
+
+impl Progress {
+	fn step(&self, rebuild: bool) -> Step {
+		let step = self.step as usize;
+		if rebuild {
+			REBUILD_STEPS.get(step).copied().unwrap_or(Step::Done)
+		} else if step < COLUMNS.len() {
+			Step::Copy(COLUMNS[step])
+		} else if step == COLUMNS.len() {
+			Step::ConvertJournals
+		} else if step <= 2 * COLUMNS.len() {
+			Step::Verify(COLUMNS[step - COLUMNS.len() - 1])
+		} else {
+			Step::Done
+		}
+	}
+
+	fn next_step(&mut self) {
+		*self = Progress { step: self.step + 1, entries: 0 };
+	}
+}
+
+/// Copy the database at `source` to a new database at `target`.
+///
+/// The source must not be in use while the migration runs. If the target contains an unfinished
+/// migration from an earlier run, the migration is resumed.
+///
+/// The keys of the aux and offchain columns of a ParityDb source can't be recovered, so migrating
+/// from ParityDb is refused unless these columns are empty.
+pub fn migrate_database<Block: BlockT>(
+	source: &DatabaseSource,
+	target: &DatabaseSource,
+) -> ClientResult<()> {
+	if source.path().is_some() && source.path() == target.path() {
+		return Err(ClientError::Backend(
+			"The source and the target of a database migration must differ".into(),
+		))
+	}
+
+	let source = utils::open_database::<Block>(source, DatabaseType::Full, false)?;
+	// The keys of the source are rebuilt from the data.
+	let rebuild = source.supports_ref_counting();
+	if rebuild {
+		for column in [columns::AUX, columns::OFFCHAIN] {
+			let mut empty = true;
+			source.iter_column(column, &mut |_| {
+				empty = false;
+				false
+			})?;
+			if !empty {
+				return Err(ClientError::Backend(format!(
+					"Database column {} can't be migrated: ParityDb does not keep the keys of the \
+					aux and offchain columns",
+					column
+				)))
+			}
+		}
+	}
+	if source.get(columns::META, meta_keys::MIGRATION).is_some() {
+		return Err(ClientError::Backend(
+			"The source database is the target of an unfinished migration".into(),
+		))
+	}
+
+	let target = utils::open_database::<Block>(target, DatabaseType::Full, true)?;
+	if rebuild && target.supports_ref_counting() {
+		return Err(ClientError::Backend(
+			"Migrating between two databases with reference counting is not supported".into(),
+		))
+	}
+	let mut progress = match target.get(columns::META, meta_keys::MIGRATION) {
+		Some(progress) => {
+			let progress = Progress::decode(&mut progress.as_slice()).map_err(|e| {
+				ClientError::Backend(format!("Error decoding the migration progress: {}", e))
+			})?;
+			info!(target: "db", "Resuming the database migration at step {}", progress.step);
+			progress
+		},
+		None if target.get(columns::META, meta_keys::GENESIS_HASH).is_some() =>
+			return Err(ClientError::Backend("The target database already contains a chain".into())),
+		None => Progress::default(),
+	};
+
+	let migration = Migration { source, target };
+	if rebuild {
+		let storage = migration.source_storage::<Block>()?;
+		loop {
+			match progress.step(true) {
+				Step::Rebuild(Rebuilt::Meta) => migration.copy_meta(&mut progress, false)?,
+				Step::VerifyRebuilt(Rebuilt::Meta) => migration.copy_meta(&mut progress, true)?,
+				Step::Rebuild(Rebuilt::State) =>
+					migration.rebuild_state(&storage, &mut progress)?,
+				Step::VerifyRebuilt(Rebuilt::State) =>
+					migration.verify_state(&storage, &mut progress)?,
+				Step::Rebuild(rebuilt) =>
+					migration.rebuild_column::<Block>(rebuilt, &mut progress, false)?,
+				Step::VerifyRebuilt(rebuilt) =>
+					migration.rebuild_column::<Block>(rebuilt, &mut progress, true)?,
+				Step::ConvertStateMeta => migration.convert_state_meta::<Block>(&mut progress)?,
+				_ => break,
+			}
+		}
+	} else {
+		loop {
+			match progress.step(false) {
+				Step::Copy(column) => migration.copy_column(column, &mut progress)?,
+				Step::ConvertJournals => migration.convert_journals::<Block>(&mut progress)?,
+				Step::Verify(column) => migration.verify_column(column, &mut progress)?,
+				_ => break,
+			}
+		}
+	}
+
+	let mut transaction = Transaction::new();
+	transaction.remove(columns::META, meta_keys::MIGRATION);
+	migration.target.commit(transaction)?;
+	info!(target: "db", "Database migration complete");
+	Ok(())
+}
+
+struct Migration {
+	source: Arc<dyn Database<DbHash>>,
+	target: Arc<dyn Database<DbHash>>,
+}
+
+impl Migration {
+	/// Whether the target counts references instead of storing trie nodes under prefixed keys.
+	fn ref_counting(&self) -> bool {
+		self.target.supports_ref_counting()
+	}
+
+	fn commit(
+		&self,
+		mut transaction: Transaction<DbHash>,
+		progress: &Progress,
+	) -> ClientResult<()> {
+		transaction.set_from_vec(columns::META, meta_keys::MIGRATION, progress.encode());
+		self.target.commit(transaction)?;
+		Ok(())
+	}
+
+	/// Returns the key of a source entry in the target, or `None` if the entry has no
+	/// counterpart in the target.
+	fn target_key(&self, column: ColumnId, key: &[u8]) -> Option<Vec<u8>> {
+		let mut key = key.to_vec();
+		if column == columns::STATE {
+			self.target.sanitize_key(&mut key);
+		} else if column == columns::TRANSACTION && self.ref_counting() && key.len() != DB_HASH_LEN
+		{
+			// Reference counters of the source, applied with the value they belong to.
+			return None
+		}
+		Some(key)
+	}
+
+	/// Number of times a source entry is written to the target.
+	fn references(&self, column: ColumnId, key: &[u8]) -> ClientResult<u32> {
+		if column != columns::TRANSACTION || !self.ref_counting() {
+			return Ok(1)
+		}
+		let mut counter_key = key.to_vec();
+		counter_key.push(0);
+		match self.source.get(column, &counter_key) {
+			Some(counter) => counter.as_slice().try_into().map(u32::from_le_bytes).map_err(|_| {
+				ClientError::Backend(format!("Unexpected counter length {}", counter.len()))
+			}),
+			None => Ok(1),
+		}
+	}
+
+	fn copy_column(&self, column: ColumnId, progress: &mut Progress) -> ClientResult<()> {
+		info!(target: "db", "Copying database column {}", column);
+		let skip = progress.entries;
+		let mut entries = 0;
+		let mut transaction = Transaction::new();
+		let mut batch_size = 0;
+		let mut result = Ok(());
+		self.source.iter_column(column, &mut |entry| {
+			entries += 1;
+			if entries <= skip {
+				return true
+			}
+			result = (|| -> ClientResult<()> {
+				let key = entry.key.ok_or_else(|| missing_keys(column))?;
+				if let Some(target_key) = self.target_key(column, key) {
+					// Each write of a reference counted column adds a reference.
+					for _ in 0..self.references(column, key)? {
+						transaction.set(column, &target_key, entry.value);
+					}
+					batch_size += target_key.len() + entry.value.len();
+				}
+				if batch_size >= MAX_BATCH_SIZE {
+					progress.entries = entries;
+					self.commit(std::mem::take(&mut transaction), progress)?;
+					batch_size = 0;
+				}
+				Ok(())
+			})();
+			result.is_ok()
+		})?;
+		result?;
+		progress.next_step();
+		self.commit(transaction, progress)
+	}
+
+	/// Rewrite the state-db journals, which refer to trie nodes by their key.
+	fn convert_journals<Block: BlockT>(&self, progress: &mut Progress) -> ClientResult<()> {
+		let mut transaction = Transaction::new();
+		if self.ref_counting() {
+			info!(target: "db", "Converting the state journals");
+			let journals = sc_state_db::ref_counted_journals::<Block::Hash, Vec<u8>, _>(
+				&StateMetaDb(self.source.clone()),
+				|key| {
+					let mut key = key.clone();
+					self.target.sanitize_key(&mut key);
+					key
+				},
+			)
+			.map_err(ClientError::from_state_db)?;
+			for (key, value) in journals {
+				transaction.set_from_vec(columns::STATE_META, &key, value);
+			}
+		}
+		progress.next_step();
+		self.commit(transaction, progress)
+	}
+
+	fn verify_column(&self, column: ColumnId, progress: &mut Progress) -> ClientResult<()> {
+		info!(target: "db", "Verifying database column {}", column);
+		let skip = progress.entries;
+		let mut entries = 0;
+		let mut result = Ok(());
+		self.source.iter_column(column, &mut |entry| {
+			entries += 1;
+			if entries <= skip {
+				return true
+			}
+			result = (|| -> ClientResult<()> {
+				let key = entry.key.ok_or_else(|| missing_keys(column))?;
+				if let Some(target_key) = self.target_key(column, key) {
+					// The converted journals differ from the source.
+					let converted = column == columns::STATE_META && self.ref_counting();
+					let matches = if converted {
+						self.target.contains(column, &target_key)
+					} else {
+						self.target.get(column, &target_key).as_deref() == Some(entry.value)
+					};
+					if !matches {
+						return Err(ClientError::Backend(format!(
+							"Verification failed: entry 0x{} of column {} differs in the target",
+							HexDisplay::from(&key),
+							column,
+						)))
+					}
+				}
+				if entries % VERIFY_BATCH == 0 {
+					progress.entries = entries;
+					self.commit(Transaction::new(), progress)?;
+				}
+				Ok(())
+			})();
+			result.is_ok()
+		})?;
+		result?;
+		progress.next_step();
+		self.commit(Transaction::new(), progress)
+	}
+
+	/// Open the state-db of a source that doesn't keep its keys, to read the trie nodes of the
+	/// non-canonical blocks from the journals.
+	fn source_storage<Block: BlockT>(&self) -> ClientResult<StorageDb<Block>> {
+		let (_, state_db) = StateDb::open(StateMetaDb(self.source.clone()), None, false, false)
+			.map_err(ClientError::from_state_db)?;
+		let checkpoints = match state_db.pruning_mode() {
+			PruningMode::Constrained(constraints) =>
+				constraints.checkpoints.map_or(false, |policy| policy.is_enabled()),
+			_ => false,
+		};
+		if checkpoints || !state_db.checkpoints().is_empty() {
+			return Err(ClientError::Backend(
+				"State checkpoints can't be migrated to a database without reference counting"
+					.into(),
+			))
+		}
+		Ok(StorageDb { db: self.source.clone(), state_db, prefix_keys: false })
+	}
+
+	/// Target entries rebuilt from an entry of the source.
+	fn rebuilt_entries<Block: BlockT>(
+		&self,
+		rebuilt: Rebuilt,
+		entry: &ColumnEntry,
+	) -> ClientResult<Vec<(ColumnId, Vec<u8>, Vec<u8>)>> {
+		match rebuilt {
+			Rebuilt::Blocks => self.block_entries::<Block>(entry.value),
+			Rebuilt::Transactions => {
+				let hash = HashFor::<Block>::hash(entry.value);
+				// Reference counter of the target database adapter.
+				let mut counter_key = hash.as_ref().to_vec();
+				counter_key.push(0);
+				Ok(vec![
+					(columns::TRANSACTION, hash.as_ref().to_vec(), entry.value.to_vec()),
+					(columns::TRANSACTION, counter_key, entry.references.to_le_bytes().to_vec()),
+				])
+			},
+			Rebuilt::Meta | Rebuilt::State => Ok(Vec::new()),
+		}
+	}
+
+	/// Entries of a block, found from its header: the block data under its lookup key, the lookup
+	/// entries and the children of the block.
+	fn block_entries<Block: BlockT>(
+		&self,
+		header: &[u8],
+	) -> ClientResult<Vec<(ColumnId, Vec<u8>, Vec<u8>)>> {
+		let decoded = Block::Header::decode(&mut &header[..]).map_err(|e| {
+			ClientError::Backend(format!("Error decoding a header of the source: {}", e))
+		})?;
+		let hash = decoded.hash();
+		let number = *decoded.number();
+		let lookup_key = utils::number_and_hash_to_lookup_key(number, hash)?;
+		let mut children_key = meta_keys::CHILDREN_PREFIX.to_vec();
+		hash.using_encoded(|encoded| children_key.extend(encoded));
+		let keys = [
+			(columns::BODY, lookup_key.clone()),
+			(columns::BODY_INDEX, lookup_key.clone()),
+			(columns::BODY_INDEX, utils::pruned_body_index_key(&lookup_key)),
+			(columns::JUSTIFICATIONS, lookup_key.clone()),
+			(columns::KEY_LOOKUP, hash.as_ref().to_vec()),
+			(columns::KEY_LOOKUP, utils::number_index_key(number)?.to_vec()),
+			(columns::META, children_key),
+		];
+		let mut entries = vec![(columns::HEADER, lookup_key, header.to_vec())];
+		entries.extend(keys.into_iter().filter_map(|(column, key)| {
+			self.source.get(column, &key).map(|value| (column, key, value))
+		}));
+		Ok(entries)
+	}
+
+	/// Copy, or verify, the entries rebuilt from each entry of a source column.
+	fn rebuild_column<Block: BlockT>(
+		&self,
+		rebuilt: Rebuilt,
+		progress: &mut Progress,
+		verify: bool,
+	) -> ClientResult<()> {
+		let column = match rebuilt {
+			Rebuilt::Transactions => columns::TRANSACTION,
+			_ => columns::HEADER,
+		};
+		if verify {
+			info!(target: "db", "Verifying the entries rebuilt from database column {}", column);
+		} else {
+			info!(target: "db", "Rebuilding the entries of database column {}", column);
+		}
+		let skip = progress.entries;
+		let mut entries = 0;
+		let mut transaction = Transaction::new();
+		let mut batch_size = 0;
+		let mut result = Ok(());
+		self.source.iter_column(column, &mut |entry| {
+			entries += 1;
+			if entries <= skip {
+				return true
+			}
+			result = (|| -> ClientResult<()> {
+				for (column, key, value) in self.rebuilt_entries::<Block>(rebuilt, &entry)? {
+					if verify {
+						self.verify_entry(column, &key, &value)?;
+					} else {
+						batch_size += key.len() + value.len();
+						transaction.set_from_vec(column, &key, value);
+					}
+				}
+				if batch_size >= MAX_BATCH_SIZE || (verify && entries % VERIFY_BATCH == 0) {
+					progress.entries = entries;
+					self.commit(std::mem::take(&mut transaction), progress)?;
+					batch_size = 0;
+				}
+				Ok(())
+			})();
+			result.is_ok()
+		})?;
+		result?;
+		progress.next_step();
+		self.commit(transaction, progress)
+	}
+
+	/// Copy, or verify, the metadata of the blocks.
+	fn copy_meta(&self, progress: &mut Progress, verify: bool) -> ClientResult<()> {
+		let mut transaction = Transaction::new();
+		for key in BLOCK_META_KEYS {
+			if let Some(value) = self.source.get(columns::META, key) {
+				if verify {
+					self.verify_entry(columns::META, key, &value)?;
+				} else {
+					transaction.set_from_vec(columns::META, key, value);
+				}
+			}
+		}
+		progress.next_step();
+		self.commit(transaction, progress)
+	}
+
+	fn verify_entry(&self, column: ColumnId, key: &[u8], value: &[u8]) -> ClientResult<()> {
+		if self.target.get(column, key).as_deref() != Some(value) {
+			return Err(ClientError::Backend(format!(
+				"Verification failed: entry 0x{} of column {} differs in the target",
+				HexDisplay::from(&key),
+				column,
+			)))
+		}
+		Ok(())
+	}
+
+	/// Source keys of the trie nodes referenced by the state-db journals.
+	fn journaled_keys<Block: BlockT>(&self) -> ClientResult<HashSet<Vec<u8>>> {
+		sc_state_db::journaled_keys::<Block::Hash, Vec<u8>, _>(&StateMetaDb(self.source.clone()))
+			.map_err(ClientError::from_state_db)
+	}
+
+	/// Prefixed keys of the journaled trie nodes recorded so far, with the number of the last block
+	/// using each key.
+	fn node_keys(&self, journaled_keys: &HashSet<Vec<u8>>) -> ClientResult<NodeKeys> {
+		let mut node_keys = HashMap::new();
+		for key in journaled_keys {
+			if let Some(keys) = self.target.get(columns::META, &node_keys_key(key)) {
+				let keys = Decode::decode(&mut keys.as_slice()).map_err(|e| {
+					ClientError::Backend(format!("Error decoding the migrated node keys: {}", e))
+				})?;
+				node_keys.insert(key.clone(), keys);
+			}
+		}
+		Ok(node_keys)
+	}
+
+	/// Copy the trie nodes of the state of every block under their prefixed keys.
+	fn rebuild_state<Block: BlockT>(
+		&self,
+		storage: &StorageDb<Block>,
+		progress: &mut Progress,
+	) -> ClientResult<()> {
+		info!(target: "db", "Rebuilding the state");
+		let journaled_keys = self.journaled_keys::<Block>()?;
+		let roots = self.state_roots::<Block>()?;
+		let mut copier = StateCopier {
+			migration: self,
+			storage,
+			node_keys: self.node_keys(&journaled_keys)?,
+			journaled_keys,
+			changed_node_keys: HashSet::new(),
+			overlay_nodes: HashSet::new(),
+			number: 0,
+			rewrite: false,
+			transaction: Transaction::new(),
+			batch_size: 0,
+			progress: *progress,
+		};
+		let start = progress.entries as usize;
+		for (index, (number, root)) in roots.into_iter().enumerate().skip(start) {
+			copier.progress.entries = index as u64;
+			copier.number = number;
+			// The nodes of the state being copied when the migration was interrupted may be
+			// partially committed, without all their descendants.
+			copier.rewrite = index == start;
+			let mut child_roots = Vec::new();
+			walk_trie::<Block, _>(&mut copier, true, root, &[], Some(&mut child_roots))?;
+			for (child_root, keyspace) in child_roots {
+				walk_trie::<Block, _>(&mut copier, true, child_root, &keyspace, None)?;
+			}
+			if !copier.transaction.0.is_empty() {
+				copier.progress.entries = index as u64 + 1;
+				copier.commit()?;
+			}
+		}
+		copier.progress.next_step();
+		copier.commit()?;
+		*progress = copier.progress;
+		Ok(())
+	}
+
+	/// State roots of the source blocks with their numbers, in decreasing block numbers. A root
+	/// shared by several blocks is only returned with the highest number.
+	fn state_roots<Block: BlockT>(&self) -> ClientResult<Vec<(u64, Block::Hash)>> {
+		let mut roots: Vec<(u64, Block::Hash)> = Vec::new();
+		let mut result = Ok(());
+		self.source.iter_column(columns::HEADER, &mut |entry| {
+			match Block::Header::decode(&mut &entry.value[..]) {
+				Ok(header) =>
+					roots.push(((*header.number()).unique_saturated_into(), *header.state_root())),
+				Err(e) =>
+					result = Err(ClientError::Backend(format!(
+						"Error decoding a header of the source: {}",
+						e
+					))),
+			}
+			result.is_ok()
+		})?;
+		result?;
+		roots.sort_unstable_by(|a, b| b.cmp(a));
+		let mut seen = HashSet::new();
+		roots.retain(|(_, root)| seen.insert(*root));
+		Ok(roots)
+	}
+
+	/// Convert the state-db metadata to the prefixed keys of the trie nodes.
+	fn convert_state_meta<Block: BlockT>(&self, progress: &mut Progress) -> ClientResult<()> {
+		info!(target: "db", "Converting the state journals");
+		let node_keys = self.node_keys(&self.journaled_keys::<Block>()?)?;
+		let no_keys = Vec::new();
+		let keys = |key: &Vec<u8>| node_keys.get(key).unwrap_or(&no_keys).iter();
+		let meta = sc_state_db::prefixed_meta::<Block::Hash, Vec<u8>, _>(
+			&StateMetaDb(self.source.clone()),
+			|key| keys(key).map(|(key, _)| key.clone()).collect(),
+			|key, number| {
+				keys(key)
+					.filter(|(_, last)| *last < number)
+					.map(|(key, _)| key.clone())
+					.collect()
+			},
+		)
+		.map_err(ClientError::from_state_db)?;
+		let mut transaction = Transaction::new();
+		for (key, value) in meta {
+			transaction.set_from_vec(columns::STATE_META, &key, value);
+		}
+		for key in node_keys.keys() {
+			transaction.remove(columns::META, &node_keys_key(key));
+		}
+		progress.next_step();
+		self.commit(transaction, progress)
+	}
+
+	/// Compare the state of the blocks of the journals, of the finalized state and of the best
+	/// block with the source, through the state-db of the target.
+	fn verify_state<Block: BlockT>(
+		&self,
+		storage: &StorageDb<Block>,
+		progress: &mut Progress,
+	) -> ClientResult<()> {
+		info!(target: "db", "Verifying the state");
+		let (_, state_db) = StateDb::open(StateMetaDb(self.target.clone()), None, true, false)
+			.map_err(ClientError::from_state_db)?;
+		let target = StorageDb::<Block> { db: self.target.clone(), state_db, prefix_keys: true };
+
+		let meta = utils::read_meta::<Block>(&*self.target, columns::HEADER)?;
+		let mut blocks = sc_state_db::journaled_blocks::<Block::Hash, Vec<u8>, _>(&StateMetaDb(
+			self.source.clone(),
+		))
+		.map_err(ClientError::from_state_db)?
+		.into_iter()
+		.map(|(hash, _)| hash)
+		.collect::<Vec<_>>();
+		blocks.extend(meta.finalized_state.map(|(hash, _)| hash));
+		blocks.push(meta.best_hash);
+
+		let mut verifier =
+			StateVerifier { migration: self, source: storage, target, visited: HashSet::new() };
+		for (index, hash) in blocks.into_iter().enumerate().skip(progress.entries as usize) {
+			let header = utils::read_header::<Block>(
+				&*self.target,
+				columns::KEY_LOOKUP,
+				columns::HEADER,
+				BlockId::Hash(hash),
+			)?
+			.ok_or_else(|| {
+				ClientError::Backend(format!("Verification failed: header of {} is missing", hash))
+			})?;
+			let mut child_roots = Vec::new();
+			walk_trie::<Block, _>(
+				&mut verifier,
+				true,
+				*header.state_root(),
+				&[],
+				Some(&mut child_roots),
+			)?;
+			for (child_root, keyspace) in child_roots {
+				walk_trie::<Block, _>(&mut verifier, true, child_root, &keyspace, None)?;
+			}
+			progress.entries = index as u64 + 1;
+			self.commit(Transaction::new(), progress)?;
+		}
+		progress.next_step();
+		self.commit(Transaction::new(), progress)
+	}
+}
+
+/// Prefixed keys of journaled trie nodes, by source key, with the number of the last block using
+/// each of them.
+type NodeKeys = HashMap<Vec<u8>, Vec<(Vec<u8>, u64)>>;
+
+fn node_keys_key(key: &[u8]) -> Vec<u8> {
+	let mut node_keys_key = NODE_KEYS_PREFIX.to_vec();
+	node_keys_key.extend_from_slice(key);
+	node_keys_key
+}
+
+/// Copies the trie nodes of a source that doesn't keep its keys under their prefixed keys.
+struct StateCopier<'a, Block: BlockT> {
+	migration: &'a Migration,
+	storage: &'a StorageDb<Block>,
+	/// Source keys of the trie nodes referenced by the journals.
+	journaled_keys: HashSet<Vec<u8>>,
+	node_keys: NodeKeys,
+	/// Source keys which prefixed keys changed since the last commit.
+	changed_node_keys: HashSet<Vec<u8>>,
+	/// Visited nodes that are only stored in the non-canonical journals. They are not copied.
+	overlay_nodes: HashSet<Vec<u8>>,
+	/// Number of the block being walked.
+	number: u64,
+	/// Copy the nodes that are already in the target, and their descendants.
+	rewrite: bool,
+	transaction: Transaction<DbHash>,
+	batch_size: usize,
+	progress: Progress,
+}
+
+impl<'a, Block: BlockT> StateCopier<'a, Block> {
+	fn commit(&mut self) -> ClientResult<()> {
+		let mut transaction = std::mem::take(&mut self.transaction);
+		for key in self.changed_node_keys.drain() {
+			transaction.set_from_vec(
+				columns::META,
+				&node_keys_key(&key),
+				self.node_keys[&key].encode(),
+			);
+		}
+		self.batch_size = 0;
+		self.migration.commit(transaction, &self.progress)
+	}
+}
+
+impl<'a, Block: BlockT> TrieVisitor for StateCopier<'a, Block> {
+	type Error = ClientError;
+
+	fn node(&mut self, key: &[u8]) -> ClientResult<Option<DBValue>> {
+		let mut source_key = key.to_vec();
+		self.migration.source.sanitize_key(&mut source_key);
+		if self.journaled_keys.contains(&source_key) {
+			let keys = self.node_keys.entry(source_key.clone()).or_default();
+			// States are walked in decreasing block numbers, so the first block to use a key is
+			// the last one.
+			if !keys.iter().any(|(prefixed_key, _)| prefixed_key == key) {
+				keys.push((key.to_vec(), self.number));
+				self.changed_node_keys.insert(source_key.clone());
+			}
+		}
+
+		let stored = self.migration.source.contains(columns::STATE, &source_key);
+		if stored {
+			// The descendants of a copied node are copied as well.
+			if !self.rewrite && self.migration.target.contains(columns::STATE, key) {
+				return Ok(None)
+			}
+		} else if !self.overlay_nodes.insert(key.to_vec()) {
+			return Ok(None)
+		}
+		let value = self
+			.storage
+			.state_db
+			.get(source_key.as_slice(), self.storage)
+			.map_err(ClientError::from_state_db)?;
+		if let (true, Some(value)) = (stored, &value) {
+			self.batch_size += key.len() + value.len();
+			self.transaction.set(columns::STATE, key, value);
+			if self.batch_size >= MAX_BATCH_SIZE {
+				self.commit()?;
+			}
+		}
+		Ok(value)
+	}
+
+	fn value_node(&mut self, key: &[u8]) -> ClientResult<()> {
+		self.node(key).map(|_| ())
+	}
+
+	fn invalid_node(&mut self, error: String) -> ClientResult<()> {
+		Err(ClientError::Backend(format!("Invalid trie node in the source: {}", error)))
+	}
+}
+
+/// Compares the trie nodes read through the state-db of the target with the source.
+struct StateVerifier<'a, Block: BlockT> {
+	migration: &'a Migration,
+	source: &'a StorageDb<Block>,
+	target: StorageDb<Block>,
+	visited: HashSet<Vec<u8>>,
+}
+
+impl<'a, Block: BlockT> TrieVisitor for StateVerifier<'a, Block> {
+	type Error = ClientError;
+
+	fn node(&mut self, key: &[u8]) -> ClientResult<Option<DBValue>> {
+		if !self.visited.insert(key.to_vec()) {
+			return Ok(None)
+		}
+		let mut source_key = key.to_vec();
+		self.migration.source.sanitize_key(&mut source_key);
+		let expected = self
+			.source
+			.state_db
+			.get(source_key.as_slice(), self.source)
+			.map_err(ClientError::from_state_db)?;
+		let migrated = self
+			.target
+			.state_db
+			.get(key, &self.target)
+			.map_err(ClientError::from_state_db)?;
+		if migrated != expected {
+			return Err(ClientError::Backend(format!(
+				"Verification failed: trie node 0x{} differs in the target",
+				HexDisplay::from(&key),
+			)))
+		}
+		Ok(expected)
+	}
+
+	fn value_node(&mut self, key: &[u8]) -> ClientResult<()> {
+		self.node(key).map(|_| ())
+	}
+
+	fn invalid_node(&mut self, error: String) -> ClientResult<()> {
+		Err(ClientError::Backend(format!("Invalid trie node in the source: {}", error)))
+	}
+}
+
+fn missing_keys(column: ColumnId) -> ClientError {
+	ClientError::Backend(format!(
+		"The source database does not return the keys of column {}",
+		column
+	))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{
+		tests::{insert_block, insert_header, Block},
+		Backend, BlocksPruning, DatabaseSettings, PruningMode,
+	};
+	use sc_client_api::{backend::Backend as _, blockchain::Backend as _, AuxStore};
+	use sp_blockchain::HeaderBackend;
+	use sp_core::{offchain::OffchainStorage, H256};
+	use sp_runtime::traits::HashFor;
+	use sp_state_machine::{Backend as _, IndexOperation};
+
+	#[test]
+	fn migrates_to_parity_db() {
+		let backend = Backend::<Block>::new_test_with_tx_storage(BlocksPruning::Some(2), 2);
+		let x0 = sp_runtime::testing::ExtrinsicWrapper::from(0u64).encode();
+		let x0_hash = <HashFor<Block> as sp_core::Hasher>::hash(&x0[1..]);
+		let index = vec![IndexOperation::Insert {
+			extrinsic: 0,
+			hash: x0_hash.as_ref().to_vec(),
+			size: (x0.len() - 1) as u32,
+		}];
+		let genesis = insert_block(
+			&backend,
+			0,
+			Default::default(),
+			None,
+			Default::default(),
+			vec![0u64.into()],
+			Some(index),
+		)
+		.unwrap();
+		let mut hashes = vec![genesis];
+		for number in 1..6 {
+			let hash = insert_header(
+				&backend,
+				number,
+				hashes[number as usize - 1],
+				None,
+				Default::default(),
+			);
+			hashes.push(hash);
+		}
+		backend.insert_aux(&[(&b"aux"[..], &b"value"[..])], &[]).unwrap();
+		backend.offchain_storage().unwrap().set(b"prefix", b"key", b"value");
+		let source =
+			DatabaseSource::Custom { db: backend.storage.db.clone(), require_create_flag: false };
+		drop(backend);
+
+		let dir = tempfile::tempdir().unwrap();
+		let target = DatabaseSource::ParityDb { path: dir.path().to_owned() };
+		migrate_database::<Block>(&source, &target).unwrap();
+
+		let backend = Backend::<Block>::new(
+			DatabaseSettings {
+				trie_cache_maximum_size: None,
+				trie_cache_snapshot: None,
+				state_pruning: Some(PruningMode::blocks_pruning(2)),
+				source: target,
+				blocks_pruning: BlocksPruning::Some(2),
+				justifications_pruning: None,
+				transactions_pruning: None,
+			},
+			2,
+		)
+		.unwrap();
+		let blockchain = backend.blockchain();
+		assert_eq!(blockchain.info().best_hash, hashes[5]);
+		assert_eq!(blockchain.hash(3).unwrap(), Some(hashes[3]));
+		assert_eq!(blockchain.indexed_transaction(x0_hash).unwrap(), Some(x0[1..].to_vec()));
+		assert_eq!(backend.get_aux(b"aux").unwrap(), Some(b"value".to_vec()));
+		assert_eq!(
+			backend.offchain_storage().unwrap().get(b"prefix", b"key"),
+			Some(b"value".to_vec())
+		);
+		// The state of canonical and non-canonical blocks is migrated.
+		for number in 3..6 {
+			let state = backend.state_at(hashes[number]).unwrap();
+			let parent = hashes[number - 1];
+			assert_eq!(state.storage(parent.as_ref()).unwrap(), Some(parent.as_ref().to_vec()));
+		}
+
+		// Pruning continues with the converted journals.
+		let hash = insert_header(&backend, 6, hashes[5], None, Default::default());
+		hashes.push(hash);
+		for hash in &hashes[1..] {
+			backend.finalize_block(*hash, None).unwrap();
+		}
+		let state = backend.state_at(hash).unwrap();
+		assert_eq!(state.storage(hashes[5].as_ref()).unwrap(), Some(hashes[5].as_ref().to_vec()));
+	}
+
+	#[test]
+	fn migrates_from_parity_db() {
+		let dir = tempfile::tempdir().unwrap();
+		let source = DatabaseSource::ParityDb { path: dir.path().to_owned() };
+		let settings = |source| DatabaseSettings {
+			trie_cache_maximum_size: None,
+			trie_cache_snapshot: None,
+			state_pruning: Some(PruningMode::blocks_pruning(2)),
+			source,
+			blocks_pruning: BlocksPruning::Some(2),
+			justifications_pruning: None,
+			transactions_pruning: None,
+		};
+		let backend = Backend::<Block>::new(settings(source.clone()), 2).unwrap();
+		let x0 = sp_runtime::testing::ExtrinsicWrapper::from(0u64).encode();
+		let x0_hash = <HashFor<Block> as sp_core::Hasher>::hash(&x0[1..]);
+		let index = vec![IndexOperation::Insert {
+			extrinsic: 0,
+			hash: x0_hash.as_ref().to_vec(),
+			size: (x0.len() - 1) as u32,
+		}];
+		let genesis = insert_block(
+			&backend,
+			0,
+			Default::default(),
+			None,
+			Default::default(),
+			vec![0u64.into()],
+			Some(index),
+		)
+		.unwrap();
+		let mut hashes = vec![genesis];
+		let mut fork = Default::default();
+		for number in 1..6 {
+			let parent = hashes[number as usize - 1];
+			if number == 5 {
+				// A fork that is not canonicalized.
+				fork = insert_header(&backend, number, parent, None, H256::from_low_u64_be(1));
+			}
+			hashes.push(insert_header(&backend, number, parent, None, Default::default()));
+		}
+		backend.insert_aux(&[(&b"aux"[..], &b"value"[..])], &[]).unwrap();
+		drop(backend);
+
+		let db = sp_database::as_database(kvdb_memorydb::create(utils::NUM_COLUMNS));
+		let target = DatabaseSource::Custom { db, require_create_flag: false };
+		// The aux data can't be migrated.
+		assert!(migrate_database::<Block>(&source, &target).is_err());
+
+		let backend = Backend::<Block>::new(settings(source.clone()), 2).unwrap();
+		backend.insert_aux(&[], &[&b"aux"[..]]).unwrap();
+		drop(backend);
+		migrate_database::<Block>(&source, &target).unwrap();
+
+		let backend = Backend::<Block>::new(settings(target), 2).unwrap();
+		let blockchain = backend.blockchain();
+		assert_eq!(blockchain.info().best_hash, hashes[5]);
+		assert_eq!(blockchain.hash(3).unwrap(), Some(hashes[3]));
+		assert_eq!(blockchain.children(hashes[4]).unwrap().len(), 2);
+		assert_eq!(blockchain.indexed_transaction(x0_hash).unwrap(), Some(x0[1..].to_vec()));
+		// The state of canonical and non-canonical blocks is migrated.
+		for hash in hashes[3..].iter().chain([&fork]) {
+			let state = backend.state_at(*hash).unwrap();
+			let parent = blockchain.header(*hash).unwrap().unwrap().parent_hash;
+			assert_eq!(state.storage(parent.as_ref()).unwrap(), Some(parent.as_ref().to_vec()));
+		}
+
+		// Pruning continues with the converted journals.
+		let hash = insert_header(&backend, 6, hashes[5], None, Default::default());
+		hashes.push(hash);
+		for hash in &hashes[1..] {
+			backend.finalize_block(*hash, None).unwrap();
+		}
+		let state = backend.state_at(hash).unwrap();
+		assert_eq!(state.storage(hashes[5].as_ref()).unwrap(), Some(hashes[5].as_ref().to_vec()));
+	}
+
+	#[test]
+	fn refuses_unfinished_migration() {
+		let backend = Backend::<Block>::new_test(2, 0);
+		insert_header(&backend, 0, Default::default(), None, Default::default());
+		let db = backend.storage.db.clone();
+		drop(backend);
+		let mut transaction = Transaction::new();
+		transaction.set_from_vec(columns::META, meta_keys::MIGRATION, Progress::default().encode());
+		db.commit(transaction).unwrap();
+
+		let settings = DatabaseSettings {
+			trie_cache_maximum_size: None,
+			trie_cache_snapshot: None,
+			state_pruning: None,
+			source: DatabaseSource::Custom { db, require_create_flag: false },
+			blocks_pruning: BlocksPruning::Some(2),
+			justifications_pruning: None,
+			transactions_pruning: None,
+		};
+		assert!(Backend::<Block>::new(settings, 0).is_err());
+	}
+}
@@ -20,7 +20,7 @@ use crate::{
 	utils::{DatabaseType, NUM_COLUMNS},
 };
 /// A `Database` adapter for parity-db.
-use sp_database::{error::DatabaseError, Change, ColumnId, Database, Transaction};
+use sp_database::{error::DatabaseError, Change, ColumnEntry, ColumnId, Database, Transaction};
 
 struct DbAdapter(parity_db::Db);
 
@@ -156,6 +156,19 @@ impl<H: Clone + AsRef<[u8]>> Database<H> for DbAdapter {
 		true
 	}
 
+	fn iter_column(
+		&self,
+		col: ColumnId,
+		f: &mut dyn FnMut(ColumnEntry) -> bool,
+	) -> Result<(), DatabaseError> {
+		// parity-db only keeps the hash of the key in the index.
+		self.0
+			.iter_column_while(col as u8, |entry| {
+				f(ColumnEntry { key: None, value: &entry.value, references: entry.rc })
+			})
+			.map_err(|e| DatabaseError(Box::new(e)))
+	}
+
 	fn sanitize_key(&self, key: &mut Vec<u8>) {
 		let _prefix = key.drain(0..key.len() - crate::DB_HASH_LEN);
 	}
@@ -0,0 +1,187 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Snapshot of the shared trie cache, stored at shutdown and loaded at the next start.
+//!
+//! The trie nodes of the cache are stored with the best block at the time. If the best block is
+//! still the same when the snapshot is loaded, all of the nodes are loaded. Otherwise only the
+//! nodes of the state of the new best block are loaded.
+//!
+//! The snapshot starts with a [`Header`], followed by the encoded nodes up to the end of the file,
+//! the most recently used first. The nodes are written one at a time, so that storing the snapshot
+//! doesn't need another copy of the cache in memory.
+
+use std::{
+	fs,
+	io::{self, Write},
+	path::Path,
+};
+
+use codec::{Decode, Encode};
+use log::{debug, info, warn};
+use sp_runtime::traits::{Block as BlockT, HashFor};
+use sp_trie::cache::{ExportedNode, SharedTrieCache};
+
+const LOG_TARGET: &str = "db::trie-cache";
+const SNAPSHOT_VERSION: u32 = 2;
+
+#[derive(Encode, Decode)]
+struct Header<Hash> {
+	version: u32,
+	best_hash: Hash,
+}
+
+/// Store the nodes of the cache.
+pub(crate) fn store<Block: BlockT>(
+	path: &Path,
+	cache: &SharedTrieCache<HashFor<Block>>,
+	best_hash: Block::Hash,
+) -> io::Result<()> {
+	// Written to a temporary file first, so that an interrupted write leaves no snapshot.
+	let tmp_path = path.with_extension("tmp");
+	let mut file = io::BufWriter::new(fs::File::create(&tmp_path)?);
+	file.write_all(&Header { version: SNAPSHOT_VERSION, best_hash }.encode())?;
+	let mut stored = 0usize;
+	cache.try_for_each_exported_node(|node| {
+		stored += 1;
+		file.write_all(&node.encode())
+	})?;
+	file.into_inner().map_err(io::IntoInnerError::into_error)?;
+	fs::rename(&tmp_path, path)?;
+	info!(target: LOG_TARGET, "Stored {} trie cache nodes at {}", stored, path.display());
+	Ok(())
+}
+
+/// Load the nodes of a snapshot into the cache, if any. The snapshot is removed once loaded.
+///
+/// `best_state_root` is the state root of the current best block.
+pub(crate) fn load<Block: BlockT>(
+	path: &Path,
+	cache: &SharedTrieCache<HashFor<Block>>,
+	best_hash: Block::Hash,
+	best_state_root: Block::Hash,
+) {
+	let data = match fs::read(path) {
+		Ok(data) => data,
+		Err(e) if e.kind() == io::ErrorKind::NotFound => return,
+		Err(e) => {
+			warn!(target: LOG_TARGET, "Failed to read the trie cache snapshot: {}", e);
+			return
+		},
+	};
+	if let Err(e) = fs::remove_file(path) {
+		warn!(target: LOG_TARGET, "Failed to remove the trie cache snapshot: {}", e);
+	}
+
+	let mut input = &data[..];
+	let header = match Header::<Block::Hash>::decode(&mut input) {
+		Ok(header) if header.version == SNAPSHOT_VERSION => header,
+		Ok(header) => {
+			debug!(target: LOG_TARGET, "Ignoring a trie cache snapshot of version {}", header.version);
+			return
+		},
+		Err(e) => {
+			warn!(target: LOG_TARGET, "Invalid trie cache snapshot: {}", e);
+			return
+		},
+	};
+	let mut nodes = Vec::new();
+	while !input.is_empty() {
+		match ExportedNode::decode(&mut input) {
+			Ok(node) => nodes.push(node),
+			Err(e) => {
+				warn!(target: LOG_TARGET, "Invalid trie cache snapshot: {}", e);
+				return
+			},
+		}
+	}
+	// The nodes are imported the least recently used first.
+	nodes.reverse();
+
+	let stored = nodes.len();
+	let (root, kind) = if header.best_hash == best_hash {
+		(None, "all")
+	} else {
+		(Some(best_state_root), "best state")
+	};
+	let loaded = cache.import_nodes(nodes, root);
+	info!(
+		target: LOG_TARGET,
+		"Loaded {}/{} trie cache nodes from the snapshot ({} nodes)",
+		loaded,
+		stored,
+		kind,
+	);
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::H256;
+	use sp_runtime::testing::{Block as RawBlock, ExtrinsicWrapper};
+	use sp_trie::{cache::CacheSize, LayoutV1, MemoryDB, TrieDBBuilder, TrieDBMutBuilder};
+	use trie_db::{Trie, TrieMut};
+
+	type Block = RawBlock<ExtrinsicWrapper<u64>>;
+	type Layout = LayoutV1<HashFor<Block>>;
+
+	#[test]
+	fn stored_snapshot_is_loaded_once() {
+		let mut db = MemoryDB::<HashFor<Block>>::default();
+		let mut root = Default::default();
+		{
+			let mut trie = TrieDBMutBuilder::<Layout>::new(&mut db, &mut root).build();
+			for i in 0..16u8 {
+				trie.insert(&[i; 4], &[i; 64]).unwrap();
+			}
+		}
+
+		let cache = SharedTrieCache::<HashFor<Block>>::new(CacheSize::new(1024 * 1024));
+		{
+			let local_cache = cache.local_cache();
+			let mut trie_cache = local_cache.as_trie_db_cache(root);
+			let trie = TrieDBBuilder::<Layout>::new(&db, &root).with_cache(&mut trie_cache).build();
+			for i in 0..16u8 {
+				assert_eq!(trie.get(&[i; 4]).unwrap(), Some(vec![i; 64]));
+			}
+		}
+		let nodes = cache.export_nodes();
+		assert!(!nodes.is_empty());
+
+		let dir = tempfile::tempdir().unwrap();
+		let path = dir.path().join("trie_cache");
+		let best_hash = H256::repeat_byte(1);
+		store::<Block>(&path, &cache, best_hash).unwrap();
+
+		// Another best block, only the nodes of its state are loaded.
+		let loaded = SharedTrieCache::<HashFor<Block>>::new(CacheSize::new(1024 * 1024));
+		load::<Block>(&path, &loaded, H256::repeat_byte(2), H256::repeat_byte(3));
+		assert!(loaded.export_nodes().is_empty());
+		assert!(!path.exists());
+
+		store::<Block>(&path, &cache, best_hash).unwrap();
+		let loaded = SharedTrieCache::<HashFor<Block>>::new(CacheSize::new(1024 * 1024));
+		load::<Block>(&path, &loaded, H256::repeat_byte(2), root);
+		assert_eq!(loaded.export_nodes(), nodes);
+
+		store::<Block>(&path, &cache, best_hash).unwrap();
+		let loaded = SharedTrieCache::<HashFor<Block>>::new(CacheSize::new(1024 * 1024));
+		load::<Block>(&path, &loaded, best_hash, H256::repeat_byte(3));
+		assert_eq!(loaded.export_nodes(), nodes);
+	}
+}
@@ -0,0 +1,173 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Traversal of the trie nodes stored in the state column.
+
+use hash_db::Prefix;
+use sp_core::storage::{well_known_keys, ChildInfo};
+use sp_runtime::traits::{Block as BlockT, HashFor};
+use sp_state_machine::DBValue;
+use sp_trie::{prefixed_key, NodeCodec, NodePlan, ValuePlan};
+use trie_db::{node::NodeHandlePlan, NibbleVec, NodeCodec as _};
+
+/// Fingerprint of a state column key. Keys end with the node hash for both key layouts.
+pub(crate) fn fingerprint(key: &[u8]) -> u64 {
+	let mut bytes = [0u8; 8];
+	let len = key.len().min(8);
+	bytes[..len].copy_from_slice(&key[key.len() - len..]);
+	u64::from_le_bytes(bytes)
+}
+
+/// Receives the nodes of a trie walk.
+pub(crate) trait TrieVisitor {
+	type Error;
+
+	/// Called with the state column key of each node referenced by hash. Returns the encoded node
+	/// to descend into it, or `None` to skip it.
+	fn node(&mut self, key: &[u8]) -> Result<Option<DBValue>, Self::Error>;
+
+	/// Called with the state column key of each value stored in a separate node.
+	fn value_node(&mut self, key: &[u8]) -> Result<(), Self::Error>;
+
+	/// Called for a node that can't be decoded. The node is skipped.
+	fn invalid_node(&mut self, error: String) -> Result<(), Self::Error>;
+}
+
+/// A trie node to visit, referenced by its hash or inlined in the parent node.
+enum NodeRef<H> {
+	Hash(H),
+	Inline(Vec<u8>),
+}
+
+fn node_ref<Block: BlockT>(handle: &NodeHandlePlan, data: &[u8]) -> Option<NodeRef<Block::Hash>> {
+	match handle {
+		NodeHandlePlan::Hash(range) =>
+			decode_hash::<Block>(&data[range.clone()]).map(NodeRef::Hash),
+		NodeHandlePlan::Inline(range) => Some(NodeRef::Inline(data[range.clone()].to_vec())),
+	}
+}
+
+fn decode_hash<Block: BlockT>(data: &[u8]) -> Option<Block::Hash> {
+	let mut hash = Block::Hash::default();
+	if hash.as_ref().len() != data.len() {
+		return None
+	}
+	hash.as_mut().copy_from_slice(data);
+	Some(hash)
+}
+
+/// Key of a trie node in the state column.
+fn node_key<Block: BlockT>(
+	prefix_keys: bool,
+	hash: &Block::Hash,
+	keyspace: &[u8],
+	prefix: Prefix,
+) -> Vec<u8> {
+	if prefix_keys {
+		let mut prefix_bytes = keyspace.to_vec();
+		prefix_bytes.extend_from_slice(prefix.0);
+		prefixed_key::<HashFor<Block>>(hash, (&prefix_bytes, prefix.1))
+	} else {
+		hash.as_ref().to_vec()
+	}
+}
+
+/// Walk the nodes of a trie. Roots of the default child tries are collected into `child_roots`
+/// if given.
+pub(crate) fn walk_trie<Block: BlockT, V: TrieVisitor>(
+	visitor: &mut V,
+	prefix_keys: bool,
+	root: Block::Hash,
+	keyspace: &[u8],
+	mut child_roots: Option<&mut Vec<(Block::Hash, Vec<u8>)>>,
+) -> Result<(), V::Error> {
+	let mut stack = vec![(NodeRef::Hash(root), NibbleVec::new())];
+	while let Some((node, mut key)) = stack.pop() {
+		let data = match node {
+			NodeRef::Hash(hash) => match visitor.node(&node_key::<Block>(
+				prefix_keys,
+				&hash,
+				keyspace,
+				key.as_prefix(),
+			))? {
+				Some(data) => data,
+				None => continue,
+			},
+			NodeRef::Inline(data) => data,
+		};
+		let plan = match NodeCodec::<HashFor<Block>>::decode_plan(&data) {
+			Ok(plan) => plan,
+			Err(e) => {
+				visitor.invalid_node(format!("{:?}", e))?;
+				continue
+			},
+		};
+
+		let (value, children) = match &plan {
+			NodePlan::Empty => continue,
+			NodePlan::Leaf { partial, value } => {
+				key.append_partial(partial.build(&data).right());
+				(Some(value), None)
+			},
+			NodePlan::Extension { partial, child } => {
+				key.append_partial(partial.build(&data).right());
+				stack.extend(node_ref::<Block>(child, &data).map(|child| (child, key)));
+				continue
+			},
+			NodePlan::Branch { value, children } => (value.as_ref(), Some(children)),
+			NodePlan::NibbledBranch { partial, value, children } => {
+				key.append_partial(partial.build(&data).right());
+				(value.as_ref(), Some(children))
+			},
+		};
+
+		match value {
+			Some(ValuePlan::Node(range)) =>
+				if let Some(hash) = decode_hash::<Block>(&data[range.clone()]) {
+					visitor.value_node(&node_key::<Block>(
+						prefix_keys,
+						&hash,
+						keyspace,
+						key.as_prefix(),
+					))?;
+				},
+			Some(ValuePlan::Inline(range)) =>
+				if let (Some(child_roots), (full_key, None)) =
+					(child_roots.as_mut(), key.as_prefix())
+				{
+					if let (Some(storage_key), Some(root)) = (
+						full_key.strip_prefix(well_known_keys::DEFAULT_CHILD_STORAGE_KEY_PREFIX),
+						decode_hash::<Block>(&data[range.clone()]),
+					) {
+						let keyspace = ChildInfo::new_default(storage_key).keyspace().to_vec();
+						child_roots.push((root, keyspace));
+					}
+				},
+			None => {},
+		}
+
+		for (index, child) in children.into_iter().flatten().enumerate() {
+			if let Some(child) = child.as_ref().and_then(|c| node_ref::<Block>(c, &data)) {
+				let mut child_key = key.clone();
+				child_key.push(index as u8);
+				stack.push((child, child_key));
+			}
+		}
+	}
+	Ok(())
+}
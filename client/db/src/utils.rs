@@ -56,6 +56,8 @@ pub mod meta_keys {
 	pub const LEAF_PREFIX: &[u8; 4] = b"leaf";
 	/// Children prefix list key.
 	pub const CHILDREN_PREFIX: &[u8; 8] = b"children";
+	/// Progress of an unfinished database migration.
+	pub const MIGRATION: &[u8; 9] = b"migration";
 }
 
 /// Database metadata.
@@ -112,6 +114,14 @@ where
 	Ok(lookup_key)
 }
 
+/// Key, in the body index column, of the indexed transaction hashes of a block which body was
+/// pruned before its indexed transactions.
+pub fn pruned_body_index_key(lookup_key: &[u8]) -> Vec<u8> {
+	let mut key = lookup_key.to_vec();
+	key.extend_from_slice(b"transactions");
+	key
+}
+
 /// Delete number to hash mapping in DB transaction.
 pub fn remove_number_to_key_mapping<N: TryInto<u32>>(
 	transaction: &mut Transaction<DbHash>,
@@ -164,6 +164,17 @@ impl<B: BlockT> InformantDisplay<B> {
 				TransferRateFormat(avg_bytes_per_sec_outbound),
 			)
 		}
+
+		if let Some(compaction) = info.usage.as_ref().and_then(|u| u.state_compaction.as_ref()) {
+			info!(
+				target: "substrate",
+				"🧹 Compacting state: {} nodes retained, sweep pass {}/{}, {} nodes removed",
+				compaction.marked_nodes,
+				compaction.sweep_passes_done,
+				compaction.sweep_passes,
+				compaction.removed_nodes,
+			)
+		}
 	}
 }
 
@@ -0,0 +1,264 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+//
+//! On-disk format of the key files.
+//!
+//! Key files of stores without a password contain the secret URI of the key as a plain JSON
+//! string. This is also the format written by earlier versions of the keystore, where the keystore
+//! password was only used as derivation password of the keys.
+//!
+//! Key files of stores protected by a password are encrypted at rest. They contain a versioned JSON
+//! envelope:
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "kdf": { "name": "scrypt", "salt": "0x..", "logN": 15, "r": 8, "p": 1 },
+//!   "cipher": "xchacha20poly1305",
+//!   "nonce": "0x..",
+//!   "ciphertext": "0x.."
+//! }
+//! ```
+//!
+//! The encryption key is derived from the keystore password with the given key derivation function
+//! and the plaintext is a JSON encoded [`KeySecret`]. The derivation password of the key is stored
+//! alongside the secret URI, so that the keystore password can be changed without changing the
+//! keys themselves.
+
+use chacha20poly1305::{
+	aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng},
+	Key, XChaCha20Poly1305, XNonce,
+};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use sp_core::{
+	crypto::{ByteArray, ExposeSecret, Pair as CorePair, SecretString},
+	ecdsa, ed25519, sr25519,
+};
+use std::{
+	collections::HashMap,
+	fs::{self, File},
+	io::Write,
+	path::Path,
+};
+
+use crate::{Error, Result};
+
+/// Version of the encrypted key file format.
+const VERSION: u64 = 1;
+/// Length of the salt used for the key derivation.
+const SALT_LEN: usize = 32;
+/// Length of the nonce used by XChaCha20-Poly1305.
+const NONCE_LEN: usize = 24;
+/// Length of the encryption key.
+const KEY_LEN: usize = 32;
+
+/// Scrypt cost parameter of newly written key files.
+///
+/// Kept low in tests, as the key is derived at least once for every opened store.
+const SCRYPT_LOG_N: u8 = if cfg!(test) { 10 } else { 15 };
+const SCRYPT_R: u32 = 8;
+const SCRYPT_P: u32 = 1;
+/// Upper bound for the scrypt cost parameter read from a key file.
+const MAX_SCRYPT_LOG_N: u8 = 20;
+
+/// Secret stored in a key file.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct KeySecret {
+	/// Secret URI of the key.
+	pub suri: String,
+	/// Password used to derive the key from the secret URI.
+	pub password: Option<String>,
+}
+
+impl KeySecret {
+	/// Returns `true` if the secret derives the given raw public key with any of the supported
+	/// crypto schemes.
+	pub fn derives(&self, public: &[u8]) -> bool {
+		fn derives<P: CorePair>(secret: &KeySecret, public: &[u8]) -> bool {
+			P::from_string(&secret.suri, secret.password.as_deref())
+				.map_or(false, |pair| pair.public().as_slice() == public)
+		}
+
+		derives::<sr25519::Pair>(self, public) ||
+			derives::<ed25519::Pair>(self, public) ||
+			derives::<ecdsa::Pair>(self, public)
+	}
+}
+
+/// Content of a key file.
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum KeyFile {
+	/// The secret URI, stored in plain.
+	Legacy(String),
+	/// The encrypted [`KeySecret`].
+	Encrypted(EncryptedKeyFile),
+}
+
+/// Encrypted key file envelope.
+#[derive(Serialize, Deserialize)]
+pub struct EncryptedKeyFile {
+	version: u64,
+	kdf: Kdf,
+	cipher: Cipher,
+	nonce: String,
+	ciphertext: String,
+}
+
+/// Key derivation function and its parameters.
+#[derive(Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "name", rename_all = "lowercase")]
+enum Kdf {
+	Scrypt {
+		salt: String,
+		#[serde(rename = "logN")]
+		log_n: u8,
+		r: u32,
+		p: u32,
+	},
+}
+
+/// Authenticated cipher used to encrypt the key secret.
+#[derive(Serialize, Deserialize)]
+enum Cipher {
+	#[serde(rename = "xchacha20poly1305")]
+	XChaCha20Poly1305,
+}
+
+/// Read the key file at `path`.
+pub fn read(path: &Path) -> Result<KeyFile> {
+	let file = File::open(path)?;
+	match serde_json::from_reader(&file)? {
+		serde_json::Value::String(suri) => Ok(KeyFile::Legacy(suri)),
+		value => {
+			let version = value.get("version").and_then(|v| v.as_u64()).unwrap_or_default();
+			if version != VERSION {
+				return Err(Error::UnsupportedKeyFileVersion(version))
+			}
+			Ok(KeyFile::Encrypted(serde_json::from_value(value)?))
+		},
+	}
+}
+
+/// Write `key_file` to `path`.
+///
+/// The content is written to a temporary file first, which then replaces any existing file.
+pub fn write(path: &Path, key_file: &KeyFile) -> Result<()> {
+	let tmp_path = path.with_extension("tmp");
+	let mut file = File::create(&tmp_path)?;
+
+	#[cfg(target_family = "unix")]
+	{
+		use std::os::unix::fs::PermissionsExt;
+		file.set_permissions(fs::Permissions::from_mode(0o600))?;
+	}
+
+	serde_json::to_writer(&file, key_file)?;
+	file.flush()?;
+	file.sync_all()?;
+	fs::rename(tmp_path, path)?;
+	Ok(())
+}
+
+/// Encrypts and decrypts key files with a password.
+pub struct KeyFileCipher {
+	password: SecretString,
+	/// Key derivation function used for newly written files.
+	kdf: Kdf,
+	/// Cache of the derived encryption keys, as the key derivation is purposely expensive.
+	keys: Mutex<HashMap<Kdf, [u8; KEY_LEN]>>,
+}
+
+impl KeyFileCipher {
+	/// Create a new cipher for the given password.
+	pub fn new(password: SecretString) -> Self {
+		let mut salt = [0u8; SALT_LEN];
+		OsRng.fill_bytes(&mut salt);
+		let kdf = Kdf::Scrypt {
+			salt: array_bytes::bytes2hex("0x", &salt),
+			log_n: SCRYPT_LOG_N,
+			r: SCRYPT_R,
+			p: SCRYPT_P,
+		};
+		Self { password, kdf, keys: Default::default() }
+	}
+
+	/// Encrypt the given secret.
+	pub fn encrypt(&self, secret: &KeySecret) -> Result<EncryptedKeyFile> {
+		let key = self.key(&self.kdf)?;
+		let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+		let plaintext = serde_json::to_vec(secret)?;
+		let ciphertext = XChaCha20Poly1305::new(Key::from_slice(&key))
+			.encrypt(&nonce, plaintext.as_slice())
+			.expect("Encryption only fails for messages larger than the cipher limit; qed");
+
+		Ok(EncryptedKeyFile {
+			version: VERSION,
+			kdf: self.kdf.clone(),
+			cipher: Cipher::XChaCha20Poly1305,
+			nonce: array_bytes::bytes2hex("0x", &nonce),
+			ciphertext: array_bytes::bytes2hex("0x", &ciphertext),
+		})
+	}
+
+	/// Decrypt the secret of the given key file.
+	///
+	/// Fails with [`Error::KeyFileDecryption`] if the file was encrypted with another password.
+	pub fn decrypt(&self, file: &EncryptedKeyFile) -> Result<KeySecret> {
+		let key = self.key(&file.kdf)?;
+		let nonce = array_bytes::hex2bytes(&file.nonce)
+			.ok()
+			.filter(|nonce| nonce.len() == NONCE_LEN)
+			.ok_or(Error::InvalidKeyFile)?;
+		let ciphertext =
+			array_bytes::hex2bytes(&file.ciphertext).map_err(|_| Error::InvalidKeyFile)?;
+		let plaintext = match file.cipher {
+			Cipher::XChaCha20Poly1305 => XChaCha20Poly1305::new(Key::from_slice(&key))
+				.decrypt(XNonce::from_slice(&nonce), ciphertext.as_slice())
+				.map_err(|_| Error::KeyFileDecryption)?,
+		};
+
+		Ok(serde_json::from_slice(&plaintext)?)
+	}
+
+	/// Derive the encryption key with the given key derivation function.
+	fn key(&self, kdf: &Kdf) -> Result<[u8; KEY_LEN]> {
+		let mut keys = self.keys.lock();
+		if let Some(key) = keys.get(kdf) {
+			return Ok(*key)
+		}
+
+		let mut key = [0u8; KEY_LEN];
+		match kdf {
+			Kdf::Scrypt { salt, log_n, r, p } => {
+				if *log_n > MAX_SCRYPT_LOG_N {
+					return Err(Error::InvalidKeyFile)
+				}
+				let salt = array_bytes::hex2bytes(salt).map_err(|_| Error::InvalidKeyFile)?;
+				let params = scrypt::Params::new(*log_n, *r, *p, KEY_LEN)
+					.map_err(|_| Error::InvalidKeyFile)?;
+				scrypt::scrypt(self.password.expose_secret().as_bytes(), &salt, &params, &mut key)
+					.map_err(|_| Error::InvalidKeyFile)?;
+			},
+		}
+
+		keys.insert(kdf.clone(), key);
+		Ok(key)
+	}
+}
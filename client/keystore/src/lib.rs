@@ -23,9 +23,14 @@ use sp_core::crypto::KeyTypeId;
 use sp_keystore::Error as TraitError;
 use std::io;
 
+/// On-disk format of the key files
+mod key_file;
 /// Local keystore implementation
 mod local;
+/// Remote keystore implementation
+mod remote;
 pub use local::LocalKeystore;
+pub use remote::RemoteKeystore;
 
 /// Keystore error.
 #[derive(Debug, thiserror::Error)]
@@ -54,6 +59,24 @@ pub enum Error {
 	/// Keystore unavailable
 	#[error("Keystore unavailable")]
 	Unavailable,
+	/// Invalid remote signer URI
+	#[error("Invalid remote signer URI {0}")]
+	InvalidUri(String),
+	/// Remote signer request failed
+	#[error("Remote signer error: {0}")]
+	Remote(String),
+	/// The keystore contains encrypted keys, but no password was provided
+	#[error("Keystore is encrypted, but no password was provided")]
+	MissingPassword,
+	/// Key file decryption failed
+	#[error("Failed to decrypt key file, the keystore password is incorrect")]
+	KeyFileDecryption,
+	/// Key file is malformed
+	#[error("Invalid key file")]
+	InvalidKeyFile,
+	/// Key file format version is not supported
+	#[error("Unsupported key file version {0}")]
+	UnsupportedKeyFileVersion(u64),
 }
 
 /// Keystore Result
@@ -63,11 +86,18 @@ impl From<Error> for TraitError {
 	fn from(error: Error) -> Self {
 		match error {
 			Error::KeyNotSupported(id) => TraitError::KeyNotSupported(id),
-			Error::InvalidSeed | Error::InvalidPhrase | Error::PublicKeyMismatch =>
-				TraitError::ValidationError(error.to_string()),
+			Error::InvalidSeed |
+			Error::InvalidPhrase |
+			Error::PublicKeyMismatch |
+			Error::MissingPassword |
+			Error::KeyFileDecryption => TraitError::ValidationError(error.to_string()),
 			Error::Unavailable => TraitError::Unavailable,
 			Error::Io(e) => TraitError::Other(e.to_string()),
 			Error::Json(e) => TraitError::Other(e.to_string()),
+			Error::InvalidUri(_) |
+			Error::Remote(_) |
+			Error::InvalidKeyFile |
+			Error::UnsupportedKeyFileVersion(_) => TraitError::Other(error.to_string()),
 		}
 	}
 }
@@ -26,13 +26,15 @@ use sp_core::{
 use sp_keystore::{Error as TraitError, Keystore, KeystorePtr};
 use std::{
 	collections::HashMap,
-	fs::{self, File},
-	io::Write,
-	path::PathBuf,
+	fs,
+	path::{Path, PathBuf},
 	sync::Arc,
 };
 
-use crate::{Error, Result};
+use crate::{
+	key_file::{self, KeyFile, KeyFileCipher, KeySecret},
+	Error, Result,
+};
 
 /// A local based keystore that is either memory-based or filesystem-based.
 pub struct LocalKeystore(RwLock<KeystoreInner>);
@@ -50,6 +52,18 @@ impl LocalKeystore {
 		Self(RwLock::new(inner))
 	}
 
+	/// Change the password protecting the key files of the store.
+	///
+	/// Every key file is re-encrypted with the new `password`, including key files still stored
+	/// in the legacy plain format. The derivation password of the keys is kept, so the keys
+	/// themselves don't change. All key files are decrypted before any of them is rewritten,
+	/// so the store is left untouched if the current password is wrong.
+	///
+	/// Returns the number of re-encrypted key files.
+	pub fn change_password(&self, password: SecretString) -> Result<usize> {
+		self.0.write().change_password(password)
+	}
+
 	/// Get a key pair for the given public key.
 	///
 	/// Returns `Ok(None)` if the key doesn't exist, `Ok(Some(_))` if the key exists and
@@ -232,6 +246,34 @@ impl Keystore for LocalKeystore {
 		Ok(sig)
 	}
 
+	fn sr25519_ecies_decrypt(
+		&self,
+		key_type: KeyTypeId,
+		public: &sr25519::Public,
+		encrypted: &[u8],
+	) -> std::result::Result<Option<Vec<u8>>, TraitError> {
+		self.0
+			.read()
+			.key_pair_by_type::<sr25519::Pair>(public, key_type)?
+			.map(|pair| sp_statement_store::ecies::decrypt_sr25519(&pair, encrypted))
+			.transpose()
+			.map_err(|e| TraitError::ValidationError(e.to_string()))
+	}
+
+	fn ed25519_ecies_decrypt(
+		&self,
+		key_type: KeyTypeId,
+		public: &ed25519::Public,
+		encrypted: &[u8],
+	) -> std::result::Result<Option<Vec<u8>>, TraitError> {
+		self.0
+			.read()
+			.key_pair_by_type::<ed25519::Pair>(public, key_type)?
+			.map(|pair| sp_statement_store::ecies::decrypt_ed25519(&pair, encrypted))
+			.transpose()
+			.map_err(|e| TraitError::ValidationError(e.to_string()))
+	}
+
 	fn insert(
 		&self,
 		key_type: KeyTypeId,
@@ -248,7 +290,7 @@ impl Keystore for LocalKeystore {
 	fn has_keys(&self, public_keys: &[(Vec<u8>, KeyTypeId)]) -> bool {
 		public_keys
 			.iter()
-			.all(|(p, t)| self.0.read().key_phrase_by_type(p, *t).ok().flatten().is_some())
+			.all(|(p, t)| self.0.read().key_secret_by_type(p, *t).ok().flatten().is_some())
 	}
 }
 
@@ -268,17 +310,90 @@ struct KeystoreInner {
 	/// Map over `(KeyTypeId, Raw public key)` -> `Key phrase/seed`
 	additional: HashMap<(KeyTypeId, Vec<u8>), String>,
 	password: Option<SecretString>,
+	/// Cipher used to encrypt the key files, if the store is protected by a password.
+	cipher: Option<KeyFileCipher>,
 }
 
 impl KeystoreInner {
 	/// Open the store at the given path.
 	///
-	/// Optionally takes a password that will be used to encrypt/decrypt the keys.
+	/// Optionally takes a password that will be used to encrypt/decrypt the keys. Key files
+	/// still stored in the legacy plain format are encrypted with the password.
 	fn open<T: Into<PathBuf>>(path: T, password: Option<SecretString>) -> Result<Self> {
 		let path = path.into();
 		fs::create_dir_all(&path)?;
 
-		Ok(Self { path: Some(path), additional: HashMap::new(), password })
+		let cipher = password.clone().map(KeyFileCipher::new);
+		let store = Self { path: Some(path), additional: HashMap::new(), password, cipher };
+		store.migrate()?;
+
+		Ok(store)
+	}
+
+	/// Encrypt the key files that are still stored in the legacy plain format.
+	///
+	/// A key file is only encrypted if its secret derives the public key it is stored under with
+	/// the store password. Otherwise the password is most likely wrong and encrypting the file
+	/// would lock the key away, so the file is left as is.
+	fn migrate(&self) -> Result<()> {
+		let cipher = match &self.cipher {
+			Some(cipher) => cipher,
+			None => return Ok(()),
+		};
+
+		for (path, _, public) in self.key_files()? {
+			let suri = match key_file::read(&path) {
+				Ok(KeyFile::Legacy(suri)) => suri,
+				Ok(KeyFile::Encrypted(_)) => continue,
+				Err(e) => {
+					log::warn!(target: "keystore", "Skipping key file {}: {}", path.display(), e);
+					continue
+				},
+			};
+
+			let secret = KeySecret { suri, password: self.password().map(Into::into) };
+			if !secret.derives(&public) {
+				log::warn!(
+					target: "keystore",
+					"Not encrypting key file {}: the key doesn't match with the keystore password",
+					path.display(),
+				);
+				continue
+			}
+
+			key_file::write(&path, &KeyFile::Encrypted(cipher.encrypt(&secret)?))?;
+			log::debug!(target: "keystore", "Encrypted key file {}", path.display());
+		}
+
+		Ok(())
+	}
+
+	/// Re-encrypt all key files with the given password.
+	///
+	/// In-memory stores don't have any key files and are left untouched.
+	fn change_password(&mut self, password: SecretString) -> Result<usize> {
+		if self.path.is_none() {
+			return Ok(0)
+		}
+
+		let mut secrets = Vec::new();
+		for (path, _, public) in self.key_files()? {
+			let secret = self.read_key_file(&path)?;
+			if !secret.derives(&public) {
+				return Err(Error::PublicKeyMismatch)
+			}
+			secrets.push((path, secret));
+		}
+
+		let cipher = KeyFileCipher::new(password.clone());
+		for (path, secret) in &secrets {
+			key_file::write(path, &KeyFile::Encrypted(cipher.encrypt(secret)?))?;
+		}
+
+		self.password = Some(password);
+		self.cipher = Some(cipher);
+
+		Ok(secrets.len())
 	}
 
 	/// Get the password for this store.
@@ -288,7 +403,7 @@ impl KeystoreInner {
 
 	/// Create a new in-memory store.
 	fn new_in_memory() -> Self {
-		Self { path: None, additional: HashMap::new(), password: None }
+		Self { path: None, additional: HashMap::new(), password: None, cipher: None }
 	}
 
 	/// Get the key phrase for the given public key and key type from the in-memory store.
@@ -315,7 +430,7 @@ impl KeystoreInner {
 	/// Places it into the file system store, if a path is configured.
 	fn insert(&self, key_type: KeyTypeId, suri: &str, public: &[u8]) -> Result<()> {
 		if let Some(path) = self.key_file_path(public, key_type) {
-			Self::write_to_file(path, suri)?;
+			self.write_key_file(path, suri)?;
 		}
 
 		Ok(())
@@ -328,7 +443,7 @@ impl KeystoreInner {
 	fn generate_by_type<Pair: CorePair>(&mut self, key_type: KeyTypeId) -> Result<Pair> {
 		let (pair, phrase, _) = Pair::generate_with_phrase(self.password());
 		if let Some(path) = self.key_file_path(pair.public().as_slice(), key_type) {
-			Self::write_to_file(path, &phrase)?;
+			self.write_key_file(path, &phrase)?;
 		} else {
 			self.insert_ephemeral_pair(&pair, &phrase, key_type);
 		}
@@ -336,19 +451,29 @@ impl KeystoreInner {
 		Ok(pair)
 	}
 
-	/// Write the given `data` to `file`.
-	fn write_to_file(file: PathBuf, data: &str) -> Result<()> {
-		let mut file = File::create(file)?;
+	/// Write the secret `suri` of a key to `file`.
+	///
+	/// The secret is encrypted if the store is protected by a password.
+	fn write_key_file(&self, file: PathBuf, suri: &str) -> Result<()> {
+		let key_file = match &self.cipher {
+			Some(cipher) => KeyFile::Encrypted(cipher.encrypt(&KeySecret {
+				suri: suri.into(),
+				password: self.password().map(Into::into),
+			})?),
+			None => KeyFile::Legacy(suri.into()),
+		};
 
-		#[cfg(target_family = "unix")]
-		{
-			use std::os::unix::fs::PermissionsExt;
-			file.set_permissions(fs::Permissions::from_mode(0o600))?;
-		}
+		key_file::write(&file, &key_file)
+	}
 
-		serde_json::to_writer(&file, data)?;
-		file.flush()?;
-		Ok(())
+	/// Read the secret stored in the key `file`.
+	fn read_key_file(&self, file: &Path) -> Result<KeySecret> {
+		match key_file::read(file)? {
+			KeyFile::Legacy(suri) =>
+				Ok(KeySecret { suri, password: self.password().map(Into::into) }),
+			KeyFile::Encrypted(encrypted) =>
+				self.cipher.as_ref().ok_or(Error::MissingPassword)?.decrypt(&encrypted),
+		}
 	}
 
 	/// Create a new key from seed.
@@ -364,10 +489,13 @@ impl KeystoreInner {
 		Ok(pair)
 	}
 
-	/// Get the key phrase for a given public key and key type.
-	fn key_phrase_by_type(&self, public: &[u8], key_type: KeyTypeId) -> Result<Option<String>> {
+	/// Get the key secret for a given public key and key type.
+	fn key_secret_by_type(&self, public: &[u8], key_type: KeyTypeId) -> Result<Option<KeySecret>> {
 		if let Some(phrase) = self.get_additional_pair(public, key_type) {
-			return Ok(Some(phrase.clone()))
+			return Ok(Some(KeySecret {
+				suri: phrase.clone(),
+				password: self.password().map(Into::into),
+			}))
 		}
 
 		let path = if let Some(path) = self.key_file_path(public, key_type) {
@@ -377,9 +505,7 @@ impl KeystoreInner {
 		};
 
 		if path.exists() {
-			let file = File::open(path)?;
-
-			serde_json::from_reader(&file).map_err(Into::into).map(Some)
+			self.read_key_file(&path).map(Some)
 		} else {
 			Ok(None)
 		}
@@ -391,13 +517,14 @@ impl KeystoreInner {
 		public: &Pair::Public,
 		key_type: KeyTypeId,
 	) -> Result<Option<Pair>> {
-		let phrase = if let Some(p) = self.key_phrase_by_type(public.as_slice(), key_type)? {
-			p
+		let secret = if let Some(s) = self.key_secret_by_type(public.as_slice(), key_type)? {
+			s
 		} else {
 			return Ok(None)
 		};
 
-		let pair = Pair::from_string(&phrase, self.password()).map_err(|_| Error::InvalidPhrase)?;
+		let pair = Pair::from_string(&secret.suri, secret.password.as_deref())
+			.map_err(|_| Error::InvalidPhrase)?;
 
 		if &pair.public() == public {
 			Ok(Some(pair))
@@ -426,6 +553,20 @@ impl KeystoreInner {
 			.filter_map(|k| if k.0 == key_type { Some(k.1.clone()) } else { None })
 			.collect();
 
+		for (_, file_key_type, public) in self.key_files()? {
+			if file_key_type == key_type {
+				public_keys.push(public);
+			}
+		}
+
+		Ok(public_keys)
+	}
+
+	/// Returns the paths of all key files in the store along with their key type and raw public
+	/// key.
+	fn key_files(&self) -> Result<Vec<(PathBuf, KeyTypeId, Vec<u8>)>> {
+		let mut key_files = Vec::new();
+
 		if let Some(path) = &self.path {
 			for entry in fs::read_dir(&path)? {
 				let entry = entry?;
@@ -435,11 +576,13 @@ impl KeystoreInner {
 				if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
 					match array_bytes::hex2bytes(name) {
 						Ok(ref hex) if hex.len() > 4 => {
-							if hex[0..4] != key_type.0 {
-								continue
-							}
+							let key_type = KeyTypeId(
+								hex[0..4]
+									.try_into()
+									.expect("Slice has the length of a key type; qed"),
+							);
 							let public = hex[4..].to_vec();
-							public_keys.push(public);
+							key_files.push((path, key_type, public));
 						},
 						_ => continue,
 					}
@@ -447,7 +590,7 @@ impl KeystoreInner {
 			}
 		}
 
-		Ok(public_keys)
+		Ok(key_files)
 	}
 
 	/// Get a key pair for the given public key.
@@ -468,7 +611,10 @@ mod tests {
 	use super::*;
 	use sp_application_crypto::{ed25519, sr25519, AppPublic};
 	use sp_core::{crypto::Ss58Codec, testing::SR25519, Pair};
-	use std::{fs, str::FromStr};
+	use std::{
+		fs::{self, File},
+		str::FromStr,
+	};
 	use tempfile::TempDir;
 
 	const TEST_KEY_TYPE: KeyTypeId = KeyTypeId(*b"test");
@@ -575,6 +721,107 @@ mod tests {
 		);
 	}
 
+	#[test]
+	fn key_files_are_encrypted_with_password() {
+		let temp_dir = TempDir::new().unwrap();
+		let password = || Some(FromStr::from_str("password").unwrap());
+		let mut store = KeystoreInner::open(temp_dir.path(), password()).unwrap();
+
+		let (pair, phrase, _) = sr25519::AppPair::generate_with_phrase(Some("password"));
+		store.insert(SR25519, &phrase, pair.public().as_ref()).unwrap();
+		let generated: ed25519::AppPair = store.generate().unwrap();
+
+		let path = store.key_file_path(pair.public().as_ref(), SR25519).unwrap();
+		let content = fs::read_to_string(path).unwrap();
+		assert!(content.starts_with("{\"version\":1,"));
+		assert!(!content.contains(&phrase));
+
+		let store = KeystoreInner::open(temp_dir.path(), password()).unwrap();
+		assert_eq!(
+			pair.public(),
+			store.key_pair::<sr25519::AppPair>(&pair.public()).unwrap().unwrap().public(),
+		);
+		assert_eq!(
+			generated.public(),
+			store
+				.key_pair::<ed25519::AppPair>(&generated.public())
+				.unwrap()
+				.unwrap()
+				.public(),
+		);
+
+		let store = KeystoreInner::open(temp_dir.path(), None).unwrap();
+		assert!(matches!(
+			store.key_pair::<sr25519::AppPair>(&pair.public()),
+			Err(Error::MissingPassword)
+		));
+
+		let store = KeystoreInner::open(temp_dir.path(), Some(FromStr::from_str("wrong").unwrap()))
+			.unwrap();
+		assert!(matches!(
+			store.key_pair::<sr25519::AppPair>(&pair.public()),
+			Err(Error::KeyFileDecryption)
+		));
+	}
+
+	#[test]
+	fn legacy_key_files_are_migrated() {
+		let temp_dir = TempDir::new().unwrap();
+		let legacy = KeystoreInner::open(temp_dir.path(), None).unwrap();
+
+		// Written by a previous version with the keystore password used for derivation only.
+		let pair = sr25519::AppPair::from_string("//Alice", Some("password")).unwrap();
+		let path = legacy.key_file_path(pair.public().as_ref(), SR25519).unwrap();
+		key_file::write(&path, &KeyFile::Legacy("//Alice".into())).unwrap();
+		// A key that doesn't derive with the password is left untouched.
+		let other = sr25519::AppPair::from_string("//Bob", Some("other")).unwrap();
+		let other_path = legacy.key_file_path(other.public().as_ref(), SR25519).unwrap();
+		key_file::write(&other_path, &KeyFile::Legacy("//Bob".into())).unwrap();
+
+		let store =
+			KeystoreInner::open(temp_dir.path(), Some(FromStr::from_str("password").unwrap()))
+				.unwrap();
+		assert!(matches!(key_file::read(&path).unwrap(), KeyFile::Encrypted(_)));
+		assert!(matches!(key_file::read(&other_path).unwrap(), KeyFile::Legacy(_)));
+		assert_eq!(
+			pair.public(),
+			store.key_pair::<sr25519::AppPair>(&pair.public()).unwrap().unwrap().public(),
+		);
+	}
+
+	#[test]
+	fn change_password_reencrypts_key_files() {
+		let temp_dir = TempDir::new().unwrap();
+		let store =
+			LocalKeystore::open(temp_dir.path(), Some(FromStr::from_str("old").unwrap())).unwrap();
+		let public = store.sr25519_generate_new(TEST_KEY_TYPE, None).unwrap();
+		let legacy = sr25519::Pair::from_string("//Alice", Some("old")).unwrap();
+		let path = store.0.read().key_file_path(legacy.public().as_ref(), TEST_KEY_TYPE).unwrap();
+		key_file::write(&path, &KeyFile::Legacy("//Alice".into())).unwrap();
+
+		// Nothing is rewritten with a wrong current password.
+		let wrong = LocalKeystore::open(temp_dir.path(), Some(FromStr::from_str("wrong").unwrap()))
+			.unwrap();
+		assert!(wrong.change_password(FromStr::from_str("new").unwrap()).is_err());
+		assert!(matches!(key_file::read(&path).unwrap(), KeyFile::Legacy(_)));
+
+		assert_eq!(store.change_password(FromStr::from_str("new").unwrap()).unwrap(), 2);
+		assert!(store.has_keys(&[(public.to_raw_vec(), TEST_KEY_TYPE)]));
+
+		drop(store);
+		let store =
+			LocalKeystore::open(temp_dir.path(), Some(FromStr::from_str("old").unwrap())).unwrap();
+		assert!(!store.has_keys(&[(public.to_raw_vec(), TEST_KEY_TYPE)]));
+
+		let store =
+			LocalKeystore::open(temp_dir.path(), Some(FromStr::from_str("new").unwrap())).unwrap();
+		assert!(store.has_keys(&[
+			(public.to_raw_vec(), TEST_KEY_TYPE),
+			(legacy.public().to_raw_vec(), TEST_KEY_TYPE),
+		]));
+		assert!(store.sr25519_sign(TEST_KEY_TYPE, &public, b"message").unwrap().is_some());
+	}
+
 	#[test]
 	fn public_keys_are_returned() {
 		let temp_dir = TempDir::new().unwrap();
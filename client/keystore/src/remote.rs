@@ -0,0 +1,798 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+//
+//! Remote keystore implementation
+//!
+//! [`RemoteKeystore`] forwards all keystore operations to a signer running in a separate
+//! process, so that the secret key material never has to be stored next to the node. The signer
+//! is reached either over a Unix domain socket (`unix:///path/to/signer.sock`) or over HTTP on a
+//! loopback address (`http://127.0.0.1:8765`).
+//!
+//! Every operation is a JSON-RPC 2.0 request sent over a new connection. Over a Unix socket the
+//! request is written followed by a newline, after which the write half of the socket is shut
+//! down and the response is read until the signer closes the connection. Over HTTP the request
+//! is the body of a `POST` request.
+//!
+//! The method names and parameters mirror the [`Keystore`] trait:
+//!
+//! - `{sr25519,ed25519,ecdsa}_public_keys`: `{ keyType }`, returns a list of public keys.
+//! - `{sr25519,ed25519,ecdsa}_generate_new`: `{ keyType, seed }`, returns the public key.
+//! - `{sr25519,ed25519,ecdsa}_sign` and `ecdsa_sign_prehashed`: `{ keyType, public, message }`,
+//!   returns the SCALE encoded signature.
+//! - `sr25519_vrf_sign`: `{ keyType, public, transcript, extra }`, returns the SCALE encoded VRF
+//!   signature.
+//! - `sr25519_vrf_output`: `{ keyType, public, transcript }`, returns the SCALE encoded VRF output.
+//! - `insert`: `{ keyType, suri, public }`.
+//! - `keys`: `{ keyType }`, returns a list of raw public keys.
+//! - `has_keys`: `{ keys }` with a list of `[public, keyType]` pairs, returns a boolean.
+//!
+//! All binary values, including key type identifiers, are hex encoded with a `0x` prefix. VRF
+//! transcripts are sent as `{ label, items }` objects, where `items` is the list of
+//! `[domain, message]` pairs the transcript was built from. Signing methods return `null` if the
+//! signer doesn't hold the requested key.
+//!
+//! The [`Keystore`] trait is synchronous, so the requests block the calling thread. When called
+//! from a worker thread of a multi-threaded tokio runtime, the worker hands its other tasks over
+//! to the runtime for the duration of the request. The public keys returned by the signer are
+//! cached for [`PUBLIC_KEYS_CACHE_TTL`], or until a key is generated or inserted through this
+//! keystore.
+
+use codec::Decode;
+use parking_lot::Mutex;
+use serde_json::{json, Value};
+use sp_core::{
+	crypto::{ByteArray, KeyTypeId},
+	ecdsa, ed25519, sr25519,
+};
+use sp_keystore::{Error as TraitError, Keystore};
+use std::{
+	collections::HashMap,
+	io::{Read, Write},
+	net::{SocketAddr, TcpStream, ToSocketAddrs},
+	sync::atomic::{AtomicU64, Ordering},
+	time::{Duration, Instant},
+};
+
+use crate::{Error, Result};
+
+/// Log target for this file.
+const LOG_TARGET: &str = "keystore";
+
+/// Timeout for connecting to the signer and for each read and write.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long the public keys returned by the signer are cached.
+///
+/// Keys added to the signer by other means than this keystore show up after at most this long.
+pub const PUBLIC_KEYS_CACHE_TTL: Duration = Duration::from_secs(10);
+
+/// Location of the remote signer.
+#[derive(Debug, Clone)]
+enum Endpoint {
+	/// Unix domain socket at the given path.
+	#[cfg(unix)]
+	Unix(std::path::PathBuf),
+	/// HTTP server listening on a loopback address.
+	Http {
+		/// The resolved address of the server.
+		addr: SocketAddr,
+		/// The value of the `Host` header.
+		host: String,
+		/// The request path.
+		path: String,
+	},
+}
+
+impl Endpoint {
+	fn parse(uri: &str) -> Result<Self> {
+		let invalid = |reason: &str| Error::InvalidUri(format!("{}: {}", uri, reason));
+
+		if let Some(path) = uri.strip_prefix("unix://") {
+			if path.is_empty() {
+				return Err(invalid("missing socket path"))
+			}
+			#[cfg(unix)]
+			{
+				return Ok(Endpoint::Unix(path.into()))
+			}
+			#[cfg(not(unix))]
+			{
+				return Err(invalid("unix sockets are not supported on this platform"))
+			}
+		}
+
+		if let Some(rest) = uri.strip_prefix("http://") {
+			let (host, path) = match rest.find('/') {
+				Some(index) => rest.split_at(index),
+				None => (rest, "/"),
+			};
+			let addr = host
+				.to_socket_addrs()
+				.map_err(|e| invalid(&e.to_string()))?
+				.next()
+				.ok_or_else(|| invalid("host did not resolve to any address"))?;
+			if !addr.ip().is_loopback() {
+				return Err(invalid("only loopback addresses are allowed"))
+			}
+			return Ok(Endpoint::Http { addr, host: host.into(), path: path.into() })
+		}
+
+		Err(invalid("expected a `unix://` or `http://` URI"))
+	}
+
+	/// Send the request and return the response body.
+	fn request(&self, body: &[u8]) -> Result<Vec<u8>> {
+		match tokio::runtime::Handle::try_current() {
+			// Blocking a worker of a current thread runtime can't be avoided, and
+			// `block_in_place` panics there.
+			Ok(handle) if handle.runtime_flavor() == tokio::runtime::RuntimeFlavor::MultiThread =>
+				tokio::task::block_in_place(|| self.blocking_request(body)),
+			_ => self.blocking_request(body),
+		}
+	}
+
+	fn blocking_request(&self, body: &[u8]) -> Result<Vec<u8>> {
+		let mut response = Vec::new();
+		match self {
+			#[cfg(unix)]
+			Endpoint::Unix(path) => {
+				let mut stream = std::os::unix::net::UnixStream::connect(path)?;
+				stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+				stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+				stream.write_all(body)?;
+				stream.write_all(b"\n")?;
+				stream.shutdown(std::net::Shutdown::Write)?;
+				stream.read_to_end(&mut response)?;
+				Ok(response)
+			},
+			Endpoint::Http { addr, host, path } => {
+				let mut stream = TcpStream::connect_timeout(addr, REQUEST_TIMEOUT)?;
+				stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+				stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+				// HTTP/1.0 ensures the server closes the connection after the response and doesn't
+				// use chunked encoding.
+				write!(
+					stream,
+					"POST {} HTTP/1.0\r\nHost: {}\r\nContent-Type: application/json\r\n\
+					Content-Length: {}\r\n\r\n",
+					path,
+					host,
+					body.len()
+				)?;
+				stream.write_all(body)?;
+				stream.read_to_end(&mut response)?;
+				http_body(response)
+			},
+		}
+	}
+}
+
+/// Check the status of an HTTP response and return its body.
+fn http_body(mut response: Vec<u8>) -> Result<Vec<u8>> {
+	let header_end = response
+		.windows(4)
+		.position(|window| window == b"\r\n\r\n")
+		.ok_or_else(|| Error::Remote("Malformed HTTP response".into()))?;
+	let status_line = String::from_utf8_lossy(
+		response[..header_end].split(|byte| *byte == b'\n').next().unwrap_or_default(),
+	)
+	.trim()
+	.to_string();
+	if status_line.split_whitespace().nth(1) != Some("200") {
+		return Err(Error::Remote(format!("Unexpected HTTP response: {}", status_line)))
+	}
+	Ok(response.split_off(header_end + 4))
+}
+
+fn hex(data: &[u8]) -> Value {
+	Value::String(array_bytes::bytes2hex("0x", data))
+}
+
+fn from_hex(value: &Value) -> Result<Vec<u8>> {
+	let invalid = || Error::Remote(format!("Invalid hex value: {}", value));
+	let hex = value.as_str().ok_or_else(invalid)?;
+	match hex.strip_prefix("0x").unwrap_or(hex) {
+		"" => Ok(Vec::new()),
+		hex => array_bytes::hex2bytes(hex).map_err(|_| invalid()),
+	}
+}
+
+fn public_key<T: ByteArray>(value: &Value) -> Result<T> {
+	T::from_slice(&from_hex(value)?)
+		.map_err(|_| Error::Remote(format!("Invalid public key: {}", value)))
+}
+
+fn list(value: &Value) -> Result<&Vec<Value>> {
+	value
+		.as_array()
+		.ok_or_else(|| Error::Remote(format!("Expected a list: {}", value)))
+}
+
+/// Decode a SCALE encoded value, `null` meaning the signer doesn't hold the key.
+fn optional<T: Decode>(value: Value) -> Result<Option<T>> {
+	if value.is_null() {
+		return Ok(None)
+	}
+	T::decode(&mut &from_hex(&value)?[..])
+		.map(Some)
+		.map_err(|e| Error::Remote(format!("Invalid SCALE encoded value: {}", e)))
+}
+
+fn transcript(transcript: &sr25519::vrf::VrfTranscript) -> Value {
+	let data = transcript.data();
+	let items: Vec<_> = data
+		.items
+		.iter()
+		.map(|(domain, message)| json!([hex(domain), hex(message)]))
+		.collect();
+	json!({ "label": hex(data.label), "items": items })
+}
+
+/// A keystore that forwards all operations to a remote signer.
+pub struct RemoteKeystore {
+	endpoint: Endpoint,
+	next_id: AtomicU64,
+	/// Public keys returned by the `*_public_keys` methods, along with when they were fetched.
+	public_keys: Mutex<HashMap<(&'static str, KeyTypeId), (Instant, Value)>>,
+}
+
+impl RemoteKeystore {
+	/// Create a keystore that uses the signer at the given URI.
+	///
+	/// Supported URIs are `unix://<socket path>` and `http://<loopback address>:<port>[/path]`.
+	/// No connection is made until the first operation.
+	pub fn open(uri: &str) -> Result<Self> {
+		Ok(Self {
+			endpoint: Endpoint::parse(uri)?,
+			next_id: AtomicU64::new(0),
+			public_keys: Default::default(),
+		})
+	}
+
+	/// Call `method` on the signer and return the result.
+	fn call(&self, method: &str, params: Value) -> Result<Value> {
+		let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+		let request = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+		let response = self.endpoint.request(&serde_json::to_vec(&request)?)?;
+		let mut response: Value = serde_json::from_slice(&response)?;
+
+		if let Some(error) = response.get("error") {
+			let message = error
+				.get("message")
+				.and_then(Value::as_str)
+				.map(ToString::to_string)
+				.unwrap_or_else(|| error.to_string());
+			return Err(Error::Remote(message))
+		}
+		response
+			.get_mut("result")
+			.map(Value::take)
+			.ok_or_else(|| Error::Remote(format!("Missing result in `{}` response", method)))
+	}
+
+	/// Return the public keys of the given type, from the cache if they were fetched recently.
+	fn cached_public_keys(&self, method: &'static str, key_type: KeyTypeId) -> Result<Value> {
+		if let Some((fetched, keys)) = self.public_keys.lock().get(&(method, key_type)) {
+			if fetched.elapsed() < PUBLIC_KEYS_CACHE_TTL {
+				return Ok(keys.clone())
+			}
+		}
+		let keys = self.call(method, json!({ "keyType": hex(&key_type.0) }))?;
+		self.public_keys
+			.lock()
+			.insert((method, key_type), (Instant::now(), keys.clone()));
+		Ok(keys)
+	}
+
+	fn public_keys<T: ByteArray>(&self, method: &'static str, key_type: KeyTypeId) -> Vec<T> {
+		self.cached_public_keys(method, key_type)
+			.and_then(|keys| list(&keys)?.iter().map(public_key).collect())
+			.unwrap_or_else(|e| {
+				log::warn!(target: LOG_TARGET, "Remote signer `{}` failed: {}", method, e);
+				Vec::new()
+			})
+	}
+
+	fn generate_new<T: ByteArray>(
+		&self,
+		method: &str,
+		key_type: KeyTypeId,
+		seed: Option<&str>,
+	) -> Result<T> {
+		let public = self.call(method, json!({ "keyType": hex(&key_type.0), "seed": seed }));
+		self.public_keys.lock().clear();
+		public_key(&public?)
+	}
+
+	fn sign<T: Decode>(
+		&self,
+		method: &str,
+		key_type: KeyTypeId,
+		public: &[u8],
+		message: &[u8],
+	) -> Result<Option<T>> {
+		let params =
+			json!({ "keyType": hex(&key_type.0), "public": hex(public), "message": hex(message) });
+		optional(self.call(method, params)?)
+	}
+}
+
+impl Keystore for RemoteKeystore {
+	fn sr25519_public_keys(&self, key_type: KeyTypeId) -> Vec<sr25519::Public> {
+		self.public_keys("sr25519_public_keys", key_type)
+	}
+
+	fn sr25519_generate_new(
+		&self,
+		key_type: KeyTypeId,
+		seed: Option<&str>,
+	) -> std::result::Result<sr25519::Public, TraitError> {
+		Ok(self.generate_new("sr25519_generate_new", key_type, seed)?)
+	}
+
+	fn sr25519_sign(
+		&self,
+		key_type: KeyTypeId,
+		public: &sr25519::Public,
+		msg: &[u8],
+	) -> std::result::Result<Option<sr25519::Signature>, TraitError> {
+		Ok(self.sign("sr25519_sign", key_type, public.as_slice(), msg)?)
+	}
+
+	fn sr25519_vrf_sign(
+		&self,
+		key_type: KeyTypeId,
+		public: &sr25519::Public,
+		data: &sr25519::vrf::VrfSignData,
+	) -> std::result::Result<Option<sr25519::vrf::VrfSignature>, TraitError> {
+		let params = json!({
+			"keyType": hex(&key_type.0),
+			"public": hex(public.as_slice()),
+			"transcript": transcript(data.as_ref()),
+			"extra": data.extra().map(transcript),
+		});
+		Ok(optional(self.call("sr25519_vrf_sign", params)?)?)
+	}
+
+	fn sr25519_vrf_output(
+		&self,
+		key_type: KeyTypeId,
+		public: &sr25519::Public,
+		input: &sr25519::vrf::VrfInput,
+	) -> std::result::Result<Option<sr25519::vrf::VrfOutput>, TraitError> {
+		let params = json!({
+			"keyType": hex(&key_type.0),
+			"public": hex(public.as_slice()),
+			"transcript": transcript(input),
+		});
+		Ok(optional(self.call("sr25519_vrf_output", params)?)?)
+	}
+
+	fn ed25519_public_keys(&self, key_type: KeyTypeId) -> Vec<ed25519::Public> {
+		self.public_keys("ed25519_public_keys", key_type)
+	}
+
+	fn ed25519_generate_new(
+		&self,
+		key_type: KeyTypeId,
+		seed: Option<&str>,
+	) -> std::result::Result<ed25519::Public, TraitError> {
+		Ok(self.generate_new("ed25519_generate_new", key_type, seed)?)
+	}
+
+	fn ed25519_sign(
+		&self,
+		key_type: KeyTypeId,
+		public: &ed25519::Public,
+		msg: &[u8],
+	) -> std::result::Result<Option<ed25519::Signature>, TraitError> {
+		Ok(self.sign("ed25519_sign", key_type, public.as_slice(), msg)?)
+	}
+
+	fn ecdsa_public_keys(&self, key_type: KeyTypeId) -> Vec<ecdsa::Public> {
+		self.public_keys("ecdsa_public_keys", key_type)
+	}
+
+	fn ecdsa_generate_new(
+		&self,
+		key_type: KeyTypeId,
+		seed: Option<&str>,
+	) -> std::result::Result<ecdsa::Public, TraitError> {
+		Ok(self.generate_new("ecdsa_generate_new", key_type, seed)?)
+	}
+
+	fn ecdsa_sign(
+		&self,
+		key_type: KeyTypeId,
+		public: &ecdsa::Public,
+		msg: &[u8],
+	) -> std::result::Result<Option<ecdsa::Signature>, TraitError> {
+		Ok(self.sign("ecdsa_sign", key_type, public.as_slice(), msg)?)
+	}
+
+	fn ecdsa_sign_prehashed(
+		&self,
+		key_type: KeyTypeId,
+		public: &ecdsa::Public,
+		msg: &[u8; 32],
+	) -> std::result::Result<Option<ecdsa::Signature>, TraitError> {
+		Ok(self.sign("ecdsa_sign_prehashed", key_type, public.as_slice(), msg)?)
+	}
+
+	fn insert(
+		&self,
+		key_type: KeyTypeId,
+		suri: &str,
+		public: &[u8],
+	) -> std::result::Result<(), ()> {
+		let params = json!({ "keyType": hex(&key_type.0), "suri": suri, "public": hex(public) });
+		let result = self.call("insert", params);
+		self.public_keys.lock().clear();
+		result.map(|_| ()).map_err(|e| {
+			log::warn!(target: LOG_TARGET, "Remote signer `insert` failed: {}", e);
+		})
+	}
+
+	fn keys(&self, key_type: KeyTypeId) -> std::result::Result<Vec<Vec<u8>>, TraitError> {
+		let keys = self.call("keys", json!({ "keyType": hex(&key_type.0) }))?;
+		Ok(list(&keys)?.iter().map(from_hex).collect::<Result<_>>()?)
+	}
+
+	fn has_keys(&self, public_keys: &[(Vec<u8>, KeyTypeId)]) -> bool {
+		let keys: Vec<_> = public_keys
+			.iter()
+			.map(|(public, key_type)| json!([hex(public), hex(&key_type.0)]))
+			.collect();
+		match self.call("has_keys", json!({ "keys": keys })) {
+			Ok(Value::Bool(has_keys)) => has_keys,
+			Ok(other) => {
+				log::warn!(target: LOG_TARGET, "Remote signer `has_keys` returned {}", other);
+				false
+			},
+			Err(e) => {
+				log::warn!(target: LOG_TARGET, "Remote signer `has_keys` failed: {}", e);
+				false
+			},
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::LocalKeystore;
+	use codec::Encode;
+	use sp_core::{
+		crypto::{key_types::BABE, Pair, VrfPublic},
+		testing::SR25519,
+	};
+	use std::{net::TcpListener, thread};
+
+	fn param<'a>(params: &'a Value, name: &str) -> &'a Value {
+		params.get(name).unwrap_or_else(|| panic!("Missing `{}` parameter", name))
+	}
+
+	fn key_type(params: &Value) -> KeyTypeId {
+		key_type_from_hex(param(params, "keyType"))
+	}
+
+	fn key_type_from_hex(value: &Value) -> KeyTypeId {
+		KeyTypeId(from_hex(value).unwrap().try_into().unwrap())
+	}
+
+	fn message(params: &Value) -> Vec<u8> {
+		from_hex(param(params, "message")).unwrap()
+	}
+
+	fn leak(data: Vec<u8>) -> &'static [u8] {
+		Box::leak(data.into_boxed_slice())
+	}
+
+	fn rebuild_transcript(value: &Value) -> sr25519::vrf::VrfTranscript {
+		let label = leak(from_hex(param(value, "label")).unwrap());
+		let items: Vec<_> = list(param(value, "items"))
+			.unwrap()
+			.iter()
+			.map(|item| {
+				let item = list(item).unwrap();
+				(leak(from_hex(&item[0]).unwrap()), from_hex(&item[1]).unwrap())
+			})
+			.collect();
+		let items: Vec<_> = items.iter().map(|(domain, message)| (*domain, &message[..])).collect();
+		sr25519::vrf::VrfTranscript::new(label, &items)
+	}
+
+	/// Mock signer serving requests with an in-memory keystore.
+	fn handle_request(keystore: &LocalKeystore, request: &[u8]) -> Vec<u8> {
+		let request: Value = serde_json::from_slice(request).unwrap();
+		let params = param(&request, "params");
+		let public = || from_hex(param(params, "public")).unwrap();
+		let encoded = |signature: Option<Vec<u8>>| signature.map(|s| hex(&s)).unwrap_or_default();
+
+		let result = match param(&request, "method").as_str().unwrap() {
+			"sr25519_public_keys" => Value::Array(
+				keystore
+					.sr25519_public_keys(key_type(params))
+					.iter()
+					.map(|key| hex(key.as_slice()))
+					.collect(),
+			),
+			"sr25519_generate_new" => hex(&keystore
+				.sr25519_generate_new(key_type(params), param(params, "seed").as_str())
+				.unwrap()
+				.as_slice()),
+			"ed25519_generate_new" => hex(&keystore
+				.ed25519_generate_new(key_type(params), param(params, "seed").as_str())
+				.unwrap()
+				.as_slice()),
+			"ecdsa_generate_new" => hex(&keystore
+				.ecdsa_generate_new(key_type(params), param(params, "seed").as_str())
+				.unwrap()
+				.as_slice()),
+			"sr25519_sign" => encoded(
+				keystore
+					.sr25519_sign(
+						key_type(params),
+						&sr25519::Public::from_slice(&public()).unwrap(),
+						&message(params),
+					)
+					.unwrap()
+					.map(|s| s.encode()),
+			),
+			"ed25519_sign" => encoded(
+				keystore
+					.ed25519_sign(
+						key_type(params),
+						&ed25519::Public::from_slice(&public()).unwrap(),
+						&message(params),
+					)
+					.unwrap()
+					.map(|s| s.encode()),
+			),
+			"ecdsa_sign" => encoded(
+				keystore
+					.ecdsa_sign(
+						key_type(params),
+						&ecdsa::Public::from_slice(&public()).unwrap(),
+						&message(params),
+					)
+					.unwrap()
+					.map(|s| s.encode()),
+			),
+			"sr25519_vrf_sign" => {
+				let mut data =
+					sr25519::vrf::VrfSignData::new(rebuild_transcript(param(params, "transcript")));
+				let extra = param(params, "extra");
+				if !extra.is_null() {
+					data = data.with_extra(rebuild_transcript(extra));
+				}
+				encoded(
+					keystore
+						.sr25519_vrf_sign(
+							key_type(params),
+							&sr25519::Public::from_slice(&public()).unwrap(),
+							&data,
+						)
+						.unwrap()
+						.map(|s| s.encode()),
+				)
+			},
+			"sr25519_vrf_output" => encoded(
+				keystore
+					.sr25519_vrf_output(
+						key_type(params),
+						&sr25519::Public::from_slice(&public()).unwrap(),
+						&rebuild_transcript(param(params, "transcript")),
+					)
+					.unwrap()
+					.map(|s| s.encode()),
+			),
+			"has_keys" => {
+				let keys: Vec<_> = list(param(params, "keys"))
+					.unwrap()
+					.iter()
+					.map(|key| {
+						let key = list(key).unwrap();
+						(from_hex(&key[0]).unwrap(), key_type_from_hex(&key[1]))
+					})
+					.collect();
+				Value::Bool(keystore.has_keys(&keys))
+			},
+			method => {
+				let error =
+					json!({ "code": -32601, "message": format!("Unknown method {}", method) });
+				return serde_json::to_vec(
+					&json!({ "jsonrpc": "2.0", "id": request["id"], "error": error }),
+				)
+				.unwrap()
+			},
+		};
+		serde_json::to_vec(&json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }))
+			.unwrap()
+	}
+
+	#[cfg(unix)]
+	fn unix_signer(path: &std::path::Path) {
+		let listener = std::os::unix::net::UnixListener::bind(path).unwrap();
+		thread::spawn(move || {
+			let keystore = LocalKeystore::in_memory();
+			for mut stream in listener.incoming().map(|stream| stream.unwrap()) {
+				let mut request = Vec::new();
+				stream.read_to_end(&mut request).unwrap();
+				stream.write_all(&handle_request(&keystore, &request)).unwrap();
+			}
+		});
+	}
+
+	fn http_signer() -> SocketAddr {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let addr = listener.local_addr().unwrap();
+		thread::spawn(move || {
+			let keystore = LocalKeystore::in_memory();
+			for mut stream in listener.incoming().map(|stream| stream.unwrap()) {
+				let mut request = Vec::new();
+				let mut buf = [0u8; 1024];
+				// Read until the whole body announced by `Content-Length` is received.
+				let body = loop {
+					let read = stream.read(&mut buf).unwrap();
+					request.extend_from_slice(&buf[..read]);
+					let Some(header_end) = request.windows(4).position(|w| w == b"\r\n\r\n") else {
+						continue
+					};
+					let headers = String::from_utf8_lossy(&request[..header_end]).to_lowercase();
+					let length: usize = headers
+						.lines()
+						.find_map(|line| line.strip_prefix("content-length:"))
+						.unwrap()
+						.trim()
+						.parse()
+						.unwrap();
+					if request.len() >= header_end + 4 + length {
+						break request[header_end + 4..].to_vec()
+					}
+				};
+				let response = handle_request(&keystore, &body);
+				write!(stream, "HTTP/1.0 200 OK\r\nContent-Length: {}\r\n\r\n", response.len())
+					.unwrap();
+				stream.write_all(&response).unwrap();
+			}
+		});
+		addr
+	}
+
+	fn check_signing(keystore: &RemoteKeystore) {
+		let msg = b"message";
+
+		let public = keystore.sr25519_generate_new(SR25519, None).unwrap();
+		let signature = keystore.sr25519_sign(SR25519, &public, msg).unwrap().unwrap();
+		assert!(sr25519::Pair::verify(&signature, msg, &public));
+		assert_eq!(keystore.sr25519_public_keys(SR25519), vec![public]);
+		assert!(keystore.sr25519_public_keys(BABE).is_empty());
+
+		let public = keystore.ed25519_generate_new(SR25519, None).unwrap();
+		let signature = keystore.ed25519_sign(SR25519, &public, msg).unwrap().unwrap();
+		assert!(ed25519::Pair::verify(&signature, msg, &public));
+
+		let public = keystore.ecdsa_generate_new(SR25519, None).unwrap();
+		let signature = keystore.ecdsa_sign(SR25519, &public, msg).unwrap().unwrap();
+		assert!(ecdsa::Pair::verify(&signature, msg, &public));
+
+		// Unknown keys.
+		let (unknown, _) = sr25519::Pair::generate();
+		assert_eq!(keystore.sr25519_sign(SR25519, &unknown.public(), msg).unwrap(), None);
+		assert!(keystore.has_keys(&[(public.to_raw_vec(), SR25519)]));
+		assert!(!keystore.has_keys(&[(unknown.public().to_raw_vec(), SR25519)]));
+
+		// Methods the mock signer doesn't know about surface the signer error.
+		assert!(matches!(
+			keystore.keys(SR25519),
+			Err(TraitError::Other(e)) if e.contains("Unknown method keys")
+		));
+	}
+
+	#[cfg(unix)]
+	#[test]
+	fn unix_socket_signing_works() {
+		let temp_dir = tempfile::TempDir::new().unwrap();
+		let path = temp_dir.path().join("signer.sock");
+		unix_signer(&path);
+
+		let keystore = RemoteKeystore::open(&format!("unix://{}", path.display())).unwrap();
+		check_signing(&keystore);
+	}
+
+	#[test]
+	fn http_signing_works() {
+		let addr = http_signer();
+		let keystore = RemoteKeystore::open(&format!("http://{}/sign", addr)).unwrap();
+		check_signing(&keystore);
+	}
+
+	#[cfg(unix)]
+	#[test]
+	fn vrf_signing_works() {
+		let temp_dir = tempfile::TempDir::new().unwrap();
+		let path = temp_dir.path().join("signer.sock");
+		unix_signer(&path);
+		let keystore = RemoteKeystore::open(&format!("unix://{}", path.display())).unwrap();
+
+		let public = keystore.sr25519_generate_new(SR25519, None).unwrap();
+		let input = sr25519::vrf::VrfTranscript::new(b"label", &[(b"domain", b"data")]);
+		let extra = sr25519::vrf::VrfTranscript::new(b"extra", &[(b"domain2", b"data2")]);
+		let data = sr25519::vrf::VrfSignData::new(input.clone()).with_extra(extra);
+
+		let signature = keystore.sr25519_vrf_sign(SR25519, &public, &data).unwrap().unwrap();
+		assert!(public.vrf_verify(&data, &signature));
+
+		let output = keystore.sr25519_vrf_output(SR25519, &public, &input).unwrap().unwrap();
+		assert_eq!(output, signature.output);
+
+		let (unknown, _) = sr25519::Pair::generate();
+		assert_eq!(keystore.sr25519_vrf_sign(SR25519, &unknown.public(), &data).unwrap(), None);
+	}
+
+	#[cfg(unix)]
+	#[test]
+	fn public_keys_are_cached() {
+		let temp_dir = tempfile::TempDir::new().unwrap();
+		let path = temp_dir.path().join("signer.sock");
+		unix_signer(&path);
+		let keystore = RemoteKeystore::open(&format!("unix://{}", path.display())).unwrap();
+
+		let first = keystore.sr25519_generate_new(SR25519, None).unwrap();
+		assert_eq!(keystore.sr25519_public_keys(SR25519), vec![first]);
+		// Generating a key invalidates the cache.
+		let second = keystore.sr25519_generate_new(SR25519, None).unwrap();
+		let mut keys = keystore.sr25519_public_keys(SR25519);
+		keys.sort();
+		let mut expected = vec![first, second];
+		expected.sort();
+		assert_eq!(keys, expected);
+
+		// The signer is gone, the cached keys are still returned.
+		std::fs::remove_file(&path).unwrap();
+		assert_eq!(keystore.sr25519_public_keys(SR25519).len(), 2);
+		assert!(keystore.sr25519_public_keys(BABE).is_empty());
+	}
+
+	#[test]
+	fn parse_uri() {
+		assert!(matches!(
+			Endpoint::parse("http://127.0.0.1:8765"),
+			Ok(Endpoint::Http { path, .. }) if path == "/"
+		));
+		assert!(matches!(
+			Endpoint::parse("http://localhost:8765/sign"),
+			Ok(Endpoint::Http { path, .. }) if path == "/sign"
+		));
+		#[cfg(unix)]
+		assert!(matches!(Endpoint::parse("unix:///run/signer.sock"), Ok(Endpoint::Unix(_))));
+
+		assert!(matches!(Endpoint::parse("http://8.8.8.8:80"), Err(Error::InvalidUri(_))));
+		assert!(matches!(Endpoint::parse("https://127.0.0.1:80"), Err(Error::InvalidUri(_))));
+		assert!(matches!(Endpoint::parse("unix://"), Err(Error::InvalidUri(_))));
+	}
+
+	#[test]
+	fn unreachable_signer_fails() {
+		let keystore = RemoteKeystore::open("http://127.0.0.1:1").unwrap();
+		assert!(keystore.sr25519_generate_new(SR25519, None).is_err());
+		assert!(keystore.sr25519_public_keys(SR25519).is_empty());
+		assert!(!keystore.has_keys(&[]));
+	}
+}
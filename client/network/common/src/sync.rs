@@ -85,6 +85,17 @@ pub struct StateDownloadProgress {
 	pub size: u64,
 }
 
+/// Reported block history download progress.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct GapSyncProgress<BlockNumber> {
+	/// First block of the history gap.
+	pub start: BlockNumber,
+	/// Highest block of the gap queued for import.
+	pub best_queued: BlockNumber,
+	/// Last block of the history gap.
+	pub target: BlockNumber,
+}
+
 /// Syncing status and statistics.
 #[derive(Debug, Clone)]
 pub struct SyncStatus<Block: BlockT> {
@@ -102,6 +113,8 @@ pub struct SyncStatus<Block: BlockT> {
 	pub state_sync: Option<StateDownloadProgress>,
 	/// Warp sync in progress, if any.
 	pub warp_sync: Option<WarpSyncProgress<Block>>,
+	/// Block history download in progress, if any.
+	pub gap_sync: Option<GapSyncProgress<NumberFor<Block>>>,
 }
 
 /// A peer did not behave as expected and should be reported.
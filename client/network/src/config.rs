@@ -32,11 +32,13 @@ pub use crate::{
 use codec::Encode;
 use libp2p::{identity::Keypair, multiaddr, Multiaddr, PeerId};
 use prometheus_endpoint::Registry;
+use sc_client_api::AuxStore;
 pub use sc_network_common::{
 	role::{Role, Roles},
 	sync::warp::WarpSyncProvider,
 	ExHashT,
 };
+pub use sc_peerset::{DefaultPeerScoring, PeerScoring};
 use sc_utils::mpsc::TracingUnboundedSender;
 use zeroize::Zeroize;
 
@@ -51,6 +53,7 @@ use std::{
 	path::{Path, PathBuf},
 	pin::Pin,
 	str::{self, FromStr},
+	sync::Arc,
 };
 
 pub use libp2p::{
@@ -307,6 +310,25 @@ impl Default for SyncMode {
 	}
 }
 
+/// How the block history, missing after warp sync, is downloaded.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum GapSyncMode {
+	/// Download the headers, bodies and justifications of the blocks.
+	Full,
+	/// Download the headers and justifications of the blocks.
+	HeadersAndJustifications,
+	/// Download the headers of the blocks.
+	Headers,
+	/// Don't download the block history.
+	Skip,
+}
+
+impl Default for GapSyncMode {
+	fn default() -> Self {
+		Self::Full
+	}
+}
+
 /// The configuration of a node's secret key, describing the type of key
 /// and how it is obtained. A node's identity keypair is the result of
 /// the evaluation of the node key configuration.
@@ -597,6 +619,19 @@ pub struct NetworkConfiguration {
 	/// Initial syncing mode.
 	pub sync_mode: SyncMode,
 
+	/// How the block history missing after warp sync is downloaded.
+	pub gap_sync_mode: GapSyncMode,
+
+	/// Maximum number of peers to download the block history from in parallel. `None` for no
+	/// limit.
+	pub max_parallel_gap_downloads: Option<u32>,
+
+	/// Policy for the reputation of the peers, which decides which peers are banned.
+	///
+	/// If [`NetworkConfiguration::net_config_path`] is set, the banned peers are stored in it and
+	/// restored at the next start.
+	pub peer_scoring: Arc<dyn PeerScoring>,
+
 	/// True if Kademlia random discovery should be enabled.
 	///
 	/// If true, the node will automatically randomly walk the DHT in order to find new peers.
@@ -659,6 +694,9 @@ impl NetworkConfiguration {
 			max_parallel_downloads: 5,
 			max_blocks_per_request: 64,
 			sync_mode: SyncMode::Full,
+			gap_sync_mode: GapSyncMode::Full,
+			max_parallel_gap_downloads: None,
+			peer_scoring: Arc::new(DefaultPeerScoring),
 			enable_dht_random_walk: true,
 			allow_non_globals_in_dht: false,
 			kademlia_disjoint_query_paths: false,
@@ -730,6 +768,32 @@ pub struct Params<Block: BlockT> {
 
 	/// Request response protocol configurations
 	pub request_response_protocol_configs: Vec<RequestResponseConfig>,
+
+	/// Auxiliary storage of the node database.
+	///
+	/// The nodes we know of, along with their addresses and reputation, are stored in it and used
+	/// to find peers again after a restart. None means they are not stored.
+	pub aux_store: Option<Arc<dyn NetworkAuxStore>>,
+}
+
+/// Auxiliary storage of the node database, in which the network keeps the data that outlives a
+/// restart.
+pub trait NetworkAuxStore: Send + Sync {
+	/// Returns the value stored under the key.
+	fn get_aux(&self, key: &[u8]) -> sp_blockchain::Result<Option<Vec<u8>>>;
+
+	/// Store the value under the key.
+	fn insert_aux(&self, key: &[u8], value: &[u8]) -> sp_blockchain::Result<()>;
+}
+
+impl<T: AuxStore + Send + Sync> NetworkAuxStore for T {
+	fn get_aux(&self, key: &[u8]) -> sp_blockchain::Result<Option<Vec<u8>>> {
+		AuxStore::get_aux(self, key)
+	}
+
+	fn insert_aux(&self, key: &[u8], value: &[u8]) -> sp_blockchain::Result<()> {
+		AuxStore::insert_aux(self, &[(key, value)], &[])
+	}
 }
 
 #[cfg(test)]
@@ -247,6 +247,7 @@
 mod behaviour;
 mod discovery;
 mod peer_info;
+mod peer_store;
 mod protocol;
 mod service;
 mod transport;
@@ -283,7 +284,7 @@ pub use service::{
 };
 pub use types::ProtocolName;
 
-pub use sc_peerset::ReputationChange;
+pub use sc_peerset::{BannedPeer, ReputationChange};
 
 /// The maximum allowed number of established connections per peer.
 ///
@@ -0,0 +1,292 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Address book of the nodes we know of, stored in the node database so that it outlives a restart.
+//!
+//! The network worker periodically records the nodes known to the peerset, along with their
+//! addresses and reputation, and records them a last time when it is dropped. At startup, the
+//! stored nodes are handed to the discovery and to the peerset, so that a restarting node doesn't
+//! have to rediscover the network through the bootnodes. Nodes that haven't been seen for
+//! [`MAX_AGE`] are forgotten.
+//!
+//! The nodes are written to the auxiliary storage of the database by a background thread, so that
+//! the network worker doesn't block on disk I/O.
+
+use crate::config::NetworkAuxStore;
+use codec::{Decode, Encode};
+use libp2p::{Multiaddr, PeerId};
+use log::warn;
+use std::{
+	collections::HashMap,
+	sync::{mpsc, Arc},
+	thread,
+	time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+/// Key of the auxiliary storage under which the known nodes are stored.
+const PEER_STORE_KEY: &[u8] = b"network_known_peers";
+
+/// Interval between two writes of the store.
+const STORE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Time after which a node that we haven't seen is forgotten.
+const MAX_AGE: Duration = Duration::from_secs(7 * 24 * 3600);
+
+/// Maximum number of nodes in the store. The most recently seen ones are kept.
+const MAX_PEERS: usize = 1000;
+
+/// Maximum number of addresses stored per node.
+const MAX_ADDRESSES: usize = 10;
+
+/// A node of the store.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct KnownPeer {
+	/// Known addresses of the node.
+	pub addresses: Vec<Multiaddr>,
+	/// Reputation of the node when it was last seen.
+	pub reputation: i32,
+	/// When the node was last known to the peerset, in seconds since the UNIX epoch.
+	pub last_seen: u64,
+}
+
+#[derive(Encode, Decode)]
+struct StoredPeer {
+	peer_id: Vec<u8>,
+	addresses: Vec<Vec<u8>>,
+	reputation: i32,
+	last_seen: u64,
+}
+
+/// Current time, in seconds since the UNIX epoch.
+fn now() -> u64 {
+	SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |since| since.as_secs())
+}
+
+/// Known nodes, backed by the auxiliary storage of the node database.
+///
+/// The nodes given to the store last are written when it is dropped.
+pub(crate) struct PeerStore {
+	/// Known nodes, including the ones that the peerset has since forgotten.
+	peers: HashMap<PeerId, KnownPeer>,
+	/// When the store was last written.
+	last_store: Instant,
+	/// Sends the nodes to write to `writer`. `None` if the nodes are not written.
+	sender: Option<mpsc::Sender<Vec<StoredPeer>>>,
+	/// Thread writing the nodes to the database.
+	writer: Option<thread::JoinHandle<()>>,
+}
+
+impl PeerStore {
+	/// Load the store from the database. Nodes that are too old are skipped, and an empty store
+	/// is returned if the stored nodes can't be read.
+	pub fn load(aux_store: Arc<dyn NetworkAuxStore>) -> Self {
+		let peers = Self::read(&*aux_store);
+		let (sender, receiver) = mpsc::channel();
+		let writer = thread::Builder::new()
+			.name("known-peers".into())
+			.spawn(move || write_peers(&*aux_store, receiver));
+		let (sender, writer) = match writer {
+			Ok(writer) => (Some(sender), Some(writer)),
+			Err(e) => {
+				warn!(target: "sub-libp2p", "Failed to start the known nodes writer: {}", e);
+				(None, None)
+			},
+		};
+		let mut store = Self { peers, last_store: Instant::now(), sender, writer };
+		store.prune();
+		store
+	}
+
+	fn read(aux_store: &dyn NetworkAuxStore) -> HashMap<PeerId, KnownPeer> {
+		let stored = match aux_store.get_aux(PEER_STORE_KEY) {
+			Ok(Some(encoded)) =>
+				Vec::<StoredPeer>::decode(&mut &encoded[..]).map_err(|e| e.to_string()),
+			Ok(None) => Ok(Vec::new()),
+			Err(e) => Err(e.to_string()),
+		};
+		let stored = stored.unwrap_or_else(|e| {
+			warn!(target: "sub-libp2p", "Failed to load the known nodes: {}", e);
+			Vec::new()
+		});
+		stored
+			.into_iter()
+			.filter_map(|peer| {
+				let peer_id = match PeerId::from_bytes(&peer.peer_id) {
+					Ok(peer_id) => peer_id,
+					Err(e) => {
+						warn!(target: "sub-libp2p", "Invalid known node: {}", e);
+						return None
+					},
+				};
+				let addresses = peer
+					.addresses
+					.into_iter()
+					.filter_map(|addr| Multiaddr::try_from(addr).ok())
+					.collect();
+				Some((
+					peer_id,
+					KnownPeer { addresses, reputation: peer.reputation, last_seen: peer.last_seen },
+				))
+			})
+			.collect()
+	}
+
+	/// Returns the known nodes, along with the time elapsed since they were last seen.
+	pub fn peers(&self) -> impl Iterator<Item = (&PeerId, &KnownPeer, Duration)> {
+		let now = now();
+		self.peers.iter().map(move |(peer_id, peer)| {
+			(peer_id, peer, Duration::from_secs(now.saturating_sub(peer.last_seen)))
+		})
+	}
+
+	/// Record that the node is currently known to the peerset. If no address is given, the
+	/// previously stored addresses are kept.
+	pub fn update(&mut self, peer_id: PeerId, mut addresses: Vec<Multiaddr>, reputation: i32) {
+		addresses.truncate(MAX_ADDRESSES);
+		let last_seen = now();
+		match self.peers.get_mut(&peer_id) {
+			Some(peer) => {
+				if !addresses.is_empty() {
+					peer.addresses = addresses;
+				}
+				peer.reputation = reputation;
+				peer.last_seen = last_seen;
+			},
+			None if !addresses.is_empty() => {
+				self.peers.insert(peer_id, KnownPeer { addresses, reputation, last_seen });
+			},
+			None => {},
+		}
+	}
+
+	/// Returns true if it is time to write the store again.
+	pub fn should_store(&self) -> bool {
+		self.last_store.elapsed() >= STORE_INTERVAL
+	}
+
+	/// Forget the old nodes and hand the others to the writer. They are written in the
+	/// background.
+	pub fn store(&mut self) {
+		self.last_store = Instant::now();
+		self.prune();
+		let sender = match &self.sender {
+			Some(sender) => sender,
+			None => return,
+		};
+
+		let stored = self
+			.peers
+			.iter()
+			.map(|(peer_id, peer)| StoredPeer {
+				peer_id: peer_id.to_bytes(),
+				addresses: peer.addresses.iter().map(|addr| addr.to_vec()).collect(),
+				reputation: peer.reputation,
+				last_seen: peer.last_seen,
+			})
+			.collect();
+		let _ = sender.send(stored);
+	}
+
+	/// Forget the nodes that are too old, then the least recently seen nodes above [`MAX_PEERS`].
+	fn prune(&mut self) {
+		let oldest = now().saturating_sub(MAX_AGE.as_secs());
+		self.peers.retain(|_, peer| peer.last_seen >= oldest);
+
+		if self.peers.len() > MAX_PEERS {
+			let mut peers = self.peers.drain().collect::<Vec<_>>();
+			peers.sort_unstable_by(|(_, a), (_, b)| b.last_seen.cmp(&a.last_seen));
+			peers.truncate(MAX_PEERS);
+			self.peers = peers.into_iter().collect();
+		}
+	}
+}
+
+impl Drop for PeerStore {
+	fn drop(&mut self) {
+		// The writer writes the pending nodes and stops once the channel is closed.
+		self.sender = None;
+		if let Some(writer) = self.writer.take() {
+			if writer.join().is_err() {
+				warn!(target: "sub-libp2p", "The known nodes writer panicked");
+			}
+		}
+	}
+}
+
+/// Write the received nodes until the channel is closed. Only the last received nodes are
+/// written if several are pending.
+fn write_peers(aux_store: &dyn NetworkAuxStore, receiver: mpsc::Receiver<Vec<StoredPeer>>) {
+	while let Ok(mut stored) = receiver.recv() {
+		while let Ok(newer) = receiver.try_recv() {
+			stored = newer;
+		}
+		if let Err(e) = aux_store.insert_aux(PEER_STORE_KEY, &stored.encode()) {
+			warn!(target: "sub-libp2p", "Failed to store the known nodes: {}", e);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use parking_lot::Mutex;
+
+	#[derive(Default)]
+	struct MemAuxStore(Mutex<HashMap<Vec<u8>, Vec<u8>>>);
+
+	impl NetworkAuxStore for MemAuxStore {
+		fn get_aux(&self, key: &[u8]) -> sp_blockchain::Result<Option<Vec<u8>>> {
+			Ok(self.0.lock().get(key).cloned())
+		}
+
+		fn insert_aux(&self, key: &[u8], value: &[u8]) -> sp_blockchain::Result<()> {
+			self.0.lock().insert(key.to_vec(), value.to_vec());
+			Ok(())
+		}
+	}
+
+	#[test]
+	fn known_peers_are_stored_and_aged_out() {
+		let aux_store = Arc::new(MemAuxStore::default());
+		let peer_id = PeerId::random();
+		let old_peer_id = PeerId::random();
+		let address: Multiaddr = "/ip4/198.51.100.19/tcp/30333".parse().unwrap();
+
+		let mut store = PeerStore::load(aux_store.clone());
+		assert_eq!(store.peers().count(), 0);
+
+		// Nodes without any address are not worth storing.
+		store.update(PeerId::random(), Vec::new(), 10);
+		store.update(peer_id, vec![address.clone()], 10);
+		store.update(old_peer_id, vec![address.clone()], 20);
+		store.peers.get_mut(&old_peer_id).unwrap().last_seen -= MAX_AGE.as_secs() + 1;
+		// The addresses are kept when none is known anymore.
+		store.update(peer_id, Vec::new(), 30);
+		store.store();
+		// The nodes are written by the time the store is dropped.
+		drop(store);
+
+		let store = PeerStore::load(aux_store);
+		let peers = store.peers().collect::<Vec<_>>();
+		assert_eq!(peers.len(), 1);
+		let (stored_peer_id, peer, _) = peers[0];
+		assert_eq!(*stored_peer_id, peer_id);
+		assert_eq!(peer.addresses, vec![address]);
+		assert_eq!(peer.reputation, 30);
+	}
+}
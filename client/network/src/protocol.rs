@@ -45,6 +45,7 @@ use std::{
 	iter,
 	pin::Pin,
 	task::Poll,
+	time::Duration,
 };
 
 use message::{generic::Message as GenericMessage, Message};
@@ -60,6 +61,9 @@ pub mod message;
 // Must be equal to `max(MAX_BLOCK_ANNOUNCE_SIZE, MAX_TRANSACTIONS_SIZE)`.
 pub(crate) const BLOCK_ANNOUNCES_TRANSACTIONS_SUBSTREAM_SIZE: u64 = 16 * 1024 * 1024;
 
+/// File in the network configuration directory in which the banned peers are stored.
+const BAN_LIST_FILE: &str = "banned_peers.json";
+
 /// Identifier of the peerset for the block announces protocol.
 const HARDCODED_PEERSETS_SYNC: sc_peerset::SetId = sc_peerset::SetId::from(0);
 /// Number of hardcoded peersets (the constants right above). Any set whose identifier is equal or
@@ -154,7 +158,14 @@ impl<B: BlockT> Protocol<B> {
 				});
 			}
 
-			sc_peerset::Peerset::from_config(sc_peerset::PeersetConfig { sets })
+			sc_peerset::Peerset::from_config(sc_peerset::PeersetConfig {
+				sets,
+				scoring: network_config.peer_scoring.clone(),
+				ban_list_path: network_config
+					.net_config_path
+					.as_ref()
+					.map(|path| path.join(BAN_LIST_FILE)),
+			})
 		};
 
 		let behaviour = {
@@ -221,6 +232,18 @@ impl<B: BlockT> Protocol<B> {
 		self.behaviour.peerset_debug_info()
 	}
 
+	/// Returns all the nodes known to the peerset manager, with their reputation.
+	pub fn peer_reputations(&mut self) -> Vec<(PeerId, i32)> {
+		self.behaviour.peer_reputations()
+	}
+
+	/// Adds a node known from a previous run to the default set, with the reputation it had
+	/// `elapsed` ago.
+	pub fn restore_known_peer(&mut self, peer_id: PeerId, reputation: i32, elapsed: Duration) {
+		self.behaviour
+			.restore_peer(HARDCODED_PEERSETS_SYNC, peer_id, reputation, elapsed)
+	}
+
 	/// Returns the number of peers we're connected to.
 	pub fn num_connected_peers(&self) -> usize {
 		self.peers.len()
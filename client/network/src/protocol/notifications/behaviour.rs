@@ -545,6 +545,24 @@ impl Notifications {
 		self.peerset.debug_info()
 	}
 
+	/// Returns all the nodes known to the peerset manager, with their reputation.
+	pub fn peer_reputations(&mut self) -> Vec<(PeerId, i32)> {
+		self.peerset.peer_reputations()
+	}
+
+	/// Adds a node known from a previous run to the given set.
+	///
+	/// See [`sc_peerset::Peerset::restore_peer`].
+	pub fn restore_peer(
+		&mut self,
+		set_id: sc_peerset::SetId,
+		peer_id: PeerId,
+		reputation: i32,
+		elapsed: Duration,
+	) {
+		self.peerset.restore_peer(set_id, peer_id, reputation, elapsed)
+	}
+
 	/// Function that is called when the peerset wants us to connect to a peer.
 	fn peerset_report_connect(&mut self, peer_id: PeerId, set_id: sc_peerset::SetId) {
 		// If `PeerId` is unknown to us, insert an entry, start dialing, and return early.
@@ -2119,7 +2137,11 @@ mod tests {
 				reserved_only: false,
 			});
 
-			sc_peerset::Peerset::from_config(sc_peerset::PeersetConfig { sets })
+			sc_peerset::Peerset::from_config(sc_peerset::PeersetConfig {
+				sets,
+				scoring: Arc::new(sc_peerset::DefaultPeerScoring),
+				ban_list_path: None,
+			})
 		};
 
 		(
@@ -33,6 +33,7 @@ use libp2p::{
 use std::{
 	iter,
 	pin::Pin,
+	sync::Arc,
 	task::{Context, Poll},
 	time::Duration,
 };
@@ -79,6 +80,8 @@ fn build_nodes() -> (Swarm<CustomProtoWithAddr>, Swarm<CustomProtoWithAddr>) {
 				reserved_nodes: Default::default(),
 				reserved_only: false,
 			}],
+			scoring: Arc::new(sc_peerset::DefaultPeerScoring),
+			ban_list_path: None,
 		});
 
 		let behaviour = CustomProtoWithAddr {
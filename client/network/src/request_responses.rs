@@ -54,7 +54,7 @@ use libp2p::{
 	},
 };
 
-use sc_peerset::{PeersetHandle, BANNED_THRESHOLD};
+use sc_peerset::PeersetHandle;
 
 use std::{
 	collections::{hash_map::Entry, HashMap},
@@ -284,7 +284,7 @@ pub struct RequestResponsesBehaviour {
 	/// when the request has been sent out.
 	send_feedback: HashMap<ProtocolRequestId, oneshot::Sender<()>>,
 
-	/// Primarily used to get a reputation of a node.
+	/// Primarily used to check whether a node is banned.
 	peerset: PeersetHandle,
 
 	/// Pending message request, holds `MessageRequest` as a Future state to poll it
@@ -293,7 +293,7 @@ pub struct RequestResponsesBehaviour {
 }
 
 // This is a state of processing incoming request Message.
-// The main reason of this struct is to hold `get_peer_reputation` as a Future state.
+// The main reason of this struct is to hold `is_peer_banned` as a Future state.
 struct MessageRequest {
 	peer: PeerId,
 	request_id: RequestId,
@@ -302,8 +302,8 @@ struct MessageRequest {
 	protocol: ProtocolName,
 	resp_builder: Option<futures::channel::mpsc::Sender<IncomingRequest>>,
 	// Once we get incoming request we save all params, create an async call to Peerset
-	// to get the reputation of the peer.
-	get_peer_reputation: Pin<Box<dyn Future<Output = Result<i32, ()>> + Send>>,
+	// to check whether the peer is banned.
+	is_peer_banned: Pin<Box<dyn Future<Output = Result<bool, ()>> + Send>>,
 }
 
 /// Generated by the response builder and waiting to be processed.
@@ -570,7 +570,7 @@ impl NetworkBehaviour for RequestResponsesBehaviour {
 	) -> Poll<NetworkBehaviourAction<Self::OutEvent, Self::ConnectionHandler>> {
 		'poll_all: loop {
 			if let Some(message_request) = self.message_request.take() {
-				// Now we can can poll `MessageRequest` until we get the reputation
+				// Now we can can poll `MessageRequest` until we know whether the peer is banned
 
 				let MessageRequest {
 					peer,
@@ -579,11 +579,11 @@ impl NetworkBehaviour for RequestResponsesBehaviour {
 					channel,
 					protocol,
 					resp_builder,
-					mut get_peer_reputation,
+					mut is_peer_banned,
 				} = message_request;
 
-				let reputation = Future::poll(Pin::new(&mut get_peer_reputation), cx);
-				match reputation {
+				let banned = Future::poll(Pin::new(&mut is_peer_banned), cx);
+				match banned {
 					Poll::Pending => {
 						// Save the state to poll it again next time.
 
@@ -594,23 +594,23 @@ impl NetworkBehaviour for RequestResponsesBehaviour {
 							channel,
 							protocol,
 							resp_builder,
-							get_peer_reputation,
+							is_peer_banned,
 						});
 						return Poll::Pending
 					},
-					Poll::Ready(reputation) => {
-						// Once we get the reputation we can continue processing the request.
+					Poll::Ready(banned) => {
+						// Once we know the peer isn't banned we can continue processing the
+						// request.
 
-						let reputation = reputation.expect(
+						let banned = banned.expect(
 							"The channel can only be closed if the peerset no longer exists; qed",
 						);
 
-						if reputation < BANNED_THRESHOLD {
+						if banned {
 							log::debug!(
 								target: "sub-libp2p",
-								"Cannot handle requests from a node with a low reputation {}: {}",
+								"Cannot handle requests from a banned node {}",
 								peer,
-								reputation,
 							);
 							continue 'poll_all
 						}
@@ -743,12 +743,11 @@ impl NetworkBehaviour for RequestResponsesBehaviour {
 							self.pending_responses_arrival_time
 								.insert((protocol.clone(), request_id).into(), Instant::now());
 
-							let get_peer_reputation = self.peerset.clone().peer_reputation(peer);
-							let get_peer_reputation = Box::pin(get_peer_reputation);
+							let is_peer_banned = Box::pin(self.peerset.clone().is_banned(peer));
 
-							// Save the Future-like state with params to poll `get_peer_reputation`
-							// and to continue processing the request once we get the reputation of
-							// the peer.
+							// Save the Future-like state with params to poll `is_peer_banned`
+							// and to continue processing the request once we know whether the
+							// peer is banned.
 							self.message_request = Some(MessageRequest {
 								peer,
 								request_id,
@@ -756,7 +755,7 @@ impl NetworkBehaviour for RequestResponsesBehaviour {
 								channel,
 								protocol: protocol.clone(),
 								resp_builder: resp_builder.clone(),
-								get_peer_reputation,
+								is_peer_banned,
 							});
 
 							// This `continue` makes sure that `message_request` gets polled
@@ -1057,8 +1056,8 @@ mod tests {
 		swarm::{Executor, Swarm, SwarmEvent},
 		Multiaddr,
 	};
-	use sc_peerset::{Peerset, PeersetConfig, SetConfig};
-	use std::{iter, time::Duration};
+	use sc_peerset::{DefaultPeerScoring, Peerset, PeersetConfig, SetConfig};
+	use std::{iter, sync::Arc, time::Duration};
 
 	struct TokioExecutor(tokio::runtime::Runtime);
 	impl Executor for TokioExecutor {
@@ -1089,6 +1088,8 @@ mod tests {
 				reserved_nodes: Default::default(),
 				reserved_only: false,
 			}],
+			scoring: Arc::new(DefaultPeerScoring),
+			ban_list_path: None,
 		};
 
 		let (peerset, handle) = Peerset::from_config(config);
@@ -36,6 +36,7 @@ use crate::{
 	network_state::{
 		NetworkState, NotConnectedPeer as NetworkStateNotConnectedPeer, Peer as NetworkStatePeer,
 	},
+	peer_store::PeerStore,
 	protocol::{self, NotifsHandlerError, Protocol, Ready},
 	request_responses::{IfDisconnected, RequestFailure},
 	service::{
@@ -71,7 +72,7 @@ use metrics::{Histogram, HistogramVec, MetricSources, Metrics};
 use parking_lot::Mutex;
 
 use sc_network_common::ExHashT;
-use sc_peerset::PeersetHandle;
+use sc_peerset::{BannedPeer, PeersetHandle};
 use sc_utils::mpsc::{tracing_unbounded, TracingUnboundedReceiver, TracingUnboundedSender};
 use sp_runtime::traits::Block as BlockT;
 
@@ -431,6 +432,26 @@ where
 			);
 		}
 
+		// Seed the discovery and the peerset with the nodes known from the previous runs.
+		let peer_store = params.aux_store.clone().map(PeerStore::load);
+		if let Some(peer_store) = &peer_store {
+			for (peer_id, peer, elapsed) in peer_store.peers() {
+				for addr in &peer.addresses {
+					swarm.behaviour_mut().add_known_address(*peer_id, addr.clone());
+				}
+				swarm.behaviour_mut().user_protocol_mut().restore_known_peer(
+					*peer_id,
+					peer.reputation,
+					elapsed,
+				);
+			}
+			debug!(
+				target: "sub-libp2p",
+				"Restored {} known nodes",
+				peer_store.peers().count(),
+			);
+		}
+
 		let external_addresses = Arc::new(Mutex::new(Vec::new()));
 		let listen_addresses = Arc::new(Mutex::new(Vec::new()));
 		let peers_notifications_sinks = Arc::new(Mutex::new(HashMap::new()));
@@ -463,6 +484,7 @@ where
 			peers_notifications_sinks,
 			metrics,
 			boot_node_ids,
+			peer_store,
 			_marker: Default::default(),
 			_block: Default::default(),
 		})
@@ -657,6 +679,20 @@ impl<B: BlockT + 'static, H: ExHashT> NetworkService<B, H> {
 		rx.await.map_err(|_| ())
 	}
 
+	/// Get the list of banned peers, together with the reason they were banned for.
+	///
+	/// Returns an error if the peerset is no longer running.
+	pub async fn banned_peers(&self) -> Result<Vec<BannedPeer>, ()> {
+		self.peerset.clone().banned_peers().await
+	}
+
+	/// Lift the ban of the given peer.
+	///
+	/// The peer is forgiven by the scoring policy and can be connected to again.
+	pub fn unban_peer(&self, peer_id: PeerId) {
+		self.peerset.unban_peer(peer_id);
+	}
+
 	/// Utility function to extract `PeerId` from each `Multiaddr` for peer set updates.
 	///
 	/// Returns an `Err` if one of the given addresses is invalid or contains an
@@ -1175,6 +1211,9 @@ where
 	metrics: Option<Metrics>,
 	/// The `PeerId`'s of all boot nodes.
 	boot_node_ids: Arc<HashSet<PeerId>>,
+	/// Nodes known to the network, stored across restarts. `None` if there is no network
+	/// configuration directory.
+	peer_store: Option<PeerStore>,
 	/// For each peer and protocol combination, an object that allows sending notifications to
 	/// that peer. Shared with the [`NetworkService`].
 	peers_notifications_sinks: Arc<Mutex<HashMap<(PeerId, ProtocolName), NotificationsSink>>>,
@@ -1256,9 +1295,28 @@ where
 			);
 		}
 
+		if self.peer_store.as_ref().map_or(false, |peer_store| peer_store.should_store()) {
+			self.store_known_peers();
+		}
+
 		true
 	}
 
+	/// Record the nodes known to the peerset, along with their addresses, in the peer store.
+	fn store_known_peers(&mut self) {
+		let peer_store = match self.peer_store.as_mut() {
+			Some(peer_store) => peer_store,
+			None => return,
+		};
+
+		let behaviour = self.network_service.behaviour_mut();
+		for (peer_id, reputation) in behaviour.user_protocol_mut().peer_reputations() {
+			let addresses = NetworkBehaviour::addresses_of_peer(behaviour, &peer_id);
+			peer_store.update(peer_id, addresses, reputation);
+		}
+		peer_store.store();
+	}
+
 	/// Process the next message coming from the `NetworkService`.
 	fn handle_worker_message(&mut self, msg: ServiceToWorkerMsg) {
 		match msg {
@@ -1762,6 +1820,17 @@ where
 {
 }
 
+impl<B, H> Drop for NetworkWorker<B, H>
+where
+	B: BlockT + 'static,
+	H: ExHashT,
+{
+	fn drop(&mut self) {
+		// The nodes seen since the last periodic write are stored when the worker shuts down.
+		self.store_known_peers();
+	}
+}
+
 fn ensure_addresses_consistent_with_transport<'a>(
 	addresses: impl Iterator<Item = &'a Multiaddr>,
 	transport: &TransportConfig,
@@ -33,7 +33,7 @@ use prometheus_endpoint::{
 	register, Gauge, GaugeVec, MetricSource, Opts, PrometheusError, Registry, SourcedGauge, U64,
 };
 
-use sc_client_api::{BlockBackend, HeaderBackend, ProofProvider};
+use sc_client_api::{AuxStore, BlockBackend, HeaderBackend, ProofProvider};
 use sc_consensus::import_queue::ImportQueueService;
 use sc_network::{
 	config::{
@@ -252,6 +252,7 @@ where
 		+ BlockBackend<B>
 		+ HeaderMetadata<B, Error = sp_blockchain::Error>
 		+ ProofProvider<B>
+		+ AuxStore
 		+ Send
 		+ Sync
 		+ 'static,
@@ -342,6 +343,8 @@ where
 			block_announce_validator,
 			max_parallel_downloads,
 			max_blocks_per_request,
+			network_config.gap_sync_mode,
+			network_config.max_parallel_gap_downloads,
 			warp_sync_params,
 			metrics_registry,
 			network_service.clone(),
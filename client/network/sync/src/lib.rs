@@ -45,13 +45,14 @@ use log::{debug, error, info, trace, warn};
 use prost::Message;
 
 use prometheus_endpoint::{register, Counter, PrometheusError, Registry, U64};
-use sc_client_api::{BlockBackend, ProofProvider};
+use sc_client_api::{AuxStore, BlockBackend, ProofProvider};
 use sc_consensus::{
 	import_queue::ImportQueueService, BlockImportError, BlockImportStatus, IncomingBlock,
 };
 use sc_network::{
 	config::{
-		NonDefaultSetConfig, NonReservedPeerMode, NotificationHandshake, ProtocolId, SetConfig,
+		GapSyncMode, NonDefaultSetConfig, NonReservedPeerMode, NotificationHandshake, ProtocolId,
+		SetConfig,
 	},
 	request_responses::{IfDisconnected, RequestFailure},
 	types::ProtocolName,
@@ -64,10 +65,10 @@ use sc_network_common::{
 			BlockResponse, Direction, FromBlock,
 		},
 		warp::{EncodedProof, WarpProofRequest, WarpSyncParams, WarpSyncPhase, WarpSyncProgress},
-		BadPeer, ChainSync as ChainSyncT, ImportResult, Metrics, OnBlockData, OnBlockJustification,
-		OnStateData, OpaqueBlockRequest, OpaqueBlockResponse, OpaqueStateRequest,
-		OpaqueStateResponse, PeerInfo, PeerRequest, PollBlockAnnounceValidation, SyncMode,
-		SyncState, SyncStatus,
+		BadPeer, ChainSync as ChainSyncT, GapSyncProgress, ImportResult, Metrics, OnBlockData,
+		OnBlockJustification, OnStateData, OpaqueBlockRequest, OpaqueBlockResponse,
+		OpaqueStateRequest, OpaqueStateResponse, PeerInfo, PeerRequest,
+		PollBlockAnnounceValidation, SyncMode, SyncState, SyncStatus,
 	},
 };
 use sp_arithmetic::traits::Saturating;
@@ -144,6 +145,10 @@ const MIN_PEERS_TO_START_WARP_SYNC: usize = 3;
 /// Maximum allowed size for a block announce.
 const MAX_BLOCK_ANNOUNCE_SIZE: u64 = 1024 * 1024;
 
+/// Number of consecutive refused state requests after which the state of the state sync target is
+/// assumed to be pruned by peers, and a newer target is picked.
+const MAX_STATE_REQUEST_REFUSALS: usize = 3;
+
 /// Maximum blocks per response.
 pub(crate) const MAX_BLOCKS_IN_RESPONSE: usize = 128;
 
@@ -267,6 +272,7 @@ impl SyncingMetrics {
 
 struct GapSync<B: BlockT> {
 	blocks: BlockCollection<B>,
+	start: NumberFor<B>,
 	best_queued_number: NumberFor<B>,
 	target: NumberFor<B>,
 }
@@ -326,13 +332,20 @@ pub struct ChainSync<B: BlockT, Client> {
 	warp_sync: Option<WarpSync<B, Client>>,
 	/// Warp sync params.
 	///
-	/// Will be `None` after `self.warp_sync` is `Some(_)`.
+	/// Will be `None` after `self.warp_sync` is `Some(_)`, unless there is a warp sync provider
+	/// to restart the warp sync with.
 	warp_sync_params: Option<WarpSyncParams<B>>,
+	/// Number of consecutive refused state requests for the current state sync target.
+	state_request_refusals: usize,
 	/// Enable importing existing blocks. This is used used after the state download to
 	/// catch up to the latest state while re-importing blocks.
 	import_existing: bool,
 	/// Gap download process.
 	gap_sync: Option<GapSync<B>>,
+	/// How the block history gap is downloaded.
+	gap_sync_mode: GapSyncMode,
+	/// Maximum number of peers to download the block history gap from in parallel.
+	max_parallel_gap_downloads: Option<u32>,
 	/// Handle for communicating with `NetworkService`
 	network_service: service::network::NetworkServiceHandle,
 	/// Protocol name used for block announcements
@@ -476,6 +489,7 @@ where
 		+ BlockBackend<B>
 		+ HeaderMetadata<B, Error = sp_blockchain::Error>
 		+ ProofProvider<B>
+		+ AuxStore
 		+ Send
 		+ Sync
 		+ 'static,
@@ -529,6 +543,11 @@ where
 			queued_blocks: self.queue_blocks.len() as u32,
 			state_sync: self.state_sync.as_ref().map(|s| s.progress()),
 			warp_sync: warp_sync_progress,
+			gap_sync: self.gap_sync.as_ref().map(|gap_sync| GapSyncProgress {
+				start: gap_sync.start,
+				best_queued: gap_sync.best_queued_number,
+				target: gap_sync.target,
+			}),
 		}
 	}
 
@@ -643,6 +662,10 @@ where
 					{
 						log::debug!(target: "sync", "Starting warp state sync.");
 						if let Some(params) = self.warp_sync_params.take() {
+							if let WarpSyncParams::WithProvider(provider) = &params {
+								self.warp_sync_params =
+									Some(WarpSyncParams::WithProvider(provider.clone()));
+							}
 							self.warp_sync = Some(WarpSync::new(self.client.clone(), params));
 						}
 					}
@@ -1237,6 +1260,7 @@ where
 		if let Some(gap_sync) = &mut self.gap_sync {
 			gap_sync.blocks.clear_peer_download(who)
 		}
+		self.clear_state_download(who);
 		self.peers.remove(who);
 		self.pending_responses.remove(who);
 		self.extra_justifications.peer_disconnected(who);
@@ -1393,6 +1417,7 @@ where
 		+ BlockBackend<B>
 		+ HeaderMetadata<B, Error = sp_blockchain::Error>
 		+ ProofProvider<B>
+		+ AuxStore
 		+ Send
 		+ Sync
 		+ 'static,
@@ -1407,6 +1432,8 @@ where
 		block_announce_validator: Box<dyn BlockAnnounceValidator<B> + Send>,
 		max_parallel_downloads: u32,
 		max_blocks_per_request: u32,
+		gap_sync_mode: GapSyncMode,
+		max_parallel_gap_downloads: Option<u32>,
 		warp_sync_params: Option<WarpSyncParams<B>>,
 		metrics_registry: Option<&Registry>,
 		network_service: service::network::NetworkServiceHandle,
@@ -1449,10 +1476,13 @@ where
 			warp_sync: None,
 			import_existing: false,
 			gap_sync: None,
+			gap_sync_mode,
+			max_parallel_gap_downloads,
 			network_service,
 			block_request_protocol_name,
 			state_request_protocol_name,
 			warp_sync_params,
+			state_request_refusals: 0,
 			warp_sync_protocol_name,
 			block_announce_protocol_name: block_announce_config
 				.notifications_protocol
@@ -1474,9 +1504,76 @@ where
 		};
 
 		sync.reset_sync_start_point()?;
+		sync.resume_state_sync();
 		Ok((sync, block_announce_config))
 	}
 
+	/// Resume the state download persisted by a previous run, if the state is still synced.
+	fn resume_state_sync(&mut self) {
+		match self.mode {
+			SyncMode::LightState { .. } => {
+				self.state_sync = StateSync::resume(self.client.clone());
+			},
+			SyncMode::Warp => {
+				self.warp_sync = StateSync::resume(self.client.clone())
+					.map(|state_sync| WarpSync::resume(self.client.clone(), state_sync));
+			},
+			SyncMode::Full | SyncMode::Light => state::clear_persisted(&*self.client),
+		}
+	}
+
+	/// Clear the state range requested from the given peer, if any.
+	fn clear_state_download(&mut self, who: &PeerId) {
+		if let Some(sync) = &mut self.state_sync {
+			sync.clear_peer_download(who);
+		}
+		if let Some(sync) = &mut self.warp_sync {
+			sync.clear_state_download(who);
+		}
+	}
+
+	/// Peers refuse state requests for blocks they have pruned the state of. Once enough of them
+	/// did, the state sync is restarted for a newer target. The downloaded child tries are kept.
+	fn on_state_request_refused(&mut self) {
+		self.state_request_refusals += 1;
+		if self.state_request_refusals < MAX_STATE_REQUEST_REFUSALS {
+			return
+		}
+		self.state_request_refusals = 0;
+
+		if let Some(sync) = self.state_sync.take() {
+			// A new state sync is started for the next finalized block, once the headers are
+			// synced up to it.
+			info!(
+				target: "sync",
+				"State of #{} is no longer available, switching to a newer state sync target.",
+				sync.target_block_num(),
+			);
+			self.allowed_requests.set_all();
+		} else if let Some(number) = self
+			.warp_sync
+			.as_ref()
+			.and_then(|sync| sync.target_block_hash().and(sync.target_block_number()))
+		{
+			match self.warp_sync_params.take() {
+				Some(params) => {
+					info!(
+						target: "sync",
+						"State of #{} is no longer available, restarting warp sync.",
+						number,
+					);
+					self.warp_sync = Some(WarpSync::new(self.client.clone(), params));
+					self.allowed_requests.set_all();
+				},
+				None => debug!(
+					target: "sync",
+					"State of warp sync target #{} is no longer available, can't restart warp sync.",
+					number,
+				),
+			}
+		}
+	}
+
 	/// Returns the median seen block number.
 	fn median_seen(&self) -> Option<NumberFor<B>> {
 		let mut best_seens = self.peers.values().map(|p| p.best_number).collect::<Vec<_>>();
@@ -1505,6 +1602,17 @@ where
 		}
 	}
 
+	/// Block attributes requested for the block history gap, given the attributes of the block
+	/// requests at the tip of the chain.
+	fn gap_block_attributes(&self, attrs: BlockAttributes) -> BlockAttributes {
+		match self.gap_sync_mode {
+			GapSyncMode::Full | GapSyncMode::Skip => attrs,
+			GapSyncMode::HeadersAndJustifications =>
+				BlockAttributes::HEADER | BlockAttributes::JUSTIFICATION,
+			GapSyncMode::Headers => BlockAttributes::HEADER,
+		}
+	}
+
 	fn skip_execution(&self) -> bool {
 		match self.mode {
 			SyncMode::Full => false,
@@ -1813,6 +1921,7 @@ where
 
 			// since the request is not a justification, remove it from pending responses
 			self.pending_responses.remove(&id);
+			self.clear_state_download(&id);
 
 			// handle peers that were in other states.
 			match self.new_peer(id, p.best_hash, p.best_number) {
@@ -1861,18 +1970,30 @@ where
 			}
 		}
 
-		if let Some((start, end)) = info.block_gap {
-			debug!(target: "sync", "Starting gap sync #{} - #{}", start, end);
-			self.gap_sync = Some(GapSync {
-				best_queued_number: start - One::one(),
-				target: end,
-				blocks: BlockCollection::new(),
-			});
-		}
+		self.start_gap_sync(info.block_gap);
 		trace!(target: "sync", "Restarted sync at #{} ({:?})", self.best_queued_number, self.best_queued_hash);
 		Ok(())
 	}
 
+	/// Start downloading the block history gap, unless the gap is skipped.
+	fn start_gap_sync(&mut self, block_gap: Option<(NumberFor<B>, NumberFor<B>)>) {
+		match block_gap {
+			Some((start, end)) if self.gap_sync_mode == GapSyncMode::Skip => {
+				debug!(target: "sync", "Skipping gap sync #{} - #{}", start, end);
+			},
+			Some((start, end)) => {
+				debug!(target: "sync", "Starting gap sync #{} - #{}", start, end);
+				self.gap_sync = Some(GapSync {
+					start,
+					best_queued_number: start - One::one(),
+					target: end,
+					blocks: BlockCollection::new(),
+				});
+			},
+			None => {},
+		}
+	}
+
 	/// What is the status of the block corresponding to the given hash?
 	fn block_status(&self, hash: &B::Hash) -> Result<BlockStatus, ClientError> {
 		if self.queue_blocks.contains(hash) {
@@ -2165,7 +2286,7 @@ where
 			self.send_block_request(id, request);
 		}
 
-		if let Some((id, request)) = self.state_request() {
+		for (id, request) in self.state_requests() {
 			self.send_state_request(id, request);
 		}
 
@@ -2263,6 +2384,9 @@ where
 							self.network_service.report_peer(id, rep::REFUSED);
 							self.network_service
 								.disconnect_peer(id, self.block_announce_protocol_name.clone());
+							if let PeerRequest::State = request {
+								self.on_state_request_refused();
+							}
 						},
 						RequestFailure::Network(OutboundFailure::ConnectionClosed) |
 						RequestFailure::NotConnected => {
@@ -2375,6 +2499,15 @@ where
 		}
 		let is_major_syncing = self.status().state.is_major_syncing();
 		let attrs = self.required_block_attributes();
+		let gap_attrs = self.gap_block_attributes(attrs);
+		let mut gap_downloads_left = self.max_parallel_gap_downloads.map(|max| {
+			let downloading = self
+				.peers
+				.values()
+				.filter(|peer| matches!(peer.state, PeerSyncState::DownloadingGap(_)))
+				.count();
+			(max as usize).saturating_sub(downloading)
+		});
 		let blocks = &mut self.blocks;
 		let fork_targets = &mut self.fork_targets;
 		let last_finalized =
@@ -2456,17 +2589,21 @@ where
 					trace!(target: "sync", "Downloading fork {:?} from {}", hash, id);
 					peer.state = PeerSyncState::DownloadingStale(hash);
 					Some((id, req))
-				} else if let Some((range, req)) = gap_sync.as_mut().and_then(|sync| {
-					peer_gap_block_request(
-						&id,
-						peer,
-						&mut sync.blocks,
-						attrs,
-						sync.target,
-						sync.best_queued_number,
-						max_blocks_per_request,
-					)
-				}) {
+				} else if let Some((range, req)) =
+					gap_sync.as_mut().filter(|_| gap_downloads_left != Some(0)).and_then(|sync| {
+						peer_gap_block_request(
+							&id,
+							peer,
+							&mut sync.blocks,
+							gap_attrs,
+							sync.target,
+							sync.best_queued_number,
+							max_blocks_per_request,
+						)
+					}) {
+					if let Some(left) = &mut gap_downloads_left {
+						*left -= 1;
+					}
 					peer.state = PeerSyncState::DownloadingGap(range.start);
 					trace!(
 						target: "sync",
@@ -2485,49 +2622,45 @@ where
 		// Box::new(iter)
 	}
 
-	fn state_request(&mut self) -> Option<(PeerId, OpaqueStateRequest)> {
+	fn state_requests(&mut self) -> Vec<(PeerId, OpaqueStateRequest)> {
+		let mut requests = Vec::new();
 		if self.allowed_requests.is_empty() {
-			return None
-		}
-		if (self.state_sync.is_some() || self.warp_sync.is_some()) &&
-			self.peers.iter().any(|(_, peer)| peer.state == PeerSyncState::DownloadingState)
-		{
-			// Only one pending state request is allowed.
-			return None
+			return requests
 		}
-		if let Some(sync) = &self.state_sync {
+		// Disjoint ranges of the state are requested from all the available peers.
+		if let Some(sync) = &mut self.state_sync {
 			if sync.is_complete() {
-				return None
+				return requests
 			}
 
 			for (id, peer) in self.peers.iter_mut() {
 				if peer.state.is_available() && peer.common_number >= sync.target_block_num() {
-					peer.state = PeerSyncState::DownloadingState;
-					let request = sync.next_request();
+					let Some(request) = sync.next_request(*id) else { break };
 					trace!(target: "sync", "New StateRequest for {}: {:?}", id, request);
-					self.allowed_requests.clear();
-					return Some((*id, OpaqueStateRequest(Box::new(request))))
+					peer.state = PeerSyncState::DownloadingState;
+					requests.push((*id, OpaqueStateRequest(Box::new(request))));
 				}
 			}
 		}
-		if let Some(sync) = &self.warp_sync {
+		if let Some(sync) = &mut self.warp_sync {
 			if sync.is_complete() {
-				return None
+				return requests
 			}
-			if let (Some(request), Some(target)) =
-				(sync.next_state_request(), sync.target_block_number())
-			{
+			if let Some(target) = sync.target_block_number() {
 				for (id, peer) in self.peers.iter_mut() {
 					if peer.state.is_available() && peer.best_number >= target {
+						let Some(request) = sync.next_state_request(*id) else { break };
 						trace!(target: "sync", "New StateRequest for {}: {:?}", id, request);
 						peer.state = PeerSyncState::DownloadingState;
-						self.allowed_requests.clear();
-						return Some((*id, OpaqueStateRequest(Box::new(request))))
+						requests.push((*id, OpaqueStateRequest(Box::new(request))));
 					}
 				}
 			}
 		}
-		None
+		if !requests.is_empty() {
+			self.allowed_requests.clear();
+		}
+		requests
 	}
 
 	fn warp_sync_request(&mut self) -> Option<(PeerId, WarpProofRequest<B>)> {
@@ -2589,7 +2722,7 @@ where
 				response.entries.len(),
 				response.proof.len(),
 			);
-			sync.import(*response)
+			sync.import(*who, *response)
 		} else if let Some(sync) = &mut self.warp_sync {
 			debug!(
 				target: "sync",
@@ -2598,7 +2731,7 @@ where
 				response.entries.len(),
 				response.proof.len(),
 			);
-			sync.import_state(*response)
+			sync.import_state(*who, *response)
 		} else {
 			debug!(target: "sync", "Ignored obsolete state response from {}", who);
 			return Err(BadPeer(*who, rep::NOT_REQUESTED))
@@ -2622,7 +2755,10 @@ where
 				debug!(target: "sync", "State download is complete. Import is queued");
 				Ok(OnStateData::Import(origin, block))
 			},
-			state::ImportResult::Continue => Ok(OnStateData::Continue),
+			state::ImportResult::Continue => {
+				self.state_request_refusals = 0;
+				Ok(OnStateData::Continue)
+			},
 			state::ImportResult::BadResponse => {
 				debug!(target: "sync", "Bad state data received from {}", who);
 				Err(BadPeer(*who, rep::BAD_BLOCK))
@@ -2752,6 +2888,7 @@ where
 						);
 						self.state_sync = None;
 						self.mode = SyncMode::Full;
+						state::clear_persisted(&*self.client);
 						output.extend(self.restart());
 					}
 					let warp_sync_complete = self
@@ -2765,7 +2902,9 @@ where
 							self.warp_sync.as_ref().map_or(0, |s| s.progress().total_bytes / (1024 * 1024)),
 						);
 						self.warp_sync = None;
+						self.warp_sync_params = None;
 						self.mode = SyncMode::Full;
+						state::clear_persisted(&*self.client);
 						output.extend(self.restart());
 					}
 					let gap_sync_complete =
@@ -2820,6 +2959,8 @@ where
 					warn!(target: "sync", "💔 Error importing block {hash:?}: {}", e.unwrap_err());
 					self.state_sync = None;
 					self.warp_sync = None;
+					// The downloaded state may be invalid, don't resume it.
+					state::clear_persisted(&*self.client);
 					output.extend(self.restart());
 				},
 				Err(BlockImportError::Cancelled) => {},
@@ -3221,6 +3362,8 @@ mod test {
 			block_announce_validator,
 			1,
 			64,
+			GapSyncMode::Full,
+			None,
 			None,
 			None,
 			chain_sync_network_handle,
@@ -3288,6 +3431,8 @@ mod test {
 			Box::new(DefaultBlockAnnounceValidator),
 			1,
 			64,
+			GapSyncMode::Full,
+			None,
 			None,
 			None,
 			chain_sync_network_handle,
@@ -3470,6 +3615,8 @@ mod test {
 			Box::new(DefaultBlockAnnounceValidator),
 			5,
 			64,
+			GapSyncMode::Full,
+			None,
 			None,
 			None,
 			chain_sync_network_handle,
@@ -3597,6 +3744,8 @@ mod test {
 			Box::new(DefaultBlockAnnounceValidator),
 			5,
 			64,
+			GapSyncMode::Full,
+			None,
 			None,
 			None,
 			chain_sync_network_handle,
@@ -3755,6 +3904,8 @@ mod test {
 			Box::new(DefaultBlockAnnounceValidator),
 			5,
 			64,
+			GapSyncMode::Full,
+			None,
 			None,
 			None,
 			chain_sync_network_handle,
@@ -3898,6 +4049,8 @@ mod test {
 			Box::new(DefaultBlockAnnounceValidator),
 			5,
 			64,
+			GapSyncMode::Full,
+			None,
 			None,
 			None,
 			chain_sync_network_handle,
@@ -4043,6 +4196,8 @@ mod test {
 			Box::new(DefaultBlockAnnounceValidator),
 			1,
 			64,
+			GapSyncMode::Full,
+			None,
 			None,
 			None,
 			chain_sync_network_handle,
@@ -4089,6 +4244,8 @@ mod test {
 			Box::new(DefaultBlockAnnounceValidator),
 			1,
 			64,
+			GapSyncMode::Full,
+			None,
 			None,
 			None,
 			chain_sync_network_handle,
@@ -4143,6 +4300,8 @@ mod test {
 			block_announce_validator,
 			1,
 			64,
+			GapSyncMode::Full,
+			None,
 			None,
 			None,
 			chain_sync_network_handle,
@@ -4211,4 +4370,118 @@ mod test {
 		sync.peer_disconnected(&peers[1]);
 		assert_eq!(sync.pending_responses.len(), 0);
 	}
+
+	/// Create a chain of 10 blocks and a sync whose block history gap is blocks 1 to 8, with two
+	/// blocks per request.
+	fn gap_sync_setup(
+		gap_sync_mode: GapSyncMode,
+		max_parallel_gap_downloads: Option<u32>,
+	) -> (ChainSync<Block, TestClient>, Vec<Block>, NetworkServiceProvider) {
+		let mut client = Arc::new(TestClientBuilder::new().build());
+		let blocks = (0..10).map(|_| build_block(&mut client, None, false)).collect::<Vec<_>>();
+		let import_queue = Box::new(sc_consensus::import_queue::mock::MockImportQueueHandle::new());
+		let (chain_sync_network_provider, chain_sync_network_handle) =
+			NetworkServiceProvider::new();
+
+		let (mut sync, _) = ChainSync::new(
+			SyncMode::Full,
+			client.clone(),
+			ProtocolId::from("test-protocol-name"),
+			&Some(String::from("test-fork-id")),
+			Roles::from(&Role::Full),
+			Box::new(DefaultBlockAnnounceValidator),
+			1,
+			2,
+			gap_sync_mode,
+			max_parallel_gap_downloads,
+			None,
+			None,
+			chain_sync_network_handle,
+			import_queue,
+			ProtocolName::from("block-request"),
+			ProtocolName::from("state-request"),
+			None,
+		)
+		.unwrap();
+		sync.start_gap_sync(Some((1, 8)));
+		(sync, blocks, chain_sync_network_provider)
+	}
+
+	#[test]
+	fn gap_sync_requests_the_configured_block_attributes() {
+		for (gap_sync_mode, fields) in [
+			(
+				GapSyncMode::Full,
+				BlockAttributes::HEADER | BlockAttributes::BODY | BlockAttributes::JUSTIFICATION,
+			),
+			(
+				GapSyncMode::HeadersAndJustifications,
+				BlockAttributes::HEADER | BlockAttributes::JUSTIFICATION,
+			),
+			(GapSyncMode::Headers, BlockAttributes::HEADER),
+		] {
+			let (mut sync, blocks, _provider) = gap_sync_setup(gap_sync_mode, None);
+			let best = blocks.last().unwrap();
+			let peer_id = PeerId::random();
+			sync.new_peer(peer_id, best.hash(), *best.header().number()).unwrap();
+
+			let requests = sync.block_requests();
+			assert_eq!(requests.len(), 1);
+			let (who, request) = &requests[0];
+			assert_eq!(*who, peer_id);
+			assert_eq!(request.fields, fields);
+			assert_eq!(request.from, FromBlock::Number(2));
+			assert_eq!(request.direction, Direction::Descending);
+		}
+	}
+
+	#[test]
+	fn skipped_gap_is_not_downloaded() {
+		let (mut sync, blocks, _provider) = gap_sync_setup(GapSyncMode::Skip, None);
+		assert!(sync.gap_sync.is_none());
+		assert_eq!(sync.status().gap_sync, None);
+
+		let best = blocks.last().unwrap();
+		sync.new_peer(PeerId::random(), best.hash(), *best.header().number()).unwrap();
+		assert!(sync.block_requests().is_empty());
+	}
+
+	#[test]
+	fn gap_downloads_are_capped_and_report_progress() {
+		let (mut sync, blocks, _provider) = gap_sync_setup(GapSyncMode::Headers, Some(2));
+		assert_eq!(
+			sync.status().gap_sync,
+			Some(GapSyncProgress { start: 1, best_queued: 0, target: 8 }),
+		);
+
+		let best = blocks.last().unwrap();
+		let peers = (0..3).map(|_| PeerId::random()).collect::<Vec<_>>();
+		for peer_id in &peers {
+			sync.new_peer(*peer_id, best.hash(), *best.header().number()).unwrap();
+		}
+
+		// Only two of the three peers download the gap.
+		let requests = sync.block_requests();
+		assert_eq!(requests.len(), 2);
+		let (who, request) = requests
+			.into_iter()
+			.find(|(_, request)| request.from == FromBlock::Number(2))
+			.unwrap();
+
+		// Blocks 1 and 2 are queued for import.
+		let response = create_block_response(vec![blocks[1].clone(), blocks[0].clone()]);
+		match sync.on_block_data(&who, Some(request), response) {
+			Ok(OnBlockData::Import(_, blocks)) => assert_eq!(blocks.len(), 2),
+			_ => panic!("Gap blocks should be imported"),
+		}
+		assert_eq!(
+			sync.status().gap_sync,
+			Some(GapSyncProgress { start: 1, best_queued: 2, target: 8 }),
+		);
+
+		// The peer that completed its download leaves room for a single new download.
+		let requests = sync.block_requests();
+		assert_eq!(requests.len(), 1);
+		assert!(peers.contains(&requests[0].0));
+	}
 }
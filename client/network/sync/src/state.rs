@@ -17,20 +17,227 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 //! State sync support.
+//!
+//! The keys of the top trie are split into disjoint ranges, which are downloaded from different
+//! peers at the same time. Each response is verified against the cursor of its range, and the key
+//! values of the ranges are merged in key order once all of them are complete. A range is split
+//! when a peer is free and the remaining part of a range is large compared to the key space covered
+//! by its last response.
+//!
+//! The downloaded state is persisted in the aux store of the client as it is imported, so that an
+//! interrupted download can be resumed with [`StateSync::resume`]. When the download moves to
+//! another target block, the child tries downloaded for the previous target are kept.
+//!
+//! Persisting the download about doubles the disk writes of a state sync: the state is written to
+//! the aux store while it is downloaded, then to the state database when it is imported. The
+//! responses are buffered and written in chunks of about [`PERSIST_CHUNK_SIZE`] bytes, so that an
+//! interrupted download loses at most the buffered responses, which are downloaded again.
 
 use crate::schema::v1::{StateEntry, StateRequest, StateResponse};
 use codec::{Decode, Encode};
-use log::debug;
-use sc_client_api::{CompactProof, ProofProvider};
+use libp2p::PeerId;
+use log::{debug, warn};
+use sc_client_api::{AuxStore, CompactProof, ProofProvider};
 use sc_consensus::ImportedState;
 use sc_network_common::sync::StateDownloadProgress;
 use smallvec::SmallVec;
-use sp_core::storage::well_known_keys;
+use sp_core::{hexdisplay::HexDisplay, storage::well_known_keys, U512};
 use sp_runtime::{
 	traits::{Block as BlockT, Header, NumberFor},
 	Justifications,
 };
-use std::{collections::HashMap, sync::Arc};
+use std::{
+	collections::{hash_map::Entry, HashMap, HashSet},
+	sync::Arc,
+};
+
+/// Aux key of the target of the persisted state sync.
+const TARGET_KEY: &[u8] = b"state_sync_target";
+/// Aux key of the download position of the persisted state sync.
+const CURSOR_KEY: &[u8] = b"state_sync_cursor";
+/// Aux key prefix of the persisted state chunks, followed by the chunk index. Each chunk holds the
+/// state added by several responses.
+const CHUNK_PREFIX: &[u8] = b"state_sync_chunk";
+/// Size of the state written to the aux store at once.
+const PERSIST_CHUNK_SIZE: usize = 4 * 1024 * 1024;
+
+/// Maximum number of key ranges of the state that are downloaded at the same time.
+const MAX_PARALLEL_RANGES: usize = 8;
+/// A range split off from another one is at most `2^MAX_SPLIT_SHIFT` responses long.
+const MAX_SPLIT_SHIFT: usize = 3;
+/// Number of leading key bytes used to estimate the position of a key in the key space.
+const POSITION_BYTES: usize = 64;
+
+/// Downloaded key values and child trie storage keys, by trie root.
+type StateMap = HashMap<Vec<u8>, (Vec<(Vec<u8>, Vec<u8>)>, Vec<Vec<u8>>)>;
+
+/// Key values of a response, by trie root.
+type KeyValueLevels = Vec<(Vec<u8>, Vec<(Vec<u8>, Vec<u8>)>)>;
+
+/// Target block of the persisted state sync.
+#[derive(Encode, Decode)]
+struct PersistedTarget<H, E> {
+	header: H,
+	body: Option<Vec<E>>,
+	justifications: Option<Justifications>,
+	skip_proof: bool,
+}
+
+/// Download position of a persisted key range.
+#[derive(Encode, Decode)]
+struct PersistedRange {
+	start: Option<Vec<u8>>,
+	end: Option<Vec<u8>>,
+	last_key: Vec<Vec<u8>>,
+	complete: bool,
+}
+
+/// Download position of the persisted state sync.
+#[derive(Encode, Decode)]
+struct PersistedCursor {
+	ranges: Vec<PersistedRange>,
+	/// Roots of the child tries that are completely downloaded.
+	complete_children: Vec<Vec<u8>>,
+	imported_bytes: u64,
+	chunks: u32,
+}
+
+/// State added by a single response.
+#[derive(Encode, Decode, Default)]
+struct StateChunk {
+	/// Index of the key range of the response.
+	range: u32,
+	/// Child trie roots, along with the storage key of the child trie.
+	child_roots: Vec<(Vec<u8>, Vec<u8>)>,
+	/// Key values, along with the root of their trie. The root of the top trie is empty.
+	key_values: KeyValueLevels,
+}
+
+/// State sync persisted by a previous run.
+struct Persisted<B: BlockT> {
+	target: PersistedTarget<B::Header, B::Extrinsic>,
+	cursor: PersistedCursor,
+	chunks: Vec<StateChunk>,
+}
+
+/// A range of the keys of the top trie, downloaded independently of the other ranges.
+struct StateRange {
+	/// Key after which the range starts, `None` for the first range.
+	start: Option<Vec<u8>>,
+	/// Last key of the range, `None` for the last range.
+	end: Option<Vec<u8>>,
+	/// Download position in the range.
+	last_key: SmallVec<[Vec<u8>; 2]>,
+	/// Downloaded key values of the top trie.
+	key_values: Vec<(Vec<u8>, Vec<u8>)>,
+	complete: bool,
+	/// Key space covered by the last response for the range.
+	span: Option<U512>,
+}
+
+impl StateRange {
+	fn new(start: Option<Vec<u8>>, end: Option<Vec<u8>>) -> Self {
+		Self {
+			last_key: start.iter().cloned().collect(),
+			start,
+			end,
+			key_values: Vec::new(),
+			complete: false,
+			span: None,
+		}
+	}
+
+	fn start_position(&self) -> U512 {
+		self.start.as_ref().map_or(U512::zero(), |start| key_position(start))
+	}
+
+	fn end_position(&self) -> U512 {
+		self.end.as_ref().map_or(U512::MAX, |end| key_position(end))
+	}
+
+	fn cursor_position(&self) -> U512 {
+		self.last_key.first().map_or(self.start_position(), |key| key_position(key))
+	}
+
+	/// Whether the top trie cursor is at the end of the range.
+	fn is_at_end(&self) -> bool {
+		match (self.last_key.as_slice(), &self.end) {
+			([key], Some(end)) => key >= end,
+			_ => false,
+		}
+	}
+}
+
+/// Position of a key in the key space, from its leading bytes.
+fn key_position(key: &[u8]) -> U512 {
+	let mut position = [0u8; POSITION_BYTES];
+	let len = key.len().min(POSITION_BYTES);
+	position[..len].copy_from_slice(&key[..len]);
+	U512::from_big_endian(&position)
+}
+
+/// Shortest key at the given position of the key space.
+fn position_key(position: U512) -> Vec<u8> {
+	let mut key = [0u8; POSITION_BYTES];
+	position.to_big_endian(&mut key);
+	let len = key.iter().rposition(|byte| *byte != 0).map_or(0, |index| index + 1);
+	key[..len].to_vec()
+}
+
+fn chunk_key(index: u32) -> Vec<u8> {
+	let mut key = CHUNK_PREFIX.to_vec();
+	key.extend(index.to_le_bytes());
+	key
+}
+
+fn read_aux<T: Decode, Client: AuxStore>(client: &Client, key: &[u8]) -> Option<T> {
+	match client.get_aux(key) {
+		Ok(Some(data)) => match T::decode(&mut &data[..]) {
+			Ok(value) => Some(value),
+			Err(e) => {
+				warn!(target: "sync", "Invalid persisted state sync data: {}", e);
+				None
+			},
+		},
+		Ok(None) => None,
+		Err(e) => {
+			warn!(target: "sync", "Failed to read persisted state sync data: {}", e);
+			None
+		},
+	}
+}
+
+fn load_persisted<B: BlockT, Client: AuxStore>(client: &Client) -> Option<Persisted<B>> {
+	let target = read_aux::<PersistedTarget<B::Header, B::Extrinsic>, _>(client, TARGET_KEY)?;
+	let cursor = read_aux::<PersistedCursor, _>(client, CURSOR_KEY)?;
+	if cursor.ranges.is_empty() {
+		return None
+	}
+	let chunks = (0..cursor.chunks)
+		.map(|index| read_aux::<Vec<StateChunk>, _>(client, &chunk_key(index)))
+		.collect::<Option<Vec<_>>>()?
+		.into_iter()
+		.flatten()
+		.collect();
+	Some(Persisted { target, cursor, chunks })
+}
+
+/// Remove the persisted state sync, if any.
+pub fn clear_persisted<Client: AuxStore>(client: &Client) {
+	if matches!(client.get_aux(CURSOR_KEY), Ok(None)) {
+		return
+	}
+	let chunks =
+		read_aux::<PersistedCursor, _>(client, CURSOR_KEY).map_or(0, |cursor| cursor.chunks);
+	let chunk_keys = (0..chunks).map(chunk_key).collect::<Vec<_>>();
+	let delete = [TARGET_KEY, CURSOR_KEY]
+		.into_iter()
+		.chain(chunk_keys.iter().map(|key| key.as_slice()))
+		.collect::<Vec<_>>();
+	if let Err(e) = client.insert_aux(&[], &delete) {
+		warn!(target: "sync", "Failed to remove persisted state sync data: {}", e);
+	}
+}
 
 /// State sync state machine. Accumulates partial state data until it
 /// is ready to be imported.
@@ -40,12 +247,27 @@ pub struct StateSync<B: BlockT, Client> {
 	target_root: B::Hash,
 	target_body: Option<Vec<B::Extrinsic>>,
 	target_justifications: Option<Justifications>,
-	last_key: SmallVec<[Vec<u8>; 2]>,
-	state: HashMap<Vec<u8>, (Vec<(Vec<u8>, Vec<u8>)>, Vec<Vec<u8>>)>,
+	/// Key ranges of the top trie, in the order they were created.
+	ranges: Vec<StateRange>,
+	/// Index of the range requested from each peer.
+	downloads: HashMap<PeerId, usize>,
+	/// Downloaded child tries.
+	state: StateMap,
+	/// Range that imports each child trie being downloaded. The same child trie may be part of
+	/// several ranges.
+	child_owners: HashMap<Vec<u8>, usize>,
+	/// Roots of the child tries that are completely downloaded, which are not imported again.
+	complete_children: HashSet<Vec<u8>>,
 	complete: bool,
 	client: Arc<Client>,
 	imported_bytes: u64,
 	skip_proof: bool,
+	/// Number of state chunks persisted for the target.
+	persisted_chunks: u32,
+	/// State added by the responses that are not persisted yet.
+	pending_chunks: Vec<StateChunk>,
+	/// Encoded size of the pending state.
+	pending_size: usize,
 }
 
 /// Import state chunk result.
@@ -61,15 +283,75 @@ pub enum ImportResult<B: BlockT> {
 impl<B, Client> StateSync<B, Client>
 where
 	B: BlockT,
-	Client: ProofProvider<B> + Send + Sync + 'static,
+	Client: ProofProvider<B> + AuxStore + Send + Sync + 'static,
 {
 	///  Create a new instance.
+	///
+	/// If the persisted state sync is for the same target, the download continues from where it
+	/// stopped. Otherwise the child tries it downloaded are kept, and its other state is dropped.
 	pub fn new(
 		client: Arc<Client>,
 		target_header: B::Header,
 		target_body: Option<Vec<B::Extrinsic>>,
 		target_justifications: Option<Justifications>,
 		skip_proof: bool,
+	) -> Self {
+		let mut sync = Self::with_target(
+			client,
+			target_header,
+			target_body,
+			target_justifications,
+			skip_proof,
+		);
+		let previous_chunks = match load_persisted::<B, _>(&*sync.client) {
+			Some(persisted)
+				if persisted.target.header.hash() == sync.target_block &&
+					persisted.target.skip_proof == skip_proof =>
+			{
+				debug!(target: "sync", "Continuing persisted state sync for {}", sync.target_block);
+				sync.restore(persisted);
+				return sync
+			},
+			Some(persisted) => {
+				let chunks = persisted.cursor.chunks;
+				// State downloaded without proofs is only kept if proofs are still skipped.
+				if !persisted.target.skip_proof || skip_proof {
+					sync.retain_child_tries(persisted);
+				}
+				chunks
+			},
+			None => 0,
+		};
+		sync.persist_target(previous_chunks);
+		sync
+	}
+
+	/// Resume the state sync persisted by a previous run, if any.
+	pub fn resume(client: Arc<Client>) -> Option<Self> {
+		let persisted = load_persisted::<B, _>(&*client)?;
+		let mut sync = Self::with_target(
+			client,
+			persisted.target.header.clone(),
+			persisted.target.body.clone(),
+			persisted.target.justifications.clone(),
+			persisted.target.skip_proof,
+		);
+		debug!(
+			target: "sync",
+			"Resuming state sync for #{} ({})",
+			sync.target_block_num(),
+			sync.target_block,
+		);
+		sync.restore(persisted);
+		Some(sync)
+	}
+
+	fn with_target(
+		client: Arc<Client>,
+		target_header: B::Header,
+		target_body: Option<Vec<B::Extrinsic>>,
+		target_justifications: Option<Justifications>,
+		skip_proof: bool,
 	) -> Self {
 		Self {
 			client,
@@ -78,147 +360,421 @@ where
 			target_header,
 			target_body,
 			target_justifications,
-			last_key: SmallVec::default(),
+			ranges: vec![StateRange::new(None, None)],
+			downloads: HashMap::default(),
 			state: HashMap::default(),
+			child_owners: HashMap::default(),
+			complete_children: HashSet::default(),
 			complete: false,
 			imported_bytes: 0,
 			skip_proof,
+			persisted_chunks: 0,
+			pending_chunks: Vec::new(),
+			pending_size: 0,
 		}
 	}
 
-	///  Validate and import a state response.
-	pub fn import(&mut self, response: StateResponse) -> ImportResult<B> {
-		if response.entries.is_empty() && response.proof.is_empty() {
-			debug!(target: "sync", "Bad state response");
-			return ImportResult::BadResponse
+	fn restore(&mut self, persisted: Persisted<B>) {
+		self.ranges = persisted
+			.cursor
+			.ranges
+			.into_iter()
+			.map(|range| StateRange {
+				last_key: range.last_key.into(),
+				complete: range.complete,
+				..StateRange::new(range.start, range.end)
+			})
+			.collect();
+		self.complete_children = persisted.cursor.complete_children.into_iter().collect();
+		self.imported_bytes = persisted.cursor.imported_bytes;
+		self.persisted_chunks = persisted.cursor.chunks;
+		for chunk in persisted.chunks {
+			let index = chunk.range as usize;
+			for (root, storage_key) in chunk.child_roots {
+				self.state.entry(root).or_default().1.push(storage_key);
+			}
+			for (root, key_values) in chunk.key_values {
+				if root.is_empty() {
+					if let Some(range) = self.ranges.get_mut(index) {
+						range.key_values.extend(key_values);
+					}
+					continue
+				}
+				if !self.complete_children.contains(&root) {
+					self.child_owners.insert(root.clone(), index);
+				}
+				self.state.entry(root).or_default().0.extend(key_values);
+			}
 		}
-		if !self.skip_proof && response.proof.is_empty() {
-			debug!(target: "sync", "Missing proof");
-			return ImportResult::BadResponse
+	}
+
+	/// Keep the complete child tries of a state sync for another target. Child tries are
+	/// identified by their root, so they don't need to be downloaded again for this target.
+	fn retain_child_tries(&mut self, persisted: Persisted<B>) {
+		let complete = persisted.cursor.complete_children.into_iter().collect::<HashSet<_>>();
+		for (root, key_values) in persisted.chunks.into_iter().flat_map(|chunk| chunk.key_values) {
+			if root.is_empty() || !complete.contains(&root) {
+				continue
+			}
+			for (key, _value) in key_values.iter() {
+				self.imported_bytes += key.len() as u64;
+			}
+			self.state.entry(root.clone()).or_default().0.extend(key_values);
+			self.complete_children.insert(root);
 		}
-		let complete = if !self.skip_proof {
-			debug!(target: "sync", "Importing state from {} trie nodes", response.proof.len());
-			let proof_size = response.proof.len() as u64;
-			let proof = match CompactProof::decode(&mut response.proof.as_ref()) {
-				Ok(proof) => proof,
-				Err(e) => {
-					debug!(target: "sync", "Error decoding proof: {:?}", e);
-					return ImportResult::BadResponse
-				},
-			};
-			let (values, completed) = match self.client.verify_range_proof(
-				self.target_root,
-				proof,
-				self.last_key.as_slice(),
-			) {
+		debug!(
+			target: "sync",
+			"Kept {} previously downloaded child tries",
+			self.complete_children.len(),
+		);
+	}
+
+	fn persisted_cursor(&self, chunks: u32) -> PersistedCursor {
+		PersistedCursor {
+			ranges: self
+				.ranges
+				.iter()
+				.map(|range| PersistedRange {
+					start: range.start.clone(),
+					end: range.end.clone(),
+					last_key: range.last_key.to_vec(),
+					complete: range.complete,
+				})
+				.collect(),
+			complete_children: self.complete_children.iter().cloned().collect(),
+			imported_bytes: self.imported_bytes,
+			chunks,
+		}
+	}
+
+	/// Split the retained child tries in chunks of about [`PERSIST_CHUNK_SIZE`] bytes.
+	fn retained_chunks(&self) -> Vec<StateChunk> {
+		let mut chunks = Vec::new();
+		let mut chunk = StateChunk::default();
+		let mut size = 0;
+		for (root, (key_values, _)) in self.state.iter() {
+			for (key, value) in key_values {
+				if size >= PERSIST_CHUNK_SIZE {
+					chunks.push(std::mem::take(&mut chunk));
+					size = 0;
+				}
+				match chunk.key_values.last_mut() {
+					Some((last_root, key_values)) if last_root == root =>
+						key_values.push((key.clone(), value.clone())),
+					_ => chunk.key_values.push((root.clone(), vec![(key.clone(), value.clone())])),
+				}
+				size += key.len() + value.len();
+			}
+		}
+		if !chunk.key_values.is_empty() {
+			chunks.push(chunk);
+		}
+		chunks
+	}
+
+	/// Persist the target, along with the retained child tries. Chunks of the previous target that
+	/// are not overwritten are removed.
+	///
+	/// The retained child tries are written one chunk at a time. The previous target is removed
+	/// first, so that an interrupted write doesn't leave it along with the chunks of this target.
+	fn persist_target(&mut self, previous_chunks: u32) {
+		let target = PersistedTarget {
+			header: self.target_header.clone(),
+			body: self.target_body.clone(),
+			justifications: self.target_justifications.clone(),
+			skip_proof: self.skip_proof,
+		};
+		let retained = self.retained_chunks();
+		let chunks = retained.len() as u32;
+
+		// The cursor keeps track of all the written chunks, so that they can be removed.
+		let cursor = self.persisted_cursor(chunks.max(previous_chunks)).encode();
+		if let Err(e) = self.client.insert_aux(&[(CURSOR_KEY, cursor.as_slice())], &[TARGET_KEY]) {
+			warn!(target: "sync", "Failed to persist state sync target: {}", e);
+			return
+		}
+		for (index, chunk) in retained.into_iter().enumerate() {
+			let (key, chunk) = (chunk_key(index as u32), vec![chunk].encode());
+			if let Err(e) = self.client.insert_aux(&[(key.as_slice(), chunk.as_slice())], &[]) {
+				warn!(target: "sync", "Failed to persist state sync target: {}", e);
+				return
+			}
+		}
+
+		let (target, cursor) = (target.encode(), self.persisted_cursor(chunks).encode());
+		let stale = (chunks..previous_chunks).map(chunk_key).collect::<Vec<_>>();
+		let delete = stale.iter().map(|key| key.as_slice()).collect::<Vec<_>>();
+		match self.client.insert_aux(
+			&[(TARGET_KEY, target.as_slice()), (CURSOR_KEY, cursor.as_slice())],
+			&delete,
+		) {
+			Ok(()) => self.persisted_chunks = chunks,
+			Err(e) => warn!(target: "sync", "Failed to persist state sync target: {}", e),
+		}
+	}
+
+	/// Buffer the state added by a response. Once the buffered state reaches
+	/// [`PERSIST_CHUNK_SIZE`] bytes, it is persisted along with the new download position.
+	fn persist_chunk(&mut self, chunk: StateChunk) {
+		self.pending_size += chunk.encoded_size();
+		self.pending_chunks.push(chunk);
+		if self.pending_size < PERSIST_CHUNK_SIZE {
+			return
+		}
+
+		let cursor = self.persisted_cursor(self.persisted_chunks + 1);
+		let key = chunk_key(self.persisted_chunks);
+		let (chunk, cursor) = (self.pending_chunks.encode(), cursor.encode());
+		match self
+			.client
+			.insert_aux(&[(key.as_slice(), chunk.as_slice()), (CURSOR_KEY, cursor.as_slice())], &[])
+		{
+			Ok(()) => {
+				self.persisted_chunks += 1;
+				self.pending_chunks.clear();
+				self.pending_size = 0;
+			},
+			// The state stays buffered, it is written along with the next response.
+			Err(e) => warn!(target: "sync", "Failed to persist state sync data: {}", e),
+		}
+	}
+
+	/// Root of the child trie with the given storage key.
+	fn child_root(&self, storage_key: &[u8]) -> Option<Vec<u8>> {
+		self.state
+			.iter()
+			.find(|(_, (_, storage_keys))| storage_keys.iter().any(|key| key == storage_key))
+			.map(|(root, _)| root.clone())
+	}
+
+	/// Verify the proof of a response, starting at `last_key`. Returns the key values of the
+	/// response and the download position after it, which is `None` at the end of the state.
+	fn verify_response(
+		&mut self,
+		last_key: &SmallVec<[Vec<u8>; 2]>,
+		response: StateResponse,
+	) -> Option<(KeyValueLevels, Option<SmallVec<[Vec<u8>; 2]>>)> {
+		debug!(target: "sync", "Importing state from {} trie nodes", response.proof.len());
+		let proof_size = response.proof.len() as u64;
+		let proof = match CompactProof::decode(&mut response.proof.as_ref()) {
+			Ok(proof) => proof,
+			Err(e) => {
+				debug!(target: "sync", "Error decoding proof: {:?}", e);
+				return None
+			},
+		};
+		let (values, completed) =
+			match self.client.verify_range_proof(self.target_root, proof, last_key.as_slice()) {
 				Err(e) => {
 					debug!(
 						target: "sync",
 						"StateResponse failed proof verification: {}",
 						e,
 					);
-					return ImportResult::BadResponse
+					return None
 				},
 				Ok(values) => values,
 			};
-			debug!(target: "sync", "Imported with {} keys", values.len());
+		debug!(target: "sync", "Imported with {} keys", values.len());
 
-			let complete = completed == 0;
-			if !complete && !values.update_last_key(completed, &mut self.last_key) {
+		let next_key = (completed != 0).then(|| {
+			let mut next_key = last_key.clone();
+			if !values.update_last_key(completed, &mut next_key) {
 				debug!(target: "sync", "Error updating key cursor, depth: {}", completed);
-			};
+			}
+			next_key
+		});
+		self.imported_bytes += proof_size;
+		Some((
+			values
+				.0
+				.into_iter()
+				.map(|values| (values.state_root, values.key_values))
+				.collect(),
+			next_key,
+		))
+	}
 
-			for values in values.0 {
-				let key_values = if values.state_root.is_empty() {
-					// Read child trie roots.
-					values
-						.key_values
-						.into_iter()
-						.filter(|key_value| {
-							if well_known_keys::is_child_storage_key(key_value.0.as_slice()) {
-								self.state
-									.entry(key_value.1.clone())
-									.or_default()
-									.1
-									.push(key_value.0.clone());
-								false
-							} else {
-								true
-							}
-						})
-						.collect()
-				} else {
-					values.key_values
-				};
-				let mut entry = self.state.entry(values.state_root).or_default();
-				if entry.0.len() > 0 && entry.1.len() > 1 {
-					// Already imported child_trie with same root.
-					// Warning this will not work with parallel download.
-				} else if entry.0.is_empty() {
-					for (key, _value) in key_values.iter() {
-						self.imported_bytes += key.len() as u64;
-					}
+	/// Read the entries of a response without proof, starting at `last_key`. Returns the key
+	/// values of the response and the download position after it, which is `None` at the end of
+	/// the state.
+	fn read_entries(
+		last_key: &SmallVec<[Vec<u8>; 2]>,
+		response: StateResponse,
+	) -> (KeyValueLevels, Option<SmallVec<[Vec<u8>; 2]>>) {
+		let mut next_key = last_key.clone();
+		let mut complete = true;
+		// if the trie is a child trie and one of its parent trie is empty,
+		// the parent cursor stays valid.
+		// Empty parent trie content only happens when all the response content
+		// is part of a single child trie.
+		if next_key.len() == 2 && response.entries.first().map_or(false, |e| e.entries.is_empty()) {
+			// Do not remove the parent trie position.
+			next_key.pop();
+		} else {
+			next_key.clear();
+		}
+		let mut levels = Vec::new();
+		for state in response.entries {
+			debug!(
+				target: "sync",
+				"Importing state from {:?} to {:?}",
+				state.entries.last().map(|e| HexDisplay::from(&e.key)),
+				state.entries.first().map(|e| HexDisplay::from(&e.key)),
+			);
 
-					entry.0 = key_values;
-				} else {
-					for (key, value) in key_values {
-						self.imported_bytes += key.len() as u64;
-						entry.0.push((key, value))
-					}
+			if !state.complete {
+				if let Some(e) = state.entries.last() {
+					next_key.push(e.key.clone());
 				}
+				complete = false;
+			}
+			let key_values = state
+				.entries
+				.into_iter()
+				.map(|StateEntry { key, value }| (key, value))
+				.collect();
+			levels.push((state.state_root, key_values));
+		}
+		(levels, (!complete).then_some(next_key))
+	}
+
+	///  Validate and import a state response from the given peer.
+	pub fn import(&mut self, who: PeerId, response: StateResponse) -> ImportResult<B> {
+		let index = match self.downloads.remove(&who) {
+			Some(index) => index,
+			None => {
+				debug!(target: "sync", "Unexpected state response from {}", who);
+				return ImportResult::BadResponse
+			},
+		};
+		if response.entries.is_empty() && response.proof.is_empty() {
+			debug!(target: "sync", "Bad state response");
+			return ImportResult::BadResponse
+		}
+		if !self.skip_proof && response.proof.is_empty() {
+			debug!(target: "sync", "Missing proof");
+			return ImportResult::BadResponse
+		}
+		let last_key = self.ranges[index].last_key.clone();
+		let (mut levels, next_key) = if !self.skip_proof {
+			match self.verify_response(&last_key, response) {
+				Some(verified) => verified,
+				None => return ImportResult::BadResponse,
 			}
-			self.imported_bytes += proof_size;
-			complete
 		} else {
-			let mut complete = true;
-			// if the trie is a child trie and one of its parent trie is empty,
-			// the parent cursor stays valid.
-			// Empty parent trie content only happens when all the response content
-			// is part of a single child trie.
-			if self.last_key.len() == 2 && response.entries[0].entries.is_empty() {
-				// Do not remove the parent trie position.
-				self.last_key.pop();
-			} else {
-				self.last_key.clear();
+			Self::read_entries(&last_key, response)
+		};
+		if levels.first().map_or(true, |(root, _)| !root.is_empty()) {
+			levels.insert(0, (Vec::new(), Vec::new()));
+		}
+		let (_, mut top_key_values) = levels.remove(0);
+
+		// The response may go past the end of the range, which is downloaded by another peer.
+		let range = &self.ranges[index];
+		let past_end = match &range.end {
+			Some(end) => match top_key_values.iter().position(|(key, _)| key > end) {
+				Some(position) => {
+					top_key_values.truncate(position);
+					true
+				},
+				None => false,
+			},
+			None => false,
+		};
+
+		// Child tries of this range: the one the response started in and those of its top keys.
+		let mut child_roots = HashSet::new();
+		if let [storage_key, _] = last_key.as_slice() {
+			child_roots.extend(self.child_root(storage_key));
+		}
+		let previous_position = range.cursor_position();
+
+		let range = &mut self.ranges[index];
+		match next_key {
+			Some(next_key) if !past_end => {
+				range.last_key = next_key;
+				range.complete = range.is_at_end();
+			},
+			_ => range.complete = true,
+		}
+		if !range.complete {
+			let position = range.cursor_position();
+			if position > previous_position {
+				range.span = Some(position - previous_position);
 			}
-			for state in response.entries {
-				debug!(
-					target: "sync",
-					"Importing state from {:?} to {:?}",
-					state.entries.last().map(|e| sp_core::hexdisplay::HexDisplay::from(&e.key)),
-					state.entries.first().map(|e| sp_core::hexdisplay::HexDisplay::from(&e.key)),
-				);
+		}
 
-				if !state.complete {
-					if let Some(e) = state.entries.last() {
-						self.last_key.push(e.key.clone());
-					}
-					complete = false;
-				}
-				let is_top = state.state_root.is_empty();
-				let entry = self.state.entry(state.state_root).or_default();
-				if entry.0.len() > 0 && entry.1.len() > 1 {
-					// Already imported child trie with same root.
-				} else {
-					let mut child_roots = Vec::new();
-					for StateEntry { key, value } in state.entries {
-						// Skip all child key root (will be recalculated on import).
-						if is_top && well_known_keys::is_child_storage_key(key.as_slice()) {
-							child_roots.push((value, key));
-						} else {
-							self.imported_bytes += key.len() as u64;
-							entry.0.push((key, value))
-						}
-					}
-					for (root, storage_key) in child_roots {
-						self.state.entry(root).or_default().1.push(storage_key);
-					}
-				}
+		let mut chunk = StateChunk { range: index as u32, ..Default::default() };
+		let mut key_values = Vec::new();
+		for (key, value) in top_key_values {
+			// Skip all child key root (will be recalculated on import).
+			if well_known_keys::is_child_storage_key(key.as_slice()) {
+				self.state.entry(value.clone()).or_default().1.push(key.clone());
+				child_roots.insert(value.clone());
+				chunk.child_roots.push((value, key));
+			} else {
+				self.imported_bytes += key.len() as u64;
+				key_values.push((key, value));
 			}
-			complete
+		}
+		self.ranges[index].key_values.extend(key_values.iter().cloned());
+		chunk.key_values.push((Vec::new(), key_values));
+
+		// A complete child trie, possibly kept from a previous target, is not downloaded again:
+		// the download continues after its storage key in the top trie.
+		let skip_child = match self.ranges[index].last_key.as_slice() {
+			[storage_key, _] if !self.ranges[index].complete => self
+				.child_root(storage_key)
+				.map_or(false, |root| self.complete_children.contains(&root)),
+			_ => false,
+		};
+		if skip_child {
+			let range = &mut self.ranges[index];
+			range.last_key.truncate(1);
+			range.complete = range.is_at_end();
+		}
+
+		let partial_child = match self.ranges[index].last_key.as_slice() {
+			[storage_key, _] if !self.ranges[index].complete => self.child_root(storage_key),
+			_ => None,
 		};
-		if complete {
+		for (root, key_values) in levels {
+			if !child_roots.contains(&root) || self.complete_children.contains(&root) {
+				continue
+			}
+			match self.child_owners.entry(root.clone()) {
+				// Already imported by another range.
+				Entry::Occupied(owner) if *owner.get() != index => continue,
+				Entry::Occupied(_) => (),
+				Entry::Vacant(owner) => {
+					owner.insert(index);
+				},
+			}
+			for (key, _value) in key_values.iter() {
+				self.imported_bytes += key.len() as u64;
+			}
+			self.state.entry(root.clone()).or_default().0.extend(key_values.iter().cloned());
+			if partial_child.as_ref() != Some(&root) {
+				self.child_owners.remove(&root);
+				self.complete_children.insert(root.clone());
+			}
+			chunk.key_values.push((root, key_values));
+		}
+
+		if self.ranges.iter().all(|range| range.complete) {
 			self.complete = true;
+			self.downloads.clear();
+			// Key values of the ranges, in key order.
+			self.ranges.sort_by(|a, b| a.start.cmp(&b.start));
+			let key_values = self
+				.ranges
+				.iter_mut()
+				.flat_map(|range| std::mem::take(&mut range.key_values))
+				.collect();
+			// Child tries kept from a previous target that are not part of this state.
+			self.state.retain(|_, (_, storage_keys)| !storage_keys.is_empty());
+			self.state.insert(Vec::new(), (key_values, Vec::new()));
 			ImportResult::Import(
 				self.target_block,
 				self.target_header.clone(),
@@ -230,17 +786,66 @@ where
 				self.target_justifications.clone(),
 			)
 		} else {
+			self.persist_chunk(chunk);
 			ImportResult::Continue
 		}
 	}
 
-	/// Produce next state request.
-	pub fn next_request(&self) -> StateRequest {
-		StateRequest {
+	/// Produce the next state request for the given peer, if there is a range left to request.
+	pub fn next_request(&mut self, who: PeerId) -> Option<StateRequest> {
+		if self.complete || self.downloads.contains_key(&who) {
+			return None
+		}
+		let index = match (0..self.ranges.len()).find(|index| {
+			!self.ranges[*index].complete && !self.downloads.values().any(|range| range == index)
+		}) {
+			Some(index) => index,
+			None => self.split_range()?,
+		};
+		self.downloads.insert(who, index);
+		Some(StateRequest {
 			block: self.target_block.encode(),
-			start: self.last_key.clone().into_vec(),
+			start: self.ranges[index].last_key.clone().into_vec(),
 			no_proof: self.skip_proof,
+		})
+	}
+
+	/// Split the range with the most remaining responses, relative to the key space covered by
+	/// its last response. Returns the index of the new range.
+	fn split_range(&mut self) -> Option<usize> {
+		if self.ranges.iter().filter(|range| !range.complete).count() >= MAX_PARALLEL_RANGES {
+			return None
 		}
+		let (index, shift) = self
+			.ranges
+			.iter()
+			.enumerate()
+			.filter(|(_, range)| !range.complete)
+			.filter_map(|(index, range)| {
+				let remaining = range.end_position().checked_sub(range.cursor_position())?;
+				// Approximate number of responses left, as a power of two.
+				let shift = remaining.bits().checked_sub(range.span?.bits())?;
+				(shift >= 2).then_some((index, shift))
+			})
+			.max_by_key(|(_, shift)| *shift)?;
+
+		// The split is at most half of the remaining range, so it is before the end of the range.
+		let range = &mut self.ranges[index];
+		let span = range.span.expect("only ranges with a span are split; qed");
+		let split_key =
+			position_key(range.cursor_position() + (span << (shift - 1).min(MAX_SPLIT_SHIFT)));
+		debug!(target: "sync", "Splitting state range at {}", HexDisplay::from(&split_key));
+		let end = range.end.replace(split_key.clone());
+		let mut split = StateRange::new(Some(split_key), end);
+		split.span = Some(span);
+		self.ranges.push(split);
+		Some(self.ranges.len() - 1)
+	}
+
+	/// Clear the range requested from the given peer, so that it can be requested from another
+	/// peer.
+	pub fn clear_peer_download(&mut self, who: &PeerId) {
+		self.downloads.remove(who);
 	}
 
 	/// Check if the state is complete.
@@ -260,8 +865,273 @@ where
 
 	/// Returns state sync estimated progress.
 	pub fn progress(&self) -> StateDownloadProgress {
-		let cursor = *self.last_key.get(0).and_then(|last| last.get(0)).unwrap_or(&0u8);
-		let percent_done = cursor as u32 * 100 / 256;
+		// Share of the key space downloaded, from the leading 32 bits of the key positions.
+		let done = self.ranges.iter().fold(0u64, |done, range| {
+			let position =
+				if range.complete { range.end_position() } else { range.cursor_position() };
+			let downloaded = position.saturating_sub(range.start_position());
+			done + (downloaded >> (POSITION_BYTES * 8 - 32)).low_u64()
+		});
+		let percent_done = ((done * 100) >> 32) as u32;
 		StateDownloadProgress { percentage: percent_done, size: self.imported_bytes }
 	}
 }
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::schema::v1::KeyValueStateEntry;
+	use futures::executor::block_on;
+	use sc_block_builder::BlockBuilderProvider;
+	use sc_consensus::{
+		BlockImport, BlockImportParams, ForkChoiceStrategy, StateAction, StorageChanges,
+	};
+	use sp_blockchain::HeaderBackend;
+	use sp_consensus::BlockOrigin;
+	use sp_core::storage::ChildInfo;
+	use substrate_test_runtime_client::{
+		runtime::Block, ClientBlockImportExt, DefaultTestClientBuilderExt, TestClient,
+		TestClientBuilder, TestClientBuilderExt,
+	};
+
+	fn entry(key: &[u8], value: &[u8]) -> StateEntry {
+		StateEntry { key: key.to_vec(), value: value.to_vec() }
+	}
+
+	fn level(state_root: &[u8], entries: Vec<StateEntry>, complete: bool) -> KeyValueStateEntry {
+		KeyValueStateEntry { state_root: state_root.to_vec(), entries, complete }
+	}
+
+	#[test]
+	fn persisted_state_sync_is_resumed() {
+		let client = Arc::new(TestClientBuilder::new().build());
+		let target = client.header(client.info().genesis_hash).unwrap().unwrap();
+		let peer = PeerId::random();
+		let mut sync = StateSync::<Block, _>::new(client.clone(), target.clone(), None, None, true);
+		assert!(sync.next_request(peer).is_some());
+		let large_value = vec![0; PERSIST_CHUNK_SIZE];
+		let response = StateResponse {
+			entries: vec![level(b"", vec![entry(b"a", b"1"), entry(b"b", &large_value)], false)],
+			proof: Vec::new(),
+		};
+		assert!(matches!(sync.import(peer, response), ImportResult::Continue));
+		let request = sync.next_request(peer);
+		let size = sync.progress().size;
+		let key_values = sync.ranges[0].key_values.clone();
+
+		// Smaller responses are buffered, and downloaded again after a restart.
+		let response = StateResponse {
+			entries: vec![level(b"", vec![entry(b"c", b"3")], false)],
+			proof: Vec::new(),
+		};
+		assert!(matches!(sync.import(peer, response), ImportResult::Continue));
+
+		let mut resumed = StateSync::<Block, _>::resume(client.clone()).unwrap();
+		assert_eq!(resumed.target(), target.hash());
+		assert_eq!(resumed.next_request(peer), request);
+		assert_eq!(resumed.progress().size, size);
+		assert_eq!(resumed.ranges.len(), 1);
+		assert_eq!(resumed.ranges[0].key_values, key_values);
+		assert_eq!(resumed.state, HashMap::new());
+
+		clear_persisted(&*client);
+		assert!(StateSync::<Block, _>::resume(client).is_none());
+	}
+
+	#[test]
+	fn complete_child_tries_are_kept_for_a_new_target() {
+		let client = Arc::new(TestClientBuilder::new().build());
+		let genesis = client.header(client.info().genesis_hash).unwrap().unwrap();
+		let complete = ChildInfo::new_default(b"complete").prefixed_storage_key().into_inner();
+		let partial = ChildInfo::new_default(b"partial").prefixed_storage_key().into_inner();
+
+		let peer = PeerId::random();
+		let mut sync =
+			StateSync::<Block, _>::new(client.clone(), genesis.clone(), None, None, true);
+		assert!(sync.next_request(peer).is_some());
+		let response = StateResponse {
+			entries: vec![
+				level(
+					b"",
+					vec![entry(b"a", b"1"), entry(&complete, b"root1"), entry(&partial, b"root2")],
+					false,
+				),
+				level(b"root1", vec![entry(b"c", b"3")], true),
+				level(b"root2", vec![entry(b"d", b"4")], false),
+			],
+			proof: Vec::new(),
+		};
+		assert!(matches!(sync.import(peer, response), ImportResult::Continue));
+
+		// The target is pruned, the download continues for a newer block.
+		let mut target = genesis;
+		target.number = 1;
+		let mut sync = StateSync::<Block, _>::new(client.clone(), target.clone(), None, None, true);
+		assert!(sync.next_request(peer).unwrap().start.is_empty());
+		assert_eq!(sync.state.len(), 1);
+		assert_eq!(
+			sync.state.get(&b"root1"[..]),
+			Some(&(vec![(b"c".to_vec(), b"3".to_vec())], Vec::new())),
+		);
+
+		// The kept child trie is not downloaded again.
+		let response = StateResponse {
+			entries: vec![
+				level(b"", vec![entry(&complete, b"root1")], false),
+				level(b"root1", vec![entry(b"c", b"3")], false),
+			],
+			proof: Vec::new(),
+		};
+		assert!(matches!(sync.import(peer, response), ImportResult::Continue));
+		assert_eq!(sync.next_request(peer).unwrap().start, vec![complete.clone()]);
+
+		let response = StateResponse {
+			entries: vec![level(b"", vec![entry(b"a", b"1")], true)],
+			proof: Vec::new(),
+		};
+		match sync.import(peer, response) {
+			ImportResult::Import(hash, _, state, _, _) => {
+				assert_eq!(hash, target.hash());
+				let mut levels = state.state.0;
+				levels.sort_by(|a, b| a.state_root.cmp(&b.state_root));
+				assert_eq!(levels.len(), 2);
+				assert_eq!(levels[0].key_values, vec![(b"a".to_vec(), b"1".to_vec())]);
+				assert_eq!(levels[1].state_root, b"root1".to_vec());
+				assert_eq!(levels[1].key_values, vec![(b"c".to_vec(), b"3".to_vec())]);
+				assert_eq!(levels[1].parent_storage_keys, vec![complete]);
+			},
+			_ => panic!("State download should be complete"),
+		}
+	}
+
+	#[test]
+	fn state_ranges_are_downloaded_in_parallel() {
+		let client = Arc::new(TestClientBuilder::new().build());
+		let target = client.header(client.info().genesis_hash).unwrap().unwrap();
+		let (first, second) = (PeerId::random(), PeerId::random());
+		let mut sync = StateSync::<Block, _>::new(client.clone(), target, None, None, true);
+
+		// Nothing is known about the state before the first response, it is not split.
+		assert!(sync.next_request(first).unwrap().start.is_empty());
+		assert!(sync.next_request(second).is_none());
+		let response = StateResponse {
+			entries: vec![level(
+				b"",
+				vec![entry(&[0, 0, 1], b"1"), entry(&[0, 0, 2], b"2")],
+				false,
+			)],
+			proof: Vec::new(),
+		};
+		assert!(matches!(sync.import(first, response), ImportResult::Continue));
+
+		// The remaining keys are split in two ranges, each requested from a peer.
+		assert_eq!(sync.next_request(first).unwrap().start, vec![vec![0, 0, 2]]);
+		assert_eq!(sync.next_request(second).unwrap().start, vec![vec![0, 0, 18]]);
+
+		let response = StateResponse {
+			entries: vec![level(b"", vec![entry(&[0, 0, 20], b"5")], true)],
+			proof: Vec::new(),
+		};
+		assert!(matches!(sync.import(second, response), ImportResult::Continue));
+		assert!(!sync.is_complete());
+
+		// Keys after the end of the range are ignored.
+		let response = StateResponse {
+			entries: vec![level(
+				b"",
+				vec![entry(&[0, 0, 3], b"3"), entry(&[0, 0, 18], b"4"), entry(&[0, 0, 20], b"6")],
+				false,
+			)],
+			proof: Vec::new(),
+		};
+		match sync.import(first, response) {
+			ImportResult::Import(_, _, state, _, _) => {
+				assert_eq!(state.state.0.len(), 1);
+				let keys =
+					state.state.0[0].key_values.iter().map(|(key, value)| (key[2], value.clone()));
+				assert_eq!(
+					keys.collect::<Vec<_>>(),
+					vec![
+						(1, b"1".to_vec()),
+						(2, b"2".to_vec()),
+						(3, b"3".to_vec()),
+						(18, b"4".to_vec()),
+						(20, b"5".to_vec()),
+					],
+				);
+			},
+			_ => panic!("State download should be complete"),
+		}
+	}
+
+	/// Client with keys spread over the key space, and two child tries with the same content.
+	fn client_with_large_state() -> TestClient {
+		let mut builder = TestClientBuilder::new();
+		for index in 0..=255u8 {
+			builder = builder.add_extra_storage(vec![index, 1], vec![index; 32]);
+		}
+		for storage_key in [&b"first"[..], b"second"] {
+			let child_info = ChildInfo::new_default(storage_key);
+			for index in 0..64u8 {
+				builder =
+					builder.add_extra_child_storage(&child_info, vec![index], vec![index; 32]);
+			}
+		}
+		builder.build()
+	}
+
+	#[test]
+	fn proven_state_ranges_are_downloaded_in_parallel_and_imported() {
+		let client = Arc::new(client_with_large_state());
+		let block = client.new_block(Default::default()).unwrap().build().unwrap().block;
+		block_on(client.import(BlockOrigin::Own, block.clone())).unwrap();
+		let (target, body) = block.deconstruct();
+		let peers = (0..4).map(|_| PeerId::random()).collect::<Vec<_>>();
+		let mut sync = StateSync::<Block, _>::new(
+			client.clone(),
+			target.clone(),
+			Some(body.clone()),
+			None,
+			false,
+		);
+
+		let mut max_ranges = 0;
+		let mut imported = None;
+		for _ in 0..1000 {
+			let requests = peers
+				.iter()
+				.filter_map(|peer| Some((*peer, sync.next_request(*peer)?)))
+				.collect::<Vec<_>>();
+			max_ranges = max_ranges.max(sync.ranges.len());
+			// The responses are received in another order than the requests.
+			for (peer, request) in requests.into_iter().rev() {
+				let (proof, _) =
+					client.read_proof_collection(target.hash(), &request.start, 2000).unwrap();
+				let response = StateResponse { entries: Vec::new(), proof: proof.encode() };
+				match sync.import(peer, response) {
+					ImportResult::Continue => (),
+					ImportResult::Import(hash, _, state, _, _) => {
+						assert_eq!(hash, target.hash());
+						imported = Some(state);
+					},
+					ImportResult::BadResponse => panic!("Valid state response rejected"),
+				}
+			}
+			if imported.is_some() {
+				break
+			}
+		}
+		assert!(max_ranges > 2, "State should be split in several ranges");
+		let state = imported.expect("State download should be complete");
+
+		// The downloaded state is imported by a client that only has the genesis block.
+		let importing_client = client_with_large_state();
+		let mut import = BlockImportParams::new(BlockOrigin::NetworkInitialSync, target.clone());
+		import.body = Some(body);
+		import.state_action = StateAction::ApplyChanges(StorageChanges::Import(state));
+		import.fork_choice = Some(ForkChoiceStrategy::LongestChain);
+		let result = block_on((&importing_client).import_block(import)).unwrap();
+		assert!(matches!(result, sc_consensus::ImportResult::Imported(_)));
+		assert_eq!(importing_client.info().best_hash, target.hash());
+	}
+}
@@ -24,8 +24,9 @@ use crate::{
 	state::{ImportResult, StateSync},
 };
 use futures::FutureExt;
+use libp2p::PeerId;
 use log::error;
-use sc_client_api::ProofProvider;
+use sc_client_api::{AuxStore, ProofProvider};
 use sc_network_common::sync::{
 	message::{BlockAttributes, BlockData, BlockRequest, Direction, FromBlock},
 	warp::{
@@ -78,7 +79,7 @@ pub struct WarpSync<B: BlockT, Client> {
 impl<B, Client> WarpSync<B, Client>
 where
 	B: BlockT,
-	Client: HeaderBackend<B> + ProofProvider<B> + 'static,
+	Client: HeaderBackend<B> + ProofProvider<B> + AuxStore + 'static,
 {
 	/// Create a new instance. When passing a warp sync provider we will be checking for proof and
 	/// authorities. Alternatively we can pass a target block when we want to skip downloading
@@ -103,6 +104,12 @@ where
 		}
 	}
 
+	/// Create an instance continuing the state download of a previous run. The warp proofs and
+	/// the target block were already verified then.
+	pub fn resume(client: Arc<Client>, state_sync: StateSync<B, Client>) -> Self {
+		Self { client, phase: Phase::State(state_sync), total_proof_bytes: 0 }
+	}
+
 	/// Poll to make progress.
 	///
 	/// This only makes progress when `phase = Phase::PendingTargetBlock` and the pending block was
@@ -126,14 +133,14 @@ where
 		self.phase = new_phase;
 	}
 
-	///  Validate and import a state response.
-	pub fn import_state(&mut self, response: StateResponse) -> ImportResult<B> {
+	///  Validate and import a state response from the given peer.
+	pub fn import_state(&mut self, who: PeerId, response: StateResponse) -> ImportResult<B> {
 		match &mut self.phase {
 			Phase::WarpProof { .. } | Phase::TargetBlock(_) | Phase::PendingTargetBlock { .. } => {
 				log::debug!(target: "sync", "Unexpected state response");
 				ImportResult::BadResponse
 			},
-			Phase::State(sync) => sync.import(response),
+			Phase::State(sync) => sync.import(who, response),
 		}
 	}
 
@@ -209,12 +216,19 @@ where
 		}
 	}
 
-	/// Produce next state request.
-	pub fn next_state_request(&self) -> Option<StateRequest> {
-		match &self.phase {
+	/// Produce next state request for the given peer.
+	pub fn next_state_request(&mut self, who: PeerId) -> Option<StateRequest> {
+		match &mut self.phase {
 			Phase::WarpProof { .. } | Phase::TargetBlock(_) | Phase::PendingTargetBlock { .. } =>
 				None,
-			Phase::State(sync) => Some(sync.next_request()),
+			Phase::State(sync) => sync.next_request(who),
+		}
+	}
+
+	/// Clear the state range requested from the given peer.
+	pub fn clear_state_download(&mut self, who: &PeerId) {
+		if let Phase::State(sync) = &mut self.phase {
+			sync.clear_peer_download(who);
 		}
 	}
 
@@ -234,7 +248,8 @@ where
 				let request = BlockRequest::<B> {
 					id: 0,
 					fields: BlockAttributes::HEADER |
-						BlockAttributes::BODY | BlockAttributes::JUSTIFICATION,
+						BlockAttributes::BODY |
+						BlockAttributes::JUSTIFICATION,
 					from: FromBlock::Hash(header.hash()),
 					direction: Direction::Ascending,
 					max: Some(1),
@@ -927,6 +927,7 @@ where
 				warp_protocol_config,
 			]
 			.to_vec(),
+			aux_store: None,
 		})
 		.unwrap();
 
@@ -220,6 +220,7 @@ impl TestNetworkBuilder {
 			]
 			.to_vec(),
 			tx,
+			aux_store: None,
 		})
 		.unwrap();
 
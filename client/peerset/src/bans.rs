@@ -0,0 +1,181 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Banned nodes, stored in a file so that bans outlive a restart.
+//!
+//! The file is written by a background thread, so that the peerset doesn't block on disk I/O. The
+//! changes of the bans made within [`STORE_DELAY`] are written at once.
+
+use libp2p::PeerId;
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::{
+	fs, io,
+	path::{Path, PathBuf},
+	sync::mpsc,
+	thread,
+	time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+/// Time during which the changes of the bans are gathered before they are written.
+const STORE_DELAY: Duration = Duration::from_secs(5);
+
+/// A node banned by the peerset.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BannedPeer {
+	/// Identity of the node.
+	pub peer_id: PeerId,
+	/// Current reputation of the node.
+	pub reputation: i32,
+	/// Reason of the reputation change that got the node banned.
+	pub reason: String,
+	/// When the node was banned, in seconds since the UNIX epoch.
+	pub banned_at: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StoredBan {
+	peer_id: String,
+	reputation: i32,
+	reason: String,
+	banned_at: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StoredBans {
+	stored_at: u64,
+	bans: Vec<StoredBan>,
+}
+
+/// Current time, in seconds since the UNIX epoch.
+pub(crate) fn now() -> u64 {
+	SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |since| since.as_secs())
+}
+
+/// Writes the bans it is given to a file, from a background thread.
+///
+/// The last given bans are written when the store is dropped.
+#[derive(Debug)]
+pub(crate) struct BanStore {
+	sender: Option<mpsc::Sender<Vec<BannedPeer>>>,
+	writer: Option<thread::JoinHandle<()>>,
+}
+
+impl BanStore {
+	/// Start the thread writing the bans to the given file.
+	pub fn new(path: PathBuf) -> io::Result<Self> {
+		let (sender, receiver) = mpsc::channel();
+		let writer = thread::Builder::new()
+			.name("peerset-bans".into())
+			.spawn(move || write_bans(&path, receiver))?;
+		Ok(Self { sender: Some(sender), writer: Some(writer) })
+	}
+
+	/// Write the given bans, replacing the ones given before that are not written yet.
+	pub fn store(&self, bans: Vec<BannedPeer>) {
+		if let Some(sender) = &self.sender {
+			let _ = sender.send(bans);
+		}
+	}
+}
+
+impl Drop for BanStore {
+	fn drop(&mut self) {
+		// The writer writes the pending bans and stops once the channel is closed.
+		self.sender = None;
+		if let Some(writer) = self.writer.take() {
+			if writer.join().is_err() {
+				warn!(target: "peerset", "The banned nodes writer panicked");
+			}
+		}
+	}
+}
+
+/// Write the received bans until the channel is closed. The bans received within
+/// [`STORE_DELAY`] of a first change are written once.
+fn write_bans(path: &Path, receiver: mpsc::Receiver<Vec<BannedPeer>>) {
+	while let Ok(mut bans) = receiver.recv() {
+		let deadline = Instant::now() + STORE_DELAY;
+		while let Ok(newer) =
+			receiver.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+		{
+			bans = newer;
+		}
+		if let Err(e) = store(path, &bans) {
+			warn!(target: "peerset", "Failed to store the banned nodes: {}", e);
+		}
+	}
+}
+
+/// Store the bans, along with the current time.
+fn store(path: &Path, bans: &[BannedPeer]) -> io::Result<()> {
+	let stored = StoredBans {
+		stored_at: now(),
+		bans: bans
+			.iter()
+			.map(|ban| StoredBan {
+				peer_id: ban.peer_id.to_base58(),
+				reputation: ban.reputation,
+				reason: ban.reason.clone(),
+				banned_at: ban.banned_at,
+			})
+			.collect(),
+	};
+	// Written to a temporary file first, so that an interrupted write leaves the previous bans.
+	let tmp_path = path.with_extension("tmp");
+	fs::write(&tmp_path, serde_json::to_vec_pretty(&stored)?)?;
+	fs::rename(&tmp_path, path)
+}
+
+/// Load the stored bans, if any, along with the number of seconds elapsed since they were stored.
+pub(crate) fn load(path: &Path) -> (Vec<BannedPeer>, u64) {
+	let data = match fs::read(path) {
+		Ok(data) => data,
+		Err(e) if e.kind() == io::ErrorKind::NotFound => return (Vec::new(), 0),
+		Err(e) => {
+			warn!(target: "peerset", "Failed to read the banned nodes: {}", e);
+			return (Vec::new(), 0)
+		},
+	};
+	let stored = match serde_json::from_slice::<StoredBans>(&data) {
+		Ok(stored) => stored,
+		Err(e) => {
+			warn!(target: "peerset", "Invalid banned nodes file: {}", e);
+			return (Vec::new(), 0)
+		},
+	};
+	let bans = stored
+		.bans
+		.into_iter()
+		.filter_map(|ban| match ban.peer_id.parse() {
+			Ok(peer_id) => Some(BannedPeer {
+				peer_id,
+				reputation: ban.reputation,
+				reason: ban.reason,
+				banned_at: ban.banned_at,
+			}),
+			Err(e) => {
+				warn!(target: "peerset", "Invalid banned node {}: {}", ban.peer_id, e);
+				None
+			},
+		})
+		.collect();
+	(bans, now().saturating_sub(stored.stored_at))
+}
@@ -31,27 +31,35 @@
 //!
 //! In addition, for each, set, the peerset also holds a list of reserved nodes towards which it
 //! will at all time try to maintain a connection with.
+//!
+//! The reputation of the nodes is managed by a [`PeerScoring`] policy, which also decides which
+//! nodes are banned. Bans can be listed and lifted through the [`PeersetHandle`], and are stored
+//! across restarts if [`PeersetConfig::ban_list_path`] is set.
 
+mod bans;
 mod peersstate;
+mod scoring;
 
 use futures::{channel::oneshot, prelude::*};
-use log::{debug, error, trace};
+use log::{debug, error, trace, warn};
 use sc_utils::mpsc::{tracing_unbounded, TracingUnboundedReceiver, TracingUnboundedSender};
 use serde_json::json;
 use std::{
 	collections::{HashMap, HashSet, VecDeque},
+	path::{Path, PathBuf},
 	pin::Pin,
+	sync::Arc,
 	task::{Context, Poll},
 	time::{Duration, Instant},
 };
 use wasm_timer::Delay;
 
+pub use bans::BannedPeer;
 pub use libp2p::PeerId;
+pub use scoring::{DefaultPeerScoring, PeerScoring};
 
-/// We don't accept nodes whose reputation is under this value.
+/// We don't accept nodes whose reputation is under this value, with the [`DefaultPeerScoring`].
 pub const BANNED_THRESHOLD: i32 = 82 * (i32::MIN / 100);
-/// Reputation change for a node when we get disconnected from it.
-const DISCONNECT_REPUTATION_CHANGE: i32 = -256;
 /// Amount of time between the moment we disconnect from a node and the moment we remove it from
 /// the list.
 const FORGET_AFTER: Duration = Duration::from_secs(3600);
@@ -66,6 +74,9 @@ enum Action {
 	AddToPeersSet(SetId, PeerId),
 	RemoveFromPeersSet(SetId, PeerId),
 	PeerReputation(PeerId, oneshot::Sender<i32>),
+	IsBanned(PeerId, oneshot::Sender<bool>),
+	BannedPeers(oneshot::Sender<Vec<BannedPeer>>),
+	UnbanPeer(PeerId),
 }
 
 /// Identifier of a set in the peerset.
@@ -176,6 +187,33 @@ impl PeersetHandle {
 		// The channel can only be closed if the peerset no longer exists.
 		rx.await.map_err(|_| ())
 	}
+
+	/// Returns whether the peer is banned.
+	pub async fn is_banned(self, peer_id: PeerId) -> Result<bool, ()> {
+		let (tx, rx) = oneshot::channel();
+
+		let _ = self.tx.unbounded_send(Action::IsBanned(peer_id, tx));
+
+		// The channel can only be closed if the peerset no longer exists.
+		rx.await.map_err(|_| ())
+	}
+
+	/// Returns the list of banned peers.
+	pub async fn banned_peers(self) -> Result<Vec<BannedPeer>, ()> {
+		let (tx, rx) = oneshot::channel();
+
+		let _ = self.tx.unbounded_send(Action::BannedPeers(tx));
+
+		// The channel can only be closed if the peerset no longer exists.
+		rx.await.map_err(|_| ())
+	}
+
+	/// Lifts the ban of a peer. The reputation of the peer is reset by the [`PeerScoring`] policy.
+	///
+	/// Has no effect if the peer is not banned.
+	pub fn unban_peer(&self, peer_id: PeerId) {
+		let _ = self.tx.unbounded_send(Action::UnbanPeer(peer_id));
+	}
 }
 
 /// Message that can be sent by the peer set manager (PSM).
@@ -218,6 +256,12 @@ impl From<u64> for IncomingIndex {
 pub struct PeersetConfig {
 	/// List of sets of nodes the peerset manages.
 	pub sets: Vec<SetConfig>,
+
+	/// Policy for the reputation of the nodes.
+	pub scoring: Arc<dyn PeerScoring>,
+
+	/// File in which the banned nodes are stored, if any.
+	pub ban_list_path: Option<PathBuf>,
 }
 
 /// Configuration for a single set of nodes.
@@ -270,6 +314,14 @@ pub struct Peerset {
 	/// Next time to do a periodic call to `alloc_slots` with all sets. This is done once per
 	/// second, to match the period of the reputation updates.
 	next_periodic_alloc_slots: Delay,
+	/// Policy for the reputation of the nodes.
+	scoring: Arc<dyn PeerScoring>,
+	/// Nodes currently banned by `scoring`.
+	bans: HashMap<PeerId, BannedPeer>,
+	/// Writer of `bans` to the file they are stored in, if any.
+	ban_store: Option<bans::BanStore>,
+	/// Whether `bans` changed since they were last given to `ban_store`.
+	bans_changed: bool,
 }
 
 impl Peerset {
@@ -297,9 +349,22 @@ impl Peerset {
 				created: now,
 				latest_time_update: now,
 				next_periodic_alloc_slots: Delay::new(Duration::new(0, 0)),
+				scoring: config.scoring,
+				bans: HashMap::new(),
+				ban_store: None,
+				bans_changed: false,
 			}
 		};
 
+		if let Some(path) = config.ban_list_path {
+			peerset.restore_bans(&path);
+			match bans::BanStore::new(path) {
+				Ok(ban_store) => peerset.ban_store = Some(ban_store),
+				Err(e) =>
+					warn!(target: "peerset", "Failed to start the banned nodes writer: {}", e),
+			}
+		}
+
 		for (set, set_config) in config.sets.into_iter().enumerate() {
 			for node in set_config.reserved_nodes {
 				peerset.data.add_no_slot_node(set, node);
@@ -414,6 +479,35 @@ impl Peerset {
 		}
 	}
 
+	/// Adds a node known from a previous run to the given set, with the reputation it had
+	/// `elapsed` ago. The reputation is updated for the time elapsed since.
+	///
+	/// Does nothing if the node already belongs to the set. Contrary to
+	/// [`Peerset::add_to_peers_set`], the slots are only allocated at the next periodic update.
+	pub fn restore_peer(
+		&mut self,
+		set_id: SetId,
+		peer_id: PeerId,
+		mut reputation: i32,
+		elapsed: Duration,
+	) {
+		let mut entry = match self.data.peer(set_id.0, &peer_id) {
+			peersstate::Peer::Unknown(entry) => entry.discover(),
+			_ => return,
+		};
+
+		// The reputation of banned nodes has already been restored along with their ban.
+		if self.bans.contains_key(&peer_id) {
+			return
+		}
+
+		for _ in 0..elapsed.min(FORGET_AFTER).as_secs() {
+			reputation = self.scoring.on_tick(&peer_id, reputation);
+		}
+		entry.set_reputation(reputation);
+		self.update_ban(peer_id, reputation, "Restored");
+	}
+
 	fn on_remove_from_peers_set(&mut self, set_id: SetId, peer_id: PeerId) {
 		// Don't do anything if node is reserved.
 		if self.reserved_nodes[set_id.0].0.contains(&peer_id) {
@@ -437,20 +531,22 @@ impl Peerset {
 		self.update_time();
 
 		let mut reputation = self.data.peer_reputation(peer_id);
-		reputation.add_reputation(change.value);
-		if reputation.reputation() >= BANNED_THRESHOLD {
+		let value = self.scoring.on_report(&peer_id, reputation.reputation(), change);
+		reputation.set_reputation(value);
+		drop(reputation);
+
+		self.update_ban(peer_id, value, change.reason);
+		if !self.scoring.should_disconnect(&peer_id, value) {
 			trace!(target: "peerset", "Report {}: {:+} to {}. Reason: {}",
-				peer_id, change.value, reputation.reputation(), change.reason
+				peer_id, change.value, value, change.reason
 			);
 			return
 		}
 
 		debug!(target: "peerset", "Report {}: {:+} to {}. Reason: {}, Disconnecting",
-			peer_id, change.value, reputation.reputation(), change.reason
+			peer_id, change.value, value, change.reason
 		);
 
-		drop(reputation);
-
 		for set_index in 0..self.data.num_sets() {
 			if let peersstate::Peer::Connected(peer) = self.data.peer(set_index, &peer_id) {
 				let peer = peer.disconnect();
@@ -469,6 +565,105 @@ impl Peerset {
 		let _ = pending_response.send(reputation.reputation());
 	}
 
+	fn on_is_banned(&mut self, peer_id: PeerId, pending_response: oneshot::Sender<bool>) {
+		let reputation = self.data.peer_reputation(peer_id).reputation();
+		let _ = pending_response.send(self.scoring.is_banned(&peer_id, reputation));
+	}
+
+	fn on_banned_peers(&mut self, pending_response: oneshot::Sender<Vec<BannedPeer>>) {
+		self.update_time();
+		let _ = pending_response.send(self.banned_peers());
+	}
+
+	fn on_unban_peer(&mut self, peer_id: PeerId) {
+		self.update_time();
+		if self.bans.remove(&peer_id).is_none() {
+			return
+		}
+
+		let mut reputation = self.data.peer_reputation(peer_id);
+		let value = self.scoring.on_unban(&peer_id, reputation.reputation());
+		reputation.set_reputation(value);
+		drop(reputation);
+
+		debug!(target: "peerset", "Lifted the ban of {}, reputation set to {}", peer_id, value);
+		self.bans_changed = true;
+
+		for set_index in 0..self.data.num_sets() {
+			self.alloc_slots(SetId(set_index));
+		}
+	}
+
+	/// Track the ban of a node after a change of its reputation.
+	fn update_ban(&mut self, peer_id: PeerId, reputation: i32, reason: &str) {
+		let banned = self.scoring.is_banned(&peer_id, reputation);
+		match (banned, self.bans.contains_key(&peer_id)) {
+			(true, false) => {
+				debug!(target: "peerset", "Banned {} with reputation {}. Reason: {}",
+					peer_id, reputation, reason
+				);
+				self.bans.insert(
+					peer_id,
+					BannedPeer {
+						peer_id,
+						reputation,
+						reason: reason.to_string(),
+						banned_at: bans::now(),
+					},
+				);
+			},
+			(false, true) => {
+				debug!(target: "peerset", "Ban of {} expired", peer_id);
+				self.bans.remove(&peer_id);
+			},
+			_ => return,
+		}
+		self.bans_changed = true;
+	}
+
+	/// Returns the banned nodes, with their current reputation.
+	fn banned_peers(&mut self) -> Vec<BannedPeer> {
+		let data = &mut self.data;
+		self.bans
+			.values()
+			.map(|ban| BannedPeer {
+				reputation: data.peer_reputation(ban.peer_id).reputation(),
+				..ban.clone()
+			})
+			.collect()
+	}
+
+	/// Hand the bans to the writer if they changed. They are written in the background.
+	fn store_bans(&mut self) {
+		if !self.bans_changed {
+			return
+		}
+		self.bans_changed = false;
+		if self.ban_store.is_some() {
+			let bans = self.banned_peers();
+			if let Some(ban_store) = &self.ban_store {
+				ban_store.store(bans);
+			}
+		}
+	}
+
+	/// Restore the bans stored by a previous run. The reputations of the nodes are updated for the
+	/// time elapsed since, and the nodes that are no longer banned are skipped.
+	fn restore_bans(&mut self, path: &Path) {
+		let (bans, elapsed) = bans::load(path);
+		for mut ban in bans {
+			for _ in 0..elapsed.min(FORGET_AFTER.as_secs()) {
+				ban.reputation = self.scoring.on_tick(&ban.peer_id, ban.reputation);
+			}
+			if !self.scoring.is_banned(&ban.peer_id, ban.reputation) {
+				continue
+			}
+			self.data.peer_reputation(ban.peer_id).set_reputation(ban.reputation);
+			self.bans.insert(ban.peer_id, ban);
+		}
+		debug!(target: "peerset", "Restored {} banned nodes", self.bans.len());
+	}
+
 	/// Updates the value of `self.latest_time_update` and performs all the updates that happen
 	/// over time, such as reputation increases for staying connected.
 	fn update_time(&mut self) {
@@ -483,37 +678,25 @@ impl Peerset {
 			elapsed_now.as_secs() - elapsed_latest.as_secs()
 		};
 
-		// For each elapsed second, move the node reputation towards zero.
-		// If we multiply each second the reputation by `k` (where `k` is between 0 and 1), it
-		// takes `ln(0.5) / ln(k)` seconds to reduce the reputation by half. Use this formula to
-		// empirically determine a value of `k` that looks correct.
+		// For each elapsed second, update the node reputation.
 		for _ in 0..secs_diff {
 			for peer_id in self.data.peers().cloned().collect::<Vec<_>>() {
-				// We use `k = 0.98`, so we divide by `50`. With that value, it takes 34.3 seconds
-				// to reduce the reputation by half.
-				fn reput_tick(reput: i32) -> i32 {
-					let mut diff = reput / 50;
-					if diff == 0 && reput < 0 {
-						diff = -1;
-					} else if diff == 0 && reput > 0 {
-						diff = 1;
-					}
-					reput.saturating_sub(diff)
-				}
-
 				let mut peer_reputation = self.data.peer_reputation(peer_id);
 
 				let before = peer_reputation.reputation();
-				let after = reput_tick(before);
+				let after = self.scoring.on_tick(&peer_id, before);
 				trace!(target: "peerset", "Fleeting {}: {} -> {}", peer_id, before, after);
 				peer_reputation.set_reputation(after);
+				drop(peer_reputation);
+
+				if self.bans.contains_key(&peer_id) {
+					self.update_ban(peer_id, after, "Reputation update");
+				}
 
 				if after != 0 {
 					continue
 				}
 
-				drop(peer_reputation);
-
 				// If the peer reaches a reputation of 0, and there is no connection to it,
 				// forget it.
 				for set_index in 0..self.data.num_sets() {
@@ -549,7 +732,7 @@ impl Peerset {
 			// This is a rather opinionated behaviour, and it wouldn't be fundamentally wrong to
 			// remove that check. If necessary, the peerset should be refactored to give more
 			// control over what happens in that situation.
-			if entry.reputation() < BANNED_THRESHOLD {
+			if self.scoring.is_banned(reserved_node, entry.reputation()) {
 				break
 			}
 
@@ -586,8 +769,8 @@ impl Peerset {
 				None => break,
 			};
 
-			// Don't connect to nodes with an abysmal reputation.
-			if next.reputation() < BANNED_THRESHOLD {
+			// Don't connect to banned nodes.
+			if self.scoring.is_banned(next.peer_id(), next.reputation()) {
 				break
 			}
 
@@ -634,7 +817,7 @@ impl Peerset {
 			peersstate::Peer::Unknown(entry) => entry.discover(),
 		};
 
-		if not_connected.reputation() < BANNED_THRESHOLD {
+		if self.scoring.is_banned(&peer_id, not_connected.reputation()) {
 			self.message_queue.push_back(Message::Reject(index));
 			return
 		}
@@ -656,10 +839,13 @@ impl Peerset {
 		match self.data.peer(set_id.0, &peer_id) {
 			peersstate::Peer::Connected(mut entry) => {
 				// Decrease the node's reputation so that we don't try it again and again and again.
-				entry.add_reputation(DISCONNECT_REPUTATION_CHANGE);
+				let before = entry.reputation();
+				let after = self.scoring.on_disconnect(&peer_id, before);
+				entry.set_reputation(after);
 				trace!(target: "peerset", "Dropping {}: {:+} to {}",
-					peer_id, DISCONNECT_REPUTATION_CHANGE, entry.reputation());
+					peer_id, after.saturating_sub(before), after);
 				entry.disconnect();
+				self.update_ban(peer_id, after, "Disconnected");
 			},
 			peersstate::Peer::NotConnected(_) | peersstate::Peer::Unknown(_) => {
 				error!(target: "peerset", "Received dropped() for non-connected node")
@@ -713,6 +899,19 @@ impl Peerset {
 		})
 	}
 
+	/// Returns all the nodes that we know of, with their reputation.
+	pub fn peer_reputations(&mut self) -> Vec<(PeerId, i32)> {
+		self.update_time();
+
+		let data = &mut self.data;
+		data.peers()
+			.cloned()
+			.collect::<Vec<_>>()
+			.into_iter()
+			.map(|peer_id| (peer_id, data.peer_reputation(peer_id).reputation()))
+			.collect()
+	}
+
 	/// Returns the number of peers that we have discovered.
 	pub fn num_discovered_peers(&self) -> usize {
 		self.data.peers().len()
@@ -734,6 +933,7 @@ impl Stream for Peerset {
 				for set_index in 0..self.data.num_sets() {
 					self.alloc_slots(SetId(set_index));
 				}
+				self.store_bans();
 			}
 
 			let action = match Stream::poll_next(Pin::new(&mut self.rx), cx) {
@@ -758,11 +958,22 @@ impl Stream for Peerset {
 					self.on_remove_from_peers_set(sets_name, peer_id),
 				Action::PeerReputation(peer_id, pending_response) =>
 					self.on_peer_reputation(peer_id, pending_response),
+				Action::IsBanned(peer_id, pending_response) =>
+					self.on_is_banned(peer_id, pending_response),
+				Action::BannedPeers(pending_response) => self.on_banned_peers(pending_response),
+				Action::UnbanPeer(peer_id) => self.on_unban_peer(peer_id),
 			}
 		}
 	}
 }
 
+impl Drop for Peerset {
+	fn drop(&mut self) {
+		// The changes since the last periodic update are written before the writer stops.
+		self.store_bans();
+	}
+}
+
 /// Reason for calling [`Peerset::dropped`].
 pub enum DropReason {
 	/// Substream or connection has been closed for an unknown reason.
@@ -777,12 +988,12 @@ pub enum DropReason {
 #[cfg(test)]
 mod tests {
 	use super::{
-		IncomingIndex, Message, Peerset, PeersetConfig, ReputationChange, SetConfig, SetId,
-		BANNED_THRESHOLD,
+		DefaultPeerScoring, IncomingIndex, Message, Peerset, PeersetConfig, ReputationChange,
+		SetConfig, SetId, BANNED_THRESHOLD,
 	};
 	use futures::prelude::*;
 	use libp2p::PeerId;
-	use std::{pin::Pin, task::Poll, thread, time::Duration};
+	use std::{pin::Pin, sync::Arc, task::Poll, thread, time::Duration};
 
 	fn assert_messages(mut peerset: Peerset, messages: Vec<Message>) -> Peerset {
 		for expected_message in messages {
@@ -812,6 +1023,8 @@ mod tests {
 				reserved_nodes: Default::default(),
 				reserved_only: true,
 			}],
+			scoring: Arc::new(DefaultPeerScoring),
+			ban_list_path: None,
 		};
 
 		let (peerset, handle) = Peerset::from_config(config);
@@ -845,6 +1058,8 @@ mod tests {
 				reserved_nodes: Default::default(),
 				reserved_only: false,
 			}],
+			scoring: Arc::new(DefaultPeerScoring),
+			ban_list_path: None,
 		};
 
 		let (mut peerset, _handle) = Peerset::from_config(config);
@@ -876,6 +1091,8 @@ mod tests {
 				reserved_nodes: Default::default(),
 				reserved_only: true,
 			}],
+			scoring: Arc::new(DefaultPeerScoring),
+			ban_list_path: None,
 		};
 
 		let (mut peerset, _) = Peerset::from_config(config);
@@ -897,6 +1114,8 @@ mod tests {
 				reserved_nodes: Default::default(),
 				reserved_only: false,
 			}],
+			scoring: Arc::new(DefaultPeerScoring),
+			ban_list_path: None,
 		};
 
 		let (mut peerset, _handle) = Peerset::from_config(config);
@@ -923,6 +1142,8 @@ mod tests {
 				reserved_nodes: Default::default(),
 				reserved_only: false,
 			}],
+			scoring: Arc::new(DefaultPeerScoring),
+			ban_list_path: None,
 		});
 
 		// We ban a node by setting its reputation under the threshold.
@@ -966,6 +1187,8 @@ mod tests {
 				reserved_nodes: Default::default(),
 				reserved_only: false,
 			}],
+			scoring: Arc::new(DefaultPeerScoring),
+			ban_list_path: None,
 		});
 
 		// We ban a node by setting its reputation under the threshold.
@@ -996,4 +1219,87 @@ mod tests {
 
 		futures::executor::block_on(fut);
 	}
+
+	#[test]
+	fn bans_are_stored_and_lifted() {
+		let dir = tempfile::tempdir().unwrap();
+		let config = || PeersetConfig {
+			sets: vec![SetConfig {
+				in_peers: 25,
+				out_peers: 25,
+				bootnodes: vec![],
+				reserved_nodes: Default::default(),
+				reserved_only: false,
+			}],
+			scoring: Arc::new(DefaultPeerScoring),
+			ban_list_path: Some(dir.path().join("banned_peers.json")),
+		};
+		let peer_id = PeerId::random();
+
+		let (mut peerset, handle) = Peerset::from_config(config());
+		handle.report_peer(peer_id, ReputationChange::new_fatal("Misbehaved"));
+		futures::executor::block_on(futures::future::poll_fn(|cx| {
+			assert_eq!(Stream::poll_next(Pin::new(&mut peerset), cx), Poll::Pending);
+			Poll::Ready(())
+		}));
+		drop(peerset);
+
+		// The ban is restored, along with its reason.
+		let (mut peerset, handle) = Peerset::from_config(config());
+		let mut banned_peers = handle.clone().banned_peers().boxed();
+		futures::executor::block_on(futures::future::poll_fn(|cx| {
+			assert_eq!(Stream::poll_next(Pin::new(&mut peerset), cx), Poll::Pending);
+			let banned_peers = match banned_peers.poll_unpin(cx) {
+				Poll::Ready(banned_peers) => banned_peers.unwrap(),
+				Poll::Pending => panic!("The banned peers are sent back right away"),
+			};
+			assert_eq!(banned_peers.len(), 1);
+			assert_eq!(banned_peers[0].peer_id, peer_id);
+			assert_eq!(banned_peers[0].reason, "Misbehaved");
+
+			peerset.incoming(SetId::from(0), peer_id, IncomingIndex(1));
+			assert_eq!(
+				Stream::poll_next(Pin::new(&mut peerset), cx),
+				Poll::Ready(Some(Message::Reject(IncomingIndex(1)))),
+			);
+			Poll::Ready(())
+		}));
+
+		// Once the ban is lifted, the node is connected to again.
+		handle.unban_peer(peer_id);
+		let peerset =
+			assert_messages(peerset, vec![Message::Connect { set_id: SetId::from(0), peer_id }]);
+		drop(peerset);
+
+		let (peerset, _) = Peerset::from_config(config());
+		assert!(peerset.bans.is_empty());
+	}
+
+	#[test]
+	fn restored_peers_are_connected() {
+		let config = PeersetConfig {
+			sets: vec![SetConfig {
+				in_peers: 0,
+				out_peers: 1,
+				bootnodes: vec![],
+				reserved_nodes: Default::default(),
+				reserved_only: false,
+			}],
+			scoring: Arc::new(DefaultPeerScoring),
+			ban_list_path: None,
+		};
+		let peer_id = PeerId::random();
+		let stale_peer_id = PeerId::random();
+
+		let (mut peerset, _handle) = Peerset::from_config(config);
+		peerset.restore_peer(SetId::from(0), peer_id, 1000, Duration::from_secs(0));
+		peerset.restore_peer(SetId::from(0), stale_peer_id, 1000, Duration::from_secs(7200));
+
+		// The reputation of the node stored long ago went back to zero.
+		let mut reputations = peerset.peer_reputations();
+		reputations.sort_by_key(|(_, reputation)| *reputation);
+		assert_eq!(reputations, vec![(stale_peer_id, 0), (peer_id, 1000)]);
+
+		assert_messages(peerset, vec![Message::Connect { set_id: SetId::from(0), peer_id }]);
+	}
 }
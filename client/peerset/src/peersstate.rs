@@ -386,16 +386,14 @@ impl<'a> ConnectedPeer<'a> {
 		NotConnectedPeer { state: self.state, set: self.set, peer_id: self.peer_id }
 	}
 
-	/// Performs an arithmetic addition on the reputation score of that peer.
-	///
-	/// In case of overflow, the value will be capped.
+	/// Sets the reputation of the peer.
 	///
 	/// > **Note**: Reputation values aren't specific to a set but are global per peer.
-	pub fn add_reputation(&mut self, modifier: i32) {
+	pub fn set_reputation(&mut self, value: i32) {
 		if let Some(node) = self.state.nodes.get_mut(&*self.peer_id) {
-			node.reputation = node.reputation.saturating_add(modifier);
+			node.reputation = value;
 		} else {
-			debug_assert!(false, "State inconsistency: add_reputation on an unknown node");
+			debug_assert!(false, "State inconsistency: set_reputation on an unknown node");
 		}
 	}
 
@@ -416,6 +414,11 @@ pub struct NotConnectedPeer<'a> {
 }
 
 impl<'a> NotConnectedPeer<'a> {
+	/// Get the `PeerId` associated to this `NotConnectedPeer`.
+	pub fn peer_id(&self) -> &PeerId {
+		&self.peer_id
+	}
+
 	/// Destroys this `NotConnectedPeer` and returns the `PeerId` inside of it.
 	pub fn into_peer_id(self) -> PeerId {
 		self.peer_id.into_owned()
@@ -601,14 +604,6 @@ impl<'a> Reputation<'a> {
 	pub fn set_reputation(&mut self, value: i32) {
 		self.node.as_mut().unwrap().get_mut().reputation = value;
 	}
-
-	/// Performs an arithmetic addition on the reputation score of that peer.
-	///
-	/// In case of overflow, the value will be capped.
-	pub fn add_reputation(&mut self, modifier: i32) {
-		let reputation = &mut self.node.as_mut().unwrap().get_mut().reputation;
-		*reputation = reputation.saturating_add(modifier);
-	}
 }
 
 impl<'a> Drop for Reputation<'a> {
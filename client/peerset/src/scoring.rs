@@ -0,0 +1,89 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Peer scoring policy, deciding how reputations evolve and which nodes are banned.
+
+use crate::{ReputationChange, BANNED_THRESHOLD};
+use libp2p::PeerId;
+use std::fmt::Debug;
+
+/// Reputation change for a node when we get disconnected from it.
+const DISCONNECT_REPUTATION_CHANGE: i32 = -256;
+
+/// Policy of the peerset for the reputation of the nodes.
+///
+/// Every reputation change goes through the policy, which also decides which nodes are
+/// disconnected and banned. Banned nodes are neither connected to nor accepted.
+pub trait PeerScoring: Debug + Send + Sync {
+	/// Returns the reputation of a node after a reported change.
+	fn on_report(&self, peer_id: &PeerId, reputation: i32, change: ReputationChange) -> i32;
+
+	/// Returns the reputation of a node after we got disconnected from it.
+	fn on_disconnect(&self, peer_id: &PeerId, reputation: i32) -> i32;
+
+	/// Returns the reputation of a node after a second has elapsed. Nodes with a reputation of
+	/// zero are eventually forgotten once disconnected.
+	fn on_tick(&self, peer_id: &PeerId, reputation: i32) -> i32;
+
+	/// Whether a node with the given reputation is banned.
+	fn is_banned(&self, peer_id: &PeerId, reputation: i32) -> bool;
+
+	/// Whether a connected node with the given reputation must be disconnected.
+	fn should_disconnect(&self, peer_id: &PeerId, reputation: i32) -> bool {
+		self.is_banned(peer_id, reputation)
+	}
+
+	/// Returns the reputation of a node of which the ban is lifted manually.
+	fn on_unban(&self, _peer_id: &PeerId, _reputation: i32) -> i32 {
+		0
+	}
+}
+
+/// Default policy: reputation changes are applied as reported, reputations move towards zero
+/// over time, and nodes under [`BANNED_THRESHOLD`] are banned.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DefaultPeerScoring;
+
+impl PeerScoring for DefaultPeerScoring {
+	fn on_report(&self, _peer_id: &PeerId, reputation: i32, change: ReputationChange) -> i32 {
+		reputation.saturating_add(change.value)
+	}
+
+	fn on_disconnect(&self, _peer_id: &PeerId, reputation: i32) -> i32 {
+		reputation.saturating_add(DISCONNECT_REPUTATION_CHANGE)
+	}
+
+	// If we multiply each second the reputation by `k` (where `k` is between 0 and 1), it
+	// takes `ln(0.5) / ln(k)` seconds to reduce the reputation by half. Use this formula to
+	// empirically determine a value of `k` that looks correct.
+	fn on_tick(&self, _peer_id: &PeerId, reputation: i32) -> i32 {
+		// We use `k = 0.98`, so we divide by `50`. With that value, it takes 34.3 seconds
+		// to reduce the reputation by half.
+		let mut diff = reputation / 50;
+		if diff == 0 && reputation < 0 {
+			diff = -1;
+		} else if diff == 0 && reputation > 0 {
+			diff = 1;
+		}
+		reputation.saturating_sub(diff)
+	}
+
+	fn is_banned(&self, _peer_id: &PeerId, reputation: i32) -> bool {
+		reputation < BANNED_THRESHOLD
+	}
+}
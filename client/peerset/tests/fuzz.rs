@@ -23,11 +23,13 @@ use rand::{
 	seq::IteratorRandom,
 };
 use sc_peerset::{
-	DropReason, IncomingIndex, Message, Peerset, PeersetConfig, ReputationChange, SetConfig, SetId,
+	DefaultPeerScoring, DropReason, IncomingIndex, Message, Peerset, PeersetConfig,
+	ReputationChange, SetConfig, SetId,
 };
 use std::{
 	collections::{HashMap, HashSet},
 	pin::Pin,
+	sync::Arc,
 	task::Poll,
 };
 
@@ -70,6 +72,8 @@ fn test_once() {
 			out_peers: Uniform::new_inclusive(0, 25).sample(&mut rng),
 			reserved_only: Uniform::new_inclusive(0, 10).sample(&mut rng) == 0,
 		}],
+		scoring: Arc::new(DefaultPeerScoring),
+		ban_list_path: None,
 	});
 
 	futures::executor::block_on(futures::future::poll_fn(move |cx| {
@@ -51,6 +51,18 @@ pub enum Error {
 	/// Invalid session keys encoding.
 	#[error("Session keys are not encoded correctly")]
 	InvalidSessionKeys,
+	/// The signature authorizing the cancellation of an extrinsic is invalid.
+	#[error("Invalid cancellation signature")]
+	BadCancellationSignature,
+	/// The extrinsic to cancel is not in the pool.
+	#[error("Extrinsic to cancel is not in the pool")]
+	UnknownExtrinsic,
+	/// The signer of the cancellation is not the sender of the extrinsic.
+	#[error("Cancellation signer is not the sender of the extrinsic")]
+	NotExtrinsicSender,
+	/// The best block number exceeds the validity bound of the cancellation.
+	#[error("Cancellation expired")]
+	CancellationExpired,
 	/// Call to an unsafe RPC was denied.
 	#[error(transparent)]
 	UnsafeRpcCalled(#[from] crate::policy::UnsafeRpcError),
@@ -62,6 +74,14 @@ const BASE_ERROR: i32 = 1000;
 const BAD_FORMAT: i32 = BASE_ERROR + 1;
 /// Error during transaction verification in runtime.
 const VERIFICATION_ERROR: i32 = BASE_ERROR + 2;
+/// The signature authorizing the cancellation is invalid.
+const BAD_CANCELLATION_SIGNATURE: i32 = BASE_ERROR + 3;
+/// The extrinsic to cancel is not in the pool.
+const UNKNOWN_EXTRINSIC: i32 = BASE_ERROR + 4;
+/// The signer of the cancellation is not the sender of the extrinsic.
+const NOT_EXTRINSIC_SENDER: i32 = BASE_ERROR + 5;
+/// The best block number exceeds the validity bound of the cancellation.
+const CANCELLATION_EXPIRED: i32 = BASE_ERROR + 6;
 
 /// Pool rejected the transaction as invalid
 const POOL_INVALID_TX: i32 = BASE_ERROR + 10;
@@ -86,6 +106,10 @@ const POOL_NO_TAGS: i32 = POOL_INVALID_TX + 9;
 const POOL_INVALID_BLOCK_ID: i32 = POOL_INVALID_TX + 10;
 /// The pool is not accepting future transactions.
 const POOL_FUTURE_TX: i32 = POOL_INVALID_TX + 11;
+/// The sender of the transaction reached its limit of transactions in the pool.
+const POOL_SENDER_LIMIT_REACHED: i32 = POOL_INVALID_TX + 12;
+/// The pool capacity for normal transactions is exhausted.
+const POOL_LANE_FULL: i32 = POOL_INVALID_TX + 13;
 
 impl From<Error> for JsonRpseeError {
 	fn from(e: Error) -> Self {
@@ -176,6 +200,37 @@ impl From<Error> for JsonRpseeError {
 					None::<()>,
 				))
 			},
+			Error::Pool(PoolError::SenderLimitReached) => CallError::Custom(ErrorObject::owned(
+				POOL_SENDER_LIMIT_REACHED,
+				"Sender limit reached",
+				Some("The sender of the transaction reached its limit of transactions in the pool"),
+			)),
+			Error::Pool(PoolError::LaneFull) => CallError::Custom(ErrorObject::owned(
+				POOL_LANE_FULL,
+				"Lane full",
+				Some("The pool capacity for normal transactions is exhausted, the remaining \
+				capacity is reserved for operational transactions"),
+			)),
+			Error::BadCancellationSignature => CallError::Custom(ErrorObject::owned(
+				BAD_CANCELLATION_SIGNATURE,
+				"Invalid cancellation signature",
+				None::<()>,
+			)),
+			Error::UnknownExtrinsic => CallError::Custom(ErrorObject::owned(
+				UNKNOWN_EXTRINSIC,
+				"Extrinsic to cancel is not in the pool",
+				None::<()>,
+			)),
+			Error::NotExtrinsicSender => CallError::Custom(ErrorObject::owned(
+				NOT_EXTRINSIC_SENDER,
+				"Cancellation signer is not the sender of the extrinsic",
+				None::<()>,
+			)),
+			Error::CancellationExpired => CallError::Custom(ErrorObject::owned(
+				CANCELLATION_EXPIRED,
+				"Cancellation expired",
+				Some("The best block number exceeds the validity bound of the cancellation"),
+			)),
 			Error::UnsafeRpcCalled(e) => e.into(),
 			e => CallError::Failed(e.into()),
 		}.into()
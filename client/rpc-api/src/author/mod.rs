@@ -18,6 +18,7 @@
 
 //! Substrate block-author/full-node API.
 
+use codec::Encode;
 use jsonrpsee::{core::RpcResult, proc_macros::rpc};
 use sc_transaction_pool_api::TransactionStatus;
 use sp_core::Bytes;
@@ -25,6 +26,23 @@ use sp_core::Bytes;
 pub mod error;
 pub mod hash;
 
+/// Context of the message signed to cancel an extrinsic with `author_cancelExtrinsic`.
+pub const CANCELLATION_CONTEXT: &[u8] = b"author_cancelExtrinsic";
+
+/// Returns the message to sign to cancel the extrinsic with the given hash, on the chain with the
+/// given genesis hash, as long as the best block number is at most `valid_until`.
+///
+/// This is the SCALE encoding of the `(CANCELLATION_CONTEXT, genesis_hash, valid_until, hash)`
+/// tuple. The genesis hash and the validity bound prevent the cancellation from being replayed on
+/// another chain, or against the same extrinsic submitted again later.
+pub fn cancellation_payload<Hash: Encode, BlockHash: Encode>(
+	genesis_hash: &BlockHash,
+	valid_until: u64,
+	hash: &Hash,
+) -> Vec<u8> {
+	(CANCELLATION_CONTEXT, genesis_hash, valid_until, hash).encode()
+}
+
 /// Substrate authoring RPC API
 #[rpc(client, server)]
 pub trait AuthorApi<Hash, BlockHash> {
@@ -65,6 +83,30 @@ pub trait AuthorApi<Hash, BlockHash> {
 		bytes_or_hash: Vec<hash::ExtrinsicOrHash<Hash>>,
 	) -> RpcResult<Vec<Hash>>;
 
+	/// Remove a pending extrinsic from the pool on request of its sender and temporarily ban it to
+	/// prevent reimporting.
+	///
+	/// `signer` is the SCALE encoded `MultiSigner` of the sender and `signature` the SCALE
+	/// encoded `MultiSignature` of the [`cancellation_payload`] of the extrinsic hash, for the
+	/// genesis hash of the chain and `valid_until`. The cancellation is rejected once the best
+	/// block number exceeds `valid_until`. The extrinsic must provide a tag prefixed with the
+	/// account id of the signer, like the `(AccountId, Nonce)` tag provided by the `CheckNonce`
+	/// signed extension of FRAME.
+	///
+	/// The extrinsics depending on the cancelled one are removed as well, and the watchers of
+	/// all of them receive the `cancelled` event. This only affects the pool of this node, an
+	/// extrinsic that was already propagated might still be included by other nodes.
+	///
+	/// Returns the hashes of the removed extrinsics.
+	#[method(name = "author_cancelExtrinsic")]
+	fn cancel_extrinsic(
+		&self,
+		hash: Hash,
+		valid_until: u64,
+		signer: Bytes,
+		signature: Bytes,
+	) -> RpcResult<Vec<Hash>>;
+
 	/// Submit an extrinsic to watch.
 	///
 	/// See [`TransactionStatus`](sc_transaction_pool_api::TransactionStatus) for details on
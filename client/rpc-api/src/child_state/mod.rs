@@ -17,7 +17,7 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 //! Substrate child state API
-use crate::state::ReadProof;
+use crate::state::{RangeProof, ReadProof};
 use jsonrpsee::{core::RpcResult, proc_macros::rpc};
 use sp_core::storage::{PrefixedStorageKey, StorageData, StorageKey};
 
@@ -94,4 +94,19 @@ pub trait ChildStateApi<Hash> {
 		keys: Vec<StorageKey>,
 		hash: Option<Hash>,
 	) -> RpcResult<ReadProof<Hash>>;
+
+	/// Returns a compact proof of the child storage entries with prefix, starting at `start_key`
+	/// (inclusive), of at most about `size_limit` bytes.
+	///
+	/// The proof is rooted in the state root of the block and also covers the key following the
+	/// last returned one. The next range starts at the returned continuation key.
+	#[method(name = "state_getChildRangeProof", blocking)]
+	fn child_range_proof(
+		&self,
+		child_storage_key: PrefixedStorageKey,
+		prefix: Option<StorageKey>,
+		start_key: Option<StorageKey>,
+		size_limit: u32,
+		hash: Option<Hash>,
+	) -> RpcResult<RangeProof<Hash>>;
 }
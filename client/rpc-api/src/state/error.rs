@@ -49,6 +49,14 @@ pub enum Error {
 		/// Maximum allowed value
 		max: u32,
 	},
+	/// Provided proof size limit exceeds maximum value.
+	#[error("proof size limit exceeds maximum value. value: {}, max: {}", .value, .max)]
+	InvalidProofSize {
+		/// Provided value
+		value: u32,
+		/// Maximum allowed value
+		max: u32,
+	},
 	/// Call to an unsafe RPC was denied.
 	#[error(transparent)]
 	UnsafeRpcCalled(#[from] crate::policy::UnsafeRpcError),
@@ -66,6 +74,9 @@ impl From<Error> for JsonRpseeError {
 			Error::InvalidCount { .. } =>
 				CallError::Custom(ErrorObject::owned(BASE_ERROR + 2, e.to_string(), None::<()>))
 					.into(),
+			Error::InvalidProofSize { .. } =>
+				CallError::Custom(ErrorObject::owned(BASE_ERROR + 3, e.to_string(), None::<()>))
+					.into(),
 			e => Self::to_call_error(e),
 		}
 	}
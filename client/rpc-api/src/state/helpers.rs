@@ -19,7 +19,7 @@
 //! Substrate state API helpers.
 
 use serde::{Deserialize, Serialize};
-use sp_core::Bytes;
+use sp_core::{storage::StorageKey, Bytes};
 
 /// ReadProof struct returned by the RPC
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
@@ -30,3 +30,17 @@ pub struct ReadProof<Hash> {
 	/// A proof used to prove that storage entries are included in the storage trie
 	pub proof: Vec<Bytes>,
 }
+
+/// RangeProof struct returned by the RPC
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RangeProof<Hash> {
+	/// Block hash used to generate the proof
+	pub at: Hash,
+	/// Nodes of the compact proof of the storage entries of the range
+	pub proof: Vec<Bytes>,
+	/// Last key of the range, `None` if the range is empty
+	pub last_key: Option<StorageKey>,
+	/// Key following the range, to request the next range at. `None` if no key is left
+	pub continuation: Option<StorageKey>,
+}
@@ -28,7 +28,7 @@ use sp_version::RuntimeVersion;
 pub mod error;
 pub mod helpers;
 
-pub use self::helpers::ReadProof;
+pub use self::helpers::{RangeProof, ReadProof};
 
 /// Substrate state API
 #[rpc(client, server)]
@@ -111,6 +111,20 @@ pub trait StateApi<Hash> {
 	#[method(name = "state_getReadProof", blocking)]
 	fn read_proof(&self, keys: Vec<StorageKey>, hash: Option<Hash>) -> RpcResult<ReadProof<Hash>>;
 
+	/// Returns a compact proof of the storage entries with prefix, starting at `start_key`
+	/// (inclusive), of at most about `size_limit` bytes.
+	///
+	/// The proof also covers the key following the last returned one, so that it proves that
+	/// the range is contiguous. The next range starts at the returned continuation key.
+	#[method(name = "state_getRangeProof", blocking)]
+	fn range_proof(
+		&self,
+		prefix: Option<StorageKey>,
+		start_key: Option<StorageKey>,
+		size_limit: u32,
+		hash: Option<Hash>,
+	) -> RpcResult<RangeProof<Hash>>;
+
 	/// New runtime version subscription
 	#[subscription(
 		name = "state_subscribeRuntimeVersion" => "state_runtimeVersion",
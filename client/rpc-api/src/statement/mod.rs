@@ -19,10 +19,23 @@
 //! Substrate Statement Store RPC API.
 
 use jsonrpsee::{core::RpcResult, proc_macros::rpc};
+use serde::{Deserialize, Serialize};
 use sp_core::Bytes;
 
 pub mod error;
 
+/// A statement reported by the statement subscription.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatementNotification {
+	/// The sequence number assigned to the statement by the store.
+	///
+	/// Can be used as the `from` parameter when subscribing again.
+	pub sequence: u64,
+	/// The SCALE-encoded statement.
+	pub statement: Bytes,
+}
+
 /// Substrate statement RPC API
 #[rpc(client, server)]
 pub trait StatementApi {
@@ -57,4 +70,22 @@ pub trait StatementApi {
 	/// Remove a statement from the store.
 	#[method(name = "statement_remove")]
 	fn remove(&self, statement_hash: [u8; 32]) -> RpcResult<()>;
+
+	/// Subscribe to the statements accepted by the store which include all topics and whose
+	/// decryption key is identified as `dest`, or that have no `DecryptionKey` field if `dest` is
+	/// not provided.
+	///
+	/// If `from` is provided, the matching statements already in the store with a sequence number
+	/// greater than `from` are reported first.
+	#[subscription(
+		name = "statement_subscribeStatements" => "statement_statement",
+		unsubscribe = "statement_unsubscribeStatements",
+		item = StatementNotification,
+	)]
+	fn subscribe_statements(
+		&self,
+		match_all_topics: Vec<[u8; 32]>,
+		dest: Option<[u8; 32]>,
+		from: Option<u64>,
+	);
 }
@@ -71,6 +71,20 @@ pub struct PeerInfo<Hash, Number> {
 	pub best_number: Number,
 }
 
+/// Banned peer information
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BannedPeer {
+	/// Peer ID
+	pub peer_id: String,
+	/// Current reputation of the peer
+	pub reputation: i32,
+	/// Reason of the ban
+	pub reason: String,
+	/// UNIX timestamp, in seconds, at which the peer was banned
+	pub banned_at: u64,
+}
+
 /// The role the node is running as
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub enum NodeRole {
@@ -90,6 +104,21 @@ pub struct SyncState<Number> {
 	pub current_block: Number,
 	/// Height of the highest block in the network.
 	pub highest_block: Number,
+	/// Download of the block history missing after warp sync, if in progress.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub gap_sync: Option<GapSyncState<Number>>,
+}
+
+/// The state of the download of the block history missing after warp sync.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GapSyncState<Number> {
+	/// Height of the first block of the history gap.
+	pub starting_block: Number,
+	/// Height of the highest block of the gap that is downloaded.
+	pub current_block: Number,
+	/// Height of the last block of the history gap.
+	pub target_block: Number,
 }
 
 #[cfg(test)]
@@ -109,6 +138,20 @@ mod tests {
 		);
 	}
 
+	#[test]
+	fn should_serialize_banned_peer() {
+		assert_eq!(
+			::serde_json::to_string(&BannedPeer {
+				peer_id: "2".into(),
+				reputation: -1000,
+				reason: "a".into(),
+				banned_at: 6,
+			})
+			.unwrap(),
+			r#"{"peerId":"2","reputation":-1000,"reason":"a","bannedAt":6}"#,
+		);
+	}
+
 	#[test]
 	fn should_serialize_peer_info() {
 		assert_eq!(
@@ -130,6 +173,7 @@ mod tests {
 				starting_block: 12u32,
 				current_block: 50u32,
 				highest_block: 128u32,
+				gap_sync: None,
 			})
 			.unwrap(),
 			r#"{"startingBlock":12,"currentBlock":50,"highestBlock":128}"#,
@@ -140,9 +184,25 @@ mod tests {
 				starting_block: 12u32,
 				current_block: 50u32,
 				highest_block: 50u32,
+				gap_sync: None,
 			})
 			.unwrap(),
 			r#"{"startingBlock":12,"currentBlock":50,"highestBlock":50}"#,
 		);
+
+		assert_eq!(
+			::serde_json::to_string(&SyncState {
+				starting_block: 1000u32,
+				current_block: 1000u32,
+				highest_block: 1000u32,
+				gap_sync: Some(GapSyncState {
+					starting_block: 1u32,
+					current_block: 100u32,
+					target_block: 999u32,
+				}),
+			})
+			.unwrap(),
+			r#"{"startingBlock":1000,"currentBlock":1000,"highestBlock":1000,"gapSync":{"startingBlock":1,"currentBlock":100,"targetBlock":999}}"#,
+		);
 	}
 }
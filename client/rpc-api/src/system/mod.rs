@@ -23,7 +23,9 @@ use jsonrpsee::{
 	proc_macros::rpc,
 };
 
-pub use self::helpers::{Health, NodeRole, PeerInfo, SyncState, SystemInfo};
+pub use self::helpers::{
+	BannedPeer, GapSyncState, Health, NodeRole, PeerInfo, SyncState, SystemInfo,
+};
 
 pub mod error;
 pub mod helpers;
@@ -100,6 +102,16 @@ pub trait SystemApi<Hash, Number> {
 	#[method(name = "system_reservedPeers")]
 	async fn system_reserved_peers(&self) -> RpcResult<Vec<String>>;
 
+	/// Returns the list of banned peers, with their current reputation and the reason they were
+	/// banned for.
+	#[method(name = "system_bannedPeers")]
+	async fn system_banned_peers(&self) -> RpcResult<Vec<BannedPeer>>;
+
+	/// Lift the ban of a peer. The string should encode only the PeerId
+	/// e.g. `QmSk5HQbn6LhUwDiNMseVUjuRYhEtYj4aUZ6WfWoGURpdV`.
+	#[method(name = "system_unbanPeer")]
+	async fn system_unban_peer(&self, peer_id: String) -> RpcResult<()>;
+
 	/// Returns the roles the node is running as.
 	#[method(name = "system_nodeRoles")]
 	async fn system_node_roles(&self) -> RpcResult<Vec<NodeRole>>;
@@ -0,0 +1,74 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Error helpers for `archive` RPC module.
+
+use jsonrpsee::{
+	core::Error as RpcError,
+	types::error::{CallError, ErrorObject},
+};
+use sp_blockchain::Error as BlockchainError;
+
+/// Archive RPC errors.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+	/// Invalid parameter provided to the RPC method.
+	#[error("Invalid parameter: {0}")]
+	InvalidParam(String),
+	/// Fetch block header error.
+	#[error("Could not fetch block header: {0}")]
+	FetchBlockHeader(BlockchainError),
+	/// Fetch block body error.
+	#[error("Could not fetch block body: {0}")]
+	FetchBlockBody(BlockchainError),
+	/// Fetch the leaves of the blockchain error.
+	#[error("Could not fetch the leaves: {0}")]
+	FetchLeaves(BlockchainError),
+}
+
+// Base code for all `archive` errors.
+const BASE_ERROR: i32 = 3000;
+/// Invalid parameter error.
+const INVALID_PARAM_ERROR: i32 = BASE_ERROR + 1;
+/// Fetch block header error.
+const FETCH_BLOCK_HEADER_ERROR: i32 = BASE_ERROR + 2;
+/// Fetch block body error.
+const FETCH_BLOCK_BODY_ERROR: i32 = BASE_ERROR + 3;
+/// Fetch the leaves of the blockchain error.
+const FETCH_LEAVES_ERROR: i32 = BASE_ERROR + 4;
+
+impl From<Error> for ErrorObject<'static> {
+	fn from(e: Error) -> Self {
+		let msg = e.to_string();
+
+		match e {
+			Error::InvalidParam(_) => ErrorObject::owned(INVALID_PARAM_ERROR, msg, None::<()>),
+			Error::FetchBlockHeader(_) =>
+				ErrorObject::owned(FETCH_BLOCK_HEADER_ERROR, msg, None::<()>),
+			Error::FetchBlockBody(_) => ErrorObject::owned(FETCH_BLOCK_BODY_ERROR, msg, None::<()>),
+			Error::FetchLeaves(_) => ErrorObject::owned(FETCH_LEAVES_ERROR, msg, None::<()>),
+		}
+		.into()
+	}
+}
+
+impl From<Error> for RpcError {
+	fn from(e: Error) -> Self {
+		CallError::Custom(e.into()).into()
+	}
+}
@@ -0,0 +1,510 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::common::events::{
+	ArchiveStorageResult, MethodResult, PaginatedStorageQuery, StorageQueryType, StorageResult,
+	StorageResultType,
+};
+
+use super::*;
+
+use assert_matches::assert_matches;
+use codec::{Decode, Encode};
+use jsonrpsee::{
+	core::error::Error,
+	rpc_params,
+	types::{error::CallError, EmptyServerParams as EmptyParams},
+	RpcModule,
+};
+use sc_block_builder::BlockBuilderProvider;
+use sc_client_api::ChildInfo;
+use sp_blockchain::HeaderBackend;
+use sp_consensus::BlockOrigin;
+use sp_core::{hexdisplay::HexDisplay, Blake2Hasher, Hasher};
+use sp_runtime::{traits::Header as HeaderT, SaturatedConversion};
+use std::sync::Arc;
+use substrate_test_runtime::Transfer;
+use substrate_test_runtime_client::{
+	prelude::*, runtime, Backend, BlockBuilderExt, Client, ClientBlockImportExt,
+};
+
+const CHAIN_GENESIS: [u8; 32] = [0; 32];
+const INVALID_HASH: [u8; 32] = [1; 32];
+const KEY: &[u8] = b":mock";
+const VALUE: &[u8] = b"hello world";
+const CHILD_STORAGE_KEY: &[u8] = b"child";
+const CHILD_VALUE: &[u8] = b"child value";
+const MAX_DESCENDANT_RESPONSES: usize = 3;
+const MAX_QUERIED_ITEMS: usize = 4;
+
+type Header = substrate_test_runtime_client::runtime::Header;
+type Block = substrate_test_runtime_client::runtime::Block;
+
+fn setup_api(
+	max_descendant_responses: usize,
+	max_queried_items: usize,
+) -> (Arc<Client<Backend>>, RpcModule<Archive<Backend, Block, Client<Backend>>>) {
+	let child_info = ChildInfo::new_default(CHILD_STORAGE_KEY);
+	let builder = TestClientBuilder::new().add_extra_child_storage(
+		&child_info,
+		KEY.to_vec(),
+		CHILD_VALUE.to_vec(),
+	);
+	let backend = builder.backend();
+	let client = Arc::new(builder.build());
+
+	let api = Archive::new(
+		client.clone(),
+		backend,
+		CHAIN_GENESIS,
+		ArchiveConfig { max_descendant_responses, max_queried_items },
+	)
+	.into_rpc();
+
+	(client, api)
+}
+
+#[tokio::test]
+async fn archive_genesis() {
+	let (_client, api) = setup_api(MAX_DESCENDANT_RESPONSES, MAX_QUERIED_ITEMS);
+
+	let genesis: String =
+		api.call("archive_unstable_genesisHash", EmptyParams::new()).await.unwrap();
+	assert_eq!(genesis, format!("0x{:?}", HexDisplay::from(&CHAIN_GENESIS)));
+}
+
+#[tokio::test]
+async fn archive_body() {
+	let (mut client, api) = setup_api(MAX_DESCENDANT_RESPONSES, MAX_QUERIED_ITEMS);
+
+	// Invalid block hash.
+	let invalid_hash = format!("0x{:?}", HexDisplay::from(&INVALID_HASH));
+	let body: Option<Vec<String>> =
+		api.call("archive_unstable_body", [invalid_hash]).await.unwrap();
+	assert!(body.is_none());
+
+	// Import a new block with an extrinsic.
+	let mut builder = client.new_block(Default::default()).unwrap();
+	builder
+		.push_transfer(runtime::Transfer {
+			from: AccountKeyring::Alice.into(),
+			to: AccountKeyring::Ferdie.into(),
+			amount: 42,
+			nonce: 0,
+		})
+		.unwrap();
+	let block = builder.build().unwrap().block;
+	let block_hash = format!("{:?}", block.header.hash());
+	client.import(BlockOrigin::Own, block.clone()).await.unwrap();
+
+	let expected_tx = hex_string(&block.extrinsics[0].encode());
+
+	let body: Vec<String> = api.call("archive_unstable_body", [block_hash]).await.unwrap();
+	assert_eq!(vec![expected_tx], body);
+}
+
+#[tokio::test]
+async fn archive_header() {
+	let (mut client, api) = setup_api(MAX_DESCENDANT_RESPONSES, MAX_QUERIED_ITEMS);
+
+	// Invalid block hash.
+	let invalid_hash = format!("0x{:?}", HexDisplay::from(&INVALID_HASH));
+	let header: Option<String> = api.call("archive_unstable_header", [invalid_hash]).await.unwrap();
+	assert!(header.is_none());
+
+	// Import a new block.
+	let block = client.new_block(Default::default()).unwrap().build().unwrap().block;
+	let block_hash = format!("{:?}", block.header.hash());
+	client.import(BlockOrigin::Own, block.clone()).await.unwrap();
+
+	let header: String = api.call("archive_unstable_header", [block_hash]).await.unwrap();
+	let bytes = array_bytes::hex2bytes(&header).unwrap();
+	let header: Header = Decode::decode(&mut &bytes[..]).unwrap();
+	assert_eq!(header, block.header);
+}
+
+#[tokio::test]
+async fn archive_finalized_height() {
+	let (client, api) = setup_api(MAX_DESCENDANT_RESPONSES, MAX_QUERIED_ITEMS);
+
+	let client_height: u64 = client.info().finalized_number.saturated_into();
+
+	let height: u64 =
+		api.call("archive_unstable_finalizedHeight", EmptyParams::new()).await.unwrap();
+
+	assert_eq!(client_height, height);
+}
+
+#[tokio::test]
+async fn archive_hash_by_height() {
+	let (mut client, api) = setup_api(MAX_DESCENDANT_RESPONSES, MAX_QUERIED_ITEMS);
+
+	// Genesis height.
+	let hashes: Vec<String> = api.call("archive_unstable_hashByHeight", [0]).await.unwrap();
+	assert_eq!(hashes, vec![format!("{:?}", client.genesis_hash())]);
+
+	// Block tree:
+	// finalized  -> block 1 -> block 2
+	//                       -> block 2'
+	let block_1 = client.new_block(Default::default()).unwrap().build().unwrap().block;
+	let block_1_hash = block_1.header.hash();
+	client.import(BlockOrigin::Own, block_1.clone()).await.unwrap();
+
+	let block_2 = client
+		.new_block_at(block_1_hash, Default::default(), false)
+		.unwrap()
+		.build()
+		.unwrap()
+		.block;
+	let block_2_hash = block_2.header.hash();
+	client.import(BlockOrigin::Own, block_2.clone()).await.unwrap();
+
+	// Create a fork at block 1 by pushing an extrinsic to produce a different hash.
+	let mut block_builder = client.new_block_at(block_1_hash, Default::default(), false).unwrap();
+	block_builder
+		.push_transfer(Transfer {
+			from: AccountKeyring::Alice.into(),
+			to: AccountKeyring::Ferdie.into(),
+			amount: 41,
+			nonce: 0,
+		})
+		.unwrap();
+	let block_2_fork = block_builder.build().unwrap().block;
+	let block_2_fork_hash = block_2_fork.header.hash();
+	client.import(BlockOrigin::Own, block_2_fork.clone()).await.unwrap();
+
+	// Unfinalized height of block 1 has a single block.
+	let hashes: Vec<String> = api.call("archive_unstable_hashByHeight", [1]).await.unwrap();
+	assert_eq!(hashes, vec![format!("{:?}", block_1_hash)]);
+
+	// Both forks are reported at height 2.
+	let mut hashes: Vec<String> = api.call("archive_unstable_hashByHeight", [2]).await.unwrap();
+	hashes.sort();
+	let mut expected = vec![format!("{:?}", block_2_hash), format!("{:?}", block_2_fork_hash)];
+	expected.sort();
+	assert_eq!(hashes, expected);
+
+	// Height past the best block.
+	let hashes: Vec<String> = api.call("archive_unstable_hashByHeight", [3]).await.unwrap();
+	assert!(hashes.is_empty());
+
+	// Finalized height returns the canonical block.
+	client.finalize_block(block_2_hash, None).unwrap();
+	let hashes: Vec<String> = api.call("archive_unstable_hashByHeight", [2]).await.unwrap();
+	assert_eq!(hashes, vec![format!("{:?}", block_2_hash)]);
+}
+
+#[tokio::test]
+async fn archive_call() {
+	let (mut client, api) = setup_api(MAX_DESCENDANT_RESPONSES, MAX_QUERIED_ITEMS);
+	let invalid_hash = format!("0x{:?}", HexDisplay::from(&INVALID_HASH));
+
+	// Invalid parameter (non-hex).
+	let err = api
+		.call::<_, serde_json::Value>(
+			"archive_unstable_call",
+			[&invalid_hash, "BabeApi_current_epoch", "0x0"],
+		)
+		.await
+		.unwrap_err();
+	assert_matches!(err, Error::Call(CallError::Custom(ref err)) if err.code() == 3001 && err.message().contains("Invalid parameter"));
+
+	// Pass a valid parameter for an invalid block hash.
+	let result: MethodResult = api
+		.call("archive_unstable_call", [&invalid_hash, "BabeApi_current_epoch", "0x00"])
+		.await
+		.unwrap();
+	assert_matches!(result, MethodResult::Err(_));
+
+	let block = client.new_block(Default::default()).unwrap().build().unwrap().block;
+	let block_hash = format!("{:?}", block.header.hash());
+	client.import(BlockOrigin::Own, block.clone()).await.unwrap();
+
+	// Valid call.
+	let alice_id = AccountKeyring::Alice.to_account_id();
+	// Hex encoded scale encoded bytes representing the call parameters.
+	let call_parameters = hex_string(&alice_id.encode());
+	let result: MethodResult = api
+		.call(
+			"archive_unstable_call",
+			[&block_hash, "AccountNonceApi_account_nonce", &call_parameters],
+		)
+		.await
+		.unwrap();
+	let expected = MethodResult::ok("0x0000000000000000");
+	assert_eq!(result, expected);
+}
+
+#[tokio::test]
+async fn archive_storage_hashes_values() {
+	let (mut client, api) = setup_api(MAX_DESCENDANT_RESPONSES, MAX_QUERIED_ITEMS);
+
+	let mut block_builder = client.new_block(Default::default()).unwrap();
+	block_builder.push_storage_change(b":a".to_vec(), Some(b"a".to_vec())).unwrap();
+	block_builder
+		.push_storage_change(b":AA".to_vec(), Some(b"AA".to_vec()))
+		.unwrap();
+	let block = block_builder.build().unwrap().block;
+	let block_hash = format!("{:?}", block.header.hash());
+	client.import(BlockOrigin::Own, block.clone()).await.unwrap();
+
+	let key = hex_string(&b":A");
+	let items: Vec<PaginatedStorageQuery<String>> = vec![
+		PaginatedStorageQuery {
+			key: key.clone(),
+			query_type: StorageQueryType::DescendantsHashes,
+			pagination_start_key: None,
+		},
+		PaginatedStorageQuery {
+			key: key.clone(),
+			query_type: StorageQueryType::DescendantsValues,
+			pagination_start_key: None,
+		},
+		PaginatedStorageQuery {
+			key: key.clone(),
+			query_type: StorageQueryType::Hash,
+			pagination_start_key: None,
+		},
+		PaginatedStorageQuery {
+			key: key.clone(),
+			query_type: StorageQueryType::Value,
+			pagination_start_key: None,
+		},
+	];
+
+	let result: ArchiveStorageResult = api
+		.call("archive_unstable_storage", rpc_params![&block_hash, items.clone()])
+		.await
+		.unwrap();
+
+	let expected_key = hex_string(&b":AA");
+	let expected_hash = format!("{:?}", Blake2Hasher::hash(&b"AA"[..]));
+	let expected_value = hex_string(&b"AA");
+
+	match result {
+		ArchiveStorageResult::Ok(ok) => {
+			assert_eq!(ok.discarded_items, 0);
+			assert_eq!(
+				ok.result,
+				vec![
+					StorageResult {
+						key: expected_key.clone(),
+						result: StorageResultType::Hash(expected_hash),
+						child_trie_key: None,
+					},
+					StorageResult {
+						key: expected_key,
+						result: StorageResultType::Value(expected_value),
+						child_trie_key: None,
+					},
+				]
+			);
+		},
+		_ => panic!("Unexpected result"),
+	};
+
+	// Nothing is stored at `:A`, the exact key queries have no result.
+	let result: ArchiveStorageResult = api
+		.call("archive_unstable_storage", rpc_params![&block_hash, vec![items[3].clone()]])
+		.await
+		.unwrap();
+	assert_matches!(result, ArchiveStorageResult::Ok(ok) if ok.result.is_empty());
+}
+
+#[tokio::test]
+async fn archive_storage_closest_merkle_value() {
+	let (mut client, api) = setup_api(MAX_DESCENDANT_RESPONSES, MAX_QUERIED_ITEMS);
+
+	let mut block_builder = client.new_block(Default::default()).unwrap();
+	block_builder.push_storage_change(b":AAAA".to_vec(), Some(vec![1; 64])).unwrap();
+	block_builder.push_storage_change(b":AAAB".to_vec(), Some(vec![2; 64])).unwrap();
+	let block = block_builder.build().unwrap().block;
+	let block_hash = format!("{:?}", block.header.hash());
+	client.import(BlockOrigin::Own, block.clone()).await.unwrap();
+
+	let query = |key: &[u8]| PaginatedStorageQuery {
+		key: hex_string(&key),
+		query_type: StorageQueryType::ClosestDescendantMerkleValue,
+		pagination_start_key: None,
+	};
+
+	let merkle_value = |result: ArchiveStorageResult| match result {
+		ArchiveStorageResult::Ok(ok) => ok.result.into_iter().next().map(|res| match res.result {
+			StorageResultType::ClosestDescendantMerkleValue(value) => value,
+			_ => panic!("Unexpected result type"),
+		}),
+		_ => panic!("Unexpected result"),
+	};
+
+	// `:AAA` and `:AA` share the same closest descendant: the branch of `:AAAA` and `:AAAB`.
+	let result: ArchiveStorageResult = api
+		.call("archive_unstable_storage", rpc_params![&block_hash, vec![query(b":AAA")]])
+		.await
+		.unwrap();
+	let branch = merkle_value(result).unwrap();
+	let result: ArchiveStorageResult = api
+		.call("archive_unstable_storage", rpc_params![&block_hash, vec![query(b":AA")]])
+		.await
+		.unwrap();
+	assert_eq!(merkle_value(result).unwrap(), branch);
+
+	// The leaf has a different merkle value.
+	let result: ArchiveStorageResult = api
+		.call("archive_unstable_storage", rpc_params![&block_hash, vec![query(b":AAAA")]])
+		.await
+		.unwrap();
+	assert_ne!(merkle_value(result).unwrap(), branch);
+
+	// No descendant under this key.
+	let result: ArchiveStorageResult = api
+		.call("archive_unstable_storage", rpc_params![&block_hash, vec![query(b":AAAC")]])
+		.await
+		.unwrap();
+	assert!(merkle_value(result).is_none());
+}
+
+#[tokio::test]
+async fn archive_storage_paginate_iterations() {
+	// Limit the descendant responses to 1 to force pagination.
+	let (mut client, api) = setup_api(1, MAX_QUERIED_ITEMS);
+
+	let mut block_builder = client.new_block(Default::default()).unwrap();
+	block_builder.push_storage_change(b":m".to_vec(), Some(b"a".to_vec())).unwrap();
+	block_builder
+		.push_storage_change(b":mo".to_vec(), Some(b"ab".to_vec()))
+		.unwrap();
+	block_builder
+		.push_storage_change(b":moc".to_vec(), Some(b"abc".to_vec()))
+		.unwrap();
+	let block = block_builder.build().unwrap().block;
+	let block_hash = format!("{:?}", block.header.hash());
+	client.import(BlockOrigin::Own, block.clone()).await.unwrap();
+
+	let mut pagination_start_key = None;
+	let mut reported = Vec::new();
+	loop {
+		let items = vec![PaginatedStorageQuery {
+			key: hex_string(&b":m"),
+			query_type: StorageQueryType::DescendantsValues,
+			pagination_start_key: pagination_start_key.clone(),
+		}];
+		let result: ArchiveStorageResult = api
+			.call("archive_unstable_storage", rpc_params![&block_hash, items])
+			.await
+			.unwrap();
+
+		let ArchiveStorageResult::Ok(ok) = result else { panic!("Unexpected result") };
+		if ok.result.is_empty() {
+			break
+		}
+		assert_eq!(ok.result.len(), 1);
+
+		let key = ok.result[0].key.clone();
+		pagination_start_key = Some(key.clone());
+		reported.push(key);
+	}
+
+	assert_eq!(reported, vec![hex_string(&b":m"), hex_string(&b":mo"), hex_string(&b":moc")]);
+}
+
+#[tokio::test]
+async fn archive_storage_discarded_items() {
+	// One queried item allowed at a time.
+	let (mut client, api) = setup_api(MAX_DESCENDANT_RESPONSES, 1);
+
+	let mut block_builder = client.new_block(Default::default()).unwrap();
+	block_builder.push_storage_change(KEY.to_vec(), Some(VALUE.to_vec())).unwrap();
+	let block = block_builder.build().unwrap().block;
+	let block_hash = format!("{:?}", block.header.hash());
+	client.import(BlockOrigin::Own, block.clone()).await.unwrap();
+
+	let items = vec![
+		PaginatedStorageQuery {
+			key: hex_string(&KEY),
+			query_type: StorageQueryType::Value,
+			pagination_start_key: None,
+		},
+		PaginatedStorageQuery {
+			key: hex_string(&KEY),
+			query_type: StorageQueryType::Hash,
+			pagination_start_key: None,
+		},
+	];
+
+	let result: ArchiveStorageResult = api
+		.call("archive_unstable_storage", rpc_params![&block_hash, items])
+		.await
+		.unwrap();
+	match result {
+		ArchiveStorageResult::Ok(ok) => {
+			assert_eq!(ok.result.len(), 1);
+			assert_eq!(ok.result[0].result, StorageResultType::Value(hex_string(&VALUE)));
+			assert_eq!(ok.discarded_items, 1);
+		},
+		_ => panic!("Unexpected result"),
+	};
+}
+
+#[tokio::test]
+async fn archive_storage_child_trie() {
+	let (client, api) = setup_api(MAX_DESCENDANT_RESPONSES, MAX_QUERIED_ITEMS);
+	let genesis_hash = format!("{:?}", client.genesis_hash());
+
+	let items = vec![PaginatedStorageQuery {
+		key: hex_string(&KEY),
+		query_type: StorageQueryType::Value,
+		pagination_start_key: None,
+	}];
+
+	let result: ArchiveStorageResult = api
+		.call(
+			"archive_unstable_storage",
+			rpc_params![&genesis_hash, items, hex_string(&CHILD_STORAGE_KEY)],
+		)
+		.await
+		.unwrap();
+	match result {
+		ArchiveStorageResult::Ok(ok) => {
+			assert_eq!(
+				ok.result,
+				vec![StorageResult {
+					key: hex_string(&KEY),
+					result: StorageResultType::Value(hex_string(&CHILD_VALUE)),
+					child_trie_key: Some(hex_string(&CHILD_STORAGE_KEY)),
+				}]
+			);
+		},
+		_ => panic!("Unexpected result"),
+	};
+
+	// Pagination start key for a non-descendant query is rejected.
+	let items = vec![PaginatedStorageQuery {
+		key: hex_string(&KEY),
+		query_type: StorageQueryType::Value,
+		pagination_start_key: Some(hex_string(&KEY)),
+	}];
+	let err = api
+		.call::<_, serde_json::Value>("archive_unstable_storage", rpc_params![&genesis_hash, items])
+		.await
+		.unwrap_err();
+	assert_matches!(err, Error::Call(CallError::Custom(ref err)) if err.code() == 3001);
+}
+
+fn hex_string<Data: AsRef<[u8]>>(data: &Data) -> String {
+	format!("0x{:?}", HexDisplay::from(&data.as_ref()))
+}
@@ -19,7 +19,10 @@
 #![allow(non_snake_case)]
 
 //! API trait of the chain head.
-use crate::chain_head::event::{ChainHeadEvent, FollowEvent, NetworkConfig};
+use crate::{
+	chain_head::event::{ChainHeadEvent, ChainHeadStorageEvent, FollowEvent, NetworkConfig},
+	common::events::StorageQuery,
+};
 use jsonrpsee::{core::RpcResult, proc_macros::rpc};
 
 #[rpc(client, server)]
@@ -86,7 +89,13 @@ pub trait ChainHeadApi<Hash> {
 	#[method(name = "chainHead_unstable_genesisHash", blocking)]
 	fn chain_head_unstable_genesis_hash(&self) -> RpcResult<String>;
 
-	/// Return a storage entry at a specific block's state.
+	/// Returns storage entries at a specific block's state.
+	///
+	/// Multiple storage queries can be batched into a single request. The results of the
+	/// descendant queries are reported in pages: after each page that leaves results
+	/// unreported, the `waitForContinue` event is generated and no further results are
+	/// produced until `chainHead_unstable_continue` is called with the ID of this
+	/// subscription.
 	///
 	/// # Unstable
 	///
@@ -94,14 +103,14 @@ pub trait ChainHeadApi<Hash> {
 	#[subscription(
 		name = "chainHead_unstable_storage",
 		unsubscribe = "chainHead_unstable_stopStorage",
-		item = ChainHeadEvent<String>,
+		item = ChainHeadStorageEvent,
 	)]
 	fn chain_head_unstable_storage(
 		&self,
 		follow_subscription: String,
 		hash: Hash,
-		key: String,
-		child_key: Option<String>,
+		items: Vec<StorageQuery<String>>,
+		child_trie: Option<String>,
 		network_config: Option<NetworkConfig>,
 	);
 
@@ -134,4 +143,19 @@ pub trait ChainHeadApi<Hash> {
 	/// This method is unstable and subject to change in the future.
 	#[method(name = "chainHead_unstable_unpin", blocking)]
 	fn chain_head_unstable_unpin(&self, follow_subscription: String, hash: Hash) -> RpcResult<()>;
+
+	/// Resumes a storage fetch started with `chainHead_unstable_storage` after it has generated
+	/// a `waitForContinue` event.
+	///
+	/// The operation ID is the subscription ID of the `chainHead_unstable_storage` call.
+	///
+	/// # Unstable
+	///
+	/// This method is unstable and subject to change in the future.
+	#[method(name = "chainHead_unstable_continue", blocking)]
+	fn chain_head_unstable_continue(
+		&self,
+		follow_subscription: String,
+		operation_id: String,
+	) -> RpcResult<()>;
 }
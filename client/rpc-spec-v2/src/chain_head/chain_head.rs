@@ -22,10 +22,15 @@ use crate::{
 	chain_head::{
 		api::ChainHeadApiServer,
 		chain_head_follow::ChainHeadFollower,
+		chain_head_storage::ChainHeadStorage,
 		error::Error as ChainHeadRpcError,
-		event::{ChainHeadEvent, ChainHeadResult, ErrorEvent, FollowEvent, NetworkConfig},
+		event::{
+			ChainHeadEvent, ChainHeadResult, ChainHeadStorageEvent, ErrorEvent, FollowEvent,
+			NetworkConfig,
+		},
 		subscription::{SubscriptionManagement, SubscriptionManagementError},
 	},
+	common::events::StorageQuery,
 	SubscriptionTaskExecutor,
 };
 use codec::Encode;
@@ -42,12 +47,72 @@ use sc_client_api::{
 };
 use sp_api::CallApiAt;
 use sp_blockchain::{Error as BlockChainError, HeaderBackend, HeaderMetadata};
-use sp_core::{hexdisplay::HexDisplay, storage::well_known_keys, traits::CallContext, Bytes};
+use sp_core::{hexdisplay::HexDisplay, traits::CallContext, Bytes};
 use sp_runtime::traits::Block as BlockT;
 use std::{marker::PhantomData, sync::Arc, time::Duration};
 
 pub(crate) const LOG_TARGET: &str = "rpc-spec-v2";
 
+/// The configuration of [`ChainHead`].
+#[derive(Debug, Clone)]
+pub struct ChainHeadConfig {
+	/// The maximum number of pinned blocks across all subscriptions.
+	pub global_max_pinned_blocks: usize,
+	/// The maximum duration that a block is allowed to be pinned per subscription.
+	pub subscription_max_pinned_duration: Duration,
+	/// The maximum number of blocks a single subscription is allowed to pin.
+	///
+	/// A subscription that does not unpin its blocks fast enough is terminated
+	/// with the `Stop` event once it exceeds this limit.
+	pub subscription_max_pinned_blocks: usize,
+	/// The maximum number of ongoing operations (`body`, `call` and `storage`)
+	/// per subscription.
+	///
+	/// Operations started past this limit produce the `inaccessible` event.
+	pub subscription_max_ongoing_operations: usize,
+	/// The maximum number of items reported by the `storage` operation before
+	/// waiting for the user to call `continue`.
+	pub operation_max_storage_items: usize,
+	/// The maximum number of `follow` subscriptions per connection.
+	pub max_follow_subscriptions_per_connection: usize,
+}
+
+/// Maximum pinned blocks across all connections.
+/// This number is large enough to consider immediate blocks.
+/// Note: This should never exceed the `PINNING_CACHE_SIZE` from client/db.
+const MAX_PINNED_BLOCKS: usize = 512;
+
+/// Any block of any subscription should not be pinned more than
+/// this constant. When a subscription contains a block older than this,
+/// the subscription becomes subject to termination.
+/// Note: This should be enough for immediate blocks.
+const MAX_PINNED_DURATION: Duration = Duration::from_secs(60);
+
+/// The maximum number of blocks pinned by a single subscription.
+const MAX_PINNED_BLOCKS_PER_SUBSCRIPTION: usize = 256;
+
+/// The maximum number of ongoing operations per subscription.
+const MAX_ONGOING_OPERATIONS: usize = 16;
+
+/// The maximum number of items reported by the `storage` operation at once.
+const MAX_STORAGE_ITER_ITEMS: usize = 10;
+
+/// The maximum number of `follow` subscriptions per connection.
+const MAX_FOLLOW_SUBSCRIPTIONS_PER_CONNECTION: usize = 4;
+
+impl Default for ChainHeadConfig {
+	fn default() -> Self {
+		ChainHeadConfig {
+			global_max_pinned_blocks: MAX_PINNED_BLOCKS,
+			subscription_max_pinned_duration: MAX_PINNED_DURATION,
+			subscription_max_pinned_blocks: MAX_PINNED_BLOCKS_PER_SUBSCRIPTION,
+			subscription_max_ongoing_operations: MAX_ONGOING_OPERATIONS,
+			operation_max_storage_items: MAX_STORAGE_ITER_ITEMS,
+			max_follow_subscriptions_per_connection: MAX_FOLLOW_SUBSCRIPTIONS_PER_CONNECTION,
+		}
+	}
+}
+
 /// An API for chain head RPC calls.
 pub struct ChainHead<BE: Backend<Block>, Block: BlockT, Client> {
 	/// Substrate client.
@@ -60,6 +125,8 @@ pub struct ChainHead<BE: Backend<Block>, Block: BlockT, Client> {
 	subscriptions: Arc<SubscriptionManagement<Block, BE>>,
 	/// The hexadecimal encoded hash of the genesis block.
 	genesis_hash: String,
+	/// The maximum number of items reported by the `storage` operation at once.
+	operation_max_storage_items: usize,
 	/// Phantom member to pin the block type.
 	_phantom: PhantomData<Block>,
 }
@@ -71,8 +138,7 @@ impl<BE: Backend<Block>, Block: BlockT, Client> ChainHead<BE, Block, Client> {
 		backend: Arc<BE>,
 		executor: SubscriptionTaskExecutor,
 		genesis_hash: GenesisHash,
-		max_pinned_blocks: usize,
-		max_pinned_duration: Duration,
+		config: ChainHeadConfig,
 	) -> Self {
 		let genesis_hash = format!("0x{:?}", HexDisplay::from(&genesis_hash.as_ref()));
 
@@ -81,11 +147,15 @@ impl<BE: Backend<Block>, Block: BlockT, Client> ChainHead<BE, Block, Client> {
 			backend: backend.clone(),
 			executor,
 			subscriptions: Arc::new(SubscriptionManagement::new(
-				max_pinned_blocks,
-				max_pinned_duration,
+				config.global_max_pinned_blocks,
+				config.subscription_max_pinned_duration,
+				config.subscription_max_pinned_blocks,
+				config.subscription_max_ongoing_operations,
+				config.max_follow_subscriptions_per_connection,
 				backend,
 			)),
 			genesis_hash,
+			operation_max_storage_items: config.operation_max_storage_items,
 			_phantom: PhantomData,
 		}
 	}
@@ -113,6 +183,20 @@ impl<BE: Backend<Block>, Block: BlockT, Client> ChainHead<BE, Block, Client> {
 	}
 }
 
+/// Get the ID of the connection that opened the subscription.
+///
+/// JsonRPSee doesn't expose the connection ID to the method handlers, although the
+/// sink tracks it internally. Extract it from the `Debug` representation of the sink.
+/// Subscriptions of an unknown connection share the limits of a single connection.
+fn connection_id(sink: &SubscriptionSink) -> usize {
+	let debug = format!("{:?}", sink);
+	debug
+		.split_once("uniq_sub: SubscriptionKey { conn_id: ")
+		.and_then(|(_, rest)| rest.split(|c: char| !c.is_ascii_digit()).next())
+		.and_then(|id| id.parse().ok())
+		.unwrap_or(usize::MAX)
+}
+
 /// Parse hex-encoded string parameter as raw bytes.
 ///
 /// If the parsing fails, the subscription is rejected.
@@ -162,10 +246,19 @@ where
 			},
 		};
 		// Keep track of the subscription.
-		let Some(rx_stop) = self.subscriptions.insert_subscription(sub_id.clone(), runtime_updates) else {
+		let connection = connection_id(&sink);
+		let Some(rx_stop) =
+			self.subscriptions
+				.insert_subscription(sub_id.clone(), runtime_updates, connection)
+		else {
 			// Inserting the subscription can only fail if the JsonRPSee
-			// generated a duplicate subscription ID.
-			debug!(target: LOG_TARGET, "[follow][id={:?}] Subscription already accepted", sub_id);
+			// generated a duplicate subscription ID or the connection
+			// reached its maximum number of subscriptions.
+			debug!(
+				target: LOG_TARGET,
+				"[follow][id={:?}] Subscription already accepted or limit reached",
+				sub_id
+			);
 			let _ = sink.send(&FollowEvent::<Block::Hash>::Stop);
 			return Ok(())
 		};
@@ -203,13 +296,20 @@ where
 		let client = self.client.clone();
 		let subscriptions = self.subscriptions.clone();
 
-		let block_guard = match subscriptions.lock_block(&follow_subscription, hash) {
+		let block_guard = match subscriptions.lock_block_for_operation(&follow_subscription, hash) {
 			Ok(block) => block,
 			Err(SubscriptionManagementError::SubscriptionAbsent) => {
 				// Invalid invalid subscription ID.
 				let _ = sink.send(&ChainHeadEvent::<String>::Disjoint);
 				return Ok(())
 			},
+			Err(SubscriptionManagementError::ExceededOperations) => {
+				// Too many operations are in progress, the user can retry later.
+				let _ = sink.send(&ChainHeadEvent::<String>::Inaccessible(ErrorEvent {
+					error: SubscriptionManagementError::ExceededOperations.to_string(),
+				}));
+				return Ok(())
+			},
 			Err(SubscriptionManagementError::BlockHashAbsent) => {
 				// Block is not part of the subscription.
 				let _ = sink.reject(ChainHeadRpcError::InvalidBlock);
@@ -285,90 +385,69 @@ where
 		mut sink: SubscriptionSink,
 		follow_subscription: String,
 		hash: Block::Hash,
-		key: String,
-		child_key: Option<String>,
+		items: Vec<StorageQuery<String>>,
+		child_trie: Option<String>,
 		_network_config: Option<NetworkConfig>,
 	) -> SubscriptionResult {
-		let key = StorageKey(parse_hex_param(&mut sink, key)?);
+		let mut storage_items = Vec::with_capacity(items.len());
+		for item in items {
+			let key = StorageKey(parse_hex_param(&mut sink, item.key)?);
+			storage_items.push(StorageQuery { key, query_type: item.query_type });
+		}
 
-		let child_key = child_key
-			.map(|child_key| parse_hex_param(&mut sink, child_key))
+		let child_trie = child_trie
+			.map(|child_trie| parse_hex_param(&mut sink, child_trie))
 			.transpose()?
 			.map(ChildInfo::new_default_from_vec);
 
-		let client = self.client.clone();
-		let subscriptions = self.subscriptions.clone();
+		let block_guard =
+			match self.subscriptions.lock_block_for_operation(&follow_subscription, hash) {
+				Ok(block) => block,
+				Err(SubscriptionManagementError::SubscriptionAbsent) => {
+					// Invalid invalid subscription ID.
+					let _ = sink.send(&ChainHeadStorageEvent::Disjoint);
+					return Ok(())
+				},
+				Err(SubscriptionManagementError::ExceededOperations) => {
+					// Too many operations are in progress, the user can retry later.
+					let _ = sink.send(&ChainHeadStorageEvent::Inaccessible(ErrorEvent {
+						error: SubscriptionManagementError::ExceededOperations.to_string(),
+					}));
+					return Ok(())
+				},
+				Err(SubscriptionManagementError::BlockHashAbsent) => {
+					// Block is not part of the subscription.
+					let _ = sink.reject(ChainHeadRpcError::InvalidBlock);
+					return Ok(())
+				},
+				Err(error) => {
+					let _ = sink.send(&ChainHeadStorageEvent::Error(ErrorEvent {
+						error: error.to_string(),
+					}));
+					return Ok(())
+				},
+			};
 
-		let block_guard = match subscriptions.lock_block(&follow_subscription, hash) {
-			Ok(block) => block,
-			Err(SubscriptionManagementError::SubscriptionAbsent) => {
-				// Invalid invalid subscription ID.
-				let _ = sink.send(&ChainHeadEvent::<String>::Disjoint);
-				return Ok(())
-			},
-			Err(SubscriptionManagementError::BlockHashAbsent) => {
-				// Block is not part of the subscription.
-				let _ = sink.reject(ChainHeadRpcError::InvalidBlock);
-				return Ok(())
-			},
-			Err(error) => {
-				let _ = sink.send(&ChainHeadEvent::<String>::Error(ErrorEvent {
-					error: error.to_string(),
-				}));
-				return Ok(())
+		// The subscription ID of this method identifies the operation
+		// for `chainHead_unstable_continue`.
+		let operation_id = match self.accept_subscription(&mut sink) {
+			Ok(operation_id) => operation_id,
+			Err(err) => {
+				sink.close(ChainHeadRpcError::InvalidSubscriptionID);
+				return Err(err)
 			},
 		};
 
+		let storage_client = ChainHeadStorage::<Client, Block, BE>::new(
+			self.client.clone(),
+			self.subscriptions.clone(),
+			follow_subscription,
+			operation_id,
+			self.operation_max_storage_items,
+		);
 		let fut = async move {
 			let _block_guard = block_guard;
-			// The child key is provided, use the key to query the child trie.
-			if let Some(child_key) = child_key {
-				// The child key must not be prefixed with ":child_storage:" nor
-				// ":child_storage:default:".
-				if well_known_keys::is_default_child_storage_key(child_key.storage_key()) ||
-					well_known_keys::is_child_storage_key(child_key.storage_key())
-				{
-					let _ = sink
-						.send(&ChainHeadEvent::Done(ChainHeadResult { result: None::<String> }));
-					return
-				}
-
-				let res = client
-					.child_storage(hash, &child_key, &key)
-					.map(|result| {
-						let result =
-							result.map(|storage| format!("0x{:?}", HexDisplay::from(&storage.0)));
-						ChainHeadEvent::Done(ChainHeadResult { result })
-					})
-					.unwrap_or_else(|error| {
-						ChainHeadEvent::Error(ErrorEvent { error: error.to_string() })
-					});
-				let _ = sink.send(&res);
-				return
-			}
-
-			// The main key must not be prefixed with b":child_storage:" nor
-			// b":child_storage:default:".
-			if well_known_keys::is_default_child_storage_key(&key.0) ||
-				well_known_keys::is_child_storage_key(&key.0)
-			{
-				let _ =
-					sink.send(&ChainHeadEvent::Done(ChainHeadResult { result: None::<String> }));
-				return
-			}
-
-			// Main root trie storage query.
-			let res = client
-				.storage(hash, &key)
-				.map(|result| {
-					let result =
-						result.map(|storage| format!("0x{:?}", HexDisplay::from(&storage.0)));
-					ChainHeadEvent::Done(ChainHeadResult { result })
-				})
-				.unwrap_or_else(|error| {
-					ChainHeadEvent::Error(ErrorEvent { error: error.to_string() })
-				});
-			let _ = sink.send(&res);
+			storage_client.generate_events(sink, hash, storage_items, child_trie).await;
 		};
 
 		self.executor.spawn("substrate-rpc-subscription", Some("rpc"), fut.boxed());
@@ -389,13 +468,20 @@ where
 		let client = self.client.clone();
 		let subscriptions = self.subscriptions.clone();
 
-		let block_guard = match subscriptions.lock_block(&follow_subscription, hash) {
+		let block_guard = match subscriptions.lock_block_for_operation(&follow_subscription, hash) {
 			Ok(block) => block,
 			Err(SubscriptionManagementError::SubscriptionAbsent) => {
 				// Invalid invalid subscription ID.
 				let _ = sink.send(&ChainHeadEvent::<String>::Disjoint);
 				return Ok(())
 			},
+			Err(SubscriptionManagementError::ExceededOperations) => {
+				// Too many operations are in progress, the user can retry later.
+				let _ = sink.send(&ChainHeadEvent::<String>::Inaccessible(ErrorEvent {
+					error: SubscriptionManagementError::ExceededOperations.to_string(),
+				}));
+				return Ok(())
+			},
 			Err(SubscriptionManagementError::BlockHashAbsent) => {
 				// Block is not part of the subscription.
 				let _ = sink.reject(ChainHeadRpcError::InvalidBlock);
@@ -460,4 +546,19 @@ where
 			Err(_) => Err(ChainHeadRpcError::InvalidBlock.into()),
 		}
 	}
+
+	fn chain_head_unstable_continue(
+		&self,
+		follow_subscription: String,
+		operation_id: String,
+	) -> RpcResult<()> {
+		match self.subscriptions.continue_operation(&follow_subscription, &operation_id) {
+			Ok(()) => Ok(()),
+			Err(SubscriptionManagementError::SubscriptionAbsent) => {
+				// Invalid invalid subscription ID.
+				Ok(())
+			},
+			Err(_) => Err(ChainHeadRpcError::InvalidContinue.into()),
+		}
+	}
 }
@@ -0,0 +1,281 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Implementation of the `chainHead_storage` method.
+
+use std::{collections::VecDeque, sync::Arc};
+
+use futures::{channel::mpsc, future, pin_mut, SinkExt};
+use jsonrpsee::SubscriptionSink;
+use log::debug;
+use sc_client_api::{Backend, ChildInfo, StorageKey, StorageProvider};
+use sp_runtime::traits::Block as BlockT;
+
+use crate::{
+	chain_head::{
+		chain_head::LOG_TARGET,
+		event::{ChainHeadStorageEvent, ErrorEvent, ItemsEvent},
+		subscription::SubscriptionManagement,
+	},
+	common::{
+		events::{StorageQuery, StorageQueryType, StorageResult},
+		storage::{is_child_storage_key, IterQueryType, QueryIter, QueryIterResult, Storage},
+	},
+};
+
+/// The number of events buffered before the operation waits for the sink.
+const STORAGE_EVENTS_BUFFER: usize = 16;
+
+/// Generates the events of the `chainHead_storage` method.
+pub struct ChainHeadStorage<Client, Block: BlockT, BE: Backend<Block>> {
+	/// Storage client.
+	client: Storage<Client, Block, BE>,
+	/// Subscriptions handle.
+	sub_handle: Arc<SubscriptionManagement<Block, BE>>,
+	/// The ID of the `follow` subscription that pinned the block.
+	follow_subscription: String,
+	/// The ID of this storage operation.
+	operation_id: String,
+	/// The maximum number of items reported by a single `Items` event of a descendant query.
+	///
+	/// After each such page, the operation waits for the user to call `continue`.
+	operation_max_storage_items: usize,
+}
+
+impl<Client, Block: BlockT, BE: Backend<Block>> ChainHeadStorage<Client, Block, BE> {
+	/// Constructs a new [`ChainHeadStorage`].
+	pub fn new(
+		client: Arc<Client>,
+		sub_handle: Arc<SubscriptionManagement<Block, BE>>,
+		follow_subscription: String,
+		operation_id: String,
+		operation_max_storage_items: usize,
+	) -> Self {
+		Self {
+			client: Storage::new(client),
+			sub_handle,
+			follow_subscription,
+			operation_id,
+			operation_max_storage_items,
+		}
+	}
+}
+
+/// Signals that no further events must be generated.
+struct OperationEnded;
+
+/// Stops waiting for `continue` when dropped, including when the operation is dropped while
+/// waiting.
+struct WaitingGuard<'a, Block: BlockT, BE: Backend<Block>> {
+	sub_handle: &'a SubscriptionManagement<Block, BE>,
+	follow_subscription: &'a str,
+	operation_id: &'a str,
+}
+
+impl<Block: BlockT, BE: Backend<Block>> Drop for WaitingGuard<'_, Block, BE> {
+	fn drop(&mut self) {
+		self.sub_handle.stop_waiting(self.follow_subscription, self.operation_id);
+	}
+}
+
+impl<Client, Block, BE> ChainHeadStorage<Client, Block, BE>
+where
+	Block: BlockT + 'static,
+	BE: Backend<Block> + 'static,
+	Client: StorageProvider<Block, BE> + 'static,
+{
+	/// Send the event to the sink.
+	async fn send(
+		tx: &mut mpsc::Sender<ChainHeadStorageEvent>,
+		event: ChainHeadStorageEvent,
+	) -> Result<(), OperationEnded> {
+		// The receiver is dropped when the user stops the subscription.
+		tx.send(event).await.map_err(|_| OperationEnded)
+	}
+
+	/// Wait for the user to call `chainHead_unstable_continue` for this operation.
+	async fn wait_for_continue(
+		&self,
+		tx: &mut mpsc::Sender<ChainHeadStorageEvent>,
+	) -> Result<(), OperationEnded> {
+		let rx_continue = match self
+			.sub_handle
+			.wait_for_continue(&self.follow_subscription, self.operation_id.clone())
+		{
+			Ok(rx_continue) => rx_continue,
+			Err(_) => {
+				Self::send(tx, ChainHeadStorageEvent::Disjoint).await?;
+				return Err(OperationEnded)
+			},
+		};
+		let _guard = WaitingGuard {
+			sub_handle: &self.sub_handle,
+			follow_subscription: &self.follow_subscription,
+			operation_id: &self.operation_id,
+		};
+
+		Self::send(tx, ChainHeadStorageEvent::WaitForContinue).await?;
+
+		// The sender is dropped if the `follow` subscription ends in the meanwhile.
+		if rx_continue.await.is_err() {
+			Self::send(tx, ChainHeadStorageEvent::Disjoint).await?;
+			return Err(OperationEnded)
+		}
+
+		Ok(())
+	}
+
+	/// Answer the storage queries, reporting the results to the provided sender.
+	async fn process_queries(
+		&self,
+		tx: &mut mpsc::Sender<ChainHeadStorageEvent>,
+		hash: Block::Hash,
+		items: Vec<StorageQuery<StorageKey>>,
+		child_key: Option<ChildInfo>,
+	) -> Result<(), OperationEnded> {
+		// The child key must not be prefixed with ":child_storage:" nor
+		// ":child_storage:default:".
+		if child_key
+			.as_ref()
+			.map_or(false, |child| is_child_storage_key(child.storage_key()))
+		{
+			return Self::send(tx, ChainHeadStorageEvent::Done).await
+		}
+
+		let mut storage_results = Vec::with_capacity(items.len());
+		let mut descendants = VecDeque::new();
+
+		for item in items {
+			// The main key must not be prefixed with ":child_storage:" nor
+			// ":child_storage:default:".
+			if is_child_storage_key(&item.key.0) {
+				continue
+			}
+
+			let result = match item.query_type {
+				StorageQueryType::Value =>
+					self.client.query_value(hash, &item.key, child_key.as_ref()),
+				StorageQueryType::Hash =>
+					self.client.query_hash(hash, &item.key, child_key.as_ref()),
+				StorageQueryType::ClosestDescendantMerkleValue =>
+					self.client.query_merkle_value(hash, &item.key, child_key.as_ref()),
+				StorageQueryType::DescendantsValues => {
+					descendants.push_back(QueryIter {
+						query_key: item.key,
+						pagination_start_key: None,
+						ty: IterQueryType::Value,
+					});
+					continue
+				},
+				StorageQueryType::DescendantsHashes => {
+					descendants.push_back(QueryIter {
+						query_key: item.key,
+						pagination_start_key: None,
+						ty: IterQueryType::Hash,
+					});
+					continue
+				},
+			};
+
+			match result {
+				Ok(Some(result)) => storage_results.push(result),
+				Ok(None) => continue,
+				Err(error) =>
+					return Self::send(tx, ChainHeadStorageEvent::Error(ErrorEvent { error })).await,
+			}
+		}
+
+		Self::send_items(tx, storage_results).await?;
+
+		// The descendant queries are answered in order, one page at a time.
+		while let Some(query) = descendants.pop_front() {
+			let QueryIterResult { results, next } = match self.client.query_iter_pagination(
+				query,
+				hash,
+				child_key.as_ref(),
+				self.operation_max_storage_items,
+			) {
+				Ok(result) => result,
+				Err(error) =>
+					return Self::send(tx, ChainHeadStorageEvent::Error(ErrorEvent { error })).await,
+			};
+
+			Self::send_items(tx, results).await?;
+
+			if let Some(next) = next {
+				debug!(
+					target: LOG_TARGET,
+					"[storage][id={:?}] Waiting for continue after a full page",
+					self.operation_id,
+				);
+				descendants.push_front(next);
+				self.wait_for_continue(tx).await?;
+			}
+		}
+
+		Self::send(tx, ChainHeadStorageEvent::Done).await
+	}
+
+	/// Send the storage results as an `Items` event, if any.
+	async fn send_items(
+		tx: &mut mpsc::Sender<ChainHeadStorageEvent>,
+		items: Vec<StorageResult>,
+	) -> Result<(), OperationEnded> {
+		if items.is_empty() {
+			return Ok(())
+		}
+
+		Self::send(tx, ChainHeadStorageEvent::Items(ItemsEvent { items })).await
+	}
+
+	/// Generate the events of the `chainHead_storage` method.
+	///
+	/// The events are produced only as fast as the sink consumes them. Stopping the
+	/// subscription also stops answering the remaining queries.
+	pub async fn generate_events(
+		&self,
+		mut sink: SubscriptionSink,
+		hash: Block::Hash,
+		items: Vec<StorageQuery<StorageKey>>,
+		child_key: Option<ChildInfo>,
+	) {
+		let (mut tx, rx) = mpsc::channel(STORAGE_EVENTS_BUFFER);
+
+		let process = async move {
+			// The result is irrelevant, the sender is dropped in both cases.
+			let _ = self.process_queries(&mut tx, hash, items, child_key).await;
+		};
+		let pipe = sink.pipe_from_stream(rx);
+		pin_mut!(process, pipe);
+
+		match future::select(process, pipe).await {
+			// All the events were produced, wait for the sink to report them.
+			future::Either::Left((_, pipe)) => {
+				pipe.await;
+			},
+			// The subscription was stopped by the user.
+			future::Either::Right(_) => {
+				debug!(
+					target: LOG_TARGET,
+					"[storage][id={:?}] Subscription stopped by the user",
+					self.operation_id,
+				);
+			},
+		}
+	}
+}
@@ -18,6 +18,7 @@
 
 //! The chain head's event returned as json compatible object.
 
+use crate::common::events::StorageResult;
 use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
 use sp_api::ApiError;
 use sp_version::RuntimeVersion;
@@ -243,9 +244,45 @@ pub enum ChainHeadEvent<T> {
 	Disjoint,
 }
 
+/// The storage items reported by the `chainHead_storage` method.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ItemsEvent {
+	/// The results of the storage queries.
+	pub items: Vec<StorageResult>,
+}
+
+/// The event generated by the `chainHead_storage` method.
+///
+/// The results of the storage queries are reported by one or more `Items` events,
+/// followed by a single `Done` event.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(tag = "event")]
+pub enum ChainHeadStorageEvent {
+	/// The results of a part of the storage queries.
+	Items(ItemsEvent),
+	/// More results are available.
+	///
+	/// The results are produced only after the user calls
+	/// `chainHead_unstable_continue` for this operation.
+	WaitForContinue,
+	/// All the storage queries were answered.
+	Done,
+	/// The resources requested are inaccessible.
+	///
+	/// Resubmitting the request later might succeed.
+	Inaccessible(ErrorEvent),
+	/// An error occurred. This is definitive.
+	Error(ErrorEvent),
+	/// The provided subscription ID is stale or invalid.
+	Disjoint,
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use crate::common::events::StorageResultType;
 
 	#[test]
 	fn follow_initialized_event_no_updates() {
@@ -462,6 +499,48 @@ mod tests {
 		assert_eq!(event_dec, event);
 	}
 
+	#[test]
+	fn chain_head_storage_items_event() {
+		let event = ChainHeadStorageEvent::Items(ItemsEvent {
+			items: vec![StorageResult {
+				key: "0x1".into(),
+				result: StorageResultType::Value("0x123".into()),
+				child_trie_key: None,
+			}],
+		});
+
+		let ser = serde_json::to_string(&event).unwrap();
+		let exp = r#"{"event":"items","items":[{"key":"0x1","value":"0x123"}]}"#;
+		assert_eq!(ser, exp);
+
+		let event_dec: ChainHeadStorageEvent = serde_json::from_str(exp).unwrap();
+		assert_eq!(event_dec, event);
+	}
+
+	#[test]
+	fn chain_head_storage_wait_for_continue_event() {
+		let event = ChainHeadStorageEvent::WaitForContinue;
+
+		let ser = serde_json::to_string(&event).unwrap();
+		let exp = r#"{"event":"waitForContinue"}"#;
+		assert_eq!(ser, exp);
+
+		let event_dec: ChainHeadStorageEvent = serde_json::from_str(exp).unwrap();
+		assert_eq!(event_dec, event);
+	}
+
+	#[test]
+	fn chain_head_storage_done_event() {
+		let event = ChainHeadStorageEvent::Done;
+
+		let ser = serde_json::to_string(&event).unwrap();
+		let exp = r#"{"event":"done"}"#;
+		assert_eq!(ser, exp);
+
+		let event_dec: ChainHeadStorageEvent = serde_json::from_str(exp).unwrap();
+		assert_eq!(event_dec, event);
+	}
+
 	#[test]
 	fn chain_head_network_config() {
 		let conf = NetworkConfig {
@@ -26,6 +26,10 @@ pub enum SubscriptionManagementError {
 	/// of blocks pinned.
 	#[error("Exceeded pinning limits")]
 	ExceededLimits,
+	/// The subscription has exceeded the maximum number
+	/// of ongoing operations.
+	#[error("Exceeded the maximum number of ongoing operations")]
+	ExceededOperations,
 	/// Error originated from the blockchain (client or backend).
 	#[error("Blockchain error {0}")]
 	Blockchain(Error),
@@ -38,6 +42,9 @@ pub enum SubscriptionManagementError {
 	/// The specified subscription ID is not present.
 	#[error("Subscription is absent")]
 	SubscriptionAbsent,
+	/// The specified operation is not waiting to be continued.
+	#[error("Operation is not waiting to be continued")]
+	OperationAbsent,
 	/// Custom error.
 	#[error("Subscription error {0}")]
 	Custom(String),
@@ -48,11 +55,13 @@ impl PartialEq for SubscriptionManagementError {
 	fn eq(&self, other: &SubscriptionManagementError) -> bool {
 		match (self, other) {
 			(Self::ExceededLimits, Self::ExceededLimits) |
+			(Self::ExceededOperations, Self::ExceededOperations) |
 			// Not needed for testing.
 			(Self::Blockchain(_), Self::Blockchain(_)) |
 			(Self::BlockHashAbsent, Self::BlockHashAbsent) |
 			(Self::BlockHeaderAbsent, Self::BlockHeaderAbsent) |
-			(Self::SubscriptionAbsent, Self::SubscriptionAbsent) => true,
+			(Self::SubscriptionAbsent, Self::SubscriptionAbsent) |
+			(Self::OperationAbsent, Self::OperationAbsent) => true,
 			(Self::Custom(lhs), Self::Custom(rhs)) => lhs == rhs,
 			_ => false,
 		}
@@ -21,7 +21,10 @@ use sc_client_api::Backend;
 use sp_runtime::traits::Block as BlockT;
 use std::{
 	collections::{hash_map::Entry, HashMap},
-	sync::Arc,
+	sync::{
+		atomic::{AtomicUsize, Ordering},
+		Arc,
+	},
 	time::{Duration, Instant},
 };
 
@@ -112,10 +115,20 @@ struct BlockState {
 
 /// The state of a single subscription ID.
 struct SubscriptionState<Block: BlockT> {
+	/// The connection of the subscription.
+	connection: usize,
 	/// The `runtime_updates` parameter flag of the subscription.
 	runtime_updates: bool,
 	/// Signals the "Stop" event.
 	tx_stop: Option<oneshot::Sender<()>>,
+	/// The operations waiting for the user to call `continue`, indexed by operation ID.
+	///
+	/// Dropping the senders signals the operations that the subscription is no longer valid.
+	waiting_operations: HashMap<String, oneshot::Sender<()>>,
+	/// The number of operations (`body`, `call`, `storage`) currently in progress.
+	///
+	/// The counter is shared with the [`OperationPermit`] of each operation.
+	ongoing_operations: Arc<AtomicUsize>,
 	/// Track the block hashes available for this subscription.
 	///
 	/// This implementation assumes:
@@ -215,6 +228,30 @@ impl<Block: BlockT> SubscriptionState<Block> {
 		!state.state_machine.was_unpinned()
 	}
 
+	/// Get the number of blocks that are pinned by this subscription.
+	///
+	/// # Note
+	///
+	/// This iterates over all the blocks of the subscription.
+	fn pinned_blocks(&self) -> usize {
+		self.blocks.values().filter(|state| !state.state_machine.was_unpinned()).count()
+	}
+
+	/// Reserve one operation from the budget of this subscription.
+	///
+	/// Returns `None` if the subscription has reached the maximum number of
+	/// ongoing operations.
+	fn reserve_operation(&self, max_ongoing_operations: usize) -> Option<OperationPermit> {
+		// Operations are reserved under the write lock of the subscriptions, only
+		// the release of permits can happen concurrently.
+		if self.ongoing_operations.load(Ordering::Acquire) >= max_ongoing_operations {
+			return None
+		}
+
+		self.ongoing_operations.fetch_add(1, Ordering::AcqRel);
+		Some(OperationPermit { ongoing_operations: self.ongoing_operations.clone() })
+	}
+
 	/// Get the timestamp of the oldest inserted block.
 	///
 	/// # Note
@@ -229,6 +266,18 @@ impl<Block: BlockT> SubscriptionState<Block> {
 	}
 }
 
+/// Reserves one of the ongoing operations of a subscription while alive.
+struct OperationPermit {
+	/// The counter of ongoing operations of the subscription.
+	ongoing_operations: Arc<AtomicUsize>,
+}
+
+impl Drop for OperationPermit {
+	fn drop(&mut self) {
+		self.ongoing_operations.fetch_sub(1, Ordering::AcqRel);
+	}
+}
+
 /// Keeps a specific block pinned while the handle is alive.
 /// This object ensures that the block is not unpinned while
 /// executing an RPC method call.
@@ -236,6 +285,8 @@ pub struct BlockGuard<Block: BlockT, BE: Backend<Block>> {
 	hash: Block::Hash,
 	runtime_updates: bool,
 	backend: Arc<BE>,
+	/// The operation reserved for the duration of the RPC method call, if any.
+	_permit: Option<OperationPermit>,
 }
 
 // Custom implementation of Debug to avoid bounds on `backend: Debug` for `unwrap_err()` needed for
@@ -252,12 +303,13 @@ impl<Block: BlockT, BE: Backend<Block>> BlockGuard<Block, BE> {
 		hash: Block::Hash,
 		runtime_updates: bool,
 		backend: Arc<BE>,
+		permit: Option<OperationPermit>,
 	) -> Result<Self, SubscriptionManagementError> {
 		backend
 			.pin_block(hash)
 			.map_err(|err| SubscriptionManagementError::Custom(err.to_string()))?;
 
-		Ok(Self { hash, runtime_updates, backend })
+		Ok(Self { hash, runtime_updates, backend, _permit: permit })
 	}
 
 	/// The `runtime_updates` flag of the subscription.
@@ -282,6 +334,17 @@ pub struct SubscriptionsInner<Block: BlockT, BE: Backend<Block>> {
 	global_max_pinned_blocks: usize,
 	/// The maximum duration that a block is allowed to be pinned per subscription.
 	local_max_pin_duration: Duration,
+	/// The maximum number of blocks that a single subscription is allowed to pin.
+	///
+	/// Subscriptions that do not unpin their blocks fast enough are stopped via
+	/// the `Stop` event, instead of holding onto an unbounded number of blocks.
+	local_max_pinned_blocks: usize,
+	/// The maximum number of ongoing operations per subscription.
+	local_max_ongoing_operations: usize,
+	/// The maximum number of subscriptions per connection.
+	max_subscriptions_per_connection: usize,
+	/// The number of subscriptions of each connection.
+	connections: HashMap<usize, usize>,
 	/// Map the subscription ID to internal details of the subscription.
 	subs: HashMap<String, SubscriptionState<Block>>,
 	/// Backend pinning / unpinning blocks.
@@ -295,31 +358,51 @@ impl<Block: BlockT, BE: Backend<Block>> SubscriptionsInner<Block, BE> {
 	pub fn new(
 		global_max_pinned_blocks: usize,
 		local_max_pin_duration: Duration,
+		local_max_pinned_blocks: usize,
+		local_max_ongoing_operations: usize,
+		max_subscriptions_per_connection: usize,
 		backend: Arc<BE>,
 	) -> Self {
 		SubscriptionsInner {
 			global_blocks: Default::default(),
 			global_max_pinned_blocks,
 			local_max_pin_duration,
+			local_max_pinned_blocks,
+			local_max_ongoing_operations,
+			max_subscriptions_per_connection,
+			connections: Default::default(),
 			subs: Default::default(),
 			backend,
 		}
 	}
 
 	/// Insert a new subscription ID.
+	///
+	/// Returns `None` if the subscription ID was already inserted or
+	/// the maximum number of subscriptions of the connection was reached.
 	pub fn insert_subscription(
 		&mut self,
 		sub_id: String,
 		runtime_updates: bool,
+		connection: usize,
 	) -> Option<oneshot::Receiver<()>> {
+		let count = self.connections.get(&connection).copied().unwrap_or(0);
+		if count >= self.max_subscriptions_per_connection {
+			return None
+		}
+
 		if let Entry::Vacant(entry) = self.subs.entry(sub_id) {
 			let (tx_stop, rx_stop) = oneshot::channel();
 			let state = SubscriptionState::<Block> {
+				connection,
 				runtime_updates,
 				tx_stop: Some(tx_stop),
+				waiting_operations: Default::default(),
+				ongoing_operations: Default::default(),
 				blocks: Default::default(),
 			};
 			entry.insert(state);
+			self.connections.insert(connection, count + 1);
 			Some(rx_stop)
 		} else {
 			None
@@ -328,13 +411,18 @@ impl<Block: BlockT, BE: Backend<Block>> SubscriptionsInner<Block, BE> {
 
 	/// Remove the subscription ID with associated pinned blocks.
 	pub fn remove_subscription(&mut self, sub_id: &str) {
-		let Some(mut sub) = self.subs.remove(sub_id) else {
-			return
-		};
+		let Some(mut sub) = self.subs.remove(sub_id) else { return };
 
 		// The `Stop` event can be generated only once.
 		sub.stop();
 
+		if let Entry::Occupied(mut entry) = self.connections.entry(sub.connection) {
+			*entry.get_mut() -= 1;
+			if *entry.get() == 0 {
+				entry.remove();
+			}
+		}
+
 		for (hash, state) in sub.blocks.iter() {
 			if !state.state_machine.was_unpinned() {
 				self.global_unregister_block(*hash);
@@ -410,6 +498,13 @@ impl<Block: BlockT, BE: Backend<Block>> SubscriptionsInner<Block, BE> {
 			return Err(SubscriptionManagementError::SubscriptionAbsent)
 		};
 
+		// The subscription pinned too many blocks without unpinning them.
+		// Terminate it, instead of letting a slow consumer hold onto the blocks.
+		if !sub.blocks.contains_key(&hash) && sub.pinned_blocks() >= self.local_max_pinned_blocks {
+			self.remove_subscription(sub_id);
+			return Err(SubscriptionManagementError::ExceededLimits)
+		}
+
 		// Block was already registered for this subscription and therefore
 		// globally tracked.
 		if !sub.register_block(hash) {
@@ -501,7 +596,64 @@ impl<Block: BlockT, BE: Backend<Block>> SubscriptionsInner<Block, BE> {
 			return Err(SubscriptionManagementError::BlockHashAbsent)
 		}
 
-		BlockGuard::new(hash, sub.runtime_updates, self.backend.clone())
+		BlockGuard::new(hash, sub.runtime_updates, self.backend.clone(), None)
+	}
+
+	pub fn lock_block_for_operation(
+		&mut self,
+		sub_id: &str,
+		hash: Block::Hash,
+	) -> Result<BlockGuard<Block, BE>, SubscriptionManagementError> {
+		let Some(sub) = self.subs.get(sub_id) else {
+			return Err(SubscriptionManagementError::SubscriptionAbsent)
+		};
+
+		if !sub.contains_block(hash) {
+			return Err(SubscriptionManagementError::BlockHashAbsent)
+		}
+
+		let Some(permit) = sub.reserve_operation(self.local_max_ongoing_operations) else {
+			return Err(SubscriptionManagementError::ExceededOperations)
+		};
+
+		BlockGuard::new(hash, sub.runtime_updates, self.backend.clone(), Some(permit))
+	}
+
+	pub fn wait_for_continue(
+		&mut self,
+		sub_id: &str,
+		operation_id: String,
+	) -> Result<oneshot::Receiver<()>, SubscriptionManagementError> {
+		let Some(sub) = self.subs.get_mut(sub_id) else {
+			return Err(SubscriptionManagementError::SubscriptionAbsent)
+		};
+
+		let (tx_continue, rx_continue) = oneshot::channel();
+		sub.waiting_operations.insert(operation_id, tx_continue);
+		Ok(rx_continue)
+	}
+
+	pub fn stop_waiting(&mut self, sub_id: &str, operation_id: &str) {
+		if let Some(sub) = self.subs.get_mut(sub_id) {
+			sub.waiting_operations.remove(operation_id);
+		}
+	}
+
+	pub fn continue_operation(
+		&mut self,
+		sub_id: &str,
+		operation_id: &str,
+	) -> Result<(), SubscriptionManagementError> {
+		let Some(sub) = self.subs.get_mut(sub_id) else {
+			return Err(SubscriptionManagementError::SubscriptionAbsent)
+		};
+
+		let Some(tx_continue) = sub.waiting_operations.remove(operation_id) else {
+			return Err(SubscriptionManagementError::OperationAbsent)
+		};
+
+		// The operation was stopped by the user while waiting.
+		tx_continue.send(()).map_err(|_| SubscriptionManagementError::OperationAbsent)
 	}
 }
 
@@ -518,6 +670,10 @@ mod tests {
 		Client, ClientBlockImportExt, GenesisInit,
 	};
 
+	const MAX_PINNED_BLOCKS: usize = 32;
+	const MAX_OPERATIONS: usize = 16;
+	const MAX_SUBSCRIPTIONS: usize = 16;
+
 	fn init_backend() -> (
 		Arc<sc_client_api::in_mem::Backend<Block>>,
 		Arc<Client<sc_client_api::in_mem::Backend<Block>>>,
@@ -608,8 +764,11 @@ mod tests {
 	#[test]
 	fn sub_state_register_twice() {
 		let mut sub_state = SubscriptionState::<Block> {
+			connection: 0,
 			runtime_updates: false,
 			tx_stop: None,
+			waiting_operations: Default::default(),
+			ongoing_operations: Default::default(),
 			blocks: Default::default(),
 		};
 
@@ -633,8 +792,11 @@ mod tests {
 	#[test]
 	fn sub_state_register_unregister() {
 		let mut sub_state = SubscriptionState::<Block> {
+			connection: 0,
 			runtime_updates: false,
 			tx_stop: None,
+			waiting_operations: Default::default(),
+			ongoing_operations: Default::default(),
 			blocks: Default::default(),
 		};
 
@@ -667,7 +829,14 @@ mod tests {
 	fn subscription_lock_block() {
 		let builder = TestClientBuilder::new();
 		let backend = builder.backend();
-		let mut subs = SubscriptionsInner::new(10, Duration::from_secs(10), backend);
+		let mut subs = SubscriptionsInner::new(
+			10,
+			Duration::from_secs(10),
+			MAX_PINNED_BLOCKS,
+			MAX_OPERATIONS,
+			MAX_SUBSCRIPTIONS,
+			backend,
+		);
 
 		let id = "abc".to_string();
 		let hash = H256::random();
@@ -676,9 +845,9 @@ mod tests {
 		let err = subs.lock_block(&id, hash).unwrap_err();
 		assert_eq!(err, SubscriptionManagementError::SubscriptionAbsent);
 
-		let _stop = subs.insert_subscription(id.clone(), true).unwrap();
+		let _stop = subs.insert_subscription(id.clone(), true, 0).unwrap();
 		// Cannot insert the same subscription ID twice.
-		assert!(subs.insert_subscription(id.clone(), true).is_none());
+		assert!(subs.insert_subscription(id.clone(), true, 0).is_none());
 
 		// No block hash.
 		let err = subs.lock_block(&id, hash).unwrap_err();
@@ -699,10 +868,17 @@ mod tests {
 		let hash = block.header.hash();
 		futures::executor::block_on(client.import(BlockOrigin::Own, block.clone())).unwrap();
 
-		let mut subs = SubscriptionsInner::new(10, Duration::from_secs(10), backend);
+		let mut subs = SubscriptionsInner::new(
+			10,
+			Duration::from_secs(10),
+			MAX_PINNED_BLOCKS,
+			MAX_OPERATIONS,
+			MAX_SUBSCRIPTIONS,
+			backend,
+		);
 		let id = "abc".to_string();
 
-		let _stop = subs.insert_subscription(id.clone(), true).unwrap();
+		let _stop = subs.insert_subscription(id.clone(), true, 0).unwrap();
 
 		// First time we are pinning the block.
 		assert_eq!(subs.pin_block(&id, hash).unwrap(), true);
@@ -728,10 +904,17 @@ mod tests {
 		let hash = block.header.hash();
 		futures::executor::block_on(client.import(BlockOrigin::Own, block.clone())).unwrap();
 
-		let mut subs = SubscriptionsInner::new(10, Duration::from_secs(10), backend);
+		let mut subs = SubscriptionsInner::new(
+			10,
+			Duration::from_secs(10),
+			MAX_PINNED_BLOCKS,
+			MAX_OPERATIONS,
+			MAX_SUBSCRIPTIONS,
+			backend,
+		);
 		let id = "abc".to_string();
 
-		let _stop = subs.insert_subscription(id.clone(), true).unwrap();
+		let _stop = subs.insert_subscription(id.clone(), true, 0).unwrap();
 		assert_eq!(subs.pin_block(&id, hash).unwrap(), true);
 		// Check the global ref count.
 		assert_eq!(*subs.global_blocks.get(&hash).unwrap(), 1);
@@ -745,7 +928,7 @@ mod tests {
 
 		// Ensure the hash propagates for the second subscription.
 		let id_second = "abcd".to_string();
-		let _stop = subs.insert_subscription(id_second.clone(), true).unwrap();
+		let _stop = subs.insert_subscription(id_second.clone(), true, 0).unwrap();
 		assert_eq!(subs.pin_block(&id_second, hash).unwrap(), true);
 		// Check the global ref count.
 		assert_eq!(*subs.global_blocks.get(&hash).unwrap(), 2);
@@ -776,18 +959,25 @@ mod tests {
 		let hash_3 = block.header.hash();
 		futures::executor::block_on(client.import(BlockOrigin::Own, block.clone())).unwrap();
 
-		let mut subs = SubscriptionsInner::new(10, Duration::from_secs(10), backend);
+		let mut subs = SubscriptionsInner::new(
+			10,
+			Duration::from_secs(10),
+			MAX_PINNED_BLOCKS,
+			MAX_OPERATIONS,
+			MAX_SUBSCRIPTIONS,
+			backend,
+		);
 		let id_1 = "abc".to_string();
 		let id_2 = "abcd".to_string();
 
 		// Pin all blocks for the first subscription.
-		let _stop = subs.insert_subscription(id_1.clone(), true).unwrap();
+		let _stop = subs.insert_subscription(id_1.clone(), true, 0).unwrap();
 		assert_eq!(subs.pin_block(&id_1, hash_1).unwrap(), true);
 		assert_eq!(subs.pin_block(&id_1, hash_2).unwrap(), true);
 		assert_eq!(subs.pin_block(&id_1, hash_3).unwrap(), true);
 
 		// Pin only block 2 for the second subscription.
-		let _stop = subs.insert_subscription(id_2.clone(), true).unwrap();
+		let _stop = subs.insert_subscription(id_2.clone(), true, 0).unwrap();
 		assert_eq!(subs.pin_block(&id_2, hash_2).unwrap(), true);
 
 		// Check reference count.
@@ -821,16 +1011,23 @@ mod tests {
 		futures::executor::block_on(client.import(BlockOrigin::Own, block.clone())).unwrap();
 
 		// Maximum number of pinned blocks is 2.
-		let mut subs = SubscriptionsInner::new(2, Duration::from_secs(10), backend);
+		let mut subs = SubscriptionsInner::new(
+			2,
+			Duration::from_secs(10),
+			MAX_PINNED_BLOCKS,
+			MAX_OPERATIONS,
+			MAX_SUBSCRIPTIONS,
+			backend,
+		);
 		let id_1 = "abc".to_string();
 		let id_2 = "abcd".to_string();
 
 		// Both subscriptions can pin the maximum limit.
-		let _stop = subs.insert_subscription(id_1.clone(), true).unwrap();
+		let _stop = subs.insert_subscription(id_1.clone(), true, 0).unwrap();
 		assert_eq!(subs.pin_block(&id_1, hash_1).unwrap(), true);
 		assert_eq!(subs.pin_block(&id_1, hash_2).unwrap(), true);
 
-		let _stop = subs.insert_subscription(id_2.clone(), true).unwrap();
+		let _stop = subs.insert_subscription(id_2.clone(), true, 0).unwrap();
 		assert_eq!(subs.pin_block(&id_2, hash_1).unwrap(), true);
 		assert_eq!(subs.pin_block(&id_2, hash_2).unwrap(), true);
 
@@ -871,11 +1068,18 @@ mod tests {
 		futures::executor::block_on(client.import(BlockOrigin::Own, block.clone())).unwrap();
 
 		// Maximum number of pinned blocks is 2 and maximum pin duration is 5 second.
-		let mut subs = SubscriptionsInner::new(2, Duration::from_secs(5), backend);
+		let mut subs = SubscriptionsInner::new(
+			2,
+			Duration::from_secs(5),
+			MAX_PINNED_BLOCKS,
+			MAX_OPERATIONS,
+			MAX_SUBSCRIPTIONS,
+			backend,
+		);
 		let id_1 = "abc".to_string();
 		let id_2 = "abcd".to_string();
 
-		let _stop = subs.insert_subscription(id_1.clone(), true).unwrap();
+		let _stop = subs.insert_subscription(id_1.clone(), true, 0).unwrap();
 		assert_eq!(subs.pin_block(&id_1, hash_1).unwrap(), true);
 		assert_eq!(subs.pin_block(&id_1, hash_2).unwrap(), true);
 
@@ -883,7 +1087,7 @@ mod tests {
 		// the first subscription.
 		std::thread::sleep(std::time::Duration::from_secs(5));
 
-		let _stop = subs.insert_subscription(id_2.clone(), true).unwrap();
+		let _stop = subs.insert_subscription(id_2.clone(), true, 0).unwrap();
 		assert_eq!(subs.pin_block(&id_2, hash_1).unwrap(), true);
 
 		// Check reference count.
@@ -920,11 +1124,18 @@ mod tests {
 	fn subscription_check_stop_event() {
 		let builder = TestClientBuilder::new();
 		let backend = builder.backend();
-		let mut subs = SubscriptionsInner::new(10, Duration::from_secs(10), backend);
+		let mut subs = SubscriptionsInner::new(
+			10,
+			Duration::from_secs(10),
+			MAX_PINNED_BLOCKS,
+			MAX_OPERATIONS,
+			MAX_SUBSCRIPTIONS,
+			backend,
+		);
 
 		let id = "abc".to_string();
 
-		let mut rx_stop = subs.insert_subscription(id.clone(), true).unwrap();
+		let mut rx_stop = subs.insert_subscription(id.clone(), true, 0).unwrap();
 
 		// Check the stop signal was not received.
 		let res = rx_stop.try_recv().unwrap();
@@ -937,4 +1148,178 @@ mod tests {
 		let res = rx_stop.try_recv().unwrap();
 		assert!(res.is_some());
 	}
+
+	#[test]
+	fn subscription_continue_operation() {
+		let builder = TestClientBuilder::new();
+		let backend = builder.backend();
+		let mut subs = SubscriptionsInner::new(
+			10,
+			Duration::from_secs(10),
+			MAX_PINNED_BLOCKS,
+			MAX_OPERATIONS,
+			MAX_SUBSCRIPTIONS,
+			backend,
+		);
+
+		let id = "abc".to_string();
+		let operation_id = "op".to_string();
+
+		// Subscription not inserted.
+		let err = subs.wait_for_continue(&id, operation_id.clone()).unwrap_err();
+		assert_eq!(err, SubscriptionManagementError::SubscriptionAbsent);
+		let err = subs.continue_operation(&id, &operation_id).unwrap_err();
+		assert_eq!(err, SubscriptionManagementError::SubscriptionAbsent);
+
+		let _stop = subs.insert_subscription(id.clone(), true, 0).unwrap();
+
+		// The operation is not waiting.
+		let err = subs.continue_operation(&id, &operation_id).unwrap_err();
+		assert_eq!(err, SubscriptionManagementError::OperationAbsent);
+
+		let mut rx_continue = subs.wait_for_continue(&id, operation_id.clone()).unwrap();
+		assert!(rx_continue.try_recv().unwrap().is_none());
+
+		subs.continue_operation(&id, &operation_id).unwrap();
+		assert!(rx_continue.try_recv().unwrap().is_some());
+
+		// The operation can be continued only once per wait.
+		let err = subs.continue_operation(&id, &operation_id).unwrap_err();
+		assert_eq!(err, SubscriptionManagementError::OperationAbsent);
+
+		// The operation stops waiting when it ends.
+		let mut rx_continue = subs.wait_for_continue(&id, operation_id.clone()).unwrap();
+		subs.stop_waiting(&id, &operation_id);
+		assert!(rx_continue.try_recv().is_err());
+		assert!(subs.subs.get(&id).unwrap().waiting_operations.is_empty());
+		let err = subs.continue_operation(&id, &operation_id).unwrap_err();
+		assert_eq!(err, SubscriptionManagementError::OperationAbsent);
+
+		// The operation is canceled when the subscription is removed.
+		let mut rx_continue = subs.wait_for_continue(&id, operation_id.clone()).unwrap();
+		subs.remove_subscription(&id);
+		assert!(rx_continue.try_recv().is_err());
+		subs.stop_waiting(&id, &operation_id);
+	}
+
+	#[test]
+	fn subscription_check_operations() {
+		let (backend, mut client) = init_backend();
+		let block = client.new_block(Default::default()).unwrap().build().unwrap().block;
+		let hash = block.header.hash();
+		futures::executor::block_on(client.import(BlockOrigin::Own, block.clone())).unwrap();
+
+		// Maximum number of ongoing operations is 2.
+		let mut subs = SubscriptionsInner::new(
+			10,
+			Duration::from_secs(10),
+			MAX_PINNED_BLOCKS,
+			2,
+			MAX_SUBSCRIPTIONS,
+			backend,
+		);
+		let id = "abc".to_string();
+
+		let _stop = subs.insert_subscription(id.clone(), true, 0).unwrap();
+		assert_eq!(subs.pin_block(&id, hash).unwrap(), true);
+
+		let first = subs.lock_block_for_operation(&id, hash).unwrap();
+		let _second = subs.lock_block_for_operation(&id, hash).unwrap();
+
+		// The operation budget is exhausted.
+		let err = subs.lock_block_for_operation(&id, hash).unwrap_err();
+		assert_eq!(err, SubscriptionManagementError::ExceededOperations);
+
+		// Locking the block outside of an operation does not consume the budget.
+		let _block = subs.lock_block(&id, hash).unwrap();
+
+		// Completing an operation releases its permit.
+		drop(first);
+		let _third = subs.lock_block_for_operation(&id, hash).unwrap();
+	}
+
+	#[test]
+	fn subscription_check_max_subscriptions() {
+		let builder = TestClientBuilder::new();
+		let backend = builder.backend();
+		// Maximum number of subscriptions per connection is 2.
+		let mut subs = SubscriptionsInner::new(
+			10,
+			Duration::from_secs(10),
+			MAX_PINNED_BLOCKS,
+			MAX_OPERATIONS,
+			2,
+			backend,
+		);
+
+		let _stop = subs.insert_subscription("abc".to_string(), true, 0).unwrap();
+		let _stop = subs.insert_subscription("abcd".to_string(), true, 0).unwrap();
+		assert!(subs.insert_subscription("abcde".to_string(), true, 0).is_none());
+
+		// Other connections have their own limit.
+		let _stop = subs.insert_subscription("xyz".to_string(), true, 1).unwrap();
+		let _stop = subs.insert_subscription("xyzw".to_string(), true, 1).unwrap();
+		assert!(subs.insert_subscription("xyzwv".to_string(), true, 1).is_none());
+
+		// Removing a subscription makes room for a new one on the same connection.
+		subs.remove_subscription("abc");
+		assert!(subs.insert_subscription("xyzwv".to_string(), true, 1).is_none());
+		let _stop = subs.insert_subscription("abcde".to_string(), true, 0).unwrap();
+	}
+
+	#[test]
+	fn subscription_check_slow_consumer() {
+		let (backend, mut client) = init_backend();
+		let block = client.new_block(Default::default()).unwrap().build().unwrap().block;
+		let hash_1 = block.header.hash();
+		futures::executor::block_on(client.import(BlockOrigin::Own, block.clone())).unwrap();
+		let block = client.new_block(Default::default()).unwrap().build().unwrap().block;
+		let hash_2 = block.header.hash();
+		futures::executor::block_on(client.import(BlockOrigin::Own, block.clone())).unwrap();
+		let block = client.new_block(Default::default()).unwrap().build().unwrap().block;
+		let hash_3 = block.header.hash();
+		futures::executor::block_on(client.import(BlockOrigin::Own, block.clone())).unwrap();
+		let block = client.new_block(Default::default()).unwrap().build().unwrap().block;
+		let hash_4 = block.header.hash();
+		futures::executor::block_on(client.import(BlockOrigin::Own, block.clone())).unwrap();
+
+		// Each subscription can pin at most 2 blocks.
+		let mut subs = SubscriptionsInner::new(
+			10,
+			Duration::from_secs(10),
+			2,
+			MAX_OPERATIONS,
+			MAX_SUBSCRIPTIONS,
+			backend,
+		);
+		let id_1 = "abc".to_string();
+		let id_2 = "abcd".to_string();
+
+		let mut rx_stop = subs.insert_subscription(id_1.clone(), true, 0).unwrap();
+		assert_eq!(subs.pin_block(&id_1, hash_1).unwrap(), true);
+		assert_eq!(subs.pin_block(&id_1, hash_2).unwrap(), true);
+		// Registering a block twice does not count towards the limit.
+		assert_eq!(subs.pin_block(&id_1, hash_2).unwrap(), false);
+
+		// Unpinned blocks do not count towards the limit.
+		subs.unpin_block(&id_1, hash_1).unwrap();
+		assert_eq!(subs.pin_block(&id_1, hash_3).unwrap(), true);
+
+		let _stop = subs.insert_subscription(id_2.clone(), true, 0).unwrap();
+		assert_eq!(subs.pin_block(&id_2, hash_2).unwrap(), true);
+		assert_eq!(subs.pin_block(&id_2, hash_3).unwrap(), true);
+
+		// The first subscription does not unpin its blocks and is terminated.
+		let err = subs.pin_block(&id_1, hash_4).unwrap_err();
+		assert_eq!(err, SubscriptionManagementError::ExceededLimits);
+		assert!(rx_stop.try_recv().unwrap().is_some());
+		let err = subs.lock_block(&id_1, hash_2).unwrap_err();
+		assert_eq!(err, SubscriptionManagementError::SubscriptionAbsent);
+
+		// The blocks of the second subscription are still pinned.
+		assert!(subs.global_blocks.get(&hash_1).is_none());
+		assert_eq!(*subs.global_blocks.get(&hash_2).unwrap(), 1);
+		assert_eq!(*subs.global_blocks.get(&hash_3).unwrap(), 1);
+		assert!(subs.global_blocks.get(&hash_4).is_none());
+	}
 }
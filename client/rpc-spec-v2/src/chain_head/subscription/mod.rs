@@ -41,12 +41,18 @@ impl<Block: BlockT, BE: Backend<Block>> SubscriptionManagement<Block, BE> {
 	pub fn new(
 		global_max_pinned_blocks: usize,
 		local_max_pin_duration: Duration,
+		local_max_pinned_blocks: usize,
+		local_max_ongoing_operations: usize,
+		max_subscriptions_per_connection: usize,
 		backend: Arc<BE>,
 	) -> Self {
 		SubscriptionManagement {
 			inner: RwLock::new(SubscriptionsInner::new(
 				global_max_pinned_blocks,
 				local_max_pin_duration,
+				local_max_pinned_blocks,
+				local_max_ongoing_operations,
+				max_subscriptions_per_connection,
 				backend,
 			)),
 		}
@@ -56,14 +62,15 @@ impl<Block: BlockT, BE: Backend<Block>> SubscriptionManagement<Block, BE> {
 	///
 	/// If the subscription was not previously inserted, returns the receiver that is
 	/// triggered upon the "Stop" event. Otherwise, if the subscription ID was already
-	/// inserted returns none.
+	/// inserted or the connection reached its maximum number of subscriptions returns none.
 	pub fn insert_subscription(
 		&self,
 		sub_id: String,
 		runtime_updates: bool,
+		connection: usize,
 	) -> Option<oneshot::Receiver<()>> {
 		let mut inner = self.inner.write();
-		inner.insert_subscription(sub_id, runtime_updates)
+		inner.insert_subscription(sub_id, runtime_updates, connection)
 	}
 
 	/// Remove the subscription ID with associated pinned blocks.
@@ -122,4 +129,56 @@ impl<Block: BlockT, BE: Backend<Block>> SubscriptionManagement<Block, BE> {
 		let mut inner = self.inner.write();
 		inner.lock_block(sub_id, hash)
 	}
+
+	/// Ensure the block remains pinned until the return object is dropped, while
+	/// reserving one of the ongoing operations of the subscription.
+	///
+	/// Returns a [`BlockGuard`] that pins and unpins the block hash in RAII manner,
+	/// and releases the reserved operation when dropped.
+	/// Returns an error if the block hash is not pinned for the subscription,
+	/// the subscription ID is invalid or the subscription has reached the
+	/// maximum number of ongoing operations.
+	pub fn lock_block_for_operation(
+		&self,
+		sub_id: &str,
+		hash: Block::Hash,
+	) -> Result<BlockGuard<Block, BE>, SubscriptionManagementError> {
+		let mut inner = self.inner.write();
+		inner.lock_block_for_operation(sub_id, hash)
+	}
+
+	/// Mark the operation as waiting for the user to call `continue`.
+	///
+	/// Returns the receiver that is triggered when the operation is continued.
+	/// The receiver is canceled if the subscription is removed in the meanwhile.
+	/// Returns an error if the subscription ID is invalid.
+	pub fn wait_for_continue(
+		&self,
+		sub_id: &str,
+		operation_id: String,
+	) -> Result<oneshot::Receiver<()>, SubscriptionManagementError> {
+		let mut inner = self.inner.write();
+		inner.wait_for_continue(sub_id, operation_id)
+	}
+
+	/// Stop waiting for the user to call `continue` for the operation.
+	///
+	/// Called when the operation ends, whether it was continued or not.
+	pub fn stop_waiting(&self, sub_id: &str, operation_id: &str) {
+		let mut inner = self.inner.write();
+		inner.stop_waiting(sub_id, operation_id)
+	}
+
+	/// Continue the operation that is waiting for the user.
+	///
+	/// Returns an error if the subscription ID is invalid or the operation
+	/// is not waiting to be continued.
+	pub fn continue_operation(
+		&self,
+		sub_id: &str,
+		operation_id: &str,
+	) -> Result<(), SubscriptionManagementError> {
+		let mut inner = self.inner.write();
+		inner.continue_operation(sub_id, operation_id)
+	}
 }
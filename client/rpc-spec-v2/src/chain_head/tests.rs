@@ -1,4 +1,7 @@
-use crate::chain_head::test_utils::ChainHeadMockClient;
+use crate::{
+	chain_head::test_utils::ChainHeadMockClient,
+	common::events::{StorageQuery, StorageQueryType, StorageResultType},
+};
 
 use super::*;
 use assert_matches::assert_matches;
@@ -6,6 +9,7 @@ use codec::{Decode, Encode};
 use futures::Future;
 use jsonrpsee::{
 	core::{error::Error, server::rpc_module::Subscription as RpcSubscription},
+	rpc_params,
 	types::{error::CallError, EmptyServerParams as EmptyParams},
 	RpcModule,
 };
@@ -19,6 +23,7 @@ use sp_core::{
 	hexdisplay::HexDisplay,
 	storage::well_known_keys::{self, CODE},
 	testing::TaskExecutor,
+	Blake2Hasher, Hasher,
 };
 use sp_version::RuntimeVersion;
 use std::{sync::Arc, time::Duration};
@@ -75,8 +80,11 @@ async fn setup_api() -> (
 		backend,
 		Arc::new(TaskExecutor::default()),
 		CHAIN_GENESIS,
-		MAX_PINNED_BLOCKS,
-		Duration::from_secs(MAX_PINNED_SECS),
+		ChainHeadConfig {
+			global_max_pinned_blocks: MAX_PINNED_BLOCKS,
+			subscription_max_pinned_duration: Duration::from_secs(MAX_PINNED_SECS),
+			..Default::default()
+		},
 	)
 	.into_rpc();
 
@@ -115,8 +123,11 @@ async fn follow_subscription_produces_blocks() {
 		backend,
 		Arc::new(TaskExecutor::default()),
 		CHAIN_GENESIS,
-		MAX_PINNED_BLOCKS,
-		Duration::from_secs(MAX_PINNED_SECS),
+		ChainHeadConfig {
+			global_max_pinned_blocks: MAX_PINNED_BLOCKS,
+			subscription_max_pinned_duration: Duration::from_secs(MAX_PINNED_SECS),
+			..Default::default()
+		},
 	)
 	.into_rpc();
 
@@ -173,8 +184,11 @@ async fn follow_with_runtime() {
 		backend,
 		Arc::new(TaskExecutor::default()),
 		CHAIN_GENESIS,
-		MAX_PINNED_BLOCKS,
-		Duration::from_secs(MAX_PINNED_SECS),
+		ChainHeadConfig {
+			global_max_pinned_blocks: MAX_PINNED_BLOCKS,
+			subscription_max_pinned_duration: Duration::from_secs(MAX_PINNED_SECS),
+			..Default::default()
+		},
 	)
 	.into_rpc();
 
@@ -280,8 +294,11 @@ async fn get_genesis() {
 		backend,
 		Arc::new(TaskExecutor::default()),
 		CHAIN_GENESIS,
-		MAX_PINNED_BLOCKS,
-		Duration::from_secs(MAX_PINNED_SECS),
+		ChainHeadConfig {
+			global_max_pinned_blocks: MAX_PINNED_BLOCKS,
+			subscription_max_pinned_duration: Duration::from_secs(MAX_PINNED_SECS),
+			..Default::default()
+		},
 	)
 	.into_rpc();
 
@@ -465,8 +482,11 @@ async fn call_runtime_without_flag() {
 		backend,
 		Arc::new(TaskExecutor::default()),
 		CHAIN_GENESIS,
-		MAX_PINNED_BLOCKS,
-		Duration::from_secs(MAX_PINNED_SECS),
+		ChainHeadConfig {
+			global_max_pinned_blocks: MAX_PINNED_BLOCKS,
+			subscription_max_pinned_duration: Duration::from_secs(MAX_PINNED_SECS),
+			..Default::default()
+		},
 	)
 	.into_rpc();
 
@@ -508,24 +528,32 @@ async fn call_runtime_without_flag() {
 	);
 }
 
+fn hex_string<Data: AsRef<[u8]>>(data: &Data) -> String {
+	format!("0x{:?}", HexDisplay::from(&data.as_ref()))
+}
+
 #[tokio::test]
-async fn get_storage() {
+async fn get_storage_hash() {
 	let (mut client, api, mut block_sub, sub_id, block) = setup_api().await;
 	let block_hash = format!("{:?}", block.header.hash());
-	let invalid_hash = format!("0x{:?}", HexDisplay::from(&INVALID_HASH));
-	let key = format!("0x{:?}", HexDisplay::from(&KEY));
+	let invalid_hash = hex_string(&INVALID_HASH);
+	let key = hex_string(&KEY);
+	let items = vec![StorageQuery { key: key.clone(), query_type: StorageQueryType::Hash }];
 
 	// Subscription ID is stale the disjoint event is emitted.
 	let mut sub = api
-		.subscribe("chainHead_unstable_storage", ["invalid_sub_id", &invalid_hash, &key])
+		.subscribe(
+			"chainHead_unstable_storage",
+			rpc_params!["invalid_sub_id", &invalid_hash, items.clone()],
+		)
 		.await
 		.unwrap();
-	let event: ChainHeadEvent<String> = get_next_event(&mut sub).await;
-	assert_eq!(event, ChainHeadEvent::<String>::Disjoint);
+	let event: ChainHeadStorageEvent = get_next_event(&mut sub).await;
+	assert_eq!(event, ChainHeadStorageEvent::Disjoint);
 
 	// Valid subscription ID with invalid block hash will error.
 	let err = api
-		.subscribe("chainHead_unstable_storage", [&sub_id, &invalid_hash, &key])
+		.subscribe("chainHead_unstable_storage", rpc_params![&sub_id, &invalid_hash, items.clone()])
 		.await
 		.unwrap_err();
 	assert_matches!(err,
@@ -534,11 +562,11 @@ async fn get_storage() {
 
 	// Valid call without storage at the key.
 	let mut sub = api
-		.subscribe("chainHead_unstable_storage", [&sub_id, &block_hash, &key])
+		.subscribe("chainHead_unstable_storage", rpc_params![&sub_id, &block_hash, items.clone()])
 		.await
 		.unwrap();
-	let event: ChainHeadEvent<Option<String>> = get_next_event(&mut sub).await;
-	assert_matches!(event, ChainHeadEvent::<Option<String>>::Done(done) if done.result.is_none());
+	let event: ChainHeadStorageEvent = get_next_event(&mut sub).await;
+	assert_eq!(event, ChainHeadStorageEvent::Done);
 
 	// Import a new block with storage changes.
 	let mut builder = client.new_block(Default::default()).unwrap();
@@ -558,75 +586,381 @@ async fn get_storage() {
 	);
 
 	// Valid call with storage at the key.
-	let expected_value = Some(format!("0x{:?}", HexDisplay::from(&VALUE)));
+	let expected_hash = format!("{:?}", Blake2Hasher::hash(VALUE));
 	let mut sub = api
-		.subscribe("chainHead_unstable_storage", [&sub_id, &block_hash, &key])
+		.subscribe("chainHead_unstable_storage", rpc_params![&sub_id, &block_hash, items.clone()])
 		.await
 		.unwrap();
-	let event: ChainHeadEvent<Option<String>> = get_next_event(&mut sub).await;
-	assert_matches!(event, ChainHeadEvent::<Option<String>>::Done(done) if done.result == expected_value);
+	assert_matches!(
+		get_next_event::<ChainHeadStorageEvent>(&mut sub).await,
+		ChainHeadStorageEvent::Items(res) if res.items.len() == 1 &&
+			res.items[0].key == key && res.items[0].result == StorageResultType::Hash(expected_hash)
+	);
+	assert_eq!(
+		get_next_event::<ChainHeadStorageEvent>(&mut sub).await,
+		ChainHeadStorageEvent::Done
+	);
 
 	// Child value set in `setup_api`.
-	let child_info = format!("0x{:?}", HexDisplay::from(b"child"));
+	let child_info = hex_string(&CHILD_STORAGE_KEY);
 	let genesis_hash = format!("{:?}", client.genesis_hash());
-	let expected_value = Some(format!("0x{:?}", HexDisplay::from(&CHILD_VALUE)));
+	let expected_hash = format!("{:?}", Blake2Hasher::hash(CHILD_VALUE));
 	let mut sub = api
-		.subscribe("chainHead_unstable_storage", [&sub_id, &genesis_hash, &key, &child_info])
+		.subscribe(
+			"chainHead_unstable_storage",
+			rpc_params![&sub_id, &genesis_hash, items, &child_info],
+		)
+		.await
+		.unwrap();
+	assert_matches!(
+		get_next_event::<ChainHeadStorageEvent>(&mut sub).await,
+		ChainHeadStorageEvent::Items(res) if res.items.len() == 1 &&
+			res.items[0].key == key && res.items[0].result == StorageResultType::Hash(expected_hash) &&
+			res.items[0].child_trie_key == Some(child_info)
+	);
+	assert_eq!(
+		get_next_event::<ChainHeadStorageEvent>(&mut sub).await,
+		ChainHeadStorageEvent::Done
+	);
+}
+
+#[tokio::test]
+async fn get_storage_multi_query_iter() {
+	let (mut client, api, mut block_sub, sub_id, _block) = setup_api().await;
+	let key = hex_string(&KEY);
+
+	// Import a new block with storage changes.
+	let mut builder = client.new_block(Default::default()).unwrap();
+	builder.push_storage_change(KEY.to_vec(), Some(VALUE.to_vec())).unwrap();
+	let block = builder.build().unwrap().block;
+	let block_hash = format!("{:?}", block.header.hash());
+	client.import(BlockOrigin::Own, block.clone()).await.unwrap();
+
+	// Ensure the imported block is propagated and pinned for this subscription.
+	assert_matches!(
+		get_next_event::<FollowEvent<String>>(&mut block_sub).await,
+		FollowEvent::NewBlock(_)
+	);
+	assert_matches!(
+		get_next_event::<FollowEvent<String>>(&mut block_sub).await,
+		FollowEvent::BestBlockChanged(_)
+	);
+
+	// Valid call with storage at the key.
+	let expected_hash = format!("{:?}", Blake2Hasher::hash(VALUE));
+	let expected_value = hex_string(&VALUE);
+	let mut sub = api
+		.subscribe(
+			"chainHead_unstable_storage",
+			rpc_params![
+				&sub_id,
+				&block_hash,
+				vec![
+					StorageQuery {
+						key: key.clone(),
+						query_type: StorageQueryType::DescendantsHashes
+					},
+					StorageQuery {
+						key: key.clone(),
+						query_type: StorageQueryType::DescendantsValues
+					}
+				]
+			],
+		)
+		.await
+		.unwrap();
+
+	// Each descendant query is reported by its own `Items` event.
+	assert_matches!(
+		get_next_event::<ChainHeadStorageEvent>(&mut sub).await,
+		ChainHeadStorageEvent::Items(res) if res.items.len() == 1 &&
+			res.items[0].key == key && res.items[0].result == StorageResultType::Hash(expected_hash)
+	);
+	assert_matches!(
+		get_next_event::<ChainHeadStorageEvent>(&mut sub).await,
+		ChainHeadStorageEvent::Items(res) if res.items.len() == 1 &&
+			res.items[0].key == key && res.items[0].result == StorageResultType::Value(expected_value)
+	);
+	assert_eq!(
+		get_next_event::<ChainHeadStorageEvent>(&mut sub).await,
+		ChainHeadStorageEvent::Done
+	);
+
+	// Child value set in `setup_api`.
+	let child_info = hex_string(&CHILD_STORAGE_KEY);
+	let genesis_hash = format!("{:?}", client.genesis_hash());
+	let expected_hash = format!("{:?}", Blake2Hasher::hash(CHILD_VALUE));
+	let expected_value = hex_string(&CHILD_VALUE);
+	let mut sub = api
+		.subscribe(
+			"chainHead_unstable_storage",
+			rpc_params![
+				&sub_id,
+				&genesis_hash,
+				vec![
+					StorageQuery {
+						key: key.clone(),
+						query_type: StorageQueryType::DescendantsHashes
+					},
+					StorageQuery {
+						key: key.clone(),
+						query_type: StorageQueryType::DescendantsValues
+					}
+				],
+				&child_info
+			],
+		)
+		.await
+		.unwrap();
+
+	assert_matches!(
+		get_next_event::<ChainHeadStorageEvent>(&mut sub).await,
+		ChainHeadStorageEvent::Items(res) if res.items.len() == 1 &&
+			res.items[0].key == key && res.items[0].result == StorageResultType::Hash(expected_hash)
+	);
+	assert_matches!(
+		get_next_event::<ChainHeadStorageEvent>(&mut sub).await,
+		ChainHeadStorageEvent::Items(res) if res.items.len() == 1 &&
+			res.items[0].key == key && res.items[0].result == StorageResultType::Value(expected_value)
+	);
+	assert_eq!(
+		get_next_event::<ChainHeadStorageEvent>(&mut sub).await,
+		ChainHeadStorageEvent::Done
+	);
+}
+
+#[tokio::test]
+async fn get_storage_value_and_merkle_value() {
+	let (mut client, api, mut block_sub, sub_id, _block) = setup_api().await;
+	let key = hex_string(&KEY);
+
+	// Import a new block with storage changes.
+	let mut builder = client.new_block(Default::default()).unwrap();
+	builder.push_storage_change(KEY.to_vec(), Some(VALUE.to_vec())).unwrap();
+	let block = builder.build().unwrap().block;
+	let block_hash = format!("{:?}", block.header.hash());
+	client.import(BlockOrigin::Own, block.clone()).await.unwrap();
+
+	// Ensure the imported block is propagated and pinned for this subscription.
+	assert_matches!(
+		get_next_event::<FollowEvent<String>>(&mut block_sub).await,
+		FollowEvent::NewBlock(_)
+	);
+	assert_matches!(
+		get_next_event::<FollowEvent<String>>(&mut block_sub).await,
+		FollowEvent::BestBlockChanged(_)
+	);
+
+	// The queries that are not iterating over descendants are batched into a single event.
+	let expected_value = hex_string(&VALUE);
+	let mut sub = api
+		.subscribe(
+			"chainHead_unstable_storage",
+			rpc_params![
+				&sub_id,
+				&block_hash,
+				vec![
+					StorageQuery { key: key.clone(), query_type: StorageQueryType::Value },
+					StorageQuery {
+						key: key.clone(),
+						query_type: StorageQueryType::ClosestDescendantMerkleValue
+					}
+				]
+			],
+		)
+		.await
+		.unwrap();
+
+	let res = match get_next_event::<ChainHeadStorageEvent>(&mut sub).await {
+		ChainHeadStorageEvent::Items(res) => res,
+		event => panic!("Expected items event, got {:?}", event),
+	};
+	assert_eq!(res.items.len(), 2);
+	assert_eq!(res.items[0].key, key);
+	assert_eq!(res.items[0].result, StorageResultType::Value(expected_value));
+	assert_eq!(res.items[1].key, key);
+	assert_matches!(
+		res.items[1].result,
+		StorageResultType::ClosestDescendantMerkleValue(ref merkle_value) if merkle_value.starts_with("0x")
+	);
+	assert_eq!(
+		get_next_event::<ChainHeadStorageEvent>(&mut sub).await,
+		ChainHeadStorageEvent::Done
+	);
+}
+
+#[tokio::test]
+async fn get_storage_descendants_wait_for_continue() {
+	let (mut client, api, mut block_sub, sub_id, _block) = setup_api().await;
+
+	// Import a new block with more descendants than reported by a single page.
+	let mut builder = client.new_block(Default::default()).unwrap();
+	for i in 0..15u8 {
+		builder.push_storage_change(vec![b':', b'm', i], Some(vec![i])).unwrap();
+	}
+	let block = builder.build().unwrap().block;
+	let block_hash = format!("{:?}", block.header.hash());
+	client.import(BlockOrigin::Own, block.clone()).await.unwrap();
+
+	// Ensure the imported block is propagated and pinned for this subscription.
+	assert_matches!(
+		get_next_event::<FollowEvent<String>>(&mut block_sub).await,
+		FollowEvent::NewBlock(_)
+	);
+	assert_matches!(
+		get_next_event::<FollowEvent<String>>(&mut block_sub).await,
+		FollowEvent::BestBlockChanged(_)
+	);
+
+	let mut sub = api
+		.subscribe(
+			"chainHead_unstable_storage",
+			rpc_params![
+				&sub_id,
+				&block_hash,
+				vec![StorageQuery {
+					key: hex_string(&b":m"),
+					query_type: StorageQueryType::DescendantsValues
+				}]
+			],
+		)
+		.await
+		.unwrap();
+	let operation_id = serde_json::to_string(&sub.subscription_id()).unwrap();
+
+	// The first page of results.
+	assert_matches!(
+		get_next_event::<ChainHeadStorageEvent>(&mut sub).await,
+		ChainHeadStorageEvent::Items(res) if res.items.len() == 10 &&
+			res.items[0].key == hex_string(&[b':', b'm', 0]) &&
+			res.items[9].key == hex_string(&[b':', b'm', 9])
+	);
+	assert_eq!(
+		get_next_event::<ChainHeadStorageEvent>(&mut sub).await,
+		ChainHeadStorageEvent::WaitForContinue
+	);
+
+	// No further events are produced until the operation is continued.
+	assert!(tokio::time::timeout(Duration::from_secs(1), sub.next::<ChainHeadStorageEvent>())
+		.await
+		.is_err());
+
+	let _res: () = api.call("chainHead_unstable_continue", [&sub_id, &operation_id]).await.unwrap();
+
+	assert_matches!(
+		get_next_event::<ChainHeadStorageEvent>(&mut sub).await,
+		ChainHeadStorageEvent::Items(res) if res.items.len() == 5 &&
+			res.items[0].key == hex_string(&[b':', b'm', 10]) &&
+			res.items[4].key == hex_string(&[b':', b'm', 14])
+	);
+	assert_eq!(
+		get_next_event::<ChainHeadStorageEvent>(&mut sub).await,
+		ChainHeadStorageEvent::Done
+	);
+
+	// The operation is no longer waiting.
+	let err = api
+		.call::<_, ()>("chainHead_unstable_continue", [&sub_id, &operation_id])
+		.await
+		.unwrap_err();
+	assert_matches!(err,
+		Error::Call(CallError::Custom(ref err)) if err.code() == 2005
+	);
+
+	// Invalid follow subscription IDs are ignored.
+	let _res: () = api
+		.call("chainHead_unstable_continue", ["invalid_sub_id", &operation_id])
 		.await
 		.unwrap();
-	let event: ChainHeadEvent<Option<String>> = get_next_event(&mut sub).await;
-	assert_matches!(event, ChainHeadEvent::<Option<String>>::Done(done) if done.result == expected_value);
 }
 
 #[tokio::test]
 async fn get_storage_wrong_key() {
 	let (mut _client, api, mut _block_sub, sub_id, block) = setup_api().await;
 	let block_hash = format!("{:?}", block.header.hash());
-	let key = format!("0x{:?}", HexDisplay::from(&KEY));
+	let key = hex_string(&KEY);
 
 	// Key is prefixed by CHILD_STORAGE_KEY_PREFIX.
 	let mut prefixed_key = well_known_keys::CHILD_STORAGE_KEY_PREFIX.to_vec();
 	prefixed_key.extend_from_slice(&KEY);
-	let prefixed_key = format!("0x{:?}", HexDisplay::from(&prefixed_key));
+	let prefixed_key = hex_string(&prefixed_key);
 	let mut sub = api
-		.subscribe("chainHead_unstable_storage", [&sub_id, &block_hash, &prefixed_key])
+		.subscribe(
+			"chainHead_unstable_storage",
+			rpc_params![
+				&sub_id,
+				&block_hash,
+				vec![StorageQuery { key: prefixed_key, query_type: StorageQueryType::Value }]
+			],
+		)
 		.await
 		.unwrap();
-	let event: ChainHeadEvent<Option<String>> = get_next_event(&mut sub).await;
-	assert_matches!(event, ChainHeadEvent::<Option<String>>::Done(done) if done.result.is_none());
+	assert_eq!(
+		get_next_event::<ChainHeadStorageEvent>(&mut sub).await,
+		ChainHeadStorageEvent::Done
+	);
 
 	// Key is prefixed by DEFAULT_CHILD_STORAGE_KEY_PREFIX.
 	let mut prefixed_key = well_known_keys::DEFAULT_CHILD_STORAGE_KEY_PREFIX.to_vec();
 	prefixed_key.extend_from_slice(&KEY);
-	let prefixed_key = format!("0x{:?}", HexDisplay::from(&prefixed_key));
+	let prefixed_key = hex_string(&prefixed_key);
 	let mut sub = api
-		.subscribe("chainHead_unstable_storage", [&sub_id, &block_hash, &prefixed_key])
+		.subscribe(
+			"chainHead_unstable_storage",
+			rpc_params![
+				&sub_id,
+				&block_hash,
+				vec![StorageQuery { key: prefixed_key, query_type: StorageQueryType::Hash }]
+			],
+		)
 		.await
 		.unwrap();
-	let event: ChainHeadEvent<Option<String>> = get_next_event(&mut sub).await;
-	assert_matches!(event, ChainHeadEvent::<Option<String>>::Done(done) if done.result.is_none());
+	assert_eq!(
+		get_next_event::<ChainHeadStorageEvent>(&mut sub).await,
+		ChainHeadStorageEvent::Done
+	);
 
 	// Child key is prefixed by CHILD_STORAGE_KEY_PREFIX.
 	let mut prefixed_key = well_known_keys::CHILD_STORAGE_KEY_PREFIX.to_vec();
-	prefixed_key.extend_from_slice(b"child");
-	let prefixed_key = format!("0x{:?}", HexDisplay::from(&prefixed_key));
+	prefixed_key.extend_from_slice(CHILD_STORAGE_KEY);
+	let prefixed_key = hex_string(&prefixed_key);
 	let mut sub = api
-		.subscribe("chainHead_unstable_storage", [&sub_id, &block_hash, &key, &prefixed_key])
+		.subscribe(
+			"chainHead_unstable_storage",
+			rpc_params![
+				&sub_id,
+				&block_hash,
+				vec![StorageQuery { key: key.clone(), query_type: StorageQueryType::Value }],
+				&prefixed_key
+			],
+		)
 		.await
 		.unwrap();
-	let event: ChainHeadEvent<Option<String>> = get_next_event(&mut sub).await;
-	assert_matches!(event, ChainHeadEvent::<Option<String>>::Done(done) if done.result.is_none());
+	assert_eq!(
+		get_next_event::<ChainHeadStorageEvent>(&mut sub).await,
+		ChainHeadStorageEvent::Done
+	);
 
 	// Child key is prefixed by DEFAULT_CHILD_STORAGE_KEY_PREFIX.
 	let mut prefixed_key = well_known_keys::DEFAULT_CHILD_STORAGE_KEY_PREFIX.to_vec();
-	prefixed_key.extend_from_slice(b"child");
-	let prefixed_key = format!("0x{:?}", HexDisplay::from(&prefixed_key));
+	prefixed_key.extend_from_slice(CHILD_STORAGE_KEY);
+	let prefixed_key = hex_string(&prefixed_key);
 	let mut sub = api
-		.subscribe("chainHead_unstable_storage", [&sub_id, &block_hash, &key, &prefixed_key])
+		.subscribe(
+			"chainHead_unstable_storage",
+			rpc_params![
+				&sub_id,
+				&block_hash,
+				vec![StorageQuery { key, query_type: StorageQueryType::Value }],
+				&prefixed_key
+			],
+		)
 		.await
 		.unwrap();
-	let event: ChainHeadEvent<Option<String>> = get_next_event(&mut sub).await;
-	assert_matches!(event, ChainHeadEvent::<Option<String>>::Done(done) if done.result.is_none());
+	assert_eq!(
+		get_next_event::<ChainHeadStorageEvent>(&mut sub).await,
+		ChainHeadStorageEvent::Done
+	);
 }
 
 #[tokio::test]
@@ -640,8 +974,11 @@ async fn follow_generates_initial_blocks() {
 		backend,
 		Arc::new(TaskExecutor::default()),
 		CHAIN_GENESIS,
-		MAX_PINNED_BLOCKS,
-		Duration::from_secs(MAX_PINNED_SECS),
+		ChainHeadConfig {
+			global_max_pinned_blocks: MAX_PINNED_BLOCKS,
+			subscription_max_pinned_duration: Duration::from_secs(MAX_PINNED_SECS),
+			..Default::default()
+		},
 	)
 	.into_rpc();
 
@@ -768,8 +1105,11 @@ async fn follow_exceeding_pinned_blocks() {
 		backend,
 		Arc::new(TaskExecutor::default()),
 		CHAIN_GENESIS,
-		2,
-		Duration::from_secs(MAX_PINNED_SECS),
+		ChainHeadConfig {
+			global_max_pinned_blocks: 2,
+			subscription_max_pinned_duration: Duration::from_secs(MAX_PINNED_SECS),
+			..Default::default()
+		},
 	)
 	.into_rpc();
 
@@ -819,8 +1159,11 @@ async fn follow_with_unpin() {
 		backend,
 		Arc::new(TaskExecutor::default()),
 		CHAIN_GENESIS,
-		2,
-		Duration::from_secs(MAX_PINNED_SECS),
+		ChainHeadConfig {
+			global_max_pinned_blocks: 2,
+			subscription_max_pinned_duration: Duration::from_secs(MAX_PINNED_SECS),
+			..Default::default()
+		},
 	)
 	.into_rpc();
 
@@ -900,8 +1243,11 @@ async fn follow_prune_best_block() {
 		backend,
 		Arc::new(TaskExecutor::default()),
 		CHAIN_GENESIS,
-		MAX_PINNED_BLOCKS,
-		Duration::from_secs(MAX_PINNED_SECS),
+		ChainHeadConfig {
+			global_max_pinned_blocks: MAX_PINNED_BLOCKS,
+			subscription_max_pinned_duration: Duration::from_secs(MAX_PINNED_SECS),
+			..Default::default()
+		},
 	)
 	.into_rpc();
 
@@ -1057,8 +1403,11 @@ async fn follow_forks_pruned_block() {
 		backend,
 		Arc::new(TaskExecutor::default()),
 		CHAIN_GENESIS,
-		MAX_PINNED_BLOCKS,
-		Duration::from_secs(MAX_PINNED_SECS),
+		ChainHeadConfig {
+			global_max_pinned_blocks: MAX_PINNED_BLOCKS,
+			subscription_max_pinned_duration: Duration::from_secs(MAX_PINNED_SECS),
+			..Default::default()
+		},
 	)
 	.into_rpc();
 
@@ -1171,8 +1520,11 @@ async fn follow_report_multiple_pruned_block() {
 		backend,
 		Arc::new(TaskExecutor::default()),
 		CHAIN_GENESIS,
-		MAX_PINNED_BLOCKS,
-		Duration::from_secs(MAX_PINNED_SECS),
+		ChainHeadConfig {
+			global_max_pinned_blocks: MAX_PINNED_BLOCKS,
+			subscription_max_pinned_duration: Duration::from_secs(MAX_PINNED_SECS),
+			..Default::default()
+		},
 	)
 	.into_rpc();
 
@@ -1377,8 +1729,11 @@ async fn pin_block_references() {
 		backend.clone(),
 		Arc::new(TaskExecutor::default()),
 		CHAIN_GENESIS,
-		3,
-		Duration::from_secs(MAX_PINNED_SECS),
+		ChainHeadConfig {
+			global_max_pinned_blocks: 3,
+			subscription_max_pinned_duration: Duration::from_secs(MAX_PINNED_SECS),
+			..Default::default()
+		},
 	)
 	.into_rpc();
 
@@ -1487,8 +1842,11 @@ async fn follow_finalized_before_new_block() {
 		backend,
 		Arc::new(TaskExecutor::default()),
 		CHAIN_GENESIS,
-		MAX_PINNED_BLOCKS,
-		Duration::from_secs(MAX_PINNED_SECS),
+		ChainHeadConfig {
+			global_max_pinned_blocks: MAX_PINNED_BLOCKS,
+			subscription_max_pinned_duration: Duration::from_secs(MAX_PINNED_SECS),
+			..Default::default()
+		},
 	)
 	.into_rpc();
 
@@ -1566,3 +1924,145 @@ async fn follow_finalized_before_new_block() {
 	});
 	assert_eq!(event, expected);
 }
+
+#[tokio::test]
+async fn follow_exceeding_max_subscriptions() {
+	let builder = TestClientBuilder::new();
+	let backend = builder.backend();
+	let client = Arc::new(builder.build());
+
+	let api = ChainHead::new(
+		client.clone(),
+		backend,
+		Arc::new(TaskExecutor::default()),
+		CHAIN_GENESIS,
+		ChainHeadConfig {
+			global_max_pinned_blocks: MAX_PINNED_BLOCKS,
+			subscription_max_pinned_duration: Duration::from_secs(MAX_PINNED_SECS),
+			max_follow_subscriptions_per_connection: 1,
+			..Default::default()
+		},
+	)
+	.into_rpc();
+
+	let mut sub = api.subscribe("chainHead_unstable_follow", [false]).await.unwrap();
+	assert_matches!(
+		get_next_event::<FollowEvent<String>>(&mut sub).await,
+		FollowEvent::Initialized(_)
+	);
+
+	// The connection reached its maximum number of subscriptions.
+	let mut sub = api.subscribe("chainHead_unstable_follow", [false]).await.unwrap();
+	assert_matches!(get_next_event::<FollowEvent<String>>(&mut sub).await, FollowEvent::Stop);
+}
+
+#[tokio::test]
+async fn operations_exceeding_max_ongoing_operations() {
+	let builder = TestClientBuilder::new();
+	let backend = builder.backend();
+	let mut client = Arc::new(builder.build());
+
+	let api = ChainHead::new(
+		client.clone(),
+		backend,
+		Arc::new(TaskExecutor::default()),
+		CHAIN_GENESIS,
+		ChainHeadConfig {
+			global_max_pinned_blocks: MAX_PINNED_BLOCKS,
+			subscription_max_pinned_duration: Duration::from_secs(MAX_PINNED_SECS),
+			subscription_max_ongoing_operations: 1,
+			operation_max_storage_items: 1,
+			..Default::default()
+		},
+	)
+	.into_rpc();
+
+	let mut block_sub = api.subscribe("chainHead_unstable_follow", [true]).await.unwrap();
+	let sub_id = block_sub.subscription_id();
+	let sub_id = serde_json::to_string(&sub_id).unwrap();
+
+	// Import a block with more descendants than reported by a single page.
+	let mut builder = client.new_block(Default::default()).unwrap();
+	for i in 0..2u8 {
+		builder.push_storage_change(vec![b':', b'm', i], Some(vec![i])).unwrap();
+	}
+	let block = builder.build().unwrap().block;
+	let block_hash = format!("{:?}", block.header.hash());
+	client.import(BlockOrigin::Own, block.clone()).await.unwrap();
+
+	// Ensure the imported block is propagated and pinned for this subscription.
+	assert_matches!(
+		get_next_event::<FollowEvent<String>>(&mut block_sub).await,
+		FollowEvent::Initialized(_)
+	);
+	assert_matches!(
+		get_next_event::<FollowEvent<String>>(&mut block_sub).await,
+		FollowEvent::NewBlock(_)
+	);
+	assert_matches!(
+		get_next_event::<FollowEvent<String>>(&mut block_sub).await,
+		FollowEvent::BestBlockChanged(_)
+	);
+
+	// The storage operation stays in progress while waiting for `continue`.
+	let mut storage_sub = api
+		.subscribe(
+			"chainHead_unstable_storage",
+			rpc_params![
+				&sub_id,
+				&block_hash,
+				vec![StorageQuery {
+					key: hex_string(&b":m"),
+					query_type: StorageQueryType::DescendantsValues
+				}]
+			],
+		)
+		.await
+		.unwrap();
+	let operation_id = serde_json::to_string(&storage_sub.subscription_id()).unwrap();
+	assert_matches!(
+		get_next_event::<ChainHeadStorageEvent>(&mut storage_sub).await,
+		ChainHeadStorageEvent::Items(res) if res.items.len() == 1
+	);
+	assert_eq!(
+		get_next_event::<ChainHeadStorageEvent>(&mut storage_sub).await,
+		ChainHeadStorageEvent::WaitForContinue
+	);
+
+	// No other operation can be started.
+	let mut sub = api.subscribe("chainHead_unstable_body", [&sub_id, &block_hash]).await.unwrap();
+	assert_matches!(
+		get_next_event::<ChainHeadEvent<String>>(&mut sub).await,
+		ChainHeadEvent::Inaccessible(_)
+	);
+	let mut sub = api
+		.subscribe(
+			"chainHead_unstable_storage",
+			rpc_params![
+				&sub_id,
+				&block_hash,
+				vec![StorageQuery { key: hex_string(&KEY), query_type: StorageQueryType::Value }]
+			],
+		)
+		.await
+		.unwrap();
+	assert_matches!(
+		get_next_event::<ChainHeadStorageEvent>(&mut sub).await,
+		ChainHeadStorageEvent::Inaccessible(_)
+	);
+
+	// Headers are not subject to the operation limits.
+	let response: Option<String> =
+		api.call("chainHead_unstable_header", [&sub_id, &block_hash]).await.unwrap();
+	assert!(response.is_some());
+
+	let _res: () = api.call("chainHead_unstable_continue", [&sub_id, &operation_id]).await.unwrap();
+	assert_matches!(
+		get_next_event::<ChainHeadStorageEvent>(&mut storage_sub).await,
+		ChainHeadStorageEvent::Items(res) if res.items.len() == 1
+	);
+	assert_eq!(
+		get_next_event::<ChainHeadStorageEvent>(&mut storage_sub).await,
+		ChainHeadStorageEvent::Done
+	);
+}
@@ -0,0 +1,234 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Storage queries for the RPC-V2 spec.
+
+use std::{marker::PhantomData, sync::Arc};
+
+use sc_client_api::{Backend, ChildInfo, MerkleValue, StorageKey, StorageProvider};
+use sp_core::{hexdisplay::HexDisplay, storage::well_known_keys};
+use sp_runtime::traits::Block as BlockT;
+
+use super::events::{StorageResult, StorageResultType};
+
+/// Call into the storage of blocks.
+pub struct Storage<Client, Block, BE> {
+	/// Substrate client.
+	client: Arc<Client>,
+	_phandom: PhantomData<(BE, Block)>,
+}
+
+impl<Client, Block, BE> Storage<Client, Block, BE> {
+	/// Constructs a new [`Storage`].
+	pub fn new(client: Arc<Client>) -> Self {
+		Self { client, _phandom: PhantomData }
+	}
+}
+
+impl<Client, Block, BE> Clone for Storage<Client, Block, BE> {
+	fn clone(&self) -> Self {
+		Self { client: self.client.clone(), _phandom: PhantomData }
+	}
+}
+
+/// Query to iterate over storage.
+#[derive(Debug, Clone)]
+pub struct QueryIter {
+	/// The key from which the iteration was started.
+	pub query_key: StorageKey,
+	/// The key after which pagination should resume.
+	pub pagination_start_key: Option<StorageKey>,
+	/// The type of the query (either value or hash).
+	pub ty: IterQueryType,
+}
+
+/// The query type of an iteration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IterQueryType {
+	/// Iterating over (key, value) pairs.
+	Value,
+	/// Iterating over (key, hash) pairs.
+	Hash,
+}
+
+/// The result of making a query call.
+pub type QueryResult = Result<Option<StorageResult>, String>;
+
+/// The result of iterating over keys.
+pub struct QueryIterResult {
+	/// The storage results produced by the iteration.
+	pub results: Vec<StorageResult>,
+	/// The iteration that must be resumed to produce the remaining results.
+	///
+	/// This is `None` when all the descendants of the query key have been reported.
+	pub next: Option<QueryIter>,
+}
+
+/// Returns `true` if the key is prefixed by `:child_storage:` or `:child_storage:default:`.
+///
+/// Keys of the main trie that point into child tries must not be queried directly.
+pub fn is_child_storage_key(key: &[u8]) -> bool {
+	well_known_keys::is_default_child_storage_key(key) || well_known_keys::is_child_storage_key(key)
+}
+
+/// Hex-encode the given bytes with the `0x` prefix.
+fn hex_encode(data: &[u8]) -> String {
+	format!("0x{:?}", HexDisplay::from(&data))
+}
+
+impl<Client, Block, BE> Storage<Client, Block, BE>
+where
+	Block: BlockT + 'static,
+	BE: Backend<Block> + 'static,
+	Client: StorageProvider<Block, BE> + 'static,
+{
+	/// Fetch the value from storage.
+	pub fn query_value(
+		&self,
+		hash: Block::Hash,
+		key: &StorageKey,
+		child_key: Option<&ChildInfo>,
+	) -> QueryResult {
+		let result = if let Some(child_key) = child_key {
+			self.client.child_storage(hash, child_key, key)
+		} else {
+			self.client.storage(hash, key)
+		};
+
+		result
+			.map(|opt| {
+				opt.map(|storage_data| StorageResult {
+					key: hex_encode(&key.0),
+					result: StorageResultType::Value(hex_encode(&storage_data.0)),
+					child_trie_key: child_key.map(|child| hex_encode(child.storage_key())),
+				})
+			})
+			.map_err(|error| error.to_string())
+	}
+
+	/// Fetch the hash of a value from storage.
+	pub fn query_hash(
+		&self,
+		hash: Block::Hash,
+		key: &StorageKey,
+		child_key: Option<&ChildInfo>,
+	) -> QueryResult {
+		let result = if let Some(child_key) = child_key {
+			self.client.child_storage_hash(hash, child_key, key)
+		} else {
+			self.client.storage_hash(hash, key)
+		};
+
+		result
+			.map(|opt| {
+				opt.map(|storage_hash| StorageResult {
+					key: hex_encode(&key.0),
+					result: StorageResultType::Hash(hex_encode(storage_hash.as_ref())),
+					child_trie_key: child_key.map(|child| hex_encode(child.storage_key())),
+				})
+			})
+			.map_err(|error| error.to_string())
+	}
+
+	/// Fetch the closest merkle value.
+	pub fn query_merkle_value(
+		&self,
+		hash: Block::Hash,
+		key: &StorageKey,
+		child_key: Option<&ChildInfo>,
+	) -> QueryResult {
+		let result = if let Some(child_key) = child_key {
+			self.client.child_closest_merkle_value(hash, child_key, key)
+		} else {
+			self.client.closest_merkle_value(hash, key)
+		};
+
+		result
+			.map(|opt| {
+				opt.map(|storage_data| {
+					let result = match &storage_data {
+						MerkleValue::Node(data) => hex_encode(data.as_slice()),
+						MerkleValue::Hash(hash) => hex_encode(hash.as_ref()),
+					};
+
+					StorageResult {
+						key: hex_encode(&key.0),
+						result: StorageResultType::ClosestDescendantMerkleValue(result),
+						child_trie_key: child_key.map(|child| hex_encode(child.storage_key())),
+					}
+				})
+			})
+			.map_err(|error| error.to_string())
+	}
+
+	/// Iterate over at most `count` descendants of the query key.
+	///
+	/// The iteration starts after the pagination key of the query, if provided. When more
+	/// descendants are left to report, the returned [`QueryIterResult`] contains the query
+	/// that must be used to resume the iteration.
+	pub fn query_iter_pagination(
+		&self,
+		query: QueryIter,
+		hash: Block::Hash,
+		child_key: Option<&ChildInfo>,
+		count: usize,
+	) -> Result<QueryIterResult, String> {
+		let QueryIter { query_key, pagination_start_key, ty } = query;
+
+		let mut keys_iter = if let Some(child_key) = child_key {
+			self.client.child_storage_keys(
+				hash,
+				child_key.to_owned(),
+				Some(&query_key),
+				pagination_start_key.as_ref(),
+			)
+		} else {
+			self.client.storage_keys(hash, Some(&query_key), pagination_start_key.as_ref())
+		}
+		.map_err(|err| err.to_string())?;
+
+		let mut results = Vec::new();
+		let mut last_key = pagination_start_key;
+
+		while results.len() < count {
+			let Some(key) = keys_iter.next() else {
+				return Ok(QueryIterResult { results, next: None })
+			};
+
+			let result = match ty {
+				IterQueryType::Value => self.query_value(hash, &key, child_key),
+				IterQueryType::Hash => self.query_hash(hash, &key, child_key),
+			}?;
+
+			if let Some(result) = result {
+				results.push(result);
+			}
+			last_key = Some(key);
+		}
+
+		// The number of results reached the limit, check if there are any keys left.
+		if keys_iter.next().is_none() {
+			return Ok(QueryIterResult { results, next: None })
+		}
+
+		Ok(QueryIterResult {
+			results,
+			next: Some(QueryIter { query_key, pagination_start_key: last_key, ty }),
+		})
+	}
+}
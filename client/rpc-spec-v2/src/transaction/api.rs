@@ -19,7 +19,7 @@
 //! API trait for transactions.
 
 use crate::transaction::event::TransactionEvent;
-use jsonrpsee::proc_macros::rpc;
+use jsonrpsee::{core::RpcResult, proc_macros::rpc};
 use sp_core::Bytes;
 
 #[rpc(client, server)]
@@ -35,3 +35,32 @@ pub trait TransactionApi<Hash: Clone> {
 	)]
 	fn submit_and_watch(&self, bytes: Bytes);
 }
+
+#[rpc(client, server)]
+pub trait TransactionBroadcastApi {
+	/// Broadcast an extrinsic to the chain.
+	///
+	/// The extrinsic is submitted to the transaction pool and gossiped to the peers of the
+	/// node. If the extrinsic is dropped from the pool, it is submitted again on top of the
+	/// next best block. The broadcast ends once the extrinsic is included in a finalized
+	/// block or when `transaction_unstable_stop` is called with the returned operation ID.
+	///
+	/// The broadcast also ends if the extrinsic becomes invalid.
+	///
+	/// Returns an operation ID that can be used to stop the broadcast, or `null` if the server
+	/// is already running too many broadcasts.
+	///
+	/// # Unstable
+	///
+	/// This method is unstable and subject to change in the future.
+	#[method(name = "transaction_unstable_broadcast")]
+	fn broadcast(&self, bytes: Bytes) -> RpcResult<Option<String>>;
+
+	/// Stop broadcasting the extrinsic associated with the provided operation ID.
+	///
+	/// # Unstable
+	///
+	/// This method is unstable and subject to change in the future.
+	#[method(name = "transaction_unstable_stop")]
+	fn stop_broadcast(&self, operation_id: String) -> RpcResult<()>;
+}
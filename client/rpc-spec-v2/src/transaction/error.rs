@@ -21,6 +21,10 @@
 //! Errors are interpreted as transaction events for subscriptions.
 
 use crate::transaction::event::{TransactionError, TransactionEvent};
+use jsonrpsee::{
+	core::Error as RpcError,
+	types::error::{CallError, ErrorObject, INVALID_PARAMS_CODE},
+};
 use sc_transaction_pool_api::error::Error as PoolError;
 use sp_runtime::transaction_validity::InvalidTransaction;
 
@@ -95,6 +99,53 @@ impl<Hash> From<Error> for TransactionEvent<Hash> {
 				TransactionEvent::Invalid(TransactionError {
 					error: "The pool is not accepting future transactions".into(),
 				}),
+			Error::Pool(PoolError::SenderLimitReached) =>
+				TransactionEvent::Invalid(TransactionError {
+					error: "The sender of the transaction reached its limit of transactions in the pool"
+						.into(),
+				}),
+			Error::Pool(PoolError::LaneFull) => TransactionEvent::Invalid(TransactionError {
+				error: "The pool capacity for normal transactions is exhausted".into(),
+			}),
+		}
+	}
+}
+
+/// Extrinsic has an invalid format.
+///
+/// # Note
+///
+/// This is similar to the old `author` API error code.
+pub(crate) const BAD_FORMAT: i32 = 1001;
+
+/// Transaction broadcast RPC errors.
+///
+/// Unlike [`Error`], these are returned directly by the `transaction_unstable_broadcast`
+/// and `transaction_unstable_stop` methods.
+#[derive(Debug, thiserror::Error)]
+pub enum ErrorBroadcast {
+	/// The provided extrinsic cannot be decoded.
+	#[error("Extrinsic has invalid format: {0}")]
+	BadFormat(String),
+	/// The provided operation ID does not belong to an active broadcast.
+	#[error("Invalid operation id: {0}")]
+	InvalidOperationId(String),
+}
+
+impl From<ErrorBroadcast> for ErrorObject<'static> {
+	fn from(e: ErrorBroadcast) -> Self {
+		let msg = e.to_string();
+
+		match e {
+			ErrorBroadcast::BadFormat(_) => ErrorObject::owned(BAD_FORMAT, msg, None::<()>),
+			ErrorBroadcast::InvalidOperationId(_) =>
+				ErrorObject::owned(INVALID_PARAMS_CODE, msg, None::<()>),
 		}
 	}
 }
+
+impl From<ErrorBroadcast> for RpcError {
+	fn from(e: ErrorBroadcast) -> Self {
+		CallError::Custom(e.into()).into()
+	}
+}
@@ -0,0 +1,207 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use super::*;
+use crate::transaction::{
+	error::BAD_FORMAT, transaction::TX_SOURCE, transaction_broadcast::OPERATION_ID_LEN,
+};
+use assert_matches::assert_matches;
+use codec::Encode;
+use jsonrpsee::{
+	core::Error as RpcError,
+	types::error::{CallError, INVALID_PARAMS_CODE},
+	RpcModule,
+};
+use sc_block_builder::BlockBuilderProvider;
+use sc_transaction_pool::{BasicPool, FullChainApi, Options};
+use sc_transaction_pool_api::{
+	ChainEvent, InPoolTransaction, MaintainedTransactionPool, TransactionPool,
+};
+use sp_blockchain::HeaderBackend;
+use sp_consensus::BlockOrigin;
+use sp_core::{testing::TaskExecutor, Bytes};
+use sp_runtime::{generic, traits::Block as BlockT, Perbill};
+use std::{sync::Arc, time::Duration};
+use substrate_test_runtime_client::{
+	runtime::{Block, Extrinsic, ExtrinsicBuilder, Transfer},
+	AccountKeyring, Backend, Client, ClientBlockImportExt,
+};
+
+type FullTransactionPool = BasicPool<FullChainApi<Client<Backend>, Block>, Block>;
+
+fn uxt(sender: AccountKeyring, nonce: u64) -> Extrinsic {
+	let tx = Transfer {
+		amount: Default::default(),
+		nonce,
+		from: sender.into(),
+		to: AccountKeyring::Bob.into(),
+	};
+	ExtrinsicBuilder::new_transfer(tx).build()
+}
+
+type BroadcastApi = RpcModule<TransactionBroadcast<FullTransactionPool, Client<Backend>>>;
+
+fn setup_api_with_options(
+	options: Options,
+) -> (Arc<Client<Backend>>, Arc<FullTransactionPool>, BroadcastApi) {
+	let client = Arc::new(substrate_test_runtime_client::new());
+	let pool = BasicPool::new_full(options, true.into(), None, TaskExecutor::new(), client.clone());
+
+	let api =
+		TransactionBroadcast::new(client.clone(), pool.clone(), Arc::new(TaskExecutor::default()))
+			.into_rpc();
+
+	(client, pool, api)
+}
+
+fn setup_api() -> (Arc<FullTransactionPool>, BroadcastApi) {
+	let (_client, pool, api) = setup_api_with_options(Default::default());
+	(pool, api)
+}
+
+async fn wait_for_ready(pool: &FullTransactionPool, ready: usize) {
+	tokio::time::timeout(Duration::from_secs(60), async {
+		while pool.status().ready != ready {
+			tokio::time::sleep(Duration::from_millis(10)).await;
+		}
+	})
+	.await
+	.unwrap();
+}
+
+#[tokio::test]
+async fn broadcast_invalid_format() {
+	let (_pool, api) = setup_api();
+
+	let xt: Bytes = Vec::<u8>::new().into();
+	let err = api.call::<_, Option<String>>("transaction_unstable_broadcast", [xt]).await;
+	assert_matches!(err,
+		Err(RpcError::Call(CallError::Custom(err))) if err.code() == BAD_FORMAT
+	);
+}
+
+#[tokio::test]
+async fn broadcast_submits_to_pool_until_stopped() {
+	let (pool, api) = setup_api();
+
+	let xt: Bytes = uxt(AccountKeyring::Alice, 0).encode().into();
+	let operation_id: String = api
+		.call::<_, Option<String>>("transaction_unstable_broadcast", [xt.clone()])
+		.await
+		.unwrap()
+		.unwrap();
+	wait_for_ready(&pool, 1).await;
+
+	// Operation IDs are random.
+	assert_eq!(operation_id.len(), OPERATION_ID_LEN);
+	assert!(operation_id.chars().all(|c| c.is_ascii_alphanumeric()));
+
+	// Broadcasting the same extrinsic starts an independent operation.
+	let other_operation_id: String = api
+		.call::<_, Option<String>>("transaction_unstable_broadcast", [xt])
+		.await
+		.unwrap()
+		.unwrap();
+	assert_ne!(operation_id, other_operation_id);
+
+	let _: () = api.call("transaction_unstable_stop", [&operation_id]).await.unwrap();
+	let _: () = api.call("transaction_unstable_stop", [&other_operation_id]).await.unwrap();
+
+	// Operations cannot be stopped twice.
+	let err = api.call::<_, ()>("transaction_unstable_stop", [&operation_id]).await;
+	assert_matches!(err,
+		Err(RpcError::Call(CallError::Custom(err))) if err.code() == INVALID_PARAMS_CODE
+	);
+}
+
+#[tokio::test]
+async fn stop_invalid_operation_id() {
+	let (_pool, api) = setup_api();
+
+	let err = api.call::<_, ()>("transaction_unstable_stop", ["invalid_id"]).await;
+	assert_matches!(err,
+		Err(RpcError::Call(CallError::Custom(err))) if err.code() == INVALID_PARAMS_CODE
+	);
+}
+
+#[tokio::test]
+async fn broadcast_resubmits_after_eviction() {
+	// A single ready transaction fits in the pool, and evicted transactions are only banned until
+	// the next maintenance.
+	let options = Options {
+		ready: sc_transaction_pool::PoolLimit { count: 1, total_bytes: 1024 * 1024 },
+		ban_time: Duration::ZERO,
+		..Default::default()
+	};
+	let (client, pool, api) = setup_api_with_options(options);
+
+	let xt = uxt(AccountKeyring::Alice, 0);
+	let xt_hash = pool.hash_of(&xt);
+	let _: Option<String> = api
+		.call("transaction_unstable_broadcast", [Bytes::from(xt.encode())])
+		.await
+		.unwrap();
+	wait_for_ready(&pool, 1).await;
+
+	// A newer transaction evicts the broadcast one.
+	let best_hash = client.info().best_hash;
+	pool.submit_one(&generic::BlockId::hash(best_hash), TX_SOURCE, uxt(AccountKeyring::Bob, 0))
+		.await
+		.unwrap();
+	assert!(pool.ready().all(|tx| *tx.hash() != xt_hash));
+
+	// The broadcast extrinsic is submitted again on top of one of the next best blocks.
+	let mut resubmitted = false;
+	for _ in 0..10 {
+		let block = client.new_block(Default::default()).unwrap().build().unwrap().block;
+		let hash = block.hash();
+		client.import(BlockOrigin::Own, block).await.unwrap();
+		pool.maintain(ChainEvent::NewBestBlock { hash, tree_route: None }).await;
+
+		tokio::time::sleep(Duration::from_millis(100)).await;
+		if pool.ready().any(|tx| *tx.hash() == xt_hash) {
+			resubmitted = true;
+			break
+		}
+	}
+	assert!(resubmitted);
+}
+
+#[tokio::test]
+async fn broadcast_stops_on_invalid_extrinsic() {
+	let (client, pool, api) = setup_api_with_options(Default::default());
+
+	let xt = ExtrinsicBuilder::new_fill_block(Perbill::from_percent(100)).build();
+	let at = generic::BlockId::hash(client.info().best_hash);
+	// The pool rejects the extrinsic as invalid. This also gets the runtime ready, such that the
+	// broadcast below is rejected quickly.
+	assert!(pool.submit_one(&at, TX_SOURCE, xt.clone()).await.is_err());
+
+	let operation_id: String = api
+		.call::<_, Option<String>>("transaction_unstable_broadcast", [Bytes::from(xt.encode())])
+		.await
+		.unwrap()
+		.unwrap();
+	tokio::time::sleep(Duration::from_secs(1)).await;
+
+	// The operation ended on its own, it can't be stopped anymore.
+	let err = api.call::<_, ()>("transaction_unstable_stop", [&operation_id]).await;
+	assert_matches!(err,
+		Err(RpcError::Call(CallError::Custom(err))) if err.code() == INVALID_PARAMS_CODE
+	);
+}
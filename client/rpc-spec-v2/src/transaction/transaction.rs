@@ -21,7 +21,7 @@
 use crate::{
 	transaction::{
 		api::TransactionApiServer,
-		error::Error,
+		error::{Error, BAD_FORMAT},
 		event::{
 			TransactionBlock, TransactionBroadcasted, TransactionDropped, TransactionError,
 			TransactionEvent,
@@ -73,14 +73,7 @@ impl<Pool, Client> Transaction<Pool, Client> {
 /// Possibly in the future we could allow opt-in for special treatment
 /// of such transactions, so that the block authors can inject
 /// some unique transactions via RPC and have them included in the pool.
-const TX_SOURCE: TransactionSource = TransactionSource::External;
-
-/// Extrinsic has an invalid format.
-///
-/// # Note
-///
-/// This is similar to the old `author` API error code.
-const BAD_FORMAT: i32 = 1001;
+pub(crate) const TX_SOURCE: TransactionSource = TransactionSource::External;
 
 #[async_trait]
 impl<Pool, Client> TransactionApiServer<BlockHash<Pool>> for Transaction<Pool, Client>
@@ -203,6 +196,10 @@ impl TransactionState {
 			TransactionStatus::Invalid => Some(TransactionEvent::Invalid(TransactionError {
 				error: "Extrinsic marked as invalid".into(),
 			})),
+			TransactionStatus::Cancelled => Some(TransactionEvent::Dropped(TransactionDropped {
+				broadcasted: self.broadcasted,
+				error: "Extrinsic cancelled by its sender".into(),
+			})),
 		}
 	}
 }
@@ -0,0 +1,241 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! API implementation for broadcasting transactions.
+//!
+//! A broadcast submits the transaction to the pool, which in turn announces it to the
+//! `sc-network-transactions` handler for gossiping. Whenever the transaction leaves the pool
+//! without being finalized, it is submitted again on top of the next best block. The broadcast
+//! ends once the pool reports the transaction as invalid.
+
+use crate::{
+	chain_head::LOG_TARGET,
+	transaction::{
+		api::TransactionBroadcastApiServer, error::ErrorBroadcast, transaction::TX_SOURCE,
+	},
+	SubscriptionTaskExecutor,
+};
+use codec::Decode;
+use futures::{
+	future::{self, AbortHandle, Abortable},
+	FutureExt, StreamExt,
+};
+use jsonrpsee::core::{async_trait, RpcResult};
+use log::debug;
+use parking_lot::RwLock;
+use rand::{distributions::Alphanumeric, Rng};
+use sc_client_api::BlockchainEvents;
+use sc_transaction_pool_api::{
+	error::{Error as PoolError, IntoPoolError},
+	TransactionFor, TransactionPool, TransactionStatus,
+};
+use sp_blockchain::HeaderBackend;
+use sp_core::Bytes;
+use sp_runtime::generic;
+use std::{collections::HashMap, sync::Arc};
+
+/// Maximum number of broadcasts running at the same time.
+///
+/// The limit applies to all the connections of the server together, because jsonrpsee doesn't
+/// tell method handlers which connection a call was made on.
+const MAX_CONCURRENT_BROADCASTS: usize = 512;
+
+/// Length of the operation IDs.
+pub(crate) const OPERATION_ID_LEN: usize = 16;
+
+/// An API for transaction broadcast RPC calls.
+pub struct TransactionBroadcast<Pool, Client> {
+	/// Substrate client.
+	client: Arc<Client>,
+	/// Transactions pool.
+	pool: Arc<Pool>,
+	/// Executor to spawn the broadcast tasks.
+	executor: SubscriptionTaskExecutor,
+	/// The abort handles of the active broadcasts, indexed by operation ID.
+	broadcast_ids: Arc<RwLock<HashMap<String, AbortHandle>>>,
+}
+
+impl<Pool, Client> TransactionBroadcast<Pool, Client> {
+	/// Creates a new [`TransactionBroadcast`].
+	pub fn new(client: Arc<Client>, pool: Arc<Pool>, executor: SubscriptionTaskExecutor) -> Self {
+		TransactionBroadcast { client, pool, executor, broadcast_ids: Default::default() }
+	}
+}
+
+/// Generate a random operation ID.
+///
+/// The IDs are unguessable, such that a client can't stop the broadcasts of other clients.
+fn generate_operation_id() -> String {
+	rand::thread_rng()
+		.sample_iter(Alphanumeric)
+		.take(OPERATION_ID_LEN)
+		.map(char::from)
+		.collect()
+}
+
+#[async_trait]
+impl<Pool, Client> TransactionBroadcastApiServer for TransactionBroadcast<Pool, Client>
+where
+	Pool: TransactionPool + Sync + Send + 'static,
+	Client: HeaderBackend<Pool::Block> + BlockchainEvents<Pool::Block> + Send + Sync + 'static,
+{
+	fn broadcast(&self, bytes: Bytes) -> RpcResult<Option<String>> {
+		let decoded_extrinsic = TransactionFor::<Pool>::decode(&mut &bytes[..])
+			.map_err(|e| ErrorBroadcast::BadFormat(e.to_string()))?;
+
+		let (abort_handle, abort_registration) = AbortHandle::new_pair();
+
+		// Register the operation before spawning, such that a quickly finalized broadcast
+		// cannot remove its ID before it was inserted.
+		let operation_id = {
+			let mut broadcast_ids = self.broadcast_ids.write();
+			if broadcast_ids.len() >= MAX_CONCURRENT_BROADCASTS {
+				debug!(target: LOG_TARGET, "[broadcast] Too many broadcasts, rejecting extrinsic");
+				return Ok(None)
+			}
+
+			let operation_id = loop {
+				let operation_id = generate_operation_id();
+				if !broadcast_ids.contains_key(&operation_id) {
+					break operation_id
+				}
+			};
+			broadcast_ids.insert(operation_id.clone(), abort_handle);
+			operation_id
+		};
+
+		let broadcast_fut = broadcast_until_finalized(
+			self.client.clone(),
+			self.pool.clone(),
+			decoded_extrinsic,
+			operation_id.clone(),
+		);
+		let broadcast_ids = self.broadcast_ids.clone();
+		let drop_id = operation_id.clone();
+		let fut = async move {
+			// The result is irrelevant: the broadcast either ended on its own or was stopped.
+			let _ = Abortable::new(broadcast_fut, abort_registration).await;
+			broadcast_ids.write().remove(&drop_id);
+		};
+
+		self.executor
+			.spawn("substrate-rpc-transaction-broadcast", Some("rpc"), fut.boxed());
+
+		Ok(Some(operation_id))
+	}
+
+	fn stop_broadcast(&self, operation_id: String) -> RpcResult<()> {
+		let Some(abort_handle) = self.broadcast_ids.write().remove(&operation_id) else {
+			return Err(ErrorBroadcast::InvalidOperationId(operation_id).into())
+		};
+
+		abort_handle.abort();
+		Ok(())
+	}
+}
+
+/// Keep the extrinsic in the transaction pool until it is included in a finalized block.
+///
+/// The extrinsic is submitted again on top of the next best block every time the pool
+/// rejects it or reports that it is no longer tracked, unless the extrinsic is invalid.
+async fn broadcast_until_finalized<Pool, Client>(
+	client: Arc<Client>,
+	pool: Arc<Pool>,
+	extrinsic: TransactionFor<Pool>,
+	operation_id: String,
+) where
+	Pool: TransactionPool + 'static,
+	Client: HeaderBackend<Pool::Block> + BlockchainEvents<Pool::Block> + 'static,
+{
+	loop {
+		let best_block_hash = client.info().best_hash;
+		let submit = pool
+			.submit_and_watch(
+				&generic::BlockId::hash(best_block_hash),
+				TX_SOURCE,
+				extrinsic.clone(),
+			)
+			.await;
+
+		match submit {
+			Ok(mut stream) =>
+				while let Some(event) = stream.next().await {
+					match event {
+						TransactionStatus::Finalized(_) => {
+							debug!(
+								target: LOG_TARGET,
+								"[broadcast][id={:?}] Extrinsic finalized",
+								operation_id,
+							);
+							return
+						},
+						// The extrinsic was included in a block, but the pool no longer tracks
+						// its finality. Submitting it again would be rejected as stale.
+						TransactionStatus::FinalityTimeout(_) => {
+							debug!(
+								target: LOG_TARGET,
+								"[broadcast][id={:?}] Extrinsic finality timeout",
+								operation_id,
+							);
+							return
+						},
+						// The sender cancelled the extrinsic, stop broadcasting it.
+						TransactionStatus::Cancelled => {
+							debug!(
+								target: LOG_TARGET,
+								"[broadcast][id={:?}] Extrinsic cancelled",
+								operation_id,
+							);
+							return
+						},
+						// Submitting an invalid extrinsic again would only be rejected.
+						TransactionStatus::Invalid => {
+							debug!(
+								target: LOG_TARGET,
+								"[broadcast][id={:?}] Extrinsic invalid",
+								operation_id,
+							);
+							return
+						},
+						// The extrinsic left the pool, submit it again.
+						TransactionStatus::Usurped(_) | TransactionStatus::Dropped => break,
+						_ => {},
+					}
+				},
+			Err(err) => {
+				debug!(
+					target: LOG_TARGET,
+					"[broadcast][id={:?}] Extrinsic rejected by the pool: {:?}",
+					operation_id,
+					err,
+				);
+				if let Ok(PoolError::InvalidTransaction(_)) = err.into_pool_error() {
+					return
+				}
+			},
+		}
+
+		// Wait for the next best block before submitting the extrinsic again.
+		let mut best_blocks = client
+			.import_notification_stream()
+			.filter(|notification| future::ready(notification.is_new_best));
+		if best_blocks.next().await.is_none() {
+			return
+		}
+	}
+}
@@ -41,7 +41,11 @@ use sp_api::ProvideRuntimeApi;
 use sp_blockchain::HeaderBackend;
 use sp_core::Bytes;
 use sp_keystore::KeystorePtr;
-use sp_runtime::{generic, traits::Block as BlockT};
+use sp_runtime::{
+	generic,
+	traits::{Block as BlockT, IdentifyAccount, UniqueSaturatedInto, Verify},
+	MultiSignature, MultiSigner,
+};
 use sp_session::SessionKeys;
 
 use self::error::{Error, Result};
@@ -88,7 +92,7 @@ where
 	P: TransactionPool + Sync + Send + 'static,
 	Client: HeaderBackend<P::Block> + ProvideRuntimeApi<P::Block> + Send + Sync + 'static,
 	Client::Api: SessionKeys<P::Block>,
-	P::Hash: Unpin,
+	P::Hash: Unpin + Encode,
 	<P::Block as BlockT>::Hash: Unpin,
 {
 	async fn submit_extrinsic(&self, ext: Bytes) -> RpcResult<TxHash<P>> {
@@ -178,6 +182,36 @@ where
 			.collect())
 	}
 
+	fn cancel_extrinsic(
+		&self,
+		hash: TxHash<P>,
+		valid_until: u64,
+		signer: Bytes,
+		signature: Bytes,
+	) -> RpcResult<Vec<TxHash<P>>> {
+		let info = self.client.info();
+		let best_number: u64 = info.best_number.unique_saturated_into();
+		if best_number > valid_until {
+			return Err(Error::CancellationExpired.into())
+		}
+
+		let signer = MultiSigner::decode(&mut &signer[..]).map_err(Error::from)?;
+		let signature = MultiSignature::decode(&mut &signature[..]).map_err(Error::from)?;
+		let account = signer.into_account();
+		let payload = cancellation_payload(&info.genesis_hash, valid_until, &hash);
+		if !signature.verify(&payload[..], &account) {
+			return Err(Error::BadCancellationSignature.into())
+		}
+
+		let xt = self.pool.pending_transaction(&hash).ok_or(Error::UnknownExtrinsic)?;
+		let sender = account.encode();
+		if !xt.provides().iter().any(|tag| tag.starts_with(&sender)) {
+			return Err(Error::NotExtrinsicSender.into())
+		}
+
+		Ok(self.pool.cancel(&hash).into_iter().map(|tx| tx.hash().clone()).collect())
+	}
+
 	fn watch_extrinsic(&self, mut sink: SubscriptionSink, xt: Bytes) -> SubscriptionResult {
 		let best_block_hash = self.client.info().best_hash;
 		let dxt = match TransactionFor::<P>::decode(&mut &xt[..]).map_err(|e| Error::from(e)) {
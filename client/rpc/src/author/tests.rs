@@ -26,8 +26,10 @@ use jsonrpsee::{
 	types::{error::CallError, EmptyServerParams as EmptyParams},
 	RpcModule,
 };
+use sc_block_builder::BlockBuilderProvider;
 use sc_transaction_pool::{BasicPool, FullChainApi};
 use sc_transaction_pool_api::TransactionStatus;
+use sp_consensus::BlockOrigin;
 use sp_core::{
 	blake2_256,
 	bytes::to_hex,
@@ -37,12 +39,13 @@ use sp_core::{
 	H256,
 };
 use sp_keystore::{testing::MemoryKeystore, Keystore};
-use sp_runtime::Perbill;
+use sp_runtime::{MultiSignature, MultiSigner, Perbill};
 use std::sync::Arc;
 use substrate_test_runtime_client::{
 	self,
 	runtime::{Block, Extrinsic, ExtrinsicBuilder, SessionKeys, Transfer},
-	AccountKeyring, Backend, Client, DefaultTestClientBuilderExt, TestClientBuilderExt,
+	AccountKeyring, Backend, Client, ClientBlockImportExt, DefaultTestClientBuilderExt,
+	TestClientBuilderExt,
 };
 
 fn uxt(sender: AccountKeyring, nonce: u64) -> Extrinsic {
@@ -216,6 +219,71 @@ async fn author_should_remove_extrinsics() {
 	assert_eq!(removed, vec![xt1_hash, xt2_hash, xt3_hash]);
 }
 
+#[tokio::test]
+async fn author_should_cancel_extrinsics_of_the_signer() {
+	const METHOD: &'static str = "author_cancelExtrinsic";
+	let setup = TestSetup::default();
+	let api = setup.author().into_rpc();
+
+	// Submit two extrinsics, cancelling the first one will remove the second as well.
+	let xt1 = to_hex(&uxt(AccountKeyring::Alice, 0).encode(), true);
+	let xt1_hash: H256 = api.call("author_submitExtrinsic", [xt1]).await.unwrap();
+	let xt2 = to_hex(&uxt(AccountKeyring::Alice, 1).encode(), true);
+	let xt2_hash: H256 = api.call("author_submitExtrinsic", [xt2]).await.unwrap();
+	assert_eq!(setup.pool.status().ready, 2);
+
+	let genesis_hash = setup.client.info().genesis_hash;
+	let signed = |keyring: AccountKeyring, genesis_hash: H256, valid_until: u64| {
+		let signer = MultiSigner::from(keyring.public());
+		let payload = cancellation_payload(&genesis_hash, valid_until, &xt1_hash);
+		let signature = MultiSignature::from(keyring.sign(&payload));
+		(xt1_hash, valid_until, Bytes::from(signer.encode()), Bytes::from(signature.encode()))
+	};
+	let params = |keyring: AccountKeyring| signed(keyring, genesis_hash, 1);
+
+	// Bob is not the sender.
+	assert_matches!(
+		api.call::<_, Vec<H256>>(METHOD, params(AccountKeyring::Bob)).await,
+		Err(RpcError::Call(CallError::Custom(err))) if err.code() == 1005
+	);
+
+	// The signature must match the signer.
+	let (hash, valid_until, signer, _) = params(AccountKeyring::Alice);
+	let (_, _, _, signature) = params(AccountKeyring::Bob);
+	assert_matches!(
+		api.call::<_, Vec<H256>>(METHOD, (hash, valid_until, signer, signature)).await,
+		Err(RpcError::Call(CallError::Custom(err))) if err.code() == 1003
+	);
+
+	// The signature must be for this chain and the given validity bound.
+	assert_matches!(
+		api.call::<_, Vec<H256>>(METHOD, signed(AccountKeyring::Alice, H256::random(), 1)).await,
+		Err(RpcError::Call(CallError::Custom(err))) if err.code() == 1003
+	);
+	let (hash, _, signer, signature) = params(AccountKeyring::Alice);
+	assert_matches!(
+		api.call::<_, Vec<H256>>(METHOD, (hash, 2, signer, signature)).await,
+		Err(RpcError::Call(CallError::Custom(err))) if err.code() == 1003
+	);
+
+	// The cancellation expires once the best block number exceeds the validity bound.
+	let mut client = setup.client.clone();
+	for _ in 0..2 {
+		let block = client.new_block(Default::default()).unwrap().build().unwrap().block;
+		client.import(BlockOrigin::Own, block).await.unwrap();
+	}
+	assert_matches!(
+		api.call::<_, Vec<H256>>(METHOD, params(AccountKeyring::Alice)).await,
+		Err(RpcError::Call(CallError::Custom(err))) if err.code() == 1006
+	);
+	assert_eq!(setup.pool.status().ready, 2);
+
+	let params = |keyring: AccountKeyring| signed(keyring, genesis_hash, 2);
+	let removed: Vec<H256> = api.call(METHOD, params(AccountKeyring::Alice)).await.unwrap();
+	assert_eq!(removed, vec![xt1_hash, xt2_hash]);
+	assert_eq!(setup.pool.status().ready, 0);
+}
+
 #[tokio::test]
 async fn author_should_insert_key() {
 	let setup = TestSetup::default();
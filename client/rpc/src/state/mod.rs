@@ -33,7 +33,10 @@ use jsonrpsee::{
 	types::SubscriptionResult,
 };
 
-use sc_rpc_api::{state::ReadProof, DenyUnsafe};
+use sc_rpc_api::{
+	state::{RangeProof, ReadProof},
+	DenyUnsafe,
+};
 use sp_core::{
 	storage::{PrefixedStorageKey, StorageChangeSet, StorageData, StorageKey},
 	Bytes,
@@ -53,6 +56,9 @@ use sp_blockchain::{HeaderBackend, HeaderMetadata};
 
 const STORAGE_KEYS_PAGED_MAX_COUNT: u32 = 1000;
 
+/// Maximum size limit of a range proof, in bytes.
+const RANGE_PROOF_MAX_SIZE: u32 = 4 * 1024 * 1024;
+
 /// State backend API.
 #[async_trait]
 pub trait StateBackend<Block: BlockT, Client>: Send + Sync + 'static
@@ -148,6 +154,15 @@ where
 		keys: Vec<StorageKey>,
 	) -> Result<ReadProof<Block::Hash>, Error>;
 
+	/// Returns a size-bounded proof of the storage entries with prefix, starting at `start_key`.
+	fn range_proof(
+		&self,
+		block: Option<Block::Hash>,
+		prefix: Option<StorageKey>,
+		start_key: Option<StorageKey>,
+		size_limit: u32,
+	) -> Result<RangeProof<Block::Hash>, Error>;
+
 	/// Trace storage changes for block
 	fn trace_block(
 		&self,
@@ -307,6 +322,19 @@ where
 		self.backend.read_proof(block, keys).map_err(Into::into)
 	}
 
+	fn range_proof(
+		&self,
+		prefix: Option<StorageKey>,
+		start_key: Option<StorageKey>,
+		size_limit: u32,
+		block: Option<Block::Hash>,
+	) -> RpcResult<RangeProof<Block::Hash>> {
+		check_range_proof_size(size_limit)?;
+		self.backend
+			.range_proof(block, prefix, start_key, size_limit)
+			.map_err(Into::into)
+	}
+
 	/// Re-execute the given block with the tracing targets given in `targets`
 	/// and capture all state changes.
 	///
@@ -361,6 +389,17 @@ where
 		keys: Vec<StorageKey>,
 	) -> Result<ReadProof<Block::Hash>, Error>;
 
+	/// Returns a size-bounded proof of the child storage entries with prefix, starting at
+	/// `start_key`.
+	fn range_proof(
+		&self,
+		block: Option<Block::Hash>,
+		storage_key: PrefixedStorageKey,
+		prefix: Option<StorageKey>,
+		start_key: Option<StorageKey>,
+		size_limit: u32,
+	) -> Result<RangeProof<Block::Hash>, Error>;
+
 	/// Returns the keys with prefix from a child storage,
 	/// leave prefix empty to get all the keys.
 	fn storage_keys(
@@ -493,6 +532,27 @@ where
 			.read_child_proof(block, child_storage_key, keys)
 			.map_err(Into::into)
 	}
+
+	fn child_range_proof(
+		&self,
+		child_storage_key: PrefixedStorageKey,
+		prefix: Option<StorageKey>,
+		start_key: Option<StorageKey>,
+		size_limit: u32,
+		block: Option<Block::Hash>,
+	) -> RpcResult<RangeProof<Block::Hash>> {
+		check_range_proof_size(size_limit)?;
+		self.backend
+			.range_proof(block, child_storage_key, prefix, start_key, size_limit)
+			.map_err(Into::into)
+	}
+}
+
+fn check_range_proof_size(size_limit: u32) -> Result<(), Error> {
+	if size_limit > RANGE_PROOF_MAX_SIZE {
+		return Err(Error::InvalidProofSize { value: size_limit, max: RANGE_PROOF_MAX_SIZE })
+	}
+	Ok(())
 }
 
 fn client_err(err: sp_blockchain::Error) -> Error {
@@ -36,7 +36,7 @@ use sc_client_api::{
 	Backend, BlockBackend, BlockchainEvents, CallExecutor, ExecutorProvider, ProofProvider,
 	StorageProvider,
 };
-use sc_rpc_api::state::ReadProof;
+use sc_rpc_api::state::{RangeProof, ReadProof};
 use sp_api::{CallApiAt, Metadata, ProvideRuntimeApi};
 use sp_blockchain::{
 	CachedHeaderMetadata, Error as ClientError, HeaderBackend, HeaderMetadata,
@@ -377,6 +377,33 @@ where
 			.map_err(client_err)
 	}
 
+	fn range_proof(
+		&self,
+		block: Option<Block::Hash>,
+		prefix: Option<StorageKey>,
+		start_key: Option<StorageKey>,
+		size_limit: u32,
+	) -> std::result::Result<RangeProof<Block::Hash>, Error> {
+		self.block_or_best(block)
+			.and_then(|block| {
+				self.client
+					.read_range_proof(
+						block,
+						None,
+						prefix.as_ref().map(|prefix| prefix.0.as_slice()),
+						start_key.as_ref().map(|key| key.0.as_slice()),
+						size_limit as usize,
+					)
+					.map(|(proof, last_key, continuation)| RangeProof {
+						at: block,
+						proof: proof.encoded_nodes.into_iter().map(Into::into).collect(),
+						last_key: last_key.map(StorageKey),
+						continuation: continuation.map(StorageKey),
+					})
+			})
+			.map_err(client_err)
+	}
+
 	fn subscribe_runtime_version(&self, mut sink: SubscriptionSink) {
 		let client = self.client.clone();
 
@@ -525,6 +552,39 @@ where
 			.map_err(client_err)
 	}
 
+	fn range_proof(
+		&self,
+		block: Option<Block::Hash>,
+		storage_key: PrefixedStorageKey,
+		prefix: Option<StorageKey>,
+		start_key: Option<StorageKey>,
+		size_limit: u32,
+	) -> std::result::Result<RangeProof<Block::Hash>, Error> {
+		self.block_or_best(block)
+			.and_then(|block| {
+				let child_info = match ChildType::from_prefixed_key(&storage_key) {
+					Some((ChildType::ParentKeyId, storage_key)) =>
+						ChildInfo::new_default(storage_key),
+					None => return Err(sp_blockchain::Error::InvalidChildStorageKey),
+				};
+				self.client
+					.read_range_proof(
+						block,
+						Some(&child_info),
+						prefix.as_ref().map(|prefix| prefix.0.as_slice()),
+						start_key.as_ref().map(|key| key.0.as_slice()),
+						size_limit as usize,
+					)
+					.map(|(proof, last_key, continuation)| RangeProof {
+						at: block,
+						proof: proof.encoded_nodes.into_iter().map(Into::into).collect(),
+						last_key: last_key.map(StorageKey),
+						continuation: continuation.map(StorageKey),
+					})
+			})
+			.map_err(client_err)
+	}
+
 	fn storage_keys(
 		&self,
 		block: Option<Block::Hash>,
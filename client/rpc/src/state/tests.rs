@@ -29,6 +29,9 @@ use sc_block_builder::BlockBuilderProvider;
 use sc_rpc_api::DenyUnsafe;
 use sp_consensus::BlockOrigin;
 use sp_core::{hash::H256, storage::ChildInfo};
+use sp_runtime::traits::BlakeTwo256;
+use sp_state_machine::read_range_proof_check_with_continuation;
+use sp_trie::CompactProof;
 use std::sync::Arc;
 use substrate_test_runtime_client::{
 	prelude::*,
@@ -194,6 +197,86 @@ async fn should_return_child_storage_entries() {
 	assert_matches!(child.storage_size(child_key.clone(), keys[0].clone(), None), Ok(Some(1)));
 }
 
+fn check_range_proof(
+	root: H256,
+	range: RangeProof<H256>,
+	child_info: Option<&ChildInfo>,
+	prefix: Option<&[u8]>,
+	start_at: Option<&[u8]>,
+) -> Vec<(Vec<u8>, Vec<u8>)> {
+	let proof =
+		CompactProof { encoded_nodes: range.proof.into_iter().map(|node| node.0).collect() };
+	let (proof, _) = proof.to_storage_proof::<BlakeTwo256>(Some(&root)).unwrap();
+	read_range_proof_check_with_continuation::<BlakeTwo256>(
+		root,
+		proof,
+		child_info,
+		prefix,
+		start_at,
+		range.last_key.as_ref().map(|key| key.0.as_slice()),
+		range.continuation.as_ref().map(|key| key.0.as_slice()),
+	)
+	.unwrap()
+}
+
+#[tokio::test]
+async fn should_return_range_proof() {
+	let child_info = ChildInfo::new_default(STORAGE_KEY);
+	let client = TestClientBuilder::new()
+		.add_extra_storage(b":map:acc1".to_vec(), vec![1])
+		.add_extra_storage(b":map:acc2".to_vec(), vec![2])
+		.add_extra_storage(b":map:acc3".to_vec(), vec![3])
+		.add_extra_child_storage(&child_info, b"key1".to_vec(), vec![1])
+		.add_extra_child_storage(&child_info, b"key2".to_vec(), vec![2])
+		.build();
+	let genesis_hash = client.genesis_hash();
+	let root = client.header(genesis_hash).unwrap().unwrap().state_root;
+	let (client, child) = new_full(Arc::new(client), test_executor(), DenyUnsafe::No);
+	let prefix = StorageKey(b":map:".to_vec());
+
+	// The smallest range holds a single entry.
+	let range = client.range_proof(Some(prefix.clone()), None, 1, Some(genesis_hash)).unwrap();
+	assert_eq!(range.at, genesis_hash);
+	assert_eq!(range.last_key, Some(StorageKey(b":map:acc1".to_vec())));
+	assert_eq!(range.continuation, Some(StorageKey(b":map:acc2".to_vec())));
+	assert_eq!(
+		check_range_proof(root, range, None, Some(b":map:"), None),
+		vec![(b":map:acc1".to_vec(), vec![1])],
+	);
+
+	// The next range starts at the continuation key.
+	let start_key = StorageKey(b":map:acc2".to_vec());
+	let range = client
+		.range_proof(
+			Some(prefix.clone()),
+			Some(start_key.clone()),
+			RANGE_PROOF_MAX_SIZE,
+			Some(genesis_hash),
+		)
+		.unwrap();
+	assert_eq!(range.last_key, Some(StorageKey(b":map:acc3".to_vec())));
+	assert_eq!(range.continuation, None);
+	assert_eq!(
+		check_range_proof(root, range, None, Some(b":map:"), Some(&start_key.0)),
+		vec![(b":map:acc2".to_vec(), vec![2]), (b":map:acc3".to_vec(), vec![3])],
+	);
+
+	// Child tries are proven from the state root.
+	let range = child
+		.child_range_proof(prefixed_storage_key(), None, None, RANGE_PROOF_MAX_SIZE, None)
+		.unwrap();
+	assert_eq!(range.continuation, None);
+	assert_eq!(
+		check_range_proof(root, range, Some(&child_info), None, None),
+		vec![(b"key1".to_vec(), vec![1]), (b"key2".to_vec(), vec![2])],
+	);
+
+	assert_matches!(
+		client.range_proof(Some(prefix), None, RANGE_PROOF_MAX_SIZE + 1, None),
+		Err(RpcError::Call(RpcCallError::Custom(err))) if err.message().contains("proof size limit exceeds maximum value")
+	);
+}
+
 #[tokio::test]
 async fn should_call_contract() {
 	let client = Arc::new(substrate_test_runtime_client::new());
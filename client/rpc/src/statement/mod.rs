@@ -18,19 +18,26 @@
 
 //! Substrate statement store API.
 
+use crate::SubscriptionTaskExecutor;
 use codec::{Decode, Encode};
-use jsonrpsee::core::{async_trait, RpcResult};
+use futures::{FutureExt, StreamExt};
+use jsonrpsee::{
+	core::{async_trait, Error as JsonRpseeError, RpcResult},
+	types::SubscriptionResult,
+	SubscriptionSink,
+};
 /// Re-export the API for backward compatibility.
-pub use sc_rpc_api::statement::{error::Error, StatementApiServer};
+pub use sc_rpc_api::statement::{error::Error, StatementApiServer, StatementNotification};
 use sc_rpc_api::DenyUnsafe;
 use sp_core::Bytes;
-use sp_statement_store::{StatementSource, SubmitResult};
+use sp_statement_store::{SequencedStatement, StatementFilter, StatementSource, SubmitResult};
 use std::sync::Arc;
 
 /// Statement store API
 pub struct StatementStore {
 	store: Arc<dyn sp_statement_store::StatementStore>,
 	deny_unsafe: DenyUnsafe,
+	executor: SubscriptionTaskExecutor,
 }
 
 impl StatementStore {
@@ -38,8 +45,9 @@ impl StatementStore {
 	pub fn new(
 		store: Arc<dyn sp_statement_store::StatementStore>,
 		deny_unsafe: DenyUnsafe,
+		executor: SubscriptionTaskExecutor,
 	) -> Self {
-		StatementStore { store, deny_unsafe }
+		StatementStore { store, deny_unsafe, executor }
 	}
 }
 
@@ -105,4 +113,31 @@ impl StatementApiServer for StatementStore {
 	fn remove(&self, hash: [u8; 32]) -> RpcResult<()> {
 		Ok(self.store.remove(&hash).map_err(|e| Error::StatementStore(e.to_string()))?)
 	}
+
+	fn subscribe_statements(
+		&self,
+		mut sink: SubscriptionSink,
+		match_all_topics: Vec<[u8; 32]>,
+		dest: Option<[u8; 32]>,
+		from: Option<u64>,
+	) -> SubscriptionResult {
+		let filter = StatementFilter { match_all_topics, dest };
+		let stream = match self.store.subscribe_statements(filter, from) {
+			Ok(stream) => stream,
+			Err(e) => {
+				let _ = sink.reject(JsonRpseeError::from(Error::StatementStore(e.to_string())));
+				return Ok(())
+			},
+		};
+
+		let stream = stream.map(|SequencedStatement { sequence, statement }| {
+			StatementNotification { sequence, statement: statement.encode().into() }
+		});
+		let fut = async move {
+			sink.pipe_from_stream(stream).await;
+		};
+
+		self.executor.spawn("substrate-rpc-subscription", Some("rpc"), fut.boxed());
+		Ok(())
+	}
 }
@@ -19,7 +19,7 @@
 //! Service configuration.
 
 pub use sc_client_api::execution_extensions::{ExecutionStrategies, ExecutionStrategy};
-pub use sc_client_db::{BlocksPruning, Database, DatabaseSource, PruningMode};
+pub use sc_client_db::{BlocksPruning, CheckpointPolicy, Database, DatabaseSource, PruningMode};
 pub use sc_executor::{WasmExecutionMethod, WasmtimeInstantiationStrategy};
 pub use sc_network::{
 	config::{
@@ -34,8 +34,11 @@ pub use sc_network::{
 
 use prometheus_endpoint::Registry;
 use sc_chain_spec::ChainSpec;
+pub use sc_rpc_spec_v2::chain_head::ChainHeadConfig;
 pub use sc_telemetry::TelemetryEndpoints;
-pub use sc_transaction_pool::Options as TransactionPoolOptions;
+pub use sc_transaction_pool::{
+	JournalOptions as TransactionPoolJournalOptions, Options as TransactionPoolOptions,
+};
 use sp_core::crypto::SecretString;
 use std::{
 	io, iter,
@@ -67,12 +70,18 @@ pub struct Configuration {
 	///
 	/// If `None` is given the cache is disabled.
 	pub trie_cache_maximum_size: Option<usize>,
+	/// Store the trie cache at shutdown and load it at the next start.
+	pub persist_trie_cache: bool,
 	/// State pruning settings.
 	pub state_pruning: Option<PruningMode>,
 	/// Number of blocks to keep in the db.
 	///
 	/// NOTE: only finalized blocks are subject for removal!
 	pub blocks_pruning: BlocksPruning,
+	/// Justifications pruning settings. Follows `blocks_pruning` if `None`.
+	pub justifications_pruning: Option<BlocksPruning>,
+	/// Indexed transactions pruning settings. Follows `blocks_pruning` if `None`.
+	pub transactions_pruning: Option<BlocksPruning>,
 	/// Chain configuration.
 	pub chain_spec: Box<dyn ChainSpec>,
 	/// Wasm execution method.
@@ -101,6 +110,8 @@ pub struct Configuration {
 	pub rpc_id_provider: Option<Box<dyn crate::RpcSubscriptionIdProvider>>,
 	/// Maximum allowed subscriptions per rpc connection
 	pub rpc_max_subs_per_conn: u32,
+	/// Limits of the `chainHead` RPC methods.
+	pub rpc_chain_head: ChainHeadConfig,
 	/// Prometheus endpoint configuration. `None` if disabled.
 	pub prometheus_config: Option<PrometheusConfig>,
 	/// Telemetry service URL. `None` if disabled.
@@ -161,6 +172,11 @@ pub enum KeystoreConfig {
 	},
 	/// In-memory keystore. Recommended for in-browser nodes.
 	InMemory,
+	/// Keys are held by a remote signer. See `sc_keystore::RemoteKeystore` for the supported URIs.
+	Remote {
+		/// The URI of the remote signer.
+		uri: String,
+	},
 }
 
 impl KeystoreConfig {
@@ -168,7 +184,7 @@ impl KeystoreConfig {
 	pub fn path(&self) -> Option<&Path> {
 		match self {
 			Self::Path { path, .. } => Some(path),
-			Self::InMemory => None,
+			Self::InMemory | Self::Remote { .. } => None,
 		}
 	}
 }
@@ -239,9 +255,12 @@ impl Configuration {
 	pub fn db_config(&self) -> sc_client_db::DatabaseSettings {
 		sc_client_db::DatabaseSettings {
 			trie_cache_maximum_size: self.trie_cache_maximum_size,
+			trie_cache_snapshot: self.persist_trie_cache.then(|| self.data_path.join("trie_cache")),
 			state_pruning: self.state_pruning.clone(),
 			source: self.database.clone(),
 			blocks_pruning: self.blocks_pruning,
+			justifications_pruning: self.justifications_pruning,
+			transactions_pruning: self.transactions_pruning,
 		}
 	}
 }
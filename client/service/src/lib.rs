@@ -70,7 +70,8 @@ pub use sc_chain_spec::{
 };
 
 pub use config::{
-	BasePath, BlocksPruning, Configuration, DatabaseSource, PruningMode, Role, RpcMethods, TaskType,
+	BasePath, BlocksPruning, CheckpointPolicy, Configuration, DatabaseSource, PruningMode, Role,
+	RpcMethods, TaskType,
 };
 pub use sc_chain_spec::{
 	ChainSpec, ChainType, Extension as ChainSpecExtension, GenericChainSpec, NoExtension,
@@ -318,6 +319,36 @@ async fn build_system_rpc_future<
 					break
 				}
 			},
+			sc_rpc::system::Request::NetworkBannedPeers(sender) => {
+				use sc_rpc::system::BannedPeer;
+
+				let banned_peers = network_service.banned_peers().await;
+				if let Ok(banned_peers) = banned_peers {
+					let banned_peers = banned_peers
+						.into_iter()
+						.map(|peer| BannedPeer {
+							peer_id: peer.peer_id.to_base58(),
+							reputation: peer.reputation,
+							reason: peer.reason,
+							banned_at: peer.banned_at,
+						})
+						.collect();
+					let _ = sender.send(banned_peers);
+				} else {
+					break
+				}
+			},
+			sc_rpc::system::Request::NetworkUnbanPeer(peer_id, sender) => {
+				let _ = match peer_id.parse::<PeerId>() {
+					Ok(peer_id) => {
+						network_service.unban_peer(peer_id);
+						sender.send(Ok(()))
+					},
+					Err(e) => sender.send(Err(sc_rpc::system::error::Error::MalformattedPeerArg(
+						e.to_string(),
+					))),
+				};
+			},
 			sc_rpc::system::Request::NodeRoles(sender) => {
 				use sc_rpc::system::NodeRole;
 
@@ -329,15 +360,20 @@ async fn build_system_rpc_future<
 				let _ = sender.send(vec![node_role]);
 			},
 			sc_rpc::system::Request::SyncState(sender) => {
-				use sc_rpc::system::SyncState;
+				use sc_rpc::system::{GapSyncState, SyncState};
 
-				match sync_service.best_seen_block().await {
-					Ok(best_seen_block) => {
+				match sync_service.status().await {
+					Ok(status) => {
 						let best_number = client.info().best_number;
 						let _ = sender.send(SyncState {
 							starting_block,
 							current_block: best_number,
-							highest_block: best_seen_block.unwrap_or(best_number),
+							highest_block: status.best_seen_block.unwrap_or(best_number),
+							gap_sync: status.gap_sync.map(|gap_sync| GapSyncState {
+								starting_block: gap_sync.start,
+								current_block: gap_sync.best_queued,
+								target_block: gap_sync.target,
+							}),
 						});
 					},
 					Err(_) => log::error!("`SyncingEngine` shut down"),
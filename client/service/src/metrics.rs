@@ -43,6 +43,8 @@ struct PrometheusMetrics {
 	// I/O
 	database_cache: Gauge<U64>,
 	state_cache: Gauge<U64>,
+	state_cache_hit_rate: GaugeVec<U64>,
+	state_compaction: GaugeVec<U64>,
 }
 
 impl PrometheusMetrics {
@@ -116,6 +118,26 @@ impl PrometheusMetrics {
 				Gauge::new("substrate_state_cache_bytes", "State cache size in bytes")?,
 				registry,
 			)?,
+			state_cache_hit_rate: register(
+				GaugeVec::new(
+					Opts::new(
+						"substrate_state_cache_hit_rate",
+						"Percentage of the state cache lookups that were hits, since the start",
+					),
+					&["cache"],
+				)?,
+				registry,
+			)?,
+			state_compaction: register(
+				GaugeVec::new(
+					Opts::new(
+						"substrate_state_compaction",
+						"Progress of the state compaction after a state pruning mode change",
+					),
+					&["progress"],
+				)?,
+				registry,
+			)?,
 		})
 	}
 }
@@ -252,6 +274,30 @@ impl MetricsService {
 			if let Some(info) = info.usage.as_ref() {
 				metrics.database_cache.set(info.memory.database_cache.as_bytes() as u64);
 				metrics.state_cache.set(info.memory.state_cache.as_bytes() as u64);
+
+				if let Some(hits) = info.state_cache_hits.as_ref() {
+					let hit_rate = |hits: u64, attempts: u64| hits * 100 / attempts.max(1);
+					metrics
+						.state_cache_hit_rate
+						.with_label_values(&["node"])
+						.set(hit_rate(hits.node_hits, hits.node_fetch_attempts));
+					metrics
+						.state_cache_hit_rate
+						.with_label_values(&["value"])
+						.set(hit_rate(hits.value_hits, hits.value_fetch_attempts));
+				}
+
+				if let Some(compaction) = info.state_compaction.as_ref() {
+					let progress = &metrics.state_compaction;
+					progress.with_label_values(&["marked_nodes"]).set(compaction.marked_nodes);
+					progress.with_label_values(&["removed_nodes"]).set(compaction.removed_nodes);
+					progress
+						.with_label_values(&["sweep_passes_done"])
+						.set(compaction.sweep_passes_done as u64);
+					progress
+						.with_label_values(&["sweep_passes"])
+						.set(compaction.sweep_passes as u64);
+				}
 			}
 		}
 
@@ -0,0 +1,286 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Retained state checkpoints.
+//!
+//! In constrained pruning mode the state of a canonical block is discarded once the block leaves
+//! the pruning window. A checkpoint policy selects some canonical blocks which state is retained
+//! instead, so that it can still be queried or exported later.
+//!
+//! A node that is deleted by a block is a part of the state of its parent. When a block is pruned,
+//! the deletion of a node is skipped if there is a checkpoint between the block that inserted the
+//! node and the pruned block. Skipping a deletion keeps a reference to the node forever, which is
+//! only sound when the database counts references.
+//!
+//! The nodes inserted by each canonical block after the first checkpoint are recorded in the meta
+//! column, until the next checkpoint is pruned. The nodes of unknown origin were inserted before
+//! the last pruned checkpoint and are part of it. The records are loaded on restart into an
+//! in-memory index of the block that inserted each node.
+//!
+//! Disabling checkpoints forgets the retained ones, but the nodes which deletion was skipped stay
+//! in the database.
+
+use crate::{pruning::LAST_PRUNED, to_meta_key, CommitSet, Error, Hash, MetaDb, LOG_TARGET};
+use codec::{Decode, Encode};
+use log::{debug, info};
+use std::{
+	collections::HashMap,
+	time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+const CHECKPOINTS: &[u8] = b"checkpoints";
+const CHECKPOINT_POLICY: &[u8] = b"checkpoint_policy";
+const CHECKPOINT_BIRTHS: &[u8] = b"checkpoint_births";
+
+/// Selects canonical blocks which state is retained after they leave the pruning window.
+///
+/// A block is selected if any of the conditions holds. A policy without conditions disables
+/// checkpoints and forgets the ones retained before.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct CheckpointPolicy {
+	/// Retain the state of every block which number is a multiple of this value.
+	pub every_blocks: Option<u32>,
+	/// Retain the state of the first block canonicalized at least this long after the previous
+	/// checkpoint. Measured in wall-clock time at canonicalization.
+	pub period: Option<Duration>,
+}
+
+impl CheckpointPolicy {
+	/// Create a policy that retains the state of every `n`-th block.
+	pub fn every_blocks(n: u32) -> Self {
+		Self { every_blocks: Some(n), period: None }
+	}
+
+	/// Create a policy that retains the state of one block per `period`.
+	pub fn period(period: Duration) -> Self {
+		Self { every_blocks: None, period: Some(period) }
+	}
+
+	/// Does this policy select any blocks?
+	pub fn is_enabled(&self) -> bool {
+		self.every_blocks.map_or(false, |n| n > 0) || self.period.is_some()
+	}
+
+	fn selects(&self, number: u64, timestamp: u64, last_timestamp: Option<u64>) -> bool {
+		let by_number = self.every_blocks.map_or(false, |n| n > 0 && number % n as u64 == 0);
+		let by_time = self.period.map_or(false, |period| {
+			last_timestamp.map_or(true, |last| timestamp.saturating_sub(last) >= period.as_secs())
+		});
+		by_number || by_time
+	}
+
+	fn encode_meta(&self) -> Vec<u8> {
+		(self.every_blocks, self.period.map(|p| p.as_secs())).encode()
+	}
+
+	fn decode_meta(mut buffer: &[u8]) -> Result<Self, codec::Error> {
+		let (every_blocks, period) = <(Option<u32>, Option<u64>)>::decode(&mut buffer)?;
+		Ok(Self { every_blocks, period: period.map(Duration::from_secs) })
+	}
+}
+
+/// A canonical block which state is retained.
+#[derive(Debug, Clone, Encode, Decode, Eq, PartialEq)]
+pub struct Checkpoint<BlockHash> {
+	/// Block number.
+	pub number: u64,
+	/// Block hash.
+	pub hash: BlockHash,
+	/// Unix time in seconds when the block was canonicalized.
+	pub timestamp: u64,
+}
+
+/// Resolve the checkpoint policy to use from the `requested` and the stored one. `None` keeps the
+/// stored policy. Changes to the stored policy are added to `commit`.
+pub(crate) fn resolve_policy<D: MetaDb, BlockHash: Hash, Key: Hash>(
+	db: &D,
+	requested: &mut Option<CheckpointPolicy>,
+	commit: &mut CommitSet<Key>,
+) -> Result<(), Error<D::Error>> {
+	let policy_key = to_meta_key(CHECKPOINT_POLICY, &());
+	let stored = match db.get_meta(&policy_key).map_err(Error::Db)? {
+		Some(buffer) => Some(CheckpointPolicy::decode_meta(&buffer)?),
+		None => None,
+	};
+	match requested.take() {
+		None => *requested = stored,
+		Some(policy) if policy.is_enabled() => {
+			if stored.as_ref() != Some(&policy) {
+				commit.meta.inserted.push((policy_key, policy.encode_meta()));
+			}
+			*requested = Some(policy);
+		},
+		Some(_) =>
+			if stored.is_some() {
+				info!(target: LOG_TARGET, "State checkpoints disabled, forgetting retained checkpoints");
+				let retained = load_retained::<D, BlockHash>(db)?;
+				let mut number = births_swept(db, &retained)?;
+				loop {
+					number += 1;
+					let key = to_meta_key(CHECKPOINT_BIRTHS, &number);
+					if db.get_meta(&key).map_err(Error::Db)?.is_none() {
+						break
+					}
+					commit.meta.deleted.push(key);
+				}
+				commit.meta.deleted.push(policy_key);
+				commit.meta.deleted.push(to_meta_key(CHECKPOINTS, &()));
+			},
+	}
+	Ok(())
+}
+
+fn load_retained<D: MetaDb, BlockHash: Hash>(
+	db: &D,
+) -> Result<Vec<Checkpoint<BlockHash>>, Error<D::Error>> {
+	Ok(match db.get_meta(&to_meta_key(CHECKPOINTS, &())).map_err(Error::Db)? {
+		Some(buffer) => Decode::decode(&mut buffer.as_slice())?,
+		None => Vec::new(),
+	})
+}
+
+/// The block up to which the births are no longer recorded: the last pruned checkpoint, or the
+/// first checkpoint if none was pruned yet.
+fn births_swept<D: MetaDb, BlockHash: Hash>(
+	db: &D,
+	retained: &[Checkpoint<BlockHash>],
+) -> Result<u64, Error<D::Error>> {
+	let last_pruned = match db.get_meta(&to_meta_key(LAST_PRUNED, &())).map_err(Error::Db)? {
+		Some(buffer) => Some(u64::decode(&mut buffer.as_slice())?),
+		None => None,
+	};
+	let pruned = retained
+		.iter()
+		.rev()
+		.map(|c| c.number)
+		.find(|c| last_pruned.map_or(false, |last| *c <= last));
+	Ok(pruned.or_else(|| retained.first().map(|c| c.number)).unwrap_or(0))
+}
+
+/// Whether a checkpoint policy or retained checkpoints are stored in the database.
+pub(crate) fn stored<D: MetaDb>(db: &D) -> Result<bool, Error<D::Error>> {
+	Ok(db.get_meta(&to_meta_key(CHECKPOINT_POLICY, &())).map_err(Error::Db)?.is_some() ||
+		db.get_meta(&to_meta_key(CHECKPOINTS, &())).map_err(Error::Db)?.is_some())
+}
+
+/// See module documentation.
+pub(crate) struct Checkpoints<BlockHash: Hash, Key: Hash> {
+	policy: CheckpointPolicy,
+	/// Retained checkpoints ordered by block number.
+	retained: Vec<Checkpoint<BlockHash>>,
+	/// Number of the block that inserted each node since the last pruned checkpoint.
+	births: HashMap<Key, u64>,
+	/// Number of the block up to which the births are not recorded.
+	swept: u64,
+}
+
+impl<BlockHash: Hash, Key: Hash> Checkpoints<BlockHash, Key> {
+	pub fn new<D: MetaDb>(
+		db: &D,
+		policy: CheckpointPolicy,
+	) -> Result<Checkpoints<BlockHash, Key>, Error<D::Error>> {
+		let retained = load_retained(db)?;
+		let swept = births_swept(db, &retained)?;
+		let mut births = HashMap::new();
+		let mut number = swept;
+		loop {
+			number += 1;
+			let keys: Vec<Key> =
+				match db.get_meta(&to_meta_key(CHECKPOINT_BIRTHS, &number)).map_err(Error::Db)? {
+					Some(buffer) => Decode::decode(&mut buffer.as_slice())?,
+					None => break,
+				};
+			for key in keys {
+				births.entry(key).or_insert(number);
+			}
+		}
+		Ok(Checkpoints { policy, retained, births, swept })
+	}
+
+	pub fn retained(&self) -> &[Checkpoint<BlockHash>] {
+		&self.retained
+	}
+
+	pub fn contains(&self, hash: &BlockHash, number: u64) -> bool {
+		self.retained
+			.binary_search_by_key(&number, |c| c.number)
+			.map_or(false, |index| self.retained[index].hash == *hash)
+	}
+
+	/// Track the nodes inserted by a newly canonicalized block and retain its state if the policy
+	/// selects it. Adds changes to `commit`.
+	pub fn note_canonical(&mut self, hash: &BlockHash, number: u64, commit: &mut CommitSet<Key>) {
+		if self.retained.last().map_or(false, |c| c.number >= number) {
+			return
+		}
+		// The nodes inserted up to the first checkpoint are part of it.
+		if !self.retained.is_empty() {
+			let keys: Vec<Key> = commit.data.inserted.iter().map(|(key, _)| key.clone()).collect();
+			for key in &keys {
+				self.births.entry(key.clone()).or_insert(number);
+			}
+			commit
+				.meta
+				.inserted
+				.push((to_meta_key(CHECKPOINT_BIRTHS, &number), keys.encode()));
+		}
+		let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+		if self
+			.policy
+			.selects(number, timestamp, self.retained.last().map(|c| c.timestamp))
+		{
+			debug!(target: LOG_TARGET, "Retaining state checkpoint #{} ({:?})", number, hash);
+			if self.retained.is_empty() {
+				self.swept = number;
+			}
+			self.retained.push(Checkpoint { number, hash: hash.clone(), timestamp });
+			commit
+				.meta
+				.inserted
+				.push((to_meta_key(CHECKPOINTS, &()), self.retained.encode()));
+		}
+	}
+
+	/// Remove the nodes of retained checkpoints from `commit.data.deleted[first..]`, the deletions
+	/// of the pruned block `number`.
+	pub fn retain_deleted(&mut self, number: u64, commit: &mut CommitSet<Key>, first: usize) {
+		// The latest checkpoint that may contain the nodes deleted by the block.
+		let checkpoint = self.retained.iter().rev().map(|c| c.number).find(|c| *c < number);
+		let deleted = &mut commit.data.deleted;
+		let mut index = first;
+		while index < deleted.len() {
+			let birth = self.births.remove(&deleted[index]).unwrap_or(0);
+			if checkpoint.map_or(false, |c| birth <= c) {
+				deleted.swap_remove(index);
+			} else {
+				index += 1;
+			}
+		}
+
+		// All nodes inserted up to a pruned checkpoint that are still alive belong to it.
+		if let Some(latest) = self.retained.iter().rev().map(|c| c.number).find(|c| *c <= number) {
+			if self.swept < latest {
+				self.births.retain(|_, birth| *birth > latest);
+				for block in self.swept + 1..=latest {
+					commit.meta.deleted.push(to_meta_key(CHECKPOINT_BIRTHS, &block));
+				}
+				self.swept = latest;
+			}
+		}
+	}
+}
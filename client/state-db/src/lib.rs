@@ -39,29 +39,45 @@
 //!
 //! # Pruning.
 //! See `RefWindow` for pruning algorithm details. `StateDb` prunes on each canonicalization until
-//! pruning constraints are satisfied.
-
+//! pruning constraints are satisfied. Pruning constraints may also select checkpoint blocks which
+//! state is retained after they leave the pruning window. See `Checkpoints` for details.
+//!
+//! # Changing pruning mode.
+//! An archive database may be reopened with a constrained pruning mode that sets
+//! `Constraints::allow_archive_downgrade`, otherwise the modes are incompatible. The pruning window
+//! then starts at the next canonicalized block, and the state that was written before the switch is
+//! left for the backend to remove with a background compaction. The compaction is tracked with a
+//! meta flag until the backend calls `StateDb::finish_compaction`. A smaller pruning window is
+//! applied gradually, with a bounded number of blocks pruned on each canonicalization.
+
+mod checkpoints;
 mod noncanonical;
 mod pruning;
 #[cfg(test)]
 mod test;
 
+use checkpoints::Checkpoints;
 use codec::Codec;
-use log::trace;
+use log::{trace, warn};
 use noncanonical::NonCanonicalOverlay;
 use parking_lot::RwLock;
 use pruning::{HaveBlock, RefWindow};
 use std::{
-	collections::{hash_map::Entry, HashMap},
+	collections::{hash_map::Entry, HashMap, HashSet},
 	fmt,
 };
 
+pub use checkpoints::{Checkpoint, CheckpointPolicy};
+
 const LOG_TARGET: &str = "state-db";
 const LOG_TARGET_PIN: &str = "state-db::pin";
 const PRUNING_MODE: &[u8] = b"mode";
 const PRUNING_MODE_ARCHIVE: &[u8] = b"archive";
 const PRUNING_MODE_ARCHIVE_CANON: &[u8] = b"archive_canonical";
 const PRUNING_MODE_CONSTRAINED: &[u8] = b"constrained";
+const COMPACTION: &[u8] = b"compaction";
+/// Maximum number of blocks leaving the pruning window on a single canonicalization.
+const MAX_PRUNED_PER_CANONICALIZATION: usize = 32;
 pub(crate) const DEFAULT_MAX_BLOCK_CONSTRAINT: u32 = 256;
 
 /// Database value type.
@@ -145,6 +161,8 @@ pub enum StateDbError {
 	BlockUnavailable,
 	/// Block record is missing from the pruning window
 	BlockMissing,
+	/// State checkpoints requested for a database without reference counting.
+	CheckpointsUnsupported,
 }
 
 impl<E> From<StateDbError> for Error<E> {
@@ -196,6 +214,8 @@ impl fmt::Debug for StateDbError {
 				write!(f, "Trying to get a block record from db while it is not commit to db yet")
 			},
 			Self::BlockMissing => write!(f, "Block record is missing from the pruning window"),
+			Self::CheckpointsUnsupported =>
+				write!(f, "State checkpoints require a database with reference counting"),
 		}
 	}
 }
@@ -224,6 +244,12 @@ pub struct Constraints {
 	/// Maximum blocks. Defaults to 0 when unspecified, effectively keeping only non-canonical
 	/// states.
 	pub max_blocks: Option<u32>,
+	/// Canonical blocks which state is retained after leaving the pruning window. Defaults to the
+	/// policy stored in the database when unspecified.
+	pub checkpoints: Option<CheckpointPolicy>,
+	/// Allow switching a database created with an archive mode to this mode. The state that is
+	/// outside of the pruning window is then removed for good. Not stored in the database.
+	pub allow_archive_downgrade: bool,
 }
 
 /// Pruning mode.
@@ -240,7 +266,21 @@ pub enum PruningMode {
 impl PruningMode {
 	/// Create a mode that keeps given number of blocks.
 	pub fn blocks_pruning(n: u32) -> PruningMode {
-		PruningMode::Constrained(Constraints { max_blocks: Some(n) })
+		PruningMode::Constrained(Constraints {
+			max_blocks: Some(n),
+			checkpoints: None,
+			allow_archive_downgrade: false,
+		})
+	}
+
+	/// Create a mode that keeps given number of blocks and the state of the checkpoint blocks
+	/// selected by `policy`.
+	pub fn blocks_pruning_with_checkpoints(n: u32, policy: CheckpointPolicy) -> PruningMode {
+		PruningMode::Constrained(Constraints {
+			max_blocks: Some(n),
+			checkpoints: Some(policy),
+			allow_archive_downgrade: false,
+		})
 	}
 
 	/// Is this an archive (either ArchiveAll or ArchiveCanonical) pruning mode?
@@ -278,7 +318,11 @@ impl Default for PruningMode {
 
 impl Default for Constraints {
 	fn default() -> Self {
-		Self { max_blocks: Some(DEFAULT_MAX_BLOCK_CONSTRAINT) }
+		Self {
+			max_blocks: Some(DEFAULT_MAX_BLOCK_CONSTRAINT),
+			checkpoints: None,
+			allow_archive_downgrade: false,
+		}
 	}
 }
 
@@ -303,8 +347,10 @@ pub struct StateDbSync<BlockHash: Hash, Key: Hash, D: MetaDb> {
 	mode: PruningMode,
 	non_canonical: NonCanonicalOverlay<BlockHash, Key>,
 	pruning: Option<RefWindow<BlockHash, Key, D>>,
+	checkpoints: Option<Checkpoints<BlockHash, Key>>,
 	pinned: HashMap<BlockHash, u32>,
 	ref_counting: bool,
+	compaction_pending: bool,
 }
 
 impl<BlockHash: Hash, Key: Hash, D: MetaDb> StateDbSync<BlockHash, Key, D> {
@@ -316,13 +362,36 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> StateDbSync<BlockHash, Key, D> {
 		trace!(target: LOG_TARGET, "StateDb settings: {:?}. Ref-counting: {}", mode, ref_counting);
 
 		let non_canonical: NonCanonicalOverlay<BlockHash, Key> = NonCanonicalOverlay::new(&db)?;
-		let pruning: Option<RefWindow<BlockHash, Key, D>> = match mode {
-			PruningMode::Constrained(Constraints { max_blocks }) =>
-				Some(RefWindow::new(db, max_blocks.unwrap_or(0), ref_counting)?),
-			PruningMode::ArchiveAll | PruningMode::ArchiveCanonical => None,
+		let compaction_pending =
+			db.get_meta(&to_meta_key(COMPACTION, &())).map_err(Error::Db)?.is_some();
+		let (pruning, checkpoints) = match &mode {
+			PruningMode::Constrained(Constraints { max_blocks, checkpoints: policy, .. }) => {
+				let checkpoints = match policy {
+					Some(policy) if policy.is_enabled() => {
+						// Without reference counting a retained node could be deleted once it is
+						// inserted again and pruned by a later block.
+						if ref_counting {
+							return Err(StateDbError::CheckpointsUnsupported.into())
+						}
+						Some(Checkpoints::new(&db, policy.clone())?)
+					},
+					_ => None,
+				};
+				let pruning = RefWindow::new(db, max_blocks.unwrap_or(0), ref_counting)?;
+				(Some(pruning), checkpoints)
+			},
+			PruningMode::ArchiveAll | PruningMode::ArchiveCanonical => (None, None),
 		};
 
-		Ok(StateDbSync { mode, non_canonical, pruning, pinned: Default::default(), ref_counting })
+		Ok(StateDbSync {
+			mode,
+			non_canonical,
+			pruning,
+			checkpoints,
+			pinned: Default::default(),
+			ref_counting,
+			compaction_pending,
+		})
 	}
 
 	fn insert_block(
@@ -358,6 +427,9 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> StateDbSync<BlockHash, Key, D> {
 		if self.mode == PruningMode::ArchiveCanonical {
 			commit.data.deleted.clear();
 		}
+		if let Some(ref mut checkpoints) = self.checkpoints {
+			checkpoints.note_canonical(hash, number, &mut commit);
+		}
 		if let Some(ref mut pruning) = self.pruning {
 			pruning.note_canonical(hash, number, &mut commit)?;
 		}
@@ -381,37 +453,54 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> StateDbSync<BlockHash, Key, D> {
 		match self.mode {
 			PruningMode::ArchiveAll => IsPruned::NotPruned,
 			PruningMode::ArchiveCanonical | PruningMode::Constrained(_) => {
-				if self
-					.non_canonical
-					.last_canonicalized_block_number()
-					.map(|c| number > c)
-					.unwrap_or(true)
-				{
+				let last_canonicalized = self.non_canonical.last_canonicalized_block_number();
+				if last_canonicalized.map(|c| number > c).unwrap_or(true) {
 					if self.non_canonical.have_block(hash) {
 						IsPruned::NotPruned
+					} else if last_canonicalized.is_none() && self.compaction_pending {
+						// Blocks imported before switching from `ArchiveAll` are not tracked.
+						IsPruned::MaybePruned
 					} else {
 						IsPruned::Pruned
 					}
+				} else if self.is_checkpoint(hash, number) {
+					IsPruned::NotPruned
 				} else {
-					match self.pruning.as_ref() {
+					match self.have_pruned_block(hash, number) {
 						// We don't know for sure.
 						None => IsPruned::MaybePruned,
-						Some(pruning) => match pruning.have_block(hash, number) {
-							HaveBlock::No => IsPruned::Pruned,
-							HaveBlock::Yes => IsPruned::NotPruned,
-							HaveBlock::Maybe => IsPruned::MaybePruned,
-						},
+						Some(HaveBlock::No) => IsPruned::Pruned,
+						Some(HaveBlock::Yes) => IsPruned::NotPruned,
+						Some(HaveBlock::Maybe) => IsPruned::MaybePruned,
 					}
 				}
 			},
 		}
 	}
 
+	/// Check if the pruning window has the state of a canonical block. Returns `None` if pruning
+	/// is disabled.
+	fn have_pruned_block(&self, hash: &BlockHash, number: u64) -> Option<HaveBlock> {
+		self.pruning.as_ref().map(|pruning| {
+			if pruning.next_number() == 0 && pruning.window_size() == 0 {
+				// Nothing was canonicalized since switching from an archive mode, the state of
+				// canonical blocks may still be there.
+				HaveBlock::Maybe
+			} else {
+				pruning.have_block(hash, number)
+			}
+		})
+	}
+
+	fn is_checkpoint(&self, hash: &BlockHash, number: u64) -> bool {
+		self.checkpoints.as_ref().map_or(false, |c| c.contains(hash, number))
+	}
+
 	fn prune(&mut self, commit: &mut CommitSet<Key>) -> Result<(), Error<D::Error>> {
 		if let (&mut Some(ref mut pruning), PruningMode::Constrained(constraints)) =
 			(&mut self.pruning, &self.mode)
 		{
-			loop {
+			for _ in 0..MAX_PRUNED_PER_CANONICALIZATION {
 				if pruning.window_size() <= constraints.max_blocks.unwrap_or(0) as u64 {
 					break
 				}
@@ -425,12 +514,17 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> StateDbSync<BlockHash, Key, D> {
 							break
 						},
 				}
+				let number = pruning.next_number();
+				let first_deleted = commit.data.deleted.len();
 				match pruning.prune_one(commit) {
 					// this branch should not reach as previous `next_hash` don't return error
 					// keeping it for robustness
 					Err(Error::StateDb(StateDbError::BlockUnavailable)) => break,
 					res => res?,
 				}
+				if let Some(ref mut checkpoints) = self.checkpoints {
+					checkpoints.retain_deleted(number, commit, first_deleted);
+				}
 			}
 		}
 		Ok(())
@@ -463,14 +557,12 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> StateDbSync<BlockHash, Key, D> {
 			PruningMode::ArchiveAll => Ok(()),
 			PruningMode::ArchiveCanonical | PruningMode::Constrained(_) => {
 				let have_block = self.non_canonical.have_block(hash) ||
-					self.pruning.as_ref().map_or_else(
-						|| hint(),
-						|pruning| match pruning.have_block(hash, number) {
-							HaveBlock::No => false,
-							HaveBlock::Yes => true,
-							HaveBlock::Maybe => hint(),
-						},
-					);
+					self.is_checkpoint(hash, number) ||
+					match self.have_pruned_block(hash, number) {
+						Some(HaveBlock::No) => false,
+						Some(HaveBlock::Yes) => true,
+						Some(HaveBlock::Maybe) | None => hint(),
+					};
 				if have_block {
 					let refs = self.pinned.entry(hash.clone()).or_default();
 					if *refs == 0 {
@@ -539,7 +631,7 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> StateDb<BlockHash, Key, D> {
 	) -> Result<(CommitSet<Key>, StateDb<BlockHash, Key, D>), Error<D::Error>> {
 		let stored_mode = fetch_stored_pruning_mode(&db)?;
 
-		let selected_mode = match (should_init, stored_mode, requested_mode) {
+		let mut selected_mode = match (should_init, stored_mode, requested_mode) {
 			(true, stored_mode, requested_mode) => {
 				assert!(stored_mode.is_none(), "The storage has just been initialized. No meta-data is expected to be found in it.");
 				requested_mode.unwrap_or_default()
@@ -556,21 +648,37 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> StateDb<BlockHash, Key, D> {
 			(false, Some(stored), Some(requested)) => choose_pruning_mode(stored, requested)?,
 		};
 
-		let db_init_commit_set = if should_init {
-			let mut cs: CommitSet<Key> = Default::default();
-
+		let mut db_init_commit_set: CommitSet<Key> = Default::default();
+		let mode_changed = stored_mode.map_or(false, |stored| stored.id() != selected_mode.id());
+		if should_init || mode_changed {
 			let key = to_meta_key(PRUNING_MODE, &());
 			let value = selected_mode.id().to_owned();
 
-			cs.meta.inserted.push((key, value));
-
-			cs
-		} else {
-			Default::default()
-		};
+			db_init_commit_set.meta.inserted.push((key, value));
+		}
+		if mode_changed {
+			warn!(
+				target: LOG_TARGET,
+				"⚠️  The archive is switched to the pruning mode {:?}. The state outside of the \
+				pruning window is removed in the background and can't be recovered.",
+				selected_mode,
+			);
+			db_init_commit_set
+				.meta
+				.inserted
+				.push((to_meta_key(COMPACTION, &()), Vec::new()));
+		}
+		if let PruningMode::Constrained(constraints) = &mut selected_mode {
+			checkpoints::resolve_policy::<_, BlockHash, _>(
+				&db,
+				&mut constraints.checkpoints,
+				&mut db_init_commit_set,
+			)?;
+		}
 
-		let state_db =
-			StateDb { db: RwLock::new(StateDbSync::new(selected_mode, ref_counting, db)?) };
+		let mut state_db = StateDbSync::new(selected_mode, ref_counting, db)?;
+		state_db.compaction_pending |= mode_changed;
+		let state_db = StateDb { db: RwLock::new(state_db) };
 
 		Ok((db_init_commit_set, state_db))
 	}
@@ -579,6 +687,32 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> StateDb<BlockHash, Key, D> {
 		self.db.read().mode.clone()
 	}
 
+	/// Returns the retained state checkpoints, oldest first.
+	pub fn checkpoints(&self) -> Vec<Checkpoint<BlockHash>> {
+		self.db
+			.read()
+			.checkpoints
+			.as_ref()
+			.map_or_else(Vec::new, |c| c.retained().to_vec())
+	}
+
+	/// Returns `true` if the pruning mode was changed and the state written before the change
+	/// still has to be compacted by the backend.
+	pub fn compaction_pending(&self) -> bool {
+		self.db.read().compaction_pending
+	}
+
+	/// Mark the compaction as completed. Returns a database commit clearing the pending flag.
+	pub fn finish_compaction(&self) -> CommitSet<Key> {
+		let mut commit = CommitSet::default();
+		let mut state_db = self.db.write();
+		if state_db.compaction_pending {
+			state_db.compaction_pending = false;
+			commit.meta.deleted.push(to_meta_key(COMPACTION, &()));
+		}
+		commit
+	}
+
 	/// Add a new non-canonical block.
 	pub fn insert_block(
 		&self,
@@ -671,6 +805,74 @@ pub enum IsPruned {
 	MaybePruned,
 }
 
+/// Convert the journals of a database that does not support reference counting for use with one
+/// that does, mapping every node key with `map_key`.
+///
+/// Returns the meta entries that replace the journal records of the non-canonical overlay and the
+/// pruning window. The node data itself is converted by the caller.
+pub fn ref_counted_journals<BlockHash: Hash, Key: Hash, D: MetaDb>(
+	db: &D,
+	map_key: impl Fn(&Key) -> Key,
+) -> Result<Vec<(Vec<u8>, DBValue)>, Error<D::Error>> {
+	let mut journals = noncanonical::map_journal_keys::<BlockHash, Key, D>(db, &map_key)?;
+	journals.extend(pruning::ref_counted_journals::<BlockHash, Key, D>(db, &map_key)?);
+	Ok(journals)
+}
+
+/// Convert the metadata of a database that supports reference counting for use with one that does
+/// not, where the node keys differ.
+///
+/// Returns all the meta entries of the target: the pruning mode, the last canonicalized and pruned
+/// blocks, the pending compaction and the journals. `map_inserted` returns the target keys of a
+/// node inserted by a non-canonical block. `map_deleted` returns the target keys of a node deleted
+/// by the block with the given number. Without reference counting, the journals don't record that a
+/// node is inserted again, so keys still in use by that block or a later one must be left out.
+/// State checkpoints are not supported by the target.
+pub fn prefixed_meta<BlockHash: Hash, Key: Hash, D: MetaDb>(
+	db: &D,
+	map_inserted: impl Fn(&Key) -> Vec<Key>,
+	map_deleted: impl Fn(&Key, u64) -> Vec<Key>,
+) -> Result<Vec<(Vec<u8>, DBValue)>, Error<D::Error>> {
+	if checkpoints::stored(db)? {
+		return Err(StateDbError::CheckpointsUnsupported.into())
+	}
+	let mut meta = Vec::new();
+	for suffix in [PRUNING_MODE, noncanonical::LAST_CANONICAL, pruning::LAST_PRUNED, COMPACTION] {
+		let key = to_meta_key(suffix, &());
+		if let Some(value) = db.get_meta(&key).map_err(Error::Db)? {
+			meta.push((key, value));
+		}
+	}
+	meta.extend(noncanonical::prefixed_journals::<BlockHash, Key, D>(
+		db,
+		&map_inserted,
+		&map_deleted,
+	)?);
+	meta.extend(pruning::prefixed_journals::<BlockHash, Key, D>(db, &map_deleted)?);
+	Ok(meta)
+}
+
+/// Keys of the trie nodes inserted or deleted by the journals of the pruning window and of the
+/// non-canonical blocks.
+pub fn journaled_keys<BlockHash: Hash, Key: Hash, D: MetaDb>(
+	db: &D,
+) -> Result<HashSet<Key>, Error<D::Error>> {
+	let mut keys = HashSet::new();
+	pruning::journaled_keys::<BlockHash, Key, D>(db, &mut keys)?;
+	noncanonical::journaled_keys::<BlockHash, Key, D>(db, &mut keys)?;
+	Ok(keys)
+}
+
+/// Blocks whose state is retained according to the journals: the blocks of the pruning window and
+/// the non-canonical blocks, with their numbers, in the order of block numbers.
+pub fn journaled_blocks<BlockHash: Hash, Key: Hash, D: MetaDb>(
+	db: &D,
+) -> Result<Vec<(BlockHash, u64)>, Error<D::Error>> {
+	let mut blocks = pruning::journaled_blocks::<BlockHash, Key, D>(db)?;
+	blocks.extend(noncanonical::journaled_blocks::<BlockHash, Key, D>(db)?);
+	Ok(blocks)
+}
+
 fn fetch_stored_pruning_mode<D: MetaDb>(db: &D) -> Result<Option<PruningMode>, Error<D::Error>> {
 	let meta_key_mode = to_meta_key(PRUNING_MODE, &());
 	if let Some(stored_mode) = db.get_meta(&meta_key_mode).map_err(Error::Db)? {
@@ -698,6 +900,11 @@ fn choose_pruning_mode(
 			Ok(PruningMode::ArchiveCanonical),
 		(PruningMode::Constrained(_), PruningMode::Constrained(requested)) =>
 			Ok(PruningMode::Constrained(requested)),
+		// The state of the archive is compacted in the background.
+		(
+			PruningMode::ArchiveAll | PruningMode::ArchiveCanonical,
+			PruningMode::Constrained(requested),
+		) if requested.allow_archive_downgrade => Ok(PruningMode::Constrained(requested)),
 		(stored, requested) => Err(StateDbError::IncompatiblePruningModes { requested, stored }),
 	}
 }
@@ -705,8 +912,10 @@ fn choose_pruning_mode(
 #[cfg(test)]
 mod tests {
 	use crate::{
+		journaled_blocks,
 		test::{make_changeset, make_db, TestDb},
-		Constraints, Error, IsPruned, PruningMode, StateDb, StateDbError,
+		to_meta_key, CheckpointPolicy, Constraints, Error, IsPruned, MetaDb, PruningMode, StateDb,
+		StateDbError,
 	};
 	use sp_core::H256;
 
@@ -788,8 +997,11 @@ mod tests {
 
 	#[test]
 	fn block_record_unavailable() {
-		let (mut db, state_db) =
-			make_test_db(PruningMode::Constrained(Constraints { max_blocks: Some(1) }));
+		let (mut db, state_db) = make_test_db(PruningMode::Constrained(Constraints {
+			max_blocks: Some(1),
+			checkpoints: None,
+			allow_archive_downgrade: false,
+		}));
 		// import 2 blocks
 		for i in &[5, 6] {
 			db.commit(
@@ -823,13 +1035,21 @@ mod tests {
 
 	#[test]
 	fn prune_window_0() {
-		let (db, _) = make_test_db(PruningMode::Constrained(Constraints { max_blocks: Some(0) }));
+		let (db, _) = make_test_db(PruningMode::Constrained(Constraints {
+			max_blocks: Some(0),
+			checkpoints: None,
+			allow_archive_downgrade: false,
+		}));
 		assert!(db.data_eq(&make_db(&[21, 3, 922, 94])));
 	}
 
 	#[test]
 	fn prune_window_1() {
-		let (db, sdb) = make_test_db(PruningMode::Constrained(Constraints { max_blocks: Some(1) }));
+		let (db, sdb) = make_test_db(PruningMode::Constrained(Constraints {
+			max_blocks: Some(1),
+			checkpoints: None,
+			allow_archive_downgrade: false,
+		}));
 		assert_eq!(sdb.is_pruned(&H256::from_low_u64_be(0), 0), IsPruned::Pruned);
 		assert_eq!(sdb.is_pruned(&H256::from_low_u64_be(1), 1), IsPruned::Pruned);
 		assert_eq!(sdb.is_pruned(&H256::from_low_u64_be(21), 2), IsPruned::Pruned);
@@ -837,9 +1057,26 @@ mod tests {
 		assert!(db.data_eq(&make_db(&[21, 3, 922, 93, 94])));
 	}
 
+	#[test]
+	fn journaled_blocks_in_window_and_overlay() {
+		let (db, _) = make_test_db(PruningMode::Constrained(Constraints {
+			max_blocks: Some(1),
+			checkpoints: None,
+			allow_archive_downgrade: false,
+		}));
+		assert_eq!(
+			journaled_blocks::<H256, H256, _>(&db).unwrap(),
+			vec![(H256::from_low_u64_be(3), 3), (H256::from_low_u64_be(4), 4)],
+		);
+	}
+
 	#[test]
 	fn prune_window_2() {
-		let (db, sdb) = make_test_db(PruningMode::Constrained(Constraints { max_blocks: Some(2) }));
+		let (db, sdb) = make_test_db(PruningMode::Constrained(Constraints {
+			max_blocks: Some(2),
+			checkpoints: None,
+			allow_archive_downgrade: false,
+		}));
 		assert_eq!(sdb.is_pruned(&H256::from_low_u64_be(0), 0), IsPruned::Pruned);
 		assert_eq!(sdb.is_pruned(&H256::from_low_u64_be(1), 1), IsPruned::Pruned);
 		assert_eq!(sdb.is_pruned(&H256::from_low_u64_be(21), 2), IsPruned::NotPruned);
@@ -847,6 +1084,116 @@ mod tests {
 		assert!(db.data_eq(&make_db(&[1, 21, 3, 921, 922, 93, 94])));
 	}
 
+	#[test]
+	fn prune_window_0_keeps_checkpoints() {
+		let policy = CheckpointPolicy::every_blocks(2);
+		let (mut db, sdb) =
+			make_test_db(PruningMode::blocks_pruning_with_checkpoints(0, policy.clone()));
+		assert_eq!(sdb.is_pruned(&H256::from_low_u64_be(1), 1), IsPruned::Pruned);
+		assert_eq!(sdb.is_pruned(&H256::from_low_u64_be(21), 2), IsPruned::NotPruned);
+		assert_eq!(sdb.is_pruned(&H256::from_low_u64_be(22), 2), IsPruned::Pruned);
+		// state of #2 is [21, 922, 93, 94]
+		assert!(db.data_eq(&make_db(&[21, 3, 922, 93, 94])));
+
+		// the policy and the checkpoints are kept on restart
+		let (state_db_init, sdb) =
+			StateDb::<H256, H256, TestDb>::open(db.clone(), None, false, false).unwrap();
+		db.commit(&state_db_init);
+		assert_eq!(sdb.pruning_mode(), PruningMode::blocks_pruning_with_checkpoints(256, policy));
+		assert_eq!(sdb.checkpoints().len(), 1);
+		assert_eq!(sdb.is_pruned(&H256::from_low_u64_be(21), 2), IsPruned::NotPruned);
+	}
+
+	fn add_block(db: &mut TestDb, sdb: &StateDb<H256, H256, TestDb>, number: u64, deleted: &[u64]) {
+		let hash = H256::from_low_u64_be(number);
+		let parent = H256::from_low_u64_be(number - 1);
+		let changeset = make_changeset(&[number], deleted);
+		db.commit(&sdb.insert_block(&hash, number, &parent, changeset).unwrap());
+		db.commit(&sdb.canonicalize_block(&hash).unwrap());
+	}
+
+	#[test]
+	fn checkpoint_births_are_kept_on_restart() {
+		let policy = CheckpointPolicy::every_blocks(2);
+		let mut db = make_db(&[91]);
+		let (state_db_init, sdb) = StateDb::<H256, H256, TestDb>::open(
+			db.clone(),
+			Some(PruningMode::blocks_pruning_with_checkpoints(0, policy)),
+			false,
+			true,
+		)
+		.unwrap();
+		db.commit(&state_db_init);
+		add_block(&mut db, &sdb, 1, &[]);
+		add_block(&mut db, &sdb, 2, &[]);
+		add_block(&mut db, &sdb, 3, &[]);
+		let births_key = to_meta_key(b"checkpoint_births", &3u64);
+		assert!(db.get_meta(&births_key).unwrap().is_some());
+
+		// node 3 was inserted after the checkpoint #2, node 1 is a part of it
+		let (state_db_init, sdb) =
+			StateDb::<H256, H256, TestDb>::open(db.clone(), None, false, false).unwrap();
+		db.commit(&state_db_init);
+		add_block(&mut db, &sdb, 4, &[1, 3]);
+		assert!(db.data_eq(&make_db(&[91, 1, 2, 4])));
+		// the births are discarded once the next checkpoint is pruned
+		assert!(db.get_meta(&births_key).unwrap().is_none());
+		add_block(&mut db, &sdb, 5, &[]);
+		let births_key = to_meta_key(b"checkpoint_births", &5u64);
+		assert!(db.get_meta(&births_key).unwrap().is_some());
+
+		// disabling the checkpoints removes the births
+		let (state_db_init, _) = StateDb::<H256, H256, TestDb>::open(
+			db.clone(),
+			Some(PruningMode::blocks_pruning_with_checkpoints(0, CheckpointPolicy::default())),
+			false,
+			false,
+		)
+		.unwrap();
+		db.commit(&state_db_init);
+		assert!(db.get_meta(&births_key).unwrap().is_none());
+	}
+
+	#[test]
+	fn checkpoints_require_ref_counting() {
+		let mode =
+			PruningMode::blocks_pruning_with_checkpoints(0, CheckpointPolicy::every_blocks(2));
+		assert!(matches!(
+			StateDb::<H256, H256, TestDb>::open(make_db(&[]), Some(mode), true, true),
+			Err(Error::StateDb(StateDbError::CheckpointsUnsupported))
+		));
+	}
+
+	#[test]
+	fn archive_switches_to_constrained() {
+		let (mut db, _) = make_test_db(PruningMode::ArchiveCanonical);
+		let (state_db_init, sdb) = StateDb::<H256, H256, TestDb>::open(
+			db.clone(),
+			Some(PruningMode::Constrained(Constraints {
+				max_blocks: Some(1),
+				checkpoints: None,
+				allow_archive_downgrade: true,
+			})),
+			false,
+			false,
+		)
+		.unwrap();
+		db.commit(&state_db_init);
+		assert!(sdb.compaction_pending());
+		// the state of canonical blocks is left to the compaction
+		assert_eq!(sdb.is_pruned(&H256::from_low_u64_be(1), 1), IsPruned::MaybePruned);
+		assert_eq!(sdb.is_pruned(&H256::from_low_u64_be(4), 4), IsPruned::NotPruned);
+
+		db.commit(&sdb.canonicalize_block(&H256::from_low_u64_be(4)).unwrap());
+		assert_eq!(sdb.is_pruned(&H256::from_low_u64_be(3), 3), IsPruned::Pruned);
+		assert_eq!(sdb.is_pruned(&H256::from_low_u64_be(4), 4), IsPruned::NotPruned);
+
+		db.commit(&sdb.finish_compaction());
+		assert!(!sdb.compaction_pending());
+		let (_, sdb) = StateDb::<H256, H256, TestDb>::open(db.clone(), None, false, false).unwrap();
+		assert!(!sdb.compaction_pending());
+	}
+
 	#[test]
 	fn detects_incompatible_mode() {
 		let mut db = make_db(&[]);
@@ -863,7 +1210,7 @@ mod tests {
 				)
 				.unwrap(),
 		);
-		let new_mode = PruningMode::Constrained(Constraints { max_blocks: Some(2) });
+		let new_mode = PruningMode::blocks_pruning(2);
 		let state_db_open_result: Result<(_, StateDb<H256, H256, TestDb>), _> =
 			StateDb::open(db.clone(), Some(new_mode), false, false);
 		assert!(state_db_open_result.is_err());
@@ -897,6 +1244,11 @@ mod tests {
 
 	#[test]
 	fn pruning_mode_compatibility() {
+		let downgrade = PruningMode::Constrained(Constraints {
+			max_blocks: Some(128),
+			checkpoints: None,
+			allow_archive_downgrade: true,
+		});
 		for (created, reopened, expected) in [
 			(None, None, Ok(PruningMode::blocks_pruning(256))),
 			(None, Some(PruningMode::blocks_pruning(256)), Ok(PruningMode::blocks_pruning(256))),
@@ -942,6 +1294,8 @@ mod tests {
 				Some(PruningMode::ArchiveCanonical),
 				Ok(PruningMode::ArchiveCanonical),
 			),
+			(Some(PruningMode::ArchiveAll), Some(downgrade.clone()), Ok(downgrade.clone())),
+			(Some(PruningMode::ArchiveCanonical), Some(downgrade.clone()), Ok(downgrade.clone())),
 		] {
 			check_stored_and_requested_mode_compatibility(created, reopened, expected);
 		}
@@ -25,7 +25,7 @@ use crate::{LOG_TARGET, LOG_TARGET_PIN};
 use super::{to_meta_key, ChangeSet, CommitSet, DBValue, Error, Hash, MetaDb, StateDbError};
 use codec::{Decode, Encode};
 use log::trace;
-use std::collections::{hash_map::Entry, HashMap, VecDeque};
+use std::collections::{hash_map::Entry, HashMap, HashSet, VecDeque};
 
 const NON_CANONICAL_JOURNAL: &[u8] = b"noncanonical_journal";
 pub(crate) const LAST_CANONICAL: &[u8] = b"last_canonical";
@@ -81,6 +81,93 @@ fn to_journal_key(block: u64, index: u64) -> Vec<u8> {
 	to_meta_key(NON_CANONICAL_JOURNAL, &(block, index))
 }
 
+/// Call `f` with the journal key, the block number and the journal record of every non-canonical
+/// block, in the order of block numbers.
+fn for_each_journal<BlockHash: Hash, Key: Hash, D: MetaDb>(
+	db: &D,
+	mut f: impl FnMut(Vec<u8>, u64, JournalRecord<BlockHash, Key>),
+) -> Result<(), Error<D::Error>> {
+	let mut block = match db.get_meta(&to_meta_key(LAST_CANONICAL, &())).map_err(Error::Db)? {
+		Some(buffer) => <(BlockHash, u64)>::decode(&mut buffer.as_slice())?.1 + 1,
+		None => return Ok(()),
+	};
+	loop {
+		let mut level_empty = true;
+		for index in 0..MAX_BLOCKS_PER_LEVEL {
+			let journal_key = to_journal_key(block, index);
+			if let Some(record) = db.get_meta(&journal_key).map_err(Error::Db)? {
+				f(journal_key, block, Decode::decode(&mut record.as_slice())?);
+				level_empty = false;
+			}
+		}
+		if level_empty {
+			return Ok(())
+		}
+		block += 1;
+	}
+}
+
+/// Read the journal records of all non-canonical blocks, mapping every key with `map_key`.
+pub(crate) fn map_journal_keys<BlockHash: Hash, Key: Hash, D: MetaDb>(
+	db: &D,
+	map_key: &dyn Fn(&Key) -> Key,
+) -> Result<Vec<(Vec<u8>, Vec<u8>)>, Error<D::Error>> {
+	let mut journals = Vec::new();
+	for_each_journal(db, |journal_key, _, mut record: JournalRecord<BlockHash, Key>| {
+		record.inserted.iter_mut().for_each(|(k, _)| *k = map_key(k));
+		record.deleted.iter_mut().for_each(|k| *k = map_key(k));
+		journals.push((journal_key, record.encode()));
+	})?;
+	Ok(journals)
+}
+
+/// Read the journal records of all non-canonical blocks of a database that supports reference
+/// counting and convert them for one that does not. Inserted keys are mapped with `map_inserted`
+/// and the keys deleted by a block with `map_deleted`, given the block number.
+pub(crate) fn prefixed_journals<BlockHash: Hash, Key: Hash, D: MetaDb>(
+	db: &D,
+	map_inserted: &dyn Fn(&Key) -> Vec<Key>,
+	map_deleted: &dyn Fn(&Key, u64) -> Vec<Key>,
+) -> Result<Vec<(Vec<u8>, Vec<u8>)>, Error<D::Error>> {
+	let mut journals = Vec::new();
+	for_each_journal(db, |journal_key, number, record: JournalRecord<BlockHash, Key>| {
+		let record = JournalRecord {
+			hash: record.hash,
+			parent_hash: record.parent_hash,
+			inserted: record
+				.inserted
+				.iter()
+				.flat_map(|(k, v)| map_inserted(k).into_iter().map(move |k| (k, v.clone())))
+				.collect(),
+			deleted: record.deleted.iter().flat_map(|k| map_deleted(k, number)).collect(),
+		};
+		journals.push((journal_key, record.encode()));
+	})?;
+	Ok(journals)
+}
+
+/// Add the keys inserted or deleted by the non-canonical blocks to `keys`.
+pub(crate) fn journaled_keys<BlockHash: Hash, Key: Hash, D: MetaDb>(
+	db: &D,
+	keys: &mut HashSet<Key>,
+) -> Result<(), Error<D::Error>> {
+	for_each_journal(db, |_, _, record: JournalRecord<BlockHash, Key>| {
+		keys.extend(record.inserted.into_iter().map(|(k, _)| k));
+		keys.extend(record.deleted);
+	})
+}
+
+/// Hashes and numbers of the non-canonical blocks in the journal.
+pub(crate) fn journaled_blocks<BlockHash: Hash, Key: Hash, D: MetaDb>(
+	db: &D,
+) -> Result<Vec<(BlockHash, u64)>, Error<D::Error>> {
+	let mut blocks = Vec::new();
+	for_each_journal(db, |_, number, record: JournalRecord<BlockHash, Key>| {
+		blocks.push((record.hash, number))
+	})?;
+	Ok(blocks)
+}
+
 #[cfg_attr(test, derive(PartialEq, Debug))]
 struct BlockOverlay<BlockHash: Hash, Key: Hash> {
 	hash: BlockHash,
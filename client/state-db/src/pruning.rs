@@ -279,6 +279,101 @@ fn to_journal_key(block: u64) -> Vec<u8> {
 	to_meta_key(PRUNING_JOURNAL, &block)
 }
 
+/// Call `f` with the block number and the journal record of every block in the pruning window, in
+/// order. Returns the number of the first block in the window.
+fn for_each_journal<BlockHash: Hash, Key: Hash, D: MetaDb>(
+	db: &D,
+	mut f: impl FnMut(u64, JournalRecord<BlockHash, Key>),
+) -> Result<u64, Error<D::Error>> {
+	let base = match db.get_meta(&to_meta_key(LAST_PRUNED, &())).map_err(Error::Db)? {
+		Some(buffer) => u64::decode(&mut buffer.as_slice())? + 1,
+		None => 0,
+	};
+	let mut block = base;
+	while let Some(record) = db.get_meta(&to_journal_key(block)).map_err(Error::Db)? {
+		f(block, Decode::decode(&mut record.as_slice())?);
+		block += 1;
+	}
+	Ok(base)
+}
+
+/// Hashes and numbers of the blocks in the pruning window.
+pub(crate) fn journaled_blocks<BlockHash: Hash, Key: Hash, D: MetaDb>(
+	db: &D,
+) -> Result<Vec<(BlockHash, u64)>, Error<D::Error>> {
+	let mut blocks = Vec::new();
+	for_each_journal(db, |number, record: JournalRecord<BlockHash, Key>| {
+		blocks.push((record.hash, number))
+	})?;
+	Ok(blocks)
+}
+
+/// Read the pruning journal of a database that does not support reference counting and convert
+/// it for one that does, mapping every key with `map_key`. Keys that are re-inserted by a later
+/// block in the window are dropped from the deletions, as the memory queue does when it loads the
+/// journal.
+pub(crate) fn ref_counted_journals<BlockHash: Hash, Key: Hash, D: MetaDb>(
+	db: &D,
+	map_key: &dyn Fn(&Key) -> Key,
+) -> Result<Vec<(Vec<u8>, Vec<u8>)>, Error<D::Error>> {
+	let mut death_rows: Vec<DeathRow<BlockHash, Key>> = Vec::new();
+	let mut death_index: HashMap<Key, usize> = HashMap::new();
+	let base = for_each_journal(db, |_, record: JournalRecord<BlockHash, Key>| {
+		for k in record.inserted {
+			if let Some(index) = death_index.remove(&k) {
+				death_rows[index].deleted.remove(&k);
+			}
+		}
+		for k in record.deleted.iter() {
+			death_index.insert(k.clone(), death_rows.len());
+		}
+		death_rows
+			.push(DeathRow { hash: record.hash, deleted: record.deleted.into_iter().collect() });
+	})?;
+	Ok(death_rows
+		.into_iter()
+		.enumerate()
+		.map(|(index, row)| {
+			let record = JournalRecord {
+				hash: row.hash,
+				inserted: Vec::new(),
+				deleted: row.deleted.iter().map(map_key).collect(),
+			};
+			(to_journal_key(base + index as u64), record.encode())
+		})
+		.collect())
+}
+
+/// Read the pruning journal of a database that supports reference counting and convert it for one
+/// that does not, mapping the keys deleted by a block with `map_deleted`, given the block number.
+pub(crate) fn prefixed_journals<BlockHash: Hash, Key: Hash, D: MetaDb>(
+	db: &D,
+	map_deleted: &dyn Fn(&Key, u64) -> Vec<Key>,
+) -> Result<Vec<(Vec<u8>, Vec<u8>)>, Error<D::Error>> {
+	let mut journals = Vec::new();
+	for_each_journal(db, |number, record: JournalRecord<BlockHash, Key>| {
+		let record = JournalRecord {
+			hash: record.hash,
+			inserted: Vec::new(),
+			deleted: record.deleted.iter().flat_map(|k| map_deleted(k, number)).collect(),
+		};
+		journals.push((to_journal_key(number), record.encode()));
+	})?;
+	Ok(journals)
+}
+
+/// Add the keys inserted or deleted by the blocks in the pruning window to `keys`.
+pub(crate) fn journaled_keys<BlockHash: Hash, Key: Hash, D: MetaDb>(
+	db: &D,
+	keys: &mut HashSet<Key>,
+) -> Result<(), Error<D::Error>> {
+	for_each_journal(db, |_, record: JournalRecord<BlockHash, Key>| {
+		keys.extend(record.inserted);
+		keys.extend(record.deleted);
+	})?;
+	Ok(())
+}
+
 /// The result return by `RefWindow::have_block`
 #[derive(Debug, PartialEq, Eq)]
 pub enum HaveBlock {
@@ -368,6 +463,11 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> RefWindow<BlockHash, Key, D> {
 		Ok(res)
 	}
 
+	/// Get the number of the next pruning block
+	pub fn next_number(&self) -> u64 {
+		self.base
+	}
+
 	fn is_empty(&self) -> bool {
 		self.window_size() == 0
 	}
@@ -427,7 +527,10 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> RefWindow<BlockHash, Key, D> {
 
 #[cfg(test)]
 mod tests {
-	use super::{to_journal_key, DeathRowQueue, HaveBlock, JournalRecord, RefWindow, LAST_PRUNED};
+	use super::{
+		prefixed_journals, ref_counted_journals, to_journal_key, DeathRowQueue, HaveBlock,
+		JournalRecord, RefWindow, LAST_PRUNED,
+	};
 	use crate::{
 		noncanonical::LAST_CANONICAL,
 		test::{make_commit, make_db, TestDb},
@@ -582,6 +685,87 @@ mod tests {
 		assert_eq!(pruning.base, 3);
 	}
 
+	#[test]
+	fn reinserted_survives_ref_counted_journals() {
+		let mut db = make_db(&[1, 2, 3]);
+		let mut pruning: RefWindow<H256, H256, TestDb> =
+			RefWindow::new(db.clone(), DEFAULT_MAX_BLOCK_CONSTRAINT, true).unwrap();
+		let mut commit = make_commit(&[], &[2]);
+		pruning.note_canonical(&H256::random(), 0, &mut commit).unwrap();
+		db.commit(&commit);
+		let mut commit = make_commit(&[2], &[]);
+		pruning.note_canonical(&H256::random(), 1, &mut commit).unwrap();
+		db.commit(&commit);
+		let mut commit = make_commit(&[], &[2]);
+		pruning.note_canonical(&H256::random(), 2, &mut commit).unwrap();
+		commit
+			.meta
+			.inserted
+			.push((to_meta_key(LAST_CANONICAL, &()), (H256::random(), 2u64).encode()));
+		db.commit(&commit);
+
+		let mut commit = CommitSet::default();
+		commit.meta.inserted = ref_counted_journals::<H256, H256, _>(&db, &|k: &H256| *k).unwrap();
+		db.commit(&commit);
+		let mut pruning: RefWindow<H256, H256, TestDb> =
+			RefWindow::new(db.clone(), DEFAULT_MAX_BLOCK_CONSTRAINT, false).unwrap();
+
+		let mut commit = CommitSet::default();
+		pruning.prune_one(&mut commit).unwrap();
+		db.commit(&commit);
+		assert!(db.data_eq(&make_db(&[1, 2, 3])));
+		let mut commit = CommitSet::default();
+		pruning.prune_one(&mut commit).unwrap();
+		db.commit(&commit);
+		assert!(db.data_eq(&make_db(&[1, 2, 3])));
+		let mut commit = CommitSet::default();
+		pruning.prune_one(&mut commit).unwrap();
+		db.commit(&commit);
+		assert!(db.data_eq(&make_db(&[1, 3])));
+		assert_eq!(pruning.base, 3);
+	}
+
+	#[test]
+	fn prefixed_journals_leave_out_keys_in_use() {
+		let mut db = make_db(&[1, 2, 3]);
+		let mut pruning: RefWindow<H256, H256, TestDb> =
+			RefWindow::new(db.clone(), DEFAULT_MAX_BLOCK_CONSTRAINT, false).unwrap();
+		let mut commit = make_commit(&[], &[1, 2]);
+		pruning.note_canonical(&H256::random(), 0, &mut commit).unwrap();
+		db.commit(&commit);
+		let mut commit = make_commit(&[], &[3]);
+		pruning.note_canonical(&H256::random(), 1, &mut commit).unwrap();
+		commit
+			.meta
+			.inserted
+			.push((to_meta_key(LAST_CANONICAL, &()), (H256::random(), 1u64).encode()));
+		db.commit(&commit);
+
+		// Node `2` is still in use by block `1`.
+		let in_use = H256::from_low_u64_be(2);
+		let mut commit = CommitSet::default();
+		commit.meta.inserted = prefixed_journals::<H256, H256, _>(&db, &|k: &H256, number| {
+			if *k == in_use && number < 2 {
+				Vec::new()
+			} else {
+				vec![*k]
+			}
+		})
+		.unwrap();
+		db.commit(&commit);
+		let mut pruning: RefWindow<H256, H256, TestDb> =
+			RefWindow::new(db.clone(), DEFAULT_MAX_BLOCK_CONSTRAINT, true).unwrap();
+
+		let mut commit = CommitSet::default();
+		pruning.prune_one(&mut commit).unwrap();
+		db.commit(&commit);
+		assert!(db.data_eq(&make_db(&[2, 3])));
+		let mut commit = CommitSet::default();
+		pruning.prune_one(&mut commit).unwrap();
+		db.commit(&commit);
+		assert!(db.data_eq(&make_db(&[2])));
+	}
+
 	#[test]
 	fn reinserted_survive_pending() {
 		let mut db = make_db(&[1, 2, 3]);
@@ -37,38 +37,70 @@
 //! statements are deleted and `Ignored` result is returned.
 //! The order in which statements with the same priority are deleted is unspecified.
 //!
+//! These constraints are implemented by [`DefaultPolicy`]. A custom [`StatementPolicy`] can be
+//! provided with [`Options::with_policy`] to change how statements are admitted and evicted.
+//!
 //! Statement expiration.
 //!
 //! Each time a statement is removed from the store (Either evicted by higher priority statement or
 //! explicitly with the `remove` function) the statement is marked as expired. Expired statements
 //! can't be added to the store for `Options::purge_after_sec` seconds. This is to prevent old
 //! statements from being propagated on the network.
+//!
+//! Statement subscriptions.
+//!
+//! Each statement accepted by the store is assigned an increasing sequence number. Subscribers
+//! are notified of the accepted statements that match their filter and may request the
+//! statements already in the store that were accepted after a given sequence number to be
+//! replayed first. Sequence numbers are stored in the database along with the statements, so
+//! that subscribers may resume after a restart of the node. Statements stored without a sequence
+//! number are numbered on startup, after all the stored ones. There may not be more than
+//! `Options::max_subscribers` subscribers at once.
+//!
+//! Encrypted statements.
+//!
+//! Statement data addressed to a decryption key may be encrypted with the helpers in
+//! `sp_statement_store::ecies`. `posted_clear` decrypts such statements with the matching sr25519
+//! or ed25519 statement key from the keystore, skipping statements it is unable to decrypt. It
+//! fails if the keystore itself can't decrypt with its keys, as is the case for keystores that
+//! don't hold the secret keys.
 
 #![warn(missing_docs)]
 #![warn(unused_extern_crates)]
 
 mod metrics;
+mod policy;
 
+pub use policy::{
+	Admission, DefaultPolicy, EvictionReason, StatementInfo, StatementPolicy, StoreView,
+};
 pub use sp_statement_store::{Error, StatementStore, MAX_TOPICS};
 
+use futures::channel::mpsc;
 use metrics::MetricsLink as PrometheusMetrics;
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 use prometheus_endpoint::Registry as PrometheusRegistry;
 use sp_api::ProvideRuntimeApi;
 use sp_blockchain::HeaderBackend;
-use sp_core::{hexdisplay::HexDisplay, traits::SpawnNamed, Decode, Encode};
+use sp_core::{
+	crypto::key_types::STATEMENT, hexdisplay::HexDisplay, traits::SpawnNamed, Decode, Encode,
+};
+use sp_keystore::KeystorePtr;
 use sp_runtime::traits::Block as BlockT;
 use sp_statement_store::{
 	runtime_api::{InvalidStatement, StatementSource, ValidStatement, ValidateStatement},
-	AccountId, BlockHash, Channel, DecryptionKey, Hash, NetworkPriority, Proof, Result, Statement,
-	SubmitResult, Topic,
+	AccountId, BlockHash, Channel, DecryptionKey, Hash, NetworkPriority, Proof, Result,
+	SequencedStatement, Statement, StatementFilter, StatementStream, SubmitResult, Topic,
 };
 use std::{
 	collections::{BTreeMap, HashMap, HashSet},
+	ops::Bound,
 	sync::Arc,
 };
 
 const KEY_VERSION: &[u8] = b"version".as_slice();
+const KEY_LAST_SEQUENCE: &[u8] = b"last_sequence".as_slice();
+const SEQUENCE_PREFIX: &[u8] = b"sequence".as_slice();
 const CURRENT_VERSION: u32 = 1;
 
 const LOG_TARGET: &str = "statement-store";
@@ -76,9 +108,13 @@ const LOG_TARGET: &str = "statement-store";
 const DEFAULT_PURGE_AFTER_SEC: u64 = 2 * 24 * 60 * 60; //48h
 const DEFAULT_MAX_TOTAL_STATEMENTS: usize = 8192;
 const DEFAULT_MAX_TOTAL_SIZE: usize = 64 * 1024 * 1024;
+const DEFAULT_MAX_SUBSCRIBERS: usize = 128;
 
 const MAINTENANCE_PERIOD: std::time::Duration = std::time::Duration::from_secs(30);
 
+/// Number of accepted statements buffered for a subscriber, in addition to the replayed ones.
+const SUBSCRIPTION_BUFFER: usize = 1024;
+
 mod col {
 	pub const META: u8 = 0;
 	pub const STATEMENTS: u8 = 1;
@@ -134,6 +170,10 @@ pub struct Options {
 	max_total_size: usize,
 	/// Number of seconds for which removed statements won't be allowed to be added back in.
 	purge_after_sec: u64,
+	/// Maximum number of statement subscribers.
+	max_subscribers: usize,
+	/// Admission and eviction policy.
+	policy: Arc<dyn StatementPolicy>,
 }
 
 impl Default for Options {
@@ -142,10 +182,20 @@ impl Default for Options {
 			max_total_statements: DEFAULT_MAX_TOTAL_STATEMENTS,
 			max_total_size: DEFAULT_MAX_TOTAL_SIZE,
 			purge_after_sec: DEFAULT_PURGE_AFTER_SEC,
+			max_subscribers: DEFAULT_MAX_SUBSCRIBERS,
+			policy: Arc::new(DefaultPolicy),
 		}
 	}
 }
 
+impl Options {
+	/// Use the given admission and eviction policy instead of [`DefaultPolicy`].
+	pub fn with_policy(mut self, policy: impl StatementPolicy + 'static) -> Self {
+		self.policy = Arc::new(policy);
+		self
+	}
+}
+
 #[derive(Default)]
 struct Index {
 	by_topic: HashMap<Topic, HashSet<Hash>>,
@@ -154,10 +204,19 @@ struct Index {
 	entries: HashMap<Hash, (AccountId, Priority, usize)>,
 	expired: HashMap<Hash, u64>, // Value is expiration timestamp.
 	accounts: HashMap<AccountId, StatementsForAccount>,
+	// Statements ordered by the sequence number assigned when they were accepted.
+	by_sequence: BTreeMap<u64, Hash>,
+	sequences: HashMap<Hash, u64>,
+	last_sequence: u64,
 	options: Options,
 	total_size: usize,
 }
 
+struct Subscriber {
+	filter: StatementFilter,
+	sender: mpsc::Sender<SequencedStatement>,
+}
+
 struct ClientWrapper<Block, Client> {
 	client: Arc<Client>,
 	_block: std::marker::PhantomData<Block>,
@@ -199,11 +258,18 @@ pub struct Store {
 			+ Send
 			+ Sync,
 	>,
+	subscribers: Mutex<Vec<Subscriber>>,
+	keystore: KeystorePtr,
 	// Used for testing
 	time_override: Option<u64>,
 	metrics: PrometheusMetrics,
 }
 
+/// Key of the sequence number of the statement in the `META` column.
+fn sequence_key(hash: &Hash) -> Vec<u8> {
+	[SEQUENCE_PREFIX, hash.as_slice()].concat()
+}
+
 enum IndexQuery {
 	Unknown,
 	Exists,
@@ -211,7 +277,7 @@ enum IndexQuery {
 }
 
 enum MaybeInserted {
-	Inserted(HashSet<Hash>),
+	Inserted(Vec<(Hash, EvictionReason)>, u64),
 	Ignored,
 }
 
@@ -246,6 +312,20 @@ impl Index {
 			.insert(PriorityKey { hash, priority }, (statement.channel(), statement.data_len()));
 	}
 
+	/// Assign the given sequence number to the statement, or the next one if `None`.
+	fn assign_sequence(&mut self, hash: Hash, sequence: Option<u64>) -> u64 {
+		let sequence = match sequence {
+			Some(sequence) => sequence,
+			None => self.last_sequence + 1,
+		};
+		self.last_sequence = self.last_sequence.max(sequence);
+		if let Some(previous) = self.sequences.insert(hash, sequence) {
+			self.by_sequence.remove(&previous);
+		}
+		self.by_sequence.insert(sequence, hash);
+		sequence
+	}
+
 	fn query(&self, hash: &Hash) -> IndexQuery {
 		if self.entries.contains_key(hash) {
 			return IndexQuery::Exists
@@ -319,6 +399,9 @@ impl Index {
 	fn make_expired(&mut self, hash: &Hash, current_time: u64) -> bool {
 		if let Some((account, priority, len)) = self.entries.remove(hash) {
 			self.total_size -= len;
+			if let Some(sequence) = self.sequences.remove(hash) {
+				self.by_sequence.remove(&sequence);
+			}
 			if let Some((topics, key)) = self.topics_and_keys.remove(hash) {
 				for t in topics.into_iter().flatten() {
 					if let std::collections::hash_map::Entry::Occupied(mut set) =
@@ -369,104 +452,109 @@ impl Index {
 		validation: &ValidStatement,
 		current_time: u64,
 	) -> MaybeInserted {
-		let statement_len = statement.data_len();
-		if statement_len > validation.max_size as usize {
-			log::debug!(
-				target: LOG_TARGET,
-				"Ignored oversize message: {:?} ({} bytes)",
-				HexDisplay::from(&hash),
-				statement_len,
-			);
-			return MaybeInserted::Ignored
-		}
+		let candidate = StatementInfo {
+			hash,
+			account: *account,
+			priority: statement.priority().unwrap_or(0),
+			channel: statement.channel(),
+			data_size: statement.data_len(),
+		};
+		let mut evicted = match self.options.policy.admit(&*self, &candidate, validation) {
+			Admission::Ignore => return MaybeInserted::Ignored,
+			Admission::Accept(evicted) => evicted,
+		};
 
-		let mut evicted = HashSet::new();
-		let mut would_free_size = 0;
-		let priority = Priority(statement.priority().unwrap_or(0));
-		let (max_size, max_count) = (validation.max_size as usize, validation.max_count as usize);
-		// It may happen that we can't delete enough lower priority messages
-		// to satisfy size constraints. We check for that before deleting anything,
-		// taking into account channel message replacement.
-		if let Some(account_rec) = self.accounts.get(account) {
-			if let Some(channel) = statement.channel() {
-				if let Some(channel_record) = account_rec.channels.get(&channel) {
-					if priority <= channel_record.priority {
-						// Trying to replace channel message with lower priority
-						log::debug!(
-							target: LOG_TARGET,
-							"Ignored lower priority channel message: {:?} {:?} <= {:?}",
-							HexDisplay::from(&hash),
-							priority,
-							channel_record.priority,
-						);
-						return MaybeInserted::Ignored
-					} else {
-						// Would replace channel message. Still need to check for size constraints
-						// below.
-						log::debug!(
-							target: LOG_TARGET,
-							"Replacing higher priority channel message: {:?} ({:?}) > {:?} ({:?})",
-							HexDisplay::from(&hash),
-							priority,
-							HexDisplay::from(&channel_record.hash),
-							channel_record.priority,
-						);
-						let key = PriorityKey {
-							hash: channel_record.hash,
-							priority: channel_record.priority,
-						};
-						if let Some((_channel, len)) = account_rec.by_priority.get(&key) {
-							would_free_size += *len;
-							evicted.insert(channel_record.hash);
-						}
-					}
-				}
-			}
-			// Check if we can evict enough lower priority statements to satisfy constraints
-			for (entry, (_, len)) in account_rec.by_priority.iter() {
-				if (account_rec.data_size - would_free_size + statement_len <= max_size) &&
-					account_rec.by_priority.len() + 1 - evicted.len() <= max_count
-				{
-					// Satisfied
-					break
-				}
-				if evicted.contains(&entry.hash) {
-					// Already accounted for above
-					continue
-				}
-				if entry.priority >= priority {
-					log::debug!(
-						target: LOG_TARGET,
-						"Ignored message due to constraints {:?} {:?} < {:?}",
-						HexDisplay::from(&hash),
-						priority,
-						entry.priority,
-					);
-					return MaybeInserted::Ignored
+		// The policy is not trusted with the invariants of the store: only one statement per
+		// account and channel is kept, and the global limits are never exceeded.
+		let mut unique = HashSet::new();
+		evicted.retain(|(h, _)| self.entries.contains_key(h) && unique.insert(*h));
+		if let Some(channel) = &candidate.channel {
+			if let Some(previous) = self.channel_statement(account, channel) {
+				if unique.insert(previous.hash) {
+					evicted.push((previous.hash, EvictionReason::ChannelReplaced));
 				}
-				evicted.insert(entry.hash);
-				would_free_size += len;
 			}
 		}
-		// Now check global constraints as well.
-		if !((self.total_size - would_free_size + statement_len <= self.options.max_total_size) &&
-			self.entries.len() + 1 - evicted.len() <= self.options.max_total_statements)
+		let freed_size: usize = evicted.iter().map(|(h, _)| self.entries[h].2).sum();
+		if self.total_size - freed_size + candidate.data_size > self.options.max_total_size ||
+			self.entries.len() - evicted.len() + 1 > self.options.max_total_statements
 		{
 			log::debug!(
 				target: LOG_TARGET,
-				"Ignored statement {} because the store is full (size={}, count={})",
+				"Ignored statement {} admitted by the policy over the store limits",
 				HexDisplay::from(&hash),
-				self.total_size,
-				self.entries.len(),
 			);
 			return MaybeInserted::Ignored
 		}
 
-		for h in &evicted {
+		for (h, _) in &evicted {
 			self.make_expired(h, current_time);
 		}
 		self.insert_new(hash, *account, statement);
-		MaybeInserted::Inserted(evicted)
+		let sequence = self.assign_sequence(hash, None);
+		MaybeInserted::Inserted(evicted, sequence)
+	}
+}
+
+impl StoreView for Index {
+	fn statement_count(&self) -> usize {
+		self.entries.len()
+	}
+
+	fn total_size(&self) -> usize {
+		self.total_size
+	}
+
+	fn max_total_statements(&self) -> usize {
+		self.options.max_total_statements
+	}
+
+	fn max_total_size(&self) -> usize {
+		self.options.max_total_size
+	}
+
+	fn account_statement_count(&self, account: &AccountId) -> usize {
+		self.accounts
+			.get(account)
+			.map_or(0, |account_rec| account_rec.by_priority.len())
+	}
+
+	fn account_data_size(&self, account: &AccountId) -> usize {
+		self.accounts.get(account).map_or(0, |account_rec| account_rec.data_size)
+	}
+
+	fn account_statements<'a>(
+		&'a self,
+		account: &AccountId,
+	) -> Box<dyn Iterator<Item = StatementInfo> + 'a> {
+		let account = *account;
+		match self.accounts.get(&account) {
+			Some(account_rec) =>
+				Box::new(account_rec.by_priority.iter().map(move |(key, (channel, len))| {
+					StatementInfo {
+						hash: key.hash,
+						account,
+						priority: key.priority.0,
+						channel: *channel,
+						data_size: *len,
+					}
+				})),
+			None => Box::new(std::iter::empty()),
+		}
+	}
+
+	fn channel_statement(&self, account: &AccountId, channel: &Channel) -> Option<StatementInfo> {
+		let account_rec = self.accounts.get(account)?;
+		let entry = account_rec.channels.get(channel)?;
+		let key = PriorityKey { hash: entry.hash, priority: entry.priority };
+		let (_, len) = account_rec.by_priority.get(&key)?;
+		Some(StatementInfo {
+			hash: entry.hash,
+			account: *account,
+			priority: entry.priority.0,
+			channel: Some(*channel),
+			data_size: *len,
+		})
 	}
 }
 
@@ -477,6 +565,7 @@ impl Store {
 		path: &std::path::Path,
 		options: Options,
 		client: Arc<Client>,
+		keystore: KeystorePtr,
 		prometheus: Option<&PrometheusRegistry>,
 		task_spawner: &dyn SpawnNamed,
 	) -> Result<Arc<Store>>
@@ -491,7 +580,7 @@ impl Store {
 			+ 'static,
 		Client::Api: ValidateStatement<Block>,
 	{
-		let store = Arc::new(Self::new(path, options, client.clone(), prometheus)?);
+		let store = Arc::new(Self::new(path, options, client.clone(), keystore, prometheus)?);
 		client.execution_extensions().register_statement_store(store.clone());
 
 		// Perform periodic statement store maintenance
@@ -517,6 +606,7 @@ impl Store {
 		path: &std::path::Path,
 		options: Options,
 		client: Arc<Client>,
+		keystore: KeystorePtr,
 		prometheus: Option<&PrometheusRegistry>,
 	) -> Result<Store>
 	where
@@ -565,6 +655,8 @@ impl Store {
 			db,
 			index: RwLock::new(Index::new(options)),
 			validate_fn,
+			subscribers: Default::default(),
+			keystore,
 			time_override: None,
 			metrics: PrometheusMetrics::new(prometheus),
 		};
@@ -618,17 +710,57 @@ impl Store {
 					true
 				})
 				.map_err(|e| Error::Db(e.to_string()))?;
+			self.load_sequences(&mut index)?;
 		}
 
 		self.maintain();
 		Ok(())
 	}
 
+	/// Assign the stored sequence numbers to the loaded statements. Statements without one are
+	/// numbered after the last stored sequence number and their numbers are stored.
+	fn load_sequences(&self, index: &mut Index) -> Result<()> {
+		if let Some(last) = self
+			.db
+			.get(col::META, KEY_LAST_SEQUENCE)
+			.map_err(|e| Error::Db(e.to_string()))?
+		{
+			index.last_sequence = u64::decode(&mut last.as_slice())
+				.map_err(|_| Error::Db("Error reading the last sequence number".into()))?;
+		}
+		let hashes: Vec<Hash> = index.entries.keys().copied().collect();
+		let mut unnumbered = Vec::new();
+		for hash in hashes {
+			let stored = self
+				.db
+				.get(col::META, &sequence_key(&hash))
+				.map_err(|e| Error::Db(e.to_string()))?
+				.and_then(|sequence| u64::decode(&mut sequence.as_slice()).ok());
+			match stored {
+				Some(sequence) => {
+					index.assign_sequence(hash, Some(sequence));
+				},
+				None => unnumbered.push(hash),
+			}
+		}
+		if unnumbered.is_empty() {
+			return Ok(())
+		}
+
+		let mut commit = Vec::with_capacity(unnumbered.len() + 1);
+		for hash in unnumbered {
+			let sequence = index.assign_sequence(hash, None);
+			commit.push((col::META, sequence_key(&hash), Some(sequence.encode())));
+		}
+		commit.push((col::META, KEY_LAST_SEQUENCE.to_vec(), Some(index.last_sequence.encode())));
+		self.db.commit(commit).map_err(|e| Error::Db(e.to_string()))
+	}
+
 	fn collect_statements<R>(
 		&self,
 		key: Option<DecryptionKey>,
 		match_all_topics: &[Topic],
-		mut f: impl FnMut(Statement) -> Option<R>,
+		mut f: impl FnMut(Statement) -> Result<Option<R>>,
 	) -> Result<Vec<R>> {
 		let mut result = Vec::new();
 		let index = self.index.read();
@@ -636,7 +768,7 @@ impl Store {
 			match self.db.get(col::STATEMENTS, hash).map_err(|e| Error::Db(e.to_string()))? {
 				Some(entry) => {
 					if let Ok(statement) = Statement::decode(&mut entry.as_slice()) {
-						if let Some(data) = f(statement) {
+						if let Some(data) = f(statement)? {
 							result.push(data);
 						}
 					} else {
@@ -662,6 +794,51 @@ impl Store {
 		Ok(result)
 	}
 
+	/// Decrypt the statement data with the keystore key that matches the statement decryption key.
+	///
+	/// Both sr25519 and ed25519 statement keys are tried. Returns `None` if the statement has no
+	/// data, the key is not in the keystore or decryption fails. Fails if the keystore is unable
+	/// to decrypt with its keys.
+	fn decrypt_statement(&self, statement: &Statement) -> Result<Option<Vec<u8>>> {
+		let (Some(key), Some(data)) = (statement.decryption_key(), statement.data()) else {
+			return Ok(None)
+		};
+		let decrypted = match self.keystore.sr25519_ecies_decrypt(
+			STATEMENT,
+			&sp_core::sr25519::Public(key),
+			data,
+		) {
+			Ok(None) =>
+				self.keystore
+					.ed25519_ecies_decrypt(STATEMENT, &sp_core::ed25519::Public(key), data),
+			result => result,
+		};
+		match decrypted {
+			Ok(Some(data)) => Ok(Some(data)),
+			Ok(None) => {
+				log::debug!(
+					target: LOG_TARGET,
+					"Missing decryption key {:?}",
+					HexDisplay::from(&key)
+				);
+				Ok(None)
+			},
+			Err(sp_keystore::Error::ValidationError(e)) => {
+				log::debug!(
+					target: LOG_TARGET,
+					"Error decrypting statement {:?}: {}",
+					HexDisplay::from(&statement.hash()),
+					e
+				);
+				Ok(None)
+			},
+			Err(e) => {
+				log::warn!(target: LOG_TARGET, "Unable to decrypt statements with the keystore: {}", e);
+				Err(Error::Keystore(e.to_string()))
+			},
+		}
+	}
+
 	/// Perform periodic store maintenance
 	pub fn maintain(&self) {
 		log::trace!(target: LOG_TARGET, "Started store maintenance");
@@ -683,6 +860,30 @@ impl Store {
 		);
 	}
 
+	/// Notify the subscribers of a newly accepted statement.
+	///
+	/// Subscribers that are gone or do not keep up with the accepted statements are removed.
+	fn notify_subscribers(&self, sequence: u64, statement: &Statement) {
+		self.subscribers.lock().retain_mut(|subscriber| {
+			if !subscriber.filter.matches(statement) {
+				return !subscriber.sender.is_closed()
+			}
+			let notification = SequencedStatement { sequence, statement: statement.clone() };
+			match subscriber.sender.try_send(notification) {
+				Ok(()) => true,
+				Err(e) => {
+					if e.is_full() {
+						log::debug!(
+							target: LOG_TARGET,
+							"Dropping subscriber that does not keep up with statements",
+						);
+					}
+					false
+				},
+			}
+		});
+	}
+
 	fn timestamp(&self) -> u64 {
 		self.time_override.unwrap_or_else(|| {
 			std::time::SystemTime::now()
@@ -749,20 +950,22 @@ impl StatementStore for Store {
 	/// Return the data of all known statements which include all topics and have no `DecryptionKey`
 	/// field.
 	fn broadcasts(&self, match_all_topics: &[Topic]) -> Result<Vec<Vec<u8>>> {
-		self.collect_statements(None, match_all_topics, |statement| statement.into_data())
+		self.collect_statements(None, match_all_topics, |statement| Ok(statement.into_data()))
 	}
 
 	/// Return the data of all known statements whose decryption key is identified as `dest` (this
 	/// will generally be the public key or a hash thereof for symmetric ciphers, or a hash of the
 	/// private key for symmetric ciphers).
 	fn posted(&self, match_all_topics: &[Topic], dest: [u8; 32]) -> Result<Vec<Vec<u8>>> {
-		self.collect_statements(Some(dest), match_all_topics, |statement| statement.into_data())
+		self.collect_statements(Some(dest), match_all_topics, |statement| Ok(statement.into_data()))
 	}
 
 	/// Return the decrypted data of all known statements whose decryption key is identified as
 	/// `dest`. The key must be available to the client.
 	fn posted_clear(&self, match_all_topics: &[Topic], dest: [u8; 32]) -> Result<Vec<Vec<u8>>> {
-		self.collect_statements(Some(dest), match_all_topics, |statement| statement.into_data())
+		self.collect_statements(Some(dest), match_all_topics, |statement| {
+			self.decrypt_statement(&statement)
+		})
 	}
 
 	/// Submit a statement to the store. Validates the statement and returns validation result.
@@ -823,18 +1026,21 @@ impl StatementStore for Store {
 
 		let current_time = self.timestamp();
 		let mut commit = Vec::new();
-		{
+		let evicted = {
 			let mut index = self.index.write();
 
-			let evicted =
+			let (evicted, sequence) =
 				match index.insert(hash, &statement, &account_id, &validation, current_time) {
 					MaybeInserted::Ignored => return SubmitResult::Ignored,
-					MaybeInserted::Inserted(evicted) => evicted,
+					MaybeInserted::Inserted(evicted, sequence) => (evicted, sequence),
 				};
 
 			commit.push((col::STATEMENTS, hash.to_vec(), Some(statement.encode())));
-			for hash in evicted {
+			commit.push((col::META, sequence_key(&hash), Some(sequence.encode())));
+			commit.push((col::META, KEY_LAST_SEQUENCE.to_vec(), Some(sequence.encode())));
+			for (hash, _) in &evicted {
 				commit.push((col::STATEMENTS, hash.to_vec(), None));
+				commit.push((col::META, sequence_key(hash), None));
 				commit.push((col::EXPIRED, hash.to_vec(), Some((hash, current_time).encode())));
 			}
 			if let Err(e) = self.db.commit(commit) {
@@ -846,8 +1052,17 @@ impl StatementStore for Store {
 				);
 				return SubmitResult::InternalError(Error::Db(e.to_string()))
 			}
-		} // Release index lock
-		self.metrics.report(|metrics| metrics.submitted_statements.inc());
+			// Notify while holding the index lock, so that subscribers observe the statements in
+			// the order of their sequence numbers.
+			self.notify_subscribers(sequence, &statement);
+			evicted
+		}; // Release index lock
+		self.metrics.report(|metrics| {
+			metrics.submitted_statements.inc();
+			for (_, reason) in &evicted {
+				metrics.statements_evicted.with_label_values(&[reason.as_str()]).inc();
+			}
+		});
 		let network_priority = NetworkPriority::High;
 		log::trace!(target: LOG_TARGET, "Statement submitted: {:?}", HexDisplay::from(&hash));
 		SubmitResult::New(network_priority)
@@ -861,6 +1076,7 @@ impl StatementStore for Store {
 			if index.make_expired(hash, current_time) {
 				let commit = [
 					(col::STATEMENTS, hash.to_vec(), None),
+					(col::META, sequence_key(hash), None),
 					(col::EXPIRED, hash.to_vec(), Some((hash, current_time).encode())),
 				];
 				if let Err(e) = self.db.commit(commit) {
@@ -876,16 +1092,62 @@ impl StatementStore for Store {
 		}
 		Ok(())
 	}
+
+	/// Subscribe to the statements accepted by the store that match the filter.
+	fn subscribe_statements(
+		&self,
+		filter: StatementFilter,
+		from: Option<u64>,
+	) -> Result<StatementStream> {
+		// Statements can't be accepted while the index is locked, so none of them are missed
+		// between the replay and the registration of the subscriber.
+		let index = self.index.read();
+		let mut subscribers = self.subscribers.lock();
+		subscribers.retain(|subscriber| !subscriber.sender.is_closed());
+		if subscribers.len() >= index.options.max_subscribers {
+			log::debug!(target: LOG_TARGET, "Rejecting statement subscriber: too many subscribers");
+			return Err(Error::TooManySubscribers)
+		}
+
+		let mut replay = Vec::new();
+		if let Some(from) = from {
+			for (sequence, hash) in
+				index.by_sequence.range((Bound::Excluded(from), Bound::Unbounded))
+			{
+				match self.statement(hash)? {
+					Some(statement) if filter.matches(&statement) =>
+						replay.push(SequencedStatement { sequence: *sequence, statement }),
+					_ => {},
+				}
+			}
+		}
+
+		let (mut sender, receiver) = mpsc::channel(replay.len() + SUBSCRIPTION_BUFFER);
+		for statement in replay {
+			sender
+				.try_send(statement)
+				.expect("The channel has capacity for all replayed statements; qed");
+		}
+		subscribers.push(Subscriber { filter, sender });
+		log::trace!(target: LOG_TARGET, "New statement subscriber from {:?}", from);
+		Ok(receiver)
+	}
 }
 
 #[cfg(test)]
 mod tests {
-	use crate::Store;
-	use sp_core::Pair;
+	use crate::{
+		Admission, EvictionReason, MaybeInserted, Options, StatementInfo, StatementPolicy, Store,
+		StoreView,
+	};
+	use sc_keystore::LocalKeystore;
+	use sp_core::{crypto::key_types::STATEMENT, Pair};
+	use sp_keystore::Keystore;
 	use sp_statement_store::{
 		runtime_api::{InvalidStatement, ValidStatement, ValidateStatement},
-		AccountId, Channel, DecryptionKey, NetworkPriority, Proof, SignatureVerificationResult,
-		Statement, StatementSource, StatementStore, SubmitResult, Topic,
+		AccountId, Channel, DecryptionKey, Error, NetworkPriority, Proof,
+		SignatureVerificationResult, Statement, StatementFilter, StatementSource, StatementStore,
+		SubmitResult, Topic,
 	};
 
 	type Extrinsic = sp_runtime::OpaqueExtrinsic;
@@ -975,9 +1237,10 @@ mod tests {
 		let temp_dir = tempfile::Builder::new().tempdir().expect("Error creating test dir");
 
 		let client = std::sync::Arc::new(TestClient);
+		let keystore = std::sync::Arc::new(LocalKeystore::in_memory());
 		let mut path: std::path::PathBuf = temp_dir.path().into();
 		path.push("db");
-		let store = Store::new(&path, Default::default(), client, None).unwrap();
+		let store = Store::new(&path, Default::default(), client, keystore, None).unwrap();
 		(store, temp_dir) // return order is important. Store must be dropped before TempDir
 	}
 
@@ -1083,9 +1346,10 @@ mod tests {
 		drop(store);
 
 		let client = std::sync::Arc::new(TestClient);
+		let keystore = std::sync::Arc::new(LocalKeystore::in_memory());
 		let mut path: std::path::PathBuf = temp.path().into();
 		path.push("db");
-		let store = Store::new(&path, Default::default(), client, None).unwrap();
+		let store = Store::new(&path, Default::default(), client, keystore, None).unwrap();
 		assert_eq!(store.statements().unwrap().len(), 3);
 		assert_eq!(store.broadcasts(&[]).unwrap().len(), 3);
 		assert_eq!(store.statement(&statement1.hash()).unwrap(), Some(statement1));
@@ -1134,6 +1398,66 @@ mod tests {
 		assert_topics(&[0, 1, 2, 3, 42], None, &[]);
 	}
 
+	#[test]
+	fn posted_clear_decrypts_with_keystore_keys() {
+		let (store, _temp) = test_store();
+		let sr25519_key = store
+			.keystore
+			.sr25519_generate_new(sp_core::crypto::key_types::STATEMENT, None)
+			.unwrap();
+		let ed25519_key = store
+			.keystore
+			.ed25519_generate_new(sp_core::crypto::key_types::STATEMENT, None)
+			.unwrap();
+		let (unknown_pair, _) = sp_core::sr25519::Pair::generate();
+		let kp = sp_core::ed25519::Pair::from_string("//Alice", None).unwrap();
+
+		let mut statement1 = Statement::new();
+		statement1.set_topic(0, topic(0));
+		statement1.encrypt_sr25519(&[1], &sr25519_key).unwrap();
+		statement1.sign_ed25519_private(&kp);
+		let mut statement2 = Statement::new();
+		statement2.set_topic(0, topic(0));
+		statement2.encrypt_ed25519(&[2], &ed25519_key).unwrap();
+		statement2.sign_ed25519_private(&kp);
+		let mut statement3 = Statement::new();
+		statement3.encrypt_sr25519(&[3], &unknown_pair.public()).unwrap();
+		statement3.sign_ed25519_private(&kp);
+		for s in [&statement1, &statement2, &statement3] {
+			store.submit(s.clone(), StatementSource::Network);
+		}
+
+		assert_eq!(store.posted_clear(&[topic(0)], sr25519_key.0).unwrap(), vec![vec![1]]);
+		assert_eq!(store.posted_clear(&[], ed25519_key.0).unwrap(), vec![vec![2]]);
+		assert_eq!(store.posted_clear(&[topic(1)], ed25519_key.0).unwrap(), Vec::<Vec<u8>>::new());
+		// The key is not in the keystore, only the encrypted data is available.
+		assert!(store.posted_clear(&[], unknown_pair.public().0).unwrap().is_empty());
+		assert_eq!(
+			store.posted(&[], unknown_pair.public().0).unwrap(),
+			vec![statement3.data().unwrap().clone()]
+		);
+	}
+
+	#[test]
+	fn posted_clear_fails_if_keystore_cannot_decrypt() {
+		let temp_dir = tempfile::Builder::new().tempdir().expect("Error creating test dir");
+		// The in-memory keystore holds the key but doesn't support ECIES decryption.
+		let keystore = std::sync::Arc::new(sp_keystore::testing::MemoryKeystore::new());
+		let key = keystore.sr25519_generate_new(STATEMENT, None).unwrap();
+		let client = std::sync::Arc::new(TestClient);
+		let store =
+			Store::new(&temp_dir.path().join("db"), Default::default(), client, keystore, None)
+				.unwrap();
+
+		let kp = sp_core::ed25519::Pair::from_string("//Alice", None).unwrap();
+		let mut statement = Statement::new();
+		statement.encrypt_sr25519(&[1], &key).unwrap();
+		statement.sign_ed25519_private(&kp);
+		store.submit(statement, StatementSource::Network);
+
+		assert!(matches!(store.posted_clear(&[], key.0), Err(Error::Keystore(_))));
+	}
+
 	#[test]
 	fn constraints() {
 		let (store, _temp) = test_store();
@@ -1217,10 +1541,237 @@ mod tests {
 		drop(store);
 
 		let client = std::sync::Arc::new(TestClient);
+		let keystore = std::sync::Arc::new(LocalKeystore::in_memory());
 		let mut path: std::path::PathBuf = temp.path().into();
 		path.push("db");
-		let store = Store::new(&path, Default::default(), client, None).unwrap();
+		let store = Store::new(&path, Default::default(), client, keystore, None).unwrap();
 		assert_eq!(store.statements().unwrap().len(), 0);
 		assert_eq!(store.index.read().expired.len(), 0);
 	}
+
+	#[test]
+	fn subscribe_statements() {
+		let (store, _temp) = test_store();
+		let statement0 = signed_statement_with_topics(0, &[topic(0)], None);
+		let statement1 = signed_statement_with_topics(1, &[topic(0)], Some(dec_key(1)));
+		let statement2 = signed_statement_with_topics(2, &[topic(0), topic(1)], None);
+		let statement3 = signed_statement_with_topics(3, &[topic(1)], None);
+		let statement4 = signed_statement_with_topics(4, &[topic(0)], Some(dec_key(1)));
+
+		store.submit(statement0.clone(), StatementSource::Network);
+		store.submit(statement1.clone(), StatementSource::Network);
+
+		let broadcasts = StatementFilter { match_all_topics: vec![topic(0)], dest: None };
+		let posted = StatementFilter { match_all_topics: vec![], dest: Some(dec_key(1)) };
+
+		// Replay all statements in the store.
+		let mut replayed = store.subscribe_statements(broadcasts.clone(), Some(0)).unwrap();
+		let mut live = store.subscribe_statements(broadcasts, None).unwrap();
+		let mut live_posted = store.subscribe_statements(posted.clone(), Some(1)).unwrap();
+
+		let notification = replayed.try_next().unwrap().unwrap();
+		assert_eq!((notification.sequence, notification.statement), (1, statement0));
+		assert!(replayed.try_next().is_err());
+		assert!(live.try_next().is_err());
+		let notification = live_posted.try_next().unwrap().unwrap();
+		assert_eq!((notification.sequence, notification.statement), (2, statement1));
+		assert!(live_posted.try_next().is_err());
+
+		store.submit(statement2.clone(), StatementSource::Network);
+		store.submit(statement3, StatementSource::Network);
+		store.submit(statement4.clone(), StatementSource::Network);
+
+		// Only the statements matching the filter are reported.
+		for subscriber in [&mut replayed, &mut live] {
+			let notification = subscriber.try_next().unwrap().unwrap();
+			assert_eq!((notification.sequence, notification.statement), (3, statement2.clone()));
+			assert!(subscriber.try_next().is_err());
+		}
+		let notification = live_posted.try_next().unwrap().unwrap();
+		assert_eq!((notification.sequence, notification.statement), (5, statement4.clone()));
+		assert!(live_posted.try_next().is_err());
+
+		// Reconnecting subscribers resume after the last reported statement.
+		let mut resumed = store.subscribe_statements(posted, Some(2)).unwrap();
+		let notification = resumed.try_next().unwrap().unwrap();
+		assert_eq!((notification.sequence, notification.statement), (5, statement4));
+		assert!(resumed.try_next().is_err());
+
+		// Removed statements are not replayed and dropped subscribers are cleaned up.
+		drop(replayed);
+		store.remove(&statement2.hash()).unwrap();
+		let broadcasts = StatementFilter { match_all_topics: vec![topic(0)], dest: None };
+		let mut resumed = store.subscribe_statements(broadcasts, Some(1)).unwrap();
+		assert!(resumed.try_next().is_err());
+		store.submit(signed_statement_with_topics(5, &[topic(0)], None), StatementSource::Network);
+		assert_eq!(store.subscribers.lock().len(), 4);
+		assert_eq!(resumed.try_next().unwrap().unwrap().sequence, 6);
+	}
+
+	#[test]
+	fn sequences_survive_restart() {
+		let (store, temp) = test_store();
+		let statement0 = signed_statement_with_topics(0, &[topic(0)], None);
+		let statement1 = signed_statement_with_topics(1, &[topic(0)], None);
+		let statement2 = signed_statement_with_topics(2, &[topic(0)], None);
+		store.submit(statement0, StatementSource::Network);
+		store.submit(statement1.clone(), StatementSource::Network);
+		store.submit(statement2.clone(), StatementSource::Network);
+		// The statement with the last sequence number is removed, its number is not reused.
+		store.remove(&statement2.hash()).unwrap();
+		drop(store);
+
+		let client = std::sync::Arc::new(TestClient);
+		let keystore = std::sync::Arc::new(LocalKeystore::in_memory());
+		let path = temp.path().join("db");
+		let store = Store::new(&path, Default::default(), client, keystore, None).unwrap();
+		let broadcasts = StatementFilter { match_all_topics: vec![topic(0)], dest: None };
+		let mut resumed = store.subscribe_statements(broadcasts, Some(1)).unwrap();
+		let notification = resumed.try_next().unwrap().unwrap();
+		assert_eq!((notification.sequence, notification.statement), (2, statement1));
+		assert!(resumed.try_next().is_err());
+
+		store.submit(signed_statement_with_topics(3, &[topic(0)], None), StatementSource::Network);
+		assert_eq!(resumed.try_next().unwrap().unwrap().sequence, 4);
+	}
+
+	#[test]
+	fn subscribers_are_limited() {
+		let temp_dir = tempfile::Builder::new().tempdir().expect("Error creating test dir");
+		let client = std::sync::Arc::new(TestClient);
+		let keystore = std::sync::Arc::new(LocalKeystore::in_memory());
+		let options = Options { max_subscribers: 2, ..Default::default() };
+		let store =
+			Store::new(&temp_dir.path().join("db"), options, client, keystore, None).unwrap();
+
+		let filter = StatementFilter { match_all_topics: vec![], dest: None };
+		let first = store.subscribe_statements(filter.clone(), None).unwrap();
+		let _second = store.subscribe_statements(filter.clone(), None).unwrap();
+		assert!(matches!(
+			store.subscribe_statements(filter.clone(), None),
+			Err(Error::TooManySubscribers)
+		));
+
+		// Subscribers that are gone no longer count.
+		drop(first);
+		assert!(store.subscribe_statements(filter, None).is_ok());
+	}
+
+	#[test]
+	fn eviction_reasons() {
+		let (store, _temp) = test_store();
+		let source = StatementSource::Network;
+		let ok = SubmitResult::New(NetworkPriority::High);
+
+		// Account 3 (limit = 3 msg, 1000 bytes)
+		assert_eq!(store.submit(statement(3, 2, Some(1), 300), source), ok);
+		assert_eq!(store.submit(statement(3, 3, Some(2), 300), source), ok);
+		assert_eq!(store.submit(statement(3, 4, Some(3), 300), source), ok);
+
+		// Replaces the statement on channel 1 and evicts the lowest priority statement left.
+		let new = statement(3, 5, Some(1), 500);
+		let validation = ValidStatement { max_count: 3, max_size: 1000 };
+		let inserted = store.index.write().insert(new.hash(), &new, &account(3), &validation, 0);
+		let MaybeInserted::Inserted(evicted, _) = inserted else {
+			panic!("Statement must be inserted")
+		};
+		assert_eq!(
+			evicted,
+			vec![
+				(statement(3, 2, Some(1), 300).hash(), EvictionReason::ChannelReplaced),
+				(statement(3, 3, Some(2), 300).hash(), EvictionReason::AccountQuota),
+			]
+		);
+	}
+
+	#[test]
+	fn custom_policy() {
+		// Admits statements with a minimum priority, regardless of the runtime constraints.
+		struct MinPriority(u32);
+
+		impl StatementPolicy for MinPriority {
+			fn admit(
+				&self,
+				_store: &dyn StoreView,
+				statement: &StatementInfo,
+				_validation: &ValidStatement,
+			) -> Admission {
+				if statement.priority >= self.0 {
+					Admission::Accept(Vec::new())
+				} else {
+					Admission::Ignore
+				}
+			}
+		}
+
+		let temp_dir = tempfile::Builder::new().tempdir().expect("Error creating test dir");
+		let client = std::sync::Arc::new(TestClient);
+		let keystore = std::sync::Arc::new(LocalKeystore::in_memory());
+		let mut path: std::path::PathBuf = temp_dir.path().into();
+		path.push("db");
+		let options = Options::default().with_policy(MinPriority(2));
+		let store = Store::new(&path, options, client, keystore, None).unwrap();
+
+		let source = StatementSource::Network;
+		assert_eq!(store.submit(statement(1, 1, None, 100), source), SubmitResult::Ignored);
+		// Account 1 is limited to 1 message by the runtime, which the policy does not enforce.
+		let ok = SubmitResult::New(NetworkPriority::High);
+		assert_eq!(store.submit(statement(1, 2, None, 100), source), ok);
+		assert_eq!(store.submit(statement(1, 3, None, 100), source), ok);
+		assert_eq!(store.index.read().entries.len(), 2);
+		drop(store);
+	}
+
+	#[test]
+	fn misbehaving_policy_cannot_break_store_invariants() {
+		// Admits every statement without evicting anything.
+		struct AcceptAll;
+
+		impl StatementPolicy for AcceptAll {
+			fn admit(
+				&self,
+				_store: &dyn StoreView,
+				_statement: &StatementInfo,
+				_validation: &ValidStatement,
+			) -> Admission {
+				Admission::Accept(Vec::new())
+			}
+		}
+
+		let temp_dir = tempfile::Builder::new().tempdir().expect("Error creating test dir");
+		let client = std::sync::Arc::new(TestClient);
+		let keystore = std::sync::Arc::new(LocalKeystore::in_memory());
+		let options = Options::default().with_policy(AcceptAll);
+		let store =
+			Store::new(&temp_dir.path().join("db"), options, client, keystore, None).unwrap();
+		store.index.write().options.max_total_statements = 3;
+		store.index.write().options.max_total_size = 1000;
+
+		let source = StatementSource::Network;
+		let ok = SubmitResult::New(NetworkPriority::High);
+
+		// The previous statement of the channel is evicted, even at a lower priority.
+		assert_eq!(store.submit(statement(5, 2, Some(1), 100), source), ok);
+		assert_eq!(store.submit(statement(5, 1, Some(1), 100), source), ok);
+		{
+			let index = store.index.read();
+			assert_eq!(index.entries.len(), 1);
+			assert_eq!(index.total_size, 100);
+			assert_eq!(index.accounts[&account(5)].by_priority.len(), 1);
+			assert_eq!(
+				index.accounts[&account(5)].channels[&channel(1)].hash,
+				statement(5, 1, Some(1), 100).hash(),
+			);
+			assert!(index.expired.contains_key(&statement(5, 2, Some(1), 100).hash()));
+		}
+
+		// The global limits are enforced.
+		assert_eq!(store.submit(statement(5, 1, None, 1000), source), SubmitResult::Ignored);
+		assert_eq!(store.submit(statement(5, 2, None, 100), source), ok);
+		assert_eq!(store.submit(statement(5, 3, None, 100), source), ok);
+		assert_eq!(store.submit(statement(5, 4, None, 100), source), SubmitResult::Ignored);
+		assert_eq!(store.index.read().entries.len(), 3);
+		assert_eq!(store.index.read().total_size, 300);
+		drop(store);
+	}
 }
@@ -0,0 +1,229 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Admission and eviction policy of the statement store.
+//!
+//! Each time a new statement is submitted, the store asks its [`StatementPolicy`] whether the
+//! statement should be admitted and which statements must be evicted to make room for it. The
+//! policy is given a read-only [`StoreView`] of the statements currently in the store.
+//!
+//! [`DefaultPolicy`] implements the constraints described in the crate documentation.
+
+use crate::LOG_TARGET;
+use sp_core::hexdisplay::HexDisplay;
+use sp_statement_store::{runtime_api::ValidStatement, AccountId, Channel, Hash};
+
+/// The reason a statement was evicted from the store.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EvictionReason {
+	/// Replaced by a higher priority statement on the same channel.
+	ChannelReplaced,
+	/// Evicted to satisfy the quota of the account.
+	AccountQuota,
+	/// Evicted to satisfy the global limits of the store.
+	GlobalLimit,
+}
+
+impl EvictionReason {
+	/// Label of the reason, as reported by the metrics.
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			Self::ChannelReplaced => "channel_replaced",
+			Self::AccountQuota => "account_quota",
+			Self::GlobalLimit => "global_limit",
+		}
+	}
+}
+
+/// Information about a statement that is relevant to the policy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatementInfo {
+	/// Hash of the statement.
+	pub hash: Hash,
+	/// Account that submitted the statement.
+	pub account: AccountId,
+	/// Priority of the statement.
+	pub priority: u32,
+	/// Channel of the statement, if any.
+	pub channel: Option<Channel>,
+	/// Size of the statement data.
+	pub data_size: usize,
+}
+
+/// The outcome of the admission of a statement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Admission {
+	/// The statement is admitted after evicting the given statements.
+	Accept(Vec<(Hash, EvictionReason)>),
+	/// The statement is not admitted. No statements are evicted.
+	Ignore,
+}
+
+/// Read-only view of the statements in the store.
+pub trait StoreView {
+	/// Total number of statements in the store.
+	fn statement_count(&self) -> usize;
+
+	/// Total data size of the statements in the store.
+	fn total_size(&self) -> usize;
+
+	/// Maximum number of statements allowed in the store, as configured by `Options`.
+	fn max_total_statements(&self) -> usize;
+
+	/// Maximum total data size allowed in the store, as configured by `Options`.
+	fn max_total_size(&self) -> usize;
+
+	/// Number of statements of the account.
+	fn account_statement_count(&self, account: &AccountId) -> usize;
+
+	/// Total data size of the statements of the account.
+	fn account_data_size(&self, account: &AccountId) -> usize;
+
+	/// Statements of the account, ordered by increasing priority.
+	fn account_statements<'a>(
+		&'a self,
+		account: &AccountId,
+	) -> Box<dyn Iterator<Item = StatementInfo> + 'a>;
+
+	/// The statement of the account on the given channel, if any.
+	fn channel_statement(&self, account: &AccountId, channel: &Channel) -> Option<StatementInfo>;
+}
+
+/// Admission and eviction policy of the statement store.
+///
+/// Whatever the policy decides, the store keeps a single statement per account and channel, and
+/// stays within its global limits: the statement previously on the channel is evicted along with
+/// the ones returned by the policy, and a statement that would exceed the limits is ignored.
+pub trait StatementPolicy: Send + Sync {
+	/// Decide whether the `statement` is admitted into the store.
+	///
+	/// `validation` contains the per-account constraints computed by the runtime.
+	fn admit(
+		&self,
+		store: &dyn StoreView,
+		statement: &StatementInfo,
+		validation: &ValidStatement,
+	) -> Admission;
+}
+
+/// The default policy of the statement store.
+///
+/// * The statement replaces a lower priority statement of the same account on the same channel.
+/// * Lower priority statements of the account are evicted until the account constraints returned by
+///   the runtime are satisfied.
+/// * The statement is ignored if the global limits of the store are exceeded.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultPolicy;
+
+impl StatementPolicy for DefaultPolicy {
+	fn admit(
+		&self,
+		store: &dyn StoreView,
+		statement: &StatementInfo,
+		validation: &ValidStatement,
+	) -> Admission {
+		let hash = &statement.hash;
+		let (max_size, max_count) = (validation.max_size as usize, validation.max_count as usize);
+		if statement.data_size > max_size {
+			log::debug!(
+				target: LOG_TARGET,
+				"Ignored oversize message: {:?} ({} bytes)",
+				HexDisplay::from(hash),
+				statement.data_size,
+			);
+			return Admission::Ignore
+		}
+
+		let mut evicted = Vec::new();
+		let mut would_free_size = 0;
+		// It may happen that we can't delete enough lower priority messages
+		// to satisfy size constraints. We check for that before deleting anything,
+		// taking into account channel message replacement.
+		if let Some(channel) = &statement.channel {
+			if let Some(channel_record) = store.channel_statement(&statement.account, channel) {
+				if statement.priority <= channel_record.priority {
+					// Trying to replace channel message with lower priority
+					log::debug!(
+						target: LOG_TARGET,
+						"Ignored lower priority channel message: {:?} {:?} <= {:?}",
+						HexDisplay::from(hash),
+						statement.priority,
+						channel_record.priority,
+					);
+					return Admission::Ignore
+				}
+				// Would replace channel message. Still need to check for size constraints
+				// below.
+				log::debug!(
+					target: LOG_TARGET,
+					"Replacing higher priority channel message: {:?} ({:?}) > {:?} ({:?})",
+					HexDisplay::from(hash),
+					statement.priority,
+					HexDisplay::from(&channel_record.hash),
+					channel_record.priority,
+				);
+				would_free_size += channel_record.data_size;
+				evicted.push((channel_record.hash, EvictionReason::ChannelReplaced));
+			}
+		}
+
+		// Check if we can evict enough lower priority statements to satisfy constraints
+		let account_size = store.account_data_size(&statement.account);
+		let account_count = store.account_statement_count(&statement.account);
+		for entry in store.account_statements(&statement.account) {
+			if (account_size - would_free_size + statement.data_size <= max_size) &&
+				account_count + 1 - evicted.len() <= max_count
+			{
+				// Satisfied
+				break
+			}
+			if evicted.iter().any(|(evicted_hash, _)| evicted_hash == &entry.hash) {
+				// Already accounted for above
+				continue
+			}
+			if entry.priority >= statement.priority {
+				log::debug!(
+					target: LOG_TARGET,
+					"Ignored message due to constraints {:?} {:?} < {:?}",
+					HexDisplay::from(hash),
+					statement.priority,
+					entry.priority,
+				);
+				return Admission::Ignore
+			}
+			evicted.push((entry.hash, EvictionReason::AccountQuota));
+			would_free_size += entry.data_size;
+		}
+
+		// Now check global constraints as well.
+		if !((store.total_size() - would_free_size + statement.data_size <= store.max_total_size()) &&
+			store.statement_count() + 1 - evicted.len() <= store.max_total_statements())
+		{
+			log::debug!(
+				target: LOG_TARGET,
+				"Ignored statement {} because the store is full (size={}, count={})",
+				HexDisplay::from(hash),
+				store.total_size(),
+				store.statement_count(),
+			);
+			return Admission::Ignore
+		}
+
+		Admission::Accept(evicted)
+	}
+}
@@ -69,6 +69,15 @@ pub enum Error {
 
 	#[error("The pool is not accepting future transactions")]
 	RejectedFutureTransaction,
+
+	/// The sender of the transaction already has as many transactions in the pool as allowed.
+	#[error("The sender of the transaction reached its limit of transactions in the pool")]
+	SenderLimitReached,
+
+	/// The capacity of the pool for normal transactions is exhausted and the transaction doesn't
+	/// outrank any of them. The remaining capacity is reserved for operational transactions.
+	#[error("The pool capacity for normal transactions is exhausted")]
+	LaneFull,
 }
 
 /// Transaction pool error conversion.
@@ -128,6 +128,8 @@ pub enum TransactionStatus<Hash, BlockHash> {
 	Usurped(Hash),
 	/// Transaction has been dropped from the pool because of the limit.
 	Dropped,
+	/// Transaction has been removed from the pool on request of its sender.
+	Cancelled,
 	/// Transaction is no longer valid in the current state.
 	Invalid,
 }
@@ -238,6 +240,25 @@ pub trait TransactionPool: Send + Sync {
 		>,
 	>;
 
+	/// Get an iterator for ready transactions ordered by priority, that are valid on top of
+	/// the given block.
+	///
+	/// Fork-aware pools serve this for any fork head. Other pools ignore the block hash and
+	/// behave like [`Self::ready_at`].
+	fn ready_at_block(
+		&self,
+		_at: BlockHash<Self>,
+		number: NumberFor<Self::Block>,
+	) -> Pin<
+		Box<
+			dyn Future<
+					Output = Box<dyn ReadyTransactions<Item = Arc<Self::InPoolTransaction>> + Send>,
+				> + Send,
+		>,
+	> {
+		self.ready_at(number)
+	}
+
 	/// Get an iterator for ready transactions ordered by priority.
 	fn ready(&self) -> Box<dyn ReadyTransactions<Item = Arc<Self::InPoolTransaction>> + Send>;
 
@@ -245,6 +266,15 @@ pub trait TransactionPool: Send + Sync {
 	/// Remove transactions identified by given hashes (and dependent transactions) from the pool.
 	fn remove_invalid(&self, hashes: &[TxHash<Self>]) -> Vec<Arc<Self::InPoolTransaction>>;
 
+	// *** RPC
+	/// Remove the transaction identified by the given hash (and dependent transactions) from the
+	/// pool on request of its sender.
+	///
+	/// The watchers of the removed transactions are notified with
+	/// [`TransactionStatus::Cancelled`]. The transaction is temporarily banned, so that it is not
+	/// reimported when received from the network again.
+	fn cancel(&self, hash: &TxHash<Self>) -> Vec<Arc<Self::InPoolTransaction>>;
+
 	// *** logging
 	/// Returns pool status.
 	fn status(&self) -> PoolStatus;
@@ -262,6 +292,11 @@ pub trait TransactionPool: Send + Sync {
 
 	/// Return specific ready transaction by hash, if there is one.
 	fn ready_transaction(&self, hash: &TxHash<Self>) -> Option<Arc<Self::InPoolTransaction>>;
+
+	/// Return specific ready or future transaction by hash, if there is one.
+	fn pending_transaction(&self, hash: &TxHash<Self>) -> Option<Arc<Self::InPoolTransaction>> {
+		self.ready_transaction(hash)
+	}
 }
 
 /// An iterator of ready transactions.
@@ -303,6 +338,13 @@ pub enum ChainEvent<B: BlockT> {
 		/// Path from old finalized to new finalized parent.
 		tree_route: Arc<[B::Hash]>,
 	},
+	/// New block, that is not the new best block, have been added to the chain.
+	///
+	/// Only used by fork-aware pools, which keep a view of the pool at every fork head.
+	NewBlock {
+		/// Hash of the block.
+		hash: B::Hash,
+	},
 }
 
 /// Trait for transaction pool maintenance.
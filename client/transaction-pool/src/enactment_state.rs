@@ -83,6 +83,11 @@ where
 		EnactmentState { recent_best_block, recent_finalized_block }
 	}
 
+	/// Returns the recent best block.
+	pub fn recent_best_block(&self) -> Block::Hash {
+		self.recent_best_block
+	}
+
 	/// Returns the recently finalized block.
 	pub fn recent_finalized_block(&self) -> Block::Hash {
 		self.recent_finalized_block
@@ -104,6 +109,7 @@ where
 		let (new_hash, current_hash, finalized) = match event {
 			ChainEvent::NewBestBlock { hash, .. } => (*hash, self.recent_best_block, false),
 			ChainEvent::Finalized { hash, .. } => (*hash, self.recent_finalized_block, true),
+			ChainEvent::NewBlock { .. } => return Ok(EnactmentAction::Skip),
 		};
 
 		// do not proceed with txpool maintain if block distance is to high
@@ -181,6 +187,7 @@ where
 		match event {
 			ChainEvent::NewBestBlock { hash, .. } => self.recent_best_block = *hash,
 			ChainEvent::Finalized { hash, .. } => self.recent_finalized_block = *hash,
+			ChainEvent::NewBlock { .. } => {},
 		};
 		log::debug!(
 			target: LOG_TARGET,
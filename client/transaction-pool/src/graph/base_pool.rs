@@ -20,7 +20,11 @@
 //!
 //! For a more full-featured pool, have a look at the `pool` module.
 
-use std::{cmp::Ordering, collections::HashSet, fmt, hash, sync::Arc};
+use std::{
+	collections::{hash_map, BTreeMap, HashMap, HashSet},
+	fmt, hash,
+	sync::Arc,
+};
 
 use crate::LOG_TARGET;
 use log::{debug, trace, warn};
@@ -37,7 +41,7 @@ use sp_runtime::{
 
 use super::{
 	future::{FutureTransactions, WaitingTransaction},
-	ready::{BestIterator, ReadyTransactions, TransactionRef},
+	ready::{BestIterator, ReadyTransactions},
 };
 
 /// Successful import result.
@@ -219,6 +223,14 @@ pub struct BasePool<Hash: hash::Hash + Eq, Ex> {
 	/// transactions to future in case they were just stuck in verification.
 	recently_pruned: [HashSet<Tag>; RECENTLY_PRUNED_TAGS],
 	recently_pruned_index: usize,
+	/// Limits of the transactions of a single sender.
+	sender_limits: SenderLimits,
+	/// Priority lanes of the ready queue.
+	lanes: Lanes,
+	/// Limit of the ready transactions in the normal lane.
+	normal_lane: Limit,
+	/// Usage of the queues by sender and by lane.
+	usage: Usage<Hash>,
 }
 
 impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> Default for BasePool<Hash, Ex> {
@@ -236,7 +248,31 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 			ready: Default::default(),
 			recently_pruned: Default::default(),
 			recently_pruned_index: 0,
+			sender_limits: Default::default(),
+			lanes: Default::default(),
+			normal_lane: Limit::unlimited(),
+			usage: Default::default(),
+		}
+	}
+
+	/// Set the per-sender limits and priority lanes of the pool.
+	///
+	/// The capacity reserved for the operational lane is deducted from the limit of the whole
+	/// `ready` queue to get the capacity of the normal lane. Must be called before any transaction
+	/// is imported.
+	pub fn with_limits(mut self, sender_limits: SenderLimits, lanes: Lanes, ready: &Limit) -> Self {
+		if lanes.reserves_capacity() {
+			self.normal_lane = lanes.normal_limit(ready);
 		}
+		self.sender_limits = sender_limits;
+		self.lanes = lanes;
+		self
+	}
+
+	/// Set the policy for replacing ready transactions.
+	pub fn with_replacement_policy(mut self, replacement: ReplacementPolicy) -> Self {
+		self.ready.set_replacement_policy(replacement);
+		self
 	}
 
 	/// Temporary enables future transactions, runs closure and then restores
@@ -274,6 +310,7 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 
 		let tx = WaitingTransaction::new(tx, self.ready.provided_tags(), &self.recently_pruned);
 		trace!(target: LOG_TARGET, "[{:?}] {:?}", tx.transaction.hash, tx);
+		self.check_limits(&tx)?;
 		debug!(
 			target: LOG_TARGET,
 			"[{:?}] Importing to {}",
@@ -288,6 +325,7 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 			}
 
 			let hash = tx.transaction.hash.clone();
+			self.usage.insert(&tx.transaction, false, &self.sender_limits, &self.lanes);
 			self.future.import(tx);
 			return Ok(Imported::Future { hash })
 		}
@@ -295,6 +333,82 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 		self.import_to_ready(tx)
 	}
 
+	/// Makes sure that a transaction entering the pool stays within the per-sender limits and the
+	/// capacity of its lane.
+	///
+	/// Transactions in the operational lane are not subject to these limits.
+	fn check_limits(&self, tx: &WaitingTransaction<Hash, Ex>) -> error::Result<()> {
+		let transaction = &*tx.transaction;
+		if self.lanes.lane(transaction) == Lane::Operational {
+			return Ok(())
+		}
+
+		if self.exceeds_sender_limit(transaction, tx.is_ready()) {
+			debug!(target: LOG_TARGET, "[{:?}] Sender limit reached", transaction.hash);
+			return Err(error::Error::SenderLimitReached)
+		}
+
+		if tx.is_ready() {
+			let (count, bytes) = self
+				.replaced_by(transaction)
+				.filter(|replaced| self.lanes.lane(replaced) == Lane::Normal)
+				.fold(self.usage.normal_lane, |usage, replaced| without(usage, replaced));
+			if self.normal_lane.is_exceeded(count + 1, bytes + transaction.bytes) {
+				// The transaction may still push out a worse one from the lane.
+				let outranks = self
+					.usage
+					.worst_ready(true)
+					.map_or(false, |(_, priority)| priority < transaction.priority);
+				if !outranks {
+					debug!(target: LOG_TARGET, "[{:?}] Normal lane is full", transaction.hash);
+					return Err(error::Error::LaneFull)
+				}
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Returns true if the transaction, entering the ready or the future queue, doesn't fit
+	/// within the limits of its sender.
+	///
+	/// The ready transactions that it replaces are not counted.
+	fn exceeds_sender_limit(&self, transaction: &Transaction<Hash, Ex>, ready: bool) -> bool {
+		let Some(sender) = self.sender_limits.sender(transaction) else { return false };
+		let (limit, (count, bytes)) = if ready {
+			let usage = self
+				.replaced_by(transaction)
+				.filter(|replaced| self.sender_limits.sender(replaced) == Some(sender))
+				.fold(self.usage.sender(sender, true), |usage, replaced| without(usage, replaced));
+			(&self.sender_limits.ready, usage)
+		} else {
+			(&self.sender_limits.future, self.usage.sender(sender, false))
+		};
+		limit.is_exceeded(count + 1, bytes + transaction.bytes)
+	}
+
+	/// Returns true if the future transaction can't be promoted to the ready queue without
+	/// exceeding the ready limits of its sender.
+	fn exceeds_promotion_limit(&self, transaction: &Transaction<Hash, Ex>) -> bool {
+		self.lanes.lane(transaction) == Lane::Normal && self.exceeds_sender_limit(transaction, true)
+	}
+
+	/// Returns the ready transactions that provide the same tags as the given transaction, and
+	/// would be replaced by it.
+	fn replaced_by<'a>(
+		&'a self,
+		transaction: &'a Transaction<Hash, Ex>,
+	) -> impl Iterator<Item = Arc<Transaction<Hash, Ex>>> + 'a {
+		let provided_tags = self.ready.provided_tags();
+		transaction
+			.provides
+			.iter()
+			.filter_map(|tag| provided_tags.get(tag))
+			.collect::<HashSet<_>>()
+			.into_iter()
+			.filter_map(|hash| self.ready.by_hash(hash))
+	}
+
 	/// Imports transaction to ready queue.
 	///
 	/// NOTE the transaction has to have all requirements satisfied.
@@ -312,16 +426,29 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 
 		// take first transaction from the list
 		while let Some(tx) = to_import.pop() {
+			let current_hash = tx.transaction.hash.clone();
+			if !first && self.exceeds_promotion_limit(&tx.transaction) {
+				debug!(target: LOG_TARGET, "[{:?}] Sender limit reached", current_hash);
+				failed.push(current_hash);
+				continue
+			}
+
 			// find transactions in Future that it unlocks
-			to_import.append(&mut self.future.satisfy_tags(&tx.transaction.provides));
+			let unlocked = self.future.satisfy_tags(&tx.transaction.provides);
+			unlocked
+				.iter()
+				.for_each(|unlocked| self.usage.remove(&unlocked.transaction.hash));
+			to_import.extend(unlocked);
 
 			// import this transaction
-			let current_hash = tx.transaction.hash.clone();
+			let transaction = tx.transaction.clone();
 			match self.ready.import(tx) {
 				Ok(mut replaced) => {
 					if !first {
 						promoted.push(current_hash);
 					}
+					replaced.iter().for_each(|replaced| self.usage.remove(&replaced.hash));
+					self.usage.insert(&transaction, true, &self.sender_limits, &self.lanes);
 					// The transactions were removed from the ready pool. We might attempt to
 					// re-import them.
 					removed.append(&mut replaced);
@@ -346,7 +473,9 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 		if removed.iter().any(|tx| tx.hash == hash) {
 			// We still need to remove all transactions that we promoted
 			// since they depend on each other and will never get to the best iterator.
-			self.ready.remove_subtree(&promoted);
+			for tx in self.ready.remove_subtree(&promoted) {
+				self.usage.remove(&tx.hash);
+			}
 
 			debug!(target: LOG_TARGET, "[{:?}] Cycle detected, bailing.", hash);
 			return Err(error::Error::CycleDetected)
@@ -394,47 +523,32 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 	) -> Vec<Arc<Transaction<Hash, Ex>>> {
 		let mut removed = vec![];
 
+		// the normal lane can't take the capacity reserved for the operational lane
+		let normal_lane = self.lanes.normal_limit(ready);
+		while self.lanes.reserves_capacity() {
+			let (count, bytes) = self.usage.normal_lane;
+			if !normal_lane.is_exceeded(count, bytes) {
+				break
+			}
+
+			if let Some(worst) = self.usage.worst_ready(true).map(|(worst, _)| worst.clone()) {
+				removed.append(&mut self.remove_subtree(&[worst]))
+			} else {
+				break
+			}
+		}
+
 		while ready.is_exceeded(self.ready.len(), self.ready.bytes()) {
-			// find the worst transaction
-			let worst = self.ready.fold::<TransactionRef<Hash, Ex>, _>(|worst, current| {
-				let transaction = &current.transaction;
-				worst
-					.map(|worst| {
-						// Here we don't use `TransactionRef`'s ordering implementation because
-						// while it prefers priority like need here, it also prefers older
-						// transactions for inclusion purposes and limit enforcement needs to prefer
-						// newer transactions instead and drop the older ones.
-						match worst.transaction.priority.cmp(&transaction.transaction.priority) {
-							Ordering::Less => worst,
-							Ordering::Equal =>
-								if worst.insertion_id > transaction.insertion_id {
-									transaction.clone()
-								} else {
-									worst
-								},
-							Ordering::Greater => transaction.clone(),
-						}
-					})
-					.or_else(|| Some(transaction.clone()))
-			});
-
-			if let Some(worst) = worst {
-				removed.append(&mut self.remove_subtree(&[worst.transaction.hash.clone()]))
+			if let Some(worst) = self.usage.worst_ready(false).map(|(worst, _)| worst.clone()) {
+				removed.append(&mut self.remove_subtree(&[worst]))
 			} else {
 				break
 			}
 		}
 
 		while future.is_exceeded(self.future.len(), self.future.bytes()) {
-			// find the worst transaction
-			let worst = self.future.fold(|worst, current| match worst {
-				None => Some(current.clone()),
-				Some(ref tx) if tx.imported_at > current.imported_at => Some(current.clone()),
-				other => other,
-			});
-
-			if let Some(worst) = worst {
-				removed.append(&mut self.remove_subtree(&[worst.transaction.hash.clone()]))
+			if let Some(worst) = self.usage.oldest_future().cloned() {
+				removed.append(&mut self.remove_subtree(&[worst]))
 			} else {
 				break
 			}
@@ -454,12 +568,15 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 	pub fn remove_subtree(&mut self, hashes: &[Hash]) -> Vec<Arc<Transaction<Hash, Ex>>> {
 		let mut removed = self.ready.remove_subtree(hashes);
 		removed.extend(self.future.remove(hashes));
+		removed.iter().for_each(|tx| self.usage.remove(&tx.hash));
 		removed
 	}
 
 	/// Removes and returns all transactions from the future queue.
 	pub fn clear_future(&mut self) -> Vec<Arc<Transaction<Hash, Ex>>> {
-		self.future.clear()
+		let removed = self.future.clear();
+		removed.iter().for_each(|tx| self.usage.remove(&tx.hash));
+		removed
 	}
 
 	/// Prunes transactions that provide given list of tags.
@@ -477,9 +594,15 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 
 		for tag in tags {
 			// make sure to promote any future transactions that could be unlocked
-			to_import.append(&mut self.future.satisfy_tags(std::iter::once(&tag)));
+			let unlocked = self.future.satisfy_tags(std::iter::once(&tag));
+			unlocked
+				.iter()
+				.for_each(|unlocked| self.usage.remove(&unlocked.transaction.hash));
+			to_import.extend(unlocked);
 			// and actually prune transactions in ready queue
-			pruned.append(&mut self.ready.prune_tags(tag.clone()));
+			let mut pruned_by_tag = self.ready.prune_tags(tag.clone());
+			pruned_by_tag.iter().for_each(|tx| self.usage.remove(&tx.hash));
+			pruned.append(&mut pruned_by_tag);
 			// store the tags for next submission
 			recently_pruned.insert(tag);
 		}
@@ -488,6 +611,11 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 		let mut failed = vec![];
 		for tx in to_import {
 			let hash = tx.transaction.hash.clone();
+			if self.exceeds_promotion_limit(&tx.transaction) {
+				debug!(target: LOG_TARGET, "[{:?}] Sender limit reached", hash);
+				failed.push(hash);
+				continue
+			}
 			match self.import_to_ready(tx) {
 				Ok(res) => promoted.push(res),
 				Err(e) => {
@@ -524,12 +652,269 @@ pub struct Limit {
 }
 
 impl Limit {
+	/// A limit that is never exceeded.
+	pub fn unlimited() -> Self {
+		Self { count: usize::MAX, total_bytes: usize::MAX }
+	}
+
 	/// Returns true if any of the provided values exceeds the limit.
 	pub fn is_exceeded(&self, count: usize, bytes: usize) -> bool {
 		self.count < count || self.total_bytes < bytes
 	}
 }
 
+/// Removes the transaction from the `(count, bytes)` usage.
+fn without<Hash, Ex>((count, bytes): (usize, usize), tx: &Transaction<Hash, Ex>) -> (usize, usize) {
+	(count.saturating_sub(1), bytes.saturating_sub(tx.bytes))
+}
+
+/// Usage of the queues by sender and by lane, along with the order in which the transactions are
+/// evicted, maintained as transactions enter and leave the queues.
+#[derive(Debug)]
+struct Usage<Hash: hash::Hash + Eq> {
+	/// `(count, bytes)` of the ready transactions of each sender.
+	ready_senders: HashMap<Vec<u8>, (usize, usize)>,
+	/// `(count, bytes)` of the future transactions of each sender.
+	future_senders: HashMap<Vec<u8>, (usize, usize)>,
+	/// `(count, bytes)` of the ready transactions in the normal lane.
+	normal_lane: (usize, usize),
+	/// Ready transactions, the worst first: lowest priority, then the oldest.
+	worst_ready: BTreeMap<(Priority, u64), Hash>,
+	/// Ready transactions in the normal lane, the worst first.
+	worst_normal: BTreeMap<(Priority, u64), Hash>,
+	/// Future transactions, the oldest first.
+	oldest_future: BTreeMap<u64, Hash>,
+	/// Transactions in the queues.
+	entries: HashMap<Hash, UsageEntry>,
+	/// Number of transactions inserted so far, ordering them by age.
+	inserted: u64,
+}
+
+#[derive(Debug)]
+struct UsageEntry {
+	id: u64,
+	ready: bool,
+	priority: Priority,
+	bytes: usize,
+	sender: Option<Vec<u8>>,
+	lane: Lane,
+}
+
+impl<Hash: hash::Hash + Eq> Default for Usage<Hash> {
+	fn default() -> Self {
+		Self {
+			ready_senders: Default::default(),
+			future_senders: Default::default(),
+			normal_lane: (0, 0),
+			worst_ready: Default::default(),
+			worst_normal: Default::default(),
+			oldest_future: Default::default(),
+			entries: Default::default(),
+			inserted: 0,
+		}
+	}
+}
+
+impl<Hash: hash::Hash + Eq + Clone> Usage<Hash> {
+	/// Record that the transaction entered the ready or the future queue.
+	fn insert<Ex>(
+		&mut self,
+		tx: &Transaction<Hash, Ex>,
+		ready: bool,
+		sender_limits: &SenderLimits,
+		lanes: &Lanes,
+	) {
+		self.inserted += 1;
+		let entry = UsageEntry {
+			id: self.inserted,
+			ready,
+			priority: tx.priority,
+			bytes: tx.bytes,
+			sender: sender_limits.sender(tx).map(<[u8]>::to_vec),
+			lane: lanes.lane(tx),
+		};
+
+		let senders = if ready { &mut self.ready_senders } else { &mut self.future_senders };
+		if let Some(sender) = &entry.sender {
+			let (count, bytes) = senders.entry(sender.clone()).or_default();
+			*count += 1;
+			*bytes += entry.bytes;
+		}
+		if ready {
+			self.worst_ready.insert((entry.priority, entry.id), tx.hash.clone());
+			if entry.lane == Lane::Normal {
+				self.normal_lane = (self.normal_lane.0 + 1, self.normal_lane.1 + entry.bytes);
+				self.worst_normal.insert((entry.priority, entry.id), tx.hash.clone());
+			}
+		} else {
+			self.oldest_future.insert(entry.id, tx.hash.clone());
+		}
+		self.entries.insert(tx.hash.clone(), entry);
+	}
+
+	/// Record that the transaction left its queue.
+	fn remove(&mut self, hash: &Hash) {
+		let Some(entry) = self.entries.remove(hash) else { return };
+
+		let senders = if entry.ready { &mut self.ready_senders } else { &mut self.future_senders };
+		if let Some(sender) = entry.sender {
+			if let hash_map::Entry::Occupied(mut usage) = senders.entry(sender) {
+				let (count, bytes) = usage.get_mut();
+				*count -= 1;
+				*bytes -= entry.bytes;
+				if *count == 0 {
+					usage.remove();
+				}
+			}
+		}
+		if entry.ready {
+			self.worst_ready.remove(&(entry.priority, entry.id));
+			if entry.lane == Lane::Normal {
+				self.normal_lane = (self.normal_lane.0 - 1, self.normal_lane.1 - entry.bytes);
+				self.worst_normal.remove(&(entry.priority, entry.id));
+			}
+		} else {
+			self.oldest_future.remove(&entry.id);
+		}
+	}
+
+	/// Returns the `(count, bytes)` of the ready or the future transactions of the sender.
+	fn sender(&self, sender: &[u8], ready: bool) -> (usize, usize) {
+		let senders = if ready { &self.ready_senders } else { &self.future_senders };
+		senders.get(sender).copied().unwrap_or_default()
+	}
+
+	/// Returns the worst ready transaction and its priority, optionally only considering the
+	/// normal lane.
+	///
+	/// Unlike the order of inclusion in blocks, the older of two transactions with the same
+	/// priority is the worse one, so that the transactions occupying the pool for the longest
+	/// time are dropped first.
+	fn worst_ready(&self, normal_lane_only: bool) -> Option<(&Hash, Priority)> {
+		let worst = if normal_lane_only { &self.worst_normal } else { &self.worst_ready };
+		worst.iter().next().map(|((priority, _), hash)| (hash, *priority))
+	}
+
+	/// Returns the oldest future transaction.
+	fn oldest_future(&self) -> Option<&Hash> {
+		self.oldest_future.values().next()
+	}
+}
+
+/// Limits of the transactions of a single sender.
+///
+/// The pool is not aware of accounts, so the sender of a transaction is derived from the first
+/// tag it provides, with the trailing [`SenderLimits::nonce_len`] bytes stripped. This matches the
+/// `(AccountId, Nonce)` tags provided by the `CheckNonce` signed extension of FRAME, where all
+/// transactions of an account share the same prefix. Transactions that don't provide a long enough
+/// tag are not subject to these limits.
+#[derive(Debug, Clone)]
+pub struct SenderLimits {
+	/// Limit of the ready transactions of a single sender.
+	pub ready: Limit,
+	/// Limit of the future transactions of a single sender.
+	pub future: Limit,
+	/// Number of trailing bytes of a provided tag that encode the nonce of the sender.
+	pub nonce_len: usize,
+}
+
+impl Default for SenderLimits {
+	fn default() -> Self {
+		Self { ready: Limit::unlimited(), future: Limit::unlimited(), nonce_len: 4 }
+	}
+}
+
+impl SenderLimits {
+	/// Returns the sender of the given transaction, if it can be identified.
+	fn sender<'a, Hash, Ex>(&self, tx: &'a Transaction<Hash, Ex>) -> Option<&'a [u8]> {
+		tx.provides
+			.first()
+			.filter(|tag| tag.len() > self.nonce_len)
+			.map(|tag| &tag[..tag.len() - self.nonce_len])
+	}
+}
+
+/// Priority lane of a transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lane {
+	/// Regular transactions.
+	Normal,
+	/// Operational and inherent-like transactions.
+	///
+	/// These can use the capacity of the ready queue reserved by [`Lanes::reserved`] and are not
+	/// subject to the [`SenderLimits`].
+	Operational,
+}
+
+/// Priority lanes of the ready queue.
+#[derive(Debug, Clone)]
+pub struct Lanes {
+	/// Minimal priority of a transaction in the operational lane.
+	///
+	/// Locally submitted transactions are always in the operational lane.
+	pub operational_priority: Priority,
+	/// Capacity of the ready queue reserved for the operational lane.
+	pub reserved: Limit,
+}
+
+impl Default for Lanes {
+	fn default() -> Self {
+		Self {
+			operational_priority: Priority::max_value(),
+			reserved: Limit { count: 0, total_bytes: 0 },
+		}
+	}
+}
+
+impl Lanes {
+	/// Returns the lane of the given transaction.
+	pub fn lane<Hash, Ex>(&self, tx: &Transaction<Hash, Ex>) -> Lane {
+		if tx.source == Source::Local || tx.priority >= self.operational_priority {
+			Lane::Operational
+		} else {
+			Lane::Normal
+		}
+	}
+
+	/// Returns true if any capacity of the ready queue is reserved for the operational lane.
+	pub fn reserves_capacity(&self) -> bool {
+		self.reserved.count > 0 || self.reserved.total_bytes > 0
+	}
+
+	/// Returns the limit of the normal lane given the limit of the whole `ready` queue.
+	pub fn normal_limit(&self, ready: &Limit) -> Limit {
+		Limit {
+			count: ready.count.saturating_sub(self.reserved.count),
+			total_bytes: ready.total_bytes.saturating_sub(self.reserved.total_bytes),
+		}
+	}
+}
+
+/// Policy for replacing ready transactions with a transaction that provides the same tags.
+///
+/// A transaction that provides a tag already provided by transactions in the ready queue, like
+/// the same `(sender, nonce)`, replaces all of them if its priority exceeds the sum of their
+/// priorities by at least the minimal bump. The transactions depending on the replaced ones are
+/// removed along with them. Otherwise the transaction is rejected with
+/// [`error::Error::TooLowPriority`]. Only transactions in the ready queue can be replaced.
+#[derive(Debug, Clone, Default)]
+pub struct ReplacementPolicy {
+	/// Minimal priority increase of the replacement, in percent of the total priority of the
+	/// replaced transactions.
+	///
+	/// With the default of zero, any strictly higher priority is enough.
+	pub min_bump_percent: u32,
+}
+
+impl ReplacementPolicy {
+	/// Returns true if a transaction with the priority `new` can replace transactions with the
+	/// total priority `old`.
+	pub fn allows(&self, old: Priority, new: Priority) -> bool {
+		let bump = u128::from(old) * u128::from(self.min_bump_percent) / 100;
+		new > old && u128::from(new - old) >= bump
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -1043,4 +1428,134 @@ source: TransactionSource::External, requires: [03, 02], provides: [04], data: [
 		assert_eq!(pool.reject_future_transactions, true);
 		assert_eq!(pool.future.len(), 1);
 	}
+
+	#[test]
+	fn should_enforce_sender_limits() {
+		// given
+		let limit = Limit { count: 2, total_bytes: 1000 };
+		let sender_limits = SenderLimits { ready: limit.clone(), future: limit, nonce_len: 1 };
+		let mut pool = pool().with_limits(sender_limits, Default::default(), &Limit::unlimited());
+		let tx = |hash: u64, sender: u8, requires: Vec<Tag>| Transaction {
+			data: vec![hash as u8],
+			hash,
+			requires,
+			provides: vec![vec![sender, hash as u8]],
+			..DEFAULT_TX.clone()
+		};
+
+		// when
+		pool.import(tx(1, 1, vec![])).unwrap();
+		pool.import(tx(2, 1, vec![])).unwrap();
+		pool.import(tx(3, 1, vec![vec![0]])).unwrap();
+		pool.import(tx(4, 1, vec![vec![0]])).unwrap();
+
+		// then
+		assert!(matches!(pool.import(tx(5, 1, vec![])), Err(error::Error::SenderLimitReached)));
+		assert!(matches!(
+			pool.import(tx(6, 1, vec![vec![0]])),
+			Err(error::Error::SenderLimitReached)
+		));
+		pool.import(tx(7, 2, vec![])).unwrap();
+		pool.import(Transaction { source: Source::Local, ..tx(8, 1, vec![]) }).unwrap();
+		assert_eq!(pool.ready.len(), 4);
+		assert_eq!(pool.future.len(), 2);
+	}
+
+	#[test]
+	fn should_replace_transaction_of_sender_at_limit() {
+		// given
+		let limit = Limit { count: 2, total_bytes: 1000 };
+		let sender_limits = SenderLimits { ready: limit.clone(), future: limit, nonce_len: 1 };
+		let mut pool = pool().with_limits(sender_limits, Default::default(), &Limit::unlimited());
+		let tx = |hash: u64, nonce: u8, priority: u64| Transaction {
+			data: vec![hash as u8],
+			hash,
+			priority,
+			provides: vec![vec![1, nonce]],
+			..DEFAULT_TX.clone()
+		};
+		pool.import(tx(1, 1, 5)).unwrap();
+		pool.import(tx(2, 2, 5)).unwrap();
+
+		// when
+		let res = pool.import(tx(3, 2, 10)).unwrap();
+
+		// then
+		assert!(matches!(pool.import(tx(4, 3, 10)), Err(error::Error::SenderLimitReached)));
+		assert_eq!(
+			res,
+			Imported::Ready {
+				hash: 3,
+				promoted: vec![],
+				failed: vec![],
+				removed: vec![Arc::new(tx(2, 2, 5))],
+			}
+		);
+		assert_eq!(pool.ready().map(|tx| tx.hash).collect::<Vec<_>>(), vec![3, 1]);
+	}
+
+	#[test]
+	fn should_enforce_sender_limits_on_promoted_transactions() {
+		// given
+		let sender_limits = SenderLimits {
+			ready: Limit { count: 1, total_bytes: 1000 },
+			future: Limit { count: 2, total_bytes: 1000 },
+			nonce_len: 1,
+		};
+		let mut pool = pool().with_limits(sender_limits, Default::default(), &Limit::unlimited());
+		let tx = |hash: u64, sender: u8, requires: Vec<Tag>| Transaction {
+			data: vec![hash as u8],
+			hash,
+			requires,
+			provides: vec![vec![sender, hash as u8]],
+			..DEFAULT_TX.clone()
+		};
+		pool.import(tx(1, 1, vec![vec![0]])).unwrap();
+		pool.import(tx(2, 1, vec![vec![1, 1]])).unwrap();
+
+		// when
+		let res = pool
+			.import(Transaction { provides: vec![vec![0]], ..tx(3, 2, vec![]) })
+			.unwrap();
+
+		// then
+		assert_eq!(
+			res,
+			Imported::Ready { hash: 3, promoted: vec![1], failed: vec![2], removed: vec![] }
+		);
+		assert_eq!(pool.ready().map(|tx| tx.hash).collect::<Vec<_>>(), vec![3, 1]);
+		assert_eq!(pool.future.len(), 0);
+		assert_eq!(pool.usage.sender(&[1], true), (1, 1));
+		assert_eq!(pool.usage.sender(&[1], false), (0, 0));
+	}
+
+	#[test]
+	fn should_reserve_capacity_for_operational_lane() {
+		// given
+		let ready = Limit { count: 3, total_bytes: 1000 };
+		let lanes =
+			Lanes { operational_priority: 100, reserved: Limit { count: 1, total_bytes: 0 } };
+		let mut pool = pool().with_limits(Default::default(), lanes, &ready);
+		let tx = |hash: u64, priority: u64| Transaction {
+			data: vec![hash as u8],
+			hash,
+			priority,
+			provides: vec![vec![hash as u8]],
+			..DEFAULT_TX.clone()
+		};
+		pool.import(tx(1, 5)).unwrap();
+		pool.import(tx(2, 6)).unwrap();
+
+		// when
+		let err = pool.import(tx(3, 5));
+		pool.import(tx(4, 10)).unwrap();
+		let removed = pool.enforce_limits(&ready, &Limit::unlimited());
+		pool.import(tx(5, 100)).unwrap();
+
+		// then
+		assert!(matches!(err, Err(error::Error::LaneFull)));
+		assert_eq!(removed.into_iter().map(|tx| tx.hash).collect::<Vec<_>>(), vec![1]);
+		assert!(pool.enforce_limits(&ready, &Limit::unlimited()).is_empty());
+		assert_eq!(pool.ready().map(|tx| tx.hash).collect::<Vec<_>>(), vec![5, 4, 2]);
+	}
 }
@@ -30,6 +30,16 @@ use super::{watcher, BlockHash, ChainApi, ExtrinsicHash};
 pub struct Listener<H: hash::Hash + Eq, C: ChainApi> {
 	watchers: HashMap<H, watcher::Sender<H, ExtrinsicHash<C>>>,
 	finality_watchers: LinkedHashMap<ExtrinsicHash<C>, Vec<H>>,
+	/// Final events that are held back until they are settled, if enabled.
+	unsettled: Option<HashMap<H, Unsettled<H>>>,
+}
+
+/// A final event of a transaction that is not settled yet.
+enum Unsettled<H> {
+	/// The transaction was dropped, possibly replaced with another one.
+	Dropped(Option<H>),
+	/// The transaction was found invalid.
+	Invalid,
 }
 
 /// Maximum number of blocks awaiting finality at any time.
@@ -37,7 +47,11 @@ const MAX_FINALITY_WATCHERS: usize = 512;
 
 impl<H: hash::Hash + Eq + Debug, C: ChainApi> Default for Listener<H, C> {
 	fn default() -> Self {
-		Self { watchers: Default::default(), finality_watchers: Default::default() }
+		Self {
+			watchers: Default::default(),
+			finality_watchers: Default::default(),
+			unsettled: None,
+		}
 	}
 }
 
@@ -58,6 +72,57 @@ impl<H: hash::Hash + traits::Member + Serialize, C: ChainApi> Listener<H, C> {
 		}
 	}
 
+	/// Creates a listener that holds back the `Dropped`, `Usurped` and `Invalid` events until
+	/// they are settled with [`Self::settle`].
+	///
+	/// A transaction that is dropped or invalid at the best block might still be valid on
+	/// another fork, so the event is only emitted once the transaction did not come back.
+	pub fn with_unsettled_events() -> Self {
+		Self { unsettled: Some(Default::default()), ..Default::default() }
+	}
+
+	/// Hold back the final event of the transaction, returns false if it should be fired now.
+	fn hold_back(&mut self, tx: &H, event: Unsettled<H>) -> bool {
+		match self.unsettled {
+			Some(ref mut unsettled) if self.watchers.contains_key(tx) => {
+				unsettled.insert(tx.clone(), event);
+				true
+			},
+			_ => false,
+		}
+	}
+
+	/// The transaction is still alive, forget its unsettled final event.
+	fn revive(&mut self, tx: &H) {
+		if let Some(ref mut unsettled) = self.unsettled {
+			unsettled.remove(tx);
+		}
+	}
+
+	/// Fire the unsettled final events of the transactions that are no longer pending.
+	pub fn settle(&mut self, is_pending: impl Fn(&H) -> bool) {
+		let settled = match self.unsettled {
+			Some(ref mut unsettled) => {
+				let settled =
+					unsettled.keys().filter(|h| !is_pending(h)).cloned().collect::<Vec<_>>();
+				settled
+					.into_iter()
+					.filter_map(|h| unsettled.remove(&h).map(|e| (h, e)))
+					.collect()
+			},
+			None => Vec::new(),
+		};
+
+		for (tx, event) in settled {
+			debug!(target: LOG_TARGET, "[{:?}] Settled", tx);
+			self.fire(&tx, |watcher| match event {
+				Unsettled::Dropped(Some(by)) => watcher.usurped(by),
+				Unsettled::Dropped(None) => watcher.dropped(),
+				Unsettled::Invalid => watcher.invalid(),
+			});
+		}
+	}
+
 	/// Creates a new watcher for given verified extrinsic.
 	///
 	/// The watcher can be used to subscribe to life-cycle events of that extrinsic.
@@ -75,21 +140,28 @@ impl<H: hash::Hash + traits::Member + Serialize, C: ChainApi> Listener<H, C> {
 	/// New transaction was added to the ready pool or promoted from the future pool.
 	pub fn ready(&mut self, tx: &H, old: Option<&H>) {
 		trace!(target: LOG_TARGET, "[{:?}] Ready (replaced with {:?})", tx, old);
+		self.revive(tx);
 		self.fire(tx, |watcher| watcher.ready());
 		if let Some(old) = old {
-			self.fire(old, |watcher| watcher.usurped(tx.clone()));
+			if !self.hold_back(old, Unsettled::Dropped(Some(tx.clone()))) {
+				self.fire(old, |watcher| watcher.usurped(tx.clone()));
+			}
 		}
 	}
 
 	/// New transaction was added to the future pool.
 	pub fn future(&mut self, tx: &H) {
 		trace!(target: LOG_TARGET, "[{:?}] Future", tx);
+		self.revive(tx);
 		self.fire(tx, |watcher| watcher.future());
 	}
 
 	/// Transaction was dropped from the pool because of the limit.
 	pub fn dropped(&mut self, tx: &H, by: Option<&H>) {
 		trace!(target: LOG_TARGET, "[{:?}] Dropped (replaced with {:?})", tx, by);
+		if self.hold_back(tx, Unsettled::Dropped(by.cloned())) {
+			return
+		}
 		self.fire(tx, |watcher| match by {
 			Some(t) => watcher.usurped(t.clone()),
 			None => watcher.dropped(),
@@ -99,12 +171,23 @@ impl<H: hash::Hash + traits::Member + Serialize, C: ChainApi> Listener<H, C> {
 	/// Transaction was removed as invalid.
 	pub fn invalid(&mut self, tx: &H) {
 		debug!(target: LOG_TARGET, "[{:?}] Extrinsic invalid", tx);
+		if self.hold_back(tx, Unsettled::Invalid) {
+			return
+		}
 		self.fire(tx, |watcher| watcher.invalid());
 	}
 
+	/// Transaction was removed on request of its sender.
+	pub fn cancelled(&mut self, tx: &H) {
+		debug!(target: LOG_TARGET, "[{:?}] Cancelled", tx);
+		self.revive(tx);
+		self.fire(tx, |watcher| watcher.cancelled());
+	}
+
 	/// Transaction was pruned from the pool.
 	pub fn pruned(&mut self, block_hash: BlockHash<C>, tx: &H) {
 		debug!(target: LOG_TARGET, "[{:?}] Pruned at {:?}", tx, block_hash);
+		self.revive(tx);
 		// Get the transactions included in the given block hash.
 		let txs = self.finality_watchers.entry(block_hash).or_insert(vec![]);
 		txs.push(tx.clone());
@@ -115,10 +115,23 @@ pub struct Options {
 	pub ready: base::Limit,
 	/// Future queue limits.
 	pub future: base::Limit,
+	/// Limits of the transactions of a single sender.
+	pub sender_limits: base::SenderLimits,
+	/// Priority lanes of the ready queue.
+	pub lanes: base::Lanes,
+	/// Policy for replacing ready transactions that provide the same tags.
+	pub replacement: base::ReplacementPolicy,
 	/// Reject future transactions.
 	pub reject_future_transactions: bool,
 	/// How long the extrinsic is banned for.
 	pub ban_time: Duration,
+	/// Journal persisting the submitted transactions across restarts.
+	pub journal: Option<crate::JournalOptions>,
+	/// Keep a validated view of the pool at the head of every fork.
+	///
+	/// The `Dropped`, `Usurped` and `Invalid` events are then held back until the transaction
+	/// is not valid at any fork head anymore, as determined on finalization.
+	pub fork_aware: bool,
 }
 
 impl Default for Options {
@@ -126,8 +139,13 @@ impl Default for Options {
 		Self {
 			ready: base::Limit { count: 8192, total_bytes: 20 * 1024 * 1024 },
 			future: base::Limit { count: 512, total_bytes: 1 * 1024 * 1024 },
+			sender_limits: Default::default(),
+			lanes: Default::default(),
+			replacement: Default::default(),
 			reject_future_transactions: false,
 			ban_time: Duration::from_secs(60 * 30),
+			journal: None,
+			fork_aware: false,
 		}
 	}
 }
@@ -883,6 +901,37 @@ mod tests {
 			assert_eq!(stream.next(), None);
 		}
 
+		#[test]
+		fn should_hold_back_invalid_until_settled() {
+			use futures::{FutureExt, StreamExt};
+
+			// given
+			let options = Options { fork_aware: true, ..Default::default() };
+			let pool = Pool::new(options, true.into(), TestApi::default().into());
+			let uxt = uxt(Transfer {
+				from: Alice.into(),
+				to: AccountId::from_h256(H256::from_low_u64_be(2)),
+				amount: 5,
+				nonce: 0,
+			});
+			let watcher =
+				block_on(pool.submit_and_watch(&BlockId::Number(0), SOURCE, uxt)).unwrap();
+			let hash = *watcher.hash();
+			let mut stream = watcher.into_stream();
+			assert_eq!(block_on(stream.next()), Some(TransactionStatus::Ready));
+
+			// when
+			pool.validated_pool.remove_invalid(&[hash]);
+
+			// then
+			assert_eq!(stream.next().now_or_never(), None);
+			pool.validated_pool.settle_events(|h| *h == hash);
+			assert_eq!(stream.next().now_or_never(), None);
+			pool.validated_pool.settle_events(|_| false);
+			assert_eq!(block_on(stream.next()), Some(TransactionStatus::Invalid));
+			assert_eq!(block_on(stream.next()), None);
+		}
+
 		#[test]
 		fn should_trigger_broadcasted() {
 			// given
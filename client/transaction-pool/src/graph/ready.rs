@@ -30,7 +30,7 @@ use serde::Serialize;
 use sp_runtime::{traits::Member, transaction_validity::TransactionTag as Tag};
 
 use super::{
-	base_pool::Transaction,
+	base_pool::{ReplacementPolicy, Transaction},
 	future::WaitingTransaction,
 	tracked_map::{self, TrackedMap},
 };
@@ -118,6 +118,8 @@ pub struct ReadyTransactions<Hash: hash::Hash + Eq, Ex> {
 	/// Best transactions that are ready to be included to the block without any other previous
 	/// transaction.
 	best: BTreeSet<TransactionRef<Hash, Ex>>,
+	/// Policy for replacing transactions that provide the same tags.
+	replacement: ReplacementPolicy,
 }
 
 impl<Hash, Ex> tracked_map::Size for ReadyTx<Hash, Ex> {
@@ -133,11 +135,17 @@ impl<Hash: hash::Hash + Eq, Ex> Default for ReadyTransactions<Hash, Ex> {
 			provided_tags: Default::default(),
 			ready: Default::default(),
 			best: Default::default(),
+			replacement: Default::default(),
 		}
 	}
 }
 
 impl<Hash: hash::Hash + Member + Serialize, Ex> ReadyTransactions<Hash, Ex> {
+	/// Set the policy for replacing transactions that provide the same tags.
+	pub fn set_replacement_policy(&mut self, replacement: ReplacementPolicy) {
+		self.replacement = replacement;
+	}
+
 	/// Borrows a map of tags that are provided by transactions in this queue.
 	pub fn provided_tags(&self) -> &HashMap<Tag, Hash> {
 		&self.provided_tags
@@ -232,14 +240,6 @@ impl<Hash: hash::Hash + Member + Serialize, Ex> ReadyTransactions<Hash, Ex> {
 		Ok(replaced)
 	}
 
-	/// Fold a list of ready transactions to compute a single value.
-	pub fn fold<R, F: FnMut(Option<R>, &ReadyTx<Hash, Ex>) -> Option<R>>(
-		&mut self,
-		f: F,
-	) -> Option<R> {
-		self.ready.read().values().fold(None, f)
-	}
-
 	/// Returns true if given transaction is part of the queue.
 	pub fn contains(&self, hash: &Hash) -> bool {
 		self.ready.read().contains_key(hash)
@@ -412,9 +412,9 @@ impl<Hash: hash::Hash + Member + Serialize, Ex> ReadyTransactions<Hash, Ex> {
 
 	/// Checks if the transaction is providing the same tags as other transactions.
 	///
-	/// In case that's true it determines if the priority of transactions that
-	/// we are about to replace is lower than the priority of the replacement transaction.
-	/// We remove/replace old transactions in case they have lower priority.
+	/// In case that's true it determines if the priority of the replacement transaction
+	/// is high enough to replace the old transactions, according to the [`ReplacementPolicy`].
+	/// We remove/replace old transactions in case it is.
 	///
 	/// In case replacement is successful returns a list of removed transactions
 	/// and a list of hashes that are still in pool and gets unlocked by the new transaction.
@@ -447,7 +447,7 @@ impl<Hash: hash::Hash + Member + Serialize, Ex> ReadyTransactions<Hash, Ex> {
 			};
 
 			// bail - the transaction has too low priority to replace the old ones
-			if old_priority >= tx.priority {
+			if !self.replacement.allows(old_priority, tx.priority) {
 				return Err(error::Error::TooLowPriority { old: old_priority, new: tx.priority })
 			}
 
@@ -641,6 +641,33 @@ mod tests {
 		assert_eq!(ready.get().count(), 1);
 	}
 
+	#[test]
+	fn should_require_minimal_priority_bump_for_replacement() {
+		// given
+		let mut ready = ReadyTransactions::default();
+		ready.set_replacement_policy(ReplacementPolicy { min_bump_percent: 10 });
+		let mut tx1 = tx(1);
+		tx1.requires.clear();
+		tx1.priority = 100;
+		let mut tx2 = tx(2);
+		tx2.requires.clear();
+		tx2.priority = 109;
+		let mut tx3 = tx(3);
+		tx3.requires.clear();
+		tx3.priority = 110;
+
+		// when
+		import(&mut ready, tx1).unwrap();
+
+		// then
+		assert!(matches!(
+			import(&mut ready, tx2),
+			Err(error::Error::TooLowPriority { old: 100, new: 109 })
+		));
+		assert_eq!(import(&mut ready, tx3).unwrap().len(), 1);
+		assert_eq!(ready.get().map(|tx| tx.hash).collect::<Vec<_>>(), vec![3]);
+	}
+
 	#[test]
 	fn should_replace_multiple_transactions_correctly() {
 		// given
@@ -114,12 +114,16 @@ pub struct ValidatedPool<B: ChainApi> {
 impl<B: ChainApi> ValidatedPool<B> {
 	/// Create a new transaction pool.
 	pub fn new(options: Options, is_validator: IsValidator, api: Arc<B>) -> Self {
-		let base_pool = base::BasePool::new(options.reject_future_transactions);
+		let base_pool = base::BasePool::new(options.reject_future_transactions)
+			.with_limits(options.sender_limits.clone(), options.lanes.clone(), &options.ready)
+			.with_replacement_policy(options.replacement.clone());
 		let ban_time = options.ban_time;
+		let listener =
+			if options.fork_aware { Listener::with_unsettled_events() } else { Default::default() };
 		Self {
 			is_validator,
 			options,
-			listener: Default::default(),
+			listener: RwLock::new(listener),
 			api,
 			pool: RwLock::new(base_pool),
 			import_notification_sinks: Default::default(),
@@ -230,10 +234,13 @@ impl<B: ChainApi> ValidatedPool<B> {
 		let status = self.pool.read().status();
 		let ready_limit = &self.options.ready;
 		let future_limit = &self.options.future;
+		let lanes = &self.options.lanes;
 
 		log::debug!(target: LOG_TARGET, "Pool Status: {:?}", status);
 		if ready_limit.is_exceeded(status.ready, status.ready_bytes) ||
-			future_limit.is_exceeded(status.future, status.future_bytes)
+			future_limit.is_exceeded(status.future, status.future_bytes) ||
+			(lanes.reserves_capacity() &&
+				lanes.normal_limit(ready_limit).is_exceeded(status.ready, status.ready_bytes))
 		{
 			log::debug!(
 				target: LOG_TARGET,
@@ -615,6 +622,30 @@ impl<B: ChainApi> ValidatedPool<B> {
 		invalid
 	}
 
+	/// Removes the given transactions, and the transactions depending on them, on request of
+	/// their sender.
+	///
+	/// The given transactions are additionally banned to prevent them from being reimported when
+	/// received from the network again.
+	pub fn cancel(&self, hashes: &[ExtrinsicHash<B>]) -> Vec<TransactionFor<B>> {
+		if hashes.is_empty() {
+			return vec![]
+		}
+
+		self.rotator.ban(&Instant::now(), hashes.iter().cloned());
+
+		let cancelled = self.pool.write().remove_subtree(hashes);
+
+		log::debug!(target: LOG_TARGET, "Cancelled transactions: {:?}", cancelled);
+
+		let mut listener = self.listener.write();
+		for tx in &cancelled {
+			listener.cancelled(&tx.hash);
+		}
+
+		cancelled
+	}
+
 	/// Get an iterator for ready transactions ordered by priority
 	pub fn ready(&self) -> impl ReadyTransactions<Item = TransactionFor<B>> + Send {
 		self.pool.read().ready()
@@ -625,6 +656,38 @@ impl<B: ChainApi> ValidatedPool<B> {
 		self.pool.read().futures().map(|tx| (tx.hash, tx.data.clone())).collect()
 	}
 
+	/// Returns a Vec of sources and extrinsics of all transactions in the pool.
+	///
+	/// The ready transactions come first, ordered by priority, followed by the future ones.
+	pub fn transactions_with_source(&self) -> Vec<(TransactionSource, ExtrinsicFor<B>)> {
+		let pool = self.pool.read();
+		pool.ready()
+			.map(|tx| (tx.source, tx.data.clone()))
+			.chain(pool.futures().map(|tx| (tx.source, tx.data.clone())))
+			.collect()
+	}
+
+	/// Returns all the transactions of the pool, along with their validity.
+	///
+	/// The ready transactions come first, ordered by priority, followed by the future ones.
+	pub fn validated_transactions(&self) -> Vec<ValidatedTransactionFor<B>> {
+		let pool = self.pool.read();
+		pool.ready()
+			.map(|tx| ValidatedTransaction::Valid(tx.duplicate()))
+			.chain(pool.futures().map(|tx| ValidatedTransaction::Valid(tx.duplicate())))
+			.collect()
+	}
+
+	/// Returns the transaction with the given hash from the ready or future pool.
+	pub fn by_hash(&self, hash: &ExtrinsicHash<B>) -> Option<TransactionFor<B>> {
+		self.pool.read().by_hashes(&[*hash]).pop().flatten()
+	}
+
+	/// Returns true if the transaction is in the ready or future pool.
+	pub fn contains(&self, hash: &ExtrinsicHash<B>) -> bool {
+		self.pool.read().is_imported(hash)
+	}
+
 	/// Returns pool status.
 	pub fn status(&self) -> PoolStatus {
 		self.pool.read().status()
@@ -641,6 +704,15 @@ impl<B: ChainApi> ValidatedPool<B> {
 		Ok(())
 	}
 
+	/// Fire the held back final events of the transactions that are neither in the pool nor
+	/// pending according to `is_pending`.
+	///
+	/// Only relevant for fork-aware pools, see [`Options::fork_aware`].
+	pub fn settle_events(&self, is_pending: impl Fn(&ExtrinsicHash<B>) -> bool) {
+		let pool = self.pool.read();
+		self.listener.write().settle(|hash| pool.is_imported(hash) || is_pending(hash));
+	}
+
 	/// Notify the listener of retracted blocks
 	pub fn on_block_retracted(&self, block_hash: BlockHash<B>) {
 		self.listener.write().retracted(block_hash)
@@ -118,6 +118,12 @@ impl<H: Clone, BH: Clone> Sender<H, BH> {
 		self.is_finalized = true;
 	}
 
+	/// Transaction has been removed from the pool on request of its sender.
+	pub fn cancelled(&mut self) {
+		self.send(TransactionStatus::Cancelled);
+		self.is_finalized = true;
+	}
+
 	/// The extrinsic has been broadcast to the given peers.
 	pub fn broadcast(&mut self, peers: Vec<String>) {
 		self.send(TransactionStatus::Broadcast(peers))
@@ -0,0 +1,391 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! On-disk journal of the transaction pool.
+//!
+//! Submitted transactions are appended to the journal along with their source, so that they
+//! survive a restart of the node. The journal is periodically rewritten with the content of the
+//! pool, which drops the transactions that were included or removed in the meantime. After a
+//! restart the journaled transactions are revalidated and resubmitted to the pool.
+//!
+//! Rewriting the journal syncs it to disk, so it is done by a blocking background task rather
+//! than during the maintenance of the pool.
+//!
+//! The journal starts with a magic header, followed by length-prefixed SCALE encoded records.
+
+use crate::LOG_TARGET;
+use codec::{Decode, Encode};
+use futures::{channel::mpsc, StreamExt};
+use parking_lot::Mutex;
+use sp_runtime::transaction_validity::TransactionSource;
+use std::{
+	fs::{self, File, OpenOptions},
+	io::{self, Write},
+	path::PathBuf,
+	sync::Arc,
+	time::{Duration, Instant},
+};
+
+/// Header of the journal file, identifying the format version.
+const MAGIC: &[u8; 8] = b"txjrnl01";
+
+/// How often the journal is rewritten with the content of the pool.
+const ROTATION_PERIOD: Duration = Duration::from_secs(60);
+
+/// Configuration of the transaction pool journal.
+#[derive(Debug, Clone)]
+pub struct JournalOptions {
+	/// Path of the journal file.
+	pub path: PathBuf,
+	/// Maximal size of the journal file in bytes.
+	///
+	/// Transactions that don't fit are not journaled.
+	pub max_bytes: usize,
+}
+
+/// A journaled transaction.
+#[derive(Encode, Decode)]
+struct Record {
+	source: TransactionSource,
+	extrinsic: Vec<u8>,
+}
+
+/// Journal of the transactions submitted to the pool.
+pub(crate) struct Journal {
+	options: JournalOptions,
+	state: Mutex<State>,
+}
+
+struct State {
+	/// The journal file, opened for appending.
+	file: File,
+	/// Current size of the journal file.
+	size: usize,
+	/// When the journal was last rewritten.
+	rotated_at: Instant,
+	/// Transactions loaded from the journal that were not resubmitted yet.
+	pending: Option<Vec<Record>>,
+	/// Records appended since the transactions of a pending rotation were collected, which are
+	/// appended again once the journal is rewritten.
+	rotating: Option<Vec<Vec<u8>>>,
+}
+
+impl State {
+	/// Append an encoded record to the journal file, unless it would exceed the maximal size.
+	fn append(&mut self, max_bytes: usize, record: &[u8]) -> bool {
+		if self.size + record.len() > max_bytes {
+			log::trace!(target: LOG_TARGET, "Journal is full, not journaling transaction");
+			return false
+		}
+
+		match self.file.write_all(record) {
+			Ok(()) => {
+				self.size += record.len();
+				true
+			},
+			Err(e) => {
+				log::warn!(target: LOG_TARGET, "Failed to append to the journal: {}", e);
+				false
+			},
+		}
+	}
+}
+
+impl Journal {
+	/// Open the journal at the configured path, loading the transactions it contains.
+	pub fn open(options: JournalOptions) -> io::Result<Self> {
+		if let Some(parent) = options.path.parent() {
+			fs::create_dir_all(parent)?;
+		}
+
+		let pending = match fs::read(&options.path) {
+			Ok(data) => decode(&data),
+			Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+			Err(e) => return Err(e),
+		};
+		log::debug!(
+			target: LOG_TARGET,
+			"Loaded {} transactions from the journal at {}",
+			pending.len(),
+			options.path.display(),
+		);
+
+		let (file, size) = write(&options, pending.iter().map(Encode::encode))?;
+		let state = State {
+			file,
+			size,
+			rotated_at: Instant::now(),
+			pending: Some(pending),
+			rotating: None,
+		};
+		Ok(Self { options, state: Mutex::new(state) })
+	}
+
+	/// Take the transactions loaded from the journal when it was opened.
+	///
+	/// Returns `None` if they were already taken.
+	pub fn take_pending(&self) -> Option<Vec<(TransactionSource, Vec<u8>)>> {
+		self.state
+			.lock()
+			.pending
+			.take()
+			.map(|records| records.into_iter().map(|r| (r.source, r.extrinsic)).collect())
+	}
+
+	/// Append a submitted transaction to the journal.
+	pub fn append(&self, source: TransactionSource, extrinsic: Vec<u8>) {
+		let record = Record { source, extrinsic }.encode().encode();
+		let mut state = self.state.lock();
+		if state.append(self.options.max_bytes, &record) {
+			if let Some(rotating) = &mut state.rotating {
+				rotating.push(record);
+			}
+		}
+	}
+
+	/// Returns true if the journal is due to be rewritten, starting the rotation if so.
+	pub fn rotation_due(&self) -> bool {
+		if self.state.lock().rotated_at.elapsed() < ROTATION_PERIOD {
+			return false
+		}
+		self.start_rotation();
+		true
+	}
+
+	/// Start rewriting the journal.
+	///
+	/// The transactions appended from now on are kept until the journal is rewritten with the
+	/// transactions of the pool collected by the caller, so that they are not lost. The ones kept
+	/// for a rotation that is still pending are not discarded.
+	pub fn start_rotation(&self) {
+		let mut state = self.state.lock();
+		state.rotated_at = Instant::now();
+		state.rotating.get_or_insert_with(Vec::new);
+	}
+
+	/// Rewrite the journal with the given transactions, followed by the transactions appended
+	/// since the rotation was due.
+	pub fn rotate(
+		&self,
+		transactions: impl IntoIterator<Item = (TransactionSource, Vec<u8>)>,
+	) -> io::Result<()> {
+		let records = transactions
+			.into_iter()
+			.map(|(source, extrinsic)| Record { source, extrinsic }.encode());
+		let written = write(&self.options, records);
+		let mut state = self.state.lock();
+		let appended = state.rotating.take().unwrap_or_default();
+		let (file, size) = written?;
+		state.file = file;
+		state.size = size;
+		for record in appended {
+			state.append(self.options.max_bytes, &record);
+		}
+		Ok(())
+	}
+}
+
+/// Transactions the journal is rewritten with.
+pub(crate) type Rotation = Vec<(TransactionSource, Vec<u8>)>;
+
+/// Task rewriting the journal with the transactions received from the pool.
+///
+/// It blocks on disk I/O and must be spawned as a blocking task.
+pub(crate) async fn rotation_task(journal: Arc<Journal>, mut rotations: mpsc::Receiver<Rotation>) {
+	while let Some(transactions) = rotations.next().await {
+		if let Err(e) = journal.rotate(transactions) {
+			log::warn!(target: LOG_TARGET, "Failed to rewrite the transaction pool journal: {}", e);
+		}
+	}
+}
+
+/// Decode the records of the journal.
+///
+/// A journal with an unknown header is ignored. Records that can't be decoded are skipped and a
+/// truncated record at the end of the journal, left behind by an interrupted write, is dropped.
+fn decode(data: &[u8]) -> Vec<Record> {
+	let mut data = match data.strip_prefix(&MAGIC[..]) {
+		Some(data) => data,
+		None => {
+			log::warn!(target: LOG_TARGET, "Ignoring journal with unknown format");
+			return Vec::new()
+		},
+	};
+
+	let mut records = Vec::new();
+	while !data.is_empty() {
+		let record = match Vec::<u8>::decode(&mut data) {
+			Ok(record) => record,
+			Err(_) => {
+				log::debug!(target: LOG_TARGET, "Dropping truncated journal record");
+				break
+			},
+		};
+
+		match Record::decode(&mut &record[..]) {
+			Ok(record) => records.push(record),
+			Err(e) => log::debug!(target: LOG_TARGET, "Skipping invalid journal record: {}", e),
+		}
+	}
+
+	records
+}
+
+/// Write the encoded records to a new journal file, which then replaces the existing one.
+///
+/// Returns the journal file opened for appending and its size.
+fn write(
+	options: &JournalOptions,
+	records: impl IntoIterator<Item = Vec<u8>>,
+) -> io::Result<(File, usize)> {
+	let tmp_path = options.path.with_extension("tmp");
+	let mut file = io::BufWriter::new(File::create(&tmp_path)?);
+	file.write_all(MAGIC)?;
+	let mut size = MAGIC.len();
+
+	for record in records {
+		let record = record.encode();
+		if size + record.len() > options.max_bytes {
+			log::debug!(target: LOG_TARGET, "Journal is full, dropping remaining transactions");
+			break
+		}
+		file.write_all(&record)?;
+		size += record.len();
+	}
+
+	file.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+	fs::rename(&tmp_path, &options.path)?;
+
+	let file = OpenOptions::new().append(true).open(&options.path)?;
+	Ok((file, size))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn options(dir: &tempfile::TempDir, max_bytes: usize) -> JournalOptions {
+		JournalOptions { path: dir.path().join("txpool").join("journal"), max_bytes }
+	}
+
+	#[test]
+	fn journal_survives_reopening() {
+		let dir = tempfile::tempdir().unwrap();
+		let journal = Journal::open(options(&dir, 1024)).unwrap();
+		assert_eq!(journal.take_pending(), Some(vec![]));
+		assert_eq!(journal.take_pending(), None);
+
+		journal.append(TransactionSource::External, vec![1, 2, 3]);
+		journal.append(TransactionSource::Local, vec![4]);
+		drop(journal);
+
+		let journal = Journal::open(options(&dir, 1024)).unwrap();
+		assert_eq!(
+			journal.take_pending(),
+			Some(vec![
+				(TransactionSource::External, vec![1, 2, 3]),
+				(TransactionSource::Local, vec![4])
+			]),
+		);
+
+		journal.rotate(vec![(TransactionSource::InBlock, vec![5])]).unwrap();
+		drop(journal);
+
+		let journal = Journal::open(options(&dir, 1024)).unwrap();
+		assert_eq!(journal.take_pending(), Some(vec![(TransactionSource::InBlock, vec![5])]));
+	}
+
+	#[test]
+	fn journal_keeps_transactions_appended_during_rotation() {
+		let dir = tempfile::tempdir().unwrap();
+		let journal = Journal::open(options(&dir, 1024)).unwrap();
+		journal.append(TransactionSource::External, vec![1]);
+		assert!(!journal.rotation_due());
+
+		// when
+		journal.state.lock().rotated_at -= ROTATION_PERIOD;
+		assert!(journal.rotation_due());
+		journal.append(TransactionSource::External, vec![2]);
+		journal.rotate(vec![(TransactionSource::External, vec![1])]).unwrap();
+		journal.append(TransactionSource::External, vec![3]);
+		drop(journal);
+
+		// then
+		let journal = Journal::open(options(&dir, 1024)).unwrap();
+		assert_eq!(
+			journal.take_pending(),
+			Some(vec![
+				(TransactionSource::External, vec![1]),
+				(TransactionSource::External, vec![2]),
+				(TransactionSource::External, vec![3])
+			]),
+		);
+	}
+
+	#[test]
+	fn journal_keeps_transactions_appended_during_first_rotation() {
+		let dir = tempfile::tempdir().unwrap();
+		let journal = Journal::open(options(&dir, 1024)).unwrap();
+		journal.append(TransactionSource::External, vec![1]);
+		drop(journal);
+		let journal = Journal::open(options(&dir, 1024)).unwrap();
+
+		// when
+		assert_eq!(journal.take_pending(), Some(vec![(TransactionSource::External, vec![1])]));
+		journal.start_rotation();
+		journal.append(TransactionSource::External, vec![2]);
+		journal.rotate(vec![(TransactionSource::External, vec![1])]).unwrap();
+		drop(journal);
+
+		// then
+		let journal = Journal::open(options(&dir, 1024)).unwrap();
+		assert_eq!(
+			journal.take_pending(),
+			Some(vec![
+				(TransactionSource::External, vec![1]),
+				(TransactionSource::External, vec![2])
+			]),
+		);
+	}
+
+	#[test]
+	fn journal_respects_size_limit_and_truncation() {
+		let dir = tempfile::tempdir().unwrap();
+		let options = options(&dir, MAGIC.len() + 2 * 13);
+		let journal = Journal::open(options.clone()).unwrap();
+
+		for i in 0..3 {
+			journal.append(TransactionSource::External, vec![i; 10]);
+		}
+		drop(journal);
+
+		// simulate an interrupted write
+		let mut file = OpenOptions::new().append(true).open(&options.path).unwrap();
+		file.write_all(&[40, 0, 1]).unwrap();
+		drop(file);
+
+		let journal = Journal::open(options).unwrap();
+		assert_eq!(
+			journal.take_pending(),
+			Some(vec![
+				(TransactionSource::External, vec![0; 10]),
+				(TransactionSource::External, vec![1; 10])
+			]),
+		);
+	}
+}
@@ -26,22 +26,26 @@ mod api;
 mod enactment_state;
 pub mod error;
 mod graph;
+mod journal;
 mod metrics;
 mod revalidation;
 #[cfg(test)]
 mod tests;
+mod view_store;
 
 pub use crate::api::FullChainApi;
 use async_trait::async_trait;
 use enactment_state::{EnactmentAction, EnactmentState};
 use futures::{
-	channel::oneshot,
-	future::{self, ready},
+	channel::{mpsc, oneshot},
+	future,
 	prelude::*,
 };
 pub use graph::{
-	base_pool::Limit as PoolLimit, ChainApi, Options, Pool, Transaction, ValidatedTransaction,
+	base_pool::{Lane, Lanes, Limit as PoolLimit, ReplacementPolicy, SenderLimits},
+	ChainApi, Options, Pool, Transaction, ValidatedTransaction,
 };
+pub use journal::JournalOptions;
 use parking_lot::Mutex;
 use std::{
 	collections::{HashMap, HashSet},
@@ -49,7 +53,9 @@ use std::{
 	sync::Arc,
 };
 
+use codec::{Decode, Encode};
 use graph::{ExtrinsicHash, IsValidator};
+use journal::{Journal, Rotation};
 use sc_transaction_pool_api::{
 	error::Error as TxPoolError, ChainEvent, ImportNotificationStream, MaintainedTransactionPool,
 	PoolFuture, PoolStatus, ReadyTransactions, TransactionFor, TransactionPool, TransactionSource,
@@ -61,6 +67,7 @@ use sp_runtime::{
 	traits::{AtLeast32Bit, Block as BlockT, Extrinsic, Header as HeaderT, NumberFor, Zero},
 };
 use std::time::Instant;
+use view_store::ViewStore;
 
 use crate::metrics::MetricsLink as PrometheusMetrics;
 use prometheus_endpoint::Registry as PrometheusRegistry;
@@ -93,6 +100,9 @@ where
 	ready_poll: Arc<Mutex<ReadyPoll<ReadyIteratorFor<PoolApi>, Block>>>,
 	metrics: PrometheusMetrics,
 	enactment_state: Arc<Mutex<EnactmentState<Block>>>,
+	journal: Option<Arc<Journal>>,
+	journal_rotations: Option<Mutex<mpsc::Sender<Rotation>>>,
+	views: Option<Arc<ViewStore<PoolApi>>>,
 }
 
 struct ReadyPoll<T, Block: BlockT> {
@@ -180,6 +190,9 @@ where
 					best_block_hash,
 					finalized_hash,
 				))),
+				journal: None,
+				journal_rotations: None,
+				views: None,
 			},
 			background_task,
 		)
@@ -198,7 +211,36 @@ where
 		best_block_hash: Block::Hash,
 		finalized_hash: Block::Hash,
 	) -> Self {
+		let journal = options.journal.clone().and_then(|options| {
+			let path = options.path.clone();
+			Journal::open(options)
+				.map_err(|e| {
+					log::warn!(
+						target: LOG_TARGET,
+						"Failed to open the transaction pool journal at {}: {}",
+						path.display(),
+						e,
+					)
+				})
+				.ok()
+				.map(Arc::new)
+		});
+		let journal_rotations = journal.clone().map(|journal| {
+			let (sender, receiver) = mpsc::channel(1);
+			spawner.spawn_essential_blocking(
+				"txpool-journal",
+				Some("transaction-pool"),
+				journal::rotation_task(journal, receiver).boxed(),
+			);
+			Mutex::new(sender)
+		});
+		let views_options = options.fork_aware.then(|| options.clone());
 		let pool = Arc::new(graph::Pool::new(options, is_validator, pool_api.clone()));
+		let views = views_options.map(|options| {
+			let (views, background_task) = ViewStore::new(pool_api.clone(), pool.clone(), &options);
+			spawner.spawn_essential("txpool-views", Some("transaction-pool"), background_task);
+			views
+		});
 		let (revalidation_queue, background_task) = match revalidation_type {
 			RevalidationType::Light =>
 				(revalidation::RevalidationQueue::new(pool_api.clone(), pool.clone()), None),
@@ -228,6 +270,9 @@ where
 				best_block_hash,
 				finalized_hash,
 			))),
+			journal,
+			journal_rotations,
+			views,
 		}
 	}
 
@@ -259,12 +304,29 @@ where
 		xts: Vec<TransactionFor<Self>>,
 	) -> PoolFuture<Vec<Result<TxHash<Self>, Self::Error>>, Self::Error> {
 		let pool = self.pool.clone();
+		let journal = self.journal.clone();
 		let at = *at;
 
 		self.metrics
 			.report(|metrics| metrics.submitted_transactions.inc_by(xts.len() as u64));
 
-		async move { pool.submit_at(&at, source, xts).await }.boxed()
+		let encoded = journal.as_ref().map(|_| xts.iter().map(Encode::encode).collect::<Vec<_>>());
+		if let Some(ref views) = self.views {
+			views.submit(source, xts.clone());
+		}
+
+		async move {
+			let results = pool.submit_at(&at, source, xts).await?;
+			if let (Some(journal), Some(encoded)) = (journal, encoded) {
+				for (result, xt) in results.iter().zip(encoded) {
+					if result.is_ok() {
+						journal.append(source, xt);
+					}
+				}
+			}
+			Ok(results)
+		}
+		.boxed()
 	}
 
 	fn submit_one(
@@ -274,11 +336,24 @@ where
 		xt: TransactionFor<Self>,
 	) -> PoolFuture<TxHash<Self>, Self::Error> {
 		let pool = self.pool.clone();
+		let journal = self.journal.clone();
 		let at = *at;
 
 		self.metrics.report(|metrics| metrics.submitted_transactions.inc());
 
-		async move { pool.submit_one(&at, source, xt).await }.boxed()
+		let encoded = journal.as_ref().map(|_| xt.encode());
+		if let Some(ref views) = self.views {
+			views.submit(source, vec![xt.clone()]);
+		}
+
+		async move {
+			let hash = pool.submit_one(&at, source, xt).await?;
+			if let (Some(journal), Some(encoded)) = (journal, encoded) {
+				journal.append(source, encoded);
+			}
+			Ok(hash)
+		}
+		.boxed()
 	}
 
 	fn submit_and_watch(
@@ -289,11 +364,20 @@ where
 	) -> PoolFuture<Pin<Box<TransactionStatusStreamFor<Self>>>, Self::Error> {
 		let at = *at;
 		let pool = self.pool.clone();
+		let journal = self.journal.clone();
 
 		self.metrics.report(|metrics| metrics.submitted_transactions.inc());
 
+		let encoded = journal.as_ref().map(|_| xt.encode());
+		if let Some(ref views) = self.views {
+			views.submit(source, vec![xt.clone()]);
+		}
+
 		async move {
 			let watcher = pool.submit_and_watch(&at, source, xt).await?;
+			if let (Some(journal), Some(encoded)) = (journal, encoded) {
+				journal.append(source, encoded);
+			}
 
 			Ok(watcher.into_stream().boxed())
 		}
@@ -307,6 +391,14 @@ where
 		removed
 	}
 
+	fn cancel(&self, hash: &TxHash<Self>) -> Vec<Arc<Self::InPoolTransaction>> {
+		let cancelled = self.pool.validated_pool().cancel(&[*hash]);
+		if let Some(ref views) = self.views {
+			views.cancel(hash);
+		}
+		cancelled
+	}
+
 	fn status(&self) -> PoolStatus {
 		self.pool.validated_pool().status()
 	}
@@ -327,6 +419,10 @@ where
 		self.pool.validated_pool().ready_by_hash(hash)
 	}
 
+	fn pending_transaction(&self, hash: &TxHash<Self>) -> Option<Arc<Self::InPoolTransaction>> {
+		self.pool.validated_pool().by_hash(hash)
+	}
+
 	fn ready_at(&self, at: NumberFor<Self::Block>) -> PolledIterator<PoolApi> {
 		let status = self.status();
 		// If there are no transactions in the pool, it is fine to return early.
@@ -355,6 +451,35 @@ where
 			.boxed()
 	}
 
+	fn ready_at_block(
+		&self,
+		at: <Self::Block as BlockT>::Hash,
+		number: NumberFor<Self::Block>,
+	) -> PolledIterator<PoolApi> {
+		let views = match self.views {
+			Some(ref views) => views,
+			None => return self.ready_at(number),
+		};
+
+		if let Some(iterator) = views.ready_at(&at) {
+			return async move { iterator }.boxed()
+		}
+
+		if self.enactment_state.lock().recent_best_block() == at {
+			return self.ready_at(number)
+		}
+
+		views
+			.add_poller(at, number)
+			.map(|received| {
+				received.unwrap_or_else(|e| {
+					log::warn!("Error receiving pending set: {:?}", e);
+					Box::new(std::iter::empty())
+				})
+			})
+			.boxed()
+	}
+
 	fn ready(&self) -> ReadyIteratorFor<PoolApi> {
 		Box::new(self.pool.validated_pool().ready())
 	}
@@ -539,6 +664,33 @@ impl<N: Clone + Copy + AtLeast32Bit> RevalidationStatus<N> {
 	}
 }
 
+/// Submit the transactions to the pool, validating them at the given block.
+///
+/// Consecutive transactions sharing the same source are submitted in a single batch. Returns the
+/// number of imported transactions.
+async fn submit_batched<Api: graph::ChainApi>(
+	pool: &graph::Pool<Api>,
+	at: graph::BlockHash<Api>,
+	transactions: impl IntoIterator<Item = (TransactionSource, graph::ExtrinsicFor<Api>)>,
+) -> usize {
+	let mut imported = 0;
+	let mut transactions = transactions.into_iter().peekable();
+	while let Some((source, xt)) = transactions.next() {
+		let xts = std::iter::once(xt)
+			.chain(std::iter::from_fn(|| {
+				transactions.next_if(|(s, _)| *s == source).map(|(_, xt)| xt)
+			}))
+			.collect::<Vec<_>>();
+
+		match pool.submit_at(&BlockId::Hash(at), source, xts).await {
+			Ok(results) => imported += results.iter().filter(|r| r.is_ok()).count(),
+			Err(e) =>
+				log::debug!(target: LOG_TARGET, "[{:?}] Error submitting transactions: {}", at, e),
+		}
+	}
+	imported
+}
+
 /// Prune the known txs for the given block.
 async fn prune_known_txs_for_block<Block: BlockT, Api: graph::ChainApi<Block = Block>>(
 	block_hash: Block::Hash,
@@ -715,6 +867,150 @@ where
 			self.revalidation_strategy.lock().clear();
 		}
 	}
+
+	/// Handles enactment in fork-aware mode.
+	///
+	/// The transactions of the views at the enacted blocks are moved to the main pool and, on a
+	/// re-org, the previous best block becomes a fork head with a view of its own.
+	async fn handle_enactment_with_views(
+		&self,
+		views: &ViewStore<PoolApi>,
+		prev_best_block: Block::Hash,
+		tree_route: TreeRoute<Block>,
+	) {
+		let best_block = match tree_route.last() {
+			Some(HashAndNumber { hash, .. }) => *hash,
+			None => return self.handle_enactment(tree_route).await,
+		};
+		let retracted = (!tree_route.retracted().is_empty())
+			.then(|| self.pool.validated_pool().validated_transactions());
+		let enacted = tree_route
+			.enacted()
+			.iter()
+			.filter_map(|block| views.remove(&block.hash))
+			.flatten()
+			.collect::<Vec<_>>();
+
+		self.handle_enactment(tree_route).await;
+
+		if !enacted.is_empty() {
+			let total = enacted.len();
+			let imported = submit_batched(&self.pool, best_block, enacted).await;
+			log::debug!(
+				target: LOG_TARGET,
+				"[{:?}] Moved {}/{} transactions from the views of the enacted blocks",
+				best_block,
+				imported,
+				total,
+			);
+		}
+
+		if let Some(transactions) = retracted {
+			match self.api.block_header(prev_best_block) {
+				Ok(Some(header)) => {
+					let at = HashAndNumber { hash: prev_best_block, number: *header.number() };
+					// The transactions were validated at the previous best block.
+					views.insert(at, *header.parent_hash(), Vec::new(), || transactions).await;
+				},
+				Ok(None) => {
+					log::debug!(target: LOG_TARGET, "Could not find header for {:?}.", prev_best_block)
+				},
+				Err(e) => log::debug!(
+					target: LOG_TARGET,
+					"Error retrieving header for {:?}: {}",
+					prev_best_block,
+					e,
+				),
+			}
+		}
+
+		views.trigger(best_block, || Box::new(self.pool.validated_pool().ready()));
+	}
+
+	/// Creates the view at a newly imported block that is not the new best block.
+	async fn insert_view(&self, views: &ViewStore<PoolApi>, hash: Block::Hash) {
+		if views.contains_view(&hash) || self.enactment_state.lock().recent_best_block() == hash {
+			return
+		}
+
+		let header = match self.api.block_header(hash) {
+			Ok(Some(header)) => header,
+			Ok(None) => {
+				log::debug!(target: LOG_TARGET, "Could not find header for {:?}.", hash);
+				return
+			},
+			Err(e) => {
+				log::debug!(target: LOG_TARGET, "Error retrieving header for {:?}: {}", hash, e);
+				return
+			},
+		};
+
+		let finalized = self.enactment_state.lock().recent_finalized_block();
+		match self.api.block_id_to_number(&BlockId::Hash(finalized)) {
+			Ok(Some(number)) if *header.number() > number => {},
+			_ => return,
+		}
+
+		let extrinsics = match self.api.block_body(hash).await {
+			Ok(extrinsics) => extrinsics.unwrap_or_default(),
+			Err(e) => {
+				log::debug!(target: LOG_TARGET, "Error retrieving body for {:?}: {}", hash, e);
+				return
+			},
+		};
+
+		let at = HashAndNumber { hash, number: *header.number() };
+		views
+			.insert(at, *header.parent_hash(), extrinsics, || {
+				self.pool.validated_pool().validated_transactions()
+			})
+			.await;
+	}
+
+	/// Drops the views of the forks abandoned by finality and settles the held back events.
+	fn finalize_views(&self, views: &ViewStore<PoolApi>, finalized: Block::Hash) {
+		let number = match self.api.block_id_to_number(&BlockId::Hash(finalized)) {
+			Ok(Some(number)) => number,
+			_ => return,
+		};
+
+		views.on_finalized(number, |head| {
+			self.api
+				.tree_route(finalized, *head)
+				.map(|tree_route| tree_route.retracted().is_empty())
+				.unwrap_or(false)
+		});
+		self.pool.validated_pool().settle_events(|hash| views.contains(hash));
+	}
+
+	/// Resubmits the transactions loaded from the journal on the first call and afterwards
+	/// periodically rewrites the journal with the content of the pool.
+	async fn maintain_journal(&self, journal: &Journal, best_block: Block::Hash) {
+		if let Some(pending) = journal.take_pending() {
+			let total = pending.len();
+			let transactions = pending.into_iter().filter_map(|(source, xt)| {
+				graph::ExtrinsicFor::<PoolApi>::decode(&mut &xt[..]).ok().map(|xt| (source, xt))
+			});
+			let resubmitted = submit_batched(&self.pool, best_block, transactions).await;
+			log::info!(
+				target: LOG_TARGET,
+				"Resubmitted {}/{} transactions from the journal",
+				resubmitted,
+				total,
+			);
+			journal.start_rotation();
+		} else if !journal.rotation_due() {
+			return
+		}
+
+		let Some(rotations) = &self.journal_rotations else { return };
+		let transactions = self.pool.validated_pool().transactions_with_source();
+		let transactions =
+			transactions.into_iter().map(|(source, xt)| (source, xt.encode())).collect();
+		if rotations.lock().try_send(transactions).is_err() {
+			log::debug!(target: LOG_TARGET, "Previous journal rotation pending, skipping");
+		}
+	}
 }
 
 #[async_trait]
@@ -724,6 +1020,17 @@ where
 	PoolApi: 'static + graph::ChainApi<Block = Block>,
 {
 	async fn maintain(&self, event: ChainEvent<Self::Block>) {
+		let best_block = match &event {
+			ChainEvent::NewBestBlock { hash, .. } => Some(*hash),
+			ChainEvent::Finalized { .. } => None,
+			ChainEvent::NewBlock { hash } => {
+				if let Some(ref views) = self.views {
+					self.insert_view(views, *hash).await;
+				}
+				return
+			},
+		};
+		let prev_best_block = self.enactment_state.lock().recent_best_block();
 		let prev_finalized_block = self.enactment_state.lock().recent_finalized_block();
 		let compute_tree_route = |from, to| -> Result<TreeRoute<Block>, String> {
 			match self.api.tree_route(from, to) {
@@ -749,8 +1056,10 @@ where
 			},
 			Ok(EnactmentAction::Skip) => return,
 			Ok(EnactmentAction::HandleFinalization) => {},
-			Ok(EnactmentAction::HandleEnactment(tree_route)) => {
-				self.handle_enactment(tree_route).await;
+			Ok(EnactmentAction::HandleEnactment(tree_route)) => match self.views {
+				Some(ref views) =>
+					self.handle_enactment_with_views(views, prev_best_block, tree_route).await,
+				None => self.handle_enactment(tree_route).await,
 			},
 		};
 
@@ -770,6 +1079,14 @@ where
 					)
 				}
 			}
+
+			if let Some(ref views) = self.views {
+				self.finalize_views(views, hash);
+			}
+		}
+
+		if let (Some(journal), Some(best_block)) = (&self.journal, best_block) {
+			self.maintain_journal(journal, best_block).await;
 		}
 	}
 }
@@ -783,7 +1100,10 @@ where
 {
 	let import_stream = client
 		.import_notification_stream()
-		.filter_map(|n| ready(n.try_into().ok()))
+		.map(|n| {
+			let hash = n.hash;
+			n.try_into().unwrap_or(ChainEvent::NewBlock { hash })
+		})
 		.fuse();
 	let finality_stream = client.finality_notification_stream().map(Into::into).fuse();
 
@@ -0,0 +1,283 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Validated views of the transaction pool at the heads of the non-best forks.
+//!
+//! The main pool follows the best block. In fork-aware mode every other fork head that is
+//! imported gets its own view. A view starts with the already validated transactions of the
+//! view of the parent, or of the main pool, minus the transactions included in the new head or
+//! made stale by it, so that the transactions are not revalidated on every imported block. A view
+//! is replaced by the view of its child when the fork is extended, and is dropped once its fork
+//! becomes the best chain or is abandoned by finality.
+//!
+//! The transactions submitted to the pool are validated at the fork heads by a background task.
+//! Transactions banned by the main pool are never imported to the views.
+
+use crate::{
+	graph::{
+		self, BlockHash, ExtrinsicFor, ExtrinsicHash, NumberFor, ValidatedTransaction,
+		ValidatedTransactionFor,
+	},
+	submit_batched, ReadyIteratorFor, LOG_TARGET,
+};
+use futures::{channel::oneshot, future, Future, FutureExt, StreamExt};
+use parking_lot::Mutex;
+use sc_transaction_pool_api::TransactionSource;
+use sc_utils::mpsc::{tracing_unbounded, TracingUnboundedReceiver, TracingUnboundedSender};
+use sp_blockchain::HashAndNumber;
+use sp_runtime::generic::BlockId;
+use std::{
+	collections::HashMap,
+	pin::Pin,
+	sync::{Arc, Weak},
+};
+
+/// Maximal number of views kept at the same time.
+const MAX_VIEWS: usize = 16;
+
+/// The transaction pool validated at a fork head.
+struct View<PoolApi: graph::ChainApi> {
+	at: HashAndNumber<PoolApi::Block>,
+	pool: graph::Pool<PoolApi>,
+}
+
+/// Requests for the ready transactions at a block that is not processed yet.
+type Pollers<PoolApi> = Vec<(NumberFor<PoolApi>, oneshot::Sender<ReadyIteratorFor<PoolApi>>)>;
+
+/// Transactions submitted to the pool, to be validated at the fork heads.
+type Submission<PoolApi> = (TransactionSource, Vec<ExtrinsicFor<PoolApi>>);
+
+/// The views of the pool at the heads of the non-best forks.
+pub(crate) struct ViewStore<PoolApi: graph::ChainApi> {
+	api: Arc<PoolApi>,
+	/// The main pool, whose banned transactions are kept out of the views.
+	pool: Arc<graph::Pool<PoolApi>>,
+	options: graph::Options,
+	views: Mutex<HashMap<BlockHash<PoolApi>, Arc<View<PoolApi>>>>,
+	pollers: Mutex<HashMap<BlockHash<PoolApi>, Pollers<PoolApi>>>,
+	to_background: TracingUnboundedSender<Submission<PoolApi>>,
+}
+
+impl<PoolApi> ViewStore<PoolApi>
+where
+	PoolApi: graph::ChainApi + 'static,
+{
+	/// Create a new, empty, view store using the options of the main pool, along with the
+	/// background task validating the submitted transactions at the fork heads.
+	pub fn new(
+		api: Arc<PoolApi>,
+		pool: Arc<graph::Pool<PoolApi>>,
+		options: &graph::Options,
+	) -> (Arc<Self>, Pin<Box<dyn Future<Output = ()> + Send>>) {
+		// The views don't have watchers, so there is nothing to settle, and only the main pool
+		// journals transactions.
+		let options = graph::Options { journal: None, fork_aware: false, ..options.clone() };
+		let (to_background, from_pool) = tracing_unbounded("mpsc_txpool_views", 100_000);
+		let store = Arc::new(Self {
+			api,
+			pool,
+			options,
+			views: Default::default(),
+			pollers: Default::default(),
+			to_background,
+		});
+		(store.clone(), Self::run(Arc::downgrade(&store), from_pool).boxed())
+	}
+
+	/// Validate the submitted transactions at the fork heads, until the store is dropped.
+	async fn run(store: Weak<Self>, mut from_pool: TracingUnboundedReceiver<Submission<PoolApi>>) {
+		while let Some((source, xts)) = from_pool.next().await {
+			let Some(store) = store.upgrade() else { return };
+			let views = store.views.lock().values().cloned().collect::<Vec<_>>();
+			let transactions = xts.into_iter().map(|xt| (source, xt)).collect::<Vec<_>>();
+			future::join_all(views.iter().map(|view| store.submit_to(view, transactions.clone())))
+				.await;
+		}
+	}
+
+	/// Returns true if the transaction is banned by the main pool.
+	fn is_banned(&self, hash: &ExtrinsicHash<PoolApi>) -> bool {
+		self.pool.validated_pool().is_banned(hash)
+	}
+
+	/// Submit the transactions that are not banned by the main pool to the view, validating
+	/// them at its head.
+	async fn submit_to(
+		&self,
+		view: &View<PoolApi>,
+		transactions: Vec<(TransactionSource, ExtrinsicFor<PoolApi>)>,
+	) -> usize {
+		let transactions = transactions
+			.into_iter()
+			.filter(|(_, xt)| !self.is_banned(&self.pool.hash_of(xt)));
+		submit_batched(&view.pool, view.at.hash, transactions).await
+	}
+
+	/// Returns true if there is a view at the given block.
+	pub fn contains_view(&self, at: &BlockHash<PoolApi>) -> bool {
+		self.views.lock().contains_key(at)
+	}
+
+	/// Returns true if the transaction is in any of the views.
+	pub fn contains(&self, hash: &ExtrinsicHash<PoolApi>) -> bool {
+		self.views.lock().values().any(|view| view.pool.validated_pool().contains(hash))
+	}
+
+	/// Create the view at the given fork head, containing the given extrinsics.
+	///
+	/// The view of the parent is replaced, its transactions are carried over without being
+	/// revalidated. Without a view of the parent, the given transactions are used instead. The
+	/// transactions included in the new head, or made stale by it, are then removed.
+	pub async fn insert(
+		&self,
+		at: HashAndNumber<PoolApi::Block>,
+		parent: BlockHash<PoolApi>,
+		extrinsics: Vec<ExtrinsicFor<PoolApi>>,
+		transactions: impl FnOnce() -> Vec<ValidatedTransactionFor<PoolApi>>,
+	) {
+		let transactions = match self.views.lock().remove(&parent) {
+			Some(view) => view.pool.validated_pool().validated_transactions(),
+			None => transactions(),
+		};
+
+		let pool = graph::Pool::new(self.options.clone(), true.into(), self.api.clone());
+		let total = transactions.len();
+		pool.validated_pool().submit(transactions.into_iter().filter(|tx| match tx {
+			ValidatedTransaction::Valid(tx) => !self.is_banned(&tx.hash),
+			_ => false,
+		}));
+		let at_id = BlockId::Hash(at.hash);
+		if let Err(e) = pool.prune(&at_id, &BlockId::Hash(parent), &extrinsics).await {
+			log::debug!(target: LOG_TARGET, "[{:?}] Error pruning view: {}", at.hash, e);
+		}
+		if let Err(e) = pool.validated_pool().clear_stale(&at_id) {
+			log::debug!(target: LOG_TARGET, "[{:?}] Error clearing stale view: {}", at.hash, e);
+		}
+		let status = pool.validated_pool().status();
+		log::debug!(
+			target: LOG_TARGET,
+			"Created view at {:?} with {}/{} transactions",
+			at.hash,
+			status.ready + status.future,
+			total,
+		);
+
+		let view = Arc::new(View { at, pool });
+		{
+			let mut views = self.views.lock();
+			views.insert(view.at.hash, view.clone());
+			while views.len() > MAX_VIEWS {
+				let oldest = views
+					.values()
+					.min_by_key(|view| view.at.number)
+					.map(|view| view.at.hash)
+					.expect("There are more than `MAX_VIEWS` views; qed");
+				log::debug!(target: LOG_TARGET, "Dropping view at {:?}, too many views", oldest);
+				views.remove(&oldest);
+			}
+		}
+
+		self.trigger(view.at.hash, || Box::new(view.pool.validated_pool().ready()));
+	}
+
+	/// Remove the view at the given block, returning its transactions.
+	pub fn remove(
+		&self,
+		at: &BlockHash<PoolApi>,
+	) -> Option<Vec<(TransactionSource, ExtrinsicFor<PoolApi>)>> {
+		self.views
+			.lock()
+			.remove(at)
+			.map(|view| view.pool.validated_pool().transactions_with_source())
+	}
+
+	/// Submit the transactions to all the views.
+	///
+	/// Returns immediately, the transactions are validated at the fork heads in the background.
+	pub fn submit(&self, source: TransactionSource, xts: Vec<ExtrinsicFor<PoolApi>>) {
+		// Views created later start with the transactions of the main pool.
+		if self.views.lock().is_empty() {
+			return
+		}
+
+		if let Err(e) = self.to_background.unbounded_send((source, xts)) {
+			log::warn!(target: LOG_TARGET, "Failed to submit to the views: {:?}", e);
+		}
+	}
+
+	/// Remove the transaction, and the transactions depending on it, from all the views.
+	pub fn cancel(&self, hash: &ExtrinsicHash<PoolApi>) {
+		for view in self.views.lock().values() {
+			view.pool.validated_pool().cancel(&[*hash]);
+		}
+	}
+
+	/// Returns the ready transactions of the view at the given block, if any.
+	pub fn ready_at(&self, at: &BlockHash<PoolApi>) -> Option<ReadyIteratorFor<PoolApi>> {
+		self.views
+			.lock()
+			.get(at)
+			.map(|view| Box::new(view.pool.validated_pool().ready()) as ReadyIteratorFor<PoolApi>)
+	}
+
+	/// Wait for the ready transactions at the given block.
+	///
+	/// Resolved once the block becomes a fork head or the best block, see [`Self::trigger`].
+	pub fn add_poller(
+		&self,
+		at: BlockHash<PoolApi>,
+		number: NumberFor<PoolApi>,
+	) -> oneshot::Receiver<ReadyIteratorFor<PoolApi>> {
+		let (sender, receiver) = oneshot::channel();
+		self.pollers.lock().entry(at).or_default().push((number, sender));
+		receiver
+	}
+
+	/// Resolve the requests for the ready transactions at the given block.
+	pub fn trigger(
+		&self,
+		at: BlockHash<PoolApi>,
+		iterator_factory: impl Fn() -> ReadyIteratorFor<PoolApi>,
+	) {
+		for (_, sender) in self.pollers.lock().remove(&at).unwrap_or_default() {
+			let _ = sender.send(iterator_factory());
+		}
+	}
+
+	/// Drop the views and the pending requests that can't be extended on top of the finalized
+	/// block.
+	///
+	/// `is_descendant` tells whether a fork head descends from the finalized block.
+	pub fn on_finalized(
+		&self,
+		finalized: NumberFor<PoolApi>,
+		is_descendant: impl Fn(&BlockHash<PoolApi>) -> bool,
+	) {
+		self.views.lock().retain(|hash, view| {
+			let keep = view.at.number > finalized && is_descendant(hash);
+			if !keep {
+				log::debug!(target: LOG_TARGET, "Dropping view at {:?}, fork abandoned", hash);
+			}
+			keep
+		});
+		self.pollers.lock().retain(|_, pollers| {
+			pollers.retain(|(number, _)| *number >= finalized);
+			!pollers.is_empty()
+		});
+	}
+}
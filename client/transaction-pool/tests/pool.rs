@@ -37,7 +37,12 @@ use sp_runtime::{
 	traits::Block as _,
 	transaction_validity::{TransactionSource, ValidTransaction},
 };
-use std::{collections::BTreeSet, pin::Pin, sync::Arc};
+use std::{
+	collections::BTreeSet,
+	pin::Pin,
+	sync::Arc,
+	time::{Duration, Instant},
+};
 use substrate_test_runtime_client::{
 	runtime::{Block, Extrinsic, ExtrinsicBuilder, Hash, Header, Index, Transfer, TransferData},
 	AccountKeyring::*,
@@ -366,6 +371,34 @@ fn should_not_retain_invalid_hashes_from_retracted() {
 	assert_eq!(pool.status().ready, 0);
 }
 
+#[test]
+fn should_notify_watchers_of_cancelled_transactions() {
+	let xt1 = uxt(Alice, 209);
+	let xt2 = uxt(Alice, 210);
+
+	let (pool, _api, _guard) = maintained_pool();
+
+	let watcher1 = block_on(pool.submit_and_watch(&BlockId::number(0), SOURCE, xt1.clone()))
+		.expect("1. Imported");
+	let watcher2 = block_on(pool.submit_and_watch(&BlockId::number(0), SOURCE, xt2.clone()))
+		.expect("2. Imported");
+	assert_eq!(pool.status().ready, 2);
+
+	let cancelled = pool.cancel(&pool.hash_of(&xt1));
+
+	assert_eq!(cancelled.len(), 2);
+	assert_eq!(pool.status().ready, 0);
+	assert_eq!(
+		futures::executor::block_on_stream(watcher1).collect::<Vec<_>>(),
+		vec![TransactionStatus::Ready, TransactionStatus::Cancelled],
+	);
+	assert_eq!(
+		futures::executor::block_on_stream(watcher2).collect::<Vec<_>>(),
+		vec![TransactionStatus::Ready, TransactionStatus::Cancelled],
+	);
+	assert!(block_on(pool.submit_one(&BlockId::number(0), SOURCE, xt1)).is_err());
+}
+
 #[test]
 fn should_revalidate_across_many_blocks() {
 	let xt1 = uxt(Alice, 209);
@@ -966,6 +999,155 @@ fn import_notification_to_pool_maintain_works() {
 	assert_eq!(pool.status().ready, 0);
 }
 
+#[test]
+fn journaled_transactions_are_revalidated_after_restart() {
+	let dir = tempfile::tempdir().unwrap();
+	let options = Options {
+		journal: Some(JournalOptions { path: dir.path().join("journal"), max_bytes: 1024 * 1024 }),
+		..Default::default()
+	};
+	let api = Arc::new(TestApi::with_alice_nonce(209));
+	let genesis_hash = api.chain().read().block_by_number[&0][0].0.header.hash();
+	let new_pool = || {
+		BasicPool::with_revalidation_type(
+			options.clone(),
+			true.into(),
+			api.clone(),
+			None,
+			RevalidationType::Light,
+			sp_core::testing::TaskExecutor::new(),
+			0,
+			genesis_hash,
+			genesis_hash,
+		)
+	};
+
+	let pool = new_pool();
+	block_on(pool.submit_at(
+		&BlockId::number(0),
+		SOURCE,
+		vec![uxt(Alice, 209), uxt(Alice, 210), uxt(Alice, 211)],
+	))
+	.unwrap();
+	assert_eq!(pool.status().ready, 3);
+	drop(pool);
+
+	// when
+	api.add_invalid(&uxt(Alice, 211));
+	let pool = new_pool();
+	assert_eq!(pool.status().ready, 0);
+	let header = api.push_block(1, vec![], true);
+	block_on(pool.maintain(block_event(header)));
+
+	// then
+	assert_eq!(pool.status().ready, 2);
+	assert_eq!(pool.status().future, 0);
+	assert!(!pool.ready().any(|tx| tx.data == uxt(Alice, 211)));
+}
+
+fn fork_aware_pool(api: Arc<TestApi>) -> BasicPool<TestApi, Block> {
+	let genesis_hash = api.chain().read().block_by_number[&0][0].0.header.hash();
+	BasicPool::with_revalidation_type(
+		Options { fork_aware: true, ..Default::default() },
+		true.into(),
+		api,
+		None,
+		RevalidationType::Light,
+		sp_core::testing::TaskExecutor::new(),
+		0,
+		genesis_hash,
+		genesis_hash,
+	)
+}
+
+fn ready_at_block(pool: &BasicPool<TestApi, Block>, header: &Header) -> Vec<Extrinsic> {
+	block_on(pool.ready_at_block(header.hash(), header.number))
+		.map(|tx| tx.data.clone())
+		.collect()
+}
+
+/// Waits for the background tasks of the pool to fulfill the condition.
+fn wait_for(condition: impl Fn() -> bool) {
+	for _ in 0..100 {
+		if condition() {
+			return
+		}
+		std::thread::sleep(Duration::from_millis(50));
+	}
+	panic!("Condition not fulfilled in time");
+}
+
+#[test]
+fn fork_aware_pool_creates_views_at_fork_heads() {
+	let api = Arc::new(TestApi::with_alice_nonce(209));
+	let pool = fork_aware_pool(api.clone());
+	let genesis_hash = api.chain().read().block_by_number[&0][0].0.header.hash();
+	block_on(pool.submit_at(&BlockId::number(0), SOURCE, vec![uxt(Alice, 209), uxt(Alice, 210)]))
+		.unwrap();
+	let best = api.push_block(1, vec![], true);
+	block_on(pool.maintain(block_event(best.clone())));
+
+	// when
+	let validation_requests = api.validation_requests().len();
+	let fork = api.push_block_with_parent(genesis_hash, vec![uxt(Alice, 209)], false);
+	block_on(pool.maintain(ChainEvent::NewBlock { hash: fork.hash() }));
+
+	// then
+	assert_eq!(api.validation_requests().len(), validation_requests);
+	assert_eq!(ready_at_block(&pool, &fork), vec![uxt(Alice, 210)]);
+	assert_eq!(ready_at_block(&pool, &best), vec![uxt(Alice, 209), uxt(Alice, 210)]);
+	assert_eq!(pool.status().ready, 2);
+}
+
+#[test]
+fn fork_aware_pool_keeps_banned_transactions_out_of_views() {
+	let api = Arc::new(TestApi::with_alice_nonce(209));
+	let pool = fork_aware_pool(api.clone());
+	let genesis_hash = api.chain().read().block_by_number[&0][0].0.header.hash();
+	block_on(pool.submit_at(&BlockId::number(0), SOURCE, vec![uxt(Alice, 209), uxt(Alice, 210)]))
+		.unwrap();
+	let best = api.push_block(1, vec![], true);
+	block_on(pool.maintain(block_event(best)));
+	let fork = api.push_block_with_parent(genesis_hash, vec![uxt(Alice, 209)], false);
+	block_on(pool.maintain(ChainEvent::NewBlock { hash: fork.hash() }));
+
+	// when
+	let banned = pool.hash_of(&uxt(Alice, 210));
+	pool.pool().validated_pool().ban(&Instant::now(), vec![banned]);
+	// transactions are only validated at the numbers of the best chain
+	api.push_block(2, vec![], true);
+	let fork = api.push_block_with_parent(fork.hash(), vec![], false);
+	block_on(pool.maintain(ChainEvent::NewBlock { hash: fork.hash() }));
+	block_on(pool.submit_one(&BlockId::number(0), SOURCE, uxt(Alice, 210))).unwrap_err();
+	block_on(pool.submit_one(&BlockId::number(0), SOURCE, uxt(Bob, 0))).unwrap();
+
+	// then
+	wait_for(|| !ready_at_block(&pool, &fork).is_empty());
+	assert_eq!(ready_at_block(&pool, &fork), vec![uxt(Bob, 0)]);
+}
+
+#[test]
+fn fork_aware_pool_moves_transactions_on_reorg() {
+	let api = Arc::new(TestApi::with_alice_nonce(209));
+	let pool = fork_aware_pool(api.clone());
+	let genesis_hash = api.chain().read().block_by_number[&0][0].0.header.hash();
+	block_on(pool.submit_at(&BlockId::number(0), SOURCE, vec![uxt(Alice, 209), uxt(Alice, 210)]))
+		.unwrap();
+	let old_best = api.push_block(1, vec![], true);
+	block_on(pool.maintain(block_event(old_best.clone())));
+	let fork = api.push_block_with_parent(genesis_hash, vec![uxt(Alice, 209)], false);
+	block_on(pool.maintain(ChainEvent::NewBlock { hash: fork.hash() }));
+
+	// when
+	let new_best = api.push_block_with_parent(fork.hash(), vec![], true);
+	block_on(pool.maintain(block_event(new_best.clone())));
+
+	// then
+	assert_eq!(ready_at_block(&pool, &new_best), vec![uxt(Alice, 210)]);
+	assert_eq!(ready_at_block(&pool, &old_best), vec![uxt(Alice, 209), uxt(Alice, 210)]);
+	assert_eq!(pool.status().ready, 1);
+}
+
 // When we prune transactions, we need to make sure that we remove
 #[test]
 fn pruning_a_transaction_should_remove_it_from_best_transaction() {
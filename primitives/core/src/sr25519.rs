@@ -549,7 +549,21 @@ pub mod vrf {
 
 	/// Transcript ready to be used for VRF related operations.
 	#[derive(Clone)]
-	pub struct VrfTranscript(pub merlin::Transcript);
+	pub struct VrfTranscript(pub merlin::Transcript, #[cfg(feature = "std")] VrfTranscriptData);
+
+	/// The label and `(domain, message)` items a [`VrfTranscript`] was built from.
+	///
+	/// A merlin transcript can't be serialized, this allows the same transcript to be rebuilt
+	/// out of process (e.g. by a remote signer). Only kept with `std`, so that the runtime
+	/// doesn't copy the items.
+	#[cfg(feature = "std")]
+	#[derive(Clone, Debug, PartialEq, Eq)]
+	pub struct VrfTranscriptData {
+		/// Transcript label.
+		pub label: &'static [u8],
+		/// Transcript `(domain, message)` items.
+		pub items: Vec<(&'static [u8], Vec<u8>)>,
+	}
 
 	impl VrfTranscript {
 		/// Build a new transcript instance.
@@ -558,7 +572,21 @@ pub mod vrf {
 		pub fn new(label: &'static [u8], data: &[(&'static [u8], &[u8])]) -> Self {
 			let mut transcript = merlin::Transcript::new(label);
 			data.iter().for_each(|(l, b)| transcript.append_message(l, b));
-			VrfTranscript(transcript)
+			#[cfg(feature = "std")]
+			{
+				let items = data.iter().map(|(l, b)| (*l, b.to_vec())).collect();
+				VrfTranscript(transcript, VrfTranscriptData { label, items })
+			}
+			#[cfg(not(feature = "std"))]
+			{
+				VrfTranscript(transcript)
+			}
+		}
+
+		/// The label and items the transcript was built from.
+		#[cfg(feature = "std")]
+		pub fn data(&self) -> &VrfTranscriptData {
+			&self.1
 		}
 
 		/// Map transcript to `VrfSignData`.
@@ -609,6 +637,11 @@ pub mod vrf {
 			self.extra = Some(extra);
 			self
 		}
+
+		/// Extra data to be signed, if any.
+		pub fn extra(&self) -> Option<&VrfTranscript> {
+			self.extra.as_ref()
+		}
 	}
 
 	/// VRF signature data
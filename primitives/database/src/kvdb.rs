@@ -18,7 +18,7 @@
 /// A wrapper around `kvdb::Database` that implements `sp_database::Database` trait
 use ::kvdb::{DBTransaction, KeyValueDB};
 
-use crate::{error, Change, ColumnId, Database, Transaction};
+use crate::{error, Change, ColumnEntry, ColumnId, Database, Transaction};
 
 struct DbAdapter<D: KeyValueDB + 'static>(D);
 
@@ -115,4 +115,18 @@ impl<D: KeyValueDB, H: Clone + AsRef<[u8]>> Database<H> for DbAdapter<D> {
 	fn contains(&self, col: ColumnId, key: &[u8]) -> bool {
 		handle_err(self.0.has_key(col, key))
 	}
+
+	fn iter_column(
+		&self,
+		col: ColumnId,
+		f: &mut dyn FnMut(ColumnEntry) -> bool,
+	) -> error::Result<()> {
+		for entry in self.0.iter(col) {
+			let (key, value) = entry.map_err(|e| error::DatabaseError(Box::new(e)))?;
+			if !f(ColumnEntry { key: Some(&key[..]), value: &value, references: 1 }) {
+				break
+			}
+		}
+		Ok(())
+	}
 }
@@ -37,6 +37,17 @@ pub enum Change<H> {
 	Release(ColumnId, H),
 }
 
+/// An entry of a database column, as passed to `Database::iter_column`.
+pub struct ColumnEntry<'a> {
+	/// The key of the entry. `None` if the database doesn't keep the keys of the column, as for
+	/// the columns which values are addressed by their hash.
+	pub key: Option<&'a [u8]>,
+	/// The stored value.
+	pub value: &'a [u8],
+	/// Number of references to the value in a reference counted column, `1` otherwise.
+	pub references: u32,
+}
+
 /// A series of changes to the database that can be committed atomically. They do not take effect
 /// until passed into `Database::commit`.
 #[derive(Default, Clone)]
@@ -111,6 +122,22 @@ pub trait Database<H: Clone + AsRef<[u8]>>: Send + Sync {
 		false
 	}
 
+	/// Call `f` with every entry of the column `col`, in no particular order, until `f` returns
+	/// `false`.
+	///
+	/// The database must not be written from within `f`. Returns an error if the database doesn't
+	/// support iteration, which is the default.
+	fn iter_column(
+		&self,
+		_col: ColumnId,
+		_f: &mut dyn FnMut(ColumnEntry) -> bool,
+	) -> error::Result<()> {
+		Err(error::DatabaseError(Box::new(std::io::Error::new(
+			std::io::ErrorKind::Unsupported,
+			"Column iteration is not supported by the database",
+		))))
+	}
+
 	/// Remove a possible path-prefix from the key.
 	///
 	/// Not all database implementations use a prefix for keys, so this function may be a noop.
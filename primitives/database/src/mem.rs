@@ -17,7 +17,7 @@
 
 //! In-memory implementation of `Database`
 
-use crate::{error, Change, ColumnId, Database, Transaction};
+use crate::{error, Change, ColumnEntry, ColumnId, Database, Transaction};
 use parking_lot::RwLock;
 use std::collections::{hash_map::Entry, HashMap};
 
@@ -73,6 +73,20 @@ where
 		let s = self.0.read();
 		s.get(&col).and_then(|c| c.get(key).map(|(_, v)| v.clone()))
 	}
+
+	fn iter_column(
+		&self,
+		col: ColumnId,
+		f: &mut dyn FnMut(ColumnEntry) -> bool,
+	) -> error::Result<()> {
+		let s = self.0.read();
+		for (key, (references, value)) in s.get(&col).into_iter().flatten() {
+			if !f(ColumnEntry { key: Some(&key[..]), value, references: *references }) {
+				break
+			}
+		}
+		Ok(())
+	}
 }
 
 impl MemDb {
@@ -184,6 +184,40 @@ pub trait Keystore: Send + Sync {
 	/// Returns `true` iff all private keys could be found.
 	fn has_keys(&self, public_keys: &[(Vec<u8>, KeyTypeId)]) -> bool;
 
+	/// Decrypt data that was encrypted for an sr25519 key with the ECIES scheme of the statement
+	/// store.
+	///
+	/// Receives [`KeyTypeId`] and an [`sr25519::Public`] key to be able to map
+	/// them to a private key that exists in the keystore.
+	///
+	/// Returns the decrypted data or `None` in case the given `key_type` and `public`
+	/// combination doesn't exist in the keystore. A [`Error::ValidationError`] is returned if
+	/// the data couldn't be decrypted with the key.
+	///
+	/// The default implementation fails, as keystores that don't hold the secret keys themselves
+	/// are unable to decrypt.
+	fn sr25519_ecies_decrypt(
+		&self,
+		_key_type: KeyTypeId,
+		_public: &sr25519::Public,
+		_encrypted: &[u8],
+	) -> Result<Option<Vec<u8>>, Error> {
+		Err(Error::Other("ECIES decryption is not supported by this keystore".into()))
+	}
+
+	/// Decrypt data that was encrypted for an ed25519 key with the ECIES scheme of the statement
+	/// store.
+	///
+	/// Same as [`Keystore::sr25519_ecies_decrypt`], for ed25519 keys.
+	fn ed25519_ecies_decrypt(
+		&self,
+		_key_type: KeyTypeId,
+		_public: &ed25519::Public,
+		_encrypted: &[u8],
+	) -> Result<Option<Vec<u8>>, Error> {
+		Err(Error::Other("ECIES decryption is not supported by this keystore".into()))
+	}
+
 	/// Convenience method to sign a message using the given key type and a raw public key
 	/// for secret lookup.
 	///
@@ -30,6 +30,7 @@ use sp_core::storage::{ChildInfo, StateVersion, TrackedStorageKey};
 #[cfg(feature = "std")]
 use sp_core::traits::RuntimeCode;
 use sp_std::vec::Vec;
+use sp_trie::MerkleValue;
 
 /// A struct containing arguments for iterating over the storage.
 #[derive(Default)]
@@ -205,6 +206,18 @@ pub trait Backend<H: Hasher>: sp_std::fmt::Debug {
 		key: &[u8],
 	) -> Result<Option<H::Out>, Self::Error>;
 
+	/// Get the merkle value of the closest descendant node of the given key, or `None` if
+	/// there is no node under the key.
+	fn closest_merkle_value(&self, key: &[u8]) -> Result<Option<MerkleValue<H::Out>>, Self::Error>;
+
+	/// Get the merkle value of the closest descendant node of the given key in a child trie,
+	/// or `None` if there is no node under the key.
+	fn child_closest_merkle_value(
+		&self,
+		child_info: &ChildInfo,
+		key: &[u8],
+	) -> Result<Option<MerkleValue<H::Out>>, Self::Error>;
+
 	/// true if a key exists in storage.
 	fn exists_storage(&self, key: &[u8]) -> Result<bool, Self::Error> {
 		Ok(self.storage_hash(key)?.is_some())
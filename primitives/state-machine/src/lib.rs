@@ -148,7 +148,7 @@ mod std_reexport {
 	};
 	pub use sp_trie::{
 		trie_types::{TrieDBMutV0, TrieDBMutV1},
-		CompactProof, DBValue, LayoutV0, LayoutV1, MemoryDB, StorageProof, TrieMut,
+		CompactProof, DBValue, LayoutV0, LayoutV1, MemoryDB, MerkleValue, StorageProof, TrieMut,
 	};
 }
 
@@ -951,6 +951,89 @@ mod execution {
 		Ok((proof, count))
 	}
 
+	/// A storage range proof bounded in size, generated by `prove_range_read_with_continuation`.
+	#[derive(Debug, PartialEq, Eq, Clone)]
+	pub struct RangeProof {
+		/// The proof of the `key => value` pairs of the range.
+		pub proof: StorageProof,
+		/// The last key of the range, `None` if the range is empty.
+		pub last_key: Option<Vec<u8>>,
+		/// The first key following the range, to start the next range at. `None` when no more
+		/// keys are left.
+		pub continuation: Option<Vec<u8>>,
+	}
+
+	/// Generate a storage range proof of at most about `size_limit` bytes.
+	///
+	/// The range starts at `start_at` (inclusive) and contains at least one key if any is left.
+	/// The proof also covers the key following the range, so that the verifier can check that no
+	/// key was left out. See `read_range_proof_check_with_continuation`.
+	pub fn prove_range_read_with_continuation<B, H>(
+		backend: B,
+		child_info: Option<&ChildInfo>,
+		prefix: Option<&[u8]>,
+		start_at: Option<&[u8]>,
+		size_limit: usize,
+	) -> Result<RangeProof, Box<dyn Error>>
+	where
+		B: AsTrieBackend<H>,
+		H: Hasher,
+		H::Out: Ord + Codec,
+	{
+		let trie_backend = backend.as_trie_backend();
+		prove_range_read_with_continuation_on_trie_backend(
+			trie_backend,
+			child_info,
+			prefix,
+			start_at,
+			size_limit,
+		)
+	}
+
+	/// Generate a storage range proof of at most about `size_limit` bytes on an existing trie
+	/// backend.
+	pub fn prove_range_read_with_continuation_on_trie_backend<S, H>(
+		trie_backend: &TrieBackend<S, H>,
+		child_info: Option<&ChildInfo>,
+		prefix: Option<&[u8]>,
+		start_at: Option<&[u8]>,
+		size_limit: usize,
+	) -> Result<RangeProof, Box<dyn Error>>
+	where
+		S: trie_backend_essence::TrieBackendStorage<H>,
+		H: Hasher,
+		H::Out: Ord + Codec,
+	{
+		let recorder = sp_trie::recorder::Recorder::default();
+		let proving_backend =
+			TrieBackendBuilder::wrap(trie_backend).with_recorder(recorder.clone()).build();
+		let mut last_key = None;
+		let mut continuation = None;
+		let iter = proving_backend
+			.pairs(IterArgs {
+				child_info: child_info.cloned(),
+				prefix,
+				start_at,
+				..IterArgs::default()
+			})
+			.map_err(|e| Box::new(e) as Box<dyn Error>)?;
+
+		for item in iter {
+			let (key, _) = item.map_err(|e| Box::new(e) as Box<dyn Error>)?;
+			// The pair that exceeds the limit stays in the proof as the continuation.
+			if last_key.is_some() && recorder.estimate_encoded_size() > size_limit {
+				continuation = Some(key);
+				break
+			}
+			last_key = Some(key);
+		}
+
+		let proof = proving_backend
+			.extract_proof()
+			.expect("A recorder was set and thus, a storage proof can be extracted; qed");
+		Ok(RangeProof { proof, last_key, continuation })
+	}
+
 	/// Generate child storage read proof.
 	pub fn prove_child_read<B, H, I>(
 		backend: B,
@@ -1081,6 +1164,35 @@ mod execution {
 		)
 	}
 
+	/// Check a storage range proof, generated by `prove_range_read_with_continuation` call.
+	///
+	/// Returns the `key => value` pairs from `start_at` to `last_key`. Fails unless the proof
+	/// shows that these are all the keys of the range and that `continuation` is the key that
+	/// follows it, or that no key follows it when `continuation` is `None`.
+	pub fn read_range_proof_check_with_continuation<H>(
+		root: H::Out,
+		proof: StorageProof,
+		child_info: Option<&ChildInfo>,
+		prefix: Option<&[u8]>,
+		start_at: Option<&[u8]>,
+		last_key: Option<&[u8]>,
+		continuation: Option<&[u8]>,
+	) -> Result<Vec<(Vec<u8>, Vec<u8>)>, Box<dyn Error>>
+	where
+		H: Hasher + 'static,
+		H::Out: Ord + Codec,
+	{
+		let proving_backend = create_proof_check_backend::<H>(root, proof)?;
+		read_range_proof_check_with_continuation_on_proving_backend(
+			&proving_backend,
+			child_info,
+			prefix,
+			start_at,
+			last_key,
+			continuation,
+		)
+	}
+
 	/// Check child storage read proof, generated by `prove_child_read` call.
 	pub fn read_child_proof_check<H, I>(
 		root: H::Out,
@@ -1171,6 +1283,59 @@ mod execution {
 		Ok((values, iter.was_complete()))
 	}
 
+	/// Check a storage range proof with a continuation on pre-created proving backend.
+	///
+	/// See `read_range_proof_check_with_continuation`.
+	pub fn read_range_proof_check_with_continuation_on_proving_backend<H>(
+		proving_backend: &TrieBackend<MemoryDB<H>, H>,
+		child_info: Option<&ChildInfo>,
+		prefix: Option<&[u8]>,
+		start_at: Option<&[u8]>,
+		last_key: Option<&[u8]>,
+		continuation: Option<&[u8]>,
+	) -> Result<Vec<(Vec<u8>, Vec<u8>)>, Box<dyn Error>>
+	where
+		H: Hasher,
+		H::Out: Ord + Codec,
+	{
+		let mut values = Vec::new();
+		let mut iter = proving_backend
+			.pairs(IterArgs {
+				child_info: child_info.cloned(),
+				prefix,
+				start_at,
+				stop_on_incomplete_database: true,
+				..IterArgs::default()
+			})
+			.map_err(|e| Box::new(e) as Box<dyn Error>)?;
+
+		if let Some(last_key) = last_key {
+			loop {
+				let (key, value) = match iter.next() {
+					Some(item) => item.map_err(|e| Box::new(e) as Box<dyn Error>)?,
+					None => return Err(Box::new("Range proof does not reach the last key.")),
+				};
+				if key.as_slice() > last_key {
+					return Err(Box::new("Last key is not part of the range proof."))
+				}
+				let done = key == last_key;
+				values.push((key, value));
+				if done {
+					break
+				}
+			}
+		}
+
+		let next = iter.next().transpose().map_err(|e| Box::new(e) as Box<dyn Error>)?;
+		match (next, continuation) {
+			(Some((key, _)), Some(continuation)) if key == continuation => Ok(values),
+			(None, None) if iter.was_complete() => Ok(values),
+			(None, _) if !iter.was_complete() =>
+				Err(Box::new("Range proof does not include the key following the range.")),
+			_ => Err(Box::new("Continuation key does not follow the range.")),
+		}
+	}
+
 	/// Check storage range proof on pre-created proving backend.
 	///
 	/// See `read_range_proof_check_with_child`.
@@ -1987,6 +2152,105 @@ mod tests {
 		assert_eq!(count, 3);
 	}
 
+	#[test]
+	fn prove_range_read_with_continuation_works() {
+		let state_version = StateVersion::V1;
+		let remote_backend = trie_backend::tests::test_trie(state_version, None, None);
+		let remote_root = remote_backend.storage_root(std::iter::empty(), state_version).0;
+		let trie_backend = remote_backend.as_trie_backend();
+		let expected = trie_backend
+			.pairs(Default::default())
+			.unwrap()
+			.map(|item| item.unwrap())
+			.collect::<Vec<_>>();
+
+		let mut results = Vec::new();
+		let mut start_at = None;
+		let mut nb_pages = 0;
+		loop {
+			nb_pages += 1;
+			let range = prove_range_read_with_continuation_on_trie_backend(
+				trie_backend,
+				None,
+				None,
+				start_at.as_deref(),
+				200,
+			)
+			.unwrap();
+			let values = read_range_proof_check_with_continuation::<BlakeTwo256>(
+				remote_root,
+				range.proof.clone(),
+				None,
+				None,
+				start_at.as_deref(),
+				range.last_key.as_deref(),
+				range.continuation.as_deref(),
+			)
+			.unwrap();
+			assert!(!values.is_empty());
+			assert_eq!(values.last().map(|(key, _)| key), range.last_key.as_ref());
+			results.extend(values);
+
+			// The proof must not pass for a range ending early.
+			assert!(
+				read_range_proof_check_with_continuation::<BlakeTwo256>(
+					remote_root,
+					range.proof,
+					None,
+					None,
+					start_at.as_deref(),
+					range.last_key.as_deref(),
+					None,
+				)
+				.is_err() == range.continuation.is_some()
+			);
+
+			match range.continuation {
+				Some(continuation) => start_at = Some(continuation),
+				None => break,
+			}
+		}
+		assert!(nb_pages > 1);
+		assert_eq!(results, expected);
+
+		// A continuation skipping keys is rejected.
+		let range =
+			prove_range_read_with_continuation_on_trie_backend(trie_backend, None, None, None, 0)
+				.unwrap();
+		assert!(read_range_proof_check_with_continuation::<BlakeTwo256>(
+			remote_root,
+			range.proof,
+			None,
+			None,
+			None,
+			range.last_key.as_deref(),
+			Some(expected[2].0.as_slice()),
+		)
+		.is_err());
+
+		// An empty range.
+		let range = prove_range_read_with_continuation_on_trie_backend(
+			trie_backend,
+			None,
+			Some(&b"unknown"[..]),
+			None,
+			0,
+		)
+		.unwrap();
+		assert_eq!((range.last_key.as_ref(), range.continuation.as_ref()), (None, None));
+		let values = read_range_proof_check_with_continuation::<BlakeTwo256>(
+			remote_root,
+			range.proof,
+			None,
+			Some(&b"unknown"[..]),
+			None,
+			None,
+			None,
+		)
+		.unwrap();
+		assert!(values.is_empty());
+	}
+
 	#[test]
 	fn inner_state_versioning_switch_proofs() {
 		let mut state_version = StateVersion::V0;
@@ -0,0 +1,221 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! ECIES encryption scheme for statement data.
+//!
+//! Data is encrypted to a recipient public key in the manner of a sealed box: a fresh ephemeral
+//! key pair is generated for each message, a shared secret is agreed with the recipient key and
+//! the payload is sealed with AES-256-GCM under a key derived from that secret with HKDF-SHA256.
+//! Only the holder of the recipient secret key is able to open the box.
+//!
+//! Key agreement is performed with x25519 for ed25519 keys and on the Ristretto group for sr25519
+//! keys. The encrypted payload has the following layout:
+//!
+//! `ephemeral public key (32 bytes) ++ nonce (12 bytes) ++ ciphertext`
+
+use aes_gcm::{
+	aead::{Aead, KeyInit},
+	AeadCore, Aes256Gcm,
+};
+use curve25519_dalek::{
+	constants::RISTRETTO_BASEPOINT_POINT, edwards::CompressedEdwardsY,
+	ristretto::CompressedRistretto, scalar::Scalar,
+};
+use rand::{rngs::OsRng, RngCore};
+use sha2::Digest;
+use sp_core::{ed25519, sr25519, Pair};
+
+/// Encryption or decryption error.
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum Error {
+	/// The recipient public key is not a valid curve point.
+	#[error("Invalid public key")]
+	BadPublicKey,
+	/// Generic AES encryption error.
+	#[error("Encryption error")]
+	Encryption,
+	/// Generic AES decryption error. The data was not encrypted for the given key or has been
+	/// tampered with.
+	#[error("Decryption error")]
+	Decryption,
+	/// The encrypted data is too short or contains an invalid ephemeral key.
+	#[error("Bad cypher text")]
+	BadData,
+}
+
+const NONCE_LEN: usize = 12;
+const PK_LEN: usize = 32;
+const AES_KEY_LEN: usize = 32;
+
+fn kdf(shared_secret: &[u8], ephemeral_pk: &[u8; PK_LEN]) -> [u8; AES_KEY_LEN] {
+	let hkdf = hkdf::Hkdf::<sha2::Sha256>::new(Some(ephemeral_pk), shared_secret);
+	let mut aes_key = [0u8; AES_KEY_LEN];
+	hkdf.expand(b"statement-store-ecies", &mut aes_key)
+		.expect("There's always enough data for derivation. qed.");
+	aes_key
+}
+
+fn seal(
+	shared_secret: &[u8],
+	ephemeral_pk: &[u8; PK_LEN],
+	plaintext: &[u8],
+) -> Result<Vec<u8>, Error> {
+	let aes_key = kdf(shared_secret, ephemeral_pk);
+	let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+	let ciphertext = Aes256Gcm::new(&aes_key.into())
+		.encrypt(&nonce, plaintext)
+		.map_err(|_| Error::Encryption)?;
+
+	let mut out = Vec::with_capacity(PK_LEN + NONCE_LEN + ciphertext.len());
+	out.extend_from_slice(ephemeral_pk);
+	out.extend_from_slice(nonce.as_slice());
+	out.extend_from_slice(&ciphertext);
+	Ok(out)
+}
+
+/// Split the encrypted data into the ephemeral public key and the remaining sealed part.
+fn split(encrypted: &[u8]) -> Result<([u8; PK_LEN], &[u8]), Error> {
+	if encrypted.len() < PK_LEN + NONCE_LEN {
+		return Err(Error::BadData)
+	}
+	let mut ephemeral_pk = [0u8; PK_LEN];
+	ephemeral_pk.copy_from_slice(&encrypted[..PK_LEN]);
+	Ok((ephemeral_pk, &encrypted[PK_LEN..]))
+}
+
+fn open(
+	shared_secret: &[u8],
+	ephemeral_pk: &[u8; PK_LEN],
+	sealed: &[u8],
+) -> Result<Vec<u8>, Error> {
+	let aes_key = kdf(shared_secret, ephemeral_pk);
+	let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+	Aes256Gcm::new(&aes_key.into())
+		.decrypt(aes_gcm::Nonce::from_slice(nonce), ciphertext)
+		.map_err(|_| Error::Decryption)
+}
+
+/// Encrypt `plaintext` with the given x25519 public key. Decryption can be performed with the
+/// matching secret key.
+pub fn encrypt_x25519(pk: &x25519_dalek::PublicKey, plaintext: &[u8]) -> Result<Vec<u8>, Error> {
+	let ephemeral_sk = x25519_dalek::EphemeralSecret::random_from_rng(OsRng);
+	let ephemeral_pk = x25519_dalek::PublicKey::from(&ephemeral_sk);
+	let shared_secret = ephemeral_sk.diffie_hellman(pk);
+	seal(shared_secret.as_bytes(), ephemeral_pk.as_bytes(), plaintext)
+}
+
+/// Decrypt data previously encrypted with [`encrypt_x25519`] using the given x25519 secret key.
+pub fn decrypt_x25519(sk: &x25519_dalek::StaticSecret, encrypted: &[u8]) -> Result<Vec<u8>, Error> {
+	let (ephemeral_pk, sealed) = split(encrypted)?;
+	let shared_secret = sk.diffie_hellman(&x25519_dalek::PublicKey::from(ephemeral_pk));
+	open(shared_secret.as_bytes(), &ephemeral_pk, sealed)
+}
+
+/// Encrypt `plaintext` with the given ed25519 public key. Decryption can be performed with the
+/// matching key pair.
+///
+/// The public key is converted to its birationally equivalent x25519 public key.
+pub fn encrypt_ed25519(pk: &ed25519::Public, plaintext: &[u8]) -> Result<Vec<u8>, Error> {
+	let montgomery = CompressedEdwardsY(pk.0)
+		.decompress()
+		.ok_or(Error::BadPublicKey)?
+		.to_montgomery();
+	encrypt_x25519(&x25519_dalek::PublicKey::from(montgomery.to_bytes()), plaintext)
+}
+
+/// Decrypt data previously encrypted with [`encrypt_ed25519`] using the given ed25519 key pair.
+pub fn decrypt_ed25519(pair: &ed25519::Pair, encrypted: &[u8]) -> Result<Vec<u8>, Error> {
+	// The x25519 secret is derived from the seed in the same way as the ed25519 signing scalar.
+	let mut secret = [0u8; 32];
+	secret.copy_from_slice(&sha2::Sha512::digest(pair.seed())[..32]);
+	decrypt_x25519(&x25519_dalek::StaticSecret::from(secret), encrypted)
+}
+
+/// Encrypt `plaintext` with the given sr25519 public key. Decryption can be performed with the
+/// matching key pair.
+pub fn encrypt_sr25519(pk: &sr25519::Public, plaintext: &[u8]) -> Result<Vec<u8>, Error> {
+	let pk = CompressedRistretto(pk.0).decompress().ok_or(Error::BadPublicKey)?;
+	let mut seed = [0u8; 64];
+	OsRng.fill_bytes(&mut seed);
+	let ephemeral_sk = Scalar::from_bytes_mod_order_wide(&seed);
+	let ephemeral_pk = (ephemeral_sk * RISTRETTO_BASEPOINT_POINT).compress();
+	let shared_secret = (ephemeral_sk * pk).compress();
+	seal(shared_secret.as_bytes(), ephemeral_pk.as_bytes(), plaintext)
+}
+
+/// Decrypt data previously encrypted with [`encrypt_sr25519`] using the given sr25519 key pair.
+pub fn decrypt_sr25519(pair: &sr25519::Pair, encrypted: &[u8]) -> Result<Vec<u8>, Error> {
+	let (ephemeral_pk, sealed) = split(encrypted)?;
+	let ephemeral = CompressedRistretto(ephemeral_pk).decompress().ok_or(Error::BadData)?;
+	// The first half of the raw secret key is the canonical encoding of the secret scalar.
+	let mut secret = [0u8; 32];
+	secret.copy_from_slice(&pair.to_raw_vec()[..32]);
+	let shared_secret = (Scalar::from_bytes_mod_order(secret) * ephemeral).compress();
+	open(shared_secret.as_bytes(), &ephemeral_pk, sealed)
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn basic_x25519_encryption() {
+		let sk = x25519_dalek::StaticSecret::random_from_rng(OsRng);
+		let pk = x25519_dalek::PublicKey::from(&sk);
+
+		let plaintext = b"test";
+		let encrypted = encrypt_x25519(&pk, plaintext).unwrap();
+		let decrypted = decrypt_x25519(&sk, &encrypted).unwrap();
+		assert_eq!(plaintext[..], decrypted);
+	}
+
+	#[test]
+	fn basic_ed25519_encryption() {
+		let (pair, _) = ed25519::Pair::generate();
+		let pk = pair.public();
+
+		let plaintext = b"test";
+		let encrypted = encrypt_ed25519(&pk, plaintext).unwrap();
+		let decrypted = decrypt_ed25519(&pair, &encrypted).unwrap();
+		assert_eq!(plaintext[..], decrypted);
+	}
+
+	#[test]
+	fn basic_sr25519_encryption() {
+		let (pair, _) = sr25519::Pair::generate();
+		let pk = pair.public();
+
+		let plaintext = b"test";
+		let encrypted = encrypt_sr25519(&pk, plaintext).unwrap();
+		let decrypted = decrypt_sr25519(&pair, &encrypted).unwrap();
+		assert_eq!(plaintext[..], decrypted);
+	}
+
+	#[test]
+	fn fails_with_wrong_key() {
+		let (pair, _) = sr25519::Pair::generate();
+		let (other, _) = sr25519::Pair::generate();
+
+		let encrypted = encrypt_sr25519(&pair.public(), b"test").unwrap();
+		assert_eq!(decrypt_sr25519(&other, &encrypted), Err(Error::Decryption));
+		assert_eq!(decrypt_sr25519(&pair, &encrypted[..PK_LEN]), Err(Error::BadData));
+
+		let mut tampered = encrypted.clone();
+		*tampered.last_mut().unwrap() ^= 1;
+		assert_eq!(decrypt_sr25519(&pair, &tampered), Err(Error::Decryption));
+	}
+}
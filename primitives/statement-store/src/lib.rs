@@ -46,27 +46,40 @@ pub const MAX_TOPICS: usize = 4;
 
 #[cfg(feature = "std")]
 pub use store_api::{
-	Error, NetworkPriority, Result, StatementSource, StatementStore, SubmitResult,
+	Error, NetworkPriority, Result, SequencedStatement, StatementFilter, StatementSource,
+	StatementStore, StatementStream, SubmitResult,
 };
 
+#[cfg(feature = "std")]
+pub mod ecies;
 pub mod runtime_api;
 #[cfg(feature = "std")]
 mod store_api;
 
-mod sr25519 {
+/// Statement store application crypto types for sr25519.
+pub mod sr25519 {
 	mod app_sr25519 {
 		use sp_application_crypto::{app_crypto, key_types::STATEMENT, sr25519};
 		app_crypto!(sr25519, STATEMENT);
 	}
+	/// Statement store sr25519 public key.
 	pub type Public = app_sr25519::Public;
+	/// Statement store sr25519 key pair.
+	#[cfg(feature = "std")]
+	pub type Pair = app_sr25519::Pair;
 }
 
-mod ed25519 {
+/// Statement store application crypto types for ed25519.
+pub mod ed25519 {
 	mod app_ed25519 {
 		use sp_application_crypto::{app_crypto, ed25519, key_types::STATEMENT};
 		app_crypto!(ed25519, STATEMENT);
 	}
+	/// Statement store ed25519 public key.
 	pub type Public = app_ed25519::Public;
+	/// Statement store ed25519 key pair.
+	#[cfg(feature = "std")]
+	pub type Pair = app_ed25519::Pair;
 }
 
 mod ecdsa {
@@ -462,6 +475,54 @@ impl Statement {
 		self.data = Some(data)
 	}
 
+	/// Encrypt `data` for the given sr25519 public key and set it as statement data. The public key
+	/// is also set as the decryption key.
+	#[cfg(feature = "std")]
+	pub fn encrypt_sr25519(
+		&mut self,
+		data: &[u8],
+		key: &sp_core::sr25519::Public,
+	) -> core::result::Result<(), ecies::Error> {
+		self.data = Some(ecies::encrypt_sr25519(key, data)?);
+		self.decryption_key = Some(key.0);
+		Ok(())
+	}
+
+	/// Encrypt `data` for the given ed25519 public key and set it as statement data. The public key
+	/// is also set as the decryption key.
+	#[cfg(feature = "std")]
+	pub fn encrypt_ed25519(
+		&mut self,
+		data: &[u8],
+		key: &sp_core::ed25519::Public,
+	) -> core::result::Result<(), ecies::Error> {
+		self.data = Some(ecies::encrypt_ed25519(key, data)?);
+		self.decryption_key = Some(key.0);
+		Ok(())
+	}
+
+	/// Decrypt statement data with the given sr25519 private key.
+	///
+	/// Returns `None` if the statement has no data.
+	#[cfg(feature = "std")]
+	pub fn decrypt_sr25519_private(
+		&self,
+		key: &sp_core::sr25519::Pair,
+	) -> core::result::Result<Option<Vec<u8>>, ecies::Error> {
+		self.data.as_ref().map(|data| ecies::decrypt_sr25519(key, data)).transpose()
+	}
+
+	/// Decrypt statement data with the given ed25519 private key.
+	///
+	/// Returns `None` if the statement has no data.
+	#[cfg(feature = "std")]
+	pub fn decrypt_ed25519_private(
+		&self,
+		key: &sp_core::ed25519::Pair,
+	) -> core::result::Result<Option<Vec<u8>>, ecies::Error> {
+		self.data.as_ref().map(|data| ecies::decrypt_ed25519(key, data)).transpose()
+	}
+
 	fn encoded(&self, for_signing: bool) -> Vec<u8> {
 		// Encoding matches that of Vec<Field>. Basically this just means accepting that there
 		// will be a prefix of vector length.
@@ -615,4 +676,23 @@ mod test {
 		statement.remove_proof();
 		assert_eq!(statement.verify_signature(), SignatureVerificationResult::NoSignature);
 	}
+
+	#[test]
+	fn encrypt_decrypt() {
+		let mut statement = Statement::new();
+		let (sr25519_kp, _) = sp_core::sr25519::Pair::generate();
+		let (ed25519_kp, _) = sp_core::ed25519::Pair::generate();
+		let plain = b"test data".to_vec();
+
+		statement.encrypt_sr25519(&plain, &sr25519_kp.public()).unwrap();
+		assert_ne!(plain.as_slice(), statement.data().unwrap().as_slice());
+		assert_eq!(statement.decryption_key(), Some(sr25519_kp.public().0));
+		let decrypted = statement.decrypt_sr25519_private(&sr25519_kp).unwrap();
+		assert_eq!(decrypted, Some(plain.clone()));
+
+		statement.encrypt_ed25519(&plain, &ed25519_kp.public()).unwrap();
+		assert_eq!(statement.decryption_key(), Some(ed25519_kp.public().0));
+		let decrypted = statement.decrypt_ed25519_private(&ed25519_kp).unwrap();
+		assert_eq!(decrypted, Some(plain));
+	}
 }
@@ -16,7 +16,7 @@
 // limitations under the License.
 
 pub use crate::runtime_api::StatementSource;
-use crate::{Hash, Statement, Topic};
+use crate::{DecryptionKey, Hash, Statement, Topic};
 
 /// Statement store error.
 #[derive(Debug, Eq, PartialEq, thiserror::Error)]
@@ -30,6 +30,12 @@ pub enum Error {
 	/// Error making runtime call.
 	#[error("Error calling into the runtime")]
 	Runtime,
+	/// The keystore is unable to decrypt statements.
+	#[error("Keystore error: {0:?}")]
+	Keystore(String),
+	/// The maximum number of statement subscribers is reached.
+	#[error("Too many statement subscribers")]
+	TooManySubscribers,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -61,6 +67,43 @@ pub enum SubmitResult {
 /// Result type for `Error`
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// Filter for the statements reported by a statement subscription.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct StatementFilter {
+	/// The statement must include all of these topics.
+	pub match_all_topics: Vec<Topic>,
+	/// The decryption key of the statement.
+	///
+	/// `None` only matches statements that have no `DecryptionKey` field.
+	pub dest: Option<DecryptionKey>,
+}
+
+impl StatementFilter {
+	/// Returns `true` if the statement matches this filter.
+	pub fn matches(&self, statement: &Statement) -> bool {
+		if statement.decryption_key() != self.dest {
+			return false
+		}
+		self.match_all_topics
+			.iter()
+			.all(|topic| (0..crate::MAX_TOPICS).any(|index| statement.topic(index) == Some(*topic)))
+	}
+}
+
+/// A statement accepted by the store.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SequencedStatement {
+	/// The sequence number assigned to the statement when it was accepted by the store.
+	///
+	/// Sequence numbers are increasing in the order statements are accepted.
+	pub sequence: u64,
+	/// The statement.
+	pub statement: Statement,
+}
+
+/// Stream of the statements accepted by the store.
+pub type StatementStream = futures::channel::mpsc::Receiver<SequencedStatement>;
+
 /// Statement store API.
 pub trait StatementStore: Send + Sync {
 	/// Return all statements.
@@ -87,4 +130,16 @@ pub trait StatementStore: Send + Sync {
 
 	/// Remove a statement from the store.
 	fn remove(&self, hash: &Hash) -> Result<()>;
+
+	/// Subscribe to the statements accepted by the store that match the filter.
+	///
+	/// Statements already in the store with a sequence number greater than `from` are
+	/// reported first, in the order they were accepted. The stream is closed if the
+	/// subscriber does not keep up with the accepted statements. Fails if there are too many
+	/// subscribers.
+	fn subscribe_statements(
+		&self,
+		filter: StatementFilter,
+		from: Option<u64>,
+	) -> Result<StatementStream>;
 }
@@ -52,7 +52,7 @@ use trie_db::{node::NodeOwned, CachedValue};
 
 mod shared_cache;
 
-pub use shared_cache::SharedTrieCache;
+pub use shared_cache::{ExportedNode, SharedTrieCache};
 
 use self::shared_cache::ValueCacheKeyHash;
 
@@ -281,6 +281,16 @@ impl std::fmt::Display for HitStats {
 	}
 }
 
+impl HitStats {
+	/// Add the shared cache hits and fetch attempts of `other`.
+	fn add_shared(&self, other: &HitStats) {
+		self.shared_hits
+			.fetch_add(other.shared_hits.load(Ordering::Relaxed), Ordering::Relaxed);
+		self.shared_fetch_attempts
+			.fetch_add(other.shared_fetch_attempts.load(Ordering::Relaxed), Ordering::Relaxed);
+	}
+}
+
 /// A struct to gather hit/miss stats for the node cache and the value cache.
 #[derive(Default)]
 struct TrieHitStats {
@@ -288,6 +298,19 @@ struct TrieHitStats {
 	value_cache: HitStats,
 }
 
+/// Hit statistics of the [`SharedTrieCache`], accumulated from the dropped [`LocalTrieCache`]s.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SharedTrieCacheStats {
+	/// Lookups of trie nodes in the shared cache.
+	pub node_fetch_attempts: u64,
+	/// Lookups of trie nodes found in the shared cache.
+	pub node_hits: u64,
+	/// Lookups of values in the shared cache.
+	pub value_fetch_attempts: u64,
+	/// Lookups of values found in the shared cache.
+	pub value_hits: u64,
+}
+
 /// An internal struct to store the cached trie nodes.
 pub(crate) struct NodeCached<H> {
 	/// The cached node.
@@ -399,6 +422,8 @@ impl<H: Hasher> Drop for LocalTrieCache<H> {
 			self.stats.value_cache
 		);
 
+		self.shared.add_stats(&self.stats);
+
 		let mut shared_inner = match self.shared.write_lock_inner() {
 			Some(inner) => inner,
 			None => {
@@ -723,6 +748,40 @@ mod tests {
 		}
 	}
 
+	#[test]
+	fn export_and_import_nodes_works() {
+		let (db, root) = create_trie();
+
+		let shared_cache = Cache::new(CACHE_SIZE);
+		for _ in 0..2 {
+			let local_cache = shared_cache.local_cache();
+			let mut cache = local_cache.as_trie_db_cache(root);
+			let trie = TrieDBBuilder::<Layout>::new(&db, &root).with_cache(&mut cache).build();
+			for (key, value) in TEST_DATA {
+				assert_eq!(value.to_vec(), trie.get(key).unwrap().unwrap());
+			}
+		}
+
+		// The second iteration found the values in the shared cache.
+		let stats = shared_cache.stats();
+		assert!(stats.value_hits > 0);
+		assert!(stats.value_fetch_attempts > stats.value_hits);
+
+		let nodes = shared_cache.export_nodes();
+		let node_count = shared_cache.read_lock_inner().node_cache().lru.len();
+		assert_eq!(nodes.len(), node_count);
+		assert!(nodes.iter().any(|node| matches!(node, ExportedNode::Value(_))));
+
+		let imported = Cache::new(CACHE_SIZE);
+		assert_eq!(imported.import_nodes(nodes.clone(), None), node_count);
+		// The least recently used order is kept.
+		assert_eq!(imported.export_nodes(), nodes);
+
+		// All the nodes are part of the trie.
+		assert_eq!(Cache::new(CACHE_SIZE).import_nodes(nodes.clone(), Some(root)), node_count);
+		assert_eq!(Cache::new(CACHE_SIZE).import_nodes(nodes, Some(Default::default())), 0);
+	}
+
 	#[test]
 	fn trie_db_mut_cache_works() {
 		let (mut db, root) = create_trie();
@@ -17,17 +17,25 @@
 
 ///! Provides the [`SharedNodeCache`], the [`SharedValueCache`] and the [`SharedTrieCache`]
 ///! that combines both caches and is exported to the outside.
-use super::{CacheSize, NodeCached};
+use super::{CacheSize, NodeCached, SharedTrieCacheStats, TrieHitStats};
+use crate::{LayoutV1, NodeCodec};
+use codec::{Decode, Encode};
 use hash_db::Hasher;
-use hashbrown::{hash_set::Entry as SetEntry, HashSet};
+use hashbrown::{hash_set::Entry as SetEntry, HashMap, HashSet};
 use nohash_hasher::BuildNoHashHasher;
 use parking_lot::{Mutex, RwLock, RwLockWriteGuard};
 use schnellru::LruMap;
 use std::{
 	hash::{BuildHasher, Hasher as _},
-	sync::Arc,
+	sync::{
+		atomic::{AtomicU64, Ordering},
+		Arc,
+	},
+};
+use trie_db::{
+	node::{NodeHandlePlan, NodeOwned, NodePlan, ValuePlan},
+	CachedValue, NodeCodec as _,
 };
-use trie_db::{node::NodeOwned, CachedValue};
 
 lazy_static::lazy_static! {
 	static ref RANDOM_STATE: ahash::RandomState = ahash::RandomState::default();
@@ -593,6 +601,58 @@ impl<H: Hasher> SharedTrieCacheInner<H> {
 	}
 }
 
+/// A node of the [`SharedTrieCache`], exported by [`SharedTrieCache::export_nodes`].
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub enum ExportedNode {
+	/// An encoded trie node.
+	Node(Vec<u8>),
+	/// A value stored in a separate node.
+	Value(Vec<u8>),
+}
+
+impl ExportedNode {
+	fn data(&self) -> &[u8] {
+		match self {
+			ExportedNode::Node(data) | ExportedNode::Value(data) => data,
+		}
+	}
+}
+
+fn decode_hash<H: Hasher>(data: &[u8]) -> Option<H::Out> {
+	let mut hash = H::Out::default();
+	if hash.as_ref().len() != data.len() {
+		return None
+	}
+	hash.as_mut().copy_from_slice(data);
+	Some(hash)
+}
+
+/// Collect the hashes of the nodes referenced by the encoded `node`, including those referenced
+/// by its inline children.
+fn referenced_nodes<H: Hasher>(node: &[u8], referenced: &mut Vec<H::Out>) {
+	let mut inline = vec![node];
+	while let Some(data) = inline.pop() {
+		let (value, children) = match NodeCodec::<H>::decode_plan(data) {
+			Ok(NodePlan::Leaf { value, .. }) => (Some(value), None),
+			Ok(NodePlan::Extension { child, .. }) => (None, Some(vec![Some(child)])),
+			Ok(NodePlan::Branch { value, children }) |
+			Ok(NodePlan::NibbledBranch { value, children, .. }) =>
+				(value, Some(children.into_iter().collect())),
+			Ok(NodePlan::Empty) | Err(_) => continue,
+		};
+
+		if let Some(ValuePlan::Node(range)) = value {
+			referenced.extend(decode_hash::<H>(&data[range]));
+		}
+		for child in children.into_iter().flatten().flatten() {
+			match child {
+				NodeHandlePlan::Hash(range) => referenced.extend(decode_hash::<H>(&data[range])),
+				NodeHandlePlan::Inline(range) => inline.push(&data[range]),
+			}
+		}
+	}
+}
+
 /// The shared trie cache.
 ///
 /// It should be instantiated once per node. It will hold the trie nodes and values of all
@@ -602,11 +662,12 @@ impl<H: Hasher> SharedTrieCacheInner<H> {
 /// The instance of this object can be shared between multiple threads.
 pub struct SharedTrieCache<H: Hasher> {
 	inner: Arc<RwLock<SharedTrieCacheInner<H>>>,
+	stats: Arc<TrieHitStats>,
 }
 
 impl<H: Hasher> Clone for SharedTrieCache<H> {
 	fn clone(&self) -> Self {
-		Self { inner: self.inner.clone() }
+		Self { inner: self.inner.clone(), stats: self.stats.clone() }
 	}
 }
 
@@ -658,6 +719,7 @@ impl<H: Hasher> SharedTrieCache<H> {
 					value_cache_max_heap_size,
 				),
 			})),
+			stats: Default::default(),
 		}
 	}
 
@@ -715,6 +777,115 @@ impl<H: Hasher> SharedTrieCache<H> {
 		node_cache_size + value_cache_size
 	}
 
+	/// Returns the hit statistics of this cache.
+	pub fn stats(&self) -> SharedTrieCacheStats {
+		let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+		SharedTrieCacheStats {
+			node_fetch_attempts: load(&self.stats.node_cache.shared_fetch_attempts),
+			node_hits: load(&self.stats.node_cache.shared_hits),
+			value_fetch_attempts: load(&self.stats.value_cache.shared_fetch_attempts),
+			value_hits: load(&self.stats.value_cache.shared_hits),
+		}
+	}
+
+	/// Add the shared cache hits of a local cache.
+	pub(super) fn add_stats(&self, stats: &TrieHitStats) {
+		self.stats.node_cache.add_shared(&stats.node_cache);
+		self.stats.value_cache.add_shared(&stats.value_cache);
+	}
+
+	/// Export the nodes of the cache, the least recently used first.
+	///
+	/// The value cache is not exported, its entries refer to the data of the cached nodes.
+	pub fn export_nodes(&self) -> Vec<ExportedNode> {
+		let mut nodes = Vec::new();
+		let _ = self.try_for_each_exported_node(|node| {
+			nodes.push(node);
+			Ok::<_, ()>(())
+		});
+		nodes.reverse();
+		nodes
+	}
+
+	/// Export the nodes of the cache one at a time, the most recently used first.
+	///
+	/// Unlike [`Self::export_nodes`], the exported nodes are not all held in memory at once. The
+	/// cache is locked until `f` returns for the last node or fails.
+	pub fn try_for_each_exported_node<E>(
+		&self,
+		mut f: impl FnMut(ExportedNode) -> Result<(), E>,
+	) -> Result<(), E> {
+		let inner = self.inner.read();
+		inner.node_cache.lru.iter().try_for_each(|(_, node)| {
+			f(match node {
+				NodeOwned::Value(data, _) => ExportedNode::Value(data.to_vec()),
+				node => ExportedNode::Node(node.to_encoded::<NodeCodec<H>>()),
+			})
+		})
+	}
+
+	/// Import nodes exported by [`Self::export_nodes`], the least recently used first.
+	///
+	/// Nodes are cached under the hash of their exported encoding, so an invalid node is never
+	/// returned for a lookup. If a `root` is given, only the nodes of the trie with this root are
+	/// imported. Nodes are imported until the cache is full, evicting the nodes imported first.
+	/// Returns the number of imported nodes.
+	pub fn import_nodes(&self, nodes: Vec<ExportedNode>, root: Option<H::Out>) -> usize {
+		let hashes = nodes.iter().map(|node| H::hash(node.data())).collect::<Vec<_>>();
+		let reachable = root.map(|root| {
+			let index =
+				hashes.iter().enumerate().map(|(i, hash)| (*hash, i)).collect::<HashMap<_, _>>();
+			let mut reachable = HashSet::new();
+			let mut stack = vec![root];
+			while let Some(hash) = stack.pop() {
+				let node = match index.get(&hash) {
+					Some(i) if reachable.insert(hash) => &nodes[*i],
+					_ => continue,
+				};
+				if let ExportedNode::Node(data) = node {
+					referenced_nodes::<H>(data, &mut stack);
+				}
+			}
+			reachable
+		});
+
+		let mut inner = self.inner.write();
+		let lru = &mut inner.node_cache.lru;
+		lru.limiter_mut().items_evicted = 0;
+		lru.limiter_mut().max_items_evicted = usize::MAX;
+
+		let mut imported = 0;
+		for (node, hash) in nodes.into_iter().zip(hashes) {
+			if reachable.as_ref().map_or(false, |reachable| !reachable.contains(&hash)) {
+				continue
+			}
+			let node = match node {
+				ExportedNode::Node(data) => match NodeCodec::<H>::decode(&data)
+					.and_then(|node| node.to_owned_node::<LayoutV1<H>>())
+				{
+					Ok(node) => node,
+					Err(_) => continue,
+				},
+				ExportedNode::Value(data) => NodeOwned::Value(data.into(), hash),
+			};
+			if lru.insert(hash, node) {
+				imported += 1;
+			}
+		}
+		lru.limiter_mut().items_evicted = 0;
+		lru.limiter_mut().max_items_evicted = 0;
+
+		tracing::debug!(
+			target: super::LOG_TARGET,
+			"Imported {} nodes into the shared node cache (length = {}, heap size={}/{})",
+			imported,
+			lru.len(),
+			lru.limiter().heap_size,
+			lru.limiter().max_heap_size,
+		);
+		imported
+	}
+
 	/// Reset the node cache.
 	pub fn reset_node_cache(&self) {
 		self.inner.write().node_cache.reset();
@@ -295,6 +295,66 @@ pub fn read_trie_value<L: TrieLayout, DB: hash_db::HashDBRef<L::Hash, trie_db::D
 		.get(key)
 }
 
+/// The merkle value of a trie node.
+///
+/// Nodes whose encoding is shorter than the hash are inlined in their parent and do not
+/// have a hash of their own. For those nodes the merkle value is the encoded node itself.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MerkleValue<H> {
+	/// The merkle value is the encoded node.
+	Node(Vec<u8>),
+	/// The merkle value is the hash of the encoded node.
+	Hash(H),
+}
+
+impl<H: AsRef<[u8]>> AsRef<[u8]> for MerkleValue<H> {
+	fn as_ref(&self) -> &[u8] {
+		match self {
+			MerkleValue::Node(data) => data.as_slice(),
+			MerkleValue::Hash(hash) => hash.as_ref(),
+		}
+	}
+}
+
+/// Find the merkle value of the closest descendant node of `key` in the given trie.
+///
+/// The closest descendant is the first node, in iteration order, whose full key starts with
+/// `key`. When a node exists at exactly `key`, its own merkle value is returned.
+fn first_descendant_merkle_value<L: TrieLayout>(
+	trie: &TrieDB<L>,
+	key: &[u8],
+) -> Result<Option<MerkleValue<TrieHash<L>>>, Box<TrieError<L>>> {
+	let mut iter = trie_db::TrieDBNodeIterator::new(trie)?;
+	iter.prefix(key)?;
+
+	match iter.next() {
+		Some(Ok((_, _, node))) if matches!(node.node_plan(), NodePlan::Empty) => Ok(None),
+		Some(Ok((_, Some(hash), _))) => Ok(Some(MerkleValue::Hash(hash))),
+		Some(Ok((_, None, node))) => Ok(Some(MerkleValue::Node(node.data().to_vec()))),
+		Some(Err(error)) => Err(error),
+		None => Ok(None),
+	}
+}
+
+/// Read the merkle value of the closest descendant node of `key` from the trie.
+pub fn read_trie_first_descendant_value<L: TrieLayout, DB>(
+	db: &DB,
+	root: &TrieHash<L>,
+	key: &[u8],
+	recorder: Option<&mut dyn TrieRecorder<TrieHash<L>>>,
+	cache: Option<&mut dyn TrieCache<L::Codec>>,
+) -> Result<Option<MerkleValue<TrieHash<L>>>, Box<TrieError<L>>>
+where
+	DB: hash_db::HashDBRef<L::Hash, trie_db::DBValue>,
+{
+	let trie = TrieDBBuilder::<L>::new(db, root)
+		.with_optional_cache(cache)
+		.with_optional_recorder(recorder)
+		.build();
+
+	first_descendant_merkle_value::<L>(&trie, key)
+}
+
 /// Read a value from the trie with given Query.
 pub fn read_trie_value_with<
 	L: TrieLayout,
@@ -397,6 +457,27 @@ where
 		.get_hash(key)
 }
 
+/// Read the merkle value of the closest descendant node of `key` from the child trie.
+pub fn read_child_trie_first_descendant_value<L: TrieConfiguration, DB>(
+	keyspace: &[u8],
+	db: &DB,
+	root: &TrieHash<L>,
+	key: &[u8],
+	recorder: Option<&mut dyn TrieRecorder<TrieHash<L>>>,
+	cache: Option<&mut dyn TrieCache<L::Codec>>,
+) -> Result<Option<MerkleValue<TrieHash<L>>>, Box<TrieError<L>>>
+where
+	DB: hash_db::HashDBRef<L::Hash, trie_db::DBValue>,
+{
+	let db = KeySpacedDB::new(db, keyspace);
+	let trie = TrieDBBuilder::<L>::new(&db, &root)
+		.with_optional_recorder(recorder)
+		.with_optional_cache(cache)
+		.build();
+
+	first_descendant_merkle_value::<L>(&trie, key)
+}
+
 /// Read a value from the child trie with given query.
 pub fn read_child_trie_value_with<L, Q, DB>(
 	keyspace: &[u8],
@@ -977,6 +1058,37 @@ mod tests {
 		assert_eq!(first_storage_root, second_storage_root);
 	}
 
+	#[test]
+	fn first_descendant_merkle_value_works() {
+		let pairs = vec![
+			(array_bytes::hex2bytes_unchecked("0102"), vec![1u8; 64]),
+			(array_bytes::hex2bytes_unchecked("0103"), vec![2u8; 64]),
+			(array_bytes::hex2bytes_unchecked("0203"), vec![3u8; 64]),
+		];
+
+		let mut memdb = MemoryDB::default();
+		let mut root = Default::default();
+		populate_trie::<LayoutV1>(&mut memdb, &mut root, &pairs);
+
+		let read = |key: &[u8]| {
+			read_trie_first_descendant_value::<LayoutV1, _>(&memdb, &root, key, None, None).unwrap()
+		};
+
+		// The closest descendant of the empty key is the root node.
+		assert_eq!(read(&[]), Some(MerkleValue::Hash(root)));
+		// No key starts with `03`.
+		assert_eq!(read(&array_bytes::hex2bytes_unchecked("03")), None);
+
+		// `0102` and `0103` share a branch node that differs from the root.
+		let branch = read(&array_bytes::hex2bytes_unchecked("01")).unwrap();
+		assert_ne!(branch, MerkleValue::Hash(root));
+
+		let leaf = read(&pairs[0].0).unwrap();
+		assert_ne!(leaf, branch);
+		// Querying past the end of an existing key has no descendant.
+		assert_eq!(read(&array_bytes::hex2bytes_unchecked("010200")), None);
+	}
+
 	#[test]
 	fn big_key() {
 		let check = |keysize: usize| {